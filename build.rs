@@ -0,0 +1,11 @@
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // bytes字段生成为 bytes::Bytes，避免解码大张量时再复制一次
+    let mut config = prost_build::Config::new();
+    config.bytes(["."]);
+    tonic_build::configure().compile_with_config(
+        config,
+        &["src/api/grpc/proto/inference.proto", "src/api/grpc/proto/plugin.proto"],
+        &["src/api/grpc/proto"],
+    )?;
+    Ok(())
+}
@@ -0,0 +1,84 @@
+/*
+ * UniModel C ABI插件接口
+ *
+ * 插件编译为动态库放入 plugins.plugin_dir，导出 unimodel_plugin_abi_version 和
+ * unimodel_plugin_descriptor 两个符号。服务器加载时先比较ABI版本，不一致的插件被拒绝。
+ *
+ * 所有调用的请求和响应都是MessagePack映射：
+ *   load_model     请求 {model_id, model_path, config, parallel, precision}；单卡运行时 parallel 为nil。
+ *                  张量并行时为 {kind: "tensor", device_ids, tensors}，tensors 中每项为
+ *                  {name, split, shape, shard_shape}，split 为 column/row/replicated，插件把第i个分片放到
+ *                  device_ids[i] 上；流水线并行时为 {kind: "pipeline", stages, micro_batches, num_layers}，
+ *                  stages 中每项为 {device_id, first_layer, end_layer, tensors}，插件把该阶段的层放到
+ *                  device_id 上，num_layers 为0时由插件按阶段数自行划分。
+ *                  precision 为 "FP32"、"FP16" 或 "BF16"，后两者时插件以该精度启用自动混合精度
+ *                  响应 {handle, supports_batching, max_batch_size, supports_multi_lora, precision}；
+ *                  precision 为实际使用的精度，省略时按能力声明推断
+ *   unload_model   请求 {handle}
+ *   infer          请求 {handle, inputs, parameters, stream, adapters}，响应为与inputs等长的输出数组；
+ *                  stream 为CUDA流编号（0到 config.optimization.cuda_streams-1，ROCm设备上为HIP流），
+ *                  同一编号不会被并发使用；adapters 只发给 supports_multi_lora 为真的插件，
+ *                  与inputs等长，每项为该输入使用的LoRA适配器名或nil，省略时整批使用 parameters.adapter
+ *   load_adapter   请求 {handle, name, path}
+ *   unload_adapter 请求 {handle, name}
+ *   capabilities   请求 {}，响应为能力声明数组，每项为
+ *                  {backend, formats, devices, max_batch_size, streaming, quantization, tensor_parallel,
+ *                   pipeline_parallel, mixed_precision}，mixed_precision 为能自动转换到的低精度数组
+ * config.optimization.cuda_graphs 为真时，服务器在预热阶段按每个捕获批大小各调用一次 infer，
+ * 插件应在这些调用中捕获CUDA graph；之后的批次都会被填充到捕获过的批大小，插件直接重放。
+ * 流水线并行的模型，服务器把每个批次拆成 micro_batches 个微批次，以不同的 stream 同时调用 infer，
+ * 插件在前一阶段完成某个微批次后即可把它交给下一阶段，各阶段同时处理不同的微批次。
+ * 返回0表示成功，response写入结果；非0表示失败，response写入UTF-8错误信息。
+ * response由插件分配，服务器读取后调用 free_buffer 释放。函数须可被多个线程同时调用。
+ */
+
+#ifndef UNIMODEL_PLUGIN_H
+#define UNIMODEL_PLUGIN_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define UNIMODEL_PLUGIN_ABI_VERSION 1
+
+typedef struct {
+    uint8_t *data;
+    size_t len;
+} UniModelBuffer;
+
+typedef int32_t (*UniModelPluginCall)(const uint8_t *request, size_t request_len, UniModelBuffer *response);
+
+typedef struct {
+    /* sizeof(UniModelPluginVTable)，服务器据此判断末尾的可选函数是否存在 */
+    size_t struct_size;
+    UniModelPluginCall load_model;
+    UniModelPluginCall unload_model;
+    UniModelPluginCall infer;
+    void (*free_buffer)(UniModelBuffer buffer);
+    /* 可选，不支持LoRA适配器时置为NULL */
+    UniModelPluginCall load_adapter;
+    UniModelPluginCall unload_adapter;
+    /* 可选，未提供时服务器不限制该插件后端可加载的模型 */
+    UniModelPluginCall capabilities;
+} UniModelPluginVTable;
+
+typedef struct {
+    uint32_t abi_version;
+    const char *name;
+    const char *version;
+    /* 插件提供的后端名称，以NULL结尾 */
+    const char *const *backends;
+    UniModelPluginVTable vtable;
+} UniModelPluginDescriptor;
+
+uint32_t unimodel_plugin_abi_version(void);
+const UniModelPluginDescriptor *unimodel_plugin_descriptor(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* UNIMODEL_PLUGIN_H */
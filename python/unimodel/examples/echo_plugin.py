@@ -0,0 +1,8 @@
+"""Minimal model for the Python backend: returns every input unchanged."""
+
+from unimodel.plugins.base import ModelPlugin
+
+
+class EchoModel(ModelPlugin):
+    def predict(self, inputs, parameters):
+        return inputs
@@ -0,0 +1,26 @@
+"""Interface implemented by user scripts served through the Python backend."""
+
+
+class ModelPlugin:
+    """Base class for Python models.
+
+    Subclass it in a script and register the model with ``backend: "python"``.
+    Inputs arrive as plain Python values: ``str`` for text, ``bytes`` for binary
+    payloads, decoded JSON for JSON inputs and ``dict`` for multimodal inputs.
+    ``predict`` returns one output per input; ``str``, ``bytes`` and JSON-compatible
+    values are wrapped automatically.
+    """
+
+    def load(self, model_path, config):
+        """Load the model. ``config`` is the server-side ModelConfig as a dict."""
+
+    def predict(self, inputs, parameters):
+        """Run inference on a batch of inputs and return a list of outputs."""
+        raise NotImplementedError
+
+    def unload(self):
+        """Release resources before the worker exits."""
+
+    def health(self):
+        """Report whether the model can still serve requests."""
+        return True
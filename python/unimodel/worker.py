@@ -0,0 +1,107 @@
+"""Worker process for the Python backend.
+
+Started by the server as ``python -m unimodel.worker <script>``. Requests and
+responses are MessagePack maps framed by a 4-byte little-endian length on
+stdin/stdout; stdout is reserved for the protocol, so user output goes to stderr.
+"""
+
+import importlib.util
+import inspect
+import struct
+import sys
+import traceback
+
+import msgpack
+
+from unimodel.plugins.base import ModelPlugin
+
+
+def read_frame(stream):
+    header = stream.read(4)
+    if len(header) < 4:
+        return None
+    (length,) = struct.unpack("<I", header)
+    return stream.read(length)
+
+
+def write_frame(stream, message):
+    payload = msgpack.packb(message, use_bin_type=True)
+    stream.write(struct.pack("<I", len(payload)))
+    stream.write(payload)
+    stream.flush()
+
+
+def load_plugin(script):
+    spec = importlib.util.spec_from_file_location("unimodel_user_model", script)
+    module = importlib.util.module_from_spec(spec)
+    spec.loader.exec_module(module)
+    classes = [
+        obj
+        for obj in vars(module).values()
+        if inspect.isclass(obj) and issubclass(obj, ModelPlugin) and obj is not ModelPlugin
+    ]
+    if len(classes) != 1:
+        raise RuntimeError(f"{script} must define exactly one ModelPlugin subclass, found {len(classes)}")
+    return classes[0]()
+
+
+def to_python(value):
+    kind, data = value["type"], value.get("data")
+    if kind == "Multimodal":
+        return {name: to_python(item) for name, item in data.items()}
+    return data
+
+
+def to_output(value):
+    if isinstance(value, dict) and set(value) <= {"type", "data"} and "type" in value:
+        return value
+    if isinstance(value, str):
+        return {"type": "Text", "data": value}
+    if isinstance(value, (bytes, bytearray)):
+        return {"type": "Binary", "data": bytes(value)}
+    return {"type": "Json", "data": value}
+
+
+def handle(plugin, method, params):
+    if method == "load":
+        plugin.load(params["model_path"], params["config"])
+        return True
+    if method == "predict":
+        inputs = [to_python(item) for item in params["inputs"]]
+        outputs = plugin.predict(inputs, params["parameters"])
+        if len(outputs) != len(inputs):
+            raise RuntimeError(f"predict returned {len(outputs)} outputs for {len(inputs)} inputs")
+        return [to_output(item) for item in outputs]
+    if method == "health":
+        return bool(plugin.health())
+    if method == "unload":
+        plugin.unload()
+        return True
+    raise RuntimeError(f"unknown method {method!r}")
+
+
+def main():
+    if len(sys.argv) != 2:
+        print("usage: python -m unimodel.worker <script>", file=sys.stderr)
+        return 2
+    protocol_in, protocol_out = sys.stdin.buffer, sys.stdout.buffer
+    sys.stdout = sys.stderr
+
+    plugin = load_plugin(sys.argv[1])
+    while True:
+        frame = read_frame(protocol_in)
+        if frame is None:
+            return 0
+        request = msgpack.unpackb(frame, raw=False)
+        try:
+            result = handle(plugin, request["method"], request.get("params"))
+            write_frame(protocol_out, {"id": request["id"], "result": result})
+        except Exception as error:
+            traceback.print_exc()
+            write_frame(protocol_out, {"id": request["id"], "error": f"{type(error).__name__}: {error}"})
+        if request["method"] == "unload":
+            return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
@@ -0,0 +1,224 @@
+//! 认证中间件
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+use dashmap::DashMap;
+use sha2::{Digest, Sha256};
+use tracing::debug;
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::SecurityConfig;
+use crate::infrastructure::security::{api_key_id, ApiKeyRecord, ApiKeyStore, SCOPE_ALL};
+
+/// API密钥请求头
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// 认证上下文，由中间件写入请求扩展
+#[derive(Debug, Clone)]
+pub struct AuthContext {
+    /// 动态密钥ID（静态密钥和匿名访问为None）
+    pub key_id: Option<String>,
+    /// 所属租户（None表示可访问全部租户）
+    pub tenant: Option<String>,
+    /// 权限范围
+    pub scopes: Vec<String>,
+    /// 每分钟请求数限制
+    pub rate_limit_per_minute: Option<u32>,
+    /// 匹配的静态密钥的哈希，按密钥限流
+    pub static_key_hash: Option<u64>,
+}
+
+impl AuthContext {
+    /// 未启用认证时的匿名上下文
+    pub fn anonymous() -> Self {
+        Self {
+            key_id: None,
+            tenant: None,
+            scopes: vec![SCOPE_ALL.to_string()],
+            rate_limit_per_minute: None,
+            static_key_hash: None,
+        }
+    }
+
+    /// 检查是否拥有指定权限
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope || s == SCOPE_ALL)
+    }
+
+    /// 限额与计量的主体标识
+    pub fn subject(&self) -> &str {
+        self.key_id.as_deref().unwrap_or("anonymous")
+    }
+
+    /// 要求拥有指定权限
+    pub fn require_scope(&self, scope: &str) -> Result<()> {
+        if self.has_scope(scope) {
+            Ok(())
+        } else {
+            Err(UniModelError::authorization(format!("Missing required scope: {}", scope)))
+        }
+    }
+}
+
+/// 最近校验通过的动态密钥
+#[derive(Debug)]
+struct VerifiedKey {
+    /// 明文密钥的SHA-256摘要
+    digest: [u8; 32],
+    /// 校验时记录中的argon2哈希
+    key_hash: String,
+    verified_at: Instant,
+}
+
+/// 认证器
+///
+/// 动态密钥的argon2校验开销较大，校验通过后按密钥ID缓存 `api_key_cache_ttl_secs`；
+/// 命中缓存时仍读取当前记录，吊销和过期立即生效。
+#[derive(Debug)]
+pub struct Authenticator {
+    enabled: bool,
+    /// 配置文件中的静态密钥，拥有全部权限
+    static_keys: HashSet<String>,
+    key_store: Arc<ApiKeyStore>,
+    cache_ttl: Duration,
+    /// 密钥ID -> 最近一次校验通过的密钥
+    verified: DashMap<String, VerifiedKey>,
+}
+
+impl Authenticator {
+    /// 创建认证器
+    pub fn new(config: &SecurityConfig, key_store: Arc<ApiKeyStore>) -> Self {
+        Self {
+            enabled: config.auth_enabled,
+            static_keys: config.api_keys.iter().cloned().collect(),
+            key_store,
+            cache_ttl: Duration::from_secs(config.api_key_cache_ttl_secs),
+            verified: DashMap::new(),
+        }
+    }
+
+    /// 认证请求
+    pub async fn authenticate(&self, headers: &HeaderMap) -> Result<AuthContext> {
+        if !self.enabled {
+            return Ok(AuthContext::anonymous());
+        }
+
+        let raw_key = extract_api_key(headers)
+            .ok_or_else(|| UniModelError::authentication("Missing API key"))?;
+
+        if self.static_keys.contains(raw_key) {
+            let mut hasher = DefaultHasher::new();
+            raw_key.hash(&mut hasher);
+            return Ok(AuthContext {
+                static_key_hash: Some(hasher.finish()),
+                ..AuthContext::anonymous()
+            });
+        }
+
+        let record = match self.cached_record(raw_key).await {
+            Some(record) => Some(record),
+            None => self.verify(raw_key).await,
+        };
+        match record {
+            Some(record) => {
+                debug!("Authenticated API key: {}", record.id);
+                Ok(AuthContext {
+                    key_id: Some(record.id),
+                    tenant: record.tenant,
+                    scopes: record.scopes,
+                    rate_limit_per_minute: record.rate_limit_per_minute,
+                    static_key_hash: None,
+                })
+            }
+            None => Err(UniModelError::authentication("Invalid, expired or revoked API key")),
+        }
+    }
+
+    /// 密钥在缓存期内校验通过过时返回当前可用的记录
+    async fn cached_record(&self, raw_key: &str) -> Option<ApiKeyRecord> {
+        let id = api_key_id(raw_key)?;
+        let digest: [u8; 32] = Sha256::digest(raw_key.as_bytes()).into();
+        let key_hash = self.verified
+            .get(id)
+            .filter(|v| v.digest == digest && v.verified_at.elapsed() < self.cache_ttl)
+            .map(|v| v.key_hash.clone())?;
+
+        let record = self.key_store.get(id).await?;
+        (record.key_hash == key_hash && record.is_active()).then_some(record)
+    }
+
+    /// 校验密钥哈希，通过时写入缓存
+    async fn verify(&self, raw_key: &str) -> Option<ApiKeyRecord> {
+        let record = self.key_store.verify(raw_key).await?;
+        if !self.cache_ttl.is_zero() {
+            self.verified.insert(record.id.clone(), VerifiedKey {
+                digest: Sha256::digest(raw_key.as_bytes()).into(),
+                key_hash: record.key_hash.clone(),
+                verified_at: Instant::now(),
+            });
+        }
+        Some(record)
+    }
+}
+
+/// 从请求头中提取API密钥（`X-API-Key` 或 `Authorization: Bearer`）
+pub fn extract_api_key(headers: &HeaderMap) -> Option<&str> {
+    if let Some(value) = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok()) {
+        return Some(value.trim());
+    }
+
+    headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .map(str::trim)
+}
+
+/// 计算限流主体标识：动态密钥ID > 静态密钥（哈希） > 客户端IP
+///
+/// 只有认证通过的密钥才单独分桶；未经验证的密钥按IP限流，否则每次换一个随机密钥就能绕过限流。
+pub fn rate_limit_key(context: Option<&AuthContext>, client_ip: Option<IpAddr>) -> String {
+    if let Some(key_id) = context.and_then(|c| c.key_id.as_deref()) {
+        return format!("key:{}", key_id);
+    }
+    if let Some(hash) = context.and_then(|c| c.static_key_hash) {
+        return format!("key-hash:{:016x}", hash);
+    }
+    match client_ip {
+        Some(ip) => format!("ip:{}", ip),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// 认证中间件
+pub async fn auth_middleware<B>(
+    State(authenticator): State<Arc<Authenticator>>,
+    mut request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    match authenticator.authenticate(request.headers()).await {
+        Ok(context) => {
+            request.extensions_mut().insert(context);
+            next.run(request).await
+        }
+        Err(e) => (
+            StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::UNAUTHORIZED),
+            Json(serde_json::json!({
+                "error": e.error_code(),
+                "message": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,7 @@
+//! 认证与授权
+
+pub mod jwt;
+pub mod middleware;
+pub mod rbac;
+
+pub use middleware::*;
@@ -0,0 +1,83 @@
+//! gRPC认证层
+//!
+//! 与REST认证中间件使用同一个 `Authenticator`：从请求元数据的 `x-api-key` 或
+//! `authorization: Bearer` 中取出API密钥，认证通过后把 `AuthContext` 写入请求扩展，
+//! 失败时返回 `UNAUTHENTICATED`。健康检查服务不需要认证。
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use tonic::body::BoxBody;
+use tower::{Layer, Service};
+
+use crate::api::auth::Authenticator;
+
+/// 不经过认证的gRPC服务路径前缀
+const UNAUTHENTICATED_PATHS: &[&str] = &["/grpc.health.v1.Health/"];
+
+/// gRPC认证层
+#[derive(Debug, Clone)]
+pub struct GrpcAuthLayer {
+    authenticator: Arc<Authenticator>,
+}
+
+impl GrpcAuthLayer {
+    pub fn new(authenticator: Arc<Authenticator>) -> Self {
+        Self { authenticator }
+    }
+}
+
+impl<S> Layer<S> for GrpcAuthLayer {
+    type Service = GrpcAuth<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcAuth {
+            inner,
+            authenticator: Arc::clone(&self.authenticator),
+        }
+    }
+}
+
+/// gRPC认证服务
+#[derive(Debug, Clone)]
+pub struct GrpcAuth<S> {
+    inner: S,
+    authenticator: Arc<Authenticator>,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for GrpcAuth<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: http::Request<ReqBody>) -> Self::Future {
+        // 使用已就绪的服务处理请求，克隆体留给下一次poll_ready
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        if UNAUTHENTICATED_PATHS.iter().any(|path| request.uri().path().starts_with(path)) {
+            return Box::pin(inner.call(request));
+        }
+
+        let authenticator = Arc::clone(&self.authenticator);
+        Box::pin(async move {
+            match authenticator.authenticate(request.headers()).await {
+                Ok(context) => {
+                    request.extensions_mut().insert(context);
+                    inner.call(request).await
+                }
+                Err(e) => Ok(tonic::Status::from(e).to_http()),
+            }
+        })
+    }
+}
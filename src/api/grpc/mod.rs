@@ -0,0 +1,7 @@
+//! gRPC API
+
+pub mod auth;
+pub mod proto;
+pub mod rate_limit;
+pub mod server;
+pub mod service;
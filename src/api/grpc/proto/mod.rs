@@ -0,0 +1,10 @@
+//! gRPC协议定义（由 build.rs 从 .proto 生成）
+
+pub mod inference {
+    tonic::include_proto!("unimodel.inference");
+}
+
+/// 进程外插件协议
+pub mod plugin {
+    tonic::include_proto!("unimodel.plugin");
+}
@@ -0,0 +1,99 @@
+//! gRPC速率限制层
+//!
+//! 位于 `GrpcAuthLayer` 之后，与REST一样按认证通过的密钥或客户端IP限流；
+//! 超限时返回 `RESOURCE_EXHAUSTED`。
+
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+use futures::future::BoxFuture;
+use tonic::body::BoxBody;
+use tonic::metadata::{MetadataMap, MetadataValue};
+use tonic::transport::server::TcpConnectInfo;
+use tower::{Layer, Service};
+
+use crate::api::auth::{rate_limit_key, AuthContext};
+use crate::common::error::OverloadHint;
+use crate::infrastructure::security::RateLimiter;
+
+/// gRPC速率限制层
+#[derive(Debug, Clone)]
+pub struct GrpcRateLimitLayer {
+    limiter: Arc<RateLimiter>,
+}
+
+impl GrpcRateLimitLayer {
+    pub fn new(limiter: Arc<RateLimiter>) -> Self {
+        Self { limiter }
+    }
+}
+
+impl<S> Layer<S> for GrpcRateLimitLayer {
+    type Service = GrpcRateLimit<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        GrpcRateLimit {
+            inner,
+            limiter: Arc::clone(&self.limiter),
+        }
+    }
+}
+
+/// gRPC速率限制服务
+#[derive(Debug, Clone)]
+pub struct GrpcRateLimit<S> {
+    inner: S,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, ReqBody> Service<http::Request<ReqBody>> for GrpcRateLimit<S>
+where
+    S: Service<http::Request<ReqBody>, Response = http::Response<BoxBody>> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: http::Request<ReqBody>) -> Self::Future {
+        if self.limiter.is_enabled() {
+            let client_ip = request
+                .extensions()
+                .get::<TcpConnectInfo>()
+                .and_then(|info| info.remote_addr())
+                .map(|addr| addr.ip());
+            let context = request.extensions().get::<AuthContext>();
+            let key_id = context.and_then(|c| c.key_id.as_deref());
+            let key_rate_limit = context.and_then(|c| c.rate_limit_per_minute);
+            let bucket_key = rate_limit_key(context, client_ip);
+            let requests_per_minute = self.limiter.requests_per_minute_for(key_id, key_rate_limit);
+
+            if let Err(retry_after) = self.limiter.check(&bucket_key, requests_per_minute) {
+                let hint = OverloadHint::retry_after(retry_after);
+                let mut metadata = MetadataMap::new();
+                metadata.insert("retry-after", MetadataValue::from(hint.retry_after_secs()));
+                metadata.insert("retry-after-ms", MetadataValue::from(hint.retry_after_ms));
+
+                let status = tonic::Status::with_metadata(
+                    tonic::Code::ResourceExhausted,
+                    format!(
+                        "Rate limit exceeded: limit of {} requests per minute reached",
+                        requests_per_minute
+                    ),
+                    metadata,
+                );
+                return Box::pin(async move { Ok(status.to_http()) });
+            }
+        }
+
+        // 使用已就绪的服务处理请求，克隆体留给下一次poll_ready
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        Box::pin(inner.call(request))
+    }
+}
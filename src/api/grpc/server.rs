@@ -0,0 +1,81 @@
+//! gRPC服务器
+
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+
+use tokio_stream::wrappers::TcpListenerStream;
+use tokio_util::sync::CancellationToken;
+use tonic::transport::Server;
+use tracing::info;
+
+use crate::api::auth::Authenticator;
+use crate::api::grpc::auth::GrpcAuthLayer;
+use crate::api::grpc::proto::inference::inference_service_server::InferenceServiceServer;
+use crate::api::grpc::rate_limit::GrpcRateLimitLayer;
+use crate::api::grpc::service::InferenceGrpcService;
+use crate::api::listener::{self, ListenFds};
+use crate::api::rest::handlers::AppState;
+use crate::common::error::*;
+use crate::infrastructure::configuration::Config;
+use crate::infrastructure::security::RateLimiter;
+
+/// 单条gRPC消息的大小上限（张量输入可能较大）
+pub(crate) const MAX_MESSAGE_BYTES: usize = 128 * 1024 * 1024;
+
+/// gRPC服务器
+pub struct GrpcServer {
+    listener: TcpListener,
+    authenticator: Arc<Authenticator>,
+    rate_limiter: Arc<RateLimiter>,
+    state: AppState,
+}
+
+impl GrpcServer {
+    /// 创建gRPC服务器并取得监听套接字，优先使用systemd传入的 `grpc` 套接字
+    pub async fn new(
+        config: &Config,
+        state: AppState,
+        rate_limiter: Arc<RateLimiter>,
+        inherited: &ListenFds,
+    ) -> Result<Self> {
+        let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.grpc_port)
+            .parse()
+            .map_err(|e| UniModelError::config(format!("Invalid gRPC address: {}", e)))?;
+        let listener = listener::listen("grpc", 1, addr, config.server.reuse_port, inherited)?;
+
+        let authenticator = Arc::new(Authenticator::new(
+            &config.security,
+            Arc::clone(&state.api_key_store),
+        ));
+
+        Ok(Self {
+            listener,
+            authenticator,
+            rate_limiter,
+            state,
+        })
+    }
+
+    /// 启动gRPC服务，`shutdown` 取消后停止接受新请求，处理中的调用完成后返回
+    pub async fn serve(self, shutdown: CancellationToken) -> Result<()> {
+        info!("gRPC API listening on {}", self.listener.local_addr()?);
+        let incoming = TcpListenerStream::new(tokio::net::TcpListener::from_std(self.listener)?);
+
+        let (_health_reporter, health_service) = tonic_health::server::health_reporter();
+        let inference_service = InferenceServiceServer::new(InferenceGrpcService::new(self.state))
+            .max_decoding_message_size(MAX_MESSAGE_BYTES)
+            .max_encoding_message_size(MAX_MESSAGE_BYTES);
+
+        Server::builder()
+            .layer(GrpcAuthLayer::new(self.authenticator))
+            .layer(GrpcRateLimitLayer::new(self.rate_limiter))
+            .add_service(health_service)
+            .add_service(inference_service)
+            .serve_with_incoming_shutdown(incoming, async move { shutdown.cancelled().await })
+            .await
+            .map_err(|e| UniModelError::Network(format!("gRPC server error: {}", e)))?;
+
+        info!("gRPC API stopped");
+        Ok(())
+    }
+}
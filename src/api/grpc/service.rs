@@ -0,0 +1,327 @@
+//! gRPC推理服务
+//!
+//! 张量以 `Tensor`（dtype、shape、原始字节）传输，不再经过JSON字符串中转。
+//! 请求由 `GrpcAuthLayer` 认证，与REST一样按租户访问模型、检查配额并记录用量。
+
+use tonic::{Request, Response, Status};
+
+use crate::api::auth::AuthContext;
+use crate::api::grpc::proto::inference as pb;
+use crate::api::grpc::proto::inference::inference_service_server::InferenceService;
+use crate::api::rest::handlers::predict_handler::record_usage;
+use crate::api::rest::handlers::AppState;
+use crate::common::error::*;
+use crate::common::types::*;
+
+/// gRPC推理服务
+pub struct InferenceGrpcService {
+    state: AppState,
+}
+
+impl InferenceGrpcService {
+    /// 创建推理服务，与REST API共用应用状态
+    pub fn new(state: AppState) -> Self {
+        Self { state }
+    }
+}
+
+#[tonic::async_trait]
+impl InferenceService for InferenceGrpcService {
+    async fn predict(
+        &self,
+        request: Request<pb::PredictRequest>,
+    ) -> std::result::Result<Response<pb::PredictResponse>, Status> {
+        let auth = request
+            .extensions()
+            .get::<AuthContext>()
+            .cloned()
+            .ok_or_else(|| Status::unauthenticated("Request was not authenticated"))?;
+        let request = request.into_inner();
+        let input = request.input
+            .ok_or_else(|| Status::invalid_argument("Missing input"))?
+            .try_into()?;
+        let parameters = match request.parameters {
+            Some(parameters) => parameters.try_into()?,
+            None => PredictionParameters::default(),
+        };
+
+        self.state.quota_manager.check_request(auth.subject()).await?;
+        // 其他租户的模型按不存在处理
+        self.state.model_service
+            .get_model_info(&request.model_id, auth.tenant.as_deref())
+            .await?;
+        let response = self.state.prediction_service
+            .predict(request.model_id, input, parameters, auth.tenant.as_deref())
+            .await?;
+        record_usage(&self.state, &auth, &response).await;
+
+        Ok(Response::new(pb::PredictResponse {
+            request_id: response.request_id,
+            model_id: response.model_id,
+            output: Some(response.output.into()),
+            total_latency_ms: response.metrics.total_latency_ms,
+            queue_wait_ms: response.metrics.queue_wait_ms,
+            tokens_input: response.metrics.tokens_input,
+            tokens_generated: response.metrics.tokens_generated,
+            metadata_json: serde_json::to_string(&response.metadata.custom_metadata).map_err(UniModelError::from)?,
+            precision: response.metadata.precision.as_ref().map(enum_name).unwrap_or_default(),
+        }))
+    }
+}
+
+impl From<UniModelError> for Status {
+    fn from(error: UniModelError) -> Self {
+        let message = error.to_string();
+        match error.status_code() {
+            400 => Status::invalid_argument(message),
+            401 => Status::unauthenticated(message),
+            403 => Status::permission_denied(message),
+            404 => Status::not_found(message),
+            429 => Status::resource_exhausted(message),
+            502 | 503 => Status::unavailable(message),
+            _ => Status::internal(message),
+        }
+    }
+}
+
+impl TryFrom<pb::Tensor> for Tensor {
+    type Error = UniModelError;
+
+    fn try_from(tensor: pb::Tensor) -> Result<Self> {
+        let dtype = pb::DataType::from_i32(tensor.dtype)
+            .and_then(data_type_from_proto)
+            .ok_or_else(|| UniModelError::validation(format!("Unsupported tensor dtype {}", tensor.dtype)))?;
+        let shape = tensor.shape
+            .into_iter()
+            .map(|dim| usize::try_from(dim).map_err(|_| UniModelError::validation("Tensor dimension too large")))
+            .collect::<Result<Vec<_>>>()?;
+
+        Tensor::new(dtype, shape, tensor.data.to_vec())
+    }
+}
+
+impl From<Tensor> for pb::Tensor {
+    fn from(tensor: Tensor) -> Self {
+        pb::Tensor {
+            dtype: data_type_to_proto(tensor.dtype) as i32,
+            shape: tensor.shape.into_iter().map(|dim| dim as u64).collect(),
+            data: tensor.data.into(),
+        }
+    }
+}
+
+impl TryFrom<pb::InputData> for InputData {
+    type Error = UniModelError;
+
+    fn try_from(input: pb::InputData) -> Result<Self> {
+        use pb::input_data::Data;
+
+        Ok(match input.data.ok_or_else(|| UniModelError::validation("Input has no data"))? {
+            Data::Text(text) => InputData::Text(text),
+            Data::Binary(data) => InputData::Binary(data),
+            Data::Json(json) => InputData::Json(serde_json::from_str(&json)?),
+            Data::Tensors(map) => InputData::Tensors(
+                map.tensors
+                    .into_iter()
+                    .map(|(name, tensor)| Ok((name, tensor.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Data::Multimodal(multimodal) => InputData::Multimodal(
+                multimodal.parts
+                    .into_iter()
+                    .map(|(name, part)| Ok((name, part.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+}
+
+impl From<OutputData> for pb::OutputData {
+    fn from(output: OutputData) -> Self {
+        use pb::output_data::Data;
+
+        let data = match output {
+            OutputData::Text(text) => Data::Text(text),
+            OutputData::Binary(data) => Data::Binary(data),
+            OutputData::Json(json) => Data::Json(json.to_string()),
+            OutputData::Tensors(tensors) => Data::Tensors(pb::TensorMap {
+                tensors: tensors.into_iter().map(|(name, tensor)| (name, tensor.into())).collect(),
+            }),
+            OutputData::Multimodal(parts) => Data::Multimodal(pb::MultimodalOutput {
+                parts: parts.into_iter().map(|(name, part)| (name, part.into())).collect(),
+            }),
+        };
+        pb::OutputData { data: Some(data) }
+    }
+}
+
+impl From<InputData> for pb::InputData {
+    fn from(input: InputData) -> Self {
+        use pb::input_data::Data;
+
+        let data = match input {
+            InputData::Text(text) => Data::Text(text),
+            InputData::Binary(data) => Data::Binary(data),
+            InputData::Json(json) => Data::Json(json.to_string()),
+            InputData::Tensors(tensors) => Data::Tensors(pb::TensorMap {
+                tensors: tensors.into_iter().map(|(name, tensor)| (name, tensor.into())).collect(),
+            }),
+            InputData::Multimodal(parts) => Data::Multimodal(pb::MultimodalInput {
+                parts: parts.into_iter().map(|(name, part)| (name, part.into())).collect(),
+            }),
+        };
+        pb::InputData { data: Some(data) }
+    }
+}
+
+impl TryFrom<pb::OutputData> for OutputData {
+    type Error = UniModelError;
+
+    fn try_from(output: pb::OutputData) -> Result<Self> {
+        use pb::output_data::Data;
+
+        Ok(match output.data.ok_or_else(|| UniModelError::plugin("Output has no data"))? {
+            Data::Text(text) => OutputData::Text(text),
+            Data::Binary(data) => OutputData::Binary(data),
+            Data::Json(json) => OutputData::Json(serde_json::from_str(&json)?),
+            Data::Tensors(map) => OutputData::Tensors(
+                map.tensors
+                    .into_iter()
+                    .map(|(name, tensor)| Ok((name, tensor.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+            Data::Multimodal(multimodal) => OutputData::Multimodal(
+                multimodal.parts
+                    .into_iter()
+                    .map(|(name, part)| Ok((name, part.try_into()?)))
+                    .collect::<Result<_>>()?,
+            ),
+        })
+    }
+}
+
+impl TryFrom<pb::PredictionParameters> for PredictionParameters {
+    type Error = UniModelError;
+
+    fn try_from(parameters: pb::PredictionParameters) -> Result<Self> {
+        Ok(PredictionParameters {
+            max_tokens: parameters.max_tokens,
+            temperature: parameters.temperature,
+            top_p: parameters.top_p,
+            top_k: parameters.top_k,
+            stream: None,
+            priority: if parameters.priority.is_empty() {
+                Priority::default()
+            } else {
+                parameters.priority.parse()?
+            },
+            timeout_ms: parameters.timeout_ms,
+            mask_format: if parameters.mask_format.is_empty() {
+                None
+            } else {
+                Some(parameters.mask_format.parse()?)
+            },
+            stop: parameters.stop,
+            logit_bias: parameters.logit_bias,
+            logprobs: parameters.logprobs,
+            top_logprobs: parameters.top_logprobs,
+            n: parameters.n,
+            num_beams: parameters.num_beams,
+            session_id: parameters.session_id,
+            adapter: parameters.adapter,
+            seed: parameters.seed,
+            grammar: parameters.grammar,
+            response_format: if parameters.response_format_json.is_empty() {
+                None
+            } else {
+                Some(serde_json::from_str(&parameters.response_format_json)?)
+            },
+            custom: if parameters.custom_json.is_empty() {
+                Default::default()
+            } else {
+                serde_json::from_str(&parameters.custom_json)?
+            },
+        })
+    }
+}
+
+impl From<&PredictionParameters> for pb::PredictionParameters {
+    fn from(parameters: &PredictionParameters) -> Self {
+        pb::PredictionParameters {
+            max_tokens: parameters.max_tokens,
+            temperature: parameters.temperature,
+            top_p: parameters.top_p,
+            top_k: parameters.top_k,
+            priority: enum_name(&parameters.priority),
+            timeout_ms: parameters.timeout_ms,
+            custom_json: if parameters.custom.is_empty() {
+                String::new()
+            } else {
+                serde_json::json!(parameters.custom).to_string()
+            },
+            mask_format: parameters.mask_format.as_ref().map(enum_name).unwrap_or_default(),
+            stop: parameters.stop.clone(),
+            logit_bias: parameters.logit_bias.clone(),
+            logprobs: parameters.logprobs,
+            top_logprobs: parameters.top_logprobs,
+            n: parameters.n,
+            num_beams: parameters.num_beams,
+            grammar: parameters.grammar.clone(),
+            response_format_json: parameters.response_format.as_ref()
+                .and_then(|format| serde_json::to_string(format).ok())
+                .unwrap_or_default(),
+            seed: parameters.seed,
+            session_id: parameters.session_id.clone(),
+            adapter: parameters.adapter.clone(),
+        }
+    }
+}
+
+/// 按serde名称输出的枚举值（如 `Priority::High` -> `"high"`）
+pub(crate) fn enum_name<T: serde::Serialize>(value: &T) -> String {
+    match serde_json::to_value(value) {
+        Ok(serde_json::Value::String(name)) => name,
+        _ => String::new(),
+    }
+}
+
+fn data_type_from_proto(dtype: pb::DataType) -> Option<DataType> {
+    Some(match dtype {
+        pb::DataType::Unspecified => return None,
+        pb::DataType::Bool => DataType::Bool,
+        pb::DataType::Uint8 => DataType::U8,
+        pb::DataType::Int8 => DataType::I8,
+        pb::DataType::Uint16 => DataType::U16,
+        pb::DataType::Int16 => DataType::I16,
+        pb::DataType::Float16 => DataType::F16,
+        pb::DataType::Bfloat16 => DataType::BF16,
+        pb::DataType::Uint32 => DataType::U32,
+        pb::DataType::Int32 => DataType::I32,
+        pb::DataType::Float32 => DataType::F32,
+        pb::DataType::Uint64 => DataType::U64,
+        pb::DataType::Int64 => DataType::I64,
+        pb::DataType::Float64 => DataType::F64,
+        pb::DataType::Float8E4m3 => DataType::F8E4M3,
+        pb::DataType::Float8E5m2 => DataType::F8E5M2,
+    })
+}
+
+fn data_type_to_proto(dtype: DataType) -> pb::DataType {
+    match dtype {
+        DataType::Bool => pb::DataType::Bool,
+        DataType::U8 => pb::DataType::Uint8,
+        DataType::I8 => pb::DataType::Int8,
+        DataType::U16 => pb::DataType::Uint16,
+        DataType::I16 => pb::DataType::Int16,
+        DataType::F16 => pb::DataType::Float16,
+        DataType::BF16 => pb::DataType::Bfloat16,
+        DataType::U32 => pb::DataType::Uint32,
+        DataType::I32 => pb::DataType::Int32,
+        DataType::F32 => pb::DataType::Float32,
+        DataType::U64 => pb::DataType::Uint64,
+        DataType::I64 => pb::DataType::Int64,
+        DataType::F64 => pb::DataType::Float64,
+        DataType::F8E4M3 => pb::DataType::Float8E4m3,
+        DataType::F8E5M2 => pb::DataType::Float8E5m2,
+    }
+}
@@ -0,0 +1,113 @@
+//! 监听套接字
+//!
+//! 滚动重启时新进程要在旧进程排空期间接收连接，支持两种交接方式：
+//! - `server.reuse_port`：以 SO_REUSEPORT 绑定，新进程启动后与旧进程同时监听同一端口，内核把新连接
+//!   分给两者；旧进程收到SIGTERM后关闭监听套接字，排空已受理的请求后退出。
+//! - systemd socket activation：监听套接字由systemd创建并通过 `LISTEN_FDS` 传给服务进程，重启期间
+//!   新连接在套接字的backlog中等待，不会被拒绝。`.socket` 单元中以 `FileDescriptorName=http`、
+//!   `FileDescriptorName=grpc` 区分，未命名时第一个为HTTP、第二个为gRPC。
+
+use std::net::{SocketAddr, TcpListener};
+use std::os::unix::io::{FromRawFd, RawFd};
+
+use socket2::{Domain, Socket, Type};
+use tracing::info;
+
+use crate::common::error::*;
+
+/// systemd传入的第一个fd
+pub const SD_LISTEN_FDS_START: RawFd = 3;
+
+/// 监听队列长度
+const LISTEN_BACKLOG: i32 = 1024;
+
+/// systemd socket activation传入的监听套接字
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ListenFds {
+    /// fd号及 `LISTEN_FDNAMES` 中对应的名称
+    fds: Vec<(RawFd, Option<String>)>,
+}
+
+impl ListenFds {
+    /// 按 sd_listen_fds 的约定解析环境变量，`LISTEN_PID` 不是 `pid` 时没有传入的套接字
+    pub fn parse(pid: u32, listen_pid: Option<&str>, listen_fds: Option<&str>, names: Option<&str>) -> Result<Self> {
+        if listen_pid.and_then(|p| p.trim().parse::<u32>().ok()) != Some(pid) {
+            return Ok(Self::default());
+        }
+        let count: RawFd = match listen_fds {
+            Some(count) => count
+                .trim()
+                .parse()
+                .map_err(|_| UniModelError::config(format!("Invalid LISTEN_FDS '{}'", count)))?,
+            None => return Ok(Self::default()),
+        };
+        let names: Vec<&str> = names.map_or_else(Vec::new, |names| names.split(':').collect());
+
+        Ok(Self {
+            fds: (0..count)
+                .map(|i| {
+                    let name = names.get(i as usize).filter(|n| !n.is_empty()).map(|n| n.to_string());
+                    (SD_LISTEN_FDS_START + i, name)
+                })
+                .collect(),
+        })
+    }
+
+    /// 读取本进程的 `LISTEN_*` 环境变量并清除，插件子进程不会继承
+    pub fn from_env() -> Result<Self> {
+        let var = |name: &str| std::env::var(name).ok();
+        let fds = Self::parse(
+            std::process::id(),
+            var("LISTEN_PID").as_deref(),
+            var("LISTEN_FDS").as_deref(),
+            var("LISTEN_FDNAMES").as_deref(),
+        )?;
+        for name in ["LISTEN_PID", "LISTEN_FDS", "LISTEN_FDNAMES"] {
+            std::env::remove_var(name);
+        }
+        Ok(fds)
+    }
+
+    /// 是否传入了套接字
+    pub fn is_empty(&self) -> bool {
+        self.fds.is_empty()
+    }
+
+    /// 按名称取套接字；所有套接字都未命名时按位置取第 `index` 个
+    pub fn get(&self, name: &str, index: usize) -> Option<RawFd> {
+        if self.fds.iter().any(|(_, n)| n.is_some()) {
+            self.fds.iter().find(|(_, n)| n.as_deref() == Some(name)).map(|(fd, _)| *fd)
+        } else {
+            self.fds.get(index).map(|(fd, _)| *fd)
+        }
+    }
+}
+
+/// 绑定监听地址，`reuse_port` 时允许其他进程同时监听同一端口
+pub fn bind(addr: SocketAddr, reuse_port: bool) -> Result<TcpListener> {
+    let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+    socket.set_reuse_address(true)?;
+    if reuse_port {
+        socket.set_reuse_port(true)?;
+    }
+    socket
+        .bind(&addr.into())
+        .map_err(|e| UniModelError::Network(format!("Cannot bind {}: {}", addr, e)))?;
+    socket.listen(LISTEN_BACKLOG)?;
+    socket.set_nonblocking(true)?;
+    Ok(socket.into())
+}
+
+/// 取得服务的监听套接字：systemd传入了名为 `name` 的套接字时使用它，否则绑定 `addr`
+pub fn listen(name: &str, index: usize, addr: SocketAddr, reuse_port: bool, inherited: &ListenFds) -> Result<TcpListener> {
+    match inherited.get(name, index) {
+        Some(fd) => {
+            // SAFETY: fd由systemd传入且只在这里取得一次所有权
+            let listener = unsafe { TcpListener::from_raw_fd(fd) };
+            listener.set_nonblocking(true)?;
+            info!("Using socket-activated {} listener on {}", name, listener.local_addr()?);
+            Ok(listener)
+        }
+        None => bind(addr, reuse_port),
+    }
+}
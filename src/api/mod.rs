@@ -0,0 +1,7 @@
+//! API层
+
+pub mod auth;
+pub mod grpc;
+pub mod listener;
+pub mod rest;
+pub mod validation;
@@ -0,0 +1,216 @@
+//! 请求与响应的内容协商
+
+use axum::body::Bytes;
+use axum::http::{header, HeaderMap, HeaderValue};
+use axum::response::{IntoResponse, Json, Response};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::common::error::*;
+use crate::common::types::{InputData, PredictionParameters};
+
+/// MessagePack
+pub const MSGPACK_CONTENT_TYPE: &str = "application/msgpack";
+
+/// CBOR（RFC 8949）
+pub const CBOR_CONTENT_TYPE: &str = "application/cbor";
+
+/// 文件上传表单
+pub const MULTIPART_CONTENT_TYPE: &str = "multipart/form-data";
+
+/// 表单中承载推理参数（JSON）的字段名
+pub const PARAMETERS_FIELD: &str = "parameters";
+
+/// Arrow IPC 流格式
+pub const ARROW_STREAM_CONTENT_TYPE: &str = "application/vnd.apache.arrow.stream";
+
+/// Arrow IPC 文件格式（Feather v2）
+pub const ARROW_FILE_CONTENT_TYPE: &str = "application/vnd.apache.arrow.file";
+
+/// NumPy 单个数组（`.npy`）
+pub const NPY_CONTENT_TYPE: &str = "application/x-npy";
+
+/// NumPy 多数组压缩包（`.npz`）
+pub const NPZ_CONTENT_TYPE: &str = "application/x-npz";
+
+/// 请求体不是JSON信封时，推理参数以JSON放在该请求头中
+pub const PARAMETERS_HEADER: &str = "x-parameters";
+
+/// 推理接口的线上格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WireFormat {
+    Json,
+    MsgPack,
+    Cbor,
+    Arrow,
+    Npy,
+    Npz,
+    Multipart,
+}
+
+impl WireFormat {
+    fn from_media_type(media_type: &str) -> Option<Self> {
+        match media_type {
+            "application/json" => Some(WireFormat::Json),
+            MSGPACK_CONTENT_TYPE | "application/x-msgpack" => Some(WireFormat::MsgPack),
+            CBOR_CONTENT_TYPE => Some(WireFormat::Cbor),
+            ARROW_STREAM_CONTENT_TYPE | ARROW_FILE_CONTENT_TYPE => Some(WireFormat::Arrow),
+            NPY_CONTENT_TYPE => Some(WireFormat::Npy),
+            NPZ_CONTENT_TYPE => Some(WireFormat::Npz),
+            MULTIPART_CONTENT_TYPE => Some(WireFormat::Multipart),
+            _ => None,
+        }
+    }
+
+    /// 按 `Content-Type` 确定请求体格式，未指定时为JSON
+    pub fn from_content_type(headers: &HeaderMap) -> Result<Self> {
+        let content_type = match headers.get(header::CONTENT_TYPE) {
+            Some(value) => value.to_str()
+                .map_err(|_| UniModelError::validation("Invalid Content-Type header"))?,
+            None => return Ok(WireFormat::Json),
+        };
+
+        let media_type = media_type(content_type);
+        Self::from_media_type(&media_type)
+            .ok_or_else(|| UniModelError::validation(format!("Unsupported content type '{}'", media_type)))
+    }
+
+    /// 是否可用于响应（NumPy和表单只用于上传）
+    pub fn is_response_format(&self) -> bool {
+        matches!(self, WireFormat::Json | WireFormat::MsgPack | WireFormat::Cbor | WireFormat::Arrow)
+    }
+
+    /// 按 `Accept` 选择响应格式，取第一个支持的类型，否则为JSON
+    pub fn from_accept(headers: &HeaderMap) -> Self {
+        headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|accept| {
+                accept.split(',')
+                    .filter_map(|item| Self::from_media_type(&media_type(item)))
+                    .find(WireFormat::is_response_format)
+            })
+            .unwrap_or(WireFormat::Json)
+    }
+}
+
+/// 反序列化完整的请求信封（JSON、MessagePack、CBOR）
+pub fn deserialize_body<T: DeserializeOwned>(format: WireFormat, body: &[u8]) -> Result<T> {
+    match format {
+        WireFormat::Json => Ok(serde_json::from_slice(body)?),
+        WireFormat::MsgPack => rmp_serde::from_slice(body)
+            .map_err(|e| UniModelError::validation(format!("Invalid MessagePack body: {}", e))),
+        WireFormat::Cbor => ciborium::de::from_reader(body)
+            .map_err(|e| UniModelError::validation(format!("Invalid CBOR body: {}", e))),
+        other => Err(UniModelError::validation(format!("{:?} body cannot carry a request envelope", other))),
+    }
+}
+
+/// 按协商的格式序列化响应信封，无法承载信封的格式回退为JSON
+pub fn serialize_body<T: Serialize>(format: WireFormat, value: &T) -> Result<Response> {
+    match format {
+        WireFormat::MsgPack => {
+            let body = rmp_serde::to_vec_named(value)
+                .map_err(|e| UniModelError::internal(format!("Failed to encode MessagePack: {}", e)))?;
+            Ok(with_content_type(body.into_response(), MSGPACK_CONTENT_TYPE))
+        }
+        WireFormat::Cbor => {
+            let mut body = Vec::new();
+            ciborium::ser::into_writer(value, &mut body)
+                .map_err(|e| UniModelError::internal(format!("Failed to encode CBOR: {}", e)))?;
+            Ok(with_content_type(body.into_response(), CBOR_CONTENT_TYPE))
+        }
+        _ => Ok(Json(value).into_response()),
+    }
+}
+
+/// 设置响应的 `Content-Type`
+pub fn with_content_type(mut response: Response, content_type: &'static str) -> Response {
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response
+}
+
+/// 解析后的上传表单
+#[derive(Debug)]
+pub struct MultipartPayload {
+    /// `parameters` 字段中的推理参数
+    pub parameters: Option<PredictionParameters>,
+    /// 其余字段按出现顺序：文件为二进制输入，`application/json` 为JSON输入，其他为文本输入
+    pub parts: Vec<(String, InputData)>,
+}
+
+impl MultipartPayload {
+    /// 合并为单个输入：只有一个字段时直接使用，否则按字段名组成多模态输入
+    pub fn into_input(self) -> Result<InputData> {
+        let mut parts = self.parts;
+        if parts.len() == 1 {
+            return Ok(parts.remove(0).1);
+        }
+
+        let mut map = std::collections::HashMap::with_capacity(parts.len());
+        for (name, input) in parts {
+            if map.insert(name.clone(), input).is_some() {
+                return Err(UniModelError::validation(format!("Duplicate form field '{}'", name)));
+            }
+        }
+        Ok(InputData::Multimodal(map))
+    }
+}
+
+/// 解析 `multipart/form-data` 请求体
+pub async fn read_multipart(headers: &HeaderMap, body: Bytes) -> Result<MultipartPayload> {
+    let invalid = |e: multer::Error| UniModelError::validation(format!("Invalid multipart body: {}", e));
+
+    let content_type = headers.get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    let boundary = multer::parse_boundary(content_type).map_err(invalid)?;
+    let stream = futures::stream::once(async move { Ok::<_, std::convert::Infallible>(body) });
+    let mut multipart = multer::Multipart::new(stream, boundary);
+
+    let mut payload = MultipartPayload {
+        parameters: None,
+        parts: Vec::new(),
+    };
+    while let Some(field) = multipart.next_field().await.map_err(invalid)? {
+        let name = field.name().unwrap_or_default().to_string();
+        let is_file = field.file_name().is_some();
+        let media_type = field.content_type().map(|mime| mime.essence_str().to_string());
+        let data = field.bytes().await.map_err(invalid)?;
+
+        if name == PARAMETERS_FIELD {
+            payload.parameters = Some(serde_json::from_slice(&data)
+                .map_err(|e| UniModelError::validation(format!("Invalid parameters field: {}", e)))?);
+            continue;
+        }
+
+        let input = match media_type.as_deref() {
+            Some("application/json") => InputData::Json(serde_json::from_slice(&data)?),
+            None | Some("text/plain") if !is_file => InputData::Text(
+                String::from_utf8(data.to_vec())
+                    .map_err(|_| UniModelError::validation(format!("Form field '{}' is not valid UTF-8", name)))?,
+            ),
+            _ => InputData::Binary(data),
+        };
+        payload.parts.push((name, input));
+    }
+
+    if payload.parts.is_empty() {
+        return Err(UniModelError::validation("Multipart body contains no input fields"));
+    }
+    Ok(payload)
+}
+
+/// 去掉参数（`;charset=...`、`;q=...`）后的小写媒体类型
+fn media_type(value: &str) -> String {
+    value.split(';').next().unwrap_or_default().trim().to_ascii_lowercase()
+}
+
+/// 读取 `X-Parameters` 头中的推理参数，未提供时使用默认值
+pub fn header_parameters(headers: &HeaderMap) -> Result<PredictionParameters> {
+    match headers.get(PARAMETERS_HEADER) {
+        Some(value) => serde_json::from_slice(value.as_bytes())
+            .map_err(|e| UniModelError::validation(format!("Invalid X-Parameters header: {}", e))),
+        None => Ok(PredictionParameters::default()),
+    }
+}
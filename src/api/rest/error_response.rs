@@ -0,0 +1,50 @@
+//! 错误响应转换
+
+use axum::{
+    http::{header, HeaderValue, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::common::error::*;
+
+/// 当前队列深度响应头
+pub const QUEUE_DEPTH_HEADER: &str = "x-queue-depth";
+
+/// 预计等待时间响应头
+pub const ESTIMATED_WAIT_HEADER: &str = "x-estimated-wait-ms";
+
+impl IntoResponse for UniModelError {
+    fn into_response(self) -> Response {
+        let status = StatusCode::from_u16(self.status_code())
+            .unwrap_or(StatusCode::INTERNAL_SERVER_ERROR);
+
+        let mut body = serde_json::json!({
+            "error": self.error_code(),
+            "message": self.to_string()
+        });
+
+        let hint = match self.overload_hint() {
+            Some(hint) => hint.clone(),
+            None => return (status, Json(body)).into_response(),
+        };
+
+        body["retry_after_ms"] = hint.retry_after_ms.into();
+        if let Some(queue_depth) = hint.queue_depth {
+            body["queue_depth"] = queue_depth.into();
+        }
+        if let Some(estimated_wait_ms) = hint.estimated_wait_ms {
+            body["estimated_wait_ms"] = estimated_wait_ms.into();
+        }
+
+        let mut response = (status, Json(body)).into_response();
+        let headers = response.headers_mut();
+        headers.insert(header::RETRY_AFTER, HeaderValue::from(hint.retry_after_secs()));
+        if let Some(queue_depth) = hint.queue_depth {
+            headers.insert(QUEUE_DEPTH_HEADER, HeaderValue::from(queue_depth));
+        }
+        if let Some(estimated_wait_ms) = hint.estimated_wait_ms {
+            headers.insert(ESTIMATED_WAIT_HEADER, HeaderValue::from(estimated_wait_ms));
+        }
+        response
+    }
+}
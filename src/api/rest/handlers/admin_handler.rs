@@ -0,0 +1,281 @@
+//! 管理API处理器
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::{delete, get, post},
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::AppState;
+use crate::common::error::*;
+use crate::domain::service::batch_processor::PredictionResponse;
+use crate::infrastructure::security::{ApiKeyRecord, NewApiKey, SCOPE_ADMIN};
+use crate::infrastructure::storage::DeadLetterRecord;
+
+/// 死信列表默认返回条数
+const DEFAULT_DEAD_LETTER_LIMIT: usize = 100;
+
+/// 创建API密钥请求
+#[derive(Debug, Deserialize)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    pub tenant: Option<String>,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// 创建API密钥响应（明文密钥只返回这一次）
+#[derive(Debug, Serialize)]
+pub struct CreateApiKeyResponse {
+    pub id: String,
+    pub key: String,
+    pub name: String,
+    pub tenant: Option<String>,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// API密钥摘要（不含哈希）
+#[derive(Debug, Serialize)]
+pub struct ApiKeySummary {
+    pub id: String,
+    pub name: String,
+    pub tenant: Option<String>,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub rate_limit_per_minute: Option<u32>,
+    pub created_at: DateTime<Utc>,
+    pub revoked_at: Option<DateTime<Utc>>,
+    pub active: bool,
+}
+
+impl From<ApiKeyRecord> for ApiKeySummary {
+    fn from(record: ApiKeyRecord) -> Self {
+        Self {
+            active: record.is_active(),
+            id: record.id,
+            name: record.name,
+            tenant: record.tenant,
+            scopes: record.scopes,
+            expires_at: record.expires_at,
+            rate_limit_per_minute: record.rate_limit_per_minute,
+            created_at: record.created_at,
+            revoked_at: record.revoked_at,
+        }
+    }
+}
+
+/// 死信查询参数
+#[derive(Debug, Deserialize)]
+pub struct DeadLetterQuery {
+    pub model_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 创建管理路由
+pub fn create_admin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/admin/api-keys", post(create_api_key).get(list_api_keys))
+        .route("/admin/api-keys/:key_id", delete(revoke_api_key))
+        .route("/admin/dead-letters", get(list_dead_letters))
+        .route("/admin/dead-letters/:id", get(get_dead_letter).delete(delete_dead_letter))
+        .route("/admin/dead-letters/:id/redrive", post(redrive_dead_letter))
+}
+
+/// 将错误转换为JSON错误响应
+fn error_response(e: &UniModelError) -> (StatusCode, Json<serde_json::Value>) {
+    (
+        StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+        Json(serde_json::json!({
+            "error": e.error_code(),
+            "message": e.to_string()
+        })),
+    )
+}
+
+/// 创建API密钥
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> Result<(StatusCode, Json<CreateApiKeyResponse>), (StatusCode, Json<serde_json::Value>)> {
+    info!("Creating API key: {}", request.name);
+
+    let result = match auth.require_scope(SCOPE_ADMIN) {
+        Ok(()) => {
+            state.api_key_store.create(NewApiKey {
+                name: request.name,
+                tenant: request.tenant,
+                scopes: request.scopes,
+                expires_at: request.expires_at,
+                rate_limit_per_minute: request.rate_limit_per_minute,
+            }).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok((record, key)) => Ok((
+            StatusCode::CREATED,
+            Json(CreateApiKeyResponse {
+                id: record.id,
+                key,
+                name: record.name,
+                tenant: record.tenant,
+                scopes: record.scopes,
+                expires_at: record.expires_at,
+                rate_limit_per_minute: record.rate_limit_per_minute,
+                created_at: record.created_at,
+            }),
+        )),
+        Err(e) => {
+            error!("Failed to create API key: {}", e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
+/// 获取API密钥列表
+pub async fn list_api_keys(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<Vec<ApiKeySummary>>, (StatusCode, Json<serde_json::Value>)> {
+    if let Err(e) = auth.require_scope(SCOPE_ADMIN) {
+        return Err((
+            StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            Json(serde_json::json!({
+                "error": e.error_code(),
+                "message": e.to_string()
+            })),
+        ));
+    }
+
+    let keys = state.api_key_store.list().await
+        .into_iter()
+        .map(ApiKeySummary::from)
+        .collect();
+    Ok(Json(keys))
+}
+
+/// 吊销API密钥
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(key_id): Path<String>,
+) -> Result<Json<ApiKeySummary>, (StatusCode, Json<serde_json::Value>)> {
+    info!("Revoking API key: {}", key_id);
+
+    let result = match auth.require_scope(SCOPE_ADMIN) {
+        Ok(()) => state.api_key_store.revoke(&key_id).await,
+        Err(e) => Err(e),
+    };
+
+    match result {
+        Ok(record) => Ok(Json(ApiKeySummary::from(record))),
+        Err(e) => {
+            error!("Failed to revoke API key {}: {}", key_id, e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
+/// 获取死信列表（租户管理员只能看到本租户的记录）
+pub async fn list_dead_letters(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<DeadLetterQuery>,
+) -> Result<Json<Vec<DeadLetterRecord>>, (StatusCode, Json<serde_json::Value>)> {
+    auth.require_scope(SCOPE_ADMIN).map_err(|e| error_response(&e))?;
+
+    let records = state.dead_letter_store.list(
+        query.model_id.as_deref(),
+        auth.tenant.as_deref(),
+        query.limit.unwrap_or(DEFAULT_DEAD_LETTER_LIMIT),
+    ).await;
+    Ok(Json(records))
+}
+
+/// 获取单条死信记录
+pub async fn get_dead_letter(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<DeadLetterRecord>, (StatusCode, Json<serde_json::Value>)> {
+    auth.require_scope(SCOPE_ADMIN).map_err(|e| error_response(&e))?;
+
+    find_dead_letter(&state, &auth, &id).await
+        .map(Json)
+        .map_err(|e| error_response(&e))
+}
+
+/// 删除死信记录
+pub async fn delete_dead_letter(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<StatusCode, (StatusCode, Json<serde_json::Value>)> {
+    auth.require_scope(SCOPE_ADMIN).map_err(|e| error_response(&e))?;
+    find_dead_letter(&state, &auth, &id).await.map_err(|e| error_response(&e))?;
+
+    state.dead_letter_store.remove(&id).await;
+    info!("Dead letter deleted: {}", id);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// 重新投递死信请求，成功后删除该记录
+pub async fn redrive_dead_letter(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(id): Path<String>,
+) -> Result<Json<PredictionResponse>, (StatusCode, Json<serde_json::Value>)> {
+    auth.require_scope(SCOPE_ADMIN).map_err(|e| error_response(&e))?;
+    let record = find_dead_letter(&state, &auth, &id).await.map_err(|e| error_response(&e))?;
+
+    info!("Re-driving dead letter {} (request {})", id, record.request_id);
+    let result = state.prediction_service.predict(
+        record.model_id.clone(),
+        record.input,
+        record.parameters,
+        record.tenant.as_deref(),
+    ).await;
+
+    match result {
+        Ok(response) => {
+            state.dead_letter_store.remove(&id).await;
+            Ok(Json(response))
+        }
+        Err(e) => {
+            error!("Failed to re-drive dead letter {}: {}", id, e);
+            Err(error_response(&e))
+        }
+    }
+}
+
+/// 查找调用方可见的死信记录
+async fn find_dead_letter(state: &AppState, auth: &AuthContext, id: &str) -> Result<DeadLetterRecord> {
+    state.dead_letter_store.get(id).await
+        .filter(|r| auth.tenant.is_none() || r.tenant == auth.tenant)
+        .ok_or_else(|| UniModelError::validation(format!("Dead letter not found: {}", id)))
+}
@@ -0,0 +1,82 @@
+//! 调试API处理器
+//!
+//! 只在 `monitoring.debug_endpoints` 开启时挂载，且需要管理权限。
+
+use std::time::Duration;
+
+use axum::{
+    extract::Query,
+    http::header,
+    response::{IntoResponse, Json, Response},
+    routing::get,
+    Extension, Router,
+};
+use serde::Deserialize;
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::AppState;
+use crate::common::error::*;
+use crate::infrastructure::monitoring::allocator::{allocator_stats, AllocatorStats};
+use crate::infrastructure::monitoring::profiler::{capture_cpu_profile, ProfileFormat};
+use crate::infrastructure::security::SCOPE_ADMIN;
+
+/// 默认采样时长（秒）
+const DEFAULT_PROFILE_SECONDS: u64 = 30;
+
+/// CPU性能分析查询参数
+#[derive(Debug, Deserialize)]
+pub struct ProfileQuery {
+    #[serde(default = "default_profile_seconds")]
+    pub seconds: u64,
+    /// `pprof`（默认）或 `flamegraph`
+    #[serde(default)]
+    pub format: Option<String>,
+}
+
+fn default_profile_seconds() -> u64 {
+    DEFAULT_PROFILE_SECONDS
+}
+
+/// 创建调试路由
+pub fn create_debug_routes() -> Router<AppState> {
+    Router::new()
+        .route("/debug/pprof/profile", get(cpu_profile))
+        .route("/debug/memory", get(memory_stats))
+}
+
+/// 获取内存分配器的全局和各arena统计（需要管理权限）
+pub async fn memory_stats(
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<AllocatorStats>, Response> {
+    auth.require_scope(SCOPE_ADMIN).map_err(IntoResponse::into_response)?;
+    allocator_stats().map(Json).map_err(IntoResponse::into_response)
+}
+
+/// 采样CPU调用栈，返回pprof或火焰图SVG（需要管理权限）
+pub async fn cpu_profile(
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<ProfileQuery>,
+) -> Result<Response, Response> {
+    auth.require_scope(SCOPE_ADMIN).map_err(IntoResponse::into_response)?;
+
+    let format = match query.format.as_deref() {
+        None | Some("pprof") => ProfileFormat::Pprof,
+        Some("flamegraph") | Some("svg") => ProfileFormat::Flamegraph,
+        Some(other) => {
+            return Err(UniModelError::validation(format!("Unknown profile format '{}'", other)).into_response());
+        }
+    };
+    let body = capture_cpu_profile(Duration::from_secs(query.seconds), format)
+        .await
+        .map_err(IntoResponse::into_response)?;
+
+    let filename = match format {
+        ProfileFormat::Pprof => "attachment; filename=\"cpu.pb\"",
+        ProfileFormat::Flamegraph => "inline; filename=\"cpu.svg\"",
+    };
+    Ok((
+        [(header::CONTENT_TYPE, format.content_type()), (header::CONTENT_DISPOSITION, filename)],
+        body,
+    )
+        .into_response())
+}
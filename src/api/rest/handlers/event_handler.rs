@@ -0,0 +1,54 @@
+//! 模型事件API处理器
+
+use axum::{
+    extract::{Query, State},
+    response::Json,
+    routing::get,
+    Extension, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::AppState;
+use crate::domain::model::ModelEvent;
+
+/// 事件列表默认返回条数
+const DEFAULT_EVENT_LIMIT: usize = 100;
+
+/// 事件查询参数
+#[derive(Debug, Deserialize)]
+pub struct EventQuery {
+    pub model_id: Option<String>,
+    pub limit: Option<usize>,
+}
+
+/// 事件列表响应
+#[derive(Debug, Serialize)]
+pub struct ListEventsResponse {
+    pub events: Vec<ModelEvent>,
+    pub total: usize,
+}
+
+/// 创建事件路由
+pub fn create_event_routes() -> Router<AppState> {
+    Router::new()
+        .route("/events", get(list_events))
+}
+
+/// 获取模型事件（注册、加载、逐出等），租户只能看到自己模型的事件
+pub async fn list_events(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<EventQuery>,
+) -> Json<ListEventsResponse> {
+    let events = state.model_service.list_events(
+        query.model_id.as_deref(),
+        auth.tenant.as_deref(),
+        query.limit.unwrap_or(DEFAULT_EVENT_LIMIT),
+    );
+
+    Json(ListEventsResponse {
+        total: events.len(),
+        events,
+    })
+}
@@ -0,0 +1,37 @@
+//! 健康检查处理器
+//!
+//! 探针端点不经过认证、限流和配额中间件。
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Router,
+};
+
+use crate::api::rest::handlers::AppState;
+use crate::domain::service::Readiness;
+
+/// 创建健康检查路由
+pub fn create_health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/healthz", get(liveness))
+        .route("/readyz", get(readiness))
+}
+
+/// 存活检查
+pub async fn liveness() -> Json<serde_json::Value> {
+    Json(serde_json::json!({ "status": "ok" }))
+}
+
+/// 就绪检查，预加载模型全部加载并预热完成前返回503
+pub async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let readiness = state.model_service.readiness().await;
+    let status = if readiness.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+    (status, Json(readiness))
+}
@@ -0,0 +1,203 @@
+//! 图像生成API处理器（请求与响应兼容OpenAI Images API）
+
+use axum::{
+    extract::{Path, State},
+    http::{header, HeaderValue},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Extension, Router,
+};
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::{record_usage, AppState, REQUEST_ID_HEADER};
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::infrastructure::postprocessing::output_images;
+
+/// 单次请求最多生成的图像数
+pub const MAX_IMAGES_PER_REQUEST: u32 = 10;
+
+/// 最大采样步数
+pub const MAX_GENERATION_STEPS: u32 = 500;
+
+/// 未指定尺寸时的默认值
+const DEFAULT_IMAGE_SIZE: (u32, u32) = (512, 512);
+
+/// 图像返回方式
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ImageResponseFormat {
+    /// JSON中的base64编码图像
+    #[default]
+    B64Json,
+    /// 直接返回图像二进制（仅限 `n = 1`）
+    Binary,
+}
+
+/// 图像生成请求
+#[derive(Debug, Deserialize)]
+pub struct ImageGenerationRequest {
+    pub prompt: String,
+    #[serde(default)]
+    pub negative_prompt: Option<String>,
+    /// 生成图像数
+    #[serde(default = "default_image_count")]
+    pub n: u32,
+    /// `宽x高`，如 `1024x1024`
+    #[serde(default)]
+    pub size: Option<String>,
+    /// 采样步数
+    #[serde(default)]
+    pub steps: Option<u32>,
+    #[serde(default)]
+    pub guidance_scale: Option<f32>,
+    /// 随机种子，相同种子和参数生成相同图像
+    #[serde(default)]
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub response_format: ImageResponseFormat,
+}
+
+fn default_image_count() -> u32 {
+    1
+}
+
+/// 图像生成响应
+#[derive(Debug, Serialize)]
+pub struct ImageGenerationResponse {
+    /// Unix时间戳（秒）
+    pub created: i64,
+    pub data: Vec<GeneratedImage>,
+}
+
+/// 单张生成图像
+#[derive(Debug, Serialize)]
+pub struct GeneratedImage {
+    pub b64_json: String,
+}
+
+impl ImageGenerationRequest {
+    /// 校验请求并转换为传给扩散模型后端的推理参数
+    pub fn to_parameters(&self) -> Result<PredictionParameters> {
+        if self.prompt.trim().is_empty() {
+            return Err(UniModelError::validation("prompt cannot be empty"));
+        }
+        if self.n == 0 || self.n > MAX_IMAGES_PER_REQUEST {
+            return Err(UniModelError::validation(format!("n must be between 1 and {}", MAX_IMAGES_PER_REQUEST)));
+        }
+        if self.response_format == ImageResponseFormat::Binary && self.n != 1 {
+            return Err(UniModelError::validation("Binary responses can only carry a single image"));
+        }
+        if let Some(steps) = self.steps {
+            if steps == 0 || steps > MAX_GENERATION_STEPS {
+                return Err(UniModelError::validation(format!("steps must be between 1 and {}", MAX_GENERATION_STEPS)));
+            }
+        }
+        let (width, height) = match &self.size {
+            Some(size) => parse_image_size(size)?,
+            None => DEFAULT_IMAGE_SIZE,
+        };
+
+        let mut parameters = PredictionParameters {
+            seed: self.seed,
+            ..Default::default()
+        };
+        let custom = &mut parameters.custom;
+        custom.insert("width".to_string(), width.into());
+        custom.insert("height".to_string(), height.into());
+        custom.insert("num_images".to_string(), self.n.into());
+        if let Some(steps) = self.steps {
+            custom.insert("num_inference_steps".to_string(), steps.into());
+        }
+        if let Some(guidance_scale) = self.guidance_scale {
+            custom.insert("guidance_scale".to_string(), guidance_scale.into());
+        }
+        if let Some(seed) = self.seed {
+            custom.insert("seed".to_string(), seed.into());
+        }
+        if let Some(negative_prompt) = &self.negative_prompt {
+            custom.insert("negative_prompt".to_string(), negative_prompt.clone().into());
+        }
+        Ok(parameters)
+    }
+}
+
+/// 解析 `宽x高`，边长需为8的倍数且在64到4096之间
+pub fn parse_image_size(size: &str) -> Result<(u32, u32)> {
+    let invalid = || UniModelError::validation(format!("Invalid image size '{}', expected WIDTHxHEIGHT", size));
+    let (width, height) = size.split_once(['x', 'X']).ok_or_else(invalid)?;
+    let width: u32 = width.trim().parse().map_err(|_| invalid())?;
+    let height: u32 = height.trim().parse().map_err(|_| invalid())?;
+
+    for side in [width, height] {
+        if !(64..=4096).contains(&side) || side % 8 != 0 {
+            return Err(UniModelError::validation(format!(
+                "Image sides must be multiples of 8 between 64 and 4096, got {}x{}",
+                width, height
+            )));
+        }
+    }
+    Ok((width, height))
+}
+
+/// 创建图像生成路由
+pub fn create_image_routes() -> Router<AppState> {
+    Router::new().route("/models/:model_id/images/generations", post(generate_images))
+}
+
+/// 图像生成
+pub async fn generate_images(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<ImageGenerationRequest>,
+) -> Result<Response, Response> {
+    info!("Processing image generation request for model: {}", model_id);
+
+    let parameters = request.to_parameters().map_err(IntoResponse::into_response)?;
+    let response = state.prediction_service
+        .predict(model_id.clone(), InputData::Text(request.prompt), parameters, auth.tenant.as_deref())
+        .await
+        .map_err(|e| {
+            error!("Image generation failed for model {}: {}", model_id, e);
+            e.into_response()
+        })?;
+    record_usage(&state, &auth, &response).await;
+
+    let request_id = response.request_id.clone();
+    let images = output_images(response.output).map_err(IntoResponse::into_response)?;
+    match request.response_format {
+        ImageResponseFormat::B64Json => Ok(Json(ImageGenerationResponse {
+            created: chrono::Utc::now().timestamp(),
+            data: images.iter()
+                .map(|image| GeneratedImage {
+                    b64_json: base64::engine::general_purpose::STANDARD.encode(image),
+                })
+                .collect(),
+        }).into_response()),
+        ImageResponseFormat::Binary => binary_image_response(&request_id, images).map_err(IntoResponse::into_response),
+    }
+}
+
+fn binary_image_response(request_id: &str, mut images: Vec<Vec<u8>>) -> Result<Response> {
+    if images.len() != 1 {
+        return Err(UniModelError::model(format!("Expected one image, model returned {}", images.len())));
+    }
+    let data = images.remove(0);
+    let content_type = match image::guess_format(&data) {
+        Ok(image::ImageFormat::Png) => "image/png",
+        Ok(image::ImageFormat::Jpeg) => "image/jpeg",
+        Ok(image::ImageFormat::WebP) => "image/webp",
+        _ => "application/octet-stream",
+    };
+    let request_id = HeaderValue::from_str(request_id)
+        .map_err(|e| UniModelError::internal(format!("Invalid request id: {}", e)))?;
+
+    let mut response = data.into_response();
+    response.headers_mut().insert(header::CONTENT_TYPE, HeaderValue::from_static(content_type));
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    Ok(response)
+}
@@ -1,11 +1,26 @@
 //! REST API处理器模块
 
+pub mod admin_handler;
+pub mod debug_handler;
+pub mod event_handler;
+pub mod image_handler;
 pub mod model_handler;
+pub mod ocr_handler;
+pub mod plugin_handler;
 pub mod predict_handler;
 pub mod health_handler;
 pub mod metrics_handler;
+pub mod tokenizer_handler;
+pub mod usage_handler;
 
+pub use admin_handler::*;
+pub use debug_handler::*;
+pub use event_handler::*;
+pub use image_handler::*;
 pub use model_handler::*;
+pub use ocr_handler::*;
+pub use plugin_handler::*;
 pub use predict_handler::*;
 pub use health_handler::*;
-pub use metrics_handler::*;
\ No newline at end of file
+pub use tokenizer_handler::*;
+pub use usage_handler::*;
\ No newline at end of file
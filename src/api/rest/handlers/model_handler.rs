@@ -4,22 +4,32 @@ use axum::{
     extract::{Path, State},
     http::StatusCode,
     response::Json,
-    routing::{delete, get, post},
-    Router,
+    routing::{delete, get, patch, post},
+    Extension, Router,
 };
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{error, info};
 
-use crate::application::services::ModelService;
+use crate::api::auth::AuthContext;
+use crate::application::services::{ModelService, OcrService, PredictionService};
 use crate::common::error::*;
 use crate::common::types::*;
 use crate::domain::model::*;
+use crate::domain::service::conversion::{ConversionJob, ConversionRequest};
+use crate::infrastructure::security::{ApiKeyStore, QuotaManager};
+use crate::infrastructure::storage::{DeadLetterStore, UsageStore};
 
 /// 应用状态
 #[derive(Clone)]
 pub struct AppState {
     pub model_service: Arc<ModelService>,
+    pub prediction_service: Arc<PredictionService>,
+    pub ocr_service: Arc<OcrService>,
+    pub api_key_store: Arc<ApiKeyStore>,
+    pub quota_manager: Arc<QuotaManager>,
+    pub usage_store: Arc<UsageStore>,
+    pub dead_letter_store: Arc<DeadLetterStore>,
 }
 
 /// 模型注册请求
@@ -27,9 +37,71 @@ pub struct AppState {
 pub struct RegisterModelRequest {
     pub name: String,
     pub model_type: ModelType,
+    /// 推理后端，为空或为 `auto` 时按插件声明的能力自动选择
+    #[serde(default)]
     pub backend: String,
     pub model_path: String,
+    /// 运行设备，默认为 `engine.gpu.accelerator`；Apple Silicon上使用Metal，为 `Auto` 时按 CUDA → ROCm → Metal → CPU 探测
+    #[serde(default)]
+    pub device_type: Option<DeviceType>,
+    /// GPU列表，默认为GPU 0；张量或流水线并行时模型切分到全部所列GPU上，多副本时每张卡各一个实例
+    #[serde(default)]
+    pub device_ids: Vec<u32>,
+    /// 多GPU并行方式
+    #[serde(default)]
+    pub parallelism: Parallelism,
+    /// 流水线并行的微批次数
+    pub micro_batches: Option<u32>,
+    /// 以FP16/BF16混合精度执行，后端不支持时按FP32运行
+    #[serde(default)]
+    pub mixed_precision: bool,
     pub config: Option<serde_json::Value>,
+    /// 同时执行的批次数上限
+    pub max_concurrent_batches: Option<u32>,
+    /// 目标租户，仅全局调用方可指定；租户密钥总是注册到自身租户
+    pub tenant: Option<String>,
+    /// 固定驻留，不会被自动逐出
+    #[serde(default)]
+    pub pinned: bool,
+    /// 加载后的预热配置
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+    /// 输入输出签名，未提供时尝试从模型文件解析
+    pub signature: Option<ModelSignature>,
+    /// JSON输入的Schema，推理前校验
+    pub input_schema: Option<InputSchema>,
+    /// 服务端输入预处理
+    pub preprocessing: Option<PreprocessingConfig>,
+    /// 服务端输出后处理
+    pub postprocessing: Option<PostprocessingConfig>,
+    /// 类别标签文件路径
+    pub labels_path: Option<String>,
+    /// 投机解码配置
+    #[serde(default)]
+    pub speculative: Option<SpeculativeConfig>,
+    /// 预热时捕获CUDA graph，适用于输入形状固定的模型
+    #[serde(default)]
+    pub cuda_graphs: bool,
+    /// CUDA流数，相邻批次的拷贝与计算重叠执行
+    #[serde(default = "default_cuda_streams")]
+    pub cuda_streams: u32,
+}
+
+fn default_cuda_streams() -> u32 {
+    1
+}
+
+/// 模型更新请求（只修改提供的字段）
+#[derive(Debug, Deserialize)]
+pub struct UpdateModelRequest {
+    pub pinned: Option<bool>,
+}
+
+/// LoRA适配器加载请求
+#[derive(Debug, Deserialize)]
+pub struct LoadAdapterRequest {
+    pub name: String,
+    pub path: String,
 }
 
 /// 模型注册响应
@@ -54,25 +126,36 @@ pub fn create_model_routes() -> Router<AppState> {
         .route("/models", get(list_models))
         .route("/models/:model_id", get(get_model))
         .route("/models/:model_id", delete(unregister_model))
+        .route("/models/:model_id", patch(update_model))
+        .route("/models/:model_id/signature", get(get_model_signature))
+        .route("/models/:model_id/adapters", post(load_adapter))
+        .route("/models/:model_id/adapters/:adapter", delete(unload_adapter))
+        .route("/models/:model_id/convert", post(convert_model))
+        .route("/conversions/:job_id", get(get_conversion))
 }
 
 /// 注册模型
 pub async fn register_model(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Json(request): Json<RegisterModelRequest>,
 ) -> Result<Json<RegisterModelResponse>, (StatusCode, Json<serde_json::Value>)> {
     info!("Registering model: {}", request.name);
 
+    let tenant = auth.tenant.clone().or(request.tenant);
+
     let model_config = ModelConfig {
         model_path: request.model_path,
         config_path: None,
         tokenizer_path: None,
         backend: request.backend,
         device: DeviceConfig {
-            device_type: DeviceType::CUDA,
-            device_ids: vec![0],
+            device_type: request.device_type.unwrap_or_else(|| state.model_service.default_device_type()),
+            device_ids: if request.device_ids.is_empty() { vec![0] } else { request.device_ids },
             memory_limit_mb: None,
-            mixed_precision: false,
+            mixed_precision: request.mixed_precision,
+            parallelism: request.parallelism,
+            micro_batches: request.micro_batches,
         },
         optimization: OptimizationConfig {
             kv_cache: true,
@@ -80,8 +163,18 @@ pub async fn register_model(
             graph_optimization: true,
             inference_parallelism: 1,
             memory_optimization: MemoryOptimization::Medium,
+            cuda_graphs: request.cuda_graphs,
+            cuda_streams: request.cuda_streams,
         },
         batch_config: BatchConfig::default(),
+        max_concurrent_batches: request.max_concurrent_batches,
+        warmup: request.warmup,
+        signature: request.signature,
+        input_schema: request.input_schema,
+        preprocessing: request.preprocessing,
+        postprocessing: request.postprocessing,
+        labels_path: request.labels_path,
+        speculative: request.speculative,
         custom_params: request
             .config
             .and_then(|v| v.as_object().cloned())
@@ -90,11 +183,20 @@ pub async fn register_model(
             .collect(),
     };
 
-    match state
+    let registered = match state
         .model_service
-        .register_model(request.name.clone(), request.model_type, model_config)
+        .register_model(request.name.clone(), request.model_type, model_config, tenant.clone())
         .await
     {
+        Ok(model_id) if request.pinned => state
+            .model_service
+            .set_pinned(&model_id, tenant.as_deref(), true)
+            .await
+            .map(|info| info.id),
+        other => other,
+    };
+
+    match registered {
         Ok(model_id) => {
             let response = RegisterModelResponse {
                 model_id,
@@ -119,8 +221,9 @@ pub async fn register_model(
 /// 获取模型列表
 pub async fn list_models(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
 ) -> Result<Json<ListModelsResponse>, (StatusCode, Json<serde_json::Value>)> {
-    match state.model_service.list_models().await {
+    match state.model_service.list_models(auth.tenant.as_deref()).await {
         Ok(models) => {
             let response = ListModelsResponse {
                 total: models.len(),
@@ -144,9 +247,10 @@ pub async fn list_models(
 /// 获取单个模型信息
 pub async fn get_model(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(model_id): Path<ModelId>,
 ) -> Result<Json<ModelInfo>, (StatusCode, Json<serde_json::Value>)> {
-    match state.model_service.get_model_info(&model_id).await {
+    match state.model_service.get_model_info(&model_id, auth.tenant.as_deref()).await {
         Ok(model_info) => Ok(Json(model_info)),
         Err(e) => {
             error!("Failed to get model {}: {}", model_id, e);
@@ -161,14 +265,36 @@ pub async fn get_model(
     }
 }
 
+/// 获取模型输入输出签名
+pub async fn get_model_signature(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+) -> Result<Json<ModelSignature>, (StatusCode, Json<serde_json::Value>)> {
+    match state.model_service.get_signature(&model_id, auth.tenant.as_deref()).await {
+        Ok(signature) => Ok(Json(signature)),
+        Err(e) => {
+            error!("Failed to get signature of model {}: {}", model_id, e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
 /// 注销模型
 pub async fn unregister_model(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(model_id): Path<ModelId>,
 ) -> Result<Json<serde_json::Value>, (StatusCode, Json<serde_json::Value>)> {
     info!("Unregistering model: {}", model_id);
 
-    match state.model_service.unregister_model(&model_id).await {
+    match state.model_service.unregister_model(&model_id, auth.tenant.as_deref()).await {
         Ok(()) => Ok(Json(serde_json::json!({
             "status": "success",
             "message": format!("Model '{}' unregistered successfully", model_id)
@@ -185,3 +311,121 @@ pub async fn unregister_model(
         }
     }
 }
+
+/// 更新模型（目前支持固定驻留）
+pub async fn update_model(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<UpdateModelRequest>,
+) -> Result<Json<ModelInfo>, (StatusCode, Json<serde_json::Value>)> {
+    info!("Updating model: {}", model_id);
+
+    let result = match request.pinned {
+        Some(pinned) => state.model_service.set_pinned(&model_id, auth.tenant.as_deref(), pinned).await,
+        None => state.model_service.get_model_info(&model_id, auth.tenant.as_deref()).await,
+    };
+
+    match result {
+        Ok(model_info) => Ok(Json(model_info)),
+        Err(e) => {
+            error!("Failed to update model {}: {}", model_id, e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
+/// 在模型上加载LoRA适配器
+pub async fn load_adapter(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<LoadAdapterRequest>,
+) -> Result<Json<ModelInfo>, (StatusCode, Json<serde_json::Value>)> {
+    match state
+        .model_service
+        .load_adapter(&model_id, auth.tenant.as_deref(), request.name, request.path)
+        .await
+    {
+        Ok(model_info) => Ok(Json(model_info)),
+        Err(e) => {
+            error!("Failed to load adapter on model {}: {}", model_id, e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
+/// 卸载模型上的LoRA适配器
+pub async fn unload_adapter(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path((model_id, adapter)): Path<(ModelId, String)>,
+) -> Result<Json<ModelInfo>, (StatusCode, Json<serde_json::Value>)> {
+    match state.model_service.unload_adapter(&model_id, auth.tenant.as_deref(), &adapter).await {
+        Ok(model_info) => Ok(Json(model_info)),
+        Err(e) => {
+            error!("Failed to unload adapter '{}' from model {}: {}", adapter, model_id, e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
+/// 启动模型格式转换，立即返回作业，通过 `/conversions/:job_id` 查询进度
+pub async fn convert_model(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<ConversionRequest>,
+) -> Result<(StatusCode, Json<ConversionJob>), (StatusCode, Json<serde_json::Value>)> {
+    info!("Converting model {} to {:?}", model_id, request.target_format);
+
+    match state.model_service.convert_model(&model_id, auth.tenant.as_deref(), request).await {
+        Ok(job) => Ok((StatusCode::ACCEPTED, Json(job))),
+        Err(e) => {
+            error!("Failed to start conversion of model {}: {}", model_id, e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
+
+/// 查询转换作业
+pub async fn get_conversion(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(job_id): Path<String>,
+) -> Result<Json<ConversionJob>, (StatusCode, Json<serde_json::Value>)> {
+    match state.model_service.get_conversion(&job_id, auth.tenant.as_deref()) {
+        Ok(job) => Ok(Json(job)),
+        Err(e) => Err((
+            StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            Json(serde_json::json!({
+                "error": e.error_code(),
+                "message": e.to_string()
+            })),
+        )),
+    }
+}
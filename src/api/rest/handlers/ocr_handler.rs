@@ -0,0 +1,76 @@
+//! OCR API处理器
+
+use axum::{
+    body::Bytes,
+    extract::{Query, State},
+    http::HeaderMap,
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Extension, Router,
+};
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::content::header_parameters;
+use crate::api::rest::handlers::{record_usage, AppState};
+use crate::application::services::ocr_service::{OcrOptions, TextRegion};
+use crate::common::types::*;
+
+/// 默认的检测分数阈值
+const DEFAULT_MIN_DETECTION_SCORE: f32 = 0.5;
+
+/// OCR查询参数
+#[derive(Debug, Deserialize)]
+pub struct OcrQuery {
+    pub detection_model: ModelId,
+    pub recognition_model: ModelId,
+    pub min_detection_score: Option<f32>,
+}
+
+/// OCR响应
+#[derive(Debug, Serialize)]
+pub struct OcrResponse {
+    pub regions: Vec<TextRegion>,
+}
+
+/// 创建OCR路由
+pub fn create_ocr_routes() -> Router<AppState> {
+    Router::new().route("/ocr", post(ocr))
+}
+
+/// 对请求体中的图像执行文本检测和识别，推理参数取自 `X-Parameters` 头
+pub async fn ocr(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<OcrQuery>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<OcrResponse>, Response> {
+    info!(
+        "Processing OCR request with detection model {} and recognition model {}",
+        query.detection_model, query.recognition_model
+    );
+
+    let parameters = header_parameters(&headers).map_err(IntoResponse::into_response)?;
+    let options = OcrOptions {
+        detection_model: query.detection_model,
+        recognition_model: query.recognition_model,
+        min_detection_score: query.min_detection_score.unwrap_or(DEFAULT_MIN_DETECTION_SCORE),
+    };
+
+    let outcome = state.ocr_service
+        .recognize(body, &options, parameters, auth.tenant.as_deref())
+        .await
+        .map_err(|e| {
+            error!("OCR failed: {}", e);
+            e.into_response()
+        })?;
+    for response in &outcome.responses {
+        record_usage(&state, &auth, response).await;
+    }
+
+    Ok(Json(OcrResponse {
+        regions: outcome.regions,
+    }))
+}
@@ -0,0 +1,50 @@
+//! 插件API处理器
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Extension, Router,
+};
+use serde::Serialize;
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::AppState;
+use crate::infrastructure::security::SCOPE_ADMIN;
+use crate::plugins::manager::PluginInfo;
+
+/// 插件列表响应
+#[derive(Debug, Serialize)]
+pub struct ListPluginsResponse {
+    pub plugins: Vec<PluginInfo>,
+    pub total: usize,
+}
+
+/// 创建插件路由
+pub fn create_plugin_routes() -> Router<AppState> {
+    Router::new()
+        .route("/plugins", get(list_plugins))
+}
+
+/// 获取已加载的插件及其版本、模型数、请求和错误统计、内存占用（需要管理权限）
+pub async fn list_plugins(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+) -> Result<Json<ListPluginsResponse>, (StatusCode, Json<serde_json::Value>)> {
+    if let Err(e) = auth.require_scope(SCOPE_ADMIN) {
+        return Err((
+            StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+            Json(serde_json::json!({
+                "error": e.error_code(),
+                "message": e.to_string()
+            })),
+        ));
+    }
+
+    let plugins = state.model_service.list_plugins();
+    Ok(Json(ListPluginsResponse {
+        total: plugins.len(),
+        plugins,
+    }))
+}
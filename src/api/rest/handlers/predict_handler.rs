@@ -1,24 +1,44 @@
 //! 推理API处理器
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
-    response::Json,
+    body::Bytes,
+    extract::{BodyStream, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
     routing::post,
-    Router,
+    Extension, Router,
 };
+use futures::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
 use tracing::{info, error};
 
 use crate::common::types::*;
 use crate::common::error::*;
-use crate::application::services::PredictionService;
-use crate::domain::service::batch_processor::PredictionResponse;
+use crate::domain::model::ModelInfo;
+use crate::domain::service::batch_processor::{PredictionResponse, ResponseMetadata};
 use crate::api::rest::handlers::AppState;
+use crate::api::rest::content::{
+    deserialize_body, header_parameters, read_multipart, serialize_body, with_content_type, WireFormat,
+    ARROW_STREAM_CONTENT_TYPE,
+};
+use crate::api::auth::AuthContext;
+use crate::infrastructure::tensor_format::{read_arrow_ipc, read_arrow_table, read_npy, read_npz, unstack, write_arrow_ipc};
+
+/// 请求优先级头，优先于请求体中的 `parameters.priority`
+pub const PRIORITY_HEADER: &str = "x-priority";
+
+/// 非JSON响应通过该头返回请求ID
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// `.npy` 单个数组的默认输入名，模型签名只有一个输入时替换为该输入名
+pub const DEFAULT_TENSOR_NAME: &str = "input";
+
+/// 原始二进制请求体上限，与二进制输入的校验上限一致
+pub const MAX_RAW_BODY_BYTES: usize = 100_000_000;
 
 /// 推理请求
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PredictRequest {
     pub input: InputData,
     pub parameters: Option<PredictionParameters>,
@@ -35,6 +55,46 @@ pub struct PredictResponse {
     pub timestamp: chrono::DateTime<chrono::Utc>,
 }
 
+/// 原始二进制推理的查询参数，覆盖 `X-Parameters` 头中的同名参数
+#[derive(Debug, Default, Deserialize)]
+pub struct RawPredictQuery {
+    pub max_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub top_k: Option<u32>,
+    pub priority: Option<String>,
+    pub timeout_ms: Option<u64>,
+    pub mask_format: Option<MaskFormat>,
+}
+
+impl RawPredictQuery {
+    /// 将查询参数合并到推理参数
+    pub fn apply(&self, parameters: &mut PredictionParameters) -> Result<()> {
+        if self.max_tokens.is_some() {
+            parameters.max_tokens = self.max_tokens;
+        }
+        if self.temperature.is_some() {
+            parameters.temperature = self.temperature;
+        }
+        if self.top_p.is_some() {
+            parameters.top_p = self.top_p;
+        }
+        if self.top_k.is_some() {
+            parameters.top_k = self.top_k;
+        }
+        if let Some(priority) = &self.priority {
+            parameters.priority = priority.parse()?;
+        }
+        if self.timeout_ms.is_some() {
+            parameters.timeout_ms = self.timeout_ms;
+        }
+        if self.mask_format.is_some() {
+            parameters.mask_format = self.mask_format;
+        }
+        Ok(())
+    }
+}
+
 /// 批量推理请求
 #[derive(Debug, Deserialize)]
 pub struct BatchPredictRequest {
@@ -58,24 +118,77 @@ pub fn create_predict_routes() -> Router<AppState> {
     Router::new()
         .route("/models/:model_id/predict", post(predict))
         .route("/models/:model_id/predict/batch", post(batch_predict))
+        .route("/models/:model_id/predict/raw", post(predict_raw))
 }
 
 /// 单个推理处理
 pub async fn predict(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(model_id): Path<ModelId>,
-    Json(request): Json<PredictRequest>,
-) -> Result<Json<PredictResponse>, (StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, Response> {
     info!("Processing prediction request for model: {}", model_id);
 
-    let parameters = request.parameters.unwrap_or_default();
+    let request = decode_predict_request(&headers, body).await.map_err(IntoResponse::into_response)?;
+    execute_predict(&state, &auth, model_id, &headers, request).await
+}
+
+/// 原始二进制推理处理
+///
+/// 请求体整体作为二进制输入，按块读入内存而不经过JSON信封；参数取自 `X-Parameters` 头，
+/// 再由查询参数覆盖，适合上百MB的音视频输入。
+pub async fn predict_raw(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Query(query): Query<RawPredictQuery>,
+    headers: HeaderMap,
+    body: BodyStream,
+) -> Result<Response, Response> {
+    info!("Processing raw prediction request for model: {}", model_id);
+
+    let request = async {
+        let mut parameters = header_parameters(&headers)?;
+        query.apply(&mut parameters)?;
+        Ok::<_, UniModelError>(PredictRequest {
+            input: InputData::Binary(read_raw_body(&headers, body).await?.into()),
+            parameters: Some(parameters),
+        })
+    }.await.map_err(IntoResponse::into_response)?;
+    execute_predict(&state, &auth, model_id, &headers, request).await
+}
+
+/// 执行单个推理并按 `Accept` 编码响应
+async fn execute_predict(
+    state: &AppState,
+    auth: &AuthContext,
+    model_id: ModelId,
+    headers: &HeaderMap,
+    request: PredictRequest,
+) -> Result<Response, Response> {
+    let mut parameters = request.parameters.unwrap_or_default();
+    apply_priority_header(headers, &mut parameters).map_err(IntoResponse::into_response)?;
 
-    match state.prediction_service.predict(
-        model_id.clone(),
-        request.input,
-        parameters,
-    ).await {
+    let result = match state.model_service.get_model_info(&model_id, auth.tenant.as_deref()).await {
+        Ok(model_info) => {
+            let mut input = request.input;
+            bind_default_input(&model_info, &mut input);
+            state.prediction_service.predict(
+                model_id.clone(),
+                input,
+                parameters,
+                auth.tenant.as_deref(),
+            ).await
+        }
+        Err(e) => Err(e),
+    };
+
+    match result {
         Ok(response) => {
+            record_usage(state, auth, &response).await;
+
             let predict_response = PredictResponse {
                 request_id: response.request_id,
                 model_id: response.model_id,
@@ -84,17 +197,13 @@ pub async fn predict(
                 metrics: response.metrics,
                 timestamp: response.timestamp,
             };
-            Ok(Json(predict_response))
+            encode_predict_response(WireFormat::from_accept(headers), predict_response)
+                .map_err(IntoResponse::into_response)
         }
         Err(e) => {
             error!("Prediction failed for model {}: {}", model_id, e);
-            Err((
-                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                Json(serde_json::json!({
-                    "error": e.error_code(),
-                    "message": e.to_string()
-                }))
-            ))
+            // 过载类错误会附带Retry-After与队列提示头
+            Err(e.into_response())
         }
     }
 }
@@ -102,20 +211,40 @@ pub async fn predict(
 /// 批量推理处理
 pub async fn batch_predict(
     State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
     Path(model_id): Path<ModelId>,
-    Json(request): Json<BatchPredictRequest>,
-) -> Result<Json<BatchPredictResponse>, (StatusCode, Json<serde_json::Value>)> {
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Response, Response> {
+    let request = decode_batch_predict_request(&headers, body).await.map_err(IntoResponse::into_response)?;
     info!("Processing batch prediction request for model: {} with {} inputs",
           model_id, request.inputs.len());
 
-    let parameters = request.parameters.unwrap_or_default();
+    let mut parameters = request.parameters.unwrap_or_default();
+    apply_priority_header(&headers, &mut parameters).map_err(IntoResponse::into_response)?;
+
+    let result = match state.model_service.get_model_info(&model_id, auth.tenant.as_deref()).await {
+        Ok(model_info) => {
+            let mut inputs = request.inputs;
+            for input in &mut inputs {
+                bind_default_input(&model_info, input);
+            }
+            state.prediction_service.batch_predict(
+                model_id.clone(),
+                inputs,
+                parameters,
+                auth.tenant.as_deref(),
+            ).await
+        }
+        Err(e) => Err(e),
+    };
 
-    match state.prediction_service.batch_predict(
-        model_id.clone(),
-        request.inputs,
-        parameters,
-    ).await {
+    match result {
         Ok(responses) => {
+            for response in &responses {
+                record_usage(&state, &auth, response).await;
+            }
+
             // 合并批量响应
             let request_id = new_request_id();
             let outputs: Vec<OutputData> = responses.iter()
@@ -131,27 +260,176 @@ pub async fn batch_predict(
                     .unwrap_or_else(|| ResponseMetadata {
                         model_version: "unknown".to_string(),
                         backend: "unknown".to_string(),
+                        precision: None,
                         custom_metadata: std::collections::HashMap::new(),
                     }),
                 metrics: merge_batch_metrics(&responses),
                 timestamp: chrono::Utc::now(),
             };
 
-            Ok(Json(batch_response))
+            encode_batch_predict_response(WireFormat::from_accept(&headers), batch_response)
+                .map_err(IntoResponse::into_response)
         }
         Err(e) => {
             error!("Batch prediction failed for model {}: {}", model_id, e);
-            Err((
-                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
-                Json(serde_json::json!({
-                    "error": e.error_code(),
-                    "message": e.to_string()
-                }))
-            ))
+            // 过载类错误会附带Retry-After与队列提示头
+            Err(e.into_response())
+        }
+    }
+}
+
+/// 按 `Content-Type` 解码推理请求
+///
+/// Arrow表格、`.npy`/`.npz` 数组整体作为一组张量输入；表单只有一个输入字段时直接作为输入，
+/// 多个字段按字段名组成多模态输入。
+async fn decode_predict_request(headers: &HeaderMap, body: Bytes) -> Result<PredictRequest> {
+    let format = WireFormat::from_content_type(headers)?;
+    let tensors = match format {
+        WireFormat::Json | WireFormat::MsgPack | WireFormat::Cbor => return deserialize_body(format, &body),
+        WireFormat::Multipart => {
+            let mut payload = read_multipart(headers, body).await?;
+            let parameters = payload.parameters.take();
+            return Ok(PredictRequest {
+                input: payload.into_input()?,
+                parameters,
+            });
+        }
+        WireFormat::Arrow => read_arrow_table(&body)?,
+        WireFormat::Npy => HashMap::from([(DEFAULT_TENSOR_NAME.to_string(), read_npy(&body)?)]),
+        WireFormat::Npz => read_npz(&body)?,
+    };
+    Ok(PredictRequest {
+        input: InputData::Tensors(tensors),
+        parameters: Some(header_parameters(headers)?),
+    })
+}
+
+/// 按 `Content-Type` 解码批量推理请求
+///
+/// Arrow请求体中每个记录批次是一个输入；`.npy`/`.npz` 数组沿第一维拆分为多个输入；
+/// 表单中每个输入字段是一个输入。
+async fn decode_batch_predict_request(headers: &HeaderMap, body: Bytes) -> Result<BatchPredictRequest> {
+    let format = WireFormat::from_content_type(headers)?;
+    let inputs = match format {
+        WireFormat::Json | WireFormat::MsgPack | WireFormat::Cbor => return deserialize_body(format, &body),
+        WireFormat::Multipart => {
+            let payload = read_multipart(headers, body).await?;
+            return Ok(BatchPredictRequest {
+                inputs: payload.parts.into_iter().map(|(_, input)| input).collect(),
+                parameters: payload.parameters,
+            });
         }
+        WireFormat::Arrow => read_arrow_ipc(&body)?,
+        WireFormat::Npy => unstack(&HashMap::from([(DEFAULT_TENSOR_NAME.to_string(), read_npy(&body)?)]))?,
+        WireFormat::Npz => unstack(&read_npz(&body)?)?,
+    };
+    Ok(BatchPredictRequest {
+        inputs: inputs.into_iter().map(InputData::Tensors).collect(),
+        parameters: Some(header_parameters(headers)?),
+    })
+}
+
+/// 模型签名只有一个输入时，把默认名称的张量绑定到该输入
+fn bind_default_input(model_info: &ModelInfo, input: &mut InputData) {
+    let tensors = match input {
+        InputData::Tensors(tensors) if tensors.len() == 1 => tensors,
+        _ => return,
+    };
+    let name = match model_info.signature.as_ref().map(|s| s.inputs.as_slice()) {
+        Some([spec]) => spec.name.clone(),
+        _ => return,
+    };
+    if let Some(tensor) = tensors.remove(DEFAULT_TENSOR_NAME) {
+        tensors.insert(name, tensor);
     }
 }
 
+/// 按 `Accept` 编码推理响应；Arrow只能承载张量输出，其他输出回退为JSON
+fn encode_predict_response(format: WireFormat, response: PredictResponse) -> Result<Response> {
+    match (format, &response.output) {
+        (WireFormat::Arrow, OutputData::Tensors(tensors)) => {
+            let body = write_arrow_ipc(std::slice::from_ref(tensors))?;
+            arrow_response(&response.request_id, body)
+        }
+        _ => serialize_body(format, &response),
+    }
+}
+
+/// 按 `Accept` 编码批量推理响应，每个输出写为一个记录批次
+fn encode_batch_predict_response(format: WireFormat, response: BatchPredictResponse) -> Result<Response> {
+    let tensors: Option<Vec<_>> = response.outputs.iter()
+        .map(|output| match output {
+            OutputData::Tensors(tensors) => Some(tensors.clone()),
+            _ => None,
+        })
+        .collect();
+
+    match (format, tensors) {
+        (WireFormat::Arrow, Some(tensors)) if !tensors.is_empty() => {
+            arrow_response(&response.request_id, write_arrow_ipc(&tensors)?)
+        }
+        _ => serialize_body(format, &response),
+    }
+}
+
+fn arrow_response(request_id: &str, body: Vec<u8>) -> Result<Response> {
+    let request_id = HeaderValue::from_str(request_id)
+        .map_err(|e| UniModelError::internal(format!("Invalid request id: {}", e)))?;
+    let mut response = with_content_type(body.into_response(), ARROW_STREAM_CONTENT_TYPE);
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    Ok(response)
+}
+
+/// 按块读取原始请求体，超过上限时立即拒绝而不读完整个请求体
+async fn read_raw_body(headers: &HeaderMap, mut body: BodyStream) -> Result<Vec<u8>> {
+    let too_large = || UniModelError::validation(format!(
+        "Raw request body exceeds the {} byte limit",
+        MAX_RAW_BODY_BYTES
+    ));
+
+    let content_length = headers.get(header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse::<usize>().ok());
+    if content_length.is_some_and(|len| len > MAX_RAW_BODY_BYTES) {
+        return Err(too_large());
+    }
+
+    let mut data = Vec::with_capacity(content_length.unwrap_or(0));
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|e| UniModelError::validation(format!("Failed to read request body: {}", e)))?;
+        if data.len() + chunk.len() > MAX_RAW_BODY_BYTES {
+            return Err(too_large());
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+}
+
+/// 读取 `X-Priority` 头并覆盖请求参数中的优先级
+fn apply_priority_header(headers: &HeaderMap, parameters: &mut PredictionParameters) -> Result<()> {
+    if let Some(value) = headers.get(PRIORITY_HEADER) {
+        let value = value.to_str()
+            .map_err(|_| UniModelError::validation("Invalid X-Priority header"))?;
+        parameters.priority = value.parse()?;
+    }
+    Ok(())
+}
+
+/// 计算一次推理消耗的token数（输入+输出）
+fn consumed_tokens(metrics: &PerformanceMetrics) -> u64 {
+    metrics.tokens_input.unwrap_or(0) as u64 + metrics.tokens_generated.unwrap_or(0) as u64
+}
+
+/// 记录配额消耗与用量计量
+pub(crate) async fn record_usage(state: &AppState, auth: &AuthContext, response: &PredictionResponse) {
+    state.quota_manager
+        .record_tokens(auth.subject(), consumed_tokens(&response.metrics))
+        .await;
+    state.usage_store
+        .record(auth.tenant.as_deref(), &response.model_id, &response.metrics)
+        .await;
+}
+
 /// 合并批量推理的性能指标
 fn merge_batch_metrics(responses: &[PredictionResponse]) -> PerformanceMetrics {
     if responses.is_empty() {
@@ -198,11 +476,3 @@ fn merge_batch_metrics(responses: &[PredictionResponse]) -> PerformanceMetrics {
         memory_usage_mb: first_response.metrics.memory_usage_mb,
     }
 }
-
-/// 响应元数据
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct ResponseMetadata {
-    pub model_version: String,
-    pub backend: String,
-    pub custom_metadata: std::collections::HashMap<String, serde_json::Value>,
-}
\ No newline at end of file
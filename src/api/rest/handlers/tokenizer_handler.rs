@@ -0,0 +1,106 @@
+//! 分词API处理器
+
+use axum::{
+    extract::{Path, State},
+    response::{IntoResponse, Json, Response},
+    routing::post,
+    Extension, Router,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::AppState;
+use crate::common::types::*;
+
+/// 分词请求
+#[derive(Debug, Deserialize)]
+pub struct TokenizeRequest {
+    pub text: String,
+    /// 是否添加BOS等特殊token，与推理时的处理一致
+    #[serde(default = "default_true")]
+    pub add_special_tokens: bool,
+}
+
+/// 分词响应
+#[derive(Debug, Serialize)]
+pub struct TokenizeResponse {
+    pub tokens: Vec<u32>,
+    pub count: usize,
+}
+
+/// 反分词请求
+#[derive(Debug, Deserialize)]
+pub struct DetokenizeRequest {
+    pub tokens: Vec<u32>,
+    #[serde(default = "default_true")]
+    pub skip_special_tokens: bool,
+}
+
+/// 反分词响应
+#[derive(Debug, Serialize)]
+pub struct DetokenizeResponse {
+    pub text: String,
+}
+
+/// token计数响应
+#[derive(Debug, Serialize)]
+pub struct TokenCountResponse {
+    pub count: usize,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// 创建分词路由
+pub fn create_tokenizer_routes() -> Router<AppState> {
+    Router::new()
+        .route("/models/:model_id/tokenize", post(tokenize))
+        .route("/models/:model_id/detokenize", post(detokenize))
+        .route("/models/:model_id/token-count", post(count_tokens))
+}
+
+/// 用模型分词器编码文本
+pub async fn tokenize(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<TokenizeRequest>,
+) -> Result<Json<TokenizeResponse>, Response> {
+    let tokens = state.model_service
+        .tokenize(&model_id, auth.tenant.as_deref(), request.text, request.add_special_tokens)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(TokenizeResponse {
+        count: tokens.len(),
+        tokens,
+    }))
+}
+
+/// 把token序列解码为文本
+pub async fn detokenize(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<DetokenizeRequest>,
+) -> Result<Json<DetokenizeResponse>, Response> {
+    let text = state.model_service
+        .detokenize(&model_id, auth.tenant.as_deref(), request.tokens, request.skip_special_tokens)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(DetokenizeResponse { text }))
+}
+
+/// 统计文本的token数
+pub async fn count_tokens(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Path(model_id): Path<ModelId>,
+    Json(request): Json<TokenizeRequest>,
+) -> Result<Json<TokenCountResponse>, Response> {
+    let tokens = state.model_service
+        .tokenize(&model_id, auth.tenant.as_deref(), request.text, request.add_special_tokens)
+        .await
+        .map_err(IntoResponse::into_response)?;
+    Ok(Json(TokenCountResponse { count: tokens.len() }))
+}
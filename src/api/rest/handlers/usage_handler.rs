@@ -0,0 +1,93 @@
+//! 用量报表API处理器
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    response::Json,
+    routing::get,
+    Extension, Router,
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::api::auth::AuthContext;
+use crate::api::rest::handlers::AppState;
+use crate::common::error::*;
+use crate::infrastructure::storage::{UsageGroupBy, UsageRow};
+
+/// 默认查询窗口（天）
+const DEFAULT_USAGE_WINDOW_DAYS: i64 = 30;
+
+/// 用量查询参数
+#[derive(Debug, Deserialize)]
+pub struct UsageQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+    /// 逗号分隔的分组维度：tenant, model, day, hour
+    pub group_by: Option<String>,
+}
+
+/// 用量查询响应
+#[derive(Debug, Serialize)]
+pub struct UsageResponse {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub group_by: Vec<String>,
+    pub rows: Vec<UsageRow>,
+}
+
+/// 创建用量路由
+pub fn create_usage_routes() -> Router<AppState> {
+    Router::new().route("/usage", get(get_usage))
+}
+
+/// 查询用量，租户密钥只能看到自身租户的数据
+pub async fn get_usage(
+    State(state): State<AppState>,
+    Extension(auth): Extension<AuthContext>,
+    Query(query): Query<UsageQuery>,
+) -> Result<Json<UsageResponse>, (StatusCode, Json<serde_json::Value>)> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or_else(|| to - chrono::Duration::days(DEFAULT_USAGE_WINDOW_DAYS));
+
+    let group_by_names: Vec<String> = query.group_by
+        .as_deref()
+        .unwrap_or("tenant,model")
+        .split(',')
+        .map(|s| s.trim().to_string())
+        .filter(|s| !s.is_empty())
+        .collect();
+
+    let result = if from >= to {
+        Err(UniModelError::validation("'from' must be earlier than 'to'"))
+    } else {
+        group_by_names.iter()
+            .map(|s| s.parse::<UsageGroupBy>())
+            .collect::<Result<Vec<_>>>()
+    };
+
+    match result {
+        Ok(group_by) => {
+            let rows = state.usage_store
+                .query(from, to, auth.tenant.as_deref(), &group_by)
+                .await;
+            Ok(Json(UsageResponse {
+                from,
+                to,
+                group_by: group_by_names,
+                rows,
+            }))
+        }
+        Err(e) => {
+            error!("Invalid usage query: {}", e);
+            Err((
+                StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR),
+                Json(serde_json::json!({
+                    "error": e.error_code(),
+                    "message": e.to_string()
+                })),
+            ))
+        }
+    }
+}
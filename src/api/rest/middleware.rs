@@ -0,0 +1,71 @@
+//! REST中间件
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{Request, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Json, Response},
+};
+
+use crate::api::auth::{rate_limit_key, AuthContext};
+use crate::common::error::*;
+use crate::infrastructure::security::{QuotaManager, RateLimiter};
+
+/// 速率限制中间件，必须位于认证中间件之后
+pub async fn rate_limit_middleware<B>(
+    State(rate_limiter): State<Arc<RateLimiter>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    if !rate_limiter.is_enabled() {
+        return next.run(request).await;
+    }
+
+    let context = request.extensions().get::<AuthContext>();
+    let key_id = context.and_then(|c| c.key_id.as_deref());
+    let key_rate_limit = context.and_then(|c| c.rate_limit_per_minute);
+    let client_ip = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+
+    let bucket_key = rate_limit_key(context, client_ip);
+    let requests_per_minute = rate_limiter.requests_per_minute_for(key_id, key_rate_limit);
+
+    match rate_limiter.check(&bucket_key, requests_per_minute) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => UniModelError::RateLimited {
+            message: format!("Limit of {} requests per minute reached", requests_per_minute),
+            hint: OverloadHint::retry_after(retry_after),
+        }
+        .into_response(),
+    }
+}
+
+/// 配额中间件，必须位于认证中间件之后
+pub async fn quota_middleware<B>(
+    State(quota_manager): State<Arc<QuotaManager>>,
+    request: Request<B>,
+    next: Next<B>,
+) -> Response {
+    let context = request
+        .extensions()
+        .get::<AuthContext>()
+        .cloned()
+        .unwrap_or_else(AuthContext::anonymous);
+
+    match quota_manager.check_request(context.subject()).await {
+        Ok(()) => next.run(request).await,
+        Err(e) => (
+            StatusCode::from_u16(e.status_code()).unwrap_or(StatusCode::TOO_MANY_REQUESTS),
+            Json(serde_json::json!({
+                "error": e.error_code(),
+                "message": e.to_string()
+            })),
+        )
+            .into_response(),
+    }
+}
@@ -0,0 +1,8 @@
+//! REST API
+
+pub mod content;
+pub mod error_response;
+pub mod handlers;
+pub mod middleware;
+pub mod routes;
+pub mod server;
@@ -0,0 +1,44 @@
+//! REST路由组装
+
+use std::sync::Arc;
+
+use axum::{middleware, Router};
+
+use crate::api::auth::{auth_middleware, Authenticator};
+use crate::api::rest::handlers::*;
+use crate::api::rest::middleware::{quota_middleware, rate_limit_middleware};
+use crate::infrastructure::security::RateLimiter;
+
+/// 创建完整的REST路由
+///
+/// 中间件按添加顺序由内向外包裹，执行顺序为：认证 -> 限流 -> 配额检查。
+/// 健康检查路由在中间件之后合并，不受其影响；调试路由只在 `debug_endpoints` 开启时挂载。
+pub fn create_router(
+    state: AppState,
+    authenticator: Arc<Authenticator>,
+    rate_limiter: Arc<RateLimiter>,
+    debug_endpoints: bool,
+) -> Router {
+    let quota_manager = Arc::clone(&state.quota_manager);
+
+    let mut routes = Router::new()
+        .merge(create_model_routes())
+        .merge(create_predict_routes())
+        .merge(create_image_routes())
+        .merge(create_ocr_routes())
+        .merge(create_tokenizer_routes())
+        .merge(create_admin_routes())
+        .merge(create_event_routes())
+        .merge(create_usage_routes())
+        .merge(create_plugin_routes());
+    if debug_endpoints {
+        routes = routes.merge(create_debug_routes());
+    }
+
+    routes
+        .layer(middleware::from_fn_with_state(quota_manager, quota_middleware))
+        .layer(middleware::from_fn_with_state(rate_limiter, rate_limit_middleware))
+        .layer(middleware::from_fn_with_state(authenticator, auth_middleware))
+        .merge(create_health_routes())
+        .with_state(state)
+}
@@ -0,0 +1,62 @@
+//! REST API服务器
+
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+
+use axum::extract::DefaultBodyLimit;
+use axum::Router;
+use tokio_util::sync::CancellationToken;
+use tracing::info;
+
+use crate::api::auth::Authenticator;
+use crate::api::listener::{self, ListenFds};
+use crate::api::rest::handlers::AppState;
+use crate::api::rest::routes::create_router;
+use crate::common::error::*;
+use crate::infrastructure::configuration::Config;
+use crate::infrastructure::security::RateLimiter;
+
+/// REST API服务器
+pub struct ApiServer {
+    listener: TcpListener,
+    router: Router,
+}
+
+impl ApiServer {
+    /// 创建REST API服务器并取得监听套接字，优先使用systemd传入的 `http` 套接字
+    pub async fn new(
+        config: &Config,
+        state: AppState,
+        rate_limiter: Arc<RateLimiter>,
+        inherited: &ListenFds,
+    ) -> Result<Self> {
+        let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port)
+            .parse()
+            .map_err(|e| UniModelError::config(format!("Invalid server address: {}", e)))?;
+        let listener = listener::listen("http", 0, addr, config.server.reuse_port, inherited)?;
+
+        let authenticator = Arc::new(Authenticator::new(
+            &config.security,
+            Arc::clone(&state.api_key_store),
+        ));
+
+        Ok(Self {
+            listener,
+            router: create_router(state, authenticator, rate_limiter, config.monitoring.debug_endpoints)
+                .layer(DefaultBodyLimit::max(config.server.max_request_body_mb * 1024 * 1024)),
+        })
+    }
+
+    /// 启动HTTP服务，`shutdown` 取消后停止接受新连接，处理中的请求完成后返回
+    pub async fn serve(self, shutdown: CancellationToken) -> Result<()> {
+        info!("REST API listening on {}", self.listener.local_addr()?);
+
+        axum::Server::from_tcp(self.listener)?
+            .serve(self.router.into_make_service_with_connect_info::<SocketAddr>())
+            .with_graceful_shutdown(async move { shutdown.cancelled().await })
+            .await?;
+
+        info!("REST API stopped");
+        Ok(())
+    }
+}
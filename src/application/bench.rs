@@ -0,0 +1,296 @@
+//! 压测
+//!
+//! `unimodel bench --model <id> --concurrency N --duration 60s --input-file inputs.jsonl` 对运行中的服务器
+//! 持续发送推理请求，统计延迟分位数、吞吐量和错误率。输入文件每行一个推理请求体
+//! （与 `POST /models/:model_id/predict` 相同），按顺序循环使用；未指定时发送固定的文本输入。
+
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tracing::info;
+
+use crate::api::auth::API_KEY_HEADER;
+use crate::api::rest::handlers::predict_handler::PredictRequest;
+use crate::common::error::*;
+use crate::common::types::*;
+
+/// 服务器以该子命令运行压测
+pub const BENCH_COMMAND: &str = "bench";
+
+/// 未指定输入文件时发送的文本
+const DEFAULT_INPUT_TEXT: &str = "Hello, UniModel!";
+
+/// 压测参数
+#[derive(Debug, Clone)]
+pub struct BenchOptions {
+    /// 服务器地址，如 `http://127.0.0.1:8000`
+    pub url: String,
+    pub model_id: ModelId,
+    /// 并发连接数
+    pub concurrency: usize,
+    pub duration: Duration,
+    /// 单个请求的超时
+    pub timeout: Duration,
+    pub input_file: Option<PathBuf>,
+    pub api_key: Option<String>,
+    /// JSON报告的输出路径
+    pub output: Option<PathBuf>,
+}
+
+impl BenchOptions {
+    /// 解析 `bench` 之后的命令行参数
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut options = Self {
+            url: "http://127.0.0.1:8000".to_string(),
+            model_id: String::new(),
+            concurrency: 1,
+            duration: Duration::from_secs(60),
+            timeout: Duration::from_secs(30),
+            input_file: None,
+            api_key: std::env::var("UNIMODEL_API_KEY").ok(),
+            output: None,
+        };
+
+        let mut args = args.iter();
+        while let Some(flag) = args.next() {
+            let value = args.next()
+                .ok_or_else(|| UniModelError::validation(format!("Missing value for {}", flag)))?;
+            match flag.as_str() {
+                "--url" => options.url = value.trim_end_matches('/').to_string(),
+                "--model" => options.model_id = value.clone(),
+                "--concurrency" => {
+                    options.concurrency = value.parse()
+                        .ok()
+                        .filter(|&n| n > 0)
+                        .ok_or_else(|| UniModelError::validation(format!("Invalid concurrency '{}'", value)))?;
+                }
+                "--duration" => options.duration = parse_duration(value)?,
+                "--timeout" => options.timeout = parse_duration(value)?,
+                "--input-file" => options.input_file = Some(PathBuf::from(value)),
+                "--api-key" => options.api_key = Some(value.clone()),
+                "--output" => options.output = Some(PathBuf::from(value)),
+                other => return Err(UniModelError::validation(format!("Unknown bench option '{}'", other))),
+            }
+        }
+
+        if options.model_id.is_empty() {
+            return Err(UniModelError::validation("bench needs --model <id>"));
+        }
+        Ok(options)
+    }
+}
+
+/// 解析 `500ms`、`60s`、`5m`、`1h` 形式的时长，不带单位时按秒计
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit()).unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: u64 = number.parse()
+        .map_err(|_| UniModelError::validation(format!("Invalid duration '{}'", value)))?;
+    match unit {
+        "ms" => Ok(Duration::from_millis(number)),
+        "" | "s" => Ok(Duration::from_secs(number)),
+        "m" => Ok(Duration::from_secs(number * 60)),
+        "h" => Ok(Duration::from_secs(number * 3600)),
+        _ => Err(UniModelError::validation(format!("Invalid duration unit in '{}'", value))),
+    }
+}
+
+/// 延迟分布（毫秒）
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct LatencySummary {
+    pub min: f64,
+    pub mean: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p95: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl LatencySummary {
+    /// 按最近秩法计算分位数
+    pub fn from_samples(samples: &mut [Duration]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        samples.sort_unstable();
+        let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+        let percentile = |p: f64| {
+            let rank = ((p / 100.0) * samples.len() as f64).ceil() as usize;
+            ms(samples[rank.clamp(1, samples.len()) - 1])
+        };
+        let total: Duration = samples.iter().sum();
+        Self {
+            min: ms(samples[0]),
+            mean: ms(total) / samples.len() as f64,
+            p50: percentile(50.0),
+            p90: percentile(90.0),
+            p95: percentile(95.0),
+            p99: percentile(99.0),
+            max: ms(samples[samples.len() - 1]),
+        }
+    }
+}
+
+/// 压测报告
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchReport {
+    pub model_id: ModelId,
+    pub concurrency: usize,
+    /// 实际运行时长（秒）
+    pub elapsed_secs: f64,
+    pub requests: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    /// 每秒成功请求数
+    pub throughput_rps: f64,
+    /// 成功请求的延迟
+    pub latency_ms: LatencySummary,
+    /// 失败原因（HTTP状态码或 `transport`）-> 次数
+    pub errors_by_kind: BTreeMap<String, u64>,
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Model:        {}", self.model_id)?;
+        writeln!(f, "Concurrency:  {}", self.concurrency)?;
+        writeln!(f, "Duration:     {:.1}s", self.elapsed_secs)?;
+        writeln!(f, "Requests:     {} ({} failed, {:.2}%)", self.requests, self.errors, self.error_rate * 100.0)?;
+        writeln!(f, "Throughput:   {:.1} req/s", self.throughput_rps)?;
+        let l = &self.latency_ms;
+        writeln!(
+            f,
+            "Latency (ms): min {:.1}  mean {:.1}  p50 {:.1}  p90 {:.1}  p95 {:.1}  p99 {:.1}  max {:.1}",
+            l.min, l.mean, l.p50, l.p90, l.p95, l.p99, l.max
+        )?;
+        for (kind, count) in &self.errors_by_kind {
+            writeln!(f, "  {}: {}", kind, count)?;
+        }
+        Ok(())
+    }
+}
+
+/// 单个并发连接的统计
+#[derive(Debug, Default)]
+struct WorkerStats {
+    latencies: Vec<Duration>,
+    errors: BTreeMap<String, u64>,
+}
+
+/// 运行压测
+pub async fn run_bench(options: &BenchOptions) -> Result<BenchReport> {
+    let bodies = Arc::new(load_bodies(options).await?);
+    let client = reqwest::Client::builder()
+        .timeout(options.timeout)
+        .pool_max_idle_per_host(options.concurrency)
+        .build()
+        .map_err(|e| UniModelError::Network(format!("Failed to create HTTP client: {}", e)))?;
+    let url = format!("{}/models/{}/predict", options.url, options.model_id);
+    info!(
+        "Benchmarking {} with {} connections for {:?} ({} distinct inputs)",
+        url, options.concurrency, options.duration, bodies.len()
+    );
+
+    let next_body = Arc::new(AtomicUsize::new(0));
+    let started = Instant::now();
+    let deadline = started + options.duration;
+    let workers: Vec<_> = (0..options.concurrency)
+        .map(|_| {
+            let (client, url, api_key) = (client.clone(), url.clone(), options.api_key.clone());
+            let (bodies, next_body) = (Arc::clone(&bodies), Arc::clone(&next_body));
+            tokio::spawn(async move {
+                let mut stats = WorkerStats::default();
+                while Instant::now() < deadline {
+                    let body = bodies[next_body.fetch_add(1, Ordering::Relaxed) % bodies.len()].clone();
+                    let mut request = client.post(&url)
+                        .header(reqwest::header::CONTENT_TYPE, "application/json")
+                        .body(body);
+                    if let Some(key) = &api_key {
+                        request = request.header(API_KEY_HEADER, key);
+                    }
+
+                    let sent = Instant::now();
+                    let failure = match request.send().await {
+                        // 读完响应体再计时，与客户端看到的延迟一致
+                        Ok(response) if response.status().is_success() => match response.bytes().await {
+                            Ok(_) => None,
+                            Err(_) => Some("transport".to_string()),
+                        },
+                        Ok(response) => Some(response.status().as_u16().to_string()),
+                        Err(e) if e.is_timeout() => Some("timeout".to_string()),
+                        Err(_) => Some("transport".to_string()),
+                    };
+                    match failure {
+                        None => stats.latencies.push(sent.elapsed()),
+                        Some(kind) => *stats.errors.entry(kind).or_insert(0) += 1,
+                    }
+                }
+                stats
+            })
+        })
+        .collect();
+
+    let mut latencies = Vec::new();
+    let mut errors_by_kind = BTreeMap::new();
+    for worker in workers {
+        let stats = worker.await
+            .map_err(|e| UniModelError::internal(format!("Bench worker failed: {}", e)))?;
+        latencies.extend(stats.latencies);
+        for (kind, count) in stats.errors {
+            *errors_by_kind.entry(kind).or_insert(0) += count;
+        }
+    }
+    let elapsed = started.elapsed().as_secs_f64();
+
+    let errors: u64 = errors_by_kind.values().sum();
+    let requests = latencies.len() as u64 + errors;
+    Ok(BenchReport {
+        model_id: options.model_id.clone(),
+        concurrency: options.concurrency,
+        elapsed_secs: elapsed,
+        requests,
+        errors,
+        error_rate: if requests == 0 { 0.0 } else { errors as f64 / requests as f64 },
+        throughput_rps: latencies.len() as f64 / elapsed.max(f64::EPSILON),
+        latency_ms: LatencySummary::from_samples(&mut latencies),
+        errors_by_kind,
+    })
+}
+
+/// 读取并校验输入文件，返回序列化好的请求体
+async fn load_bodies(options: &BenchOptions) -> Result<Vec<String>> {
+    let path = match &options.input_file {
+        Some(path) => path,
+        None => {
+            let request = PredictRequest {
+                input: InputData::Text(DEFAULT_INPUT_TEXT.to_string()),
+                parameters: None,
+            };
+            return Ok(vec![serde_json::to_string(&request)?]);
+        }
+    };
+
+    let content = tokio::fs::read_to_string(path)
+        .await
+        .map_err(|e| UniModelError::validation(format!("Failed to read input file {}: {}", path.display(), e)))?;
+    let mut bodies = Vec::new();
+    for (line_number, line) in content.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let request: PredictRequest = serde_json::from_str(line).map_err(|e| {
+            UniModelError::validation(format!("Invalid request on line {} of {}: {}", line_number + 1, path.display(), e))
+        })?;
+        bodies.push(serde_json::to_string(&request)?);
+    }
+    if bodies.is_empty() {
+        return Err(UniModelError::validation(format!("Input file {} has no requests", path.display())));
+    }
+    Ok(bodies)
+}
@@ -0,0 +1,6 @@
+//! 应用层
+
+pub mod bench;
+pub mod dto;
+pub mod orchestration;
+pub mod services;
@@ -0,0 +1,13 @@
+//! 应用服务
+
+pub mod health_service;
+pub mod metrics_service;
+pub mod model_service;
+pub mod ocr_service;
+pub mod prediction_service;
+pub mod response_cache;
+
+pub use model_service::ModelService;
+pub use ocr_service::OcrService;
+pub use prediction_service::PredictionService;
+pub use response_cache::ResponseCache;
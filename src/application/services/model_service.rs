@@ -1,17 +1,21 @@
 //! 模型应用服务
 
 use std::sync::Arc;
-use tracing::{info, error};
+use tracing::info;
 
 use crate::common::types::*;
 use crate::common::error::*;
 use crate::domain::model::*;
-use crate::domain::service::ModelManager;
+use crate::domain::service::conversion::{ConversionJob, ConversionRequest};
+use crate::domain::service::{ConversionManager, ModelManager, Readiness};
+use crate::infrastructure::tokenizer::ModelTokenizer;
+use crate::plugins::manager::PluginInfo;
 
 /// 模型应用服务
 #[derive(Debug)]
 pub struct ModelService {
     model_manager: Arc<ModelManager>,
+    conversions: Option<Arc<ConversionManager>>,
 }
 
 impl ModelService {
@@ -19,41 +23,187 @@ impl ModelService {
     pub fn new(model_manager: Arc<ModelManager>) -> Self {
         Self {
             model_manager,
+            conversions: None,
         }
     }
 
+    /// 设置格式转换管理器，启用模型转换接口
+    pub fn with_conversion_manager(mut self, conversions: Arc<ConversionManager>) -> Self {
+        self.conversions = Some(conversions);
+        self
+    }
+
     /// 注册模型
     pub async fn register_model(
         &self,
         name: String,
         model_type: ModelType,
         config: ModelConfig,
+        tenant: Option<String>,
     ) -> Result<ModelId> {
-        info!("Registering model: {} (type: {:?})", name, model_type);
+        info!("Registering model: {} (type: {:?}, tenant: {:?})", name, model_type, tenant);
 
         // 验证模型配置
         self.validate_model_config(&config)?;
 
         // 委托给领域服务
-        self.model_manager.register_model(name, model_type, config).await
+        self.model_manager.register_model(name, model_type, config, tenant).await
+    }
+
+    /// 未指定设备时模型使用的设备类型
+    pub fn default_device_type(&self) -> DeviceType {
+        self.model_manager.default_device_type()
     }
 
     /// 注销模型
-    pub async fn unregister_model(&self, model_id: &ModelId) -> Result<()> {
+    pub async fn unregister_model(&self, model_id: &ModelId, tenant: Option<&str>) -> Result<()> {
         info!("Unregistering model: {}", model_id);
 
+        // 确认调用方可见该模型
+        self.get_model_info(model_id, tenant).await?;
+
         // 委托给领域服务
         self.model_manager.unregister_model(model_id).await
     }
 
-    /// 获取模型信息
-    pub async fn get_model_info(&self, model_id: &ModelId) -> Result<ModelInfo> {
-        self.model_manager.get_model_info(model_id).await
+    /// 获取模型信息（对调用方租户不可见的模型视为不存在）
+    pub async fn get_model_info(&self, model_id: &ModelId, tenant: Option<&str>) -> Result<ModelInfo> {
+        let info = self.model_manager.get_model_info(model_id).await?;
+        if !info.is_visible_to(tenant) {
+            return Err(UniModelError::model("Model not found"));
+        }
+        Ok(info)
+    }
+
+    /// 获取模型输入输出签名
+    pub async fn get_signature(&self, model_id: &ModelId, tenant: Option<&str>) -> Result<ModelSignature> {
+        self.get_model_info(model_id, tenant)
+            .await?
+            .signature
+            .ok_or_else(|| UniModelError::model(format!("Model '{}' has no known signature", model_id)))
+    }
+
+    /// 获取模型分词器
+    pub async fn get_tokenizer(&self, model_id: &ModelId, tenant: Option<&str>) -> Result<Arc<ModelTokenizer>> {
+        self.get_model_info(model_id, tenant)
+            .await?
+            .tokenizer
+            .ok_or_else(|| UniModelError::model(format!("Model '{}' has no tokenizer", model_id)))
+    }
+
+    /// 用模型分词器编码文本，长文本的分词在阻塞线程池中执行
+    pub async fn tokenize(
+        &self,
+        model_id: &ModelId,
+        tenant: Option<&str>,
+        text: String,
+        add_special_tokens: bool,
+    ) -> Result<Vec<u32>> {
+        let tokenizer = self.get_tokenizer(model_id, tenant).await?;
+        tokio::task::spawn_blocking(move || tokenizer.encode(&text, add_special_tokens))
+            .await
+            .map_err(|e| UniModelError::internal(format!("Tokenization task failed: {}", e)))?
+    }
+
+    /// 用模型分词器解码token序列
+    pub async fn detokenize(
+        &self,
+        model_id: &ModelId,
+        tenant: Option<&str>,
+        tokens: Vec<u32>,
+        skip_special_tokens: bool,
+    ) -> Result<String> {
+        let tokenizer = self.get_tokenizer(model_id, tenant).await?;
+        tokio::task::spawn_blocking(move || tokenizer.decode(&tokens, skip_special_tokens))
+            .await
+            .map_err(|e| UniModelError::internal(format!("Detokenization task failed: {}", e)))?
     }
 
-    /// 获取模型列表
-    pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
-        self.model_manager.list_models().await
+    /// 设置模型是否固定驻留
+    pub async fn set_pinned(&self, model_id: &ModelId, tenant: Option<&str>, pinned: bool) -> Result<ModelInfo> {
+        // 确认调用方可见该模型
+        self.get_model_info(model_id, tenant).await?;
+
+        self.model_manager.set_pinned(model_id, pinned).await
+    }
+
+    /// 在模型上加载LoRA适配器
+    pub async fn load_adapter(
+        &self,
+        model_id: &ModelId,
+        tenant: Option<&str>,
+        name: String,
+        path: String,
+    ) -> Result<ModelInfo> {
+        info!("Loading adapter '{}' on model {}", name, model_id);
+
+        // 确认调用方可见该模型
+        self.get_model_info(model_id, tenant).await?;
+
+        self.model_manager.load_adapter(model_id, name, path).await
+    }
+
+    /// 卸载模型上的LoRA适配器
+    pub async fn unload_adapter(&self, model_id: &ModelId, tenant: Option<&str>, name: &str) -> Result<ModelInfo> {
+        info!("Unloading adapter '{}' from model {}", name, model_id);
+
+        // 确认调用方可见该模型
+        self.get_model_info(model_id, tenant).await?;
+
+        self.model_manager.unload_adapter(model_id, name).await
+    }
+
+    /// 启动模型格式转换，产物注册为新模型
+    pub async fn convert_model(
+        &self,
+        model_id: &ModelId,
+        tenant: Option<&str>,
+        request: ConversionRequest,
+    ) -> Result<ConversionJob> {
+        // 确认调用方可见该模型
+        self.get_model_info(model_id, tenant).await?;
+
+        self.conversion_manager()?.start(model_id, request).await
+    }
+
+    /// 获取转换作业（对调用方租户不可见的作业视为不存在）
+    pub fn get_conversion(&self, job_id: &str, tenant: Option<&str>) -> Result<ConversionJob> {
+        self.conversion_manager()?
+            .job(job_id)
+            .filter(|job| tenant.is_none_or(|t| job.tenant.as_deref() == Some(t)))
+            .ok_or_else(|| UniModelError::model(format!("Conversion job '{}' not found", job_id)))
+    }
+
+    fn conversion_manager(&self) -> Result<&ConversionManager> {
+        self.conversions.as_deref()
+            .ok_or_else(|| UniModelError::config("Model conversion is not enabled"))
+    }
+
+    /// 获取调用方租户可见的模型列表
+    pub async fn list_models(&self, tenant: Option<&str>) -> Result<Vec<ModelInfo>> {
+        let models = self.model_manager.list_models().await?;
+        Ok(models.into_iter().filter(|m| m.is_visible_to(tenant)).collect())
+    }
+
+    /// 获取调用方租户可见的模型事件（最新的在前）
+    pub fn list_events(&self, model_id: Option<&str>, tenant: Option<&str>, limit: usize) -> Vec<ModelEvent> {
+        self.model_manager
+            .events()
+            .list(model_id, usize::MAX)
+            .into_iter()
+            .filter(|e| tenant.is_none_or(|t| e.tenant.as_deref() == Some(t)))
+            .take(limit)
+            .collect()
+    }
+
+    /// 所有插件的概况
+    pub fn list_plugins(&self) -> Vec<PluginInfo> {
+        self.model_manager.plugin_manager().plugins()
+    }
+
+    /// 就绪检查
+    pub async fn readiness(&self) -> Readiness {
+        self.model_manager.readiness().await
     }
 
     /// 验证模型配置
@@ -63,10 +213,7 @@ impl ModelService {
             return Err(UniModelError::validation("Model path cannot be empty"));
         }
 
-        // 检查后端
-        if config.backend.is_empty() {
-            return Err(UniModelError::validation("Backend cannot be empty"));
-        }
+        // 后端为空时由模型管理器按插件能力自动选择
 
         // 检查设备配置
         if config.device.device_ids.is_empty() {
@@ -0,0 +1,148 @@
+//! OCR应用服务
+//!
+//! 串联文本检测模型和文本识别模型：检测模型接收整张图像并返回区域JSON
+//! （见 [`parse_regions`]），每个区域裁剪为PNG后批量交给识别模型，
+//! 识别模型返回文本，或 `{"text": ..., "confidence": ...}`。
+
+use std::sync::Arc;
+
+use bytes::Bytes;
+use serde::Serialize;
+use tracing::debug;
+
+use crate::application::services::PredictionService;
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::service::batch_processor::PredictionResponse;
+use crate::infrastructure::postprocessing::{crop_regions, parse_regions, BoundingBox};
+
+/// 单次OCR最多识别的区域数
+pub const MAX_OCR_REGIONS: usize = 256;
+
+/// OCR请求选项
+#[derive(Debug, Clone)]
+pub struct OcrOptions {
+    pub detection_model: ModelId,
+    pub recognition_model: ModelId,
+    /// 低于该分数的检测区域被丢弃
+    pub min_detection_score: f32,
+}
+
+/// 识别出的文本区域
+#[derive(Debug, Clone, Serialize)]
+pub struct TextRegion {
+    #[serde(rename = "box")]
+    pub bbox: BoundingBox,
+    pub text: String,
+    /// 识别置信度，识别模型未提供时为检测分数
+    pub confidence: f32,
+    pub detection_score: f32,
+}
+
+/// OCR结果及各阶段的推理响应（用于用量计量）
+#[derive(Debug)]
+pub struct OcrOutcome {
+    /// 按阅读顺序（自上而下、自左而右）排列的文本区域
+    pub regions: Vec<TextRegion>,
+    pub responses: Vec<PredictionResponse>,
+}
+
+/// OCR应用服务
+#[derive(Debug)]
+pub struct OcrService {
+    prediction_service: Arc<PredictionService>,
+}
+
+impl OcrService {
+    /// 创建OCR服务
+    pub fn new(prediction_service: Arc<PredictionService>) -> Self {
+        Self { prediction_service }
+    }
+
+    /// 检测并识别图像中的文本
+    pub async fn recognize(
+        &self,
+        image: Bytes,
+        options: &OcrOptions,
+        parameters: PredictionParameters,
+        tenant: Option<&str>,
+    ) -> Result<OcrOutcome> {
+        let detection = self.prediction_service.predict(
+            options.detection_model.clone(),
+            InputData::Binary(image.clone()),
+            parameters.clone(),
+            tenant,
+        ).await?;
+
+        let mut regions: Vec<_> = parse_regions(&detection.output)?
+            .into_iter()
+            .filter(|region| region.score >= options.min_detection_score && region.bbox.area() > 0.0)
+            .collect();
+        if regions.len() > MAX_OCR_REGIONS {
+            return Err(UniModelError::validation(format!(
+                "Detected {} text regions, more than the limit of {}",
+                regions.len(), MAX_OCR_REGIONS
+            )));
+        }
+        regions.sort_by(|a, b| {
+            (a.bbox.y_min, a.bbox.x_min)
+                .partial_cmp(&(b.bbox.y_min, b.bbox.x_min))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        debug!("Detected {} text regions with model {}", regions.len(), options.detection_model);
+
+        let mut responses = vec![detection];
+        if regions.is_empty() {
+            return Ok(OcrOutcome {
+                regions: Vec::new(),
+                responses,
+            });
+        }
+
+        let boxes: Vec<BoundingBox> = regions.iter().map(|region| region.bbox).collect();
+        let crops = tokio::task::spawn_blocking(move || crop_regions(&image, &boxes))
+            .await
+            .map_err(|e| UniModelError::internal(format!("Cropping task failed: {}", e)))??;
+
+        let recognitions = self.prediction_service.batch_predict(
+            options.recognition_model.clone(),
+            crops.into_iter().map(|crop| InputData::Binary(crop.into())).collect(),
+            parameters,
+            tenant,
+        ).await?;
+
+        let text_regions = regions.into_iter()
+            .zip(&recognitions)
+            .map(|(region, recognition)| {
+                let (text, confidence) = recognized_text(&recognition.output)?;
+                Ok(TextRegion {
+                    bbox: region.bbox,
+                    text,
+                    confidence: confidence.unwrap_or(region.score),
+                    detection_score: region.score,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        responses.extend(recognitions);
+        Ok(OcrOutcome {
+            regions: text_regions,
+            responses,
+        })
+    }
+}
+
+/// 解析识别模型输出的文本和可选的置信度
+fn recognized_text(output: &OutputData) -> Result<(String, Option<f32>)> {
+    match output {
+        OutputData::Text(text) => Ok((text.clone(), None)),
+        OutputData::Json(json) => {
+            let text = json.get("text")
+                .and_then(|text| text.as_str())
+                .ok_or_else(|| UniModelError::model("Recognition output has no 'text' field"))?;
+            let confidence = json.get("confidence").and_then(|c| c.as_f64()).map(|c| c as f32);
+            Ok((text.to_string(), confidence))
+        }
+        _ => Err(UniModelError::model("Recognition model must return text")),
+    }
+}
@@ -1,18 +1,54 @@
 //! 推理应用服务
 
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
-use tracing::{info, error};
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tokio::task::JoinHandle;
+use tracing::{debug, error, info, warn};
 
 use crate::common::types::*;
 use crate::common::error::*;
+use crate::domain::model::{ModelInfo, ModelStatus, WasmStage};
 use crate::domain::service::{ModelManager, BatchProcessor};
-use crate::domain::service::batch_processor::PredictionResponse;
+use crate::domain::service::batch_processor::{PredictionResponse, DEFAULT_MAX_NEW_TOKENS};
+use crate::domain::service::generation::{
+    MAX_COMPLETIONS, MAX_LOGIT_BIAS, MAX_LOGIT_BIAS_ENTRIES, MAX_STOP_SEQUENCES, MAX_TOP_LOGPROBS,
+};
+use crate::domain::service::grammar::Grammar;
+use crate::domain::service::json_schema::{schema_to_grammar, validate_json};
+use crate::domain::service::session::MAX_SESSION_ID_LEN;
+use crate::application::services::response_cache::ResponseCache;
+use crate::infrastructure::postprocessing::postprocess;
+use crate::infrastructure::preprocessing::{preprocess_binary, split_frame_outputs};
+use crate::plugins::builtin::wasm_plugin::{WasmRuntime, POSTPROCESS_EXPORT, PREPROCESS_EXPORT};
+
+/// 预处理结果的默认张量名（模型签名没有唯一输入时使用）
+const DEFAULT_PREPROCESSED_INPUT: &str = "input";
+
+/// 没有约束解码的后端生成的结构化输出不符合schema时的最大重试次数
+const MAX_STRUCTURED_OUTPUT_RETRIES: u32 = 2;
+
+/// 丢弃时中止尚未完成的任务
+struct AbortOnDrop<T>(Vec<JoinHandle<T>>);
+
+impl<T> Drop for AbortOnDrop<T> {
+    fn drop(&mut self) {
+        for task in &self.0 {
+            task.abort();
+        }
+    }
+}
 
 /// 推理应用服务
 #[derive(Debug)]
 pub struct PredictionService {
     model_manager: Arc<ModelManager>,
     batch_processor: Arc<BatchProcessor>,
+    response_cache: Option<Arc<ResponseCache>>,
+    wasm_runtime: Option<Arc<WasmRuntime>>,
 }
 
 impl PredictionService {
@@ -24,30 +60,77 @@ impl PredictionService {
         Self {
             model_manager,
             batch_processor,
+            response_cache: None,
+            wasm_runtime: None,
         }
     }
 
+    /// 启用响应缓存
+    pub fn with_response_cache(mut self, cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(cache);
+        self
+    }
+
+    /// 启用WASM预处理和后处理阶段
+    pub fn with_wasm_runtime(mut self, runtime: Arc<WasmRuntime>) -> Self {
+        self.wasm_runtime = Some(runtime);
+        self
+    }
+
     /// 执行推理
     pub async fn predict(
         &self,
         model_id: ModelId,
         input: InputData,
-        parameters: PredictionParameters,
+        mut parameters: PredictionParameters,
+        tenant: Option<&str>,
     ) -> Result<PredictionResponse> {
         info!("Processing prediction request for model: {}", model_id);
 
         // 验证模型是否存在且可用
-        self.validate_model_availability(&model_id).await?;
+        let model_info = self.validate_model_availability(&model_id).await?;
+        self.batch_processor.set_concurrency_limit(&model_id, model_info.config.max_concurrent_batches);
+        if !model_info.adapters.is_empty() {
+            let mixed = self.model_manager.supports_mixed_adapters(&model_id).await;
+            self.batch_processor.set_mixed_adapters(&model_id, mixed);
+        }
 
         // 验证输入数据
         self.validate_input_data(&input)?;
+        let (input, frame_timestamps) = self.preprocess(&model_info, input, &parameters).await?;
+        self.validate_signature(&model_info, &input)?;
+        self.validate_parameters(&model_info, &parameters)?;
+        let prompt_tokens = count_prompt_tokens(&model_info, &input);
+        fit_context_window(&model_info, prompt_tokens, &mut parameters)?;
+
+        // 确定性请求优先查询响应缓存
+        let cache = self.response_cache.as_ref()
+            .filter(|cache| parameters.is_deterministic() && cache.is_enabled_for(&model_id));
+        let cache_key = cache.map(|_| request_fingerprint(&model_id, &input, &parameters));
+        if let (Some(cache), Some(key)) = (cache, cache_key.as_deref()) {
+            if let Some(response) = cache.get(&model_id, key) {
+                debug!("Response cache hit for model {}", model_id);
+                return Ok(cached_response(response));
+            }
+        }
 
         // 通过批处理器执行推理
-        let response = self.batch_processor.submit_request(
-            model_id.clone(),
-            input,
-            parameters,
-        ).await?;
+        let mut response = match parameters.response_format.as_ref().and_then(ResponseFormat::schema) {
+            Some(schema) => self.submit_structured(&model_id, input, &parameters, &schema, tenant).await?,
+            None => self.batch_processor.submit_request(
+                model_id.clone(),
+                input,
+                parameters.clone(),
+                tenant,
+            ).await?,
+        };
+        account_tokens(&model_info, prompt_tokens, &mut response);
+        response.output = finish_output(&model_info, response.output, frame_timestamps.as_deref(), &parameters)?;
+        response.output = wasm_postprocess(self.wasm_runtime.as_ref(), &model_info, response.output, &parameters).await?;
+
+        if let (Some(cache), Some(key)) = (cache, cache_key) {
+            cache.put(&model_id, key, response.clone());
+        }
 
         // 更新模型性能统计
         self.model_manager.update_model_performance(
@@ -62,37 +145,117 @@ impl PredictionService {
         Ok(response)
     }
 
+    /// 生成符合JSON Schema的输出
+    ///
+    /// 后端支持约束解码时把schema编译为语法随请求下发；否则校验生成结果，
+    /// 不符合时最多重试 [`MAX_STRUCTURED_OUTPUT_RETRIES`] 次。
+    async fn submit_structured(
+        &self,
+        model_id: &ModelId,
+        input: InputData,
+        parameters: &PredictionParameters,
+        schema: &serde_json::Value,
+        tenant: Option<&str>,
+    ) -> Result<PredictionResponse> {
+        let mut parameters = parameters.clone();
+        let constrained = self.constrain_output(model_id, &mut parameters, schema)?;
+
+        let mut attempt = 0;
+        loop {
+            let response = self.batch_processor
+                .submit_request(model_id.clone(), input.clone(), parameters.clone(), tenant)
+                .await?;
+            match check_structured_output(&response.output, schema) {
+                Ok(()) => return Ok(response),
+                Err(e) if !constrained && attempt < MAX_STRUCTURED_OUTPUT_RETRIES => {
+                    attempt += 1;
+                    warn!("Structured output from model {} rejected (attempt {}): {}", model_id, attempt, e);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 后端支持约束解码时把schema编译为语法，返回是否已约束
+    fn constrain_output(
+        &self,
+        model_id: &ModelId,
+        parameters: &mut PredictionParameters,
+        schema: &serde_json::Value,
+    ) -> Result<bool> {
+        if !self.batch_processor.supports_grammar(model_id) {
+            return Ok(false);
+        }
+        parameters.grammar = Some(schema_to_grammar(schema)?);
+        Ok(true)
+    }
+
     /// 批量推理
     pub async fn batch_predict(
         &self,
         model_id: ModelId,
         inputs: Vec<InputData>,
         parameters: PredictionParameters,
+        tenant: Option<&str>,
     ) -> Result<Vec<PredictionResponse>> {
         info!("Processing batch prediction request for model: {} with {} inputs",
               model_id, inputs.len());
 
         // 验证模型是否存在且可用
-        self.validate_model_availability(&model_id).await?;
+        let model_info = self.validate_model_availability(&model_id).await?;
+        self.batch_processor.set_concurrency_limit(&model_id, model_info.config.max_concurrent_batches);
+        if !model_info.adapters.is_empty() {
+            let mixed = self.model_manager.supports_mixed_adapters(&model_id).await;
+            self.batch_processor.set_mixed_adapters(&model_id, mixed);
+        }
 
         // 验证输入数据
-        for input in &inputs {
-            self.validate_input_data(input)?;
+        self.validate_parameters(&model_info, &parameters)?;
+        let mut prepared = Vec::with_capacity(inputs.len());
+        for input in inputs {
+            self.validate_input_data(&input)?;
+            let (input, frame_timestamps) = self.preprocess(&model_info, input, &parameters).await?;
+            self.validate_signature(&model_info, &input)?;
+            prepared.push((input, frame_timestamps));
         }
 
-        // 并行处理多个推理请求
-        let mut tasks = Vec::new();
+        // 批量推理不重试，不符合schema的输出直接报错
+        let mut parameters = parameters;
+        let schema = parameters.response_format.as_ref().and_then(ResponseFormat::schema);
+        if let Some(schema) = &schema {
+            self.constrain_output(&model_id, &mut parameters, schema)?;
+        }
+
+        // 并行处理多个推理请求；调用方断开或某个输入失败时中止其余请求
+        let model_info = Arc::new(model_info);
+        let mut tasks = AbortOnDrop(Vec::with_capacity(prepared.len()));
+
+        for (input, frame_timestamps) in prepared {
+            let prompt_tokens = count_prompt_tokens(&model_info, &input);
+            let mut parameters = parameters.clone();
+            fit_context_window(&model_info, prompt_tokens, &mut parameters)?;
 
-        for input in inputs {
             let batch_processor = Arc::clone(&self.batch_processor);
             let model_id = model_id.clone();
-            let parameters = parameters.clone();
+            let tenant = tenant.map(str::to_string);
+            let model_info = Arc::clone(&model_info);
+            let schema = schema.clone();
+            let wasm_runtime = self.wasm_runtime.clone();
 
             let task = tokio::spawn(async move {
-                batch_processor.submit_request(model_id, input, parameters).await
+                let mut response = batch_processor
+                    .submit_request(model_id, input, parameters.clone(), tenant.as_deref())
+                    .await?;
+                if let Some(schema) = &schema {
+                    check_structured_output(&response.output, schema)?;
+                }
+                account_tokens(&model_info, prompt_tokens, &mut response);
+                response.output = finish_output(&model_info, response.output, frame_timestamps.as_deref(), &parameters)?;
+                response.output = wasm_postprocess(wasm_runtime.as_ref(), &model_info, response.output, &parameters).await?;
+                Ok::<_, UniModelError>(response)
             });
 
-            tasks.push(task);
+            tasks.0.push(task);
         }
 
         // 等待所有任务完成
@@ -100,7 +263,7 @@ impl PredictionService {
         let mut total_latency = 0u64;
         let mut success_count = 0;
 
-        for task in tasks {
+        for task in tasks.0.iter_mut() {
             match task.await {
                 Ok(Ok(response)) => {
                     total_latency += response.metrics.total_latency_ms;
@@ -119,7 +282,7 @@ impl PredictionService {
         }
 
         // 更新模型性能统计
-        let avg_latency = if success_count > 0 { total_latency / success_count } else { 0 };
+        let avg_latency = total_latency.checked_div(success_count).unwrap_or(0);
         self.model_manager.update_model_performance(
             &model_id,
             avg_latency,
@@ -133,11 +296,11 @@ impl PredictionService {
     }
 
     /// 验证模型可用性
-    async fn validate_model_availability(&self, model_id: &ModelId) -> Result<()> {
+    async fn validate_model_availability(&self, model_id: &ModelId) -> Result<ModelInfo> {
         let model_info = self.model_manager.get_model_info(model_id).await?;
 
         match model_info.status {
-            ModelStatus::Ready | ModelStatus::Running => Ok(()),
+            ModelStatus::Ready | ModelStatus::Running => Ok(model_info),
             ModelStatus::Initializing | ModelStatus::Loading => {
                 Err(UniModelError::model("Model is not ready yet"))
             }
@@ -150,6 +313,161 @@ impl PredictionService {
         }
     }
 
+    /// 按模型元数据验证推理参数
+    fn validate_parameters(&self, model_info: &ModelInfo, parameters: &PredictionParameters) -> Result<()> {
+        if parameters.stop.len() > MAX_STOP_SEQUENCES {
+            return Err(UniModelError::validation(format!(
+                "At most {} stop sequences are allowed",
+                MAX_STOP_SEQUENCES
+            )));
+        }
+        if parameters.stop.iter().any(String::is_empty) {
+            return Err(UniModelError::validation("Stop sequences cannot be empty"));
+        }
+        if parameters.logit_bias.len() > MAX_LOGIT_BIAS_ENTRIES {
+            return Err(UniModelError::validation(format!(
+                "At most {} logit_bias entries are allowed",
+                MAX_LOGIT_BIAS_ENTRIES
+            )));
+        }
+        if let Some(bias) = parameters.logit_bias.values().find(|b| !(-MAX_LOGIT_BIAS..=MAX_LOGIT_BIAS).contains(*b)) {
+            return Err(UniModelError::validation(format!(
+                "logit_bias values must be between -{} and {}, got {}",
+                MAX_LOGIT_BIAS, MAX_LOGIT_BIAS, bias
+            )));
+        }
+        if let Some(top_logprobs) = parameters.top_logprobs {
+            if !parameters.logprobs {
+                return Err(UniModelError::validation("top_logprobs requires logprobs to be enabled"));
+            }
+            if top_logprobs > MAX_TOP_LOGPROBS {
+                return Err(UniModelError::validation(format!(
+                    "top_logprobs must be at most {}",
+                    MAX_TOP_LOGPROBS
+                )));
+            }
+        }
+        for (name, value) in [("n", parameters.n), ("num_beams", parameters.num_beams)] {
+            if let Some(value) = value {
+                if value == 0 || value > MAX_COMPLETIONS {
+                    return Err(UniModelError::validation(format!(
+                        "{} must be between 1 and {}",
+                        name, MAX_COMPLETIONS
+                    )));
+                }
+            }
+        }
+        if let Some(num_beams) = parameters.num_beams.filter(|&b| b > 1) {
+            if parameters.completions() > num_beams {
+                return Err(UniModelError::validation(format!(
+                    "n ({}) cannot exceed num_beams ({})",
+                    parameters.completions(), num_beams
+                )));
+            }
+        }
+        if parameters.compute_units() > 1 && parameters.stream == Some(true) {
+            return Err(UniModelError::validation("Streaming does not support n or num_beams greater than 1"));
+        }
+        if let Some(grammar) = &parameters.grammar {
+            Grammar::parse(grammar)?;
+        }
+        if let Some(session_id) = &parameters.session_id {
+            if session_id.is_empty() || session_id.len() > MAX_SESSION_ID_LEN {
+                return Err(UniModelError::validation(format!(
+                    "session_id must be between 1 and {} bytes",
+                    MAX_SESSION_ID_LEN
+                )));
+            }
+        }
+        if let Some(adapter) = &parameters.adapter {
+            if model_info.adapter(adapter).is_none() {
+                return Err(UniModelError::validation(format!(
+                    "Adapter '{}' is not loaded on model '{}'",
+                    adapter, model_info.name
+                )));
+            }
+        }
+        if let Some(schema) = parameters.response_format.as_ref().and_then(ResponseFormat::schema) {
+            if parameters.grammar.is_some() {
+                return Err(UniModelError::validation("grammar cannot be combined with a JSON response_format"));
+            }
+            if parameters.compute_units() > 1 {
+                return Err(UniModelError::validation("A JSON response_format does not support n or num_beams greater than 1"));
+            }
+            Grammar::parse(&schema_to_grammar(&schema)?)?;
+        }
+        if let Some(tokenizer) = &model_info.tokenizer {
+            let vocab_size = tokenizer.vocab_size();
+            if let Some(token) = parameters.logit_bias.keys().find(|&&t| t as usize >= vocab_size) {
+                return Err(UniModelError::validation(format!(
+                    "logit_bias token {} is outside the vocabulary of {} tokens",
+                    token, vocab_size
+                )));
+            }
+        }
+        let segmentation = model_info.config.postprocessing.as_ref().and_then(|p| p.segmentation.as_ref());
+        if parameters.mask_format.is_some() && segmentation.is_none() {
+            return Err(UniModelError::validation(
+                "mask_format requires a model with segmentation post-processing",
+            ));
+        }
+        Ok(())
+    }
+
+    /// 按模型配置在服务端预处理原始输入，解码等CPU密集操作在阻塞线程池中执行
+    ///
+    /// 配置了WASM预处理时先执行该模块。视频输入同时返回每帧的时间戳，推理完成后用于按帧拆分输出。
+    async fn preprocess(
+        &self,
+        model_info: &ModelInfo,
+        input: InputData,
+        parameters: &PredictionParameters,
+    ) -> Result<(InputData, Option<Vec<f64>>)> {
+        let input = match model_info.config.preprocessing.as_ref().and_then(|p| p.wasm.as_ref()) {
+            Some(stage) => run_wasm_stage(self.wasm_runtime.as_ref(), stage, PREPROCESS_EXPORT, input, parameters).await?,
+            None => input,
+        };
+        let config = match &model_info.config.preprocessing {
+            Some(config) if config.handles_binary() => config.clone(),
+            _ => return Ok((input, None)),
+        };
+        let bytes = match input {
+            InputData::Binary(bytes) => bytes,
+            other => return Ok((other, None)),
+        };
+
+        let preprocessed = tokio::task::spawn_blocking(move || preprocess_binary(bytes, &config))
+            .await
+            .map_err(|e| UniModelError::internal(format!("Preprocessing task failed: {}", e)))??;
+        let name = preprocessed_input_name(model_info, preprocessed.input_name.as_deref());
+        Ok((
+            InputData::Tensors(HashMap::from([(name, preprocessed.tensor)])),
+            preprocessed.frame_timestamps,
+        ))
+    }
+
+    /// 按模型的输入Schema和签名校验JSON及张量输入
+    fn validate_signature(&self, model_info: &ModelInfo, input: &InputData) -> Result<()> {
+        let json = match input {
+            InputData::Json(json) => json,
+            InputData::Tensors(tensors) => {
+                return match &model_info.signature {
+                    Some(signature) => signature.validate_tensors(tensors),
+                    None => Ok(()),
+                };
+            }
+            _ => return Ok(()),
+        };
+        // 用户提供的Schema优先；同时声明签名时两者都需满足
+        if let Some(schema) = &model_info.config.input_schema {
+            schema.validate(json)?;
+        }
+        if let Some(signature) = &model_info.signature {
+            signature.validate_json_input(json)?;
+        }
+        Ok(())
+    }
+
     /// 验证输入数据
     fn validate_input_data(&self, input: &InputData) -> Result<()> {
         match input {
@@ -185,8 +503,178 @@ impl PredictionService {
                     self.validate_input_data(value)?;
                 }
             }
+            InputData::Tensors(tensors) => {
+                if tensors.is_empty() {
+                    return Err(UniModelError::validation("Tensor input cannot be empty"));
+                }
+                let total_bytes: usize = tensors.values().map(|t| t.data.len()).sum();
+                if total_bytes > 100_000_000 { // 100MB limit
+                    return Err(UniModelError::validation("Tensor input too large"));
+                }
+                for tensor in tensors.values() {
+                    tensor.validate()?;
+                }
+            }
         }
 
         Ok(())
     }
-}
\ No newline at end of file
+}
+
+/// 用模型分词器统计文本输入的token数，分词失败只影响计量
+fn count_prompt_tokens(model_info: &ModelInfo, input: &InputData) -> Option<u32> {
+    let (tokenizer, text) = match (&model_info.tokenizer, input) {
+        (Some(tokenizer), InputData::Text(text)) => (tokenizer, text),
+        _ => return None,
+    };
+    match tokenizer.count(text, true) {
+        Ok(count) => Some(count as u32),
+        Err(e) => {
+            warn!("Failed to count prompt tokens for model {}: {}", model_info.id, e);
+            None
+        }
+    }
+}
+
+/// 按模型上下文窗口限制生成长度
+///
+/// 提示占满上下文窗口时拒绝请求；`max_tokens` 截断到窗口的剩余空间。未指定时沿用默认生成长度，
+/// 只有剩余空间小于默认值时才设为剩余空间。无法统计提示token数时只按窗口大小截断。
+pub fn fit_context_window(
+    model_info: &ModelInfo,
+    prompt_tokens: Option<u32>,
+    parameters: &mut PredictionParameters,
+) -> Result<()> {
+    let context_length = match model_info.metadata.context_length() {
+        Some(context_length) => context_length,
+        None => return Ok(()),
+    };
+    let prompt_tokens = prompt_tokens.unwrap_or(0) as u64;
+    if prompt_tokens >= context_length {
+        return Err(UniModelError::validation(format!(
+            "Prompt has {} tokens, which leaves no room in the model's context window of {} tokens",
+            prompt_tokens, context_length
+        )));
+    }
+
+    let available = (context_length - prompt_tokens).min(u32::MAX as u64) as u32;
+    match parameters.max_tokens {
+        Some(max_tokens) if max_tokens > available => {
+            debug!(
+                "Clamping max_tokens from {} to {} for model {} ({} prompt tokens, context window {})",
+                max_tokens, available, model_info.id, prompt_tokens, context_length
+            );
+            parameters.max_tokens = Some(available);
+        }
+        None if available < DEFAULT_MAX_NEW_TOKENS => parameters.max_tokens = Some(available),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// 补全响应中的token计数和吞吐量，后端已报告的值优先
+fn account_tokens(model_info: &ModelInfo, prompt_tokens: Option<u32>, response: &mut PredictionResponse) {
+    let metrics = &mut response.metrics;
+    if metrics.tokens_input.is_none() {
+        metrics.tokens_input = prompt_tokens;
+    }
+    if metrics.tokens_generated.is_none() {
+        if let (Some(tokenizer), OutputData::Text(text)) = (&model_info.tokenizer, &response.output) {
+            match tokenizer.count(text, false) {
+                Ok(count) => metrics.tokens_generated = Some(count as u32),
+                Err(e) => warn!("Failed to count generated tokens for model {}: {}", model_info.id, e),
+            }
+        }
+    }
+    if metrics.throughput_tokens_per_sec.is_none() && metrics.inference_latency_ms > 0 {
+        metrics.throughput_tokens_per_sec = metrics.tokens_generated
+            .map(|tokens| tokens as f64 * 1000.0 / metrics.inference_latency_ms as f64);
+    }
+}
+
+/// 按帧拆分视频输出并执行模型配置的后处理
+fn finish_output(
+    model_info: &ModelInfo,
+    output: OutputData,
+    frame_timestamps: Option<&[f64]>,
+    parameters: &PredictionParameters,
+) -> Result<OutputData> {
+    let output = match frame_timestamps {
+        Some(timestamps) => split_frame_outputs(output, timestamps)?,
+        None => output,
+    };
+    match &model_info.config.postprocessing {
+        Some(config) => postprocess(output, config, model_info.labels.as_deref(), parameters),
+        None => Ok(output),
+    }
+}
+
+/// 解析文本输出并按schema校验
+fn check_structured_output(output: &OutputData, schema: &serde_json::Value) -> Result<()> {
+    let value: serde_json::Value = match output {
+        OutputData::Text(text) => serde_json::from_str(text)
+            .map_err(|e| UniModelError::model(format!("Output is not valid JSON: {}", e)))?,
+        OutputData::Json(value) => value.clone(),
+        _ => return Err(UniModelError::model("Structured output requires a text or JSON result")),
+    };
+    validate_json(&value, schema)
+}
+
+/// 执行模型配置的WASM后处理阶段
+async fn wasm_postprocess(
+    runtime: Option<&Arc<WasmRuntime>>,
+    model_info: &ModelInfo,
+    output: OutputData,
+    parameters: &PredictionParameters,
+) -> Result<OutputData> {
+    match model_info.config.postprocessing.as_ref().and_then(|p| p.wasm.as_ref()) {
+        Some(stage) => run_wasm_stage(runtime, stage, POSTPROCESS_EXPORT, output, parameters).await,
+        None => Ok(output),
+    }
+}
+
+/// 在阻塞线程池中执行WASM处理阶段
+async fn run_wasm_stage<T>(
+    runtime: Option<&Arc<WasmRuntime>>,
+    stage: &WasmStage,
+    export: &'static str,
+    data: T,
+    parameters: &PredictionParameters,
+) -> Result<T>
+where
+    T: Serialize + DeserializeOwned + Send + 'static,
+{
+    let runtime = Arc::clone(runtime.ok_or_else(|| {
+        UniModelError::plugin_unavailable("Model uses a WASM stage but WASM plugins are not enabled")
+    })?);
+    let module = PathBuf::from(&stage.module);
+    let parameters = parameters.clone();
+    tokio::task::spawn_blocking(move || runtime.run_stage(&module, export, &data, &parameters))
+        .await
+        .map_err(|e| UniModelError::internal(format!("WASM task failed: {}", e)))?
+}
+
+/// 预处理输出的张量名：配置指定的名称，否则为签名中唯一的输入名
+fn preprocessed_input_name(model_info: &ModelInfo, configured: Option<&str>) -> String {
+    if let Some(name) = configured {
+        return name.to_string();
+    }
+    match model_info.signature.as_ref().map(|s| s.inputs.as_slice()) {
+        Some([spec]) => spec.name.clone(),
+        _ => DEFAULT_PREPROCESSED_INPUT.to_string(),
+    }
+}
+
+/// 将缓存的响应转换为本次请求的响应（不计推理耗时）
+fn cached_response(mut response: PredictionResponse) -> PredictionResponse {
+    response.request_id = new_request_id();
+    response.metrics.request_id = response.request_id.clone();
+    response.metrics.total_latency_ms = 0;
+    response.metrics.inference_latency_ms = 0;
+    response.metrics.queue_wait_ms = 0;
+    response.metrics.start_time = chrono::Utc::now();
+    response.metrics.end_time = response.metrics.start_time;
+    response.metadata.custom_metadata.insert("cache_hit".to_string(), serde_json::json!(true));
+    response.timestamp = chrono::Utc::now();
+    response
+}
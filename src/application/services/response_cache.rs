@@ -0,0 +1,104 @@
+//! 推理响应缓存
+//!
+//! 以 (模型ID, 规范化输入, 参数) 的指纹为键缓存确定性请求的响应，
+//! 每个模型拥有独立的LRU缓存，容量与有效期可按模型配置。
+
+use std::num::NonZeroUsize;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use lru::LruCache;
+use parking_lot::Mutex;
+use tracing::debug;
+
+use crate::common::types::*;
+use crate::domain::service::batch_processor::PredictionResponse;
+use crate::infrastructure::configuration::ResponseCacheConfig;
+
+/// 缓存条目
+#[derive(Debug)]
+struct CachedResponse {
+    response:   PredictionResponse,
+    expires_at: Instant,
+}
+
+/// 单个模型的缓存
+#[derive(Debug)]
+struct ModelCache {
+    entries: Mutex<LruCache<String, CachedResponse>>,
+    ttl:     Duration,
+}
+
+/// 响应缓存
+#[derive(Debug)]
+pub struct ResponseCache {
+    config: ResponseCacheConfig,
+    models: DashMap<ModelId, ModelCache>,
+}
+
+impl ResponseCache {
+    /// 创建响应缓存
+    pub fn new(config: &ResponseCacheConfig) -> Self {
+        Self {
+            config: config.clone(),
+            models: DashMap::new(),
+        }
+    }
+
+    /// 模型是否启用缓存
+    pub fn is_enabled_for(&self, model_id: &str) -> bool {
+        self.config.models
+            .get(model_id)
+            .and_then(|o| o.enabled)
+            .unwrap_or(self.config.enabled)
+    }
+
+    /// 查询缓存，过期条目会被移除
+    pub fn get(&self, model_id: &ModelId, key: &str) -> Option<PredictionResponse> {
+        let cache = self.models.get(model_id)?;
+        let mut entries = cache.entries.lock();
+
+        match entries.get(key) {
+            Some(cached) if cached.expires_at > Instant::now() => Some(cached.response.clone()),
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// 写入缓存
+    pub fn put(&self, model_id: &ModelId, key: String, response: PredictionResponse) {
+        let cache = self.models
+            .entry(model_id.clone())
+            .or_insert_with(|| self.model_cache(model_id));
+
+        let expires_at = Instant::now() + cache.ttl;
+        cache.entries.lock().put(key, CachedResponse { response, expires_at });
+    }
+
+    /// 清除模型的全部缓存（模型卸载或更新时调用）
+    pub fn invalidate_model(&self, model_id: &ModelId) {
+        if self.models.remove(model_id).is_some() {
+            debug!("Response cache invalidated for model {}", model_id);
+        }
+    }
+
+    fn model_cache(&self, model_id: &str) -> ModelCache {
+        let model_override = self.config.models.get(model_id);
+        let max_entries = model_override
+            .and_then(|o| o.max_entries)
+            .unwrap_or(self.config.max_entries);
+        let ttl_secs = model_override
+            .and_then(|o| o.ttl_secs)
+            .unwrap_or(self.config.ttl_secs);
+
+        ModelCache {
+            entries: Mutex::new(LruCache::new(
+                NonZeroUsize::new(max_entries.max(1)).expect("max_entries is at least 1"),
+            )),
+            ttl: Duration::from_secs(ttl_secs),
+        }
+    }
+}
@@ -1,8 +1,38 @@
 //! 统一错误处理模块
 
-use std::fmt;
+use serde::Serialize;
+use std::time::Duration;
 use thiserror::Error;
 
+/// 过载提示，随限流/排队拒绝一起返回给客户端用于退避
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct OverloadHint {
+    /// 建议重试等待时间（毫秒）
+    pub retry_after_ms: u64,
+    /// 当前队列深度
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub queue_depth: Option<usize>,
+    /// 预计排队等待时间（毫秒）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub estimated_wait_ms: Option<u64>,
+}
+
+impl OverloadHint {
+    /// 仅包含重试时间的提示
+    pub fn retry_after(retry_after: Duration) -> Self {
+        Self {
+            retry_after_ms: retry_after.as_millis() as u64,
+            queue_depth: None,
+            estimated_wait_ms: None,
+        }
+    }
+
+    /// `Retry-After` 头的秒数（向上取整，至少1秒）
+    pub fn retry_after_secs(&self) -> u64 {
+        self.retry_after_ms.div_ceil(1000).max(1)
+    }
+}
+
 /// UniModel统一错误类型
 #[derive(Error, Debug)]
 pub enum UniModelError {
@@ -15,6 +45,12 @@ pub enum UniModelError {
     #[error("Plugin error: {0}")]
     Plugin(String),
 
+    #[error("Plugin unavailable: {0}")]
+    PluginUnavailable(String),
+
+    #[error("Out of memory: {0}")]
+    OutOfMemory(String),
+
     #[error("Batch processing error: {0}")]
     BatchProcessing(String),
 
@@ -36,6 +72,15 @@ pub enum UniModelError {
     #[error("Validation error: {0}")]
     Validation(String),
 
+    #[error("Rate limit exceeded: {message}")]
+    RateLimited { message: String, hint: OverloadHint },
+
+    #[error("Service overloaded: {message}")]
+    Overloaded { message: String, hint: OverloadHint },
+
+    #[error("Quota exceeded: {0}")]
+    QuotaExceeded(String),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -50,7 +95,7 @@ pub enum UniModelError {
 }
 
 /// UniModel结果类型别名
-pub type Result<T> = std::result::Result<T, UniModelError>;
+pub type Result<T, E = UniModelError> = std::result::Result<T, E>;
 
 impl UniModelError {
     /// 创建配置错误
@@ -68,6 +113,41 @@ impl UniModelError {
         UniModelError::Plugin(msg.into())
     }
 
+    /// 创建插件暂不可用错误（插件重启中等）
+    pub fn plugin_unavailable<T: Into<String>>(msg: T) -> Self {
+        UniModelError::PluginUnavailable(msg.into())
+    }
+
+    /// 创建显存/内存不足错误
+    pub fn out_of_memory<T: Into<String>>(msg: T) -> Self {
+        UniModelError::OutOfMemory(msg.into())
+    }
+
+    /// 创建调度错误
+    pub fn scheduling<T: Into<String>>(msg: T) -> Self {
+        UniModelError::Scheduling(msg.into())
+    }
+
+    /// 创建资源错误
+    pub fn resource<T: Into<String>>(msg: T) -> Self {
+        UniModelError::Resource(msg.into())
+    }
+
+    /// 创建认证错误
+    pub fn authentication<T: Into<String>>(msg: T) -> Self {
+        UniModelError::Authentication(msg.into())
+    }
+
+    /// 创建授权错误
+    pub fn authorization<T: Into<String>>(msg: T) -> Self {
+        UniModelError::Authorization(msg.into())
+    }
+
+    /// 创建验证错误
+    pub fn validation<T: Into<String>>(msg: T) -> Self {
+        UniModelError::Validation(msg.into())
+    }
+
     /// 创建内部错误
     pub fn internal<T: Into<String>>(msg: T) -> Self {
         UniModelError::Internal(msg.into())
@@ -79,6 +159,8 @@ impl UniModelError {
             UniModelError::Config(_) => "CONFIG_ERROR",
             UniModelError::Model(_) => "MODEL_ERROR",
             UniModelError::Plugin(_) => "PLUGIN_ERROR",
+            UniModelError::PluginUnavailable(_) => "PLUGIN_UNAVAILABLE",
+            UniModelError::OutOfMemory(_) => "OUT_OF_MEMORY",
             UniModelError::BatchProcessing(_) => "BATCH_ERROR",
             UniModelError::Scheduling(_) => "SCHEDULE_ERROR",
             UniModelError::Resource(_) => "RESOURCE_ERROR",
@@ -86,6 +168,9 @@ impl UniModelError {
             UniModelError::Authentication(_) => "AUTH_ERROR",
             UniModelError::Authorization(_) => "AUTHZ_ERROR",
             UniModelError::Validation(_) => "VALIDATION_ERROR",
+            UniModelError::RateLimited { .. } => "RATE_LIMITED",
+            UniModelError::Overloaded { .. } => "OVERLOADED",
+            UniModelError::QuotaExceeded(_) => "QUOTA_EXCEEDED",
             UniModelError::Io(_) => "IO_ERROR",
             UniModelError::Serialization(_) => "SERIALIZATION_ERROR",
             UniModelError::Http(_) => "HTTP_ERROR",
@@ -99,6 +184,8 @@ impl UniModelError {
             UniModelError::Config(_) => 500,
             UniModelError::Model(_) => 404,
             UniModelError::Plugin(_) => 500,
+            UniModelError::PluginUnavailable(_) => 503,
+            UniModelError::OutOfMemory(_) => 503,
             UniModelError::BatchProcessing(_) => 500,
             UniModelError::Scheduling(_) => 503,
             UniModelError::Resource(_) => 503,
@@ -106,10 +193,31 @@ impl UniModelError {
             UniModelError::Authentication(_) => 401,
             UniModelError::Authorization(_) => 403,
             UniModelError::Validation(_) => 400,
+            UniModelError::RateLimited { .. } => 429,
+            UniModelError::Overloaded { .. } => 503,
+            UniModelError::QuotaExceeded(_) => 429,
             UniModelError::Io(_) => 500,
             UniModelError::Serialization(_) => 400,
             UniModelError::Http(_) => 500,
             UniModelError::Internal(_) => 500,
         }
     }
+
+    /// 是否为可重试的瞬时错误
+    pub fn is_retriable(&self) -> bool {
+        matches!(
+            self,
+            UniModelError::PluginUnavailable(_)
+                | UniModelError::OutOfMemory(_)
+                | UniModelError::Network(_)
+        )
+    }
+
+    /// 获取过载提示（仅限流与过载错误携带）
+    pub fn overload_hint(&self) -> Option<&OverloadHint> {
+        match self {
+            UniModelError::RateLimited { hint, .. } | UniModelError::Overloaded { hint, .. } => Some(hint),
+            _ => None,
+        }
+    }
 }
\ No newline at end of file
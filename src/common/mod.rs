@@ -0,0 +1,4 @@
+//! 公共模块
+
+pub mod error;
+pub mod types;
@@ -1,5 +1,6 @@
 //! 通用类型定义
 
+use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -22,6 +23,27 @@ pub fn new_request_id() -> RequestId {
     Uuid::new_v4().to_string()
 }
 
+/// 计算请求指纹（模型、输入与影响输出的参数的SHA-256）
+///
+/// 参数经 `serde_json::Value` 规范化后序列化，键顺序稳定；
+/// 优先级与超时不影响推理结果，不计入指纹。
+pub fn request_fingerprint(model_id: &str, input: &InputData, parameters: &PredictionParameters) -> String {
+    use sha2::{Digest, Sha256};
+
+    let mut parameters = parameters.clone();
+    parameters.priority = Priority::default();
+    parameters.timeout_ms = None;
+
+    let canonical = serde_json::to_value((model_id, input, &parameters))
+        .and_then(|value| serde_json::to_vec(&value))
+        .unwrap_or_default();
+
+    Sha256::digest(&canonical)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
 /// 生成新的模型ID
 pub fn new_model_id() -> ModelId {
     Uuid::new_v4().to_string()
@@ -33,12 +55,15 @@ pub fn new_model_id() -> ModelId {
 pub enum InputData {
     /// 文本输入
     Text(String),
-    /// 二进制数据（如图像、音频）
-    Binary(Vec<u8>),
+    /// 二进制数据（如图像、音频），在MessagePack等二进制格式中按字节串编码；
+    /// 克隆只增加引用计数，不复制数据
+    Binary(Bytes),
     /// JSON数据
     Json(serde_json::Value),
     /// 多模态输入
     Multimodal(HashMap<String, InputData>),
+    /// 按输入名组织的张量
+    Tensors(HashMap<String, Tensor>),
 }
 
 /// 推理输出数据
@@ -48,15 +73,225 @@ pub enum OutputData {
     /// 文本输出
     Text(String),
     /// 二进制数据
-    Binary(Vec<u8>),
+    Binary(Bytes),
     /// JSON数据
     Json(serde_json::Value),
     /// 多模态输出
     Multimodal(HashMap<String, OutputData>),
+    /// 按输出名组织的张量
+    Tensors(HashMap<String, Tensor>),
+}
+
+/// 张量元素类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DataType {
+    Bool,
+    U8,
+    I8,
+    U16,
+    I16,
+    F16,
+    BF16,
+    U32,
+    I32,
+    F32,
+    U64,
+    I64,
+    F64,
+    /// FP8（E4M3）
+    F8E4M3,
+    /// FP8（E5M2）
+    F8E5M2,
+}
+
+impl DataType {
+    /// 单个元素的字节数
+    pub fn size_bytes(&self) -> usize {
+        match self {
+            DataType::Bool | DataType::U8 | DataType::I8 | DataType::F8E4M3 | DataType::F8E5M2 => 1,
+            DataType::U16 | DataType::I16 | DataType::F16 | DataType::BF16 => 2,
+            DataType::U32 | DataType::I32 | DataType::F32 => 4,
+            DataType::U64 | DataType::I64 | DataType::F64 => 8,
+        }
+    }
+}
+
+/// 张量（行优先，元素按小端序存放）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Tensor {
+    pub dtype: DataType,
+    pub shape: Vec<usize>,
+    #[serde(with = "serde_bytes")]
+    pub data: Vec<u8>,
+}
+
+impl Tensor {
+    /// 创建张量，校验数据长度与形状一致
+    pub fn new(dtype: DataType, shape: Vec<usize>, data: Vec<u8>) -> crate::common::error::Result<Self> {
+        let tensor = Self { dtype, shape, data };
+        tensor.validate()?;
+        Ok(tensor)
+    }
+
+    /// 校验数据长度与形状一致（反序列化得到的张量需显式校验）
+    pub fn validate(&self) -> crate::common::error::Result<()> {
+        let expected = self.num_elements() * self.dtype.size_bytes();
+        if self.data.len() != expected {
+            return Err(crate::common::error::UniModelError::validation(format!(
+                "Tensor of shape {:?} and dtype {:?} needs {} bytes, got {}",
+                self.shape, self.dtype, expected, self.data.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// 元素个数
+    pub fn num_elements(&self) -> usize {
+        self.shape.iter().product()
+    }
+
+    /// 按元素转换为f32（FP8除外），供后处理读取任意数值类型的输出
+    pub fn to_f32_vec(&self) -> crate::common::error::Result<Vec<f32>> {
+        self.validate()?;
+        let data = &self.data;
+        let values = match self.dtype {
+            DataType::Bool | DataType::U8 => data.iter().map(|&b| b as f32).collect(),
+            DataType::I8 => data.iter().map(|&b| b as i8 as f32).collect(),
+            DataType::U16 => data.chunks_exact(2).map(|b| u16::from_le_bytes([b[0], b[1]]) as f32).collect(),
+            DataType::I16 => data.chunks_exact(2).map(|b| i16::from_le_bytes([b[0], b[1]]) as f32).collect(),
+            DataType::F16 => data.chunks_exact(2).map(|b| f16_to_f32(u16::from_le_bytes([b[0], b[1]]))).collect(),
+            DataType::BF16 => data.chunks_exact(2)
+                .map(|b| f32::from_bits((u16::from_le_bytes([b[0], b[1]]) as u32) << 16))
+                .collect(),
+            DataType::U32 => data.chunks_exact(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32).collect(),
+            DataType::I32 => data.chunks_exact(4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]) as f32).collect(),
+            DataType::F32 => data.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect(),
+            DataType::U64 => data.chunks_exact(8).map(|b| u64::from_le_bytes(b.try_into().unwrap_or_default()) as f32).collect(),
+            DataType::I64 => data.chunks_exact(8).map(|b| i64::from_le_bytes(b.try_into().unwrap_or_default()) as f32).collect(),
+            DataType::F64 => data.chunks_exact(8).map(|b| f64::from_le_bytes(b.try_into().unwrap_or_default()) as f32).collect(),
+            DataType::F8E4M3 | DataType::F8E5M2 => {
+                return Err(crate::common::error::UniModelError::validation(format!(
+                    "Cannot convert {:?} tensors to f32",
+                    self.dtype
+                )));
+            }
+        };
+        Ok(values)
+    }
+}
+
+/// IEEE半精度转单精度
+fn f16_to_f32(bits: u16) -> f32 {
+    let sign = ((bits >> 15) as u32) << 31;
+    let exponent = ((bits >> 10) & 0x1f) as u32;
+    let mantissa = (bits & 0x3ff) as u32;
+    let bits = match (exponent, mantissa) {
+        (0, 0) => sign,
+        // 非规格化数
+        (0, _) => {
+            let value = mantissa as f32 * 2f32.powi(-24);
+            return if sign != 0 { -value } else { value };
+        }
+        (0x1f, _) => sign | 0x7f80_0000 | (mantissa << 13),
+        _ => sign | ((exponent + 112) << 23) | (mantissa << 13),
+    };
+    f32::from_bits(bits)
+}
+
+/// 请求优先级
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl Priority {
+    /// 优先级等级，数值越小越优先
+    pub fn rank(&self) -> u32 {
+        match self {
+            Priority::High => 0,
+            Priority::Normal => 1,
+            Priority::Low => 2,
+        }
+    }
+}
+
+impl std::str::FromStr for Priority {
+    type Err = crate::common::error::UniModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "high" => Ok(Priority::High),
+            "normal" => Ok(Priority::Normal),
+            "low" => Ok(Priority::Low),
+            other => Err(crate::common::error::UniModelError::validation(format!(
+                "Invalid priority '{}', expected high, normal or low",
+                other
+            ))),
+        }
+    }
+}
+
+/// 分割掩码的输出编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaskFormat {
+    /// 原始张量
+    Raw,
+    /// 按类别的游程编码（COCO未压缩RLE，列优先）
+    Rle,
+    /// 以像素值表示类别序号的灰度PNG
+    Png,
+    /// 按类别的多边形轮廓
+    Polygon,
+}
+
+impl std::str::FromStr for MaskFormat {
+    type Err = crate::common::error::UniModelError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "raw" => Ok(MaskFormat::Raw),
+            "rle" => Ok(MaskFormat::Rle),
+            "png" => Ok(MaskFormat::Png),
+            "polygon" => Ok(MaskFormat::Polygon),
+            other => Err(crate::common::error::UniModelError::validation(format!(
+                "Invalid mask format '{}', expected raw, rle, png or polygon",
+                other
+            ))),
+        }
+    }
+}
+
+/// 输出格式（`{"type": "json_schema", "schema": {...}}`）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// 不限制输出
+    Text,
+    /// 任意JSON对象
+    JsonObject,
+    /// 符合给定JSON Schema的JSON
+    JsonSchema { schema: serde_json::Value },
+}
+
+impl ResponseFormat {
+    /// 输出需符合的schema，纯文本返回None
+    pub fn schema(&self) -> Option<serde_json::Value> {
+        match self {
+            ResponseFormat::Text => None,
+            ResponseFormat::JsonObject => Some(serde_json::json!({ "type": "object" })),
+            ResponseFormat::JsonSchema { schema } => Some(schema.clone()),
+        }
+    }
 }
 
 /// 推理参数
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct PredictionParameters {
     /// 最大生成token数（针对LLM）
     pub max_tokens: Option<u32>,
@@ -68,10 +303,100 @@ pub struct PredictionParameters {
     pub top_k: Option<u32>,
     /// 是否流式输出
     pub stream: Option<bool>,
+    /// 请求优先级
+    #[serde(default)]
+    pub priority: Priority,
+    /// 请求超时时间（毫秒），不能超过批处理配置的 `timeout_ms`
+    pub timeout_ms: Option<u64>,
+    /// 分割模型的掩码编码，未指定时使用模型配置的默认编码
+    #[serde(default)]
+    pub mask_format: Option<MaskFormat>,
+    /// 停止序列，生成的文本遇到任一序列即结束（输出不含停止序列）
+    #[serde(default)]
+    pub stop: Vec<String>,
+    /// 采样前加到对应token logit上的偏置（OpenAI `logit_bias`），-100屏蔽、100强制
+    #[serde(default)]
+    pub logit_bias: HashMap<u32, f32>,
+    /// 是否返回每个生成token的对数概率
+    #[serde(default)]
+    pub logprobs: bool,
+    /// 每个位置额外返回的候选token数，需同时开启 `logprobs`
+    #[serde(default)]
+    pub top_logprobs: Option<u32>,
+    /// 返回的候选补全数
+    #[serde(default)]
+    pub n: Option<u32>,
+    /// 束搜索宽度，大于1时用束搜索代替采样
+    #[serde(default)]
+    pub num_beams: Option<u32>,
+    /// 会话ID，同一会话的连续请求复用对话前缀的KV缓存
+    #[serde(default)]
+    pub session_id: Option<String>,
+    /// 随机种子，相同种子和参数在温度大于0时也产生相同输出
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// GBNF语法，生成的文本必须匹配其 `root` 规则
+    #[serde(default)]
+    pub grammar: Option<String>,
+    /// 输出格式，JSON Schema模式下输出保证可解析且符合schema
+    #[serde(default)]
+    pub response_format: Option<ResponseFormat>,
+    /// 使用的LoRA适配器名称，须已加载到目标模型上
+    #[serde(default)]
+    pub adapter: Option<String>,
     /// 自定义参数
     pub custom: HashMap<String, serde_json::Value>,
 }
 
+impl PredictionParameters {
+    /// 相同输入是否总是产生相同输出（无采样或固定种子、非流式）
+    pub fn is_deterministic(&self) -> bool {
+        (self.temperature.is_none_or(|t| t == 0.0) || self.seed.is_some()) && self.stream != Some(true)
+    }
+
+    /// 返回的候选补全数
+    pub fn completions(&self) -> u32 {
+        self.n.unwrap_or(1).max(1)
+    }
+
+    /// 请求在批大小计算中占用的序列数：采样n个候选或保留num_beams条束
+    pub fn compute_units(&self) -> usize {
+        self.completions().max(self.num_beams.unwrap_or(1)) as usize
+    }
+
+    /// 能否与另一请求在同一批次中按同一组参数推理
+    ///
+    /// 只影响排队和返回方式的优先级、超时、流式和会话可以不同；适配器由批处理器按后端能力单独分组。
+    pub fn batch_compatible(&self, other: &Self) -> bool {
+        let shared = |p: &Self| Self {
+            priority: Priority::default(),
+            timeout_ms: None,
+            stream: None,
+            session_id: None,
+            adapter: None,
+            ..p.clone()
+        };
+        shared(self) == shared(other)
+    }
+}
+
+/// 生成token的对数概率
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f32,
+    /// 该位置概率最高的候选token，按概率降序排列
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_logprobs: Vec<TopLogprob>,
+}
+
+/// 候选token的对数概率
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TopLogprob {
+    pub token: String,
+    pub logprob: f32,
+}
+
 /// 性能指标
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
@@ -176,6 +501,77 @@ pub struct BatchConfig {
     pub dynamic_padding: bool,
     /// 超时时间（毫秒）
     pub timeout_ms: u64,
+    /// 排队请求数上限，超出后拒绝新请求
+    #[serde(default = "default_max_queue_size")]
+    pub max_queue_size: u32,
+    /// 是否对支持逐token解码的模型启用连续批处理
+    #[serde(default)]
+    pub continuous_batching: bool,
+    /// 优先级老化间隔（毫秒），请求每等待一个间隔提升一个优先级
+    #[serde(default = "default_priority_aging_ms")]
+    pub priority_aging_ms: u64,
+    /// 延迟SLO（毫秒），设置后按p95延迟在线调整有效批大小与等待时间
+    #[serde(default)]
+    pub latency_slo_ms: Option<u64>,
+    /// 瞬时错误重试策略
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// 合并相同的在途确定性请求，只执行一次并将结果分发给所有等待者
+    #[serde(default)]
+    pub coalesce_requests: bool,
+    /// 会话保留KV缓存的空闲时间（秒）
+    #[serde(default = "default_session_ttl_secs")]
+    pub session_ttl_secs: u64,
+    /// 每个模型保留KV缓存的会话数上限，超出时释放最久未用的会话
+    #[serde(default = "default_max_sessions")]
+    pub max_sessions: usize,
+}
+
+/// 重试配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetryConfig {
+    /// 最大重试次数（不含首次执行）
+    pub max_retries: u32,
+    /// 首次重试前的退避时间（毫秒）
+    pub initial_backoff_ms: u64,
+    /// 退避时间上限（毫秒）
+    pub max_backoff_ms: u64,
+}
+
+impl RetryConfig {
+    /// 第 `attempt` 次重试前的退避时间（指数增长）
+    pub fn backoff(&self, attempt: u32) -> std::time::Duration {
+        let backoff_ms = self.initial_backoff_ms
+            .saturating_mul(1u64 << attempt.min(20))
+            .min(self.max_backoff_ms);
+        std::time::Duration::from_millis(backoff_ms)
+    }
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff_ms: 50,
+            max_backoff_ms: 1000,
+        }
+    }
+}
+
+fn default_max_queue_size() -> u32 {
+    1024
+}
+
+fn default_priority_aging_ms() -> u64 {
+    1000
+}
+
+fn default_session_ttl_secs() -> u64 {
+    300
+}
+
+fn default_max_sessions() -> usize {
+    256
 }
 
 impl Default for BatchConfig {
@@ -185,6 +581,14 @@ impl Default for BatchConfig {
             max_wait_time_ms: 50,
             dynamic_padding: true,
             timeout_ms: 30000,
+            max_queue_size: default_max_queue_size(),
+            continuous_batching: false,
+            priority_aging_ms: default_priority_aging_ms(),
+            latency_slo_ms: None,
+            retry: RetryConfig::default(),
+            coalesce_requests: false,
+            session_ttl_secs: default_session_ttl_secs(),
+            max_sessions: default_max_sessions(),
         }
     }
 }
\ No newline at end of file
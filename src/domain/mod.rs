@@ -0,0 +1,6 @@
+//! 领域层
+
+pub mod model;
+pub mod repository;
+pub mod service;
+pub mod value_object;
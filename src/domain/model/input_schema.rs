@@ -0,0 +1,75 @@
+//! 模型输入JSON Schema
+
+use std::fmt;
+use std::sync::Arc;
+
+use jsonschema::JSONSchema;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::common::error::*;
+
+/// 单次校验最多报告的错误数
+const MAX_REPORTED_ERRORS: usize = 10;
+
+/// 注册时附加到模型的输入Schema，反序列化时即编译，无效的Schema在注册阶段被拒绝
+#[derive(Clone)]
+pub struct InputSchema {
+    schema: serde_json::Value,
+    compiled: Arc<JSONSchema>,
+}
+
+impl InputSchema {
+    /// 编译Schema
+    pub fn new(schema: serde_json::Value) -> Result<Self> {
+        let compiled = JSONSchema::compile(&schema)
+            .map_err(|e| UniModelError::validation(format!("Invalid input schema: {}", e)))?;
+        Ok(Self {
+            schema,
+            compiled: Arc::new(compiled),
+        })
+    }
+
+    /// 原始Schema
+    pub fn schema(&self) -> &serde_json::Value {
+        &self.schema
+    }
+
+    /// 校验输入，错误信息带JSON Pointer定位（如 `/messages/0/role: ...`）
+    pub fn validate(&self, input: &serde_json::Value) -> Result<()> {
+        let errors = match self.compiled.validate(input) {
+            Ok(()) => return Ok(()),
+            Err(errors) => errors,
+        };
+
+        let details: Vec<String> = errors
+            .take(MAX_REPORTED_ERRORS)
+            .map(|e| {
+                let pointer = e.instance_path.to_string();
+                format!("{}: {}", if pointer.is_empty() { "/" } else { &pointer }, e)
+            })
+            .collect();
+        Err(UniModelError::validation(format!(
+            "Input does not match the model's schema: {}",
+            details.join("; ")
+        )))
+    }
+}
+
+impl fmt::Debug for InputSchema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("InputSchema").field(&self.schema).finish()
+    }
+}
+
+impl Serialize for InputSchema {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.schema.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InputSchema {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        let schema = serde_json::Value::deserialize(deserializer)?;
+        InputSchema::new(schema).map_err(serde::de::Error::custom)
+    }
+}
@@ -1,11 +1,18 @@
 //! 领域模型定义
 
+pub mod input_schema;
 pub mod model_entity;
+pub mod model_event;
+pub mod model_signature;
+pub mod postprocessing;
 pub mod prediction_request;
 pub mod prediction_response;
+pub mod preprocessing;
 pub mod resource;
 
+pub use input_schema::*;
 pub use model_entity::*;
-pub use prediction_request::*;
-pub use prediction_response::*;
-pub use resource::*;
\ No newline at end of file
+pub use model_event::*;
+pub use model_signature::*;
+pub use postprocessing::*;
+pub use preprocessing::*;
@@ -3,9 +3,12 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::sync::Arc;
 
 use crate::common::error::*;
 use crate::common::types::*;
+use crate::domain::model::{InputSchema, LabelMap, ModelSignature, PostprocessingConfig, PreprocessingConfig};
+use crate::infrastructure::tokenizer::ModelTokenizer;
 
 /// 模型状态
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -58,6 +61,30 @@ pub struct ModelConfig {
     pub optimization: OptimizationConfig,
     /// 批处理配置
     pub batch_config: BatchConfig,
+    /// 同时执行的批次数上限（舱壁隔离，None表示不限制）
+    #[serde(default)]
+    pub max_concurrent_batches: Option<u32>,
+    /// 加载后的预热配置
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+    /// 用户提供的输入输出签名（优先于模型文件解析结果）
+    #[serde(default)]
+    pub signature: Option<ModelSignature>,
+    /// JSON输入的Schema，推理前校验
+    #[serde(default)]
+    pub input_schema: Option<InputSchema>,
+    /// 服务端输入预处理（如把原始图像转换为张量）
+    #[serde(default)]
+    pub preprocessing: Option<PreprocessingConfig>,
+    /// 服务端输出后处理（如检测结果的NMS）
+    #[serde(default)]
+    pub postprocessing: Option<PostprocessingConfig>,
+    /// 类别标签文件（JSON数组、JSON对象或每行一个标签）
+    #[serde(default)]
+    pub labels_path: Option<String>,
+    /// 投机解码：用小的草稿模型提出候选token，本模型一次前向验证
+    #[serde(default)]
+    pub speculative: Option<SpeculativeConfig>,
     /// 自定义参数
     pub custom_params: HashMap<String, serde_json::Value>,
 }
@@ -71,8 +98,52 @@ pub struct DeviceConfig {
     pub device_ids: Vec<u32>,
     /// 内存限制（MB）
     pub memory_limit_mb: Option<u64>,
-    /// 是否启用混合精度
+    /// 是否启用混合精度：后端以FP16/BF16自动转换执行计算，精度敏感的算子保持FP32
     pub mixed_precision: bool,
+    /// 多GPU并行方式，默认单卡运行
+    #[serde(default)]
+    pub parallelism: Parallelism,
+    /// 流水线并行时每个批次拆成的微批次数，默认与阶段数相同
+    #[serde(default)]
+    pub micro_batches: Option<u32>,
+}
+
+/// 多GPU并行方式
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Parallelism {
+    /// 单卡运行，`device_ids` 为候选设备，调度器从中选择一张
+    #[default]
+    None,
+    /// 张量并行：权重切分到 `device_ids` 列出的所有GPU上，每张卡执行每层的一部分
+    Tensor,
+    /// 流水线并行：按层划分为阶段，依次放到 `device_ids` 列出的GPU上，批次拆成微批次流过各阶段
+    Pipeline,
+    /// 多副本：`device_ids` 列出的每张GPU上各加载一个完整实例，批次分发到排队最少的副本
+    Replicas,
+}
+
+/// 计算精度
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum Precision {
+    /// 全精度
+    #[default]
+    FP32,
+    /// FP16自动混合精度
+    FP16,
+    /// BF16自动混合精度
+    BF16,
+}
+
+impl Precision {
+    /// 用于日志的小写名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            Precision::FP32 => "fp32",
+            Precision::FP16 => "fp16",
+            Precision::BF16 => "bf16",
+        }
+    }
 }
 
 /// 设备类型
@@ -82,12 +153,44 @@ pub enum DeviceType {
     CPU,
     /// CUDA GPU
     CUDA,
+    /// AMD GPU（ROCm/HIP）
+    #[serde(alias = "HIP")]
+    ROCm,
     /// Metal GPU (Apple)
     Metal,
     /// OpenCL
     OpenCL,
     /// NPU
     NPU,
+    /// 注册时探测可用设备，按 CUDA → ROCm → Metal → CPU 的顺序选择
+    Auto,
+}
+
+impl DeviceType {
+    /// 是否为有独立设备内存、由调度器按显存放置的加速器
+    pub fn is_accelerator(&self) -> bool {
+        matches!(self, DeviceType::CUDA | DeviceType::ROCm | DeviceType::NPU)
+    }
+
+    /// 混合精度可用的低精度，按优先顺序排列：BF16与FP32的数值范围相同、不需要损失缩放，
+    /// GPU和CPU上优先；Metal、NPU等设备普遍只对FP16有完整支持
+    pub fn autocast_precisions(&self) -> [Precision; 2] {
+        match self {
+            DeviceType::CPU | DeviceType::CUDA | DeviceType::ROCm => [Precision::BF16, Precision::FP16],
+            _ => [Precision::FP16, Precision::BF16],
+        }
+    }
+}
+
+/// `Auto` 设备的选择结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DeviceSelection {
+    /// 配置中的设备类型
+    pub requested: DeviceType,
+    /// 实际使用的设备类型
+    pub device_type: DeviceType,
+    /// 选择的依据，如探测到的GPU或探测失败的原因
+    pub reason: String,
 }
 
 /// 优化配置
@@ -103,10 +206,128 @@ pub struct OptimizationConfig {
     pub inference_parallelism: u32,
     /// 内存优化级别
     pub memory_optimization: MemoryOptimization,
+    /// 预热时把推理调用捕获为CUDA graph，之后每个批次直接重放，省去逐个kernel的启动开销；
+    /// 只适用于输入形状（批维度除外）固定的模型
+    #[serde(default)]
+    pub cuda_graphs: bool,
+    /// 每个CUDA模型实例的流数，相邻批次的拷贝与计算在不同的流上重叠执行
+    #[serde(default = "default_cuda_streams")]
+    pub cuda_streams: u32,
+}
+
+fn default_cuda_streams() -> u32 {
+    1
+}
+
+impl ModelConfig {
+    /// 捕获CUDA graph的批大小：不超过最大批大小的2的幂以及最大批大小本身，未启用时为空
+    pub fn cuda_graph_batch_sizes(&self) -> Vec<u32> {
+        if !self.optimization.cuda_graphs {
+            return Vec::new();
+        }
+        let max_batch_size = self.batch_config.max_batch_size.max(1);
+        let mut sizes: Vec<u32> = std::iter::successors(Some(1u32), |size| size.checked_mul(2))
+            .take_while(|&size| size < max_batch_size)
+            .collect();
+        sizes.push(max_batch_size);
+        sizes
+    }
+
+    /// 批次重放时填充到的大小：不小于 `batch_size` 的最小捕获批大小，未启用时不填充
+    pub fn cuda_graph_padded_size(&self, batch_size: usize) -> usize {
+        self.cuda_graph_batch_sizes()
+            .into_iter()
+            .map(|size| size as usize)
+            .find(|&size| size >= batch_size)
+            .unwrap_or(batch_size)
+    }
+
+    /// 模型跨越的GPU数，单卡运行时为1
+    pub fn parallel_size(&self) -> usize {
+        match self.device.parallelism {
+            Parallelism::None => 1,
+            Parallelism::Tensor | Parallelism::Pipeline | Parallelism::Replicas => self.device.device_ids.len().max(1),
+        }
+    }
+
+    /// 每个批次拆成的微批次数，非流水线并行时为1
+    pub fn micro_batches(&self) -> usize {
+        match self.device.parallelism {
+            Parallelism::Pipeline => self.device.micro_batches.map_or(self.parallel_size(), |n| n as usize).max(1),
+            _ => 1,
+        }
+    }
+
+    /// 把 `batch_size` 个输入按顺序拆成微批次，返回各微批次的下标范围
+    pub fn micro_batch_ranges(&self, batch_size: usize) -> Vec<std::ops::Range<usize>> {
+        let count = self.micro_batches().min(batch_size).max(1);
+        let (size, extra) = (batch_size / count, batch_size % count);
+        let mut start = 0;
+        (0..count)
+            .map(|i| {
+                let end = start + size + usize::from(i < extra);
+                let range = start..end;
+                start = end;
+                range
+            })
+            .collect()
+    }
+
+    /// 检查多GPU并行配置：须在GPU上运行，`device_ids` 至少列出两张不重复的卡
+    pub fn check_parallelism(&self) -> Result<()> {
+        if self.device.parallelism == Parallelism::None {
+            return Ok(());
+        }
+        if self.device.device_type == DeviceType::CPU {
+            return Err(UniModelError::validation("Multi-GPU parallelism needs a GPU device"));
+        }
+        let mut device_ids = self.device.device_ids.clone();
+        device_ids.sort_unstable();
+        device_ids.dedup();
+        if device_ids.len() < 2 || device_ids.len() != self.device.device_ids.len() {
+            return Err(UniModelError::validation(format!(
+                "{:?} parallelism needs at least two distinct device_ids, got {:?}",
+                self.device.parallelism, self.device.device_ids
+            )));
+        }
+        if self.device.parallelism == Parallelism::Pipeline {
+            if self.device.micro_batches == Some(0) {
+                return Err(UniModelError::validation("micro_batches must be greater than 0"));
+            }
+            // 微批次的大小随批次变化，无法重放按整批捕获的graph
+            if self.optimization.cuda_graphs {
+                return Err(UniModelError::validation("CUDA graphs cannot be combined with pipeline parallelism"));
+            }
+        }
+        Ok(())
+    }
+
+    /// 检查能否使用CUDA graph：须在CUDA设备上运行，已知签名时除批维度外的输入维度须固定
+    pub fn check_cuda_graphs(&self) -> Result<()> {
+        if !self.optimization.cuda_graphs {
+            return Ok(());
+        }
+        if self.device.device_type != DeviceType::CUDA {
+            return Err(UniModelError::validation(format!(
+                "CUDA graphs need a CUDA device, model runs on {:?}",
+                self.device.device_type
+            )));
+        }
+        let dynamic = self.signature.iter()
+            .flat_map(|signature| signature.inputs.iter())
+            .find(|spec| spec.shape.iter().skip(1).any(|&dim| dim < 0));
+        if let Some(spec) = dynamic {
+            return Err(UniModelError::validation(format!(
+                "CUDA graphs need static input shapes, input '{}' has shape {:?}",
+                spec.name, spec.shape
+            )));
+        }
+        Ok(())
+    }
 }
 
 /// 量化类型
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum QuantizationType {
     /// INT8量化
     INT8,
@@ -118,6 +339,18 @@ pub enum QuantizationType {
     Dynamic,
 }
 
+impl QuantizationType {
+    /// 用于文件名和日志的小写名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            QuantizationType::INT8 => "int8",
+            QuantizationType::INT4 => "int4",
+            QuantizationType::FP16 => "fp16",
+            QuantizationType::Dynamic => "dynamic",
+        }
+    }
+}
+
 /// 内存优化级别
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum MemoryOptimization {
@@ -131,6 +364,53 @@ pub enum MemoryOptimization {
     High,
 }
 
+/// 预热配置
+///
+/// 模型加载完成后、标记为就绪前执行的推理次数，使首个真实请求不必承担
+/// JIT编译、图捕获和显存分配的开销。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WarmupConfig {
+    /// 预热推理次数（0表示不预热）
+    pub requests: u32,
+    /// 预热样本，按顺序循环使用；为空时按模型类型生成占位输入
+    #[serde(default)]
+    pub samples: Vec<InputData>,
+}
+
+impl WarmupConfig {
+    /// 第 `index` 次预热使用的输入
+    pub fn input(&self, index: usize, model_type: &ModelType) -> InputData {
+        if !self.samples.is_empty() {
+            return self.samples[index % self.samples.len()].clone();
+        }
+
+        match model_type {
+            ModelType::LLM => InputData::Text("Hello".to_string()),
+            // 224x224 RGB 空白图像
+            ModelType::CV => InputData::Binary(vec![0; 224 * 224 * 3].into()),
+            // 1秒 16kHz 16bit 静音
+            ModelType::Audio => InputData::Binary(vec![0; 16000 * 2].into()),
+            _ => InputData::Json(serde_json::json!({})),
+        }
+    }
+}
+
+/// 投机解码配置
+///
+/// 草稿模型需先注册，并与本模型使用相同的词表。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SpeculativeConfig {
+    /// 草稿模型的名称（同租户）或ID
+    pub draft_model: String,
+    /// 每步提出的草稿token数
+    #[serde(default = "default_num_speculative_tokens")]
+    pub num_speculative_tokens: usize,
+}
+
+fn default_num_speculative_tokens() -> usize {
+    4
+}
+
 /// 模型元数据
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelMetadata {
@@ -152,6 +432,13 @@ pub struct ModelMetadata {
     pub custom_metadata: HashMap<String, serde_json::Value>,
 }
 
+impl ModelMetadata {
+    /// 上下文窗口长度（由模型文件元数据或 `custom_params.context_length` 填充）
+    pub fn context_length(&self) -> Option<u64> {
+        self.custom_metadata.get("context_length").and_then(|v| v.as_u64())
+    }
+}
+
 /// 模型信息
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ModelInfo {
@@ -159,6 +446,12 @@ pub struct ModelInfo {
     pub id: ModelId,
     /// 模型名称
     pub name: String,
+    /// 所属租户（None表示全局命名空间）
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// 是否固定驻留（不会被自动逐出）
+    #[serde(default)]
+    pub pinned: bool,
     /// 模型类型
     pub model_type: ModelType,
     /// 模型状态
@@ -167,6 +460,21 @@ pub struct ModelInfo {
     pub config: ModelConfig,
     /// 模型元数据
     pub metadata: ModelMetadata,
+    /// 输入输出签名（由后端模型文件解析得到）
+    #[serde(default)]
+    pub signature: Option<ModelSignature>,
+    /// 注册时从 `labels_path` 加载的类别标签
+    #[serde(skip)]
+    pub labels: Option<Arc<LabelMap>>,
+    /// 注册时从 `tokenizer_path` 或GGUF内嵌词表加载的分词器
+    #[serde(skip)]
+    pub tokenizer: Option<Arc<ModelTokenizer>>,
+    /// 运行时加载的LoRA适配器
+    #[serde(default)]
+    pub adapters: Vec<LoraAdapter>,
+    /// 设备类型为 `Auto` 时注册阶段的选择结果
+    #[serde(default)]
+    pub device_selection: Option<DeviceSelection>,
     /// 资源使用情况
     pub resource_usage: Option<ResourceUsage>,
     /// 性能统计
@@ -175,6 +483,40 @@ pub struct ModelInfo {
     pub health_status: HealthStatus,
 }
 
+impl ModelInfo {
+    /// 带命名空间的模型名称（`tenant/name`）
+    pub fn qualified_name(&self) -> String {
+        match &self.tenant {
+            Some(tenant) => format!("{}/{}", tenant, self.name),
+            None => self.name.clone(),
+        }
+    }
+
+    /// 检查模型对调用方租户是否可见（无租户的调用方可见全部模型）
+    pub fn is_visible_to(&self, tenant: Option<&str>) -> bool {
+        match tenant {
+            Some(tenant) => self.tenant.as_deref() == Some(tenant),
+            None => true,
+        }
+    }
+
+    /// 按名称查找已加载的LoRA适配器
+    pub fn adapter(&self, name: &str) -> Option<&LoraAdapter> {
+        self.adapters.iter().find(|a| a.name == name)
+    }
+}
+
+/// 加载到基础模型上的LoRA适配器
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct LoraAdapter {
+    /// 适配器名称，请求通过 `adapter` 参数选择
+    pub name: String,
+    /// 适配器权重路径（safetensors文件或PEFT目录）
+    pub path: String,
+    /// 加载时间
+    pub loaded_at: DateTime<Utc>,
+}
+
 /// 性能统计
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceStats {
@@ -203,6 +545,8 @@ pub struct Model {
     pub info: ModelInfo,
     /// 模型实例句柄
     pub instance: Option<ModelInstance>,
+    /// 多副本时其余GPU上的实例，`instance` 为第一个副本
+    pub replicas: Vec<ModelInstance>,
     /// 是否为热模型
     pub is_warm: bool,
     /// 最后访问时间
@@ -224,6 +568,10 @@ pub struct ModelInstance {
     pub supports_batching: bool,
     /// 最大批处理大小
     pub max_batch_size: u32,
+    /// 能否在同一批次中为不同序列使用不同的LoRA适配器
+    pub supports_multi_lora: bool,
+    /// 实例实际使用的计算精度
+    pub precision: Precision,
 }
 
 impl Model {
@@ -255,10 +603,17 @@ impl Model {
         let info = ModelInfo {
             id,
             name,
+            tenant: None,
+            pinned: false,
             model_type,
             status: ModelStatus::Initializing,
             config,
             metadata,
+            signature: None,
+            labels: None,
+            tokenizer: None,
+            adapters: Vec::new(),
+            device_selection: None,
             resource_usage: None,
             performance_stats,
             health_status: HealthStatus::Unknown,
@@ -267,12 +622,18 @@ impl Model {
         Self {
             info,
             instance: None,
+            replicas: Vec::new(),
             is_warm: false,
             last_accessed: now,
             loaded_at: None,
         }
     }
 
+    /// 所有已加载的实例，多副本时包括每个副本
+    pub fn instances(&self) -> impl Iterator<Item = &ModelInstance> {
+        self.instance.iter().chain(self.replicas.iter())
+    }
+
     /// 更新模型状态
     pub fn update_status(&mut self, status: ModelStatus) {
         self.info.status = status;
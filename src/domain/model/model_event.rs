@@ -0,0 +1,56 @@
+//! 模型生命周期事件
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::common::types::*;
+use crate::domain::model::ModelInfo;
+
+/// 事件类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelEventKind {
+    /// 已注册
+    Registered,
+    /// 加载完成
+    Loaded,
+    /// 加载失败
+    LoadFailed,
+    /// 因空闲被自动卸载
+    Evicted,
+    /// 已注销
+    Unregistered,
+    /// 加载了LoRA适配器
+    AdapterLoaded,
+    /// 卸载了LoRA适配器
+    AdapterUnloaded,
+}
+
+/// 模型事件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelEvent {
+    /// 事件ID
+    pub id: String,
+    pub model_id: ModelId,
+    pub model_name: String,
+    pub tenant: Option<String>,
+    pub kind: ModelEventKind,
+    /// 事件原因或错误信息
+    pub message: Option<String>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl ModelEvent {
+    /// 创建模型事件
+    pub fn new(info: &ModelInfo, kind: ModelEventKind, message: Option<String>) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            model_id: info.id.clone(),
+            model_name: info.name.clone(),
+            tenant: info.tenant.clone(),
+            kind,
+            message,
+            timestamp: Utc::now(),
+        }
+    }
+}
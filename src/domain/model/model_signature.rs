@@ -0,0 +1,162 @@
+//! 模型输入输出签名
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::ModelType;
+
+/// 张量承载的数据模态
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Modality {
+    Text,
+    Image,
+    Audio,
+    Video,
+    Tensor,
+}
+
+impl Modality {
+    /// 按模型类型和张量形状推断模态
+    pub fn infer(model_type: &ModelType, dtype: Option<DataType>, rank: usize) -> Self {
+        let integer = matches!(
+            dtype,
+            Some(DataType::I32 | DataType::I64 | DataType::U32 | DataType::U64)
+        );
+        match model_type {
+            ModelType::LLM if integer => Modality::Text,
+            ModelType::CV if rank == 3 || rank == 4 => Modality::Image,
+            ModelType::Audio if !integer => Modality::Audio,
+            _ => Modality::Tensor,
+        }
+    }
+}
+
+/// 签名来源
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureSource {
+    /// 注册时由用户提供
+    #[default]
+    User,
+    /// 从模型文件解析
+    Introspected,
+}
+
+/// 张量规格
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TensorSpec {
+    pub name: String,
+    /// 元素类型（None表示字符串等无法映射的类型）
+    pub dtype: Option<DataType>,
+    /// 形状，-1表示动态维度
+    pub shape: Vec<i64>,
+    #[serde(default)]
+    pub modality: Option<Modality>,
+}
+
+/// 模型签名
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelSignature {
+    pub inputs: Vec<TensorSpec>,
+    pub outputs: Vec<TensorSpec>,
+    #[serde(default)]
+    pub source: SignatureSource,
+}
+
+impl ModelSignature {
+    /// 为未标注模态的张量推断模态
+    pub fn infer_modalities(&mut self, model_type: &ModelType) {
+        for spec in self.inputs.iter_mut().chain(self.outputs.iter_mut()) {
+            if spec.modality.is_none() {
+                spec.modality = Some(Modality::infer(model_type, spec.dtype, spec.shape.len()));
+            }
+        }
+    }
+
+    /// 校验以张量名为键、嵌套数组为值的JSON输入
+    ///
+    /// 必须提供所有输入且不能有多余的键，数组的维数和固定维度需与签名一致。
+    pub fn validate_json_input(&self, input: &serde_json::Value) -> Result<()> {
+        let tensors = match input.as_object() {
+            Some(tensors) => tensors,
+            None => return Err(UniModelError::validation("Tensor input must be an object keyed by input name")),
+        };
+
+        self.check_input_names(tensors.keys())?;
+
+        for spec in &self.inputs {
+            let value = tensors.get(&spec.name)
+                .ok_or_else(|| UniModelError::validation(format!("Missing model input '{}'", spec.name)))?;
+            check_shape(spec, &json_shape(value))?;
+        }
+
+        Ok(())
+    }
+
+    /// 校验张量输入的名称、元素类型和形状
+    pub fn validate_tensors(&self, tensors: &HashMap<String, Tensor>) -> Result<()> {
+        self.check_input_names(tensors.keys())?;
+
+        for spec in &self.inputs {
+            let tensor = tensors.get(&spec.name)
+                .ok_or_else(|| UniModelError::validation(format!("Missing model input '{}'", spec.name)))?;
+
+            if let Some(dtype) = spec.dtype {
+                if tensor.dtype != dtype {
+                    return Err(UniModelError::validation(format!(
+                        "Input '{}' expects dtype {:?}, got {:?}",
+                        spec.name, dtype, tensor.dtype
+                    )));
+                }
+            }
+            check_shape(spec, &tensor.shape)?;
+        }
+
+        Ok(())
+    }
+
+    /// 拒绝签名中不存在的输入名
+    fn check_input_names<'a>(&self, mut names: impl Iterator<Item = &'a String>) -> Result<()> {
+        match names.find(|name| !self.inputs.iter().any(|spec| &spec.name == *name)) {
+            Some(unknown) => Err(UniModelError::validation(format!("Unknown model input '{}'", unknown))),
+            None => Ok(()),
+        }
+    }
+}
+
+/// 检查形状的维数和固定维度
+fn check_shape(spec: &TensorSpec, shape: &[usize]) -> Result<()> {
+    if shape.len() != spec.shape.len() {
+        return Err(UniModelError::validation(format!(
+            "Input '{}' expects {} dimensions, got {}",
+            spec.name, spec.shape.len(), shape.len()
+        )));
+    }
+    for (axis, (&expected, &actual)) in spec.shape.iter().zip(shape).enumerate() {
+        if expected >= 0 && expected as usize != actual {
+            return Err(UniModelError::validation(format!(
+                "Input '{}' expects size {} on axis {}, got {}",
+                spec.name, expected, axis, actual
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// 嵌套数组的形状（按第一个元素逐层展开）
+fn json_shape(value: &serde_json::Value) -> Vec<usize> {
+    let mut shape = Vec::new();
+    let mut current = value;
+    while let Some(items) = current.as_array() {
+        shape.push(items.len());
+        match items.first() {
+            Some(first) => current = first,
+            None => break,
+        }
+    }
+    shape
+}
@@ -0,0 +1,213 @@
+//! 模型输出后处理配置
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::common::types::MaskFormat;
+use crate::domain::model::WasmStage;
+
+/// 后处理配置，按模型任务分别声明
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PostprocessingConfig {
+    /// 检测模型：阈值过滤、非极大值抑制和标签映射
+    #[serde(default)]
+    pub detection: Option<DetectionPostprocessing>,
+    /// 分割模型：把掩码张量编码为RLE、PNG或多边形
+    #[serde(default)]
+    pub segmentation: Option<SegmentationPostprocessing>,
+    /// 分类模型：返回前k个类别及分数
+    #[serde(default)]
+    pub classification: Option<ClassificationPostprocessing>,
+    /// 在内置后处理之后执行的WASM模块
+    #[serde(default)]
+    pub wasm: Option<WasmStage>,
+}
+
+/// 检测输出的后处理
+///
+/// 模型输出三个张量：`[N, 4]` 的边界框（`x_min, y_min, x_max, y_max`）、`[N]` 的分数和
+/// `[N]` 的类别序号，允许带大小为1的批次维。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetectionPostprocessing {
+    #[serde(default = "default_boxes_output")]
+    pub boxes_output: String,
+    #[serde(default = "default_scores_output")]
+    pub scores_output: String,
+    #[serde(default = "default_classes_output")]
+    pub classes_output: String,
+    /// 低于该分数的检测被丢弃
+    #[serde(default = "default_score_threshold")]
+    pub score_threshold: f32,
+    /// 非极大值抑制的IoU阈值
+    #[serde(default = "default_iou_threshold")]
+    pub iou_threshold: f32,
+    /// 最多返回的检测数
+    #[serde(default = "default_max_detections")]
+    pub max_detections: usize,
+    /// 是否跨类别做非极大值抑制
+    #[serde(default)]
+    pub class_agnostic: bool,
+}
+
+fn default_boxes_output() -> String {
+    "boxes".to_string()
+}
+
+fn default_scores_output() -> String {
+    "scores".to_string()
+}
+
+fn default_classes_output() -> String {
+    "classes".to_string()
+}
+
+fn default_score_threshold() -> f32 {
+    0.25
+}
+
+fn default_iou_threshold() -> f32 {
+    0.45
+}
+
+fn default_max_detections() -> usize {
+    100
+}
+
+impl Default for DetectionPostprocessing {
+    fn default() -> Self {
+        Self {
+            boxes_output: default_boxes_output(),
+            scores_output: default_scores_output(),
+            classes_output: default_classes_output(),
+            score_threshold: default_score_threshold(),
+            iou_threshold: default_iou_threshold(),
+            max_detections: default_max_detections(),
+            class_agnostic: false,
+        }
+    }
+}
+
+/// 分割输出的后处理
+///
+/// 掩码张量可以是 `[H, W]` 的类别序号、`[C, H, W]` 的逐类别分数（取argmax）
+/// 或单通道概率（按阈值二值化），允许带大小为1的批次维。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SegmentationPostprocessing {
+    #[serde(default = "default_mask_output")]
+    pub mask_output: String,
+    /// 请求未指定 `mask_format` 时使用的编码
+    #[serde(default = "default_mask_format")]
+    pub default_format: MaskFormat,
+    /// 单通道概率掩码的二值化阈值
+    #[serde(default = "default_mask_threshold")]
+    pub threshold: f32,
+    /// RLE和多边形输出是否包含背景类（类别0）
+    #[serde(default)]
+    pub include_background: bool,
+}
+
+fn default_mask_output() -> String {
+    "masks".to_string()
+}
+
+fn default_mask_format() -> MaskFormat {
+    MaskFormat::Rle
+}
+
+fn default_mask_threshold() -> f32 {
+    0.5
+}
+
+impl Default for SegmentationPostprocessing {
+    fn default() -> Self {
+        Self {
+            mask_output: default_mask_output(),
+            default_format: default_mask_format(),
+            threshold: default_mask_threshold(),
+            include_background: false,
+        }
+    }
+}
+
+/// 分类输出的后处理
+///
+/// 分数张量形状为 `[C]` 或 `[1, C]`；请求的 `top_k` 参数覆盖这里的默认值。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ClassificationPostprocessing {
+    /// 分数输出名，模型只有一个输出时可省略
+    #[serde(default)]
+    pub scores_output: Option<String>,
+    #[serde(default = "default_top_k")]
+    pub top_k: usize,
+    /// 模型输出logits时先做softmax
+    #[serde(default)]
+    pub softmax: bool,
+}
+
+fn default_top_k() -> usize {
+    5
+}
+
+impl Default for ClassificationPostprocessing {
+    fn default() -> Self {
+        Self {
+            scores_output: None,
+            top_k: default_top_k(),
+            softmax: false,
+        }
+    }
+}
+
+/// 类别序号到名称的映射
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LabelMap {
+    labels: HashMap<usize, String>,
+}
+
+impl LabelMap {
+    /// 解析标签文件
+    ///
+    /// 支持JSON数组（序号即下标）、JSON对象（`{"0": "cat"}`）和每行一个标签的文本文件。
+    pub fn parse(content: &str) -> Result<Self> {
+        let labels = match serde_json::from_str::<serde_json::Value>(content) {
+            Ok(serde_json::Value::Array(items)) => items.into_iter()
+                .enumerate()
+                .map(|(index, label)| match label {
+                    serde_json::Value::String(label) => Ok((index, label)),
+                    other => Err(UniModelError::validation(format!("Label {} is not a string: {}", index, other))),
+                })
+                .collect::<Result<_>>()?,
+            Ok(serde_json::Value::Object(map)) => map.into_iter()
+                .map(|(key, label)| {
+                    let index = key.parse()
+                        .map_err(|_| UniModelError::validation(format!("Label key '{}' is not an index", key)))?;
+                    let label = label.as_str()
+                        .ok_or_else(|| UniModelError::validation(format!("Label {} is not a string", key)))?;
+                    Ok((index, label.to_string()))
+                })
+                .collect::<Result<_>>()?,
+            _ => content.lines()
+                .map(str::trim)
+                .enumerate()
+                .filter(|(_, line)| !line.is_empty())
+                .map(|(index, line)| (index, line.to_string()))
+                .collect(),
+        };
+        Ok(Self { labels })
+    }
+
+    /// 类别名称，未知序号返回序号本身
+    pub fn label(&self, index: usize) -> String {
+        self.labels.get(&index).cloned().unwrap_or_else(|| index.to_string())
+    }
+
+    pub fn len(&self) -> usize {
+        self.labels.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.labels.is_empty()
+    }
+}
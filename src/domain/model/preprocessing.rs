@@ -0,0 +1,206 @@
+//! 服务端输入预处理配置
+
+use serde::{Deserialize, Serialize};
+
+/// 预处理配置，按输入类型分别声明
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PreprocessingConfig {
+    /// 二进制图像输入的预处理
+    #[serde(default)]
+    pub image: Option<ImagePreprocessing>,
+    /// 二进制音频输入的预处理
+    #[serde(default)]
+    pub audio: Option<AudioPreprocessing>,
+    /// 二进制视频输入的抽帧
+    #[serde(default)]
+    pub video: Option<VideoPreprocessing>,
+    /// 在内置预处理之前执行的WASM模块
+    #[serde(default)]
+    pub wasm: Option<WasmStage>,
+}
+
+impl PreprocessingConfig {
+    /// 是否配置了二进制输入的预处理
+    pub fn handles_binary(&self) -> bool {
+        self.image.is_some() || self.audio.is_some() || self.video.is_some()
+    }
+}
+
+/// 由WASM插件实现的处理阶段，模块在沙箱中运行
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WasmStage {
+    /// `.wasm` 模块路径
+    pub module: String,
+}
+
+/// 张量内存布局
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TensorLayout {
+    /// `[1, C, H, W]`
+    #[default]
+    Nchw,
+    /// `[1, H, W, C]`
+    Nhwc,
+}
+
+/// 通道顺序
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelOrder {
+    #[default]
+    Rgb,
+    Bgr,
+}
+
+/// 图像预处理：解码 → 按短边缩放 → 中心裁剪 → 归一化 → 排布为float32张量
+///
+/// 默认值对应常见的ImageNet分类模型（短边256、裁剪224、ImageNet均值方差、NCHW）。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ImagePreprocessing {
+    /// 输出张量名，未指定时使用签名中唯一的输入名
+    #[serde(default)]
+    pub input_name: Option<String>,
+    /// 短边缩放到的像素数（None表示不缩放）
+    #[serde(default = "default_resize")]
+    pub resize_shorter_side: Option<u32>,
+    /// 中心裁剪的 `[高, 宽]`（None表示不裁剪）
+    #[serde(default = "default_center_crop")]
+    pub center_crop: Option<[u32; 2]>,
+    /// 像素值先乘以该系数
+    #[serde(default = "default_scale")]
+    pub scale: f32,
+    /// 各通道均值（按 `channel_order` 排列）
+    #[serde(default = "default_mean")]
+    pub mean: [f32; 3],
+    /// 各通道标准差
+    #[serde(default = "default_std")]
+    pub std: [f32; 3],
+    #[serde(default)]
+    pub channel_order: ChannelOrder,
+    #[serde(default)]
+    pub layout: TensorLayout,
+}
+
+fn default_resize() -> Option<u32> {
+    Some(256)
+}
+
+fn default_center_crop() -> Option<[u32; 2]> {
+    Some([224, 224])
+}
+
+fn default_scale() -> f32 {
+    1.0 / 255.0
+}
+
+fn default_mean() -> [f32; 3] {
+    [0.485, 0.456, 0.406]
+}
+
+fn default_std() -> [f32; 3] {
+    [0.229, 0.224, 0.225]
+}
+
+impl Default for ImagePreprocessing {
+    fn default() -> Self {
+        Self {
+            input_name: None,
+            resize_shorter_side: default_resize(),
+            center_crop: default_center_crop(),
+            scale: default_scale(),
+            mean: default_mean(),
+            std: default_std(),
+            channel_order: ChannelOrder::default(),
+            layout: TensorLayout::default(),
+        }
+    }
+}
+
+/// 音频预处理：解码WAV/MP3/FLAC/OGG → 可选下混为单声道 → 重采样 → float32 PCM张量
+///
+/// 单声道输出形状为 `[1, samples]`，多声道为 `[1, channels, samples]`。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct AudioPreprocessing {
+    /// 输出张量名，未指定时使用签名中唯一的输入名
+    #[serde(default)]
+    pub input_name: Option<String>,
+    /// 模型期望的采样率（Hz）
+    #[serde(default = "default_sample_rate")]
+    pub sample_rate: u32,
+    /// 是否下混为单声道
+    #[serde(default = "default_mono")]
+    pub mono: bool,
+    /// 允许的最长时长（秒），防止压缩音频解码后占用过多内存
+    #[serde(default = "default_max_duration_secs")]
+    pub max_duration_secs: u32,
+}
+
+fn default_sample_rate() -> u32 {
+    16_000
+}
+
+fn default_mono() -> bool {
+    true
+}
+
+fn default_max_duration_secs() -> u32 {
+    600
+}
+
+impl Default for AudioPreprocessing {
+    fn default() -> Self {
+        Self {
+            input_name: None,
+            sample_rate: default_sample_rate(),
+            mono: default_mono(),
+            max_duration_secs: default_max_duration_secs(),
+        }
+    }
+}
+
+/// 视频抽帧：按帧率或步长抽取帧，每帧按 `frame` 做图像预处理后堆叠为 `[N, 3, H, W]` 张量
+///
+/// 模型输出的第一维按帧拆分，以帧时间戳（秒）为键返回。抽帧依赖 `ffmpeg` 可执行文件。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct VideoPreprocessing {
+    /// 输出张量名，未指定时使用签名中唯一的输入名
+    #[serde(default)]
+    pub input_name: Option<String>,
+    /// 每秒抽取的帧数（未指定步长时默认1）
+    #[serde(default)]
+    pub fps: Option<f32>,
+    /// 每隔多少帧抽取一帧，与 `fps` 互斥
+    #[serde(default)]
+    pub stride: Option<u32>,
+    /// 最多抽取的帧数
+    #[serde(default = "default_max_frames")]
+    pub max_frames: usize,
+    /// 每帧的图像预处理
+    #[serde(default)]
+    pub frame: ImagePreprocessing,
+    /// ffmpeg可执行文件路径
+    #[serde(default = "default_ffmpeg_path")]
+    pub ffmpeg_path: String,
+}
+
+fn default_max_frames() -> usize {
+    64
+}
+
+fn default_ffmpeg_path() -> String {
+    "ffmpeg".to_string()
+}
+
+impl Default for VideoPreprocessing {
+    fn default() -> Self {
+        Self {
+            input_name: None,
+            fps: None,
+            stride: None,
+            max_frames: default_max_frames(),
+            frame: ImagePreprocessing::default(),
+            ffmpeg_path: default_ffmpeg_path(),
+        }
+    }
+}
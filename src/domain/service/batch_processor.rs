@@ -1,27 +1,44 @@
 //! 批处理器服务
 
-use std::collections::VecDeque;
+use std::borrow::Cow;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
-use tokio::sync::{mpsc, oneshot, Mutex, RwLock};
+use dashmap::mapref::entry::Entry;
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use tokio::sync::{broadcast, mpsc, oneshot, OwnedSemaphorePermit, RwLock, Semaphore};
 use tokio::time::{sleep, timeout};
 use tracing::{debug, error, info, warn};
 
 use crate::common::error::*;
 use crate::common::types::*;
 use crate::domain::model::*;
+use crate::domain::service::batch_queue::PendingQueue;
+use crate::domain::service::batch_tuner::BatchTuner;
+use crate::domain::service::generation::{find_stop_sequence, stop_scan_overlap, truncate_at_stop};
+use crate::domain::service::chaos::{ChaosInjector, ChaosSite};
+use crate::domain::service::inference_pool::InferencePool;
+use crate::domain::service::model_manager::ModelManager;
+use crate::domain::service::replica_balancer::ReplicaBalancer;
+use crate::domain::service::session::SessionTable;
 use crate::infrastructure::configuration::Config;
+use crate::infrastructure::storage::DeadLetterStore;
+use crate::plugins::builtin::mock_plugin::{self, MockBackend};
+use crate::plugins::interface::{DecodeStep, IterativeDecoder};
 
 /// 批处理请求
 #[derive(Debug)]
 pub struct BatchRequest {
     pub request_id:      RequestId,                  // 请求 ID
     pub model_id:        ModelId,                    // 模型 ID
+    pub tenant:          Option<String>,             // 租户
     pub input:           InputData,                  // 输入数据
     pub parameters:      PredictionParameters,       // 预测参数
     pub response_sender: oneshot::Sender<Result<PredictionResponse>>, // 响应通道
     pub submitted_at:    Instant,                    // 提交时间
+    pub deadline:        Instant,                    // 截止时间（提交时间 + 请求超时）
 }
 
 /// 批处理组
@@ -32,29 +49,138 @@ pub struct BatchGroup {
     pub created_at: Instant,         // 创建时间
 }
 
+impl BatchGroup {
+    /// 各请求使用的LoRA适配器（与请求顺序一致），支持多LoRA的后端据此为每个序列选择适配器
+    pub fn adapters(&self) -> Vec<Option<&str>> {
+        self.requests.iter().map(|r| r.parameters.adapter.as_deref()).collect()
+    }
+}
+
+/// 模型队列空闲多久后回收其工作任务
+const MODEL_QUEUE_IDLE_TTL: Duration = Duration::from_secs(300);
+
+/// 合并请求分发给等待者的结果（错误以消息形式传递）
+type CoalescedResult = std::result::Result<PredictionResponse, String>;
+
+/// 在途请求登记守卫，领头请求完成或被取消时移除登记
+struct InflightGuard<'a> {
+    inflight: &'a DashMap<String, broadcast::Sender<CoalescedResult>>,
+    key:      &'a str,
+}
+
+impl Drop for InflightGuard<'_> {
+    fn drop(&mut self) {
+        self.inflight.remove(self.key);
+    }
+}
+
+/// 受理请求计数守卫，请求返回结果、超时或被取消时减一
+struct OutstandingGuard<'a>(&'a AtomicUsize);
+
+impl Drop for OutstandingGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// 排空时检查剩余请求的间隔
+const DRAIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// 检查会话过期的间隔
+const SESSION_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// 请求未指定 `max_tokens` 时连续批处理的生成上限
+pub(crate) const DEFAULT_MAX_NEW_TOKENS: u32 = 256;
+
+/// 连续批处理中正在解码的序列
+#[derive(Debug)]
+struct ActiveSequence {
+    request:          BatchRequest,
+    output:           String,
+    tokens_generated: u32,
+    max_tokens:       u32,
+    joined_at:        Instant,   // 加入批次的时间
+    occupancy_sum:    u64,       // 各解码步的批次大小之和，用于计算平均批次大小
+    steps:            u64,
+    stop_sequence:    Option<String>, // 命中的停止序列
+    logprobs:         Vec<TokenLogprob>,
+    candidates:       Vec<String>,    // 结束时后端返回的其余候选
+    draft_tokens:     u32,            // 投机解码接受的草稿token数
+}
+
 /// 批处理器
+///
+/// 每个模型拥有独立的有界队列和工作任务，热门模型不会在全局锁上相互阻塞。
 #[derive(Debug)]
 pub struct BatchProcessor {
     config:           Arc<Config>,
-    pending_requests: Arc<Mutex<VecDeque<BatchRequest>>>,
-    request_sender:   mpsc::UnboundedSender<BatchRequest>,
-    request_receiver: Arc<Mutex<mpsc::UnboundedReceiver<BatchRequest>>>,
+    queues:           Arc<DashMap<ModelId, mpsc::Sender<BatchRequest>>>, // 模型 ID -> 模型队列
+    decoders:         Arc<DashMap<ModelId, Arc<dyn IterativeDecoder>>>,  // 支持逐token解码的模型
+    tuners:           Arc<DashMap<ModelId, Arc<Mutex<BatchTuner>>>>,     // 按延迟SLO调整的批大小
+    bulkheads:        Arc<DashMap<ModelId, (u32, Arc<Semaphore>)>>,      // 模型并发批次上限
+    mixed_adapters:   Arc<DashMap<ModelId, bool>>,                       // 后端能否在一个批次中混用LoRA适配器
+    dead_letters:     Option<Arc<DeadLetterStore>>,                      // 永久失败请求的死信存储
+    model_manager:    Option<Arc<ModelManager>>,                         // 推理所在的插件，显存不足时用于逐出模型
+    mock:             Arc<MockBackend>,                                  // 未设置模型管理器时执行推理
+    chaos:            Option<Arc<ChaosInjector>>,                        // 故障注入
+    inference_pool:   Option<Arc<InferencePool>>,                        // 执行解码器调用，取自模型管理器
+    replicas:         Arc<ReplicaBalancer>,                              // 多副本模型按在途批次数选择实例
+    inflight:         Arc<DashMap<String, broadcast::Sender<CoalescedResult>>>, // 请求指纹 -> 在途请求
     running:          Arc<RwLock<bool>>,
+    draining:         Arc<AtomicBool>,                // 关闭前排空中，拒绝新请求
+    outstanding:      Arc<AtomicUsize>,              // 已受理但尚未返回结果的请求数
+    queue_depth:      Arc<AtomicUsize>,              // 已提交但尚未组批的请求数
+    avg_batch_latency_ms: Arc<AtomicU64>,            // 批次执行延迟的滑动平均
+    total_processed:  Arc<AtomicU64>,                // 已完成的请求数
+    total_cancelled:  Arc<AtomicU64>,                // 调用方断开后放弃的请求数
 }
 
 impl BatchProcessor {
     /// 创建新的批处理器
     pub async fn new(config: &Config) -> Result<Self> {
-        let (request_sender, request_receiver) = mpsc::unbounded_channel();
         Ok(Self {
             config: Arc::new(config.clone()),
-            pending_requests: Arc::new(Mutex::new(VecDeque::new())),
-            request_sender,
-            request_receiver: Arc::new(Mutex::new(request_receiver)),
+            queues: Arc::new(DashMap::new()),
+            decoders: Arc::new(DashMap::new()),
+            tuners: Arc::new(DashMap::new()),
+            bulkheads: Arc::new(DashMap::new()),
+            mixed_adapters: Arc::new(DashMap::new()),
+            dead_letters: None,
+            model_manager: None,
+            mock: Arc::new(MockBackend::from_plugin_config(
+                config.plugins.plugin_configs.get(mock_plugin::BACKEND_NAME),
+            )?),
+            chaos: ChaosInjector::new(&config.chaos),
+            inference_pool: None,
+            replicas: Arc::new(ReplicaBalancer::new()),
+            inflight: Arc::new(DashMap::new()),
             running: Arc::new(RwLock::new(false)),
+            draining: Arc::new(AtomicBool::new(false)),
+            outstanding: Arc::new(AtomicUsize::new(0)),
+            queue_depth: Arc::new(AtomicUsize::new(0)),
+            avg_batch_latency_ms: Arc::new(AtomicU64::new(0)),
+            total_processed: Arc::new(AtomicU64::new(0)),
+            total_cancelled: Arc::new(AtomicU64::new(0)),
         })
     }
 
+    /// 设置死信存储，永久失败的请求将被保存以便重新投递
+    pub fn with_dead_letter_store(mut self, store: Arc<DeadLetterStore>) -> Self {
+        self.dead_letters = Some(store);
+        self
+    }
+
+    /// 设置模型管理器，批次在模型所在的插件上执行，显存不足时逐出同一GPU上的模型后重试
+    ///
+    /// 未设置时（测试、压测）批次由模拟后端按 `plugins.plugin_configs.mock` 执行。
+    /// 解码器调用与插件调用共用插件管理器的推理线程池，模型的逐token解码器随模型加载和卸载自动登记。
+    pub fn with_model_manager(mut self, model_manager: Arc<ModelManager>) -> Self {
+        self.inference_pool = Some(model_manager.plugin_manager().inference_pool());
+        self.decoders = model_manager.decoders();
+        self.model_manager = Some(model_manager);
+        self
+    }
+
     /// 启动批处理器
     pub async fn start(&self) -> Result<()> {
         {
@@ -66,12 +192,6 @@ impl BatchProcessor {
         }
 
         info!("Starting batch processor");
-
-        let processor = self.clone();
-        tokio::spawn(async move {
-            processor.run_batch_loop().await;
-        });
-
         Ok(())
     }
 
@@ -82,36 +202,199 @@ impl BatchProcessor {
             *running = false;
         }
 
+        // 关闭所有模型队列，工作任务处理完剩余请求后退出
+        self.queues.clear();
+
         info!("Stopping batch processor");
         Ok(())
     }
 
+    /// 关闭前排空：拒绝新请求，等待已受理的请求返回结果，最多等待 `timeout`
+    ///
+    /// 全部完成时返回true；超时时仍在排队或执行的请求随之后的 `stop` 被放弃。
+    pub async fn drain(&self, timeout: Duration) -> bool {
+        self.draining.store(true, Ordering::Relaxed);
+        let deadline = Instant::now() + timeout;
+        let pending = self.outstanding.load(Ordering::Relaxed);
+        if pending > 0 {
+            info!("Draining {} outstanding request(s) (up to {:?})", pending, timeout);
+        }
+
+        loop {
+            let pending = self.outstanding.load(Ordering::Relaxed);
+            if pending == 0 {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                warn!("Drain deadline reached with {} request(s) still outstanding", pending);
+                return false;
+            }
+            sleep(DRAIN_POLL_INTERVAL).await;
+        }
+    }
+
+    /// 已受理但尚未返回结果的请求数
+    pub fn outstanding_requests(&self) -> usize {
+        self.outstanding.load(Ordering::Relaxed)
+    }
+
+    /// 移除模型队列（模型卸载时调用）
+    pub fn remove_model_queue(&self, model_id: &ModelId) {
+        if self.queues.remove(model_id).is_some() {
+            debug!("Removed batch queue for model {}", model_id);
+        }
+        self.tuners.remove(model_id);
+        self.bulkheads.remove(model_id);
+        self.mixed_adapters.remove(model_id);
+    }
+
+    /// 设置模型的后端能否在一个批次中为不同请求使用不同的LoRA适配器
+    ///
+    /// 不支持时（默认）每个批次只包含使用同一适配器（或都不使用适配器）的请求。
+    pub fn set_mixed_adapters(&self, model_id: &ModelId, supported: bool) {
+        self.mixed_adapters.insert(model_id.clone(), supported);
+    }
+
+    /// 设置模型同时执行的批次数上限，None表示不限制
+    pub fn set_concurrency_limit(&self, model_id: &ModelId, limit: Option<u32>) {
+        match limit {
+            Some(limit) => {
+                let limit = limit.max(1);
+                if matches!(self.bulkheads.get(model_id), Some(entry) if entry.0 == limit) {
+                    return;
+                }
+                debug!("Limiting model {} to {} concurrent batches", model_id, limit);
+                self.bulkheads.insert(model_id.clone(), (limit, Arc::new(Semaphore::new(limit as usize))));
+            }
+            None => {
+                self.bulkheads.remove(model_id);
+            }
+        }
+    }
+
+    /// 为模型注册逐token解码器
+    ///
+    /// 启用 `continuous_batching` 时，该模型的请求在token边界加入正在运行的批次。
+    /// 设置了模型管理器时由其在模型加载时登记，无需手动调用。
+    pub fn register_decoder(&self, model_id: &ModelId, decoder: Arc<dyn IterativeDecoder>) {
+        self.decoders.insert(model_id.clone(), decoder);
+        // 让下一个请求以新的工作模式重建队列
+        self.remove_model_queue(model_id);
+    }
+
+    /// 注销模型的逐token解码器
+    pub fn unregister_decoder(&self, model_id: &ModelId) {
+        if self.decoders.remove(model_id).is_some() {
+            self.remove_model_queue(model_id);
+        }
+    }
+
     /// 提交批处理请求
+    ///
+    /// 启用 `coalesce_requests` 时，与在途请求指纹相同的确定性请求不再排队，
+    /// 而是等待在途请求的结果。
+    ///
+    /// 丢弃返回的future即取消请求：客户端断开时REST和gRPC服务端会丢弃处理中的future，
+    /// 请求的结果通道随之关闭，尚未执行的请求不再组批，正在解码的序列在下一个token边界释放。
     pub async fn submit_request(
         &self,
         model_id: ModelId,
         input: InputData,
         parameters: PredictionParameters,
+        tenant: Option<&str>,
     ) -> Result<PredictionResponse> {
+        if !self.config.engine.batch_config.coalesce_requests || !parameters.is_deterministic() {
+            return self.execute_request(model_id, input, parameters, tenant).await;
+        }
+
+        let key = request_fingerprint(&model_id, &input, &parameters);
+        let timeout_duration = self.request_timeout(&parameters);
+
+        loop {
+            let mut receiver = match self.inflight.entry(key.clone()) {
+                Entry::Occupied(entry) => entry.get().subscribe(),
+                Entry::Vacant(entry) => {
+                    let (sender, _) = broadcast::channel(1);
+                    entry.insert(sender.clone());
+
+                    let guard = InflightGuard { inflight: &self.inflight, key: &key };
+                    let result = self.execute_request(model_id, input, parameters, tenant).await;
+                    drop(guard);
+
+                    let _ = sender.send(match &result {
+                        Ok(response) => Ok(response.clone()),
+                        Err(e) => Err(e.to_string()),
+                    });
+                    return result;
+                }
+            };
+
+            match timeout(timeout_duration, receiver.recv()).await {
+                Ok(Ok(Ok(mut response))) => {
+                    debug!("Coalesced request served by {}", response.request_id);
+                    response.request_id = new_request_id();
+                    response.metrics.request_id = response.request_id.clone();
+                    response.metadata.custom_metadata
+                        .insert("coalesced".to_string(), serde_json::json!(true));
+                    return Ok(response);
+                }
+                Ok(Ok(Err(message))) => return Err(UniModelError::BatchProcessing(message)),
+                // 领头请求被取消，重新竞争执行
+                Ok(Err(_)) => continue,
+                Err(_) => return Err(UniModelError::internal("Request timeout")),
+            }
+        }
+    }
+
+    /// 请求的有效超时时间（不超过配置的 `timeout_ms`）
+    fn request_timeout(&self, parameters: &PredictionParameters) -> Duration {
+        let timeout_ms = self.config.engine.batch_config.timeout_ms;
+        Duration::from_millis(parameters.timeout_ms.map_or(timeout_ms, |ms| ms.min(timeout_ms)))
+    }
+
+    /// 将请求放入批处理队列并等待结果
+    async fn execute_request(
+        &self,
+        model_id: ModelId,
+        input: InputData,
+        parameters: PredictionParameters,
+        tenant: Option<&str>,
+    ) -> Result<PredictionResponse> {
+        if !*self.running.read().await {
+            return Err(UniModelError::internal("BatchProcessor is not running"));
+        }
+        if self.draining.load(Ordering::Relaxed) {
+            return Err(UniModelError::Overloaded {
+                message: "Server is shutting down".to_string(),
+                hint: self.overload_hint(),
+            });
+        }
+
+        self.admit_request()?;
+        self.outstanding.fetch_add(1, Ordering::Relaxed);
+        let _outstanding = OutstandingGuard(&self.outstanding);
+
         let request_id = new_request_id();
         let (response_sender, response_receiver) = oneshot::channel();
 
+        let timeout_duration = self.request_timeout(&parameters);
+        let submitted_at = Instant::now();
+
         let batch_request = BatchRequest {
             request_id: request_id.clone(),
             model_id,
+            tenant: tenant.map(str::to_string),
             input,
             parameters,
             response_sender,
-            submitted_at: Instant::now(),
+            submitted_at,
+            deadline: submitted_at + timeout_duration,
         };
 
-        self.request_sender
-            .send(batch_request)
-            .map_err(|_| UniModelError::internal("Failed to send batch request"))?;
-
-        let timeout_duration = Duration::from_millis(
-            self.config.engine.batch_config.timeout_ms,
-        );
+        if let Err(e) = self.enqueue(batch_request) {
+            self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+            return Err(e);
+        }
 
         match timeout(timeout_duration, response_receiver).await {
             Ok(Ok(response)) => response,
@@ -120,106 +403,550 @@ impl BatchProcessor {
         }
     }
 
-    /// 批处理主循环
-    async fn run_batch_loop(&self) {
-        let mut interval = tokio::time::interval(Duration::from_millis(10));
+    /// 准入控制：排队请求数达到上限时拒绝新请求
+    fn admit_request(&self) -> Result<()> {
+        let max_queue_size = self.config.engine.batch_config.max_queue_size as usize;
+        let depth = self.queue_depth.fetch_add(1, Ordering::Relaxed);
 
-        while *self.running.read().await {
-            interval.tick().await;
+        if depth >= max_queue_size {
+            self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+            warn!("Batch queue full ({} pending), rejecting request", depth);
+            return Err(UniModelError::Overloaded {
+                message: format!("Batch queue is full ({} pending requests)", depth),
+                hint: self.overload_hint(),
+            });
+        }
+
+        Ok(())
+    }
 
-            self.collect_new_requests().await;
+    /// 将请求放入对应模型的队列，队列已回收时重建一次
+    fn enqueue(&self, request: BatchRequest) -> Result<()> {
+        let model_id = request.model_id.clone();
+        let mut request = request;
 
-            if let Err(e) = self.process_batches().await {
-                error!("Error processing batches: {}", e);
+        for _ in 0..2 {
+            let sender = self.model_queue(&model_id);
+            match sender.try_send(request) {
+                Ok(()) => return Ok(()),
+                Err(mpsc::error::TrySendError::Full(_)) => {
+                    return Err(UniModelError::Overloaded {
+                        message: format!("Batch queue for model {} is full", model_id),
+                        hint: self.overload_hint(),
+                    });
+                }
+                Err(mpsc::error::TrySendError::Closed(returned)) => {
+                    // 工作任务已空闲退出，移除旧队列后重试
+                    self.queues.remove_if(&model_id, |_, tx| tx.same_channel(&sender));
+                    request = returned;
+                }
             }
         }
 
-        info!("Batch processing loop stopped");
+        Err(UniModelError::internal("Failed to send batch request"))
     }
 
-    /// 收集新请求
-    async fn collect_new_requests(&self) {
-        let mut receiver = self.request_receiver.lock().await;
-        let mut pending = self.pending_requests.lock().await;
+    /// 获取模型队列，不存在时创建队列并启动工作任务
+    fn model_queue(&self, model_id: &ModelId) -> mpsc::Sender<BatchRequest> {
+        if let Some(sender) = self.queues.get(model_id) {
+            return sender.clone();
+        }
+
+        self.queues
+            .entry(model_id.clone())
+            .or_insert_with(|| {
+                let capacity = self.config.engine.batch_config.max_queue_size.max(1) as usize;
+                let (sender, receiver) = mpsc::channel(capacity);
+
+                let processor = self.clone();
+                let model_id = model_id.clone();
+                match self.continuous_decoder(&model_id) {
+                    Some(decoder) => {
+                        debug!("Creating continuous batch queue for model {}", model_id);
+                        tokio::spawn(async move {
+                            processor.run_continuous_worker(model_id, decoder, receiver).await;
+                        });
+                    }
+                    None => {
+                        debug!("Creating batch queue for model {}", model_id);
+                        tokio::spawn(async move {
+                            processor.run_model_worker(model_id, receiver).await;
+                        });
+                    }
+                }
 
-        while let Ok(request) = receiver.try_recv() {
-            pending.push_back(request);
+                sender
+            })
+            .clone()
+    }
+
+    /// 单个模型的批处理循环
+    ///
+    /// 由请求到达驱动：队首请求到达后开始计时，凑满 `max_batch_size`
+    /// 或距队首请求提交已过 `max_wait_time_ms` 时立即下发批次。
+    /// 下发顺序由 [`PendingQueue`] 决定（优先级老化与租户加权公平），而非严格先进先出。
+    async fn run_model_worker(&self, model_id: ModelId, mut receiver: mpsc::Receiver<BatchRequest>) {
+        let mut pending = PendingQueue::new(&self.config);
+
+        loop {
+            // 模型登记了解码器后不再接收新请求，下一个请求以连续批处理模式重建队列
+            if self.continuous_decoder(&model_id).is_some() {
+                receiver.close();
+            }
+            if pending.is_empty() {
+                match timeout(MODEL_QUEUE_IDLE_TTL, receiver.recv()).await {
+                    Ok(Some(request)) => pending.push(request),
+                    // 队列已关闭且取空
+                    Ok(None) => break,
+                    Err(_) => {
+                        // 空闲回收：停止接收新请求，已入队的请求仍会被处理
+                        receiver.close();
+                        continue;
+                    }
+                }
+            }
+
+            let (max_batch_size, max_wait_time) = self.batch_limits(&model_id);
+
+            // 取出通道中已到达的请求，使排序覆盖所有等待中的请求
+            while let Ok(request) = receiver.try_recv() {
+                pending.push(request);
+            }
+
+            let deadline = pending
+                .oldest_submitted_at()
+                .map(|submitted_at| submitted_at + max_wait_time)
+                .unwrap_or_else(Instant::now);
+
+            while pending.compute_units() < max_batch_size {
+                tokio::select! {
+                    received = receiver.recv() => match received {
+                        Some(request) => pending.push(request),
+                        None => break,
+                    },
+                    _ = tokio::time::sleep_until(deadline.into()) => break,
+                }
+            }
+
+            // 达到并发上限时在此等待，请求继续在队列中积累
+            let permit = self.acquire_batch_slot(&model_id).await;
+            while let Ok(request) = receiver.try_recv() {
+                pending.push(request);
+            }
+
+            let at_risk_horizon = max_wait_time + self.avg_batch_latency();
+            self.dispatch_batch(&model_id, &mut pending, max_batch_size, at_risk_horizon, permit);
         }
+
+        self.queues.remove_if(&model_id, |_, tx| tx.is_closed());
+        debug!("Batch worker for model {} stopped", model_id);
     }
 
-    /// 处理所有批次
-    async fn process_batches(&self) -> Result<()> {
-        let mut pending = self.pending_requests.lock().await;
+    /// 模型当前的有效批大小与等待时间
+    fn batch_limits(&self, model_id: &ModelId) -> (usize, Duration) {
+        let batch_config = &self.config.engine.batch_config;
+        let tuner = match self.tuner(model_id) {
+            Some(tuner) => tuner,
+            None => {
+                return (
+                    batch_config.max_batch_size.max(1) as usize,
+                    Duration::from_millis(batch_config.max_wait_time_ms),
+                );
+            }
+        };
 
-        if pending.is_empty() {
-            return Ok(());
+        let tuner = tuner.lock();
+        (tuner.batch_size(), tuner.max_wait_time())
+    }
+
+    /// 获取模型的批大小控制器，未配置延迟SLO时返回None
+    fn tuner(&self, model_id: &ModelId) -> Option<Arc<Mutex<BatchTuner>>> {
+        if let Some(tuner) = self.tuners.get(model_id) {
+            return Some(Arc::clone(tuner.value()));
         }
 
-        let mut groups = std::collections::HashMap::new();
-        let mut expired_requests = Vec::new();
+        let tuner = BatchTuner::new(&self.config.engine.batch_config)?;
+        Some(Arc::clone(
+            self.tuners
+                .entry(model_id.clone())
+                .or_insert_with(|| Arc::new(Mutex::new(tuner)))
+                .value(),
+        ))
+    }
+
+    /// 获取模型的批次执行名额，未设置并发上限时返回None
+    async fn acquire_batch_slot(&self, model_id: &ModelId) -> Option<OwnedSemaphorePermit> {
+        let semaphore = self.bulkheads.get(model_id).map(|entry| Arc::clone(&entry.1))?;
+        semaphore.acquire_owned().await.ok()
+    }
 
-        let now = Instant::now();
-        let max_wait_time = Duration::from_millis(
-            self.config.engine.batch_config.max_wait_time_ms,
+    /// 从待处理请求中取出一个批次并异步执行
+    fn dispatch_batch(
+        &self,
+        model_id: &ModelId,
+        pending: &mut PendingQueue,
+        max_batch_size: usize,
+        at_risk_horizon: Duration,
+        permit: Option<OwnedSemaphorePermit>,
+    ) {
+        let mixed_adapters = self.mixed_adapters.get(model_id).is_some_and(|entry| *entry.value());
+        let taken = if mixed_adapters {
+            pending.take(max_batch_size, at_risk_horizon)
+        } else {
+            pending.take_single_adapter(max_batch_size, at_risk_horizon)
+        };
+        self.queue_depth.fetch_sub(taken.len(), Ordering::Relaxed);
+
+        // 跳过调用方已超时或断开的请求
+        let taken_count = taken.len();
+        let batch_requests: Vec<BatchRequest> = taken
+            .into_iter()
+            .filter(|r| !r.response_sender.is_closed())
+            .collect();
+        self.record_cancelled(model_id, taken_count - batch_requests.len());
+        if batch_requests.is_empty() {
+            return;
+        }
+
+        let batch_group = BatchGroup {
+            model_id: model_id.clone(),
+            requests: batch_requests,
+            created_at: Instant::now(),
+        };
+
+        let processor = self.clone();
+        tokio::spawn(async move {
+            if let Err(e) = processor.execute_batch(batch_group).await {
+                error!("Error executing batch: {}", e);
+            }
+            drop(permit);
+        });
+    }
+
+    /// 模型的后端是否支持语法约束解码
+    pub fn supports_grammar(&self, model_id: &ModelId) -> bool {
+        self.continuous_decoder(model_id).is_some_and(|decoder| decoder.supports_grammar())
+    }
+
+    /// 模型是否使用连续批处理
+    fn continuous_decoder(&self, model_id: &ModelId) -> Option<Arc<dyn IterativeDecoder>> {
+        if !self.config.engine.batch_config.continuous_batching {
+            return None;
+        }
+        self.decoders.get(model_id).map(|d| Arc::clone(d.value()))
+    }
+
+    /// 单个模型的连续批处理循环
+    ///
+    /// 每个解码步之间是一个token边界：新请求在此加入批次，
+    /// 已完成的序列立即返回结果并离开，不必等待同批次的其他序列。
+    /// 模型的所有请求都由这一个循环交给同一个解码器，同一会话的请求因此总能复用其KV缓存。
+    async fn run_continuous_worker(
+        &self,
+        model_id: ModelId,
+        decoder: Arc<dyn IterativeDecoder>,
+        mut receiver: mpsc::Receiver<BatchRequest>,
+    ) {
+        let max_batch_size = self.config.engine.batch_config.max_batch_size.max(1) as usize;
+        let mut active: Vec<ActiveSequence> = Vec::new();
+        let mut waiting = PendingQueue::new(&self.config);
+        let batch_config = &self.config.engine.batch_config;
+        let mut sessions = SessionTable::new(
+            Duration::from_secs(batch_config.session_ttl_secs),
+            batch_config.max_sessions,
         );
+        let mut last_sweep = Instant::now();
+
+        loop {
+            // 解码器被注销或替换（模型卸载、重新加载）后不再接收新请求，已受理的请求仍在原解码器上完成
+            if !self.continuous_decoder(&model_id).is_some_and(|current| Arc::ptr_eq(&current, &decoder)) {
+                receiver.close();
+            }
+            if active.is_empty() && waiting.is_empty() {
+                match timeout(MODEL_QUEUE_IDLE_TTL, receiver.recv()).await {
+                    Ok(Some(request)) => waiting.push(request),
+                    Ok(None) => break,
+                    Err(_) => {
+                        receiver.close();
+                        continue;
+                    }
+                }
+            }
+
+            while let Ok(request) = receiver.try_recv() {
+                waiting.push(request);
+            }
+
+            // 多候选生成的序列按候选数或束宽占用批次名额
+            let occupied: usize = active.iter().map(|s| s.request.parameters.compute_units()).sum();
+            let free_slots = max_batch_size.saturating_sub(occupied);
+            for request in waiting.take(free_slots, self.avg_batch_latency()) {
+                self.join_sequence(&decoder, request, &mut active).await;
+            }
+
+            if !active.is_empty() {
+                self.decode_step(&model_id, &decoder, &mut active, &mut sessions).await;
+            }
 
-        while let Some(request) = pending.pop_front() {
-            if now.duration_since(request.submitted_at) > max_wait_time {
-                expired_requests.push(request);
-                continue;
+            if last_sweep.elapsed() >= SESSION_SWEEP_INTERVAL {
+                last_sweep = Instant::now();
+                for session_id in sessions.expire() {
+                    debug!("Session {} expired on model {}", session_id, model_id);
+                    decoder.drop_session(&session_id);
+                }
             }
+        }
 
-            groups.entry(request.model_id.clone())
-                .or_insert_with(Vec::new)
-                .push(request);
+        for session_id in sessions.drain() {
+            decoder.drop_session(&session_id);
         }
+        self.queues.remove_if(&model_id, |_, tx| tx.is_closed());
+        debug!("Continuous batch worker for model {} stopped", model_id);
+    }
 
-        for request in expired_requests {
-            let _ = request
-                .response_sender
-                .send(Err(UniModelError::internal("Request expired")));
+    /// 将请求作为新序列加入正在运行的批次
+    async fn join_sequence(
+        &self,
+        decoder: &Arc<dyn IterativeDecoder>,
+        request: BatchRequest,
+        active: &mut Vec<ActiveSequence>,
+    ) {
+        self.queue_depth.fetch_sub(1, Ordering::Relaxed);
+        if request.response_sender.is_closed() {
+            self.record_cancelled(&request.model_id, 1);
+            return;
         }
 
-        for (model_id, requests) in groups {
-            if let Err(e) = self.process_model_group(model_id, requests).await {
-                error!("Error processing model group: {}", e);
+        let prompt = match &request.input {
+            InputData::Text(text) => text.clone(),
+            _ => {
+                let _ = request.response_sender.send(Err(UniModelError::validation(
+                    "Continuous batching requires text input",
+                )));
+                return;
             }
+        };
+
+        let unsupported = if !request.parameters.logit_bias.is_empty() && !decoder.supports_logit_bias() {
+            Some("logit_bias")
+        } else if request.parameters.logprobs && !decoder.supports_logprobs() {
+            Some("logprobs")
+        } else if request.parameters.compute_units() > 1 && !decoder.supports_multiple_completions() {
+            Some("n or num_beams greater than 1")
+        } else if request.parameters.grammar.is_some() && !decoder.supports_grammar() {
+            Some("grammar")
+        } else if request.parameters.adapter.is_some() && !decoder.supports_adapters() {
+            Some("LoRA adapters")
+        } else {
+            None
+        };
+        if let Some(option) = unsupported {
+            let _ = request.response_sender.send(Err(UniModelError::validation(format!(
+                "This model's backend does not support {}",
+                option
+            ))));
+            return;
         }
 
-        Ok(())
+        let sequence_id = request.request_id.clone();
+        let parameters = request.parameters.clone();
+        let joined = self.run_decoder(decoder, move |d| d.add_sequence(&sequence_id, &prompt, &parameters)).await;
+
+        match joined {
+            Ok(()) => active.push(ActiveSequence {
+                max_tokens: request.parameters.max_tokens.unwrap_or(DEFAULT_MAX_NEW_TOKENS).max(1),
+                request,
+                output: String::new(),
+                tokens_generated: 0,
+                joined_at: Instant::now(),
+                occupancy_sum: 0,
+                steps: 0,
+                stop_sequence: None,
+                logprobs: Vec::new(),
+                candidates: Vec::new(),
+                draft_tokens: 0,
+            }),
+            Err(e) => {
+                let _ = request.response_sender.send(Err(e));
+            }
+        }
     }
 
-    /// 处理模型分组
-    async fn process_model_group(
+    /// 对所有活跃序列执行一步解码，并移出已完成的序列
+    async fn decode_step(
         &self,
-        model_id: ModelId,
-        mut requests: Vec<BatchRequest>,
-    ) -> Result<()> {
-        let max_batch_size = self.config.engine.batch_config.max_batch_size as usize;
-
-        while !requests.is_empty() {
-            let batch_size = std::cmp::min(requests.len(), max_batch_size);
-            let batch_requests = requests.drain(0..batch_size).collect();
-
-            let batch_group = BatchGroup {
-                model_id: model_id.clone(),
-                requests: batch_requests,
-                created_at: Instant::now(),
-            };
+        model_id: &ModelId,
+        decoder: &Arc<dyn IterativeDecoder>,
+        active: &mut Vec<ActiveSequence>,
+        sessions: &mut SessionTable,
+    ) {
+        // 调用方已放弃的序列立即释放，后端不再为其生成token
+        let before = active.len();
+        active.retain(|seq| {
+            let abandoned = seq.request.response_sender.is_closed();
+            if abandoned {
+                debug!("Sequence {} cancelled after {} tokens", seq.request.request_id, seq.tokens_generated);
+                decoder.remove_sequence(&seq.request.request_id);
+            }
+            !abandoned
+        });
+        self.record_cancelled(model_id, before - active.len());
+        if active.is_empty() {
+            return;
+        }
 
-            let processor = self.clone();
-            tokio::spawn(async move {
-                if let Err(e) = processor.execute_batch(batch_group).await {
-                    error!("Error executing batch: {}", e);
+        let sequence_ids: Vec<String> = active.iter().map(|s| s.request.request_id.clone()).collect();
+        let steps: Vec<DecodeStep> = match self.run_decoder(decoder, move |d| d.step(&sequence_ids)).await {
+            Ok(steps) => steps,
+            Err(e) => {
+                error!("Decode step failed for model {}: {}", model_id, e);
+                for seq in active.drain(..) {
+                    decoder.remove_sequence(&seq.request.request_id);
+                    let _ = seq.request.response_sender.send(Err(UniModelError::BatchProcessing(
+                        format!("Decode step failed: {}", e),
+                    )));
                 }
-            });
+                return;
+            }
+        };
+
+        let batch_size = active.len() as u64;
+        let native_stop = decoder.supports_stop_sequences();
+        let mut finished_ids = Vec::new();
+        for step in steps {
+            if let Some(seq) = active.iter_mut().find(|s| s.request.request_id == step.sequence_id) {
+                let stop = &seq.request.parameters.stop;
+                let scan_from = seq.output.len().saturating_sub(stop_scan_overlap(stop));
+                seq.output.push_str(&step.token);
+                seq.tokens_generated += 1 + step.draft_tokens;
+                seq.draft_tokens += step.draft_tokens;
+                seq.occupancy_sum += batch_size;
+                seq.steps += 1;
+                if let Some(logprob) = step.logprob {
+                    seq.logprobs.push(logprob);
+                }
+                if !step.candidates.is_empty() {
+                    seq.candidates = step.candidates;
+                }
+
+                let mut stopped = step.finished;
+                if step.stop_sequence.is_some() {
+                    seq.stop_sequence = step.stop_sequence;
+                } else if !native_stop {
+                    // 停止序列可能跨越多个token，从上次输出末尾回退扫描
+                    if let Some((pos, matched)) = find_stop_sequence(&seq.output, stop, scan_from) {
+                        seq.stop_sequence = Some(matched.to_string());
+                        seq.output.truncate(pos);
+                        stopped = true;
+                    }
+                }
+                if stopped || seq.tokens_generated >= seq.max_tokens {
+                    finished_ids.push((step.sequence_id, stopped));
+                }
+            }
         }
 
-        Ok(())
+        for (sequence_id, stopped) in finished_ids {
+            if let Some(index) = active.iter().position(|s| s.request.request_id == sequence_id) {
+                let seq = active.swap_remove(index);
+                match &seq.request.parameters.session_id {
+                    Some(session_id) => {
+                        decoder.retain_for_session(&sequence_id, session_id);
+                        if let Some(evicted) = sessions.touch(session_id) {
+                            decoder.drop_session(&evicted);
+                        }
+                    }
+                    None => decoder.remove_sequence(&sequence_id),
+                }
+                self.complete_sequence(model_id, seq, stopped);
+            }
+        }
+    }
+
+    /// 返回已完成序列的结果
+    fn complete_sequence(&self, model_id: &ModelId, seq: ActiveSequence, stopped: bool) {
+        let inference_latency = seq.joined_at.elapsed();
+        let total_latency = seq.request.submitted_at.elapsed();
+        self.record_batch_latency(inference_latency, 1);
+
+        let inference_secs = inference_latency.as_secs_f64();
+        let avg_batch_size = seq.occupancy_sum / seq.steps.max(1);
+
+        let mut custom_metadata = std::collections::HashMap::new();
+        custom_metadata.insert(
+            "finish_reason".to_string(),
+            serde_json::json!(if stopped { "stop" } else { "length" }),
+        );
+        if let Some(stop_sequence) = &seq.stop_sequence {
+            custom_metadata.insert("stop_sequence".to_string(), serde_json::json!(stop_sequence));
+        }
+        if seq.request.parameters.logprobs {
+            custom_metadata.insert("logprobs".to_string(), serde_json::json!(seq.logprobs));
+        }
+        if seq.draft_tokens > 0 {
+            custom_metadata.insert("accepted_draft_tokens".to_string(), serde_json::json!(seq.draft_tokens));
+        }
+
+        let output = if seq.request.parameters.completions() > 1 {
+            let stop = &seq.request.parameters.stop;
+            let choices: Vec<_> = std::iter::once(seq.output)
+                .chain(seq.candidates.into_iter().map(|mut text| {
+                    truncate_at_stop(&mut text, stop);
+                    text
+                }))
+                .take(seq.request.parameters.completions() as usize)
+                .enumerate()
+                .map(|(index, text)| serde_json::json!({ "index": index, "text": text }))
+                .collect();
+            OutputData::Json(serde_json::json!({ "choices": choices }))
+        } else {
+            OutputData::Text(seq.output)
+        };
+
+        let response = PredictionResponse {
+            request_id: seq.request.request_id.clone(),
+            model_id: model_id.clone(),
+            output,
+            metadata: ResponseMetadata {
+                model_version: "1.0.0".to_string(),
+                backend: "continuous".to_string(),
+                precision: None,
+                custom_metadata,
+            },
+            metrics: PerformanceMetrics {
+                request_id: seq.request.request_id.clone(),
+                start_time: chrono::Utc::now()
+                    - chrono::Duration::milliseconds(total_latency.as_millis() as i64),
+                end_time: chrono::Utc::now(),
+                total_latency_ms: total_latency.as_millis() as u64,
+                inference_latency_ms: inference_latency.as_millis() as u64,
+                queue_wait_ms: seq.joined_at.duration_since(seq.request.submitted_at).as_millis() as u64,
+                preprocessing_ms: 0,
+                postprocessing_ms: 0,
+                tokens_generated: Some(seq.tokens_generated),
+                tokens_input: None,
+                throughput_tokens_per_sec: (inference_secs > 0.0)
+                    .then(|| seq.tokens_generated as f64 / inference_secs),
+                batch_size: avg_batch_size.max(1) as u32,
+                gpu_utilization: None,
+                memory_usage_mb: None,
+            },
+            timestamp: chrono::Utc::now(),
+        };
+
+        let _ = seq.request.response_sender.send(Ok(response));
     }
 
     /// 执行批次推理
-    async fn execute_batch(&self, batch_group: BatchGroup) -> Result<()> {
+    async fn execute_batch(&self, mut batch_group: BatchGroup) -> Result<()> {
+        // 等待执行名额期间断开的请求不再推理
+        let before = batch_group.requests.len();
+        batch_group.requests.retain(|r| !r.response_sender.is_closed());
+        self.record_cancelled(&batch_group.model_id, before - batch_group.requests.len());
+        if batch_group.requests.is_empty() {
+            return Ok(());
+        }
+
         debug!(
             "Executing batch for model {} with {} requests",
             batch_group.model_id,
@@ -228,30 +955,72 @@ impl BatchProcessor {
 
         let start_time = Instant::now();
 
+        // 把输入从请求中移出组成连续的批次，推理只借用它们，失败时随请求写入死信
+        let batch_size = batch_group.requests.len();
         let batch_inputs: Vec<InputData> = batch_group
             .requests
-            .iter()
-            .map(|req| req.input.clone())
+            .iter_mut()
+            .map(|req| std::mem::replace(&mut req.input, InputData::Text(String::new())))
             .collect();
 
-        sleep(Duration::from_millis(50)).await;
+        let (batch_results, backend, precision) = match self.infer_with_retry(&batch_group, &batch_inputs).await {
+            Ok(results) => results,
+            Err(e) => {
+                for (request, input) in batch_group.requests.into_iter().zip(batch_inputs) {
+                    if let Some(dead_letters) = &self.dead_letters {
+                        dead_letters.record(
+                            request.request_id.clone(),
+                            request.model_id.clone(),
+                            request.tenant.clone(),
+                            input,
+                            request.parameters.clone(),
+                            &e,
+                        ).await;
+                    }
+                    let _ = request.response_sender.send(Err(UniModelError::BatchProcessing(
+                        format!("Batch execution failed: {}", e),
+                    )));
+                }
+                return Err(e);
+            }
+        };
+        let inference_latency = start_time.elapsed();
+        self.record_batch_latency(inference_latency, batch_group.requests.len());
+
+        if let Some(tuner) = self.tuner(&batch_group.model_id) {
+            let mut tuner = tuner.lock();
+            for request in &batch_group.requests {
+                tuner.observe(request.submitted_at.elapsed());
+            }
+        }
+
+        let mut batch_results = batch_results.into_iter();
+        for request in batch_group.requests {
+            let mut output = batch_results
+                .next()
+                .unwrap_or_else(|| OutputData::Text("Error".to_string()));
 
-        let batch_results = self.simulate_batch_inference(&batch_inputs).await?;
-        let end_time = Instant::now();
-        let total_latency = end_time.duration_since(start_time);
+            // 一次性返回完整文本的后端，在服务端按停止序列截断
+            let postprocessing_started = Instant::now();
+            let mut custom_metadata = std::collections::HashMap::new();
+            if let OutputData::Text(text) = &mut output {
+                if let Some(stop_sequence) = truncate_at_stop(text, &request.parameters.stop) {
+                    custom_metadata.insert("finish_reason".to_string(), serde_json::json!("stop"));
+                    custom_metadata.insert("stop_sequence".to_string(), serde_json::json!(stop_sequence));
+                }
+            }
+            let postprocessing = postprocessing_started.elapsed();
+            let total_latency = request.submitted_at.elapsed();
 
-        for (i, request) in batch_group.requests.into_iter().enumerate() {
             let response = PredictionResponse {
                 request_id: request.request_id.clone(),
                 model_id: batch_group.model_id.clone(),
-                output: batch_results
-                    .get(i)
-                    .cloned()
-                    .unwrap_or_else(|| OutputData::Text("Error".to_string())),
+                output,
                 metadata: ResponseMetadata {
                     model_version: "1.0.0".to_string(),
-                    backend: "simulated".to_string(),
-                    custom_metadata: std::collections::HashMap::new(),
+                    backend: backend.clone(),
+                    precision: Some(precision),
+                    custom_metadata,
                 },
                 metrics: PerformanceMetrics {
                     request_id: request.request_id.clone(),
@@ -259,16 +1028,18 @@ impl BatchProcessor {
                         - chrono::Duration::milliseconds(total_latency.as_millis() as i64),
                     end_time: chrono::Utc::now(),
                     total_latency_ms: total_latency.as_millis() as u64,
-                    inference_latency_ms: total_latency.as_millis() as u64,
-                    queue_wait_ms: request.submitted_at.elapsed().as_millis() as u64,
-                    preprocessing_ms: 5,
-                    postprocessing_ms: 5,
+                    inference_latency_ms: inference_latency.as_millis() as u64,
+                    queue_wait_ms: start_time.saturating_duration_since(request.submitted_at).as_millis() as u64,
+                    // 输入由后端直接处理，批处理器没有预处理步骤
+                    preprocessing_ms: 0,
+                    postprocessing_ms: postprocessing.as_millis() as u64,
                     tokens_generated: None,
                     tokens_input: None,
                     throughput_tokens_per_sec: None,
-                    batch_size: batch_inputs.len() as u32,
-                    gpu_utilization: Some(0.75),
-                    memory_usage_mb: Some(1024),
+                    batch_size: batch_size as u32,
+                    // 插件不报告设备使用情况
+                    gpu_utilization: None,
+                    memory_usage_mb: None,
                 },
                 timestamp: chrono::Utc::now(),
             };
@@ -276,35 +1047,213 @@ impl BatchProcessor {
             let _ = request.response_sender.send(Ok(response));
         }
 
-        debug!("Batch execution completed in {:?}", total_latency);
+        debug!("Batch execution completed in {:?}", inference_latency);
         Ok(())
     }
 
-    /// 模拟推理逻辑
-    async fn simulate_batch_inference(&self, inputs: &[InputData]) -> Result<Vec<OutputData>> {
-        let mut results = Vec::new();
+    /// 执行批次推理，瞬时错误按指数退避重试，返回输出和执行的后端
+    ///
+    /// 重试次数用尽、错误不可重试或退避后会超过批次中最早的截止时间时返回错误。
+    /// 显存不足时先逐出同一GPU上最久未访问的未固定模型并立即重试一次。
+    async fn infer_with_retry(
+        &self,
+        batch_group: &BatchGroup,
+        inputs: &[InputData],
+    ) -> Result<(Vec<OutputData>, String, Precision)> {
+        let retry = &self.config.engine.batch_config.retry;
+        let earliest_deadline = batch_group.requests.iter().map(|r| r.deadline).min();
+        let mut attempt = 0;
+        let mut oom_recovered = false;
 
-        for input in inputs {
-            let output = match input {
-                InputData::Text(text) => OutputData::Text(format!("Processed: {}", text)),
-                InputData::Binary(data) => OutputData::Binary(data.clone()),
-                InputData::Json(json) => OutputData::Json(json.clone()),
-                InputData::Multimodal(map) => OutputData::Multimodal(map.clone()),
+        loop {
+            let e = match self.run_batch(batch_group, inputs).await {
+                Ok(results) => return Ok(results),
+                Err(e) => e,
             };
-            results.push(output);
+
+            if matches!(e, UniModelError::OutOfMemory(_)) {
+                if oom_recovered {
+                    return Err(e);
+                }
+                if self.recover_from_oom(&batch_group.model_id).await {
+                    oom_recovered = true;
+                    continue;
+                }
+            }
+
+            let backoff = retry.backoff(attempt);
+            let out_of_time = earliest_deadline.is_some_and(|d| Instant::now() + backoff >= d);
+            if !e.is_retriable() || attempt >= retry.max_retries || out_of_time {
+                return Err(e);
+            }
+
+            attempt += 1;
+            warn!(
+                "Transient error on model {} (attempt {}/{}), retrying in {:?}: {}",
+                batch_group.model_id, attempt, retry.max_retries, backoff, e
+            );
+            sleep(backoff).await;
         }
+    }
+
+    /// 逐出同一GPU上的模型以释放显存，返回是否有模型被逐出
+    async fn recover_from_oom(&self, model_id: &ModelId) -> bool {
+        let model_manager = match &self.model_manager {
+            Some(model_manager) => model_manager,
+            None => return false,
+        };
 
-        Ok(results)
+        match model_manager.evict_for_oom(model_id).await {
+            Some(evicted) => {
+                warn!("Out of memory on model {}, evicted {} and retrying batch", model_id, evicted);
+                metrics::increment_counter!(
+                    "unimodel_oom_evictions_total",
+                    "model_id" => model_id.clone(),
+                    "evicted_model_id" => evicted
+                );
+                true
+            }
+            None => {
+                metrics::increment_counter!("unimodel_oom_unrecovered_total", "model_id" => model_id.clone());
+                false
+            }
+        }
+    }
+
+    /// 在模型所在的插件上执行一次批次推理
+    ///
+    /// 同一批次的请求参数兼容（见 `PredictionParameters::batch_compatible`），参数取自第一个请求。
+    /// 启用故障注入时先按 `chaos.batch` 注入。
+    /// 流水线并行的模型把批次拆成微批次同时提交，由插件在各阶段间流水执行，输出按原顺序拼接。
+    /// 多副本的模型在在途批次最少的副本上执行。返回输出、后端和执行实例的计算精度。
+    async fn run_batch(
+        &self,
+        batch_group: &BatchGroup,
+        inputs: &[InputData],
+    ) -> Result<(Vec<OutputData>, String, Precision)> {
+        if let Some(chaos) = &self.chaos {
+            if let Some(fault) = chaos.inject(ChaosSite::Batch).await {
+                return Err(fault.error(ChaosSite::Batch));
+            }
+        }
+
+        let model_manager = match &self.model_manager {
+            Some(model_manager) => model_manager,
+            None => {
+                let outputs = self.mock.infer_default(inputs).await?;
+                return Ok((outputs, mock_plugin::BACKEND_NAME.to_string(), Precision::FP32));
+            }
+        };
+
+        let model = model_manager.get_model_for_inference(&batch_group.model_id).await?;
+        let lease = self.replicas.acquire(model.instances())
+            .ok_or_else(|| UniModelError::model(format!("Model {} has no loaded instance", batch_group.model_id)))?;
+        let instance = lease.instance();
+        // 混用适配器的批次（只在后端支持多LoRA时组成）逐个输入传递适配器，否则整批使用参数中的适配器
+        let mut adapters: Vec<Option<String>> = batch_group.adapters().into_iter().map(|a| a.map(str::to_string)).collect();
+        if adapters.iter().all(|adapter| *adapter == adapters[0]) {
+            adapters.clear();
+        } else {
+            debug!("Running batch of {} with adapters {:?}", inputs.len(), adapters);
+        }
+        // 启用CUDA graph时用最后一个输入把批次填充到捕获过的批大小，多出的输出丢弃；只有填充时才复制输入
+        let batch_size = inputs.len();
+        let padded_size = model.info.config.cuda_graph_padded_size(batch_size);
+        let inputs: Cow<[InputData]> = match inputs.last() {
+            Some(last) if padded_size > batch_size => {
+                let mut padded = inputs.to_vec();
+                padded.resize(padded_size, last.clone());
+                Cow::Owned(padded)
+            }
+            _ => Cow::Borrowed(inputs),
+        };
+        if let Some(last) = adapters.last().cloned() {
+            adapters.resize(padded_size, last);
+        }
+        let parameters = &batch_group.requests[0].parameters;
+        let micro_batches = model.info.config.micro_batch_ranges(inputs.len());
+        let plugin_manager = model_manager.plugin_manager();
+        let mut outputs = if micro_batches.len() > 1 {
+            let calls = micro_batches.into_iter().map(|range| {
+                let adapters = if adapters.is_empty() { &[][..] } else { &adapters[range.clone()] };
+                plugin_manager.infer_with_adapters(instance, &inputs[range], parameters, adapters)
+            });
+            futures::future::try_join_all(calls).await?.into_iter().flatten().collect()
+        } else {
+            plugin_manager.infer_with_adapters(instance, &inputs, parameters, &adapters).await?
+        };
+        outputs.truncate(batch_size);
+        Ok((outputs, model.info.config.backend, instance.precision))
+    }
+
+    /// 记录调用方断开后放弃的请求
+    fn record_cancelled(&self, model_id: &ModelId, count: usize) {
+        if count == 0 {
+            return;
+        }
+        self.total_cancelled.fetch_add(count as u64, Ordering::Relaxed);
+        metrics::counter!("unimodel_requests_cancelled_total", count as u64, "model_id" => model_id.clone());
+    }
+
+    /// 记录批次执行延迟（简化的滑动平均）
+    fn record_batch_latency(&self, latency: Duration, batch_size: usize) {
+        let latency_ms = latency.as_millis() as u64;
+        let previous = self.avg_batch_latency_ms.load(Ordering::Relaxed);
+        let updated = if previous == 0 {
+            latency_ms
+        } else {
+            (previous * 9 + latency_ms) / 10
+        };
+        self.avg_batch_latency_ms.store(updated, Ordering::Relaxed);
+        self.total_processed.fetch_add(batch_size as u64, Ordering::Relaxed);
+    }
+
+    /// 批次执行延迟的滑动平均
+    fn avg_batch_latency(&self) -> Duration {
+        Duration::from_millis(self.avg_batch_latency_ms.load(Ordering::Relaxed))
+    }
+
+    /// 根据当前队列深度估算排队等待时间，用于过载响应中的退避提示
+    pub fn overload_hint(&self) -> OverloadHint {
+        let batch_config = &self.config.engine.batch_config;
+        let queue_depth = self.queue_depth.load(Ordering::Relaxed);
+
+        let batch_latency_ms = match self.avg_batch_latency_ms.load(Ordering::Relaxed) {
+            0 => batch_config.max_wait_time_ms,
+            latency => latency,
+        };
+        let batches_ahead = queue_depth / batch_config.max_batch_size.max(1) as usize + 1;
+        let estimated_wait_ms = batches_ahead as u64 * batch_latency_ms;
+
+        OverloadHint {
+            retry_after_ms: estimated_wait_ms.max(batch_config.max_wait_time_ms),
+            queue_depth: Some(queue_depth),
+            estimated_wait_ms: Some(estimated_wait_ms),
+        }
+    }
+
+    /// 在推理线程池中调用解码器，避免阻塞异步运行时；未设置模型管理器时使用tokio的阻塞线程池
+    async fn run_decoder<T, F>(&self, decoder: &Arc<dyn IterativeDecoder>, f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&dyn IterativeDecoder) -> Result<T> + Send + 'static,
+    {
+        let decoder = Arc::clone(decoder);
+        match &self.inference_pool {
+            Some(pool) => pool.run(move || f(decoder.as_ref())).await,
+            None => tokio::task::spawn_blocking(move || f(decoder.as_ref()))
+                .await
+                .map_err(|e| UniModelError::internal(format!("Decoder task failed: {}", e)))?,
+        }
     }
 
     /// 获取状态信息
     pub async fn get_batch_stats(&self) -> BatchStats {
-        let pending = self.pending_requests.lock().await;
-
         BatchStats {
-            pending_requests: pending.len(),
+            pending_requests: self.queue_depth.load(Ordering::Relaxed),
             is_running: *self.running.read().await,
-            total_processed: 0,
+            total_processed: self.total_processed.load(Ordering::Relaxed),
+            cancelled_requests: self.total_cancelled.load(Ordering::Relaxed),
             avg_batch_size: 0.0,
             avg_wait_time_ms: 0.0,
         }
@@ -316,10 +1265,25 @@ impl Clone for BatchProcessor {
     fn clone(&self) -> Self {
         Self {
             config: Arc::clone(&self.config),
-            pending_requests: Arc::clone(&self.pending_requests),
-            request_sender: self.request_sender.clone(),
-            request_receiver: Arc::clone(&self.request_receiver),
+            queues: Arc::clone(&self.queues),
+            decoders: Arc::clone(&self.decoders),
+            tuners: Arc::clone(&self.tuners),
+            bulkheads: Arc::clone(&self.bulkheads),
+            mixed_adapters: Arc::clone(&self.mixed_adapters),
+            dead_letters: self.dead_letters.clone(),
+            model_manager: self.model_manager.clone(),
+            mock: Arc::clone(&self.mock),
+            chaos: self.chaos.clone(),
+            inference_pool: self.inference_pool.clone(),
+            replicas: Arc::clone(&self.replicas),
+            inflight: Arc::clone(&self.inflight),
             running: Arc::clone(&self.running),
+            draining: Arc::clone(&self.draining),
+            outstanding: Arc::clone(&self.outstanding),
+            queue_depth: Arc::clone(&self.queue_depth),
+            avg_batch_latency_ms: Arc::clone(&self.avg_batch_latency_ms),
+            total_processed: Arc::clone(&self.total_processed),
+            total_cancelled: Arc::clone(&self.total_cancelled),
         }
     }
 }
@@ -330,6 +1294,7 @@ pub struct BatchStats {
     pub pending_requests: usize,
     pub is_running: bool,
     pub total_processed: u64,
+    pub cancelled_requests: u64,
     pub avg_batch_size: f64,
     pub avg_wait_time_ms: f64,
 }
@@ -339,6 +1304,9 @@ pub struct BatchStats {
 pub struct ResponseMetadata {
     pub model_version: String,
     pub backend: String,
+    /// 执行推理的实例的计算精度，连续批处理的解码器未报告时为空
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub precision: Option<Precision>,
     pub custom_metadata: std::collections::HashMap<String, serde_json::Value>,
 }
 
@@ -0,0 +1,163 @@
+//! 批处理等待队列
+//!
+//! 决定等待中的请求进入批次的顺序：先按老化后的优先级分级；
+//! 同一级别内即将超时的请求按截止时间优先（EDF），
+//! 其余请求按租户加权公平排队（自计时WFQ），避免单个租户独占模型。
+
+use std::cmp::Ordering;
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use crate::domain::service::batch_processor::BatchRequest;
+use crate::infrastructure::configuration::Config;
+use crate::infrastructure::storage::DEFAULT_TENANT;
+
+/// 等待中的请求及其虚拟完成时间
+#[derive(Debug)]
+struct PendingEntry {
+    request:        BatchRequest,
+    virtual_finish: f64,
+}
+
+/// 模型的等待队列
+#[derive(Debug)]
+pub struct PendingQueue {
+    entries:         VecDeque<PendingEntry>, // 按提交顺序排列
+    tenant_weights:  HashMap<String, u32>,
+    last_finish:     HashMap<String, f64>,   // 租户 -> 最近一个请求的虚拟完成时间
+    virtual_time:    f64,
+    aging_ms:        f64,
+}
+
+impl PendingQueue {
+    /// 创建等待队列
+    pub fn new(config: &Config) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            tenant_weights: config.tenants
+                .iter()
+                .map(|(name, tenant)| (name.clone(), tenant.weight.max(1)))
+                .collect(),
+            last_finish: HashMap::new(),
+            virtual_time: 0.0,
+            aging_ms: config.engine.batch_config.priority_aging_ms.max(1) as f64,
+        }
+    }
+
+    /// 加入请求并计算其虚拟完成时间
+    pub fn push(&mut self, request: BatchRequest) {
+        let tenant = request.tenant.clone().unwrap_or_else(|| DEFAULT_TENANT.to_string());
+        let weight = self.tenant_weights.get(&tenant).copied().unwrap_or(1) as f64;
+
+        let start = self.last_finish
+            .get(&tenant)
+            .copied()
+            .unwrap_or(0.0)
+            .max(self.virtual_time);
+        let virtual_finish = start + 1.0 / weight;
+        self.last_finish.insert(tenant, virtual_finish);
+
+        self.entries.push_back(PendingEntry { request, virtual_finish });
+    }
+
+    /// 等待中的请求数
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// 等待中的请求计算量之和
+    pub fn compute_units(&self) -> usize {
+        self.entries.iter().map(|e| e.request.parameters.compute_units()).sum()
+    }
+
+    /// 队列是否为空
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// 最早提交的请求的提交时间
+    pub fn oldest_submitted_at(&self) -> Option<Instant> {
+        self.entries.front().map(|e| e.request.submitted_at)
+    }
+
+    /// 按调度顺序取出计算量合计不超过 `capacity` 的请求，剩余请求保持提交顺序
+    ///
+    /// 后端对整个批次使用同一组参数，只取与排在最前的请求参数兼容的请求
+    /// （见 `PredictionParameters::batch_compatible`），其余请求留待后续批次。
+    /// 计算量按 `PredictionParameters::compute_units` 计（多候选生成占多个序列），
+    /// 队首请求超出容量时仍单独取出，避免大请求被饿死。
+    ///
+    /// 请求每等待 `priority_aging_ms` 提升一个优先级，低优先级请求不会被无限期饿死。
+    /// 截止时间落在 `at_risk_horizon` 之内的请求视为即将超时，按截止时间先行组批。
+    pub fn take(&mut self, capacity: usize, at_risk_horizon: Duration) -> Vec<BatchRequest> {
+        self.take_matching(capacity, at_risk_horizon, false)
+    }
+
+    /// 与 [`take`](Self::take) 相同，但只取与排在最前的请求使用同一LoRA适配器的请求，
+    /// 供不能在一个批次中混用适配器的后端使用；其余请求留待后续批次
+    pub fn take_single_adapter(&mut self, capacity: usize, at_risk_horizon: Duration) -> Vec<BatchRequest> {
+        self.take_matching(capacity, at_risk_horizon, true)
+    }
+
+    fn take_matching(&mut self, capacity: usize, at_risk_horizon: Duration, single_adapter: bool) -> Vec<BatchRequest> {
+        if capacity == 0 || self.entries.is_empty() {
+            return Vec::new();
+        }
+
+        let now = Instant::now();
+        let aging_ms = self.aging_ms;
+        let priority_class = |entry: &PendingEntry| {
+            let waited_ms = now.duration_since(entry.request.submitted_at).as_millis() as f64;
+            (entry.request.parameters.priority.rank() as f64 - waited_ms / aging_ms).floor() as i64
+        };
+
+        let risk_cutoff = now + at_risk_horizon;
+        let fair_order = |a: &PendingEntry, b: &PendingEntry| {
+            let a_at_risk = a.request.deadline <= risk_cutoff;
+            let b_at_risk = b.request.deadline <= risk_cutoff;
+            match (a_at_risk, b_at_risk) {
+                (true, true) => a.request.deadline.cmp(&b.request.deadline),
+                (true, false) => Ordering::Less,
+                (false, true) => Ordering::Greater,
+                (false, false) => a.virtual_finish.total_cmp(&b.virtual_finish),
+            }
+        };
+
+        let mut entries: Vec<PendingEntry> = self.entries.drain(..).collect();
+        entries.sort_by(|a, b| {
+            priority_class(a)
+                .cmp(&priority_class(b))
+                .then_with(|| fair_order(a, b))
+                .then(a.request.submitted_at.cmp(&b.request.submitted_at))
+        });
+
+        let head = entries[0].request.parameters.clone();
+        let (mut entries, deferred): (Vec<PendingEntry>, Vec<PendingEntry>) = entries.into_iter()
+            .partition(|e| {
+                let parameters = &e.request.parameters;
+                parameters.batch_compatible(&head) && (!single_adapter || parameters.adapter == head.adapter)
+            });
+
+        let mut used = 0;
+        let taken = entries.iter()
+            .take_while(|e| {
+                used += e.request.parameters.compute_units();
+                used <= capacity
+            })
+            .count()
+            .max(1);
+        let mut remaining = entries.split_off(taken);
+        remaining.extend(deferred);
+        remaining.sort_by_key(|e| e.request.submitted_at);
+        self.entries.extend(remaining);
+
+        // 系统虚拟时间推进到已服务请求的完成时间
+        if let Some(finish) = entries.iter().map(|e| e.virtual_finish).reduce(f64::max) {
+            self.virtual_time = self.virtual_time.max(finish);
+        }
+        let virtual_time = self.virtual_time;
+        self.last_finish.retain(|_, finish| *finish > virtual_time);
+
+        entries.into_iter().map(|e| e.request).collect()
+    }
+}
@@ -0,0 +1,107 @@
+//! 自适应批大小控制器
+//!
+//! 根据观测到的p95延迟与配置的延迟SLO在线调整模型的有效批大小与等待时间：
+//! 有余量时加性增长，超出SLO时乘性收缩（AIMD）。
+//! 每轮调整只使用上一轮之后的新样本，避免对同一批慢请求重复收缩。
+
+use std::time::Duration;
+
+use tracing::debug;
+
+use crate::common::types::BatchConfig;
+
+/// 每积累多少个样本调整一次
+const SAMPLES_PER_ADJUSTMENT: usize = 20;
+
+/// p95低于SLO的该比例时视为有余量
+const HEADROOM_RATIO: f64 = 0.8;
+
+/// 超出SLO时的收缩比例
+const SHRINK_FACTOR: f64 = 0.75;
+
+/// 单个模型的批大小控制器
+#[derive(Debug, Clone)]
+pub struct BatchTuner {
+    slo_ms:          u64,
+    max_batch_size:  usize,           // 配置上限
+    max_wait_ms:     u64,             // 配置上限
+    batch_size:      usize,           // 当前有效批大小
+    wait_ms:         u64,             // 当前有效等待时间
+    latencies_ms:    Vec<u64>,       // 本轮延迟样本
+}
+
+impl BatchTuner {
+    /// 创建控制器，初始值为配置上限；未配置SLO时返回None
+    pub fn new(config: &BatchConfig) -> Option<Self> {
+        let slo_ms = config.latency_slo_ms?;
+        Some(Self {
+            slo_ms,
+            max_batch_size: config.max_batch_size.max(1) as usize,
+            max_wait_ms: config.max_wait_time_ms,
+            batch_size: config.max_batch_size.max(1) as usize,
+            wait_ms: config.max_wait_time_ms,
+            latencies_ms: Vec::with_capacity(SAMPLES_PER_ADJUSTMENT),
+        })
+    }
+
+    /// 当前有效批大小
+    pub fn batch_size(&self) -> usize {
+        self.batch_size
+    }
+
+    /// 当前有效等待时间
+    pub fn max_wait_time(&self) -> Duration {
+        Duration::from_millis(self.wait_ms)
+    }
+
+    /// 记录一次请求的端到端延迟
+    pub fn observe(&mut self, latency: Duration) {
+        self.latencies_ms.push(latency.as_millis() as u64);
+
+        if self.latencies_ms.len() >= SAMPLES_PER_ADJUSTMENT {
+            self.adjust();
+            self.latencies_ms.clear();
+        }
+    }
+
+    /// 本轮样本的p95延迟
+    pub fn p95_latency_ms(&self) -> Option<u64> {
+        if self.latencies_ms.is_empty() {
+            return None;
+        }
+        let mut sorted = self.latencies_ms.clone();
+        sorted.sort_unstable();
+        let index = ((sorted.len() as f64 * 0.95).ceil() as usize).saturating_sub(1);
+        Some(sorted[index])
+    }
+
+    fn adjust(&mut self) {
+        let p95 = match self.p95_latency_ms() {
+            Some(p95) => p95,
+            None => return,
+        };
+
+        let (batch_size, wait_ms) = if p95 > self.slo_ms {
+            (
+                ((self.batch_size as f64 * SHRINK_FACTOR) as usize).max(1),
+                (self.wait_ms as f64 * SHRINK_FACTOR) as u64,
+            )
+        } else if (p95 as f64) < self.slo_ms as f64 * HEADROOM_RATIO {
+            (
+                (self.batch_size + 1).min(self.max_batch_size),
+                (self.wait_ms + 1).min(self.max_wait_ms),
+            )
+        } else {
+            return;
+        };
+
+        if batch_size != self.batch_size || wait_ms != self.wait_ms {
+            debug!(
+                "Batch limits adjusted (p95 {}ms, SLO {}ms): size {} -> {}, wait {}ms -> {}ms",
+                p95, self.slo_ms, self.batch_size, batch_size, self.wait_ms, wait_ms
+            );
+            self.batch_size = batch_size;
+            self.wait_ms = wait_ms;
+        }
+    }
+}
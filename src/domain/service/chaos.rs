@@ -0,0 +1,137 @@
+//! 故障注入
+//!
+//! 按 `chaos` 配置在批处理执行器和插件管理器中注入延迟和故障。两处各自持有一个注入器，
+//! 设置了种子时每个注入器的抽样序列固定，同样的请求顺序得到同样的故障序列。
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use metrics::increment_counter;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tracing::warn;
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::{ChaosConfig, FaultRates};
+
+/// 注入点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChaosSite {
+    /// 批处理执行器执行批次前
+    Batch,
+    /// 插件管理器加载模型或推理前
+    Plugin,
+}
+
+impl ChaosSite {
+    fn name(self) -> &'static str {
+        match self {
+            ChaosSite::Batch => "batch",
+            ChaosSite::Plugin => "plugin",
+        }
+    }
+}
+
+/// 注入的故障
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Fault {
+    /// 不可重试的插件错误
+    Error,
+    /// 插件崩溃，由调用方决定如何模拟
+    Crash,
+    /// 显存不足
+    OutOfMemory,
+}
+
+impl Fault {
+    fn name(self) -> &'static str {
+        match self {
+            Fault::Error => "error",
+            Fault::Crash => "crash",
+            Fault::OutOfMemory => "oom",
+        }
+    }
+
+    /// 故障对应的错误
+    pub fn error(self, site: ChaosSite) -> UniModelError {
+        let message = format!("Injected {} fault at {}", self.name(), site.name());
+        match self {
+            Fault::Error => UniModelError::plugin(message),
+            Fault::Crash => UniModelError::plugin_unavailable(message),
+            Fault::OutOfMemory => UniModelError::out_of_memory(message),
+        }
+    }
+}
+
+/// 故障注入器
+#[derive(Debug)]
+pub struct ChaosInjector {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+}
+
+impl ChaosInjector {
+    /// 按配置创建注入器，未启用时返回None
+    pub fn new(config: &ChaosConfig) -> Option<Arc<Self>> {
+        if !config.enabled {
+            return None;
+        }
+        warn!("Fault injection is enabled (seed {:?}), do not use this configuration in production", config.seed);
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Some(Arc::new(Self {
+            config: config.clone(),
+            rng: Mutex::new(rng),
+        }))
+    }
+
+    /// 抽样一次：返回要注入的延迟和故障
+    ///
+    /// 每次调用固定消耗两个随机数，注入序列只取决于调用次数。
+    pub fn sample(&self, site: ChaosSite) -> (Option<Duration>, Option<Fault>) {
+        let rates = self.rates(site);
+        let (delay_roll, fault_roll): (f64, f64) = {
+            let mut rng = self.rng.lock();
+            (rng.gen(), rng.gen())
+        };
+
+        let delay = (delay_roll < rates.latency_rate && rates.latency_ms > 0)
+            .then(|| Duration::from_millis(rates.latency_ms));
+        let fault = if fault_roll < rates.error_rate {
+            Some(Fault::Error)
+        } else if fault_roll < rates.error_rate + rates.crash_rate {
+            Some(Fault::Crash)
+        } else if fault_roll < rates.error_rate + rates.crash_rate + rates.oom_rate {
+            Some(Fault::OutOfMemory)
+        } else {
+            None
+        };
+
+        if delay.is_some() {
+            increment_counter!("unimodel_chaos_injections_total", "site" => site.name(), "fault" => "latency");
+        }
+        if let Some(fault) = fault {
+            increment_counter!("unimodel_chaos_injections_total", "site" => site.name(), "fault" => fault.name());
+        }
+        (delay, fault)
+    }
+
+    /// 抽样并等待注入的延迟，返回要注入的故障
+    pub async fn inject(&self, site: ChaosSite) -> Option<Fault> {
+        let (delay, fault) = self.sample(site);
+        if let Some(delay) = delay {
+            tokio::time::sleep(delay).await;
+        }
+        fault
+    }
+
+    fn rates(&self, site: ChaosSite) -> &FaultRates {
+        match site {
+            ChaosSite::Batch => &self.config.batch,
+            ChaosSite::Plugin => &self.config.plugins,
+        }
+    }
+}
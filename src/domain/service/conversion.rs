@@ -0,0 +1,328 @@
+//! 模型格式转换
+//!
+//! 把已注册模型的文件转换为其他后端需要的格式（如PyTorch -> ONNX、HuggingFace -> GGUF）。
+//! 转换器以 [`FormatConverter`] 插件形式注册，转换在阻塞线程池中异步执行，
+//! 作业记录进度；成功后转换产物作为新模型注册到同一租户下，原模型不受影响。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use tracing::{error, info};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::service::ModelManager;
+use crate::infrastructure::configuration::Config;
+
+/// 保留的已结束作业数上限，超出后丢弃最早结束的作业
+const MAX_FINISHED_JOBS: usize = 256;
+
+/// 模型文件格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelFormat {
+    /// PyTorch检查点（`.pt`/`.pth`/`.bin`）
+    PyTorch,
+    /// ONNX
+    Onnx,
+    /// safetensors
+    SafeTensors,
+    /// GGUF（llama.cpp）
+    Gguf,
+    /// HuggingFace模型目录（`config.json` 加权重分片）
+    HuggingFace,
+}
+
+impl ModelFormat {
+    /// 按文件扩展名或目录结构识别格式
+    pub fn detect(path: &Path) -> Option<Self> {
+        if path.is_dir() {
+            return path.join("config.json").is_file().then_some(ModelFormat::HuggingFace);
+        }
+        match path.extension()?.to_str()? {
+            "pt" | "pth" | "bin" => Some(ModelFormat::PyTorch),
+            "onnx" => Some(ModelFormat::Onnx),
+            "safetensors" => Some(ModelFormat::SafeTensors),
+            "gguf" => Some(ModelFormat::Gguf),
+            _ => None,
+        }
+    }
+
+    /// 识别模型文件格式：先检查文件内容，无法识别时再按扩展名判断
+    pub fn inspect(path: &Path) -> Option<Self> {
+        crate::infrastructure::model_format::sniff_format(path).or_else(|| Self::detect(path))
+    }
+
+    /// 小写名称
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelFormat::PyTorch => "pytorch",
+            ModelFormat::Onnx => "onnx",
+            ModelFormat::SafeTensors => "safetensors",
+            ModelFormat::Gguf => "gguf",
+            ModelFormat::HuggingFace => "huggingface",
+        }
+    }
+
+    /// 转换产物的文件扩展名，目录格式返回None
+    pub fn extension(&self) -> Option<&'static str> {
+        match self {
+            ModelFormat::PyTorch => Some("pt"),
+            ModelFormat::Onnx => Some("onnx"),
+            ModelFormat::SafeTensors => Some("safetensors"),
+            ModelFormat::Gguf => Some("gguf"),
+            ModelFormat::HuggingFace => None,
+        }
+    }
+
+    /// 加载该格式的默认后端
+    pub fn default_backend(&self) -> &'static str {
+        match self {
+            ModelFormat::Onnx => "onnx",
+            ModelFormat::Gguf => "gguf",
+            ModelFormat::PyTorch | ModelFormat::SafeTensors | ModelFormat::HuggingFace => "pytorch",
+        }
+    }
+}
+
+/// 格式转换器插件
+pub trait FormatConverter: Send + Sync + std::fmt::Debug {
+    /// 转换器名称
+    fn name(&self) -> &str;
+
+    /// 能否把 `from` 格式转换为 `to` 格式
+    fn converts(&self, from: ModelFormat, to: ModelFormat) -> bool;
+
+    /// 执行转换，`progress` 接收 0.0-1.0 的进度
+    ///
+    /// 在阻塞线程池中调用；`options` 为请求中的转换器参数（如ONNX的opset、GGUF的量化类型）。
+    fn convert(
+        &self,
+        input: &Path,
+        output: &Path,
+        options: &serde_json::Value,
+        progress: &dyn Fn(f32),
+    ) -> Result<()>;
+}
+
+/// 转换作业状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// 转换请求
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConversionRequest {
+    pub target_format: ModelFormat,
+    /// 转换后模型的名称，默认为 `<原名称>-<目标格式>`
+    pub name: Option<String>,
+    /// 转换后模型的后端，默认按目标格式选择
+    pub backend: Option<String>,
+    /// 转换器参数
+    #[serde(default)]
+    pub options: serde_json::Value,
+}
+
+/// 转换作业
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversionJob {
+    pub id: String,
+    pub model_id: ModelId,
+    #[serde(skip)]
+    pub tenant: Option<String>,
+    pub source_format: ModelFormat,
+    pub target_format: ModelFormat,
+    pub converter: String,
+    pub status: ConversionStatus,
+    /// 0.0-1.0
+    pub progress: f32,
+    pub output_path: String,
+    /// 转换成功后注册的新模型
+    pub converted_model_id: Option<ModelId>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// 格式转换管理器
+#[derive(Debug)]
+pub struct ConversionManager {
+    model_manager: Arc<ModelManager>,
+    converters:    RwLock<Vec<Arc<dyn FormatConverter>>>,
+    jobs:          Arc<DashMap<String, ConversionJob>>,
+    output_dir:    PathBuf,
+}
+
+impl ConversionManager {
+    /// 创建转换管理器，产物写入 `model_storage_path/converted`
+    pub fn new(model_manager: Arc<ModelManager>, config: &Config) -> Self {
+        Self {
+            model_manager,
+            converters: RwLock::new(Vec::new()),
+            jobs: Arc::new(DashMap::new()),
+            output_dir: Path::new(&config.storage.model_storage_path).join("converted"),
+        }
+    }
+
+    /// 注册转换器，先注册的转换器优先
+    pub fn register_converter(&self, converter: Arc<dyn FormatConverter>) {
+        info!("Registered model converter '{}'", converter.name());
+        self.converters.write().push(converter);
+    }
+
+    /// 为模型启动转换作业
+    pub async fn start(
+        &self,
+        model_id: &ModelId,
+        request: ConversionRequest,
+    ) -> Result<ConversionJob> {
+        let info = self.model_manager.get_model_info(model_id).await?;
+        let source = Path::new(&info.config.model_path);
+        let source_format = ModelFormat::detect(source).ok_or_else(|| {
+            UniModelError::validation(format!("Cannot detect the format of '{}'", info.config.model_path))
+        })?;
+        let target_format = request.target_format;
+        if source_format == target_format {
+            return Err(UniModelError::validation(format!("Model is already in {} format", target_format.name())));
+        }
+        let converter = self.converters.read()
+            .iter()
+            .find(|c| c.converts(source_format, target_format))
+            .cloned()
+            .ok_or_else(|| UniModelError::validation(format!(
+                "No converter from {} to {}",
+                source_format.name(),
+                target_format.name()
+            )))?;
+
+        let id = uuid::Uuid::new_v4().to_string();
+        let output = match target_format.extension() {
+            Some(extension) => self.output_dir.join(format!("{}.{}", id, extension)),
+            None => self.output_dir.join(&id),
+        };
+        let job = ConversionJob {
+            id: id.clone(),
+            model_id: model_id.clone(),
+            tenant: info.tenant.clone(),
+            source_format,
+            target_format,
+            converter: converter.name().to_string(),
+            status: ConversionStatus::Pending,
+            progress: 0.0,
+            output_path: output.display().to_string(),
+            converted_model_id: None,
+            error: None,
+            created_at: Utc::now(),
+            finished_at: None,
+        };
+        self.prune_finished();
+        self.jobs.insert(id.clone(), job.clone());
+        info!(
+            "Conversion {} started: model {} from {} to {} with '{}'",
+            id, model_id, source_format.name(), target_format.name(), job.converter
+        );
+
+        let jobs = Arc::clone(&self.jobs);
+        let model_manager = Arc::clone(&self.model_manager);
+        let output_dir = self.output_dir.clone();
+        let input = source.to_path_buf();
+        tokio::spawn(async move {
+            set_status(&jobs, &id, ConversionStatus::Running);
+
+            let progress_jobs = Arc::clone(&jobs);
+            let progress_id = id.clone();
+            let options = request.options.clone();
+            let converted = tokio::task::spawn_blocking(move || -> Result<PathBuf> {
+                std::fs::create_dir_all(&output_dir)?;
+                let progress = |value: f32| {
+                    if let Some(mut job) = progress_jobs.get_mut(&progress_id) {
+                        job.progress = value.clamp(0.0, 1.0);
+                    }
+                };
+                converter.convert(&input, &output, &options, &progress)?;
+                Ok(output)
+            })
+            .await
+            .map_err(|e| UniModelError::internal(format!("Conversion task failed: {}", e)))
+            .and_then(|converted| converted);
+
+            // 转换产物作为新模型注册，沿用原模型的设备与批处理配置
+            let registered = match converted {
+                Ok(output) => {
+                    let mut config = info.config.clone();
+                    config.model_path = output.display().to_string();
+                    config.backend = request.backend.unwrap_or_else(|| target_format.default_backend().to_string());
+                    config.optimization.quantization = None;
+                    config.custom_params.insert("converted_from".to_string(), serde_json::json!(info.id));
+                    let name = request.name.unwrap_or_else(|| format!("{}-{}", info.name, target_format.name()));
+                    model_manager.register_model(name, info.model_type.clone(), config, info.tenant.clone()).await
+                }
+                Err(e) => Err(e),
+            };
+
+            if let Some(mut job) = jobs.get_mut(&id) {
+                job.finished_at = Some(Utc::now());
+                match registered {
+                    Ok(converted_model_id) => {
+                        info!("Conversion {} completed, registered model {}", id, converted_model_id);
+                        job.status = ConversionStatus::Completed;
+                        job.progress = 1.0;
+                        job.converted_model_id = Some(converted_model_id);
+                    }
+                    Err(e) => {
+                        error!("Conversion {} failed: {}", id, e);
+                        job.status = ConversionStatus::Failed;
+                        job.error = Some(e.to_string());
+                    }
+                }
+            }
+        });
+
+        Ok(job)
+    }
+
+    /// 获取作业
+    pub fn job(&self, job_id: &str) -> Option<ConversionJob> {
+        self.jobs.get(job_id).map(|job| job.clone())
+    }
+
+    /// 模型的所有作业（最新的在前）
+    pub fn jobs_for(&self, model_id: &ModelId) -> Vec<ConversionJob> {
+        let mut jobs: Vec<ConversionJob> = self.jobs.iter()
+            .filter(|job| &job.model_id == model_id)
+            .map(|job| job.clone())
+            .collect();
+        jobs.sort_by_key(|job| std::cmp::Reverse(job.created_at));
+        jobs
+    }
+
+    /// 丢弃最早结束的作业，使已结束作业数不超过上限
+    fn prune_finished(&self) {
+        let mut finished: Vec<(DateTime<Utc>, String)> = self.jobs.iter()
+            .filter_map(|job| job.finished_at.map(|at| (at, job.id.clone())))
+            .collect();
+        if finished.len() < MAX_FINISHED_JOBS {
+            return;
+        }
+        finished.sort();
+        let excess = finished.len() + 1 - MAX_FINISHED_JOBS;
+        for (_, id) in finished.into_iter().take(excess) {
+            self.jobs.remove(&id);
+        }
+    }
+}
+
+fn set_status(jobs: &DashMap<String, ConversionJob>, id: &str, status: ConversionStatus) {
+    if let Some(mut job) = jobs.get_mut(id) {
+        job.status = status;
+    }
+}
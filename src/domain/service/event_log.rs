@@ -0,0 +1,60 @@
+//! 模型事件日志
+//!
+//! 在内存中保留最近的模型生命周期事件，供事件API查询。
+
+use std::collections::VecDeque;
+
+use parking_lot::Mutex;
+use tracing::debug;
+
+use crate::domain::model::ModelEvent;
+
+/// 默认保留的事件数
+pub const DEFAULT_EVENT_CAPACITY: usize = 1000;
+
+/// 事件日志
+#[derive(Debug)]
+pub struct EventLog {
+    capacity: usize,
+    events:   Mutex<VecDeque<ModelEvent>>,
+}
+
+impl EventLog {
+    /// 创建事件日志，超过容量时丢弃最早的事件
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            events: Mutex::new(VecDeque::with_capacity(capacity)),
+        }
+    }
+
+    /// 记录事件
+    pub fn record(&self, event: ModelEvent) {
+        debug!("Model event {:?} for {}", event.kind, event.model_id);
+
+        let mut events = self.events.lock();
+        if events.len() >= self.capacity {
+            events.pop_front();
+        }
+        events.push_back(event);
+    }
+
+    /// 列出事件（最新的在前），可按模型过滤
+    pub fn list(&self, model_id: Option<&str>, limit: usize) -> Vec<ModelEvent> {
+        self.events
+            .lock()
+            .iter()
+            .rev()
+            .filter(|e| model_id.is_none_or(|m| e.model_id == m))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(DEFAULT_EVENT_CAPACITY)
+    }
+}
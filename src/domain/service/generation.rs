@@ -0,0 +1,206 @@
+//! 文本生成控制
+
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::common::types::{TokenLogprob, TopLogprob};
+
+/// 单次请求最多的停止序列数
+pub const MAX_STOP_SEQUENCES: usize = 16;
+
+/// 单次请求最多的logit偏置项数
+pub const MAX_LOGIT_BIAS_ENTRIES: usize = 300;
+
+/// logit偏置的绝对值上限
+pub const MAX_LOGIT_BIAS: f32 = 100.0;
+
+/// 每个位置最多返回的候选token数
+pub const MAX_TOP_LOGPROBS: u32 = 20;
+
+/// 单次请求最多返回的候选补全数，同时也是束宽上限
+pub const MAX_COMPLETIONS: u32 = 16;
+
+/// 从字节位置 `from` 起查找最早出现的停止序列，返回匹配位置和命中的序列
+///
+/// 流式扫描时 `from` 取上次扫描末尾往前回退最长停止序列长度，以覆盖跨token的匹配。
+pub fn find_stop_sequence<'a>(text: &str, stop: &'a [String], from: usize) -> Option<(usize, &'a str)> {
+    let mut from = from.min(text.len());
+    while !text.is_char_boundary(from) {
+        from -= 1;
+    }
+    stop.iter()
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| text[from..].find(s.as_str()).map(|pos| (from + pos, s.as_str())))
+        .min_by_key(|&(pos, _)| pos)
+}
+
+/// 在第一个停止序列处截断完整输出（不含停止序列本身），返回命中的序列
+pub fn truncate_at_stop(text: &mut String, stop: &[String]) -> Option<String> {
+    let (pos, matched) = find_stop_sequence(text, stop, 0)?;
+    let matched = matched.to_string();
+    text.truncate(pos);
+    Some(matched)
+}
+
+/// 流式扫描的回退长度：最长停止序列的字节数减一
+pub fn stop_scan_overlap(stop: &[String]) -> usize {
+    stop.iter().map(String::len).max().unwrap_or(0).saturating_sub(1)
+}
+
+/// 把偏置加到logits上，供后端在采样前调用；偏置为 `-MAX_LOGIT_BIAS` 的token被完全屏蔽
+pub fn apply_logit_bias(logits: &mut [f32], bias: &HashMap<u32, f32>) {
+    for (&token, &bias) in bias {
+        if let Some(logit) = logits.get_mut(token as usize) {
+            *logit = if bias <= -MAX_LOGIT_BIAS { f32::NEG_INFINITY } else { *logit + bias };
+        }
+    }
+}
+
+/// 由一步解码的logits计算对数概率及概率最高的 `top` 个候选，供后端填充 `DecodeStep::logprob`
+///
+/// `token_text` 把token ID转换为文本。
+pub fn token_logprob(
+    logits: &[f32],
+    token: u32,
+    top: usize,
+    token_text: impl Fn(u32) -> String,
+) -> TokenLogprob {
+    let max = logits.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let log_sum = logits.iter().map(|&l| (l - max).exp()).sum::<f32>().ln() + max;
+    let logprob = |id: usize| logits.get(id).map_or(f32::NEG_INFINITY, |&l| l - log_sum);
+
+    let mut ranked: Vec<usize> = (0..logits.len()).collect();
+    ranked.sort_by(|&a, &b| logits[b].total_cmp(&logits[a]));
+    TokenLogprob {
+        token: token_text(token),
+        logprob: logprob(token as usize),
+        top_logprobs: ranked.into_iter()
+            .take(top)
+            .map(|id| TopLogprob {
+                token: token_text(id as u32),
+                logprob: logprob(id),
+            })
+            .collect(),
+    }
+}
+
+/// 投机采样的接受判定：草稿token以 `min(1, p_target / p_draft)` 的概率被接受
+///
+/// `uniform` 为 [0, 1) 上的均匀随机数；贪心解码时草稿token与目标模型argmax相同即接受。
+pub fn accept_draft(draft_prob: f32, target_prob: f32, uniform: f32) -> bool {
+    if draft_prob <= 0.0 {
+        return target_prob > 0.0;
+    }
+    uniform < (target_prob / draft_prob).min(1.0)
+}
+
+/// 草稿token被拒绝后的修正分布 `max(0, p_target - p_draft)` 归一化，保证输出分布与目标模型一致
+///
+/// 两个分布相同时退化为目标分布。
+pub fn residual_distribution(target_probs: &[f32], draft_probs: &[f32]) -> Vec<f32> {
+    let mut residual: Vec<f32> = target_probs.iter()
+        .enumerate()
+        .map(|(id, &p)| (p - draft_probs.get(id).copied().unwrap_or(0.0)).max(0.0))
+        .collect();
+    let total: f32 = residual.iter().sum();
+    if total <= 0.0 {
+        return target_probs.to_vec();
+    }
+    for p in &mut residual {
+        *p /= total;
+    }
+    residual
+}
+
+/// 取logits最大的token
+pub fn argmax(logits: &[f32]) -> u32 {
+    logits.iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(id, _)| id as u32)
+}
+
+/// 供在Rust侧采样的后端使用：温度不大于0时取argmax，否则温度缩放后按top-k、top-p截断并采样
+///
+/// `top_k` 为0表示不截断，被屏蔽（负无穷）的token不参与采样。
+pub fn sample_token(logits: &[f32], temperature: f32, top_k: usize, top_p: f32, rng: &mut impl Rng) -> u32 {
+    if temperature <= 0.0 {
+        return argmax(logits);
+    }
+    let kept = nucleus(logits, temperature, top_k, top_p);
+    let kept_total: f32 = kept.iter().map(|&(_, p)| p).sum();
+    let mut target = rng.gen::<f32>() * kept_total;
+    for &(token, p) in &kept {
+        if target < p {
+            return token;
+        }
+        target -= p;
+    }
+    kept.last().map_or_else(|| argmax(logits), |&(token, _)| token)
+}
+
+/// [`sample_token`] 采样所用的分布，展开为整个词表上的概率，供投机解码的接受判定和修正采样
+///
+/// 温度不大于0时全部概率落在argmax上。
+pub fn sampling_distribution(logits: &[f32], temperature: f32, top_k: usize, top_p: f32) -> Vec<f32> {
+    let mut probs = vec![0.0; logits.len()];
+    let kept = if temperature > 0.0 { nucleus(logits, temperature, top_k, top_p) } else { Vec::new() };
+    let kept_total: f32 = kept.iter().map(|&(_, p)| p).sum();
+    if kept_total <= 0.0 {
+        if let Some(p) = probs.get_mut(argmax(logits) as usize) {
+            *p = 1.0;
+        }
+        return probs;
+    }
+    for (token, p) in kept {
+        probs[token as usize] = p / kept_total;
+    }
+    probs
+}
+
+/// 从概率分布中采样一个token
+pub fn sample_distribution(probs: &[f32], rng: &mut impl Rng) -> u32 {
+    let mut target = rng.gen::<f32>() * probs.iter().sum::<f32>();
+    for (token, &p) in probs.iter().enumerate() {
+        if p > 0.0 && target < p {
+            return token as u32;
+        }
+        target -= p;
+    }
+    argmax(probs)
+}
+
+/// 温度缩放并按top-k、top-p截断后保留的token及其概率（未按保留部分重新归一化），按概率从高到低
+fn nucleus(logits: &[f32], temperature: f32, top_k: usize, top_p: f32) -> Vec<(u32, f32)> {
+    let mut candidates: Vec<(u32, f32)> = logits.iter()
+        .enumerate()
+        .filter(|(_, l)| l.is_finite())
+        .map(|(id, &l)| (id as u32, l / temperature))
+        .collect();
+    if candidates.is_empty() {
+        return candidates;
+    }
+    candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+    if top_k > 0 {
+        candidates.truncate(top_k);
+    }
+
+    let max = candidates[0].1;
+    for candidate in &mut candidates {
+        candidate.1 = (candidate.1 - max).exp();
+    }
+    let total: f32 = candidates.iter().map(|&(_, p)| p).sum();
+    let mut cumulative = 0.0;
+    let keep = candidates.iter()
+        .position(|&(_, l)| {
+            cumulative += l / total;
+            cumulative >= top_p
+        })
+        .map_or(candidates.len(), |i| i + 1);
+    candidates.truncate(keep);
+    for candidate in &mut candidates {
+        candidate.1 /= total;
+    }
+    candidates
+}
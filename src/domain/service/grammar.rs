@@ -0,0 +1,551 @@
+//! 语法约束解码
+//!
+//! 解析GBNF（llama.cpp的语法格式，兼容常见EBNF写法），并在采样时按语法屏蔽
+//! 不能接在当前输出之后的token。规则形如 `name ::= 备选 | 备选`，支持字符串字面量、
+//! 字符类 `[a-z]` / `[^"]`、任意字符 `.`、分组和 `*` `+` `?` 重复，`#` 开始注释；
+//! 入口规则为 `root`。左递归规则会被拒绝。
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::common::error::*;
+
+/// 单次请求语法文本的最大字节数
+pub const MAX_GRAMMAR_BYTES: usize = 32 * 1024;
+
+/// 入口规则名
+const ROOT_RULE: &str = "root";
+
+/// 规则中的元素
+#[derive(Debug, Clone, PartialEq)]
+enum Element {
+    /// 匹配一个字符；`negated` 时匹配不在范围内的字符，空范围取反即任意字符
+    Char { ranges: Vec<(char, char)>, negated: bool },
+    /// 引用规则
+    Rule(usize),
+}
+
+impl Element {
+    fn literal(c: char) -> Self {
+        Element::Char { ranges: vec![(c, c)], negated: false }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            Element::Char { ranges, negated } => ranges.iter().any(|&(lo, hi)| lo <= c && c <= hi) != *negated,
+            Element::Rule(_) => false,
+        }
+    }
+}
+
+/// 解析后的语法：每条规则是若干备选，每个备选是元素序列
+#[derive(Debug, Clone)]
+pub struct Grammar {
+    rules: Vec<Vec<Vec<Element>>>,
+    names: Vec<String>,
+    root: usize,
+}
+
+impl Grammar {
+    /// 解析GBNF语法文本，语法错误、未定义的规则和左递归返回校验错误
+    pub fn parse(source: &str) -> Result<Self> {
+        if source.len() > MAX_GRAMMAR_BYTES {
+            return Err(UniModelError::validation(format!(
+                "Grammar is {} bytes, more than the limit of {}",
+                source.len(), MAX_GRAMMAR_BYTES
+            )));
+        }
+        let mut parser = Parser {
+            chars: source.chars().collect(),
+            pos: 0,
+            ids: HashMap::new(),
+            names: Vec::new(),
+            rules: Vec::new(),
+        };
+        parser.parse()?;
+
+        let mut rules = Vec::with_capacity(parser.rules.len());
+        for (id, rule) in parser.rules.into_iter().enumerate() {
+            match rule {
+                Some(rule) => rules.push(rule),
+                None => {
+                    return Err(UniModelError::validation(format!(
+                        "Grammar rule '{}' is referenced but not defined",
+                        parser.names[id]
+                    )));
+                }
+            }
+        }
+        let root = parser.ids.get(ROOT_RULE).copied()
+            .ok_or_else(|| UniModelError::validation("Grammar must define a 'root' rule"))?;
+
+        let grammar = Self {
+            rules,
+            names: parser.names,
+            root,
+        };
+        grammar.check_left_recursion()?;
+        Ok(grammar)
+    }
+
+    /// 可以匹配空串的规则
+    fn nullable_rules(&self) -> Vec<bool> {
+        let mut nullable = vec![false; self.rules.len()];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, rule) in self.rules.iter().enumerate() {
+                if !nullable[id] && rule.iter().any(|alt| alt.iter().all(|e| matches!(e, Element::Rule(r) if nullable[*r]))) {
+                    nullable[id] = true;
+                    changed = true;
+                }
+            }
+        }
+        nullable
+    }
+
+    /// 左递归会让匹配时的展开无法终止，解析时直接拒绝
+    fn check_left_recursion(&self) -> Result<()> {
+        let nullable = self.nullable_rules();
+        let leftmost: Vec<Vec<usize>> = self.rules.iter()
+            .map(|rule| {
+                let mut refs = Vec::new();
+                for alt in rule {
+                    for element in alt {
+                        match element {
+                            Element::Rule(r) => {
+                                refs.push(*r);
+                                if !nullable[*r] {
+                                    break;
+                                }
+                            }
+                            Element::Char { .. } => break,
+                        }
+                    }
+                }
+                refs
+            })
+            .collect();
+
+        // 0: 未访问，1: 在当前路径上，2: 已完成
+        let mut state = vec![0u8; self.rules.len()];
+        fn visit(rule: usize, leftmost: &[Vec<usize>], state: &mut [u8]) -> Option<usize> {
+            state[rule] = 1;
+            for &next in &leftmost[rule] {
+                match state[next] {
+                    1 => return Some(next),
+                    0 => {
+                        if let Some(cycle) = visit(next, leftmost, state) {
+                            return Some(cycle);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            state[rule] = 2;
+            None
+        }
+        for rule in 0..self.rules.len() {
+            if state[rule] == 0 {
+                if let Some(cycle) = visit(rule, &leftmost, &mut state) {
+                    return Err(UniModelError::validation(format!(
+                        "Grammar rule '{}' is left-recursive",
+                        self.names[cycle]
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 备选中的匹配位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct Position {
+    rule: usize,
+    alt: usize,
+    index: usize,
+}
+
+/// 语法匹配状态
+///
+/// 维护所有可能的解析栈（非确定下推自动机），每个栈顶都指向下一个待匹配的字符元素；
+/// 空栈表示输出已完整匹配 `root`。
+#[derive(Debug, Clone)]
+pub struct GrammarMatcher {
+    grammar: Arc<Grammar>,
+    stacks: Vec<Vec<Position>>,
+}
+
+impl GrammarMatcher {
+    /// 从 `root` 规则开始匹配
+    pub fn new(grammar: Arc<Grammar>) -> Self {
+        let mut stacks = HashSet::new();
+        for alt in 0..grammar.rules[grammar.root].len() {
+            let stack = vec![Position { rule: grammar.root, alt, index: 0 }];
+            expand(&grammar, stack, &mut stacks);
+        }
+        Self {
+            grammar,
+            stacks: stacks.into_iter().collect(),
+        }
+    }
+
+    /// 接受一段生成的文本，不符合语法时返回false且状态不变
+    pub fn accept(&mut self, text: &str) -> bool {
+        let mut stacks = self.stacks.clone();
+        for c in text.chars() {
+            let mut next = HashSet::new();
+            for stack in &stacks {
+                let position = match stack.last() {
+                    Some(position) => *position,
+                    None => continue,
+                };
+                if self.grammar.rules[position.rule][position.alt][position.index].matches(c) {
+                    let mut stack = stack.clone();
+                    if let Some(top) = stack.last_mut() {
+                        top.index += 1;
+                    }
+                    expand(&self.grammar, stack, &mut next);
+                }
+            }
+            if next.is_empty() {
+                return false;
+            }
+            stacks = next.into_iter().collect();
+        }
+        self.stacks = stacks;
+        true
+    }
+
+    /// 文本能否接在当前输出之后
+    pub fn allows(&self, text: &str) -> bool {
+        self.clone().accept(text)
+    }
+
+    /// 当前输出是否已完整匹配语法，此时才允许结束生成
+    pub fn is_complete(&self) -> bool {
+        self.stacks.iter().any(Vec::is_empty)
+    }
+}
+
+/// 把栈展开到栈顶为字符元素：弹出已匹配完的备选，规则引用按其各个备选分叉
+fn expand(grammar: &Grammar, mut stack: Vec<Position>, out: &mut HashSet<Vec<Position>>) {
+    pop_finished(grammar, &mut stack);
+    let position = match stack.last() {
+        Some(position) => *position,
+        None => {
+            out.insert(stack);
+            return;
+        }
+    };
+    match &grammar.rules[position.rule][position.alt][position.index] {
+        Element::Char { .. } => {
+            out.insert(stack);
+        }
+        Element::Rule(rule) => {
+            // 先推进调用位置，右递归（如重复）不会让栈无限增长
+            if let Some(top) = stack.last_mut() {
+                top.index += 1;
+            }
+            pop_finished(grammar, &mut stack);
+            for alt in 0..grammar.rules[*rule].len() {
+                let mut next = stack.clone();
+                next.push(Position { rule: *rule, alt, index: 0 });
+                expand(grammar, next, out);
+            }
+        }
+    }
+}
+
+fn pop_finished(grammar: &Grammar, stack: &mut Vec<Position>) {
+    while let Some(top) = stack.last() {
+        if top.index < grammar.rules[top.rule][top.alt].len() {
+            break;
+        }
+        stack.pop();
+    }
+}
+
+/// 按语法屏蔽logits，供后端在采样前调用
+///
+/// `token_text` 返回token解码后的文本；结束token只在语法已完整匹配时允许，
+/// 解码为空的其他token被屏蔽。
+pub fn apply_grammar_mask(
+    logits: &mut [f32],
+    matcher: &GrammarMatcher,
+    token_text: impl Fn(u32) -> String,
+    eos_token: Option<u32>,
+) {
+    let complete = matcher.is_complete();
+    for (token, logit) in logits.iter_mut().enumerate() {
+        let token = token as u32;
+        let allowed = if Some(token) == eos_token {
+            complete
+        } else {
+            let text = token_text(token);
+            !text.is_empty() && matcher.allows(&text)
+        };
+        if !allowed {
+            *logit = f32::NEG_INFINITY;
+        }
+    }
+}
+
+/// GBNF递归下降解析器
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    ids: HashMap<String, usize>,
+    names: Vec<String>,
+    /// 尚未定义的规则为None
+    rules: Vec<Option<Vec<Vec<Element>>>>,
+}
+
+impl Parser {
+    fn parse(&mut self) -> Result<()> {
+        loop {
+            self.skip_space();
+            if self.pos >= self.chars.len() {
+                break;
+            }
+            let name = self.identifier()?;
+            self.skip_space();
+            if !self.consume_str("::=") {
+                return Err(self.error("expected '::='"));
+            }
+            let id = self.rule_id(&name);
+            if self.rules[id].is_some() {
+                return Err(UniModelError::validation(format!("Grammar rule '{}' is defined twice", name)));
+            }
+            let alternatives = self.alternatives(&name)?;
+            self.rules[id] = Some(alternatives);
+        }
+        Ok(())
+    }
+
+    fn alternatives(&mut self, rule_name: &str) -> Result<Vec<Vec<Element>>> {
+        let mut alternatives = vec![self.sequence(rule_name)?];
+        while self.consume_str("|") {
+            alternatives.push(self.sequence(rule_name)?);
+        }
+        Ok(alternatives)
+    }
+
+    fn sequence(&mut self, rule_name: &str) -> Result<Vec<Element>> {
+        let mut sequence = Vec::new();
+        loop {
+            self.skip_space();
+            match self.peek() {
+                None | Some('|') | Some(')') => break,
+                _ if self.at_rule_definition() => break,
+                _ => {}
+            }
+            let unit = self.primary(rule_name)?;
+            let unit = match self.peek() {
+                Some('*') => {
+                    self.pos += 1;
+                    vec![Element::Rule(self.repetition(rule_name, unit))]
+                }
+                Some('+') => {
+                    self.pos += 1;
+                    let repeat = self.repetition(rule_name, unit.clone());
+                    unit.into_iter().chain(std::iter::once(Element::Rule(repeat))).collect()
+                }
+                Some('?') => {
+                    self.pos += 1;
+                    vec![Element::Rule(self.generated_rule(rule_name, vec![unit, Vec::new()]))]
+                }
+                _ => unit,
+            };
+            sequence.extend(unit);
+        }
+        Ok(sequence)
+    }
+
+    fn primary(&mut self, rule_name: &str) -> Result<Vec<Element>> {
+        match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let mut elements = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some('"') => {
+                            self.pos += 1;
+                            return Ok(elements);
+                        }
+                        Some(_) => elements.push(Element::literal(self.char_literal()?)),
+                        None => return Err(self.error("unterminated string literal")),
+                    }
+                }
+            }
+            Some('[') => {
+                self.pos += 1;
+                let negated = self.peek() == Some('^');
+                if negated {
+                    self.pos += 1;
+                }
+                let mut ranges = Vec::new();
+                loop {
+                    match self.peek() {
+                        Some(']') => {
+                            self.pos += 1;
+                            return Ok(vec![Element::Char { ranges, negated }]);
+                        }
+                        Some(_) => {
+                            let lo = self.char_literal()?;
+                            let hi = if self.peek() == Some('-') && self.chars.get(self.pos + 1) != Some(&']') {
+                                self.pos += 1;
+                                self.char_literal()?
+                            } else {
+                                lo
+                            };
+                            ranges.push((lo, hi));
+                        }
+                        None => return Err(self.error("unterminated character class")),
+                    }
+                }
+            }
+            Some('.') => {
+                self.pos += 1;
+                Ok(vec![Element::Char { ranges: Vec::new(), negated: true }])
+            }
+            Some('(') => {
+                self.pos += 1;
+                let alternatives = self.alternatives(rule_name)?;
+                self.skip_space();
+                if !self.consume_str(")") {
+                    return Err(self.error("expected ')'"));
+                }
+                Ok(vec![Element::Rule(self.generated_rule(rule_name, alternatives))])
+            }
+            Some(c) if is_name_char(c) => {
+                let name = self.identifier()?;
+                Ok(vec![Element::Rule(self.rule_id(&name))])
+            }
+            _ => Err(self.error("unexpected character")),
+        }
+    }
+
+    /// `unit*` 改写为 `R ::= unit R | ε`
+    fn repetition(&mut self, rule_name: &str, unit: Vec<Element>) -> usize {
+        let id = self.generated_rule(rule_name, Vec::new());
+        let repeat = unit.into_iter().chain(std::iter::once(Element::Rule(id))).collect();
+        self.rules[id] = Some(vec![repeat, Vec::new()]);
+        id
+    }
+
+    fn generated_rule(&mut self, rule_name: &str, alternatives: Vec<Vec<Element>>) -> usize {
+        let id = self.rules.len();
+        self.names.push(format!("{}_{}", rule_name, id));
+        self.rules.push(Some(alternatives));
+        id
+    }
+
+    fn rule_id(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.rules.len();
+        self.ids.insert(name.to_string(), id);
+        self.names.push(name.to_string());
+        self.rules.push(None);
+        id
+    }
+
+    /// 字面量或字符类中的一个字符，处理转义
+    fn char_literal(&mut self) -> Result<char> {
+        let c = self.next().ok_or_else(|| self.error("unexpected end of grammar"))?;
+        if c != '\\' {
+            return Ok(c);
+        }
+        let escaped = self.next().ok_or_else(|| self.error("unexpected end of grammar"))?;
+        let hex_digits = match escaped {
+            'n' => return Ok('\n'),
+            'r' => return Ok('\r'),
+            't' => return Ok('\t'),
+            'x' => 2,
+            'u' => 4,
+            'U' => 8,
+            other => return Ok(other),
+        };
+        let start = self.pos;
+        let end = (start + hex_digits).min(self.chars.len());
+        let digits: String = self.chars[start..end].iter().collect();
+        self.pos = end;
+        u32::from_str_radix(&digits, 16)
+            .ok()
+            .filter(|_| digits.len() == hex_digits)
+            .and_then(char::from_u32)
+            .ok_or_else(|| self.error("invalid escape sequence"))
+    }
+
+    fn identifier(&mut self) -> Result<String> {
+        let start = self.pos;
+        while self.peek().is_some_and(is_name_char) {
+            self.pos += 1;
+        }
+        if start == self.pos {
+            return Err(self.error("expected a rule name"));
+        }
+        Ok(self.chars[start..self.pos].iter().collect())
+    }
+
+    /// 是否位于下一条 `name ::=` 规则定义的开头
+    fn at_rule_definition(&self) -> bool {
+        let mut pos = self.pos;
+        while self.chars.get(pos).is_some_and(|&c| is_name_char(c)) {
+            pos += 1;
+        }
+        if pos == self.pos {
+            return false;
+        }
+        while self.chars.get(pos).is_some_and(|c| c.is_whitespace()) {
+            pos += 1;
+        }
+        self.chars[pos.min(self.chars.len())..].starts_with(&[':', ':', '='])
+    }
+
+    fn skip_space(&mut self) {
+        while let Some(c) = self.peek() {
+            if c == '#' {
+                while self.peek().is_some_and(|c| c != '\n') {
+                    self.pos += 1;
+                }
+            } else if c.is_whitespace() {
+                self.pos += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn consume_str(&mut self, s: &str) -> bool {
+        let expected: Vec<char> = s.chars().collect();
+        if self.chars[self.pos..].starts_with(&expected) {
+            self.pos += expected.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        Some(c)
+    }
+
+    fn error(&self, message: &str) -> UniModelError {
+        let line = self.chars[..self.pos.min(self.chars.len())].iter().filter(|&&c| c == '\n').count() + 1;
+        UniModelError::validation(format!("Invalid grammar at line {}: {}", line, message))
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '-'
+}
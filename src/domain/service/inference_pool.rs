@@ -0,0 +1,127 @@
+//! 推理执行线程池
+//!
+//! 后端调用是CPU/GPU密集且可能阻塞的同步调用，放在独立的线程池中执行，不占用处理HTTP/gRPC的
+//! 异步运行时线程，也不与tokio阻塞线程池中的文件IO等任务争抢线程。线程数固定，超出的调用排队。
+//! 配置了亲和性时，线程启动后绑定到指定的CPU核或NUMA节点。
+
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::InferencePoolConfig;
+use crate::infrastructure::memory::numa::{pin_current_thread, ThreadPlacement};
+
+/// 推理线程名前缀
+const THREAD_NAME_PREFIX: &str = "unimodel-infer";
+
+/// 线程池中排队和执行中的调用数
+#[derive(Debug, Default)]
+struct PoolLoad {
+    queued: AtomicUsize,
+    running: AtomicUsize,
+}
+
+/// 推理执行线程池
+#[derive(Debug)]
+pub struct InferencePool {
+    pool: rayon::ThreadPool,
+    load: Arc<PoolLoad>,
+    placement: Option<ThreadPlacement>,
+}
+
+impl InferencePool {
+    /// 按配置创建线程池，未指定线程数时与CPU核数相同；线程按 `placement` 绑核
+    pub fn new(config: &InferencePoolConfig, placement: Option<ThreadPlacement>) -> Result<Arc<Self>> {
+        // 绑定到节点时默认每个核一个线程
+        let default_threads = match &placement {
+            Some(ThreadPlacement::Node { cpus, .. }) => cpus.len(),
+            _ => std::thread::available_parallelism().map_or(4, |n| n.get()),
+        };
+        let threads = config.threads.unwrap_or(default_threads).max(1);
+        let thread_placement = placement.clone();
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("{}-{}", THREAD_NAME_PREFIX, index))
+            .start_handler(move |index| {
+                if let Some(placement) = &thread_placement {
+                    let cpus = placement.cpus_for_thread(index);
+                    if let Err(e) = pin_current_thread(&cpus) {
+                        warn!("Failed to pin inference thread {} to CPUs {:?}: {}", index, cpus, e);
+                    }
+                }
+            })
+            .build()
+            .map_err(|e| UniModelError::config(format!("Failed to start inference pool: {}", e)))?;
+        match &placement {
+            Some(placement) => info!("Inference pool started with {} thread(s), placement {:?}", threads, placement),
+            None => info!("Inference pool started with {} thread(s)", threads),
+        }
+        Ok(Arc::new(Self {
+            pool,
+            load: Arc::new(PoolLoad::default()),
+            placement,
+        }))
+    }
+
+    /// 在线程池中执行同步调用并等待结果；调用中的panic转换为内部错误
+    pub async fn run<T, F>(&self, call: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce() -> Result<T> + Send + 'static,
+    {
+        let (sender, receiver) = oneshot::channel();
+        let load = Arc::clone(&self.load);
+        load.queued.fetch_add(1, Ordering::Relaxed);
+        self.pool.spawn(move || {
+            load.queued.fetch_sub(1, Ordering::Relaxed);
+            // 等待结果的一方已放弃时不再执行
+            if sender.is_closed() {
+                return;
+            }
+            load.running.fetch_add(1, Ordering::Relaxed);
+            let result = std::panic::catch_unwind(AssertUnwindSafe(call))
+                .unwrap_or_else(|_| Err(UniModelError::internal("Inference call panicked")));
+            load.running.fetch_sub(1, Ordering::Relaxed);
+            let _ = sender.send(result);
+        });
+        receiver
+            .await
+            .map_err(|_| UniModelError::internal("Inference pool dropped the call"))?
+    }
+
+    /// 线程所在的NUMA节点，后端在该节点上分配主机缓冲区
+    pub fn numa_node(&self) -> Option<u32> {
+        self.placement.as_ref().and_then(ThreadPlacement::node)
+    }
+
+    /// 线程的放置
+    pub fn placement(&self) -> Option<&ThreadPlacement> {
+        self.placement.as_ref()
+    }
+
+    /// 线程数
+    pub fn threads(&self) -> usize {
+        self.pool.current_num_threads()
+    }
+
+    /// 等待线程的调用数
+    pub fn queued(&self) -> usize {
+        self.load.queued.load(Ordering::Relaxed)
+    }
+
+    /// 正在执行的调用数
+    pub fn running(&self) -> usize {
+        self.load.running.load(Ordering::Relaxed)
+    }
+
+    /// 把负载写入指标
+    pub fn publish_metrics(&self) {
+        metrics::gauge!("unimodel_inference_pool_threads", self.threads() as f64);
+        metrics::gauge!("unimodel_inference_pool_queued", self.queued() as f64);
+        metrics::gauge!("unimodel_inference_pool_running", self.running() as f64);
+    }
+}
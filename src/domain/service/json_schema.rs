@@ -0,0 +1,337 @@
+//! JSON Schema结构化输出
+//!
+//! 把JSON Schema编译为GBNF语法（见 [`crate::domain::service::grammar`]），交给支持约束解码的
+//! 后端；不支持的后端生成后按schema校验输出。支持 `type`、`properties`/`required`、`items`、
+//! `enum`、`const`、`anyOf`/`oneOf` 和指向 `$defs`/`definitions` 的 `$ref`；
+//! 对象属性按键名顺序生成；`pattern`、`format` 等字符串约束不参与约束和校验。
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::common::error::*;
+
+/// 通用JSON值的语法规则
+const JSON_PRIMITIVES: &str = r#"ws ::= [ \t\n]*
+string ::= "\"" string-char* "\""
+string-char ::= [^"\\\x00-\x1f] | "\\" (["\\/bfnrt] | "u" [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F] [0-9a-fA-F])
+number ::= integer ("." [0-9]+)? ([eE] [-+]? [0-9]+)?
+integer ::= "-"? ("0" | [1-9] [0-9]*)
+boolean ::= "true" | "false"
+null ::= "null"
+value ::= object | array | string | number | boolean | null
+object ::= "{" ws (string ws ":" ws value (ws "," ws string ws ":" ws value)*)? ws "}"
+array ::= "[" ws (value (ws "," ws value)*)? ws "]"
+"#;
+
+/// 把JSON Schema编译为以 `root` 为入口的GBNF语法
+pub fn schema_to_grammar(schema: &Value) -> Result<String> {
+    let mut compiler = SchemaCompiler {
+        root: schema,
+        rules: Vec::new(),
+        refs: HashMap::new(),
+    };
+    let root = compiler.visit(schema, "root")?;
+    if root != "root" {
+        compiler.rules.push(("root".to_string(), root));
+    }
+
+    let mut grammar = String::new();
+    for (name, body) in &compiler.rules {
+        grammar.push_str(&format!("{} ::= {}\n", name, body));
+    }
+    grammar.push_str(JSON_PRIMITIVES);
+    Ok(grammar)
+}
+
+struct SchemaCompiler<'a> {
+    root: &'a Value,
+    /// 已生成的规则（名称, 规则体）
+    rules: Vec<(String, String)>,
+    /// `$ref` -> 规则名，支持递归引用
+    refs: HashMap<String, String>,
+}
+
+impl<'a> SchemaCompiler<'a> {
+    /// 编译子schema，返回可在规则体中引用的表达式
+    fn visit(&mut self, schema: &'a Value, name: &str) -> Result<String> {
+        let object = match schema {
+            Value::Bool(true) => return Ok("value".to_string()),
+            Value::Object(object) => object,
+            _ => return Err(UniModelError::validation(format!("Unsupported JSON schema at '{}'", name))),
+        };
+
+        if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+            if let Some(rule) = self.refs.get(reference) {
+                return Ok(rule.clone());
+            }
+            let target = resolve_ref(self.root, reference)?;
+            let rule = self.rule_name(reference.rsplit('/').next().unwrap_or(name));
+            self.refs.insert(reference.to_string(), rule.clone());
+            let body = self.visit(target, &rule)?;
+            self.rules.push((rule.clone(), body));
+            return Ok(rule);
+        }
+        if let Some(value) = object.get("const") {
+            return Ok(json_literal(value));
+        }
+        if let Some(values) = object.get("enum").and_then(Value::as_array) {
+            let alternatives: Vec<String> = values.iter().map(json_literal).collect();
+            return Ok(self.define(name, format!("({})", alternatives.join(" | "))));
+        }
+        if let Some(schemas) = object.get("anyOf").or_else(|| object.get("oneOf")).and_then(Value::as_array) {
+            let alternatives = schemas.iter()
+                .enumerate()
+                .map(|(i, schema)| self.visit(schema, &format!("{}-{}", name, i)))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(self.define(name, format!("({})", alternatives.join(" | "))));
+        }
+
+        match object.get("type") {
+            None => Ok("value".to_string()),
+            Some(Value::String(ty)) => self.visit_type(object, ty, name),
+            Some(Value::Array(types)) => {
+                let alternatives = types.iter()
+                    .map(|ty| match ty.as_str() {
+                        Some(ty) => self.visit_type(object, ty, &format!("{}-{}", name, ty)),
+                        None => Err(UniModelError::validation(format!("Invalid type in JSON schema at '{}'", name))),
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok(self.define(name, format!("({})", alternatives.join(" | "))))
+            }
+            Some(_) => Err(UniModelError::validation(format!("Invalid type in JSON schema at '{}'", name))),
+        }
+    }
+
+    fn visit_type(&mut self, object: &'a serde_json::Map<String, Value>, ty: &str, name: &str) -> Result<String> {
+        match ty {
+            "object" => {
+                let properties = match object.get("properties").and_then(Value::as_object) {
+                    Some(properties) if !properties.is_empty() => properties,
+                    _ => return Ok("object".to_string()),
+                };
+                let required: Vec<&str> = object.get("required")
+                    .and_then(Value::as_array)
+                    .map(|r| r.iter().filter_map(Value::as_str).collect())
+                    .unwrap_or_default();
+
+                let mut members = Vec::new();
+                for (key, schema) in properties {
+                    let value = self.visit(schema, &format!("{}-{}", name, key))?;
+                    let member = format!("{} ws \":\" ws {}", json_literal(&Value::String(key.clone())), value);
+                    members.push((member, required.contains(&key.as_str())));
+                }
+                Ok(self.define(name, object_body(&members)))
+            }
+            "array" => {
+                let item = match object.get("items") {
+                    Some(items) => self.visit(items, &format!("{}-item", name))?,
+                    None => "value".to_string(),
+                };
+                let min_items = object.get("minItems").and_then(Value::as_u64).unwrap_or(0);
+                let items = format!("{} (ws \",\" ws {})*", item, item);
+                let body = if min_items > 0 {
+                    format!("\"[\" ws {} ws \"]\"", items)
+                } else {
+                    format!("\"[\" ws ({})? ws \"]\"", items)
+                };
+                Ok(self.define(name, body))
+            }
+            "string" | "number" | "integer" | "boolean" | "null" => Ok(ty.to_string()),
+            other => Err(UniModelError::validation(format!(
+                "Unsupported JSON schema type '{}' at '{}'",
+                other, name
+            ))),
+        }
+    }
+
+    fn define(&mut self, name: &str, body: String) -> String {
+        let rule = self.rule_name(name);
+        self.rules.push((rule.clone(), body));
+        rule
+    }
+
+    /// 规则名只保留字母、数字和连字符，重名时追加序号
+    fn rule_name(&self, name: &str) -> String {
+        let base: String = name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+            .collect();
+        let taken = |candidate: &str| {
+            self.rules.iter().any(|(rule, _)| rule == candidate) || self.refs.values().any(|rule| rule == candidate)
+                || JSON_PRIMITIVES.lines().any(|line| line.starts_with(&format!("{} ::=", candidate)))
+        };
+        let mut rule = base.clone();
+        let mut suffix = 1;
+        while taken(&rule) {
+            rule = format!("{}-{}", base, suffix);
+            suffix += 1;
+        }
+        rule
+    }
+}
+
+/// 对象规则体：必需属性按顺序出现，可选属性可省略但保持顺序
+fn object_body(members: &[(String, bool)]) -> String {
+    let mut body = String::from("\"{\" ws ");
+    match members.iter().position(|(_, required)| *required) {
+        Some(first_required) => {
+            // 第一个必需属性之前的可选属性可整体出现在开头
+            let leading: Vec<&str> = members[..first_required].iter().map(|(m, _)| m.as_str()).collect();
+            let mut alternatives = vec![members[first_required].0.clone()];
+            for (i, member) in leading.iter().enumerate() {
+                let rest: String = leading[i + 1..].iter()
+                    .map(|m| format!(" (ws \",\" ws {})?", m))
+                    .collect();
+                alternatives.push(format!("{}{} ws \",\" ws {}", member, rest, members[first_required].0));
+            }
+            body.push_str(&format!("({})", alternatives.join(" | ")));
+            for (member, required) in &members[first_required + 1..] {
+                if *required {
+                    body.push_str(&format!(" ws \",\" ws {}", member));
+                } else {
+                    body.push_str(&format!(" (ws \",\" ws {})?", member));
+                }
+            }
+        }
+        None => {
+            let alternatives: Vec<String> = (0..members.len())
+                .map(|i| {
+                    let rest: String = members[i + 1..].iter()
+                        .map(|(m, _)| format!(" (ws \",\" ws {})?", m))
+                        .collect();
+                    format!("{}{}", members[i].0, rest)
+                })
+                .collect();
+            body.push_str(&format!("({})?", alternatives.join(" | ")));
+        }
+    }
+    body.push_str(" ws \"}\"");
+    body
+}
+
+/// JSON值序列化后作为GBNF字符串字面量
+fn json_literal(value: &Value) -> String {
+    let json = value.to_string();
+    let mut literal = String::with_capacity(json.len() + 2);
+    literal.push('"');
+    for c in json.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+fn resolve_ref<'a>(root: &'a Value, reference: &str) -> Result<&'a Value> {
+    reference.strip_prefix('#')
+        .and_then(|pointer| root.pointer(pointer))
+        .ok_or_else(|| UniModelError::validation(format!("Unresolvable JSON schema reference '{}'", reference)))
+}
+
+/// 按schema校验生成的JSON，供没有约束解码的后端使用
+pub fn validate_json(value: &Value, schema: &Value) -> Result<()> {
+    check(value, schema, schema, "$")
+        .map_err(|e| UniModelError::model(format!("Output does not match the JSON schema: {}", e)))
+}
+
+fn check(value: &Value, schema: &Value, root: &Value, path: &str) -> std::result::Result<(), String> {
+    let object = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Bool(false) => return Err(format!("{} is not allowed", path)),
+        Value::Object(object) => object,
+        _ => return Ok(()),
+    };
+
+    if let Some(reference) = object.get("$ref").and_then(Value::as_str) {
+        let target = resolve_ref(root, reference).map_err(|e| e.to_string())?;
+        return check(value, target, root, path);
+    }
+    if let Some(expected) = object.get("const") {
+        if value != expected {
+            return Err(format!("{} must be {}", path, expected));
+        }
+    }
+    if let Some(values) = object.get("enum").and_then(Value::as_array) {
+        if !values.contains(value) {
+            return Err(format!("{} must be one of {}", path, Value::Array(values.clone())));
+        }
+    }
+    if let Some(schemas) = object.get("anyOf").and_then(Value::as_array) {
+        if !schemas.iter().any(|schema| check(value, schema, root, path).is_ok()) {
+            return Err(format!("{} does not match any of the allowed schemas", path));
+        }
+    }
+    if let Some(schemas) = object.get("oneOf").and_then(Value::as_array) {
+        let matched = schemas.iter().filter(|schema| check(value, schema, root, path).is_ok()).count();
+        if matched != 1 {
+            return Err(format!("{} matches {} of the oneOf schemas, expected exactly one", path, matched));
+        }
+    }
+
+    let types: Vec<&str> = match object.get("type") {
+        Some(Value::String(ty)) => vec![ty.as_str()],
+        Some(Value::Array(types)) => types.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    };
+    if !types.is_empty() && !types.iter().any(|ty| has_type(value, ty)) {
+        return Err(format!("{} must be of type {}", path, types.join(" or ")));
+    }
+
+    match value {
+        Value::Object(members) => {
+            if let Some(required) = object.get("required").and_then(Value::as_array) {
+                if let Some(missing) = required.iter().filter_map(Value::as_str).find(|key| !members.contains_key(*key)) {
+                    return Err(format!("{} is missing required property '{}'", path, missing));
+                }
+            }
+            let properties = object.get("properties").and_then(Value::as_object);
+            for (key, member) in members {
+                match properties.and_then(|properties| properties.get(key)) {
+                    Some(schema) => check(member, schema, root, &format!("{}.{}", path, key))?,
+                    None => match object.get("additionalProperties") {
+                        Some(Value::Bool(false)) => {
+                            return Err(format!("{} has unexpected property '{}'", path, key));
+                        }
+                        Some(schema) => check(member, schema, root, &format!("{}.{}", path, key))?,
+                        None => {}
+                    },
+                }
+            }
+        }
+        Value::Array(items) => {
+            let len = items.len() as u64;
+            if let Some(min) = object.get("minItems").and_then(Value::as_u64).filter(|&min| len < min) {
+                return Err(format!("{} must have at least {} items", path, min));
+            }
+            if let Some(max) = object.get("maxItems").and_then(Value::as_u64).filter(|&max| len > max) {
+                return Err(format!("{} must have at most {} items", path, max));
+            }
+            if let Some(schema) = object.get("items") {
+                for (i, item) in items.iter().enumerate() {
+                    check(item, schema, root, &format!("{}[{}]", path, i))?;
+                }
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+fn has_type(value: &Value, ty: &str) -> bool {
+    match ty {
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64() || value.as_f64().is_some_and(|v| v.fract() == 0.0),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        _ => false,
+    }
+}
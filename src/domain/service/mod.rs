@@ -0,0 +1,36 @@
+//! 领域服务
+
+pub mod batch_processor;
+pub mod batch_queue;
+pub mod batch_tuner;
+pub mod chaos;
+pub mod conversion;
+pub mod event_log;
+pub mod generation;
+pub mod grammar;
+pub mod inference_pool;
+pub mod json_schema;
+pub mod model_manager;
+pub mod parallelism;
+pub mod plugin_manager;
+pub mod quantization;
+pub mod replica_balancer;
+pub mod resource_manager;
+pub mod scheduler;
+pub mod session;
+pub mod speculative;
+pub mod weight_cache;
+
+pub use batch_processor::BatchProcessor;
+pub use batch_queue::PendingQueue;
+pub use batch_tuner::BatchTuner;
+pub use conversion::ConversionManager;
+pub use event_log::EventLog;
+pub use inference_pool::InferencePool;
+pub use model_manager::{ModelManager, Readiness};
+pub use parallelism::{ParallelPlan, PipelinePlan, TensorParallelPlan};
+pub use replica_balancer::ReplicaBalancer;
+pub use scheduler::Scheduler;
+pub use session::SessionTable;
+pub use speculative::SpeculativeDecoder;
+pub use weight_cache::WeightCache;
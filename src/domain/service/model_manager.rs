@@ -2,15 +2,58 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use dashmap::DashMap;
+use serde::Serialize;
 use tokio::sync::RwLock;
 use tracing::{info, warn, error};
 
 use crate::common::types::*;
 use crate::common::error::*;
 use crate::domain::model::*;
-use crate::infrastructure::configuration::Config;
+use crate::domain::service::event_log::EventLog;
+use crate::domain::service::scheduler::Scheduler;
+use crate::domain::service::quantization::QuantizedCache;
+use crate::domain::service::speculative::{SpeculativeDecoder, MAX_SPECULATIVE_TOKENS};
+use crate::domain::service::weight_cache::{SharedCheckpoint, WeightCache};
+use crate::infrastructure::configuration::{Config, PreloadModelConfig};
+use crate::infrastructure::model_format::{read_onnx_signature, GgufHeader};
+use crate::infrastructure::monitoring::DeviceProbe;
+use crate::infrastructure::tokenizer::ModelTokenizer;
+use crate::plugins::builtin::mock_plugin;
+use crate::plugins::interface::base_plugin::ModelWeights;
+use crate::plugins::interface::IterativeDecoder;
+use crate::plugins::manager::lifecycle_manager::AUTO_BACKEND;
 use crate::plugins::manager::PluginManager;
 
+/// 单个模型最多同时加载的LoRA适配器数
+pub const MAX_ADAPTERS_PER_MODEL: usize = 32;
+
+/// 等待预加载完成时检查就绪状态的间隔
+const READINESS_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 就绪状态
+#[derive(Debug, Clone, Serialize)]
+pub struct Readiness {
+    pub ready: bool,
+    /// 尚未完成加载的预加载模型
+    pub pending: Vec<String>,
+    /// 加载失败、从未就绪过的预加载模型
+    pub failed: Vec<String>,
+}
+
+/// 后台加载任务用到的模型管理器状态
+struct LoadContext {
+    plugin_manager: Arc<PluginManager>,
+    models: Arc<RwLock<HashMap<ModelId, Model>>>,
+    events: Arc<EventLog>,
+    weights: Arc<WeightCache>,
+    quantized: QuantizedCache,
+    decoders: Arc<DashMap<ModelId, Arc<dyn IterativeDecoder>>>,
+    scheduler: Option<Arc<Scheduler>>,
+}
+
 /// 模型管理器
 #[derive(Debug)]
 pub struct ModelManager {
@@ -18,6 +61,18 @@ pub struct ModelManager {
     models: Arc<RwLock<HashMap<ModelId, Model>>>,
     /// 插件管理器
     plugin_manager: Arc<PluginManager>,
+    /// GPU调度器
+    scheduler: Option<Arc<Scheduler>>,
+    /// 模型事件日志
+    events: Arc<EventLog>,
+    /// 多个模型共享的检查点权重
+    weights: Arc<WeightCache>,
+    /// 加载时量化的产物缓存
+    quantized: QuantizedCache,
+    /// 已加载模型的逐token解码器，与批处理器共享
+    decoders: Arc<DashMap<ModelId, Arc<dyn IterativeDecoder>>>,
+    /// 启动时预加载的模型
+    preloaded: parking_lot::Mutex<Vec<ModelId>>,
     /// 配置
     config: Arc<Config>,
     /// 最大模型数量
@@ -33,86 +88,753 @@ impl ModelManager {
         Ok(Self {
             models: Arc::new(RwLock::new(HashMap::new())),
             plugin_manager,
+            scheduler: None,
+            events: Arc::new(EventLog::default()),
+            weights: Arc::new(WeightCache::new()),
+            quantized: QuantizedCache::new(&config.storage.cache_storage_path),
+            decoders: Arc::new(DashMap::new()),
+            preloaded: parking_lot::Mutex::new(Vec::new()),
             config: Arc::new(config.clone()),
             max_models,
         })
     }
 
+    /// 设置GPU调度器，注册模型时由其选择设备
+    pub fn with_scheduler(mut self, scheduler: Arc<Scheduler>) -> Self {
+        self.scheduler = Some(scheduler);
+        self
+    }
+
+    /// 模型事件日志
+    pub fn events(&self) -> Arc<EventLog> {
+        Arc::clone(&self.events)
+    }
+
+    /// 插件管理器
+    pub fn plugin_manager(&self) -> Arc<PluginManager> {
+        Arc::clone(&self.plugin_manager)
+    }
+
+    /// 已加载模型的逐token解码器
+    ///
+    /// 后端提供解码器的模型在加载完成时登记、卸载时移除；多副本模型只登记主实例的解码器。
+    /// 配置了投机解码的模型在草稿模型也已加载时登记为 [`SpeculativeDecoder`]，草稿模型加载或逐出时随之更新。
+    pub fn decoders(&self) -> Arc<DashMap<ModelId, Arc<dyn IterativeDecoder>>> {
+        Arc::clone(&self.decoders)
+    }
+
+    /// 当前打开的检查点及持有它们的模型
+    pub fn shared_checkpoints(&self) -> Vec<SharedCheckpoint> {
+        self.weights.checkpoints()
+    }
+
+    /// 未指定设备时模型使用的设备类型，即本机调度的加速器
+    pub fn default_device_type(&self) -> DeviceType {
+        self.config.engine.gpu.accelerator.clone()
+    }
+
     /// 注册模型
     pub async fn register_model(
         &self,
         name: String,
         model_type: ModelType,
         config: ModelConfig,
+        tenant: Option<String>,
     ) -> Result<ModelId> {
+        if name.is_empty() || name.contains('/') {
+            return Err(UniModelError::validation("Model name must be non-empty and must not contain '/'"));
+        }
+
+        let (config, device_selection) = self.resolve_device(config).await;
+        let config = self.negotiate_backend(config)?;
+
         let model_id = new_model_id();
         let mut model = Model::new(model_id.clone(), name, model_type, config);
+        model.info.tenant = tenant;
+        model.info.device_selection = device_selection;
 
-        // 检查是否达到最大模型数量
-        {
-            let models = self.models.read().await;
-            if models.len() >= self.max_models {
-                return Err(UniModelError::model("Maximum number of models reached"));
-            }
+        if model.info.config.model_path.ends_with(".gguf") {
+            Self::introspect_gguf(&mut model.info).await?;
+        }
+        // 配置中声明的上下文长度优先于模型文件中的值
+        if let Some(context_length) = model.info.config.custom_params.get("context_length").and_then(|v| v.as_u64()) {
+            model.info.metadata.custom_metadata.insert("context_length".to_string(), serde_json::json!(context_length));
+        }
+
+        if let Some(mut signature) = model.info.config.signature.clone() {
+            signature.source = SignatureSource::User;
+            model.info.signature = Some(signature);
+        } else if model.info.config.model_path.ends_with(".onnx") {
+            Self::introspect_onnx(&mut model.info).await;
+        }
+        if let Some(signature) = &mut model.info.signature {
+            signature.infer_modalities(&model.info.model_type);
+        }
+        if let Some(path) = &model.info.config.labels_path {
+            model.info.labels = Some(Arc::new(Self::load_labels(path).await?));
+        }
+        if let Some(path) = model.info.config.tokenizer_path.clone() {
+            let tokenizer = tokio::task::spawn_blocking(move || ModelTokenizer::from_file(path))
+                .await
+                .map_err(|e| UniModelError::internal(format!("Tokenizer loading task failed: {}", e)))??;
+            model.info.tokenizer = Some(Arc::new(tokenizer));
         }
 
         // 更新模型状态为加载中
         model.update_status(ModelStatus::Loading);
 
-        // 插入模型
+        // 检查配额并插入模型（同一把写锁内完成，避免并发注册越过限制）
         {
             let mut models = self.models.write().await;
+
+            let qualified_name = model.info.qualified_name();
+            if models.values().any(|m| m.info.qualified_name() == qualified_name) {
+                return Err(UniModelError::validation(format!("Model '{}' already exists", qualified_name)));
+            }
+
+            if let Some(tenant) = &model.info.tenant {
+                self.check_tenant_limits(&models, tenant, &model.info.config)?;
+            }
+            if let Some(speculative) = &model.info.config.speculative {
+                let draft_id = Self::check_draft_model(&models, &model.info, speculative)?;
+                model.info.metadata.custom_metadata.insert("draft_model_id".to_string(), serde_json::json!(draft_id));
+            }
+
+            self.admit(&mut models, &mut model).await?;
+
+            self.events.record(ModelEvent::new(&model.info, ModelEventKind::Registered, None));
             models.insert(model_id.clone(), model);
         }
 
         info!("Model registered: {}", model_id);
 
-        // 异步加载模型
-        let manager = Arc::clone(&self.plugin_manager);
-        let models = Arc::clone(&self.models);
-        let id = model_id.clone();
+        self.spawn_load(model_id.clone());
+
+        Ok(model_id)
+    }
+
+    /// 读取GGUF文件头，填充架构、上下文长度、量化类型和参数量
+    async fn introspect_gguf(info: &mut ModelInfo) -> Result<()> {
+        let path = info.config.model_path.clone();
+        let load_vocab = info.config.tokenizer_path.is_none();
+        let (header, tokenizer) = tokio::task::spawn_blocking(move || {
+            if !load_vocab {
+                return GgufHeader::read(path).map(|header| (header, None));
+            }
+            let header = GgufHeader::read_with_vocab(path)?;
+            let tokenizer = header.vocab().map(ModelTokenizer::from_gguf_vocab);
+            Ok((header, tokenizer))
+        })
+        .await
+        .map_err(|e| UniModelError::internal(format!("GGUF introspection task failed: {}", e)))??;
+
+        // 内嵌词表无法使用时不影响注册，只是分词接口不可用
+        match tokenizer {
+            Some(Ok(tokenizer)) => info.tokenizer = Some(Arc::new(tokenizer)),
+            Some(Err(e)) => warn!("Failed to build tokenizer for model '{}': {}", info.name, e),
+            None => {}
+        }
+
+        let metadata = &mut info.metadata.custom_metadata;
+        if let Some(architecture) = header.architecture() {
+            metadata.insert("architecture".to_string(), serde_json::json!(architecture));
+        }
+        if let Some(context_length) = header.context_length() {
+            metadata.insert("context_length".to_string(), serde_json::json!(context_length));
+        }
+        if let Some(quantization) = header.quantization() {
+            metadata.insert("quantization".to_string(), serde_json::json!(quantization));
+        }
+        metadata.insert("parameter_count".to_string(), serde_json::json!(header.parameter_count()));
+        if let Some(name) = header.metadata.get("general.name").and_then(|v| v.as_str()) {
+            info.metadata.description.get_or_insert_with(|| name.to_string());
+        }
+
+        Ok(())
+    }
+
+    /// 读取ONNX图的输入输出签名
+    ///
+    /// 读取失败不影响注册，文件问题由后端加载时报告。
+    async fn introspect_onnx(info: &mut ModelInfo) {
+        let path = info.config.model_path.clone();
+        match tokio::task::spawn_blocking(move || read_onnx_signature(path)).await {
+            Ok(Ok(signature)) => info.signature = Some(signature),
+            Ok(Err(e)) => warn!("Failed to read ONNX signature for model '{}': {}", info.name, e),
+            Err(e) => warn!("ONNX introspection task failed for model '{}': {}", info.name, e),
+        }
+    }
+
+    /// 读取类别标签文件
+    async fn load_labels(path: &str) -> Result<LabelMap> {
+        let content = tokio::fs::read_to_string(path)
+            .await
+            .map_err(|e| UniModelError::validation(format!("Failed to read labels file {}: {}", path, e)))?;
+        LabelMap::parse(&content)
+    }
+
+    /// 异步加载模型，失败时释放显存预留
+    fn spawn_load(&self, model_id: ModelId) {
+        let context = LoadContext {
+            plugin_manager: Arc::clone(&self.plugin_manager),
+            models: Arc::clone(&self.models),
+            events: Arc::clone(&self.events),
+            weights: Arc::clone(&self.weights),
+            quantized: self.quantized.clone(),
+            decoders: Arc::clone(&self.decoders),
+            scheduler: self.scheduler.clone(),
+        };
+        let scheduler = self.scheduler.clone();
 
         tokio::spawn(async move {
-            if let Err(e) = Self::load_model_async(manager, models, id).await {
+            let loaded = Self::load_model_async(context, model_id.clone()).await;
+            if let Err(e) = loaded {
                 error!("Failed to load model: {}", e);
+                // 加载失败的模型不再占用显存预留
+                if let Some(scheduler) = scheduler {
+                    scheduler.release(&model_id);
+                }
             }
         });
+    }
 
-        Ok(model_id)
+    /// 注册配置中的预加载模型
+    pub async fn preload(&self) -> Result<()> {
+        for entry in &self.config.engine.preload {
+            let config = self.preload_model_config(entry);
+            let model_id = self
+                .register_model(entry.name.clone(), entry.model_type.clone(), config, entry.tenant.clone())
+                .await?;
+            if entry.pinned {
+                self.set_pinned(&model_id, true).await?;
+            }
+            self.preloaded.lock().push(model_id);
+        }
+
+        if !self.config.engine.preload.is_empty() {
+            info!("Preloading {} model(s)", self.config.engine.preload.len());
+        }
+        Ok(())
     }
 
-    /// 异步加载模型
-    async fn load_model_async(
-        plugin_manager: Arc<PluginManager>,
-        models: Arc<RwLock<HashMap<ModelId, Model>>>,
-        model_id: ModelId,
+    fn preload_model_config(&self, entry: &PreloadModelConfig) -> ModelConfig {
+        let device_ids = if entry.device_ids.is_empty() {
+            self.config.engine.gpu.device_ids.clone()
+        } else {
+            entry.device_ids.clone()
+        };
+
+        ModelConfig {
+            model_path: entry.model_path.clone(),
+            config_path: None,
+            tokenizer_path: None,
+            backend: entry.backend.clone(),
+            device: DeviceConfig {
+                device_type: entry.device_type.clone().unwrap_or_else(|| self.default_device_type()),
+                device_ids,
+                memory_limit_mb: entry.memory_limit_mb,
+                mixed_precision: entry.mixed_precision,
+                parallelism: entry.parallelism,
+                micro_batches: entry.micro_batches,
+            },
+            optimization: OptimizationConfig {
+                kv_cache: true,
+                quantization: None,
+                graph_optimization: true,
+                inference_parallelism: 1,
+                memory_optimization: MemoryOptimization::Medium,
+                cuda_graphs: entry.cuda_graphs,
+                cuda_streams: entry.cuda_streams,
+            },
+            batch_config: self.config.engine.batch_config.clone(),
+            max_concurrent_batches: None,
+            warmup: entry.warmup.clone(),
+            signature: None,
+            input_schema: None,
+            preprocessing: None,
+            postprocessing: None,
+            labels_path: None,
+            speculative: None,
+            custom_params: HashMap::new(),
+        }
+    }
+
+    /// 就绪检查：所有预加载模型都完成过一次加载和预热
+    ///
+    /// 之后被逐出的模型不影响就绪状态；被手动注销的预加载模型不再计入。加载失败的预加载模型
+    /// 单独列出，重新加载成功前同样不就绪。
+    pub async fn readiness(&self) -> Readiness {
+        let preloaded = self.preloaded.lock().clone();
+        let models = self.models.read().await;
+
+        let (failed, pending): (Vec<&Model>, Vec<&Model>) = preloaded
+            .iter()
+            .filter_map(|id| models.get(id))
+            .filter(|m| m.loaded_at.is_none())
+            .partition(|m| matches!(m.info.status, ModelStatus::Error(_)));
+        let names = |models: Vec<&Model>| models.iter().map(|m| m.info.qualified_name()).collect::<Vec<_>>();
+
+        Readiness {
+            ready: pending.is_empty() && failed.is_empty(),
+            pending: names(pending),
+            failed: names(failed),
+        }
+    }
+
+    /// 等待所有预加载模型就绪，有模型加载失败或超过 `timeout` 时返回错误
+    pub async fn wait_until_ready(&self, timeout: Duration) -> Result<()> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let readiness = self.readiness().await;
+            if readiness.ready {
+                return Ok(());
+            }
+            if !readiness.failed.is_empty() {
+                return Err(UniModelError::model(format!(
+                    "Preloaded model(s) failed to load: {}",
+                    readiness.failed.join(", ")
+                )));
+            }
+            if Instant::now() >= deadline {
+                return Err(UniModelError::model(format!(
+                    "Preloaded model(s) not ready after {:?}: {}",
+                    timeout,
+                    readiness.pending.join(", ")
+                )));
+            }
+            tokio::time::sleep(READINESS_POLL_INTERVAL).await;
+        }
+    }
+
+    /// 为模型分配驻留名额和设备
+    ///
+    /// 驻留模型数达到 `max_models` 或没有设备容纳得下时，
+    /// 按LRU顺序逐出空闲超时的模型后重试。
+    async fn admit(&self, models: &mut HashMap<ModelId, Model>, model: &mut Model) -> Result<()> {
+        loop {
+            let resident = models.values()
+                .filter(|m| m.info.id != model.info.id && m.info.status != ModelStatus::Unloaded)
+                .count();
+            if resident >= self.max_models {
+                if self.evict_lru(models, None).await {
+                    continue;
+                }
+                return Err(UniModelError::model("Maximum number of models reached"));
+            }
+
+            let scheduler = match &self.scheduler {
+                Some(scheduler) => scheduler,
+                None => return Ok(()),
+            };
+
+            match scheduler.place(&model.info.id, &model.info.config) {
+                Ok(device_ids) => {
+                    if !device_ids.is_empty() {
+                        model.info.config.device.device_ids = device_ids;
+                    }
+                    return Ok(());
+                }
+                Err(e) => {
+                    let devices = model.info.config.device.device_ids.clone();
+                    if self.evict_lru(models, Some(devices.as_slice())).await {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    /// 逐出最久未访问的空闲模型（固定驻留的模型除外）
+    ///
+    /// `devices` 为 `Some` 时表示显存不足，只考虑占用这些GPU（为空表示任意GPU）的模型。
+    async fn evict_lru(&self, models: &mut HashMap<ModelId, Model>, devices: Option<&[u32]>) -> bool {
+        let eviction = &self.config.engine.eviction;
+        if !eviction.enabled {
+            return false;
+        }
+
+        let idle_ttl = chrono::Duration::seconds(eviction.idle_ttl_secs as i64);
+        let now = Utc::now();
+        let candidate = models.values()
+            .filter(|m| m.is_loaded() && !m.info.pinned && now - m.last_accessed >= idle_ttl)
+            .filter(|m| match devices {
+                Some(devices) => {
+                    m.info.config.device.device_type != DeviceType::CPU
+                        && (devices.is_empty()
+                            || m.info.config.device.device_ids.iter().any(|id| devices.contains(id)))
+                }
+                None => true,
+            })
+            .min_by_key(|m| m.last_accessed)
+            .map(|m| m.info.id.clone());
+
+        match candidate {
+            Some(model_id) => {
+                let reason = match devices {
+                    Some(_) => "GPU memory pressure",
+                    None => "max_models reached",
+                };
+                self.evict(models, &model_id, reason).await;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// 卸载模型实例并释放显存，保留注册信息
+    async fn evict(&self, models: &mut HashMap<ModelId, Model>, model_id: &ModelId, reason: &str) {
+        let model = match models.get_mut(model_id) {
+            Some(model) => model,
+            None => return,
+        };
+
+        self.decoders.remove(model_id);
+        let replicas = std::mem::take(&mut model.replicas);
+        for instance in model.instance.take().into_iter().chain(replicas) {
+            if let Err(e) = self.plugin_manager.unload_model(&instance.plugin_id, &instance.handle).await {
+                warn!("Failed to unload evicted model from plugin: {}", e);
+            }
+        }
+        self.weights.release(model_id);
+        if let Some(scheduler) = &self.scheduler {
+            scheduler.release(model_id);
+        }
+
+        model.update_status(ModelStatus::Unloaded);
+        model.info.health_status = HealthStatus::Unknown;
+        info!("Model evicted: {} ({})", model_id, reason);
+        self.events.record(ModelEvent::new(&model.info, ModelEventKind::Evicted, Some(reason.to_string())));
+        // 以本模型为草稿的模型退回普通解码
+        Self::refresh_decoders(&self.plugin_manager, models, &self.decoders, model_id);
+    }
+
+    /// 按当前加载状态重新登记模型及以它为草稿的模型的解码器，未加载的模型移除登记
+    fn refresh_decoders(
+        plugin_manager: &PluginManager,
+        models: &HashMap<ModelId, Model>,
+        decoders: &DashMap<ModelId, Arc<dyn IterativeDecoder>>,
+        model_id: &ModelId,
+    ) {
+        let dependents = models.values()
+            .filter(|m| m.info.metadata.custom_metadata.get("draft_model_id").and_then(|v| v.as_str()) == Some(model_id.as_str()))
+            .map(|m| m.info.id.clone());
+        for id in std::iter::once(model_id.clone()).chain(dependents) {
+            match Self::model_decoder(plugin_manager, models, &id) {
+                Some(decoder) => {
+                    decoders.insert(id, decoder);
+                }
+                None => {
+                    decoders.remove(&id);
+                }
+            }
+        }
+    }
+
+    /// 已加载模型的解码器；配置了投机解码、且草稿和目标都支持时组合为投机解码器
+    fn model_decoder(
+        plugin_manager: &PluginManager,
+        models: &HashMap<ModelId, Model>,
+        model_id: &ModelId,
+    ) -> Option<Arc<dyn IterativeDecoder>> {
+        let model = models.get(model_id)?;
+        let decoder = plugin_manager.decoder(model.instance.as_ref()?)?;
+        let speculative = match &model.info.config.speculative {
+            Some(speculative) => speculative,
+            None => return Some(decoder),
+        };
+
+        let draft = model.info.metadata.custom_metadata.get("draft_model_id")
+            .and_then(|v| v.as_str())
+            .and_then(|draft_id| models.get(draft_id))
+            .and_then(|draft| draft.instance.as_ref())
+            .and_then(|instance| plugin_manager.decoder(instance));
+        let draft = match draft {
+            Some(draft) => draft,
+            None => {
+                info!("Draft model of {} is not loaded, decoding without speculation until it is", model_id);
+                return Some(decoder);
+            }
+        };
+        match (draft.as_draft_model(), Arc::clone(&decoder).as_speculative_target()) {
+            (Some(draft), Some(target)) => {
+                info!("Model {} uses speculative decoding with {} draft tokens", model_id, speculative.num_speculative_tokens);
+                Some(Arc::new(SpeculativeDecoder::new(draft, target, speculative.num_speculative_tokens)))
+            }
+            _ => {
+                warn!("Backends of model {} and its draft model do not support speculative decoding", model_id);
+                Some(decoder)
+            }
+        }
+    }
+
+    /// 显存不足时逐出与模型共用GPU、最久未访问的未固定模型，返回被逐出的模型ID
+    ///
+    /// 不要求被逐出的模型空闲超时，也不受 `eviction.enabled` 控制。
+    pub async fn evict_for_oom(&self, model_id: &ModelId) -> Option<ModelId> {
+        if !self.config.engine.eviction.evict_on_oom {
+            return None;
+        }
+
+        let mut models = self.models.write().await;
+        let devices = models.get(model_id)?.info.config.device.device_ids.clone();
+
+        let candidate = models.values()
+            .filter(|m| m.info.id != *model_id && m.is_loaded() && !m.info.pinned)
+            .filter(|m| m.info.config.device.device_type != DeviceType::CPU)
+            .filter(|m| m.info.config.device.device_ids.iter().any(|id| devices.contains(id)))
+            .min_by_key(|m| m.last_accessed)
+            .map(|m| m.info.id.clone())?;
+
+        self.evict(&mut models, &candidate, "out of memory").await;
+        Some(candidate)
+    }
+
+    /// 逐出所有空闲超时且未固定的模型，返回被逐出的模型ID（按LRU顺序）
+    pub async fn evict_idle(&self) -> Vec<ModelId> {
+        let eviction = &self.config.engine.eviction;
+        if !eviction.enabled {
+            return Vec::new();
+        }
+
+        let mut models = self.models.write().await;
+        let idle_ttl = chrono::Duration::seconds(eviction.idle_ttl_secs as i64);
+        let now = Utc::now();
+
+        let mut idle: Vec<(chrono::DateTime<Utc>, ModelId)> = models.values()
+            .filter(|m| m.is_loaded() && !m.info.pinned && now - m.last_accessed >= idle_ttl)
+            .map(|m| (m.last_accessed, m.info.id.clone()))
+            .collect();
+        idle.sort();
+
+        for (_, model_id) in &idle {
+            self.evict(&mut models, model_id, "idle timeout").await;
+        }
+        idle.into_iter().map(|(_, model_id)| model_id).collect()
+    }
+
+    /// 卸载所有已加载的模型并释放显存（服务器关闭时调用），返回被卸载的模型ID
+    pub async fn unload_all(&self) -> Vec<ModelId> {
+        let mut models = self.models.write().await;
+        let loaded: Vec<ModelId> = models.values()
+            .filter(|m| m.is_loaded())
+            .map(|m| m.info.id.clone())
+            .collect();
+        for model_id in &loaded {
+            self.evict(&mut models, model_id, "server shutdown").await;
+        }
+        loaded
+    }
+
+    /// 启动定期逐出空闲模型的任务（未启用逐出时不启动）
+    pub fn start_eviction_task(self: &Arc<Self>) {
+        let eviction = &self.config.engine.eviction;
+        if !eviction.enabled {
+            return;
+        }
+
+        let interval = Duration::from_secs(eviction.check_interval_secs.max(1));
+        let manager = Arc::clone(self);
+
+        tokio::spawn(async move {
+            info!("Idle model eviction task started (every {:?})", interval);
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                manager.evict_idle().await;
+            }
+        });
+    }
+
+    /// 检查投机解码的草稿模型：须已注册、对本模型租户可见、不是本模型，且词表大小一致
+    fn check_draft_model(
+        models: &HashMap<ModelId, Model>,
+        info: &ModelInfo,
+        speculative: &SpeculativeConfig,
+    ) -> Result<ModelId> {
+        if !(1..=MAX_SPECULATIVE_TOKENS).contains(&speculative.num_speculative_tokens) {
+            return Err(UniModelError::validation(format!(
+                "speculative.num_speculative_tokens must be between 1 and {}",
+                MAX_SPECULATIVE_TOKENS
+            )));
+        }
+
+        let draft_name = match &info.tenant {
+            Some(tenant) => format!("{}/{}", tenant, speculative.draft_model),
+            None => speculative.draft_model.clone(),
+        };
+        let draft = models.get(&speculative.draft_model)
+            .or_else(|| models.values().find(|m| m.info.qualified_name() == draft_name))
+            .filter(|m| m.info.is_visible_to(info.tenant.as_deref()))
+            .ok_or_else(|| UniModelError::validation(format!(
+                "Draft model '{}' is not registered",
+                speculative.draft_model
+            )))?;
+        if draft.info.config.speculative.is_some() {
+            return Err(UniModelError::validation("A draft model cannot itself use speculative decoding"));
+        }
+
+        if let (Some(target), Some(draft_tokenizer)) = (&info.tokenizer, &draft.info.tokenizer) {
+            if target.vocab_size() != draft_tokenizer.vocab_size() {
+                return Err(UniModelError::validation(format!(
+                    "Draft model '{}' has a vocabulary of {} tokens, target has {}",
+                    speculative.draft_model,
+                    draft_tokenizer.vocab_size(),
+                    target.vocab_size()
+                )));
+            }
+        }
+        Ok(draft.info.id.clone())
+    }
+
+    /// 检查租户资源限制
+    fn check_tenant_limits(
+        &self,
+        models: &HashMap<ModelId, Model>,
+        tenant: &str,
+        config: &ModelConfig,
     ) -> Result<()> {
+        let limits = match self.config.tenants.get(tenant) {
+            Some(limits) => limits,
+            None => return Ok(()),
+        };
+
+        let tenant_models: Vec<&Model> = models.values()
+            .filter(|m| m.info.tenant.as_deref() == Some(tenant))
+            .collect();
+
+        if let Some(max_models) = limits.max_models {
+            if tenant_models.len() >= max_models as usize {
+                return Err(UniModelError::resource(format!(
+                    "Tenant '{}' has reached its limit of {} models",
+                    tenant, max_models
+                )));
+            }
+        }
+
+        if let Some(max_gpu_memory_mb) = limits.max_gpu_memory_mb {
+            let requested = config.device.memory_limit_mb.ok_or_else(|| {
+                UniModelError::validation(format!(
+                    "Tenant '{}' has a GPU memory limit; device.memory_limit_mb must be set",
+                    tenant
+                ))
+            })?;
+            let used: u64 = tenant_models.iter()
+                .filter_map(|m| m.info.config.device.memory_limit_mb)
+                .sum();
+
+            if used + requested > max_gpu_memory_mb {
+                return Err(UniModelError::resource(format!(
+                    "Tenant '{}' GPU memory limit exceeded: {} MB used + {} MB requested > {} MB",
+                    tenant, used, requested, max_gpu_memory_mb
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 异步加载模型
+    async fn load_model_async(context: LoadContext, model_id: ModelId) -> Result<()> {
+        let LoadContext { plugin_manager, models, events, weights, quantized, decoders, scheduler } = context;
         // 获取模型配置
-        let config = {
+        let (mut config, model_type, adapters) = {
             let models = models.read().await;
             let model = models.get(&model_id)
                 .ok_or_else(|| UniModelError::model("Model not found"))?;
-            model.info.config.clone()
+            (model.info.config.clone(), model.info.model_type.clone(), model.info.adapters.clone())
+        };
+
+        // 确认所选GPU容纳得下后，通过插件管理器加载模型
+        let loaded = match &scheduler {
+            Some(scheduler) => scheduler.admit_load(&model_id, &config),
+            None => Ok(()),
+        };
+        // 要求量化而模型文件是全精度时，改为加载（必要时先生成）缓存中的量化产物
+        let loaded = match loaded {
+            Ok(()) => {
+                let quantizer = plugin_manager.quantizer(&config.backend);
+                let source_config = config.clone();
+                tokio::task::spawn_blocking(move || quantized.prepare(&source_config, quantizer.as_deref()))
+                    .await
+                    .map_err(|e| UniModelError::internal(format!("Quantization task failed: {}", e)))
+                    .and_then(|prepared| prepared)
+                    .map(|artifact| {
+                        if let Some(artifact) = artifact {
+                            config.model_path = artifact.display().to_string();
+                        }
+                    })
+            }
+            Err(e) => Err(e),
+        };
+
+        // 与其他模型共用检查点时复用已打开的权重；模拟后端不读取模型文件
+        let loaded = match loaded {
+            Ok(()) if config.backend == mock_plugin::BACKEND_NAME => {
+                Ok(Arc::new(ModelWeights::File(config.model_path.clone().into())))
+            }
+            Ok(()) => {
+                let (cache, id, model_config) = (Arc::clone(&weights), model_id.clone(), config.clone());
+                tokio::task::spawn_blocking(move || cache.acquire(&id, &model_config))
+                    .await
+                    .map_err(|e| UniModelError::internal(format!("Weight loading task failed: {}", e)))
+                    .and_then(|acquired| acquired)
+            }
+            Err(e) => Err(e),
+        };
+        let loaded = match loaded {
+            Ok(model_weights) => Self::load_instances(&plugin_manager, &model_id, &config, &model_weights).await,
+            Err(e) => Err(e),
         };
 
-        // 通过插件管理器加载模型
-        match plugin_manager.load_model(&model_id, &config).await {
-            Ok(instance) => {
+        match loaded {
+            Ok(mut instances) => {
+                // 逐出后重新加载时恢复之前加载的适配器，失败的适配器不再保留
+                let mut failed_adapters = Vec::new();
+                for adapter in &adapters {
+                    for instance in &instances {
+                        if let Err(e) = plugin_manager.load_adapter(instance, adapter).await {
+                            warn!("Failed to restore adapter '{}' on model {}: {}", adapter.name, model_id, e);
+                            failed_adapters.push(adapter.name.clone());
+                            break;
+                        }
+                    }
+                }
+
+                // 预热完成前保持加载中状态，不接收真实请求
+                for instance in &instances {
+                    Self::warmup(&plugin_manager, &model_id, &model_type, &config.warmup, instance).await;
+                    Self::capture_cuda_graphs(&plugin_manager, &model_id, &model_type, &config, instance).await;
+                }
+                let instance = instances.remove(0);
+
                 // 更新模型状态为就绪
                 let mut models = models.write().await;
                 if let Some(model) = models.get_mut(&model_id) {
+                    model.info.adapters.retain(|a| !failed_adapters.contains(&a.name));
+                    if model.info.config.model_path != config.model_path {
+                        model.info.metadata.custom_metadata
+                            .insert("quantized_artifact".to_string(), serde_json::json!(config.model_path));
+                    }
                     model.instance = Some(instance);
+                    model.replicas = instances;
                     model.update_status(ModelStatus::Ready);
                     model.info.health_status = HealthStatus::Healthy;
+                    events.record(ModelEvent::new(&model.info, ModelEventKind::Loaded, None));
                     info!("Model loaded successfully: {}", model_id);
                 }
+                // 释放写锁前登记解码器，启用连续批处理时第一个请求即按token调度
+                Self::refresh_decoders(&plugin_manager, &models, &decoders, &model_id);
             }
             Err(e) => {
+                weights.release(&model_id);
+
                 // 更新模型状态为错误
                 let mut models = models.write().await;
                 if let Some(model) = models.get_mut(&model_id) {
                     model.update_status(ModelStatus::Error(e.to_string()));
                     model.info.health_status = HealthStatus::Unhealthy;
+                    events.record(ModelEvent::new(&model.info, ModelEventKind::LoadFailed, Some(e.to_string())));
                 }
                 error!("Failed to load model {}: {}", model_id, e);
                 return Err(e);
@@ -122,19 +844,165 @@ impl ModelManager {
         Ok(())
     }
 
+    /// 加载模型的实例；多副本时在每张所列GPU上各加载一个单卡实例，任一副本失败则全部卸载
+    async fn load_instances(
+        plugin_manager: &PluginManager,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        weights: &ModelWeights,
+    ) -> Result<Vec<ModelInstance>> {
+        if config.device.parallelism != Parallelism::Replicas {
+            return Ok(vec![plugin_manager.load_model(model_id, config, weights).await?]);
+        }
+
+        let mut instances = Vec::with_capacity(config.device.device_ids.len());
+        for &device_id in &config.device.device_ids {
+            let mut replica = config.clone();
+            replica.device.device_ids = vec![device_id];
+            replica.device.parallelism = Parallelism::None;
+            match plugin_manager.load_model(model_id, &replica, weights).await {
+                Ok(instance) => instances.push(instance),
+                Err(e) => {
+                    for instance in &instances {
+                        if let Err(e) = plugin_manager.unload_model(&instance.plugin_id, &instance.handle).await {
+                            warn!("Failed to unload replica of model {}: {}", model_id, e);
+                        }
+                    }
+                    return Err(e);
+                }
+            }
+        }
+        info!("Model {} loaded as {} replicas on GPUs {:?}", model_id, instances.len(), config.device.device_ids);
+        Ok(instances)
+    }
+
+    /// 设备类型为 `Auto` 时探测本机设备并选定，其余配置原样返回
+    ///
+    /// 已指定后端时只在其声明支持的设备中选择。
+    async fn resolve_device(&self, mut config: ModelConfig) -> (ModelConfig, Option<DeviceSelection>) {
+        if config.device.device_type != DeviceType::Auto {
+            return (config, None);
+        }
+        let supported = if config.backend.is_empty() || config.backend == AUTO_BACKEND {
+            Vec::new()
+        } else {
+            self.plugin_manager.capabilities(&config.backend).map_or_else(Vec::new, |c| c.devices)
+        };
+        let selection = DeviceProbe::detect().await.select(&supported);
+        info!(
+            "Selected {:?} for {}: {}",
+            selection.device_type, config.model_path, selection.reason
+        );
+        config.device.device_type = selection.device_type.clone();
+        (config, Some(selection))
+    }
+
+    /// 未指定后端时按插件能力自动选择，否则校验配置在所选后端的能力范围内
+    ///
+    /// 没有插件声明该后端（如内置后端）时不做校验，由加载时报错。
+    fn negotiate_backend(&self, mut config: ModelConfig) -> Result<ModelConfig> {
+        if config.backend.is_empty() || config.backend == AUTO_BACKEND {
+            config.backend = self.plugin_manager.select_backend(&config)?;
+            info!("Selected backend '{}' for {}", config.backend, config.model_path);
+        } else if let Some(capabilities) = self.plugin_manager.capabilities(&config.backend) {
+            capabilities.check(&config)?;
+        }
+        config.check_cuda_graphs()?;
+        config.check_parallelism()?;
+        Ok(config)
+    }
+
+    /// 执行预热推理
+    ///
+    /// 预热失败只记录警告：占位输入未必符合模型的输入要求，不影响模型可用性。
+    async fn warmup(
+        plugin_manager: &PluginManager,
+        model_id: &ModelId,
+        model_type: &ModelType,
+        warmup: &WarmupConfig,
+        instance: &ModelInstance,
+    ) {
+        if warmup.requests == 0 {
+            return;
+        }
+
+        let started = Instant::now();
+        let parameters = PredictionParameters::default();
+        for index in 0..warmup.requests as usize {
+            let input = warmup.input(index, model_type);
+            if let Err(e) = plugin_manager.infer(instance, &[input], &parameters).await {
+                warn!("Warmup request {} for model {} failed: {}", index + 1, model_id, e);
+                return;
+            }
+        }
+        info!(
+            "Model {} warmed up with {} request(s) in {:?}",
+            model_id, warmup.requests, started.elapsed()
+        );
+    }
+
+    /// 按每个捕获批大小各执行一个批次，后端在这些调用中捕获CUDA graph
+    ///
+    /// 之后的批次会被填充到捕获过的批大小再重放，见 [`ModelConfig::cuda_graph_padded_size`]。
+    async fn capture_cuda_graphs(
+        plugin_manager: &PluginManager,
+        model_id: &ModelId,
+        model_type: &ModelType,
+        config: &ModelConfig,
+        instance: &ModelInstance,
+    ) {
+        let batch_sizes = config.cuda_graph_batch_sizes();
+        if batch_sizes.is_empty() {
+            return;
+        }
+
+        let started = Instant::now();
+        let parameters = PredictionParameters::default();
+        for &batch_size in &batch_sizes {
+            let inputs: Vec<InputData> = (0..batch_size as usize)
+                .map(|index| config.warmup.input(index, model_type))
+                .collect();
+            if let Err(e) = plugin_manager.infer(instance, &inputs, &parameters).await {
+                warn!("CUDA graph capture for model {} at batch size {} failed: {}", model_id, batch_size, e);
+                return;
+            }
+        }
+        info!(
+            "Captured CUDA graphs for model {} at batch sizes {:?} in {:?}",
+            model_id, batch_sizes, started.elapsed()
+        );
+    }
+
     /// 卸载模型
     pub async fn unregister_model(&self, model_id: &ModelId) -> Result<()> {
         let mut models = self.models.write().await;
 
+        let draft_user = models.values().find(|m| {
+            m.info.metadata.custom_metadata.get("draft_model_id").and_then(|v| v.as_str()) == Some(model_id.as_str())
+        });
+        if let Some(target) = draft_user {
+            return Err(UniModelError::validation(format!(
+                "Model is the draft model of '{}'; unregister that model first",
+                target.info.qualified_name()
+            )));
+        }
+
         if let Some(mut model) = models.remove(model_id) {
+            self.decoders.remove(model_id);
             // 通过插件管理器卸载模型
-            if let Some(instance) = &model.instance {
+            for instance in model.instances() {
                 if let Err(e) = self.plugin_manager.unload_model(&instance.plugin_id, &instance.handle).await {
                     warn!("Failed to unload model from plugin: {}", e);
                 }
             }
+            self.weights.release(model_id);
+
+            if let Some(scheduler) = &self.scheduler {
+                scheduler.release(model_id);
+            }
 
             model.update_status(ModelStatus::Unloaded);
+            self.events.record(ModelEvent::new(&model.info, ModelEventKind::Unregistered, None));
             info!("Model unregistered: {}", model_id);
             Ok(())
         } else {
@@ -150,6 +1018,103 @@ impl ModelManager {
         Ok(model.info.clone())
     }
 
+    /// 设置模型是否固定驻留
+    pub async fn set_pinned(&self, model_id: &ModelId, pinned: bool) -> Result<ModelInfo> {
+        let mut models = self.models.write().await;
+        let model = models.get_mut(model_id)
+            .ok_or_else(|| UniModelError::model("Model not found"))?;
+
+        if model.info.pinned != pinned {
+            model.info.pinned = pinned;
+            model.info.metadata.updated_at = Utc::now();
+            info!("Model {} {}", model_id, if pinned { "pinned" } else { "unpinned" });
+        }
+        Ok(model.info.clone())
+    }
+
+    /// 在已加载的模型上加载LoRA适配器
+    pub async fn load_adapter(&self, model_id: &ModelId, name: String, path: String) -> Result<ModelInfo> {
+        let valid_name = !name.is_empty()
+            && name.len() <= 64
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+        if !valid_name {
+            return Err(UniModelError::validation(
+                "Adapter name must be 1-64 characters of letters, digits, '-', '_' or '.'",
+            ));
+        }
+        if tokio::fs::metadata(&path).await.is_err() {
+            return Err(UniModelError::validation(format!("Adapter path '{}' does not exist", path)));
+        }
+
+        let mut models = self.models.write().await;
+        let model = models.get_mut(model_id)
+            .ok_or_else(|| UniModelError::model("Model not found"))?;
+        if model.info.adapter(&name).is_some() {
+            return Err(UniModelError::validation(format!("Adapter '{}' is already loaded", name)));
+        }
+        if model.info.adapters.len() >= MAX_ADAPTERS_PER_MODEL {
+            return Err(UniModelError::resource(format!(
+                "Model already has the maximum of {} adapters",
+                MAX_ADAPTERS_PER_MODEL
+            )));
+        }
+        if model.instance.is_none() {
+            return Err(UniModelError::model("Model not loaded"));
+        }
+
+        let adapter = LoraAdapter {
+            name,
+            path,
+            loaded_at: Utc::now(),
+        };
+        // 多副本时每个副本都加载；某个副本失败时从已加载的副本上撤回
+        let instances: Vec<ModelInstance> = model.instances().cloned().collect();
+        for (index, instance) in instances.iter().enumerate() {
+            if let Err(e) = self.plugin_manager.load_adapter(instance, &adapter).await {
+                for loaded in &instances[..index] {
+                    let _ = self.plugin_manager.unload_adapter(loaded, &adapter.name).await;
+                }
+                return Err(e);
+            }
+        }
+
+        info!("Adapter '{}' loaded on model {}", adapter.name, model_id);
+        self.events.record(ModelEvent::new(&model.info, ModelEventKind::AdapterLoaded, Some(adapter.name.clone())));
+        model.info.adapters.push(adapter);
+        model.info.metadata.updated_at = Utc::now();
+        Ok(model.info.clone())
+    }
+
+    /// 模型的后端能否在同一批次中混用LoRA适配器
+    pub async fn supports_mixed_adapters(&self, model_id: &ModelId) -> bool {
+        let models = self.models.read().await;
+        models.get(model_id)
+            .and_then(|m| m.instance.as_ref())
+            .is_some_and(|instance| instance.supports_multi_lora)
+    }
+
+    /// 卸载模型上的LoRA适配器
+    pub async fn unload_adapter(&self, model_id: &ModelId, name: &str) -> Result<ModelInfo> {
+        let mut models = self.models.write().await;
+        let model = models.get_mut(model_id)
+            .ok_or_else(|| UniModelError::model("Model not found"))?;
+        let index = model.info.adapters.iter()
+            .position(|a| a.name == name)
+            .ok_or_else(|| UniModelError::model(format!("Adapter '{}' is not loaded", name)))?;
+
+        for instance in model.instances() {
+            if let Err(e) = self.plugin_manager.unload_adapter(instance, name).await {
+                warn!("Failed to unload adapter '{}' from plugin: {}", name, e);
+            }
+        }
+
+        model.info.adapters.remove(index);
+        model.info.metadata.updated_at = Utc::now();
+        info!("Adapter '{}' unloaded from model {}", name, model_id);
+        self.events.record(ModelEvent::new(&model.info, ModelEventKind::AdapterUnloaded, Some(name.to_string())));
+        Ok(model.info.clone())
+    }
+
     /// 获取所有模型列表
     pub async fn list_models(&self) -> Result<Vec<ModelInfo>> {
         let models = self.models.read().await;
@@ -160,6 +1125,10 @@ impl ModelManager {
     pub async fn get_model_for_inference(&self, model_id: &ModelId) -> Result<Model> {
         let mut models = self.models.write().await;
 
+        if models.get(model_id).is_some_and(|m| m.info.status == ModelStatus::Unloaded) {
+            return Err(self.reload_evicted(&mut models, model_id).await);
+        }
+
         match models.get_mut(model_id) {
             Some(model) => {
                 if !model.is_loaded() {
@@ -176,6 +1145,30 @@ impl ModelManager {
         }
     }
 
+    /// 重新加载被逐出的模型，返回告知调用方稍后重试的错误
+    async fn reload_evicted(&self, models: &mut HashMap<ModelId, Model>, model_id: &ModelId) -> UniModelError {
+        let mut model = match models.remove(model_id) {
+            Some(model) => model,
+            None => return UniModelError::model("Model not found"),
+        };
+
+        let admitted = self.admit(models, &mut model).await;
+        match admitted {
+            Ok(()) => {
+                model.update_status(ModelStatus::Loading);
+                model.touch();
+                models.insert(model_id.clone(), model);
+                info!("Reloading evicted model: {}", model_id);
+                self.spawn_load(model_id.clone());
+                UniModelError::model("Model was evicted and is being reloaded, retry later")
+            }
+            Err(e) => {
+                models.insert(model_id.clone(), model);
+                e
+            }
+        }
+    }
+
     /// 更新模型性能统计
     pub async fn update_model_performance(
         &self,
@@ -205,9 +1198,7 @@ impl ModelManager {
             .filter(|m| m.is_healthy())
             .count();
 
-        if healthy_count == models.len() {
-            HealthStatus::Healthy
-        } else if healthy_count > 0 {
+        if healthy_count > 0 {
             HealthStatus::Healthy // 至少有一个健康的模型
         } else {
             HealthStatus::Unhealthy
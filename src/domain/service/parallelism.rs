@@ -0,0 +1,262 @@
+//! 多GPU并行的切分方案
+//!
+//! 张量并行按Megatron的方式切分Transformer权重：注意力的Q/K/V投影和MLP的上投影按输出维度
+//! 切分（列并行），注意力输出投影和MLP的下投影按输入维度切分（行并行），每层只需一次all-reduce；
+//! 词表嵌入和输出头按词表维度切分，归一化等小张量在每张卡上复制。切分方案在加载时由服务器根据
+//! safetensors头部生成并随加载请求交给插件，插件把各分片放到对应的GPU上。
+//!
+//! 流水线并行把连续的若干层作为一个阶段放到一张GPU上，阶段之间只传递激活值，适合张量并行的
+//! 通信开销过大或单机GPU仍放不下的模型。批次被拆成若干微批次依次送入，第k个阶段处理第i个微批次时
+//! 第k+1个阶段处理第i-1个，流水线填满后各阶段同时工作。
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::domain::model::{ModelConfig, Parallelism};
+use crate::plugins::interface::ModelWeights;
+
+/// 按输出维度（`[out, in]` 布局的第0维）切分的层
+const COLUMN_PARALLEL: &[&str] = &[
+    "q_proj", "k_proj", "v_proj", "qkv_proj", "query_key_value", "c_attn",
+    "gate_proj", "up_proj", "gate_up_proj", "fc1", "c_fc", "w1", "w3", "dense_h_to_4h",
+    "embed_tokens", "wte", "word_embeddings", "lm_head",
+];
+
+/// 层编号之前的路径段，如 `model.layers.3.mlp` 中的 `layers`
+const LAYER_CONTAINERS: &[&str] = &["layers", "layer", "h", "blocks", "block"];
+
+/// 不属于任何层、放在第一个阶段的张量名片段，其余放在最后一个阶段
+const INPUT_TENSORS: &[&str] = &["embed", "wte", "wpe", "word_embeddings", "position_embeddings"];
+
+/// 按输入维度（最后一维）切分的层
+const ROW_PARALLEL: &[&str] = &["o_proj", "out_proj", "down_proj", "fc2", "c_proj", "dense", "dense_4h_to_h", "w2"];
+
+/// 张量的切分方式
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShardSplit {
+    /// 沿第0维切分
+    Column,
+    /// 沿最后一维切分；行并行层的偏置在all-reduce之后只加一次，按复制处理
+    Row,
+    /// 每张卡上保存完整副本
+    Replicated,
+}
+
+impl ShardSplit {
+    /// 按张量名和形状确定切分方式
+    pub fn for_tensor(name: &str, shape: &[usize]) -> Self {
+        let mut segments = name.rsplit('.');
+        let (param, layer) = (segments.next().unwrap_or(name), segments.next().unwrap_or(""));
+        // 嵌入层的张量名为 `embed_tokens.weight` 等，也可能没有 `.weight` 后缀
+        let layer = if param == "weight" || param == "bias" { layer } else { param };
+
+        if COLUMN_PARALLEL.contains(&layer) && !shape.is_empty() {
+            ShardSplit::Column
+        } else if ROW_PARALLEL.contains(&layer) && shape.len() >= 2 {
+            ShardSplit::Row
+        } else {
+            ShardSplit::Replicated
+        }
+    }
+
+    /// `ndim` 维张量被切分的维度
+    pub fn dim(self, ndim: usize) -> Option<usize> {
+        match self {
+            ShardSplit::Column => Some(0),
+            ShardSplit::Row => Some(ndim - 1),
+            ShardSplit::Replicated => None,
+        }
+    }
+}
+
+/// 单个张量的分片
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TensorShard {
+    pub name: String,
+    pub split: ShardSplit,
+    /// 完整形状
+    pub shape: Vec<usize>,
+    /// 每张卡上分片的形状
+    pub shard_shape: Vec<usize>,
+}
+
+impl TensorShard {
+    /// 第 `rank` 张卡在被切分维度上的下标范围，复制的张量为 `None`
+    pub fn range(&self, rank: usize) -> Option<(usize, usize)> {
+        let dim = self.split.dim(self.shape.len())?;
+        let len = self.shard_shape[dim];
+        Some((rank * len, (rank + 1) * len))
+    }
+}
+
+/// 张量并行方案，随 `load_model` 请求交给插件
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TensorParallelPlan {
+    /// 第i个分片所在的GPU
+    pub device_ids: Vec<u32>,
+    /// 各张量的分片；权重不是safetensors时为空，由后端自行切分
+    pub tensors: Vec<TensorShard>,
+}
+
+impl TensorParallelPlan {
+    /// 按权重和GPU列表生成方案，被切分的维度须能被GPU数整除
+    pub fn new(weights: &ModelWeights, device_ids: &[u32]) -> Result<Self> {
+        if device_ids.len() < 2 {
+            return Err(UniModelError::validation("Tensor parallelism needs at least two GPUs"));
+        }
+        let world_size = device_ids.len();
+
+        let infos: Vec<_> = match weights {
+            ModelWeights::SafeTensors(files) => files.iter().flat_map(|file| file.tensors()).collect(),
+            ModelWeights::File(_) => Vec::new(),
+        };
+        let tensors = infos
+            .into_iter()
+            .map(|info| {
+                let split = ShardSplit::for_tensor(&info.name, &info.shape);
+                let mut shard_shape = info.shape.clone();
+                if let Some(dim) = split.dim(info.shape.len()) {
+                    if info.shape[dim] % world_size != 0 {
+                        return Err(UniModelError::validation(format!(
+                            "Tensor '{}' with shape {:?} cannot be split into {} shards along dimension {}",
+                            info.name, info.shape, world_size, dim
+                        )));
+                    }
+                    shard_shape[dim] /= world_size;
+                }
+                Ok(TensorShard {
+                    name: info.name.clone(),
+                    split,
+                    shape: info.shape.clone(),
+                    shard_shape,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            device_ids: device_ids.to_vec(),
+            tensors,
+        })
+    }
+
+    /// 分片数
+    pub fn world_size(&self) -> usize {
+        self.device_ids.len()
+    }
+
+    /// 按名称查找张量的分片
+    pub fn tensor(&self, name: &str) -> Option<&TensorShard> {
+        self.tensors.iter().find(|t| t.name == name)
+    }
+}
+
+/// 流水线的一个阶段
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PipelineStage {
+    pub device_id: u32,
+    /// 本阶段的层 `[first_layer, end_layer)`
+    pub first_layer: usize,
+    pub end_layer: usize,
+    /// 本阶段加载的张量
+    pub tensors: Vec<String>,
+}
+
+/// 流水线并行方案，阶段按 `device_ids` 的顺序排列
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct PipelinePlan {
+    pub stages: Vec<PipelineStage>,
+    /// 每个批次拆成的微批次数
+    pub micro_batches: usize,
+    /// 模型的层数；权重不是safetensors时为0，由后端按阶段数自行划分
+    pub num_layers: usize,
+}
+
+impl PipelinePlan {
+    /// 把层均匀划分到各GPU上，靠前的阶段多分余下的层
+    pub fn new(weights: &ModelWeights, device_ids: &[u32], micro_batches: usize) -> Result<Self> {
+        if device_ids.len() < 2 {
+            return Err(UniModelError::validation("Pipeline parallelism needs at least two GPUs"));
+        }
+        let names: Vec<String> = match weights {
+            ModelWeights::SafeTensors(files) => files.iter().flat_map(|file| file.tensors()).map(|t| t.name.clone()).collect(),
+            ModelWeights::File(_) => Vec::new(),
+        };
+        let num_layers = names.iter().filter_map(|name| layer_index(name)).max().map_or(0, |last| last + 1);
+        if !names.is_empty() && num_layers < device_ids.len() {
+            return Err(UniModelError::validation(format!(
+                "Model has {} layer(s), fewer than the {} pipeline stages",
+                num_layers,
+                device_ids.len()
+            )));
+        }
+
+        let (per_stage, extra) = (num_layers / device_ids.len(), num_layers % device_ids.len());
+        let mut first_layer = 0;
+        let mut stages: Vec<PipelineStage> = device_ids
+            .iter()
+            .enumerate()
+            .map(|(i, &device_id)| {
+                let end_layer = first_layer + per_stage + usize::from(i < extra);
+                let stage = PipelineStage { device_id, first_layer, end_layer, tensors: Vec::new() };
+                first_layer = end_layer;
+                stage
+            })
+            .collect();
+
+        let last = stages.len() - 1;
+        for name in names {
+            let stage = match layer_index(&name) {
+                Some(layer) => stages.iter().position(|s| layer < s.end_layer).unwrap_or(last),
+                None if INPUT_TENSORS.iter().any(|t| name.contains(t)) => 0,
+                None => last,
+            };
+            stages[stage].tensors.push(name);
+        }
+
+        Ok(Self {
+            stages,
+            micro_batches: micro_batches.max(1),
+            num_layers,
+        })
+    }
+
+    /// 张量所在的阶段
+    pub fn stage_of(&self, name: &str) -> Option<usize> {
+        self.stages.iter().position(|s| s.tensors.iter().any(|t| t == name))
+    }
+}
+
+/// 张量名中的层编号
+fn layer_index(name: &str) -> Option<usize> {
+    let segments: Vec<&str> = name.split('.').collect();
+    segments
+        .windows(2)
+        .find(|pair| LAYER_CONTAINERS.contains(&pair[0]))
+        .and_then(|pair| pair[1].parse().ok())
+}
+
+/// 加载时交给插件的多GPU方案
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ParallelPlan {
+    Tensor(TensorParallelPlan),
+    Pipeline(PipelinePlan),
+}
+
+impl ParallelPlan {
+    /// 按模型配置生成方案，单卡运行或多副本时为 `None`
+    pub fn for_model(config: &ModelConfig, weights: &ModelWeights) -> Result<Option<Self>> {
+        let device_ids = &config.device.device_ids;
+        Ok(match config.device.parallelism {
+            // 每个副本都是完整的单卡实例
+            Parallelism::None | Parallelism::Replicas => None,
+            Parallelism::Tensor => Some(ParallelPlan::Tensor(TensorParallelPlan::new(weights, device_ids)?)),
+            Parallelism::Pipeline => Some(ParallelPlan::Pipeline(PipelinePlan::new(
+                weights,
+                device_ids,
+                config.micro_batches(),
+            )?)),
+        })
+    }
+}
@@ -0,0 +1,124 @@
+//! 加载时量化
+//!
+//! 模型配置要求INT8/INT4/FP16而模型文件仍是更高精度时，加载前由后端的 [`Quantizer`] 量化，
+//! 结果写入 `cache_storage_path/quantized`，以源文件路径、大小、修改时间和量化类型命名，
+//! 下次加载直接使用缓存。后端不支持所需的量化时按原精度加载。
+//! 动态量化在推理时进行，不生成缓存文件。
+
+use std::path::{Path, PathBuf};
+
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+use crate::common::error::*;
+use crate::common::types::DataType;
+use crate::domain::model::{ModelConfig, QuantizationType};
+use crate::infrastructure::model_format::GgufHeader;
+use crate::plugins::interface::{ModelWeights, Quantizer};
+
+/// 量化产物缓存
+#[derive(Debug, Clone)]
+pub struct QuantizedCache {
+    dir: PathBuf,
+}
+
+impl QuantizedCache {
+    /// 在 `cache_storage_path` 下创建缓存
+    pub fn new<P: AsRef<Path>>(cache_storage_path: P) -> Self {
+        Self {
+            dir: cache_storage_path.as_ref().join("quantized"),
+        }
+    }
+
+    /// 量化产物的缓存路径，源文件变化后路径随之变化
+    pub fn artifact_path(&self, source: &Path, quantization: &QuantizationType) -> Result<PathBuf> {
+        let source = std::fs::canonicalize(source)?;
+        let metadata = std::fs::metadata(&source)?;
+        let modified = metadata.modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_nanos());
+
+        let mut hasher = Sha256::new();
+        hasher.update(source.display().to_string().as_bytes());
+        hasher.update(metadata.len().to_le_bytes());
+        hasher.update(modified.to_le_bytes());
+        let digest: String = hasher.finalize().iter().take(8).map(|b| format!("{:02x}", b)).collect();
+
+        let extension = source.extension()
+            .filter(|_| source.is_file())
+            .and_then(|ext| ext.to_str())
+            .unwrap_or("safetensors");
+        Ok(self.dir.join(format!("{}-{}.{}", digest, quantization.name(), extension)))
+    }
+
+    /// 准备模型要加载的文件，返回量化产物的路径；无需量化或无法量化时返回None，按原文件加载
+    ///
+    /// 量化涉及大量文件IO，应在阻塞线程池中调用。
+    pub fn prepare(&self, config: &ModelConfig, quantizer: Option<&dyn Quantizer>) -> Result<Option<PathBuf>> {
+        let quantization = match &config.optimization.quantization {
+            Some(QuantizationType::Dynamic) | None => return Ok(None),
+            Some(quantization) => quantization,
+        };
+        let source = Path::new(&config.model_path);
+        let artifact = self.artifact_path(source, quantization)?;
+        if artifact.is_file() {
+            info!("Using cached {} artifact {}", quantization.name(), artifact.display());
+            return Ok(Some(artifact));
+        }
+        if !needs_quantization(source, quantization)? {
+            return Ok(None);
+        }
+
+        let quantizer = match quantizer.filter(|q| q.supports(quantization)) {
+            Some(quantizer) => quantizer,
+            None => {
+                warn!(
+                    "Backend '{}' cannot quantize to {}, loading {} at original precision",
+                    config.backend,
+                    quantization.name(),
+                    config.model_path
+                );
+                return Ok(None);
+            }
+        };
+
+        // 先写临时文件再改名，中断的量化不会留下看似完整的缓存
+        std::fs::create_dir_all(&self.dir)?;
+        let partial = artifact.with_extension("partial");
+        let weights = ModelWeights::open(config)?;
+        if let Err(e) = quantizer.quantize(&weights, quantization, &partial) {
+            let _ = std::fs::remove_file(&partial);
+            return Err(e);
+        }
+        std::fs::rename(&partial, &artifact)?;
+        info!("Quantized {} to {} at {}", config.model_path, quantization.name(), artifact.display());
+        Ok(Some(artifact))
+    }
+}
+
+/// 模型文件的精度是否高于目标量化类型
+///
+/// 解析safetensors的张量类型和GGUF的 `general.file_type`；无法判断精度的格式交给后端处理。
+pub fn needs_quantization(path: &Path, target: &QuantizationType) -> Result<bool> {
+    let is_gguf = path.extension().is_some_and(|ext| ext == "gguf");
+    if is_gguf {
+        let header = GgufHeader::read(path)?;
+        return Ok(match header.quantization().as_deref() {
+            Some("F32") => true,
+            Some("F16") => !matches!(target, QuantizationType::FP16),
+            Some(_) => false,
+            None => true,
+        });
+    }
+
+    let files = match ModelWeights::open_path(path)? {
+        ModelWeights::SafeTensors(files) => files,
+        ModelWeights::File(_) => return Ok(true),
+    };
+    let higher_precision = |dtype: DataType| match target {
+        QuantizationType::FP16 => dtype == DataType::F32 || dtype == DataType::F64,
+        _ => matches!(dtype, DataType::F16 | DataType::BF16 | DataType::F32 | DataType::F64),
+    };
+    Ok(files.iter().flat_map(|f| f.tensors()).any(|t| higher_precision(t.dtype)))
+}
@@ -0,0 +1,65 @@
+//! 模型副本间的负载均衡
+//!
+//! 多副本的模型在每张GPU上各有一个实例。批处理器每执行一个批次，先从模型的实例中选出在途批次
+//! 最少的一个，批次完成（或被放弃）时归还；排队深度相同时选排在前面的实例。单实例模型的
+//! 选择总是落在唯一的实例上。
+
+use std::sync::Arc;
+
+use dashmap::DashMap;
+
+use crate::domain::model::ModelInstance;
+
+/// 各实例的在途批次数
+#[derive(Debug, Default)]
+pub struct ReplicaBalancer {
+    depth: Arc<DashMap<String, usize>>, // 实例ID -> 在途批次数
+}
+
+impl ReplicaBalancer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 选择在途批次最少的实例并占用，实例列表为空时返回 `None`
+    pub fn acquire<'a>(&self, instances: impl IntoIterator<Item = &'a ModelInstance>) -> Option<ReplicaLease> {
+        let instance = instances
+            .into_iter()
+            .enumerate()
+            .min_by_key(|(index, instance)| (self.depth(&instance.id), *index))
+            .map(|(_, instance)| instance.clone())?;
+        *self.depth.entry(instance.id.clone()).or_insert(0) += 1;
+        Some(ReplicaLease {
+            depth: Arc::clone(&self.depth),
+            instance,
+        })
+    }
+
+    /// 实例的在途批次数
+    pub fn depth(&self, instance_id: &str) -> usize {
+        self.depth.get(instance_id).map_or(0, |d| *d)
+    }
+}
+
+/// 占用中的实例，释放时在途批次数减一
+#[derive(Debug)]
+pub struct ReplicaLease {
+    depth: Arc<DashMap<String, usize>>,
+    instance: ModelInstance,
+}
+
+impl ReplicaLease {
+    pub fn instance(&self) -> &ModelInstance {
+        &self.instance
+    }
+}
+
+impl Drop for ReplicaLease {
+    fn drop(&mut self) {
+        // 归零的实例移除，卸载后不留下记录
+        self.depth.remove_if_mut(&self.instance.id, |_, depth| {
+            *depth -= 1;
+            *depth == 0
+        });
+    }
+}
@@ -0,0 +1,360 @@
+//! GPU感知调度器
+//!
+//! 跟踪每张GPU的可用显存（按 `memory_fraction` 折算）、已放置的模型与当前负载，
+//! 为新注册的模型选择设备；没有设备容纳得下时拒绝放置。设备表中的卡都属于
+//! `engine.gpu.accelerator`（CUDA、ROCm 或 NPU），要求其他加速器的模型无法放置。
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::RwLock;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::*;
+use crate::infrastructure::configuration::Config;
+use crate::infrastructure::monitoring::query_devices;
+
+/// 无法探测显存且未配置时假定的单卡显存（MB）
+const DEFAULT_DEVICE_MEMORY_MB: u64 = 16384;
+
+/// GPU负载刷新间隔
+const PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// 模型声明显存占用的自定义参数名
+pub const MEMORY_FOOTPRINT_PARAM: &str = "memory_footprint_mb";
+
+/// 加载后显存占用与权重文件大小之比（含运行时工作区与激活）
+fn backend_memory_factor(backend: &str) -> f64 {
+    match backend.to_ascii_lowercase().as_str() {
+        "tensorrt" => 1.05,
+        "onnx" => 1.1,
+        "gguf" | "llama.cpp" => 1.1,
+        "pytorch" | "torch" => 1.2,
+        _ => 1.2,
+    }
+}
+
+/// 估算模型加载后的显存占用（MB）
+///
+/// 优先使用 `custom_params.memory_footprint_mb` 声明的值，否则按权重文件大小乘以后端系数估算；
+/// 模型文件不可读时返回None。
+pub fn estimate_memory_mb(config: &ModelConfig) -> Option<u64> {
+    if let Some(declared) = config.custom_params.get(MEMORY_FOOTPRINT_PARAM).and_then(|v| v.as_u64()) {
+        return Some(declared);
+    }
+
+    let bytes = path_size_bytes(Path::new(&config.model_path)).ok()?;
+    let mb = bytes as f64 / (1024.0 * 1024.0);
+    Some((mb * backend_memory_factor(&config.backend)).ceil() as u64)
+}
+
+/// 文件大小，目录按其中所有文件求和
+fn path_size_bytes(path: &Path) -> std::io::Result<u64> {
+    let metadata = std::fs::metadata(path)?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        total += path_size_bytes(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// 模型在 `devices` 张卡中每张卡上占用的显存：切分的模型均分，副本各占一份
+fn per_device_mb(config: &ModelConfig, devices: usize) -> u64 {
+    match config.device.parallelism {
+        Parallelism::Replicas => required_memory_mb(config),
+        _ => required_memory_mb(config).div_ceil(devices.max(1) as u64),
+    }
+}
+
+/// 模型需要预留的显存：取声明的上限与估算值中较大者
+fn required_memory_mb(config: &ModelConfig) -> u64 {
+    let declared = config.device.memory_limit_mb.unwrap_or(0);
+    declared.max(estimate_memory_mb(config).unwrap_or(0))
+}
+
+/// 单张GPU的调度状态
+#[derive(Debug, Clone)]
+struct DeviceState {
+    total_memory_mb:  u64,
+    usable_memory_mb: u64,                  // total × memory_fraction
+    reservations:     HashMap<ModelId, u64>, // 模型 -> 预留显存（MB）
+    utilization:      Option<f32>,
+}
+
+impl DeviceState {
+    fn reserved_mb(&self) -> u64 {
+        self.reservations.values().sum()
+    }
+
+    fn free_mb(&self) -> u64 {
+        self.usable_memory_mb.saturating_sub(self.reserved_mb())
+    }
+}
+
+/// 设备状态快照
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceStatus {
+    pub device_id: u32,
+    pub device_type: DeviceType,
+    pub total_memory_mb: u64,
+    pub usable_memory_mb: u64,
+    pub reserved_memory_mb: u64,
+    pub free_memory_mb: u64,
+    pub utilization: Option<f32>,
+    pub models: Vec<ModelId>,
+}
+
+/// 调度器
+#[derive(Debug)]
+pub struct Scheduler {
+    accelerator:     DeviceType,
+    memory_fraction: f32,
+    devices:         RwLock<BTreeMap<u32, DeviceState>>,
+    running:         AtomicBool,
+}
+
+impl Scheduler {
+    /// 创建调度器并探测设备显存
+    pub async fn new(config: &Config) -> Result<Self> {
+        let gpu = &config.engine.gpu;
+
+        let probed: HashMap<u32, GpuUsage> = match gpu.device_memory_mb {
+            Some(_) => HashMap::new(),
+            None => match query_devices(&gpu.accelerator).await {
+                Ok(gpus) => gpus.into_iter().map(|g| (g.device_id, g)).collect(),
+                Err(e) => {
+                    warn!(
+                        "{:?} probe failed, assuming {} MB per device: {}",
+                        gpu.accelerator, DEFAULT_DEVICE_MEMORY_MB, e
+                    );
+                    HashMap::new()
+                }
+            },
+        };
+
+        let devices = gpu.device_ids
+            .iter()
+            .map(|&device_id| {
+                let total_memory_mb = gpu.device_memory_mb
+                    .or_else(|| probed.get(&device_id).map(|g| g.memory_total_bytes / 1024 / 1024))
+                    .unwrap_or(DEFAULT_DEVICE_MEMORY_MB);
+                let state = DeviceState {
+                    total_memory_mb,
+                    usable_memory_mb: (total_memory_mb as f64 * gpu.memory_fraction as f64) as u64,
+                    reservations: HashMap::new(),
+                    utilization: probed.get(&device_id).map(|g| g.utilization),
+                };
+                (device_id, state)
+            })
+            .collect();
+
+        Ok(Self {
+            accelerator: gpu.accelerator.clone(),
+            memory_fraction: gpu.memory_fraction,
+            devices: RwLock::new(devices),
+            running: AtomicBool::new(false),
+        })
+    }
+
+    /// 启动调度器，定期刷新GPU负载
+    pub async fn start(self: &Arc<Self>) -> Result<()> {
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err(UniModelError::internal("Scheduler already running"));
+        }
+
+        info!("Starting scheduler with {} {:?} device(s)", self.devices.read().len(), self.accelerator);
+
+        let scheduler = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(PROBE_INTERVAL);
+            while scheduler.running.load(Ordering::SeqCst) {
+                ticker.tick().await;
+                scheduler.refresh_load().await;
+            }
+        });
+
+        Ok(())
+    }
+
+    /// 停止调度器
+    pub async fn stop(&self) -> Result<()> {
+        self.running.store(false, Ordering::SeqCst);
+        info!("Stopping scheduler");
+        Ok(())
+    }
+
+    /// 为模型选择设备并预留显存
+    ///
+    /// 模型声明的 `device_ids` 为候选设备（为空时可使用任意设备），
+    /// 在容纳得下的设备中选择剩余显存最多、负载最低的一张。CPU模型不占用GPU。
+    /// 张量并行和流水线并行的模型使用全部所列设备，每张卡预留均分后的显存；
+    /// 多副本的模型在每张所列设备上预留完整的显存。
+    pub fn place(&self, model_id: &ModelId, config: &ModelConfig) -> Result<Vec<u32>> {
+        if config.device.device_type == DeviceType::CPU {
+            return Ok(Vec::new());
+        }
+        if config.device.device_type.is_accelerator() && config.device.device_type != self.accelerator {
+            return Err(UniModelError::scheduling(format!(
+                "Model {} needs a {:?} device but this host schedules {:?} devices",
+                model_id, config.device.device_type, self.accelerator
+            )));
+        }
+        if config.device.parallelism != Parallelism::None {
+            return self.place_sharded(model_id, config);
+        }
+
+        let required_mb = required_memory_mb(config);
+        let mut devices = self.devices.write();
+
+        let chosen = devices
+            .iter()
+            .filter(|(id, _)| config.device.device_ids.is_empty() || config.device.device_ids.contains(id))
+            .filter(|(_, state)| state.free_mb() >= required_mb)
+            .max_by(|(_, a), (_, b)| {
+                a.free_mb()
+                    .cmp(&b.free_mb())
+                    .then(b.utilization.unwrap_or(0.0).total_cmp(&a.utilization.unwrap_or(0.0)))
+            })
+            .map(|(id, _)| *id);
+
+        let device_id = chosen.ok_or_else(|| {
+            UniModelError::scheduling(format!(
+                "No GPU has {} MB free for model {} (memory fraction {})",
+                required_mb, model_id, self.memory_fraction
+            ))
+        })?;
+
+        if let Some(state) = devices.get_mut(&device_id) {
+            state.reservations.insert(model_id.clone(), required_mb);
+        }
+        info!("Model {} placed on GPU {} ({} MB reserved)", model_id, device_id, required_mb);
+
+        Ok(vec![device_id])
+    }
+
+    /// 多GPU并行的放置：每张所列设备都须容纳一个分片、流水线阶段或副本
+    fn place_sharded(&self, model_id: &ModelId, config: &ModelConfig) -> Result<Vec<u32>> {
+        let device_ids = &config.device.device_ids;
+        let shard_mb = per_device_mb(config, device_ids.len());
+        let mut devices = self.devices.write();
+
+        for device_id in device_ids {
+            let state = devices.get(device_id).ok_or_else(|| {
+                UniModelError::scheduling(format!("Model {} is placed on GPU {}, which does not exist", model_id, device_id))
+            })?;
+            if state.free_mb() < shard_mb {
+                return Err(UniModelError::scheduling(format!(
+                    "GPU {} has only {} MB free but model {} needs {} MB per shard (memory fraction {})",
+                    device_id, state.free_mb(), model_id, shard_mb, self.memory_fraction
+                )));
+            }
+        }
+        for device_id in device_ids {
+            if let Some(state) = devices.get_mut(device_id) {
+                state.reservations.insert(model_id.clone(), shard_mb);
+            }
+        }
+        info!("Model {} sharded across GPUs {:?} ({} MB reserved on each)", model_id, device_ids, shard_mb);
+
+        Ok(device_ids.clone())
+    }
+
+    /// 加载前的显存准入检查
+    ///
+    /// 重新估算模型占用；超出放置时的预留则尝试在所选设备上追加预留，
+    /// 设备剩余显存不足时返回资源错误，避免加载到一半时CUDA OOM。
+    /// 多GPU并行的模型在每张卡上按分片检查。
+    pub fn admit_load(&self, model_id: &ModelId, config: &ModelConfig) -> Result<()> {
+        if config.device.device_type == DeviceType::CPU {
+            return Ok(());
+        }
+
+        let mut devices = self.devices.write();
+        let placed: Vec<u32> = devices
+            .iter()
+            .filter(|(_, s)| s.reservations.contains_key(model_id))
+            .map(|(&id, _)| id)
+            .collect();
+        if placed.is_empty() {
+            return Ok(());
+        }
+        let required_mb = per_device_mb(config, placed.len());
+
+        for device_id in &placed {
+            let state = &devices[device_id];
+            let reserved_mb = state.reservations[model_id];
+            if required_mb > reserved_mb && state.free_mb() < required_mb - reserved_mb {
+                return Err(UniModelError::resource(format!(
+                    "Model {} needs about {} MB but GPU {} has only {} MB free (memory fraction {})",
+                    model_id, required_mb, device_id, state.free_mb() + reserved_mb, self.memory_fraction
+                )));
+            }
+        }
+
+        for device_id in &placed {
+            if let Some(state) = devices.get_mut(device_id) {
+                if state.reservations[model_id] < required_mb {
+                    state.reservations.insert(model_id.clone(), required_mb);
+                    debug!("Reservation for model {} on GPU {} raised to {} MB", model_id, device_id, required_mb);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// 释放模型的显存预留
+    pub fn release(&self, model_id: &ModelId) {
+        let mut devices = self.devices.write();
+        for (device_id, state) in devices.iter_mut() {
+            if let Some(reserved_mb) = state.reservations.remove(model_id) {
+                debug!("Released {} MB on GPU {} for model {}", reserved_mb, device_id, model_id);
+            }
+        }
+    }
+
+    /// 获取所有设备的调度状态
+    pub fn device_status(&self) -> Vec<DeviceStatus> {
+        self.devices
+            .read()
+            .iter()
+            .map(|(&device_id, state)| DeviceStatus {
+                device_id,
+                device_type: self.accelerator.clone(),
+                total_memory_mb: state.total_memory_mb,
+                usable_memory_mb: state.usable_memory_mb,
+                reserved_memory_mb: state.reserved_mb(),
+                free_memory_mb: state.free_mb(),
+                utilization: state.utilization,
+                models: state.reservations.keys().cloned().collect(),
+            })
+            .collect()
+    }
+
+    /// 刷新GPU利用率
+    async fn refresh_load(&self) {
+        let gpus = match query_devices(&self.accelerator).await {
+            Ok(gpus) => gpus,
+            Err(e) => {
+                debug!("GPU probe failed: {}", e);
+                return;
+            }
+        };
+
+        let mut devices = self.devices.write();
+        for gpu in gpus {
+            if let Some(state) = devices.get_mut(&gpu.device_id) {
+                state.utilization = Some(gpu.utilization);
+            }
+        }
+    }
+}
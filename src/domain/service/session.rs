@@ -0,0 +1,71 @@
+//! 会话亲和
+//!
+//! 带 `session_id` 的请求在序列结束后由解码器保留KV缓存，同一会话的下一轮请求
+//! 交给同一个解码器并复用共同的对话前缀，只需对新增的提示做prefill。
+//! 每个模型的连续批处理循环维护一张会话表，过期或被挤出的会话通知解码器释放缓存。
+
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// 会话ID的最大长度
+pub const MAX_SESSION_ID_LEN: usize = 128;
+
+/// 模型的会话表
+#[derive(Debug)]
+pub struct SessionTable {
+    last_used:    HashMap<String, Instant>,
+    ttl:          Duration,
+    max_sessions: usize,
+}
+
+impl SessionTable {
+    /// 创建会话表
+    pub fn new(ttl: Duration, max_sessions: usize) -> Self {
+        Self {
+            last_used: HashMap::new(),
+            ttl,
+            max_sessions: max_sessions.max(1),
+        }
+    }
+
+    /// 记录会话的最近一次使用，超出容量时返回被挤出的最久未用会话
+    pub fn touch(&mut self, session_id: &str) -> Option<String> {
+        self.last_used.insert(session_id.to_string(), Instant::now());
+        if self.last_used.len() <= self.max_sessions {
+            return None;
+        }
+        let oldest = self.last_used.iter()
+            .min_by_key(|(_, used)| **used)
+            .map(|(session, _)| session.clone())?;
+        self.last_used.remove(&oldest);
+        Some(oldest)
+    }
+
+    /// 取出空闲超过TTL的会话
+    pub fn expire(&mut self) -> Vec<String> {
+        let ttl = self.ttl;
+        let expired: Vec<String> = self.last_used.iter()
+            .filter(|(_, used)| used.elapsed() >= ttl)
+            .map(|(session, _)| session.clone())
+            .collect();
+        for session in &expired {
+            self.last_used.remove(session);
+        }
+        expired
+    }
+
+    /// 取出全部会话
+    pub fn drain(&mut self) -> Vec<String> {
+        self.last_used.drain().map(|(session, _)| session).collect()
+    }
+
+    /// 会话数
+    pub fn len(&self) -> usize {
+        self.last_used.len()
+    }
+
+    /// 是否没有会话
+    pub fn is_empty(&self) -> bool {
+        self.last_used.is_empty()
+    }
+}
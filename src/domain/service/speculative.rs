@@ -0,0 +1,127 @@
+//! 投机解码
+//!
+//! 小的草稿模型每步自回归提出k个token，目标模型一次前向验证全部草稿，接受最长的可接受前缀
+//! 并补上一个自己的token。接受率高时每次目标模型前向可产出多个token，输出分布与单独使用
+//! 目标模型一致。解码器对调度器表现为普通的 [`IterativeDecoder`]，照常参与连续批处理。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::service::batch_processor::DEFAULT_MAX_NEW_TOKENS;
+use crate::plugins::interface::{DecodeStep, DraftModel, DraftToken, IterativeDecoder, SpeculativeTarget};
+
+/// 每步最多提出的草稿token数
+pub const MAX_SPECULATIVE_TOKENS: usize = 16;
+
+/// 草稿加验证的投机解码器
+#[derive(Debug)]
+pub struct SpeculativeDecoder {
+    draft:  Arc<dyn DraftModel>,
+    target: Arc<dyn SpeculativeTarget>,
+    num_speculative_tokens: usize,
+    /// 各序列剩余可生成的token数，草稿不超出 `max_tokens`
+    remaining: Mutex<HashMap<String, usize>>,
+    proposed:  AtomicU64,
+    accepted:  AtomicU64,
+}
+
+impl SpeculativeDecoder {
+    /// 创建投机解码器，`num_speculative_tokens` 限制在 1..=[`MAX_SPECULATIVE_TOKENS`]
+    pub fn new(
+        draft: Arc<dyn DraftModel>,
+        target: Arc<dyn SpeculativeTarget>,
+        num_speculative_tokens: usize,
+    ) -> Self {
+        Self {
+            draft,
+            target,
+            num_speculative_tokens: num_speculative_tokens.clamp(1, MAX_SPECULATIVE_TOKENS),
+            remaining: Mutex::new(HashMap::new()),
+            proposed: AtomicU64::new(0),
+            accepted: AtomicU64::new(0),
+        }
+    }
+
+    /// 草稿token的接受率
+    pub fn acceptance_rate(&self) -> f64 {
+        let proposed = self.proposed.load(Ordering::Relaxed);
+        if proposed == 0 {
+            return 0.0;
+        }
+        self.accepted.load(Ordering::Relaxed) as f64 / proposed as f64
+    }
+}
+
+impl IterativeDecoder for SpeculativeDecoder {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()> {
+        self.target.add_sequence(sequence_id, prompt, parameters)?;
+        if let Err(e) = self.draft.add_sequence(sequence_id, prompt, parameters) {
+            self.target.remove_sequence(sequence_id);
+            return Err(e);
+        }
+        let max_tokens = parameters.max_tokens.unwrap_or(DEFAULT_MAX_NEW_TOKENS).max(1);
+        self.remaining.lock().insert(sequence_id.to_string(), max_tokens as usize);
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> Result<Vec<DecodeStep>> {
+        let mut drafts: Vec<(String, Vec<DraftToken>)> = Vec::with_capacity(sequence_ids.len());
+        for sequence_id in sequence_ids {
+            // 目标模型每步至少产出一个token，草稿只填满剩余的配额
+            let remaining = self.remaining.lock().get(sequence_id).copied().unwrap_or(1);
+            let num_tokens = self.num_speculative_tokens.min(remaining.saturating_sub(1));
+            let draft = if num_tokens > 0 {
+                self.draft.propose(sequence_id, num_tokens)?
+            } else {
+                Vec::new()
+            };
+            drafts.push((sequence_id.clone(), draft));
+        }
+
+        let verifications = self.target.verify(&drafts)?;
+        let mut steps = Vec::with_capacity(verifications.len());
+        for verification in verifications {
+            let draft = match drafts.iter().find(|(id, _)| *id == verification.sequence_id) {
+                Some((_, draft)) => draft,
+                None => continue,
+            };
+            let accepted = verification.accepted.min(draft.len());
+            self.draft.commit(&verification.sequence_id, accepted, verification.next_token.token_id)?;
+            self.proposed.fetch_add(draft.len() as u64, Ordering::Relaxed);
+            self.accepted.fetch_add(accepted as u64, Ordering::Relaxed);
+
+            if let Some(remaining) = self.remaining.lock().get_mut(&verification.sequence_id) {
+                *remaining = remaining.saturating_sub(accepted + 1);
+            }
+
+            let mut token: String = draft[..accepted].iter().map(|t| t.text.as_str()).collect();
+            token.push_str(&verification.next_token.text);
+            steps.push(DecodeStep {
+                sequence_id: verification.sequence_id,
+                token,
+                finished: verification.finished,
+                stop_sequence: None,
+                logprob: None,
+                candidates: Vec::new(),
+                draft_tokens: accepted as u32,
+            });
+        }
+        Ok(steps)
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.remaining.lock().remove(sequence_id);
+        self.draft.remove_sequence(sequence_id);
+        self.target.remove_sequence(sequence_id);
+    }
+}
@@ -0,0 +1,139 @@
+//! 共享权重缓存
+//!
+//! 多个模型版本或在同一基础模型上叠加适配器的模型指向同一份检查点时，权重只打开（mmap）一次，
+//! 各模型实例共享同一个 [`ModelWeights`]。缓存按模型记录持有者，最后一个持有者卸载后才关闭映射。
+//! 检查点按规范化路径、文件总大小和最近修改时间识别，原地替换的文件视为新的检查点。
+//!
+//! 共享的映射供多GPU并行的切分方案和内置后端使用；按 `model_path` 自行读取权重的插件
+//! 各自打开检查点，这部分内存仍随模型数增长。
+
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+use crate::common::error::*;
+use crate::common::types::ModelId;
+use crate::domain::model::ModelConfig;
+use crate::plugins::interface::ModelWeights;
+
+/// 检查点标识
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CheckpointKey {
+    path:     PathBuf,
+    bytes:    u64,
+    modified: Option<SystemTime>,
+}
+
+impl CheckpointKey {
+    /// 读取文件（或目录下各文件）的元数据
+    fn of(path: &Path) -> Result<Self> {
+        let path = std::fs::canonicalize(path)?;
+        let files: Vec<PathBuf> = if path.is_dir() {
+            std::fs::read_dir(&path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file())
+                .collect()
+        } else {
+            vec![path.clone()]
+        };
+
+        let mut bytes = 0;
+        let mut modified = None;
+        for file in files {
+            let metadata = std::fs::metadata(file)?;
+            bytes += metadata.len();
+            modified = modified.max(metadata.modified().ok());
+        }
+        Ok(Self { path, bytes, modified })
+    }
+}
+
+#[derive(Debug)]
+struct SharedEntry {
+    weights: Arc<ModelWeights>,
+    holders: HashSet<ModelId>,
+}
+
+/// 被共享的检查点
+#[derive(Debug, Clone, Serialize)]
+pub struct SharedCheckpoint {
+    pub path:    String,
+    pub bytes:   u64,
+    /// 持有该检查点的模型，按ID排序
+    pub holders: Vec<ModelId>,
+}
+
+/// 共享权重缓存
+#[derive(Debug, Default)]
+pub struct WeightCache {
+    entries: Mutex<HashMap<CheckpointKey, SharedEntry>>,
+}
+
+impl WeightCache {
+    /// 创建缓存
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 为模型获取权重，同一检查点已被打开时直接共享
+    ///
+    /// 每个模型只持有一个检查点，重复获取（如逐出后重新加载）先释放之前的持有。
+    /// 打开权重涉及文件IO，应在阻塞线程池中调用。
+    pub fn acquire(&self, model_id: &ModelId, config: &ModelConfig) -> Result<Arc<ModelWeights>> {
+        let key = CheckpointKey::of(Path::new(&config.model_path))?;
+        self.release(model_id);
+
+        if let Some(entry) = self.entries.lock().get_mut(&key) {
+            entry.holders.insert(model_id.clone());
+            return Ok(Arc::clone(&entry.weights));
+        }
+
+        // 在锁外打开权重；并发打开同一检查点时保留先插入的那份
+        let weights = Arc::new(ModelWeights::open(config)?);
+        let mut entries = self.entries.lock();
+        let entry = entries.entry(key).or_insert_with(|| SharedEntry {
+            weights,
+            holders: HashSet::new(),
+        });
+        entry.holders.insert(model_id.clone());
+        Ok(Arc::clone(&entry.weights))
+    }
+
+    /// 释放模型持有的检查点，没有持有者的检查点随之关闭
+    pub fn release(&self, model_id: &ModelId) {
+        self.entries.lock().retain(|_, entry| {
+            entry.holders.remove(model_id);
+            !entry.holders.is_empty()
+        });
+    }
+
+    /// 当前打开的检查点
+    pub fn checkpoints(&self) -> Vec<SharedCheckpoint> {
+        let entries = self.entries.lock();
+        let mut checkpoints: Vec<SharedCheckpoint> = entries.iter()
+            .map(|(key, entry)| {
+                let mut holders: Vec<ModelId> = entry.holders.iter().cloned().collect();
+                holders.sort();
+                SharedCheckpoint {
+                    path: key.path.display().to_string(),
+                    bytes: key.bytes,
+                    holders,
+                }
+            })
+            .collect();
+        checkpoints.sort_by(|a, b| a.path.cmp(&b.path));
+        checkpoints
+    }
+
+    /// 共享节省的字节数：每个检查点除第一个持有者外都不再单独占用内存
+    pub fn saved_bytes(&self) -> u64 {
+        self.entries.lock()
+            .iter()
+            .map(|(key, entry)| key.bytes * (entry.holders.len() as u64).saturating_sub(1))
+            .sum()
+    }
+}
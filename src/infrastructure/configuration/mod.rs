@@ -7,6 +7,7 @@ use tokio::fs;
 
 use crate::common::types::*;
 use crate::common::error::*;
+use crate::domain::model::{DeviceType, ModelType, Parallelism, WarmupConfig};
 
 /// 主配置结构
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,6 +19,12 @@ pub struct Config {
     pub security: SecurityConfig,
     pub storage: StorageConfig,
     pub logging: LoggingConfig,
+    /// 租户配置（租户名 -> 配置）
+    #[serde(default)]
+    pub tenants: HashMap<String, TenantConfig>,
+    /// 故障注入，仅用于开发和测试
+    #[serde(default)]
+    pub chaos: ChaosConfig,
 }
 
 /// 服务器配置
@@ -31,7 +38,48 @@ pub struct ServerConfig {
     pub enable_tls: bool,
     pub tls_cert_path: Option<String>,
     pub tls_key_path: Option<String>,
+    /// 异步运行时的工作线程数，未设置时与CPU核数相同
     pub worker_threads: Option<usize>,
+    /// 请求体大小上限（MB），用于上传图像、音频等大体积输入
+    #[serde(default = "default_max_request_body_mb")]
+    pub max_request_body_mb: usize,
+    /// 阻塞线程池（文件IO、预处理等）的线程数上限，未设置时使用tokio的默认值512
+    #[serde(default)]
+    pub max_blocking_threads: Option<usize>,
+    /// 运行时线程名
+    #[serde(default = "default_thread_name")]
+    pub thread_name: String,
+    /// 运行时线程的栈大小（KB），未设置时使用tokio的默认值2MB
+    #[serde(default)]
+    pub thread_stack_size_kb: Option<usize>,
+    /// 收到SIGTERM/SIGINT后等待排队和执行中的请求完成的时间（秒），超时后仍未完成的请求被放弃
+    #[serde(default = "default_shutdown_timeout_secs")]
+    pub shutdown_timeout_secs: u64,
+    /// 以SO_REUSEPORT绑定监听端口，滚动重启时新进程可以在旧进程排空期间同时监听
+    #[serde(default)]
+    pub reuse_port: bool,
+    /// 预加载模型全部就绪后才开始监听，启动期间连接被拒绝而不是收到503
+    #[serde(default)]
+    pub wait_for_preload: bool,
+    /// 等待预加载的最长时间（秒），超时或有模型加载失败时启动失败
+    #[serde(default = "default_preload_timeout_secs")]
+    pub preload_timeout_secs: u64,
+}
+
+fn default_max_request_body_mb() -> usize {
+    128
+}
+
+fn default_shutdown_timeout_secs() -> u64 {
+    30
+}
+
+fn default_preload_timeout_secs() -> u64 {
+    600
+}
+
+fn default_thread_name() -> String {
+    "unimodel-worker".to_string()
 }
 
 /// 引擎配置
@@ -43,6 +91,114 @@ pub struct EngineConfig {
     pub batch_config: BatchConfig,
     pub gpu: GpuConfig,
     pub memory: MemoryConfig,
+    /// 空闲模型逐出
+    #[serde(default)]
+    pub eviction: EvictionConfig,
+    /// 启动时预加载的模型，全部加载并预热完成后 `/readyz` 才报告就绪
+    #[serde(default)]
+    pub preload: Vec<PreloadModelConfig>,
+    /// 执行后端调用的线程池
+    #[serde(default)]
+    pub inference_pool: InferencePoolConfig,
+}
+
+/// 推理执行线程池配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InferencePoolConfig {
+    /// 线程数，未设置时与CPU核数相同
+    #[serde(default)]
+    pub threads: Option<usize>,
+    /// 推理线程的CPU亲和性
+    #[serde(default)]
+    pub affinity: AffinityConfig,
+}
+
+/// 推理线程的CPU亲和性与NUMA放置
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AffinityConfig {
+    /// 每个推理线程绑定一个CPU核，按线程序号轮流分配；设置后忽略NUMA节点
+    #[serde(default)]
+    pub cpus: Vec<usize>,
+    /// 推理线程只在该NUMA节点的CPU核上运行
+    #[serde(default)]
+    pub numa_node: Option<u32>,
+    /// 未指定节点时，放在 `gpu.device_ids` 中第一张卡所在的NUMA节点
+    #[serde(default)]
+    pub follow_gpu: bool,
+}
+
+/// 预加载模型配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreloadModelConfig {
+    pub name: String,
+    pub model_type: ModelType,
+    pub backend: String,
+    pub model_path: String,
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// 运行设备，默认为 `engine.gpu.accelerator`；为 `Auto` 时注册时探测
+    #[serde(default)]
+    pub device_type: Option<DeviceType>,
+    /// 候选GPU（为空时使用 `engine.gpu.device_ids`）
+    #[serde(default)]
+    pub device_ids: Vec<u32>,
+    /// 为 `tensor` 或 `pipeline` 时把模型切分到 `device_ids` 所列的全部GPU上，为 `replicas` 时每张卡各加载一个副本
+    #[serde(default)]
+    pub parallelism: Parallelism,
+    /// 流水线并行的微批次数，默认与阶段数相同
+    #[serde(default)]
+    pub micro_batches: Option<u32>,
+    #[serde(default)]
+    pub memory_limit_mb: Option<u64>,
+    /// 以FP16/BF16混合精度执行
+    #[serde(default)]
+    pub mixed_precision: bool,
+    /// 固定驻留，不会被自动逐出
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub warmup: WarmupConfig,
+    /// 预热时捕获CUDA graph
+    #[serde(default)]
+    pub cuda_graphs: bool,
+    /// 每个实例的CUDA流数
+    #[serde(default = "default_cuda_streams")]
+    pub cuda_streams: u32,
+}
+
+fn default_cuda_streams() -> u32 {
+    1
+}
+
+/// 空闲模型逐出配置
+///
+/// 启用后，空闲超过 `idle_ttl_secs` 的模型会被定期卸载；
+/// 驻留模型数达到 `max_models` 或显存不足时，也会按LRU顺序逐出空闲模型为新模型腾出位置。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvictionConfig {
+    pub enabled: bool,
+    /// 空闲多久后允许逐出（秒）
+    pub idle_ttl_secs: u64,
+    /// 定期检查间隔（秒）
+    pub check_interval_secs: u64,
+    /// 批次显存不足时逐出同一GPU上的模型后重试（与 `enabled` 无关）
+    #[serde(default = "default_evict_on_oom")]
+    pub evict_on_oom: bool,
+}
+
+fn default_evict_on_oom() -> bool {
+    true
+}
+
+impl Default for EvictionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            idle_ttl_secs: 1800,
+            check_interval_secs: 60,
+            evict_on_oom: default_evict_on_oom(),
+        }
+    }
 }
 
 /// 插件配置
@@ -52,6 +208,63 @@ pub struct PluginConfig {
     pub enabled_plugins: Vec<String>,
     pub plugin_configs: HashMap<String, serde_json::Value>,
     pub plugin_timeout_secs: u64,
+    /// 以独立进程运行、通过gRPC插件协议通信的插件
+    #[serde(default)]
+    pub external_plugins: Vec<ExternalPluginConfig>,
+    /// 每个动态库插件在各自的子进程中运行，插件崩溃只影响该插件，由服务器重启
+    #[serde(default)]
+    pub isolate_native_plugins: bool,
+    /// 为进程外插件创建子cgroup的cgroup v2目录（须已委派给服务器），未设置时使用服务器自身所在的cgroup
+    #[serde(default)]
+    pub cgroup_root: Option<String>,
+}
+
+/// 进程外插件配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalPluginConfig {
+    /// 插件名称，用于日志和套接字文件名
+    pub name: String,
+    /// 启动插件进程的命令
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// 等待插件监听套接字并完成握手的时间（秒）
+    #[serde(default = "default_plugin_startup_timeout_secs")]
+    pub startup_timeout_secs: u64,
+    /// 连续重启失败多少次后放弃该插件
+    #[serde(default = "default_plugin_max_restarts")]
+    pub max_restarts: u32,
+    /// 插件进程的资源上限
+    #[serde(default)]
+    pub limits: ProcessLimits,
+}
+
+/// 子进程资源上限，未设置的项不限制
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProcessLimits {
+    /// 内存上限（MB），超出时进程被结束并重启
+    #[serde(default)]
+    pub memory_mb: Option<u64>,
+    /// 可用CPU核数，可为小数；只在cgroup可用时生效
+    #[serde(default)]
+    pub cpu_cores: Option<f64>,
+}
+
+impl ProcessLimits {
+    /// 是否设置了任何上限
+    pub fn is_limited(&self) -> bool {
+        self.memory_mb.is_some() || self.cpu_cores.is_some()
+    }
+}
+
+pub(crate) fn default_plugin_startup_timeout_secs() -> u64 {
+    30
+}
+
+pub(crate) fn default_plugin_max_restarts() -> u32 {
+    10
 }
 
 /// 监控配置
@@ -63,6 +276,38 @@ pub struct MonitoringConfig {
     pub jaeger_endpoint: Option<String>,
     pub health_check_interval_secs: u64,
     pub metrics_collection_interval_secs: u64,
+    /// 异步运行时指标
+    #[serde(default)]
+    pub runtime: RuntimeMetricsConfig,
+    /// 挂载 `/debug` 下的性能分析和内存统计接口（需要管理权限）
+    #[serde(default)]
+    pub debug_endpoints: bool,
+}
+
+/// 异步运行时指标配置
+///
+/// 任务数、轮询耗时和阻塞的工作线程等指标只在以 `RUSTFLAGS="--cfg tokio_unstable"` 构建时可用，
+/// 同一构建方式还会启用tokio-console。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeMetricsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 采样间隔（毫秒）
+    #[serde(default = "default_runtime_metrics_interval_ms")]
+    pub interval_ms: u64,
+}
+
+fn default_runtime_metrics_interval_ms() -> u64 {
+    1000
+}
+
+impl Default for RuntimeMetricsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_ms: default_runtime_metrics_interval_ms(),
+        }
+    }
 }
 
 /// 安全配置
@@ -74,6 +319,46 @@ pub struct SecurityConfig {
     pub cors_enabled: bool,
     pub cors_allowed_origins: Vec<String>,
     pub rate_limiting: RateLimitConfig,
+    /// 动态API密钥的持久化文件路径
+    #[serde(default = "default_api_key_store_path")]
+    pub api_key_store_path: String,
+    /// 动态密钥校验通过后缓存的秒数，期间同一密钥不再计算argon2哈希；0表示不缓存
+    #[serde(default = "default_api_key_cache_ttl_secs")]
+    pub api_key_cache_ttl_secs: u64,
+}
+
+fn default_api_key_store_path() -> String {
+    "./data/api_keys.json".to_string()
+}
+
+fn default_api_key_cache_ttl_secs() -> u64 {
+    30
+}
+
+/// 租户配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantConfig {
+    /// 最大模型数量
+    pub max_models: Option<u32>,
+    /// 最大GPU显存（MB）
+    pub max_gpu_memory_mb: Option<u64>,
+    /// 共享模型时的调度权重
+    #[serde(default = "default_tenant_weight")]
+    pub weight: u32,
+}
+
+impl Default for TenantConfig {
+    fn default() -> Self {
+        Self {
+            max_models: None,
+            max_gpu_memory_mb: None,
+            weight: default_tenant_weight(),
+        }
+    }
+}
+
+fn default_tenant_weight() -> u32 {
+    1
 }
 
 /// 存储配置
@@ -83,6 +368,34 @@ pub struct StorageConfig {
     pub cache_storage_path: String,
     pub log_storage_path: String,
     pub max_storage_gb: u64,
+    /// 用量计量数据的持久化文件路径
+    #[serde(default = "default_usage_store_path")]
+    pub usage_store_path: String,
+    /// 用量数据保留天数，更早的小时分桶在落盘时删除；0表示永久保留
+    #[serde(default = "default_usage_retention_days")]
+    pub usage_retention_days: u32,
+    /// 死信记录的持久化文件路径
+    #[serde(default = "default_dead_letter_path")]
+    pub dead_letter_path: String,
+    /// 死信记录数上限
+    #[serde(default = "default_dead_letter_max_entries")]
+    pub dead_letter_max_entries: usize,
+}
+
+fn default_usage_store_path() -> String {
+    "./data/usage.json".to_string()
+}
+
+fn default_usage_retention_days() -> u32 {
+    400
+}
+
+fn default_dead_letter_path() -> String {
+    "./data/dead_letters.json".to_string()
+}
+
+fn default_dead_letter_max_entries() -> usize {
+    10000
 }
 
 /// 日志配置
@@ -100,10 +413,40 @@ pub struct LoggingConfig {
 /// GPU配置
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct GpuConfig {
+    /// `device_ids` 所属的加速器类型：CUDA、ROCm 或 NPU
+    #[serde(default = "default_accelerator")]
+    pub accelerator: DeviceType,
     pub device_ids: Vec<u32>,
     pub memory_fraction: f32,
     pub enable_pooling: bool,
     pub enable_p2p: bool,
+    /// 单卡显存（MB），设置后不再通过 nvidia-smi / rocm-smi 探测；NPU无法探测，应当设置
+    #[serde(default)]
+    pub device_memory_mb: Option<u64>,
+    /// 主机与GPU间传输使用的锁页内存池
+    #[serde(default)]
+    pub pinned_memory: PinnedMemoryConfig,
+}
+
+fn default_accelerator() -> DeviceType {
+    DeviceType::CUDA
+}
+
+/// 锁页内存池配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PinnedMemoryConfig {
+    pub enabled: bool,
+    /// 每个NUMA节点锁页内存的上限（MB），超出后使用可分页内存
+    pub max_pool_mb: u64,
+}
+
+impl Default for PinnedMemoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_pool_mb: 1024,
+        }
+    }
 }
 
 /// 内存配置
@@ -112,6 +455,42 @@ pub struct MemoryConfig {
     pub max_memory_gb: f32,
     pub enable_mmap: bool,
     pub cache_size_mb: u64,
+    /// 推理响应缓存
+    #[serde(default)]
+    pub response_cache: ResponseCacheConfig,
+}
+
+/// 推理响应缓存配置（仅缓存确定性请求）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResponseCacheConfig {
+    pub enabled: bool,
+    /// 每个模型的缓存条目上限
+    pub max_entries: usize,
+    /// 缓存有效期（秒）
+    pub ttl_secs: u64,
+    /// 按模型覆盖的缓存限制（模型ID -> 配置）
+    #[serde(default)]
+    pub models: HashMap<String, ModelCacheOverride>,
+}
+
+/// 单个模型的缓存限制覆盖
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ModelCacheOverride {
+    /// 是否缓存该模型的响应（None表示沿用全局设置）
+    pub enabled: Option<bool>,
+    pub max_entries: Option<usize>,
+    pub ttl_secs: Option<u64>,
+}
+
+impl Default for ResponseCacheConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_entries: 1000,
+            ttl_secs: 300,
+            models: HashMap::new(),
+        }
+    }
 }
 
 /// 速率限制配置
@@ -120,6 +499,88 @@ pub struct RateLimitConfig {
     pub enabled: bool,
     pub requests_per_minute: u32,
     pub burst_size: u32,
+    /// 默认每月token配额（None表示不限）
+    #[serde(default)]
+    pub tokens_per_month: Option<u64>,
+    /// 按API密钥ID覆盖的限额
+    #[serde(default)]
+    pub overrides: HashMap<String, QuotaOverride>,
+    /// 配额计数器的持久化文件路径
+    #[serde(default = "default_quota_store_path")]
+    pub quota_store_path: String,
+}
+
+/// 单个密钥的限额覆盖
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaOverride {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_month: Option<u64>,
+}
+
+fn default_quota_store_path() -> String {
+    "./data/quota_usage.json".to_string()
+}
+
+/// 故障注入配置
+///
+/// 在批处理执行器和插件管理器中按概率注入延迟、错误、插件崩溃和显存不足，用于验证重试、
+/// 逐出和插件重启等容错逻辑。设置 `seed` 后注入序列可复现。不要在生产环境启用。
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// 随机数种子，未设置时每次启动不同
+    #[serde(default)]
+    pub seed: Option<u64>,
+    /// 批处理执行器中每个批次的注入概率
+    #[serde(default)]
+    pub batch: FaultRates,
+    /// 插件管理器中每次加载和推理调用的注入概率
+    #[serde(default)]
+    pub plugins: FaultRates,
+}
+
+/// 一个注入点的故障概率（0到1）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FaultRates {
+    /// 注入延迟的概率
+    #[serde(default)]
+    pub latency_rate: f64,
+    /// 注入的延迟（毫秒）
+    #[serde(default)]
+    pub latency_ms: u64,
+    /// 返回不可重试的插件错误的概率
+    #[serde(default)]
+    pub error_rate: f64,
+    /// 插件崩溃的概率：进程外插件的进程被结束，其他插件返回插件不可用
+    #[serde(default)]
+    pub crash_rate: f64,
+    /// 返回显存不足的概率
+    #[serde(default)]
+    pub oom_rate: f64,
+}
+
+impl FaultRates {
+    fn validate(&self, site: &str) -> Result<()> {
+        let rates = [
+            ("latency_rate", self.latency_rate),
+            ("error_rate", self.error_rate),
+            ("crash_rate", self.crash_rate),
+            ("oom_rate", self.oom_rate),
+        ];
+        for (name, rate) in rates {
+            if !(0.0..=1.0).contains(&rate) {
+                return Err(UniModelError::config(format!("chaos.{}.{} must be between 0 and 1", site, name)));
+            }
+        }
+        if self.error_rate + self.crash_rate + self.oom_rate > 1.0 {
+            return Err(UniModelError::config(format!(
+                "chaos.{}: error_rate, crash_rate and oom_rate must not sum to more than 1",
+                site
+            )));
+        }
+        Ok(())
+    }
 }
 
 impl Config {
@@ -127,8 +588,19 @@ impl Config {
     pub async fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
         let content = fs::read_to_string(path).await
             .map_err(|e| UniModelError::config(format!("Failed to read config file: {}", e)))?;
+        Self::from_yaml(&content)
+    }
 
-        let config: Config = serde_yaml::from_str(&content)
+    /// 在异步运行时之外加载配置，运行时本身按配置构建
+    pub fn from_file_blocking<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .map_err(|e| UniModelError::config(format!("Failed to read config file: {}", e)))?;
+        Self::from_yaml(&content)
+    }
+
+    /// 解析并验证YAML配置
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let config: Config = serde_yaml::from_str(content)
             .map_err(|e| UniModelError::config(format!("Failed to parse config: {}", e)))?;
 
         config.validate()?;
@@ -171,35 +643,50 @@ impl Config {
 
     /// 验证配置
     pub fn validate(&self) -> Result<()> {
-        if self.server.port == 0 || self.server.port > 65535 {
+        if self.server.port == 0 {
             return Err(UniModelError::config("Invalid server port"));
         }
-        if self.server.grpc_port == 0 || self.server.grpc_port > 65535 {
+        if self.server.grpc_port == 0 {
             return Err(UniModelError::config("Invalid gRPC port"));
         }
         if self.server.port == self.server.grpc_port {
             return Err(UniModelError::config("HTTP and gRPC ports cannot be the same"));
         }
+        if self.server.worker_threads == Some(0) || self.server.max_blocking_threads == Some(0) {
+            return Err(UniModelError::config("Runtime thread counts must be greater than 0"));
+        }
         if self.engine.batch_config.max_batch_size == 0 {
             return Err(UniModelError::config("Max batch size must be greater than 0"));
         }
         if self.engine.batch_config.max_wait_time_ms == 0 {
             return Err(UniModelError::config("Max wait time must be greater than 0"));
         }
+        if self.engine.batch_config.max_queue_size < self.engine.batch_config.max_batch_size {
+            return Err(UniModelError::config("Max queue size must be at least the max batch size"));
+        }
+        if self.engine.batch_config.latency_slo_ms == Some(0) {
+            return Err(UniModelError::config("Latency SLO must be greater than 0"));
+        }
         if self.engine.gpu.device_ids.is_empty() {
             return Err(UniModelError::config("At least one GPU device must be specified"));
         }
+        if !self.engine.gpu.accelerator.is_accelerator() {
+            return Err(UniModelError::config(format!(
+                "GPU accelerator must be CUDA, ROCm or NPU, got {:?}",
+                self.engine.gpu.accelerator
+            )));
+        }
         if self.engine.gpu.memory_fraction <= 0.0 || self.engine.gpu.memory_fraction > 1.0 {
             return Err(UniModelError::config("GPU memory fraction must be between 0 and 1"));
         }
         if self.storage.model_storage_path.is_empty() {
             return Err(UniModelError::config("Model storage path cannot be empty"));
         }
-        if self.server.enable_tls {
-            if self.server.tls_cert_path.is_none() || self.server.tls_key_path.is_none() {
-                return Err(UniModelError::config("TLS cert and key paths must be provided when TLS is enabled"));
-            }
+        if self.server.enable_tls && (self.server.tls_cert_path.is_none() || self.server.tls_key_path.is_none()) {
+            return Err(UniModelError::config("TLS cert and key paths must be provided when TLS is enabled"));
         }
+        self.chaos.batch.validate("batch")?;
+        self.chaos.plugins.validate("plugins")?;
         Ok(())
     }
 
@@ -212,6 +699,8 @@ impl Config {
         self.security = other.security;
         self.storage = other.storage;
         self.logging = other.logging;
+        self.tenants = other.tenants;
+        self.chaos = other.chaos;
         self
     }
 }
@@ -229,6 +718,14 @@ impl Default for Config {
                 tls_cert_path: None,
                 tls_key_path: None,
                 worker_threads: None,
+                max_blocking_threads: None,
+                thread_name: default_thread_name(),
+                thread_stack_size_kb: None,
+                max_request_body_mb: default_max_request_body_mb(),
+                shutdown_timeout_secs: default_shutdown_timeout_secs(),
+                reuse_port: false,
+                wait_for_preload: false,
+                preload_timeout_secs: default_preload_timeout_secs(),
             },
             engine: EngineConfig {
                 max_models: 10,
@@ -236,16 +733,23 @@ impl Default for Config {
                 max_batch_wait_ms: 50,
                 batch_config: BatchConfig::default(),
                 gpu: GpuConfig {
+                    accelerator: default_accelerator(),
                     device_ids: vec![0],
                     memory_fraction: 0.8,
                     enable_pooling: true,
                     enable_p2p: false,
+                    device_memory_mb: None,
+                    pinned_memory: PinnedMemoryConfig::default(),
                 },
                 memory: MemoryConfig {
                     max_memory_gb: 16.0,
                     enable_mmap: true,
                     cache_size_mb: 1024,
+                    response_cache: ResponseCacheConfig::default(),
                 },
+                eviction: EvictionConfig::default(),
+                preload: Vec::new(),
+                inference_pool: InferencePoolConfig::default(),
             },
             plugins: PluginConfig {
                 plugin_dir: "./plugins".to_string(),
@@ -256,6 +760,9 @@ impl Default for Config {
                 ],
                 plugin_configs: HashMap::new(),
                 plugin_timeout_secs: 300,
+                external_plugins: Vec::new(),
+                isolate_native_plugins: false,
+                cgroup_root: None,
             },
             monitoring: MonitoringConfig {
                 prometheus_enabled: true,
@@ -264,6 +771,8 @@ impl Default for Config {
                 jaeger_endpoint: None,
                 health_check_interval_secs: 30,
                 metrics_collection_interval_secs: 60,
+                runtime: RuntimeMetricsConfig::default(),
+                debug_endpoints: false,
             },
             security: SecurityConfig {
                 auth_enabled: false,
@@ -275,13 +784,22 @@ impl Default for Config {
                     enabled: true,
                     requests_per_minute: 1000,
                     burst_size: 100,
+                    tokens_per_month: None,
+                    overrides: HashMap::new(),
+                    quota_store_path: default_quota_store_path(),
                 },
+                api_key_store_path: default_api_key_store_path(),
+                api_key_cache_ttl_secs: default_api_key_cache_ttl_secs(),
             },
             storage: StorageConfig {
                 model_storage_path: "./models".to_string(),
                 cache_storage_path: "./cache".to_string(),
                 log_storage_path: "./logs".to_string(),
                 max_storage_gb: 1000,
+                usage_store_path: default_usage_store_path(),
+                usage_retention_days: default_usage_retention_days(),
+                dead_letter_path: default_dead_letter_path(),
+                dead_letter_max_entries: default_dead_letter_max_entries(),
             },
             logging: LoggingConfig {
                 level: "info".to_string(),
@@ -292,6 +810,8 @@ impl Default for Config {
                 rotation_size_mb: 100,
                 retention_count: 10,
             },
+            tenants: HashMap::new(),
+            chaos: ChaosConfig::default(),
         }
     }
 }
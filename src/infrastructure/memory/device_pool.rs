@@ -0,0 +1,280 @@
+//! GPU显存池
+//!
+//! 后端从显存池申请激活等临时缓冲区。释放的块按尺寸级别缓存起来供后续批次复用，
+//! 减少每个批次反复调用 cudaMalloc/cudaFree 的开销。`GpuConfig.enable_pooling`
+//! 关闭时显存池直接透传给底层分配器，仅保留统计。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::sync::Arc;
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{debug, info, warn};
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::GpuConfig;
+
+/// 小块按2的幂取整，大块按该粒度取整
+const LARGE_BLOCK_THRESHOLD: usize = 1024 * 1024;
+const LARGE_BLOCK_GRANULARITY: usize = 2 * 1024 * 1024;
+const MIN_BLOCK_SIZE: usize = 512;
+
+/// 底层显存分配器，由推理后端针对其运行时实现
+pub trait DeviceAllocator: Send + Sync + Debug {
+    /// 在设备上分配 `bytes` 字节，返回设备指针
+    fn allocate(&self, device_id: u32, bytes: usize) -> Result<u64>;
+
+    /// 释放之前分配的显存
+    fn free(&self, device_id: u32, ptr: u64, bytes: usize);
+}
+
+/// 单个设备的池状态
+#[derive(Debug, Default)]
+struct DevicePool {
+    free_blocks:        BTreeMap<usize, Vec<u64>>, // 尺寸级别 -> 空闲块
+    reserved_bytes:     usize,                     // 从分配器持有的总字节数
+    in_use_bytes:       usize,                     // 已借出块的字节数（按尺寸级别）
+    requested_bytes:    usize,                     // 已借出块的实际请求字节数
+    peak_in_use_bytes:  usize,
+    driver_allocations: u64,
+    reuses:             u64,
+}
+
+impl DevicePool {
+    fn cached_bytes(&self) -> usize {
+        self.reserved_bytes - self.in_use_bytes
+    }
+}
+
+/// 显存池统计
+#[derive(Debug, Clone, Serialize)]
+pub struct DevicePoolStats {
+    pub device_id: u32,
+    pub reserved_bytes: usize,
+    pub in_use_bytes: usize,
+    pub cached_bytes: usize,
+    /// 已借出显存的历史峰值
+    pub peak_in_use_bytes: usize,
+    /// 碎片率：持有但未被请求使用的显存占比（取整浪费与缓存的空闲块）
+    pub fragmentation: f64,
+    pub driver_allocations: u64,
+    pub reuses: u64,
+}
+
+/// 显存池
+#[derive(Debug)]
+pub struct DeviceMemoryPool {
+    pooling:     bool,
+    limit_bytes: Option<usize>, // 单卡可用显存（按 memory_fraction 折算）
+    allocator:   Arc<dyn DeviceAllocator>,
+    devices:     Mutex<HashMap<u32, DevicePool>>,
+}
+
+impl DeviceMemoryPool {
+    /// 创建显存池
+    pub fn new(config: &GpuConfig, allocator: Arc<dyn DeviceAllocator>) -> Self {
+        let limit_bytes = config.device_memory_mb
+            .map(|mb| (mb as f64 * config.memory_fraction as f64) as usize * 1024 * 1024);
+
+        Self {
+            pooling: config.enable_pooling,
+            limit_bytes,
+            allocator,
+            devices: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 申请显存，返回的缓冲区释放时归还显存池
+    pub fn allocate(self: &Arc<Self>, device_id: u32, bytes: usize) -> Result<PooledBuffer> {
+        let size = if self.pooling { size_class(bytes) } else { bytes };
+        let mut devices = self.devices.lock();
+        let pool = devices.entry(device_id).or_default();
+
+        let reused = if self.pooling {
+            pool.free_blocks.get_mut(&size).and_then(|blocks| blocks.pop())
+        } else {
+            None
+        };
+
+        let ptr = match reused {
+            Some(ptr) => {
+                pool.reuses += 1;
+                ptr
+            }
+            None => {
+                if let Some(limit) = self.limit_bytes {
+                    if pool.reserved_bytes + size > limit {
+                        // 先把缓存的空闲块还给分配器再判断
+                        self.trim_pool(device_id, pool);
+                    }
+                    if pool.reserved_bytes + size > limit {
+                        return Err(UniModelError::out_of_memory(format!(
+                            "GPU {} memory pool exhausted: {} bytes requested, {} of {} bytes in use",
+                            device_id, size, pool.in_use_bytes, limit
+                        )));
+                    }
+                }
+
+                let ptr = self.allocator.allocate(device_id, size)?;
+                pool.reserved_bytes += size;
+                pool.driver_allocations += 1;
+                ptr
+            }
+        };
+
+        pool.in_use_bytes += size;
+        pool.requested_bytes += bytes;
+        pool.peak_in_use_bytes = pool.peak_in_use_bytes.max(pool.in_use_bytes);
+
+        Ok(PooledBuffer {
+            pool: Arc::clone(self),
+            device_id,
+            ptr,
+            size,
+            requested: bytes,
+        })
+    }
+
+    /// 归还缓冲区
+    fn release(&self, device_id: u32, ptr: u64, size: usize, requested: usize) {
+        let mut devices = self.devices.lock();
+        let pool = match devices.get_mut(&device_id) {
+            Some(pool) => pool,
+            None => {
+                warn!("Released buffer for unknown GPU {}", device_id);
+                return;
+            }
+        };
+
+        pool.in_use_bytes -= size;
+        pool.requested_bytes -= requested;
+
+        if self.pooling {
+            pool.free_blocks.entry(size).or_default().push(ptr);
+        } else {
+            self.allocator.free(device_id, ptr, size);
+            pool.reserved_bytes -= size;
+        }
+    }
+
+    /// 释放所有缓存的空闲块
+    pub fn empty_cache(&self) {
+        let mut devices = self.devices.lock();
+        for (device_id, pool) in devices.iter_mut() {
+            self.trim_pool(*device_id, pool);
+        }
+    }
+
+    fn trim_pool(&self, device_id: u32, pool: &mut DevicePool) {
+        let cached = pool.cached_bytes();
+        for (size, blocks) in std::mem::take(&mut pool.free_blocks) {
+            for ptr in blocks {
+                self.allocator.free(device_id, ptr, size);
+                pool.reserved_bytes -= size;
+            }
+        }
+        if cached > 0 {
+            debug!("Returned {} cached bytes on GPU {} to the allocator", cached, device_id);
+        }
+    }
+
+    /// 各设备的显存池统计
+    pub fn stats(&self) -> Vec<DevicePoolStats> {
+        let devices = self.devices.lock();
+        let mut stats: Vec<DevicePoolStats> = devices
+            .iter()
+            .map(|(&device_id, pool)| DevicePoolStats {
+                device_id,
+                reserved_bytes: pool.reserved_bytes,
+                in_use_bytes: pool.in_use_bytes,
+                cached_bytes: pool.cached_bytes(),
+                peak_in_use_bytes: pool.peak_in_use_bytes,
+                fragmentation: if pool.reserved_bytes == 0 {
+                    0.0
+                } else {
+                    1.0 - pool.requested_bytes as f64 / pool.reserved_bytes as f64
+                },
+                driver_allocations: pool.driver_allocations,
+                reuses: pool.reuses,
+            })
+            .collect();
+        stats.sort_by_key(|s| s.device_id);
+        stats
+    }
+
+    /// 将统计写入指标
+    pub fn publish_metrics(&self) {
+        for stats in self.stats() {
+            let device = stats.device_id.to_string();
+            metrics::gauge!("unimodel_gpu_pool_reserved_bytes", stats.reserved_bytes as f64, "device" => device.clone());
+            metrics::gauge!("unimodel_gpu_pool_in_use_bytes", stats.in_use_bytes as f64, "device" => device.clone());
+            metrics::gauge!("unimodel_gpu_pool_peak_in_use_bytes", stats.peak_in_use_bytes as f64, "device" => device.clone());
+            metrics::gauge!("unimodel_gpu_pool_fragmentation", stats.fragmentation, "device" => device.clone());
+            metrics::gauge!("unimodel_gpu_pool_driver_allocations", stats.driver_allocations as f64, "device" => device.clone());
+            metrics::gauge!("unimodel_gpu_pool_reuses", stats.reuses as f64, "device" => device);
+        }
+    }
+
+    /// 启动定期上报指标的任务
+    pub fn start_metrics_task(self: &Arc<Self>, interval: Duration) {
+        let pool = Arc::clone(self);
+
+        tokio::spawn(async move {
+            info!("GPU memory pool metrics task started (every {:?})", interval);
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                pool.publish_metrics();
+            }
+        });
+    }
+}
+
+/// 从显存池借出的缓冲区
+#[derive(Debug)]
+pub struct PooledBuffer {
+    pool:      Arc<DeviceMemoryPool>,
+    device_id: u32,
+    ptr:       u64,
+    size:      usize,
+    requested: usize,
+}
+
+impl PooledBuffer {
+    /// 设备指针
+    pub fn ptr(&self) -> u64 {
+        self.ptr
+    }
+
+    /// 设备ID
+    pub fn device_id(&self) -> u32 {
+        self.device_id
+    }
+
+    /// 请求的字节数
+    pub fn len(&self) -> usize {
+        self.requested
+    }
+
+    /// 是否为空缓冲区
+    pub fn is_empty(&self) -> bool {
+        self.requested == 0
+    }
+}
+
+impl Drop for PooledBuffer {
+    fn drop(&mut self) {
+        self.pool.release(self.device_id, self.ptr, self.size, self.requested);
+    }
+}
+
+/// 请求大小对应的尺寸级别
+pub(crate) fn size_class(bytes: usize) -> usize {
+    if bytes <= LARGE_BLOCK_THRESHOLD {
+        bytes.max(MIN_BLOCK_SIZE).next_power_of_two()
+    } else {
+        bytes.div_ceil(LARGE_BLOCK_GRANULARITY) * LARGE_BLOCK_GRANULARITY
+    }
+}
@@ -0,0 +1,11 @@
+//! 设备内存管理
+
+pub mod device_pool;
+pub mod numa;
+pub mod paged_kv;
+pub mod pinned_pool;
+
+pub use device_pool::*;
+pub use numa::*;
+pub use paged_kv::*;
+pub use pinned_pool::*;
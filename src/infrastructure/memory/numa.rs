@@ -0,0 +1,324 @@
+//! CPU亲和性与NUMA放置
+//!
+//! 双路推理服务器上，GPU挂在其中一个CPU插槽的PCIe根复合体下。推理线程和暂存输入输出的主机
+//! 缓冲区若落在另一个插槽，每次拷贝都要跨插槽互联。这里读取sysfs中的NUMA拓扑和各GPU所在的
+//! 节点，把推理线程绑定到指定的CPU核或节点，并在GPU所在节点上分配主机缓冲区。
+//! 拓扑不可用（非Linux、单节点或无GPU驱动）时这些操作退化为不做任何放置。
+
+use std::collections::{BTreeMap, HashMap};
+use std::io;
+use std::path::Path;
+use std::process::Command;
+use std::ptr::NonNull;
+use std::sync::OnceLock;
+
+use tracing::{debug, info, warn};
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::{AffinityConfig, GpuConfig};
+
+/// sysfs中的NUMA节点目录
+const NODE_ROOT: &str = "/sys/devices/system/node";
+
+/// 进程内缓存的拓扑，首次使用时探测
+static TOPOLOGY: OnceLock<NumaTopology> = OnceLock::new();
+
+/// NUMA拓扑：各节点的CPU核以及各GPU所在的节点
+#[derive(Debug, Clone, Default)]
+pub struct NumaTopology {
+    nodes: BTreeMap<u32, Vec<usize>>,
+    gpu_nodes: HashMap<u32, u32>,
+}
+
+impl NumaTopology {
+    /// 按已知的节点和GPU位置构建拓扑
+    pub fn new(nodes: BTreeMap<u32, Vec<usize>>, gpu_nodes: HashMap<u32, u32>) -> Self {
+        Self { nodes, gpu_nodes }
+    }
+
+    /// 进程内共享的拓扑
+    pub fn global() -> &'static NumaTopology {
+        TOPOLOGY.get_or_init(|| {
+            let topology = Self::detect();
+            info!(
+                "NUMA topology: {} node(s), GPU nodes {:?}",
+                topology.nodes.len(),
+                topology.gpu_nodes
+            );
+            topology
+        })
+    }
+
+    /// 从sysfs和 nvidia-smi 探测拓扑，失败的部分留空
+    pub fn detect() -> Self {
+        let nodes = read_nodes(Path::new(NODE_ROOT)).unwrap_or_else(|e| {
+            debug!("NUMA nodes are unavailable: {}", e);
+            BTreeMap::new()
+        });
+        let gpu_nodes = if nodes.len() > 1 { read_gpu_nodes() } else { HashMap::new() };
+        Self { nodes, gpu_nodes }
+    }
+
+    /// 是否有多个NUMA节点
+    pub fn is_numa(&self) -> bool {
+        self.nodes.len() > 1
+    }
+
+    /// 节点的CPU核
+    pub fn cpus(&self, node: u32) -> Option<&[usize]> {
+        self.nodes.get(&node).map(Vec::as_slice)
+    }
+
+    /// GPU所在的节点
+    pub fn gpu_node(&self, device_id: u32) -> Option<u32> {
+        self.gpu_nodes.get(&device_id).copied()
+    }
+
+    /// 按配置确定推理线程的放置；未配置或拓扑中找不到对应节点时返回 `None`
+    pub fn placement(&self, affinity: &AffinityConfig, gpu: &GpuConfig) -> Option<ThreadPlacement> {
+        if !affinity.cpus.is_empty() {
+            return Some(ThreadPlacement::PerThread(affinity.cpus.clone()));
+        }
+        let node = affinity.numa_node.or_else(|| {
+            if affinity.follow_gpu {
+                gpu.device_ids.first().and_then(|&device| self.gpu_node(device))
+            } else {
+                None
+            }
+        })?;
+        match self.cpus(node) {
+            Some(cpus) if !cpus.is_empty() => Some(ThreadPlacement::Node { node, cpus: cpus.to_vec() }),
+            _ => {
+                warn!("NUMA node {} has no CPUs, not pinning inference threads", node);
+                None
+            }
+        }
+    }
+}
+
+/// 推理线程的放置
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ThreadPlacement {
+    /// 每个线程绑定一个CPU核，按线程序号轮流分配
+    PerThread(Vec<usize>),
+    /// 线程可在节点的任意CPU核上运行
+    Node { node: u32, cpus: Vec<usize> },
+}
+
+impl ThreadPlacement {
+    /// 第 `index` 个线程可运行的CPU核
+    pub fn cpus_for_thread(&self, index: usize) -> Vec<usize> {
+        match self {
+            ThreadPlacement::PerThread(cpus) => vec![cpus[index % cpus.len()]],
+            ThreadPlacement::Node { cpus, .. } => cpus.clone(),
+        }
+    }
+
+    /// 线程所在的NUMA节点，逐核绑定时无法确定
+    pub fn node(&self) -> Option<u32> {
+        match self {
+            ThreadPlacement::PerThread(_) => None,
+            ThreadPlacement::Node { node, .. } => Some(*node),
+        }
+    }
+}
+
+/// 解析sysfs的CPU列表格式，如 `0-3,8,10-11`
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>> {
+    let parse = |s: &str| {
+        s.trim()
+            .parse::<usize>()
+            .map_err(|_| UniModelError::validation(format!("Invalid CPU list '{}'", list.trim())))
+    };
+    let mut cpus = Vec::new();
+    for range in list.trim().split(',').filter(|r| !r.trim().is_empty()) {
+        match range.split_once('-') {
+            Some((start, end)) => cpus.extend(parse(start)?..=parse(end)?),
+            None => cpus.push(parse(range)?),
+        }
+    }
+    Ok(cpus)
+}
+
+fn read_nodes(root: &Path) -> Result<BTreeMap<u32, Vec<usize>>> {
+    let mut nodes = BTreeMap::new();
+    for entry in std::fs::read_dir(root)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let node = match name.to_str().and_then(|n| n.strip_prefix("node")).and_then(|n| n.parse().ok()) {
+            Some(node) => node,
+            None => continue,
+        };
+        let cpus = std::fs::read_to_string(entry.path().join("cpulist"))?;
+        nodes.insert(node, parse_cpu_list(&cpus)?);
+    }
+    Ok(nodes)
+}
+
+/// 用GPU的PCI地址在sysfs中查找其所在节点
+fn read_gpu_nodes() -> HashMap<u32, u32> {
+    let output = match Command::new("nvidia-smi")
+        .args(["--query-gpu=index,pci.bus_id", "--format=csv,noheader"])
+        .output()
+    {
+        Ok(output) if output.status.success() => output,
+        _ => return HashMap::new(),
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (index, bus_id) = line.split_once(',')?;
+            // nvidia-smi 输出8位PCI域，sysfs使用4位
+            let bus_id = bus_id.trim().to_lowercase();
+            let bus_id = bus_id.get(bus_id.len().saturating_sub(12)..)?;
+            let node = std::fs::read_to_string(format!("/sys/bus/pci/devices/{}/numa_node", bus_id)).ok()?;
+            // 未报告位置的设备为-1
+            Some((index.trim().parse().ok()?, node.trim().parse().ok()?))
+        })
+        .collect()
+}
+
+/// 把当前线程绑定到给定的CPU核
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpus: &[usize]) -> io::Result<()> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "CPU affinity needs Linux"))
+}
+
+/// 按页分配的主机缓冲区，可指定所在的NUMA节点
+#[derive(Debug)]
+pub struct HostBuffer {
+    ptr: NonNull<u8>,
+    len: usize,
+    node: Option<u32>,
+}
+
+// 缓冲区独占其映射，可在线程间转移和共享只读引用
+unsafe impl Send for HostBuffer {}
+unsafe impl Sync for HostBuffer {}
+
+impl HostBuffer {
+    /// 分配 `len` 字节（零初始化），`node` 为首选的NUMA节点；节点策略设置失败时仍返回缓冲区
+    pub fn new(len: usize, node: Option<u32>) -> Result<Self> {
+        if len == 0 {
+            return Err(UniModelError::validation("Host buffer length must be greater than 0"));
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(UniModelError::out_of_memory(format!(
+                "Failed to map a {} byte host buffer: {}",
+                len,
+                io::Error::last_os_error()
+            )));
+        }
+        let mut buffer = Self {
+            ptr: NonNull::new(ptr.cast()).expect("mmap returned null"),
+            len,
+            node: None,
+        };
+        // 页在首次写入时才分配，此前设置策略即可让物理页落在目标节点
+        if let Some(node) = node {
+            match bind_to_node(ptr, len, node) {
+                Ok(()) => buffer.node = Some(node),
+                Err(e) => debug!("Host buffer stays on the default node: {}", e),
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// 在GPU所在的节点上分配，用于暂存拷贝到该卡的数据
+    pub fn for_device(len: usize, device_id: u32) -> Result<Self> {
+        Self::new(len, NumaTopology::global().gpu_node(device_id))
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// 实际生效的节点
+    pub fn node(&self) -> Option<u32> {
+        self.node
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.ptr.as_ptr()
+    }
+
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [u8] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl Drop for HostBuffer {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
+
+/// 设置映射的首选节点（MPOL_PREFERRED），节点内存不足时内核仍可从其他节点分配
+#[cfg(target_os = "linux")]
+fn bind_to_node(ptr: *mut libc::c_void, len: usize, node: u32) -> io::Result<()> {
+    const MPOL_PREFERRED: libc::c_long = 1;
+    let mut mask = [0u64; 16];
+    let (word, bit) = (node as usize / 64, node as usize % 64);
+    if word >= mask.len() {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("NUMA node {} is out of range", node)));
+    }
+    mask[word] |= 1 << bit;
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            ptr,
+            len,
+            MPOL_PREFERRED,
+            mask.as_ptr(),
+            (mask.len() * 64) as libc::c_ulong,
+            0 as libc::c_uint,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn bind_to_node(_ptr: *mut libc::c_void, _len: usize, _node: u32) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "NUMA placement needs Linux"))
+}
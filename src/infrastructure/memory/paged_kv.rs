@@ -0,0 +1,245 @@
+//! 分页KV缓存
+//!
+//! 把KV缓存切成固定token数的块，从显存池一次性申请整片显存后按块分配；每个序列
+//! 持有一张块表（逻辑块 -> 物理块），注意力核按块表寻址。序列只占用实际生成长度所需的块，
+//! 不必按最大长度预留连续显存，同样显存下可容纳更多并发序列。
+//! 分叉的序列（多候选、束搜索）共享已有的块，写入共享的最后一块时先复制（写时复制）。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::infrastructure::memory::{DeviceMemoryPool, PooledBuffer};
+
+/// 分页KV缓存配置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PagedKvConfig {
+    pub device_id: u32,
+    /// 每块容纳的token数
+    #[serde(default = "default_block_tokens")]
+    pub block_tokens: usize,
+    /// 每个token在所有层的K和V占用的字节数
+    pub bytes_per_token: usize,
+    /// 物理块总数
+    pub num_blocks: usize,
+}
+
+fn default_block_tokens() -> usize {
+    16
+}
+
+/// 写时复制需要后端执行的块拷贝
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockCopy {
+    pub src: u32,
+    pub dst: u32,
+}
+
+/// 分页KV缓存统计
+#[derive(Debug, Clone, Serialize)]
+pub struct PagedKvStats {
+    pub total_blocks: usize,
+    pub free_blocks: usize,
+    pub sequences: usize,
+    /// 已分配块中实际存放token的比例
+    pub utilization: f64,
+}
+
+/// 序列的块表
+#[derive(Debug, Clone, Default)]
+struct BlockTable {
+    blocks: Vec<u32>,
+    tokens: usize,
+}
+
+#[derive(Debug)]
+struct KvState {
+    free_blocks: Vec<u32>,
+    ref_counts:  Vec<u32>,
+    tables:      HashMap<String, BlockTable>,
+}
+
+impl KvState {
+    fn take_block(&mut self) -> Option<u32> {
+        let block = self.free_blocks.pop()?;
+        self.ref_counts[block as usize] = 1;
+        Some(block)
+    }
+
+    fn release_block(&mut self, block: u32) {
+        let count = &mut self.ref_counts[block as usize];
+        *count -= 1;
+        if *count == 0 {
+            self.free_blocks.push(block);
+        }
+    }
+}
+
+/// 分页KV缓存
+#[derive(Debug)]
+pub struct PagedKvCache {
+    config: PagedKvConfig,
+    arena:  PooledBuffer,
+    state:  Mutex<KvState>,
+}
+
+impl PagedKvCache {
+    /// 从显存池申请全部块
+    pub fn new(pool: &Arc<DeviceMemoryPool>, config: PagedKvConfig) -> Result<Self> {
+        if config.block_tokens == 0 || config.bytes_per_token == 0 || config.num_blocks == 0 {
+            return Err(UniModelError::config("Paged KV cache needs non-zero block_tokens, bytes_per_token and num_blocks"));
+        }
+        if config.num_blocks > u32::MAX as usize {
+            return Err(UniModelError::config("Paged KV cache has too many blocks"));
+        }
+        let block_bytes = config.block_tokens * config.bytes_per_token;
+        let arena = pool.allocate(config.device_id, block_bytes * config.num_blocks)?;
+
+        // 倒序入栈，先分配低地址的块
+        let free_blocks = (0..config.num_blocks as u32).rev().collect();
+        let ref_counts = vec![0; config.num_blocks];
+        Ok(Self {
+            config,
+            arena,
+            state: Mutex::new(KvState {
+                free_blocks,
+                ref_counts,
+                tables: HashMap::new(),
+            }),
+        })
+    }
+
+    /// 每块的字节数
+    pub fn block_bytes(&self) -> usize {
+        self.config.block_tokens * self.config.bytes_per_token
+    }
+
+    /// 物理块的设备地址
+    pub fn block_address(&self, block: u32) -> u64 {
+        self.arena.ptr() + block as u64 * self.block_bytes() as u64
+    }
+
+    /// 存放 `tokens` 个token需要的块数
+    pub fn blocks_for(&self, tokens: usize) -> usize {
+        tokens.div_ceil(self.config.block_tokens)
+    }
+
+    /// 空闲块是否足够容纳 `tokens` 个token的新序列，供调度器做准入判断
+    pub fn can_allocate(&self, tokens: usize) -> bool {
+        self.state.lock().free_blocks.len() >= self.blocks_for(tokens)
+    }
+
+    /// 为新序列的提示分配块
+    pub fn allocate(&self, sequence_id: &str, tokens: usize) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.tables.contains_key(sequence_id) {
+            return Err(UniModelError::internal(format!("Sequence {} already has a KV cache", sequence_id)));
+        }
+        let needed = self.blocks_for(tokens);
+        if state.free_blocks.len() < needed {
+            return Err(self.exhausted(needed, state.free_blocks.len()));
+        }
+        let blocks = (0..needed).filter_map(|_| state.take_block()).collect();
+        state.tables.insert(sequence_id.to_string(), BlockTable { blocks, tokens });
+        Ok(())
+    }
+
+    /// 序列追加 `tokens` 个token，按需分配新块；最后一块被共享时先复制，返回需要执行的拷贝
+    pub fn append(&self, sequence_id: &str, tokens: usize) -> Result<Vec<BlockCopy>> {
+        let mut state = self.state.lock();
+        let mut table = state.tables.remove(sequence_id)
+            .ok_or_else(|| UniModelError::internal(format!("Sequence {} has no KV cache", sequence_id)))?;
+
+        let block_tokens = self.config.block_tokens;
+        let writes_last_block = table.tokens % block_tokens != 0;
+        let shared_last = writes_last_block
+            && table.blocks.last().is_some_and(|&b| state.ref_counts[b as usize] > 1);
+        let needed = self.blocks_for(table.tokens + tokens) - table.blocks.len() + usize::from(shared_last);
+        if state.free_blocks.len() < needed {
+            let free = state.free_blocks.len();
+            state.tables.insert(sequence_id.to_string(), table);
+            return Err(self.exhausted(needed, free));
+        }
+
+        let mut copies = Vec::new();
+        if shared_last {
+            if let (Some(src), Some(dst)) = (table.blocks.pop(), state.take_block()) {
+                state.release_block(src);
+                table.blocks.push(dst);
+                copies.push(BlockCopy { src, dst });
+            }
+        }
+        while table.blocks.len() < self.blocks_for(table.tokens + tokens) {
+            match state.take_block() {
+                Some(block) => table.blocks.push(block),
+                None => break,
+            }
+        }
+        table.tokens += tokens;
+        state.tables.insert(sequence_id.to_string(), table);
+        Ok(copies)
+    }
+
+    /// 从已有序列分叉出新序列，共享父序列的全部块
+    pub fn fork(&self, parent_id: &str, child_id: &str) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.tables.contains_key(child_id) {
+            return Err(UniModelError::internal(format!("Sequence {} already has a KV cache", child_id)));
+        }
+        let table = state.tables.get(parent_id)
+            .cloned()
+            .ok_or_else(|| UniModelError::internal(format!("Sequence {} has no KV cache", parent_id)))?;
+        for &block in &table.blocks {
+            state.ref_counts[block as usize] += 1;
+        }
+        state.tables.insert(child_id.to_string(), table);
+        Ok(())
+    }
+
+    /// 释放序列的块，共享的块在最后一个持有者释放时才归还
+    pub fn free(&self, sequence_id: &str) {
+        let mut state = self.state.lock();
+        if let Some(table) = state.tables.remove(sequence_id) {
+            for block in table.blocks {
+                state.release_block(block);
+            }
+        }
+    }
+
+    /// 序列的块表，按逻辑顺序排列的物理块号
+    pub fn block_table(&self, sequence_id: &str) -> Option<Vec<u32>> {
+        self.state.lock().tables.get(sequence_id).map(|table| table.blocks.clone())
+    }
+
+    /// 序列已缓存的token数
+    pub fn sequence_tokens(&self, sequence_id: &str) -> Option<usize> {
+        self.state.lock().tables.get(sequence_id).map(|table| table.tokens)
+    }
+
+    /// 统计信息
+    pub fn stats(&self) -> PagedKvStats {
+        let state = self.state.lock();
+        let used_blocks = self.config.num_blocks - state.free_blocks.len();
+        let tokens: usize = state.tables.values().map(|table| table.tokens).sum();
+        PagedKvStats {
+            total_blocks: self.config.num_blocks,
+            free_blocks: state.free_blocks.len(),
+            sequences: state.tables.len(),
+            utilization: if used_blocks == 0 {
+                0.0
+            } else {
+                (tokens as f64 / (used_blocks * self.config.block_tokens) as f64).min(1.0)
+            },
+        }
+    }
+
+    fn exhausted(&self, needed: usize, free: usize) -> UniModelError {
+        UniModelError::out_of_memory(format!(
+            "KV cache on GPU {} exhausted: {} blocks needed, {} of {} free",
+            self.config.device_id, needed, free, self.config.num_blocks
+        ))
+    }
+}
@@ -0,0 +1,262 @@
+//! 锁页主机内存池
+//!
+//! 从可分页内存向GPU拷贝时，驱动要先把数据复制到内部的锁页缓冲区再发起DMA，
+//! 图像、音频这类大批次输入的传输吞吐因此明显下降。后端把输入输出暂存在从这里借出的
+//! 锁页缓冲区中，可直接异步DMA。锁页（如 cudaHostRegister）开销较大，释放的缓冲区按尺寸级别
+//! 缓存复用；缓冲区分配在目标GPU所在的NUMA节点上。池达到上限或锁页失败时退化为可分页缓冲区，
+//! 传输仍然正确，只是变慢。
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::ops::{Deref, DerefMut};
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use tracing::{debug, warn};
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::PinnedMemoryConfig;
+use crate::infrastructure::memory::device_pool::size_class;
+use crate::infrastructure::memory::numa::{HostBuffer, NumaTopology};
+
+/// 主机内存锁页，由推理后端针对其运行时实现
+pub trait HostMemoryRegistrar: Send + Sync + Debug {
+    /// 锁定 `[ptr, ptr + bytes)` 并注册给设备运行时
+    fn register(&self, ptr: u64, bytes: usize) -> Result<()>;
+
+    /// 取消注册并解锁
+    fn unregister(&self, ptr: u64, bytes: usize);
+}
+
+/// 单个NUMA节点（`None` 为未知节点）的池状态
+#[derive(Debug, Default)]
+struct NodePool {
+    free_buffers:   BTreeMap<usize, Vec<HostBuffer>>, // 尺寸级别 -> 空闲的锁页缓冲区
+    reserved_bytes: usize,                            // 已锁页的总字节数
+    in_use_bytes:   usize,
+    registrations:  u64,
+    reuses:         u64,
+}
+
+/// 锁页内存池统计
+#[derive(Debug, Clone, Serialize)]
+pub struct PinnedPoolStats {
+    pub numa_node: Option<u32>,
+    pub reserved_bytes: usize,
+    pub in_use_bytes: usize,
+    pub cached_bytes: usize,
+    pub registrations: u64,
+    pub reuses: u64,
+}
+
+/// 锁页内存池
+#[derive(Debug)]
+pub struct PinnedMemoryPool {
+    enabled:            bool,
+    limit_bytes:        usize,
+    registrar:          Arc<dyn HostMemoryRegistrar>,
+    nodes:              Mutex<HashMap<Option<u32>, NodePool>>,
+    pageable_fallbacks: Mutex<u64>,
+}
+
+impl PinnedMemoryPool {
+    /// 创建锁页内存池
+    pub fn new(config: &PinnedMemoryConfig, registrar: Arc<dyn HostMemoryRegistrar>) -> Self {
+        Self {
+            enabled: config.enabled,
+            limit_bytes: config.max_pool_mb as usize * 1024 * 1024,
+            registrar,
+            nodes: Mutex::new(HashMap::new()),
+            pageable_fallbacks: Mutex::new(0),
+        }
+    }
+
+    /// 借出用于向 `device_id` 传输的暂存缓冲区，释放时归还池
+    pub fn acquire(self: &Arc<Self>, device_id: u32, bytes: usize) -> Result<PinnedBuffer> {
+        self.acquire_on_node(NumaTopology::global().gpu_node(device_id), bytes)
+    }
+
+    /// 在指定NUMA节点上借出暂存缓冲区
+    pub fn acquire_on_node(self: &Arc<Self>, node: Option<u32>, bytes: usize) -> Result<PinnedBuffer> {
+        if bytes == 0 {
+            return Err(UniModelError::validation("Pinned buffer length must be greater than 0"));
+        }
+        if !self.enabled {
+            return self.pageable(node, bytes);
+        }
+
+        let size = size_class(bytes);
+        let mut nodes = self.nodes.lock();
+        let pool = nodes.entry(node).or_default();
+
+        let buffer = match pool.free_buffers.get_mut(&size).and_then(Vec::pop) {
+            Some(buffer) => {
+                pool.reuses += 1;
+                buffer
+            }
+            None => {
+                if pool.reserved_bytes + size > self.limit_bytes {
+                    // 先把缓存的空闲缓冲区解锁释放再判断
+                    self.trim_pool(pool);
+                }
+                if pool.reserved_bytes + size > self.limit_bytes {
+                    debug!(
+                        "Pinned memory pool is full ({} of {} bytes), staging {} bytes in pageable memory",
+                        pool.reserved_bytes, self.limit_bytes, bytes
+                    );
+                    drop(nodes);
+                    return self.pageable(node, bytes);
+                }
+
+                let buffer = HostBuffer::new(size, node)?;
+                if let Err(e) = self.registrar.register(buffer.as_ptr() as u64, size) {
+                    warn!("Failed to pin {} bytes of host memory: {}", size, e);
+                    drop(nodes);
+                    return self.pageable(node, bytes);
+                }
+                pool.reserved_bytes += size;
+                pool.registrations += 1;
+                buffer
+            }
+        };
+        pool.in_use_bytes += size;
+
+        Ok(PinnedBuffer {
+            pool: Some(Arc::clone(self)),
+            node,
+            buffer: Some(buffer),
+            requested: bytes,
+        })
+    }
+
+    /// 不锁页的暂存缓冲区
+    fn pageable(&self, node: Option<u32>, bytes: usize) -> Result<PinnedBuffer> {
+        *self.pageable_fallbacks.lock() += 1;
+        Ok(PinnedBuffer {
+            pool: None,
+            node,
+            buffer: Some(HostBuffer::new(bytes, node)?),
+            requested: bytes,
+        })
+    }
+
+    /// 归还锁页缓冲区
+    fn release(&self, node: Option<u32>, buffer: HostBuffer) {
+        let mut nodes = self.nodes.lock();
+        let pool = nodes.entry(node).or_default();
+        pool.in_use_bytes -= buffer.len();
+        pool.free_buffers.entry(buffer.len()).or_default().push(buffer);
+    }
+
+    /// 解锁并释放所有缓存的空闲缓冲区
+    pub fn empty_cache(&self) {
+        let mut nodes = self.nodes.lock();
+        for pool in nodes.values_mut() {
+            self.trim_pool(pool);
+        }
+    }
+
+    fn trim_pool(&self, pool: &mut NodePool) {
+        for (size, buffers) in std::mem::take(&mut pool.free_buffers) {
+            for buffer in buffers {
+                self.registrar.unregister(buffer.as_ptr() as u64, size);
+                pool.reserved_bytes -= size;
+            }
+        }
+    }
+
+    /// 因池已满、锁页失败或未启用而使用可分页内存的次数
+    pub fn pageable_fallbacks(&self) -> u64 {
+        *self.pageable_fallbacks.lock()
+    }
+
+    /// 各NUMA节点的池统计
+    pub fn stats(&self) -> Vec<PinnedPoolStats> {
+        let nodes = self.nodes.lock();
+        let mut stats: Vec<PinnedPoolStats> = nodes
+            .iter()
+            .map(|(&numa_node, pool)| PinnedPoolStats {
+                numa_node,
+                reserved_bytes: pool.reserved_bytes,
+                in_use_bytes: pool.in_use_bytes,
+                cached_bytes: pool.reserved_bytes - pool.in_use_bytes,
+                registrations: pool.registrations,
+                reuses: pool.reuses,
+            })
+            .collect();
+        stats.sort_by_key(|s| s.numa_node);
+        stats
+    }
+
+    /// 将统计写入指标
+    pub fn publish_metrics(&self) {
+        for stats in self.stats() {
+            let node = stats.numa_node.map_or_else(|| "unknown".to_string(), |n| n.to_string());
+            metrics::gauge!("unimodel_pinned_pool_reserved_bytes", stats.reserved_bytes as f64, "node" => node.clone());
+            metrics::gauge!("unimodel_pinned_pool_in_use_bytes", stats.in_use_bytes as f64, "node" => node.clone());
+            metrics::gauge!("unimodel_pinned_pool_registrations", stats.registrations as f64, "node" => node.clone());
+            metrics::gauge!("unimodel_pinned_pool_reuses", stats.reuses as f64, "node" => node);
+        }
+        metrics::gauge!("unimodel_pinned_pool_pageable_fallbacks", self.pageable_fallbacks() as f64);
+    }
+}
+
+impl Drop for PinnedMemoryPool {
+    fn drop(&mut self) {
+        self.empty_cache();
+    }
+}
+
+/// 从锁页内存池借出的暂存缓冲区，长度为请求的字节数
+#[derive(Debug)]
+pub struct PinnedBuffer {
+    pool:      Option<Arc<PinnedMemoryPool>>, // 可分页缓冲区不归还
+    node:      Option<u32>,
+    buffer:    Option<HostBuffer>,
+    requested: usize,
+}
+
+impl PinnedBuffer {
+    /// 是否为锁页内存，否则传输走驱动的可分页路径
+    pub fn is_pinned(&self) -> bool {
+        self.pool.is_some()
+    }
+
+    /// 所在的NUMA节点
+    pub fn numa_node(&self) -> Option<u32> {
+        self.node
+    }
+
+    /// 主机指针
+    pub fn ptr(&self) -> u64 {
+        self.host().as_ptr() as u64
+    }
+
+    fn host(&self) -> &HostBuffer {
+        self.buffer.as_ref().expect("pinned buffer already released")
+    }
+}
+
+impl Deref for PinnedBuffer {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.host().as_slice()[..self.requested]
+    }
+}
+
+impl DerefMut for PinnedBuffer {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        let requested = self.requested;
+        &mut self.buffer.as_mut().expect("pinned buffer already released").as_mut_slice()[..requested]
+    }
+}
+
+impl Drop for PinnedBuffer {
+    fn drop(&mut self) {
+        if let (Some(pool), Some(buffer)) = (self.pool.take(), self.buffer.take()) {
+            pool.release(self.node, buffer);
+        }
+    }
+}
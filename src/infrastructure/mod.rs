@@ -0,0 +1,14 @@
+//! 基础设施层
+
+pub mod configuration;
+pub mod memory;
+pub mod messaging;
+pub mod model_format;
+pub mod monitoring;
+pub mod postprocessing;
+pub mod preprocessing;
+pub mod repository;
+pub mod security;
+pub mod storage;
+pub mod tensor_format;
+pub mod tokenizer;
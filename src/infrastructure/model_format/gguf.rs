@@ -0,0 +1,398 @@
+//! GGUF 格式
+//!
+//! 只读取文件头：魔数、版本、键值元数据和张量描述，不加载张量数据。
+//! 数组类型的元数据（如词表）默认只记录长度、跳过内容，按需保留指定前缀的数组。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use crate::common::error::*;
+
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// 字符串长度上限，防止损坏的文件导致巨量分配
+const MAX_STRING_BYTES: u64 = 16 * 1024 * 1024;
+
+/// 保留的数组元素个数上限
+const MAX_ARRAY_LEN: u64 = 16 * 1024 * 1024;
+
+/// 词表相关元数据的键前缀
+const TOKENIZER_PREFIX: &str = "tokenizer.ggml.";
+
+/// 元数据值
+#[derive(Debug, Clone, PartialEq)]
+pub enum GgufValue {
+    UInt(u64),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    String(String),
+    /// 数组，只记录元素个数
+    Array(u64),
+}
+
+impl GgufValue {
+    /// 按无符号整数读取
+    pub fn as_u64(&self) -> Option<u64> {
+        match self {
+            GgufValue::UInt(v) => Some(*v),
+            GgufValue::Int(v) if *v >= 0 => Some(*v as u64),
+            _ => None,
+        }
+    }
+
+    /// 按字符串读取
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            GgufValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+/// 保留内容的数组元数据
+#[derive(Debug, Clone, PartialEq)]
+pub enum GgufArray {
+    Strings(Vec<String>),
+    /// 数值数组统一转换为f64
+    Numbers(Vec<f64>),
+}
+
+/// 张量描述
+#[derive(Debug, Clone)]
+pub struct GgufTensorInfo {
+    pub name: String,
+    pub dims: Vec<u64>,
+    /// ggml 张量类型编号
+    pub ggml_type: u32,
+    pub offset: u64,
+}
+
+/// GGUF 文件头
+#[derive(Debug, Clone)]
+pub struct GgufHeader {
+    pub version: u32,
+    pub metadata: HashMap<String, GgufValue>,
+    /// 按需保留的数组内容，键同 `metadata`
+    pub arrays: HashMap<String, GgufArray>,
+    pub tensors: Vec<GgufTensorInfo>,
+}
+
+impl GgufHeader {
+    /// 读取文件头
+    pub fn read<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read_retaining(path.as_ref(), None)
+    }
+
+    /// 读取文件头并保留词表数组（`tokenizer.ggml.*`）
+    pub fn read_with_vocab<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::read_retaining(path.as_ref(), Some(TOKENIZER_PREFIX))
+    }
+
+    fn read_retaining(path: &Path, retain_prefix: Option<&'static str>) -> Result<Self> {
+        let mut reader = GgufReader {
+            inner: BufReader::new(File::open(path)?),
+            version: 0,
+            retain_prefix,
+        };
+        reader
+            .read_header()
+            .map_err(|e| UniModelError::model(format!("Invalid GGUF file {}: {}", path.display(), e)))
+    }
+
+    /// 内嵌词表，文件头未保留词表数组或不含词表时为 `None`
+    pub fn vocab(&self) -> Option<GgufVocab> {
+        let strings = |key: &str| match self.arrays.get(&format!("{}{}", TOKENIZER_PREFIX, key)) {
+            Some(GgufArray::Strings(values)) => values.clone(),
+            _ => Vec::new(),
+        };
+        let numbers = |key: &str| match self.arrays.get(&format!("{}{}", TOKENIZER_PREFIX, key)) {
+            Some(GgufArray::Numbers(values)) => values.clone(),
+            _ => Vec::new(),
+        };
+        let token_id = |key: &str| {
+            self.metadata.get(&format!("{}{}", TOKENIZER_PREFIX, key))
+                .and_then(|v| v.as_u64())
+                .map(|id| id as u32)
+        };
+
+        let tokens = strings("tokens");
+        if tokens.is_empty() {
+            return None;
+        }
+        Some(GgufVocab {
+            model: self.metadata.get("tokenizer.ggml.model")
+                .and_then(|v| v.as_str())
+                .unwrap_or("llama")
+                .to_string(),
+            tokens,
+            scores: numbers("scores").into_iter().map(|s| s as f32).collect(),
+            token_types: numbers("token_type").into_iter().map(|t| t as i32).collect(),
+            merges: strings("merges"),
+            bos_token_id: token_id("bos_token_id"),
+            eos_token_id: token_id("eos_token_id"),
+            add_bos_token: match self.metadata.get("tokenizer.ggml.add_bos_token") {
+                Some(GgufValue::Bool(add)) => *add,
+                _ => true,
+            },
+        })
+    }
+
+    /// 模型架构（`general.architecture`）
+    pub fn architecture(&self) -> Option<&str> {
+        self.metadata.get("general.architecture").and_then(|v| v.as_str())
+    }
+
+    /// 上下文长度（`<arch>.context_length`）
+    pub fn context_length(&self) -> Option<u64> {
+        let key = format!("{}.context_length", self.architecture()?);
+        self.metadata.get(&key).and_then(|v| v.as_u64())
+    }
+
+    /// 量化类型，来自 `general.file_type`
+    pub fn quantization(&self) -> Option<String> {
+        let file_type = self.metadata.get("general.file_type")?.as_u64()?;
+        Some(file_type_name(file_type).map_or_else(|| format!("type_{}", file_type), str::to_string))
+    }
+
+    /// 参数总数，优先使用 `general.parameter_count`，否则按张量维度求和
+    pub fn parameter_count(&self) -> u64 {
+        if let Some(count) = self.metadata.get("general.parameter_count").and_then(|v| v.as_u64()) {
+            return count;
+        }
+        self.tensors.iter().map(|t| t.dims.iter().product::<u64>()).sum()
+    }
+}
+
+/// GGUF内嵌的词表（llama.cpp 的 `tokenizer.ggml.*` 元数据）
+#[derive(Debug, Clone, PartialEq)]
+pub struct GgufVocab {
+    /// 分词算法：`llama`（SentencePiece）或 `gpt2`（字节级BPE）
+    pub model: String,
+    pub tokens: Vec<String>,
+    pub scores: Vec<f32>,
+    /// 1为普通token，2为未知token，3为控制token
+    pub token_types: Vec<i32>,
+    /// BPE合并规则，每条为空格分隔的两个token
+    pub merges: Vec<String>,
+    pub bos_token_id: Option<u32>,
+    pub eos_token_id: Option<u32>,
+    pub add_bos_token: bool,
+}
+
+/// llama.cpp 的 `llama_ftype` 名称
+fn file_type_name(file_type: u64) -> Option<&'static str> {
+    Some(match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        32 => "BF16",
+        _ => return None,
+    })
+}
+
+/// 文件头读取器
+struct GgufReader<R> {
+    inner: R,
+    version: u32,
+    /// 键以该前缀开头的数组保留内容
+    retain_prefix: Option<&'static str>,
+}
+
+type ReadResult<T> = std::result::Result<T, String>;
+
+impl<R: Read> GgufReader<R> {
+    fn read_header(&mut self) -> ReadResult<GgufHeader> {
+        let mut magic = [0u8; 4];
+        self.read_exact(&mut magic)?;
+        if &magic != GGUF_MAGIC {
+            return Err("bad magic".to_string());
+        }
+
+        self.version = self.read_u32()?;
+        if !(1..=3).contains(&self.version) {
+            return Err(format!("unsupported version {}", self.version));
+        }
+
+        let tensor_count = self.read_count()?;
+        let kv_count = self.read_count()?;
+
+        let mut metadata = HashMap::new();
+        let mut arrays = HashMap::new();
+        for _ in 0..kv_count {
+            let key = self.read_string()?;
+            let value_type = self.read_u32()?;
+            let retain = self.retain_prefix.is_some_and(|prefix| key.starts_with(prefix));
+            let value = if value_type == 9 && retain {
+                let (len, array) = self.read_array()?;
+                if let Some(array) = array {
+                    arrays.insert(key.clone(), array);
+                }
+                GgufValue::Array(len)
+            } else {
+                self.read_value(value_type)?
+            };
+            metadata.insert(key, value);
+        }
+
+        let mut tensors = Vec::new();
+        for _ in 0..tensor_count {
+            let name = self.read_string()?;
+            let n_dims = self.read_u32()?;
+            if n_dims > 8 {
+                return Err(format!("tensor '{}' has {} dimensions", name, n_dims));
+            }
+            let dims = (0..n_dims).map(|_| self.read_count()).collect::<ReadResult<Vec<_>>>()?;
+            let ggml_type = self.read_u32()?;
+            let offset = self.read_u64()?;
+            tensors.push(GgufTensorInfo { name, dims, ggml_type, offset });
+        }
+
+        Ok(GgufHeader {
+            version: self.version,
+            metadata,
+            arrays,
+            tensors,
+        })
+    }
+
+    fn read_value(&mut self, value_type: u32) -> ReadResult<GgufValue> {
+        Ok(match value_type {
+            0 => GgufValue::UInt(self.read_bytes::<1>()?[0] as u64),
+            1 => GgufValue::Int(self.read_bytes::<1>()?[0] as i8 as i64),
+            2 => GgufValue::UInt(u16::from_le_bytes(self.read_bytes()?) as u64),
+            3 => GgufValue::Int(i16::from_le_bytes(self.read_bytes()?) as i64),
+            4 => GgufValue::UInt(self.read_u32()? as u64),
+            5 => GgufValue::Int(i32::from_le_bytes(self.read_bytes()?) as i64),
+            6 => GgufValue::Float(f32::from_le_bytes(self.read_bytes()?) as f64),
+            7 => GgufValue::Bool(self.read_bytes::<1>()?[0] != 0),
+            8 => GgufValue::String(self.read_string()?),
+            9 => {
+                let item_type = self.read_u32()?;
+                let len = self.read_count()?;
+                self.skip_array(item_type, len)?;
+                GgufValue::Array(len)
+            }
+            10 => GgufValue::UInt(self.read_u64()?),
+            11 => GgufValue::Int(i64::from_le_bytes(self.read_bytes()?)),
+            12 => GgufValue::Float(f64::from_le_bytes(self.read_bytes()?)),
+            other => return Err(format!("unknown value type {}", other)),
+        })
+    }
+
+    /// 读取数组内容；嵌套数组跳过，只返回长度
+    fn read_array(&mut self) -> ReadResult<(u64, Option<GgufArray>)> {
+        let item_type = self.read_u32()?;
+        let len = self.read_count()?;
+        if item_type == 9 {
+            self.skip_array(item_type, len)?;
+            return Ok((len, None));
+        }
+        if len > MAX_ARRAY_LEN {
+            return Err(format!("array length {} too large", len));
+        }
+
+        let array = if item_type == 8 {
+            GgufArray::Strings((0..len).map(|_| self.read_string()).collect::<ReadResult<_>>()?)
+        } else {
+            let numbers = (0..len)
+                .map(|_| match self.read_value(item_type)? {
+                    GgufValue::UInt(v) => Ok(v as f64),
+                    GgufValue::Int(v) => Ok(v as f64),
+                    GgufValue::Float(v) => Ok(v),
+                    GgufValue::Bool(v) => Ok(if v { 1.0 } else { 0.0 }),
+                    other => Err(format!("unexpected array item {:?}", other)),
+                })
+                .collect::<ReadResult<_>>()?;
+            GgufArray::Numbers(numbers)
+        };
+        Ok((len, Some(array)))
+    }
+
+    fn skip_array(&mut self, item_type: u32, len: u64) -> ReadResult<()> {
+        let item_size: u64 = match item_type {
+            0 | 1 | 7 => 1,
+            2 | 3 => 2,
+            4..=6 => 4,
+            10..=12 => 8,
+            8 => {
+                for _ in 0..len {
+                    let str_len = self.read_count()?;
+                    self.skip(str_len)?;
+                }
+                return Ok(());
+            }
+            9 => {
+                for _ in 0..len {
+                    let nested_type = self.read_u32()?;
+                    let nested_len = self.read_count()?;
+                    self.skip_array(nested_type, nested_len)?;
+                }
+                return Ok(());
+            }
+            other => return Err(format!("unknown array item type {}", other)),
+        };
+        self.skip(len.saturating_mul(item_size))
+    }
+
+    /// 数量字段：v1 为 u32，v2 起为 u64
+    fn read_count(&mut self) -> ReadResult<u64> {
+        if self.version == 1 {
+            Ok(self.read_u32()? as u64)
+        } else {
+            self.read_u64()
+        }
+    }
+
+    fn read_string(&mut self) -> ReadResult<String> {
+        let len = self.read_count()?;
+        if len > MAX_STRING_BYTES {
+            return Err(format!("string length {} too large", len));
+        }
+        let mut bytes = vec![0u8; len as usize];
+        self.read_exact(&mut bytes)?;
+        String::from_utf8(bytes).map_err(|e| format!("invalid UTF-8 string: {}", e))
+    }
+
+    fn read_u32(&mut self) -> ReadResult<u32> {
+        Ok(u32::from_le_bytes(self.read_bytes()?))
+    }
+
+    fn read_u64(&mut self) -> ReadResult<u64> {
+        Ok(u64::from_le_bytes(self.read_bytes()?))
+    }
+
+    fn read_bytes<const N: usize>(&mut self) -> ReadResult<[u8; N]> {
+        let mut buf = [0u8; N];
+        self.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn read_exact(&mut self, buf: &mut [u8]) -> ReadResult<()> {
+        self.inner.read_exact(buf).map_err(|e| format!("truncated header: {}", e))
+    }
+
+    fn skip(&mut self, bytes: u64) -> ReadResult<()> {
+        let skipped = std::io::copy(&mut (&mut self.inner).take(bytes), &mut std::io::sink())
+            .map_err(|e| e.to_string())?;
+        if skipped < bytes {
+            return Err("truncated header".to_string());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,11 @@
+//! 模型文件格式解析
+
+pub mod gguf;
+pub mod onnx;
+pub mod safetensors;
+pub mod sniff;
+
+pub use gguf::{GgufArray, GgufHeader, GgufTensorInfo, GgufValue, GgufVocab};
+pub use onnx::read_onnx_signature;
+pub use safetensors::{SafeTensorsFile, TensorInfo, TensorView};
+pub use sniff::sniff_format;
@@ -0,0 +1,264 @@
+//! ONNX 模型签名读取
+//!
+//! 直接按protobuf线格式遍历 `ModelProto.graph`，只解析输入输出的名称、元素类型和形状。
+//! 文件通过mmap映射，权重（initializer）只按长度跳过，不复制。
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::path::Path;
+
+use memmap2::Mmap;
+
+use crate::common::error::*;
+use crate::common::types::DataType;
+use crate::domain::model::{ModelSignature, SignatureSource, TensorSpec};
+
+// ModelProto / GraphProto / ValueInfoProto / TypeProto 的字段编号
+const MODEL_GRAPH: u32 = 7;
+const GRAPH_INITIALIZER: u32 = 5;
+const GRAPH_INPUT: u32 = 11;
+const GRAPH_OUTPUT: u32 = 12;
+const TENSOR_NAME: u32 = 8;
+const VALUE_INFO_NAME: u32 = 1;
+const VALUE_INFO_TYPE: u32 = 2;
+const TYPE_TENSOR: u32 = 1;
+const TENSOR_TYPE_ELEM: u32 = 1;
+const TENSOR_TYPE_SHAPE: u32 = 2;
+const SHAPE_DIM: u32 = 1;
+const DIM_VALUE: u32 = 1;
+
+type ParseResult<T> = std::result::Result<T, String>;
+
+/// 读取ONNX模型的输入输出签名
+///
+/// 同时作为initializer出现的图输入是权重，不计入签名。
+pub fn read_onnx_signature<P: AsRef<Path>>(path: P) -> Result<ModelSignature> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+    // SAFETY: 文件以只读方式映射，解析期间不应被修改
+    let mmap = unsafe { Mmap::map(&file)? };
+
+    parse_model(&mmap).map_err(|e| UniModelError::model(format!("Invalid ONNX model {}: {}", path.display(), e)))
+}
+
+fn parse_model(bytes: &[u8]) -> ParseResult<ModelSignature> {
+    let mut graph = None;
+    let mut reader = WireReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        if field == MODEL_GRAPH {
+            graph = Some(value.bytes()?);
+        }
+    }
+    let graph = graph.ok_or_else(|| "model has no graph".to_string())?;
+
+    let mut initializers = HashSet::new();
+    let mut inputs = Vec::new();
+    let mut outputs = Vec::new();
+    let mut reader = WireReader::new(graph);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            GRAPH_INITIALIZER => {
+                if let Some(name) = find_string(value.bytes()?, TENSOR_NAME)? {
+                    initializers.insert(name);
+                }
+            }
+            GRAPH_INPUT => inputs.push(parse_value_info(value.bytes()?)?),
+            GRAPH_OUTPUT => outputs.push(parse_value_info(value.bytes()?)?),
+            _ => {}
+        }
+    }
+
+    inputs.retain(|spec| !initializers.contains(&spec.name));
+    Ok(ModelSignature {
+        inputs,
+        outputs,
+        source: SignatureSource::Introspected,
+    })
+}
+
+fn parse_value_info(bytes: &[u8]) -> ParseResult<TensorSpec> {
+    let mut spec = TensorSpec {
+        name: String::new(),
+        dtype: None,
+        shape: Vec::new(),
+        modality: None,
+    };
+
+    let mut reader = WireReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            VALUE_INFO_NAME => spec.name = value.string()?,
+            VALUE_INFO_TYPE => {
+                if let Some(tensor_type) = find_bytes(value.bytes()?, TYPE_TENSOR)? {
+                    parse_tensor_type(tensor_type, &mut spec)?;
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(spec)
+}
+
+fn parse_tensor_type(bytes: &[u8], spec: &mut TensorSpec) -> ParseResult<()> {
+    let mut reader = WireReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        match field {
+            TENSOR_TYPE_ELEM => spec.dtype = onnx_dtype(value.varint()?),
+            TENSOR_TYPE_SHAPE => {
+                let mut dims = WireReader::new(value.bytes()?);
+                while let Some((field, dim)) = dims.next_field()? {
+                    if field == SHAPE_DIM {
+                        // 未给出 dim_value（符号维度或未知）视为动态
+                        let size = match find_varint(dim.bytes()?, DIM_VALUE)? {
+                            Some(size) => size as i64,
+                            None => -1,
+                        };
+                        spec.shape.push(size);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// `TensorProto.DataType` 到元素类型的映射
+fn onnx_dtype(elem_type: u64) -> Option<DataType> {
+    Some(match elem_type {
+        1 => DataType::F32,
+        2 => DataType::U8,
+        3 => DataType::I8,
+        4 => DataType::U16,
+        5 => DataType::I16,
+        6 => DataType::I32,
+        7 => DataType::I64,
+        9 => DataType::Bool,
+        10 => DataType::F16,
+        11 => DataType::F64,
+        12 => DataType::U32,
+        13 => DataType::U64,
+        16 => DataType::BF16,
+        17 => DataType::F8E4M3,
+        19 => DataType::F8E5M2,
+        _ => return None,
+    })
+}
+
+fn find_bytes(bytes: &[u8], target: u32) -> ParseResult<Option<&[u8]>> {
+    let mut reader = WireReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        if field == target {
+            return value.bytes().map(Some);
+        }
+    }
+    Ok(None)
+}
+
+fn find_string(bytes: &[u8], target: u32) -> ParseResult<Option<String>> {
+    let mut reader = WireReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        if field == target {
+            return value.string().map(Some);
+        }
+    }
+    Ok(None)
+}
+
+fn find_varint(bytes: &[u8], target: u32) -> ParseResult<Option<u64>> {
+    let mut reader = WireReader::new(bytes);
+    while let Some((field, value)) = reader.next_field()? {
+        if field == target {
+            return value.varint().map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// protobuf字段值
+enum WireValue<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed,
+}
+
+impl<'a> WireValue<'a> {
+    fn varint(&self) -> ParseResult<u64> {
+        match self {
+            WireValue::Varint(v) => Ok(*v),
+            _ => Err("expected varint field".to_string()),
+        }
+    }
+
+    fn bytes(&self) -> ParseResult<&'a [u8]> {
+        match self {
+            WireValue::Bytes(b) => Ok(b),
+            _ => Err("expected length-delimited field".to_string()),
+        }
+    }
+
+    fn string(&self) -> ParseResult<String> {
+        String::from_utf8(self.bytes()?.to_vec()).map_err(|e| format!("invalid string: {}", e))
+    }
+}
+
+/// protobuf线格式读取器
+struct WireReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> WireReader<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0 }
+    }
+
+    fn next_field(&mut self) -> ParseResult<Option<(u32, WireValue<'a>)>> {
+        if self.pos >= self.buf.len() {
+            return Ok(None);
+        }
+
+        let key = self.read_varint()?;
+        let field = (key >> 3) as u32;
+        let value = match key & 0x7 {
+            0 => WireValue::Varint(self.read_varint()?),
+            1 => {
+                self.advance(8)?;
+                WireValue::Fixed
+            }
+            2 => {
+                let len = usize::try_from(self.read_varint()?).map_err(|_| "length overflow".to_string())?;
+                let start = self.pos;
+                self.advance(len)?;
+                WireValue::Bytes(&self.buf[start..start + len])
+            }
+            5 => {
+                self.advance(4)?;
+                WireValue::Fixed
+            }
+            wire_type => return Err(format!("unsupported wire type {}", wire_type)),
+        };
+        Ok(Some((field, value)))
+    }
+
+    fn read_varint(&mut self) -> ParseResult<u64> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.buf.get(self.pos).ok_or_else(|| "truncated varint".to_string())?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err("varint too long".to_string())
+    }
+
+    fn advance(&mut self, len: usize) -> ParseResult<()> {
+        if self.buf.len() - self.pos < len {
+            return Err("truncated field".to_string());
+        }
+        self.pos += len;
+        Ok(())
+    }
+}
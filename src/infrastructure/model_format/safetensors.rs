@@ -0,0 +1,189 @@
+//! safetensors 格式
+//!
+//! 文件布局：8字节小端头部长度N，N字节JSON头部（张量名 -> dtype/shape/data_offsets，
+//! 以及可选的 `__metadata__`），之后是张量数据区。数据区通过mmap映射，
+//! 后端直接引用其中的切片，不做复制。
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use memmap2::Mmap;
+use serde::Deserialize;
+
+use crate::common::error::*;
+use crate::common::types::DataType;
+
+/// 头部长度上限，防止损坏的文件导致巨量分配
+const MAX_HEADER_BYTES: u64 = 100 * 1024 * 1024;
+
+const METADATA_KEY: &str = "__metadata__";
+
+/// 头部中的张量条目
+#[derive(Debug, Deserialize)]
+struct RawTensorInfo {
+    dtype: String,
+    shape: Vec<usize>,
+    data_offsets: (usize, usize),
+}
+
+/// 张量描述
+#[derive(Debug, Clone)]
+pub struct TensorInfo {
+    pub name: String,
+    pub dtype: DataType,
+    pub shape: Vec<usize>,
+    /// 在数据区中的字节范围
+    pub data_offsets: (usize, usize),
+}
+
+impl TensorInfo {
+    /// 元素个数
+    pub fn num_elements(&self) -> usize {
+        self.shape.iter().product()
+    }
+}
+
+/// 张量视图，数据直接引用映射的文件
+#[derive(Debug, Clone, Copy)]
+pub struct TensorView<'a> {
+    pub info: &'a TensorInfo,
+    pub data: &'a [u8],
+}
+
+/// 已打开的 safetensors 文件
+#[derive(Debug)]
+pub struct SafeTensorsFile {
+    path: PathBuf,
+    mmap: Mmap,
+    data_start: usize,
+    tensors: Vec<TensorInfo>, // 按数据偏移排序
+    index: HashMap<String, usize>,
+    metadata: HashMap<String, String>,
+}
+
+impl SafeTensorsFile {
+    /// 映射文件并解析头部
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        // SAFETY: 文件以只读方式映射；模型文件在服务期间不应被其他进程修改
+        let mmap = unsafe { Mmap::map(&file)? };
+
+        let (tensors, metadata, data_start) = parse_header(&mmap)
+            .map_err(|e| UniModelError::model(format!("Invalid safetensors file {}: {}", path.display(), e)))?;
+        let index = tensors.iter().enumerate().map(|(i, t)| (t.name.clone(), i)).collect();
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            mmap,
+            data_start,
+            tensors,
+            index,
+            metadata,
+        })
+    }
+
+    /// 文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 所有张量描述（按数据偏移排序）
+    pub fn tensors(&self) -> &[TensorInfo] {
+        &self.tensors
+    }
+
+    /// 头部中的 `__metadata__`
+    pub fn metadata(&self) -> &HashMap<String, String> {
+        &self.metadata
+    }
+
+    /// 按名称获取张量
+    pub fn tensor(&self, name: &str) -> Option<TensorView<'_>> {
+        let info = &self.tensors[*self.index.get(name)?];
+        let (begin, end) = info.data_offsets;
+        Some(TensorView {
+            info,
+            data: &self.mmap[self.data_start + begin..self.data_start + end],
+        })
+    }
+
+    /// 参数总数
+    pub fn num_parameters(&self) -> u64 {
+        self.tensors.iter().map(|t| t.num_elements() as u64).sum()
+    }
+}
+
+/// 张量描述、元数据和数据区起始位置
+type ParsedHeader = (Vec<TensorInfo>, HashMap<String, String>, usize);
+
+/// 解析头部，返回张量描述、元数据和数据区起始位置
+fn parse_header(bytes: &[u8]) -> std::result::Result<ParsedHeader, String> {
+    if bytes.len() < 8 {
+        return Err("file too small".to_string());
+    }
+
+    let header_len = u64::from_le_bytes(bytes[..8].try_into().expect("slice has 8 bytes"));
+    if header_len > MAX_HEADER_BYTES || 8 + header_len > bytes.len() as u64 {
+        return Err(format!("invalid header length {}", header_len));
+    }
+    let data_start = 8 + header_len as usize;
+    let data_len = bytes.len() - data_start;
+
+    let mut header: HashMap<String, serde_json::Value> =
+        serde_json::from_slice(&bytes[8..data_start]).map_err(|e| format!("invalid header: {}", e))?;
+
+    let metadata = match header.remove(METADATA_KEY) {
+        Some(value) => serde_json::from_value(value).map_err(|e| format!("invalid metadata: {}", e))?,
+        None => HashMap::new(),
+    };
+
+    let mut tensors = Vec::with_capacity(header.len());
+    for (name, value) in header {
+        let raw: RawTensorInfo =
+            serde_json::from_value(value).map_err(|e| format!("invalid entry for tensor '{}': {}", name, e))?;
+        let dtype = parse_dtype(&raw.dtype).ok_or_else(|| format!("unsupported dtype '{}' for tensor '{}'", raw.dtype, name))?;
+
+        let (begin, end) = raw.data_offsets;
+        let expected = raw.shape.iter().product::<usize>() * dtype.size_bytes();
+        if begin > end || end > data_len || end - begin != expected {
+            return Err(format!("tensor '{}' has invalid data offsets [{}, {})", name, begin, end));
+        }
+
+        tensors.push(TensorInfo {
+            name,
+            dtype,
+            shape: raw.shape,
+            data_offsets: raw.data_offsets,
+        });
+    }
+
+    tensors.sort_by_key(|t| t.data_offsets);
+    if tensors.windows(2).any(|w| w[0].data_offsets.1 > w[1].data_offsets.0) {
+        return Err("tensor data regions overlap".to_string());
+    }
+
+    Ok((tensors, metadata, data_start))
+}
+
+fn parse_dtype(dtype: &str) -> Option<DataType> {
+    Some(match dtype {
+        "BOOL" => DataType::Bool,
+        "U8" => DataType::U8,
+        "I8" => DataType::I8,
+        "U16" => DataType::U16,
+        "I16" => DataType::I16,
+        "F16" => DataType::F16,
+        "BF16" => DataType::BF16,
+        "U32" => DataType::U32,
+        "I32" => DataType::I32,
+        "F32" => DataType::F32,
+        "U64" => DataType::U64,
+        "I64" => DataType::I64,
+        "F64" => DataType::F64,
+        "F8_E4M3" => DataType::F8E4M3,
+        "F8_E5M2" => DataType::F8E5M2,
+        _ => return None,
+    })
+}
@@ -0,0 +1,108 @@
+//! 按文件内容识别模型格式
+//!
+//! 只读取文件开头的少量字节：GGUF魔数、safetensors的头部长度加JSON、
+//! PyTorch/TorchScript的zip或pickle头，以及ONNX `ModelProto` 开头的 `ir_version` 字段。
+
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use crate::domain::service::conversion::ModelFormat;
+
+/// 读取的文件头字节数
+const SNIFF_BYTES: usize = 16;
+
+const GGUF_MAGIC: &[u8] = b"GGUF";
+const ZIP_MAGIC: &[u8] = b"PK\x03\x04";
+/// pickle协议2及以上以PROTO操作码开头，`torch.save` 的旧格式即为pickle
+const PICKLE_PROTO: u8 = 0x80;
+/// 与 safetensors 解析器一致的头部长度上限
+const MAX_SAFETENSORS_HEADER: u64 = 100 * 1024 * 1024;
+
+/// ModelProto中 `ir_version` 之后可能出现的字段及其线格式类型（0为varint，2为长度前缀）
+const ONNX_MODEL_FIELDS: &[(u64, u8)] = &[
+    (2, 2),  // producer_name
+    (3, 2),  // producer_version
+    (4, 2),  // domain
+    (5, 0),  // model_version
+    (6, 2),  // doc_string
+    (7, 2),  // graph
+    (8, 2),  // opset_import
+    (14, 2), // metadata_props
+    (20, 2), // training_info
+    (25, 2), // functions
+];
+
+/// 按文件内容识别格式
+///
+/// 目录按 `config.json` 识别为HuggingFace模型；文件不可读或内容无法识别时返回None。
+pub fn sniff_format(path: &Path) -> Option<ModelFormat> {
+    if path.is_dir() {
+        return path.join("config.json").is_file().then_some(ModelFormat::HuggingFace);
+    }
+    let mut file = File::open(path).ok()?;
+    let file_len = file.metadata().ok()?.len();
+    let mut head = Vec::with_capacity(SNIFF_BYTES);
+    file.by_ref().take(SNIFF_BYTES as u64).read_to_end(&mut head).ok()?;
+    sniff_bytes(&head, file_len)
+}
+
+/// 按文件开头的字节识别格式，`file_len` 为完整文件长度
+pub fn sniff_bytes(head: &[u8], file_len: u64) -> Option<ModelFormat> {
+    if head.starts_with(GGUF_MAGIC) {
+        return Some(ModelFormat::Gguf);
+    }
+    if head.starts_with(ZIP_MAGIC) {
+        return Some(ModelFormat::PyTorch);
+    }
+    if head.len() >= 2 && head[0] == PICKLE_PROTO && (2..=5).contains(&head[1]) {
+        return Some(ModelFormat::PyTorch);
+    }
+    if is_safetensors(head, file_len) {
+        return Some(ModelFormat::SafeTensors);
+    }
+    if is_onnx(head) {
+        return Some(ModelFormat::Onnx);
+    }
+    None
+}
+
+fn is_safetensors(head: &[u8], file_len: u64) -> bool {
+    if head.len() < 9 {
+        return false;
+    }
+    let header_len = u64::from_le_bytes(head[..8].try_into().unwrap());
+    (2..=MAX_SAFETENSORS_HEADER).contains(&header_len) && 8 + header_len <= file_len && head[8] == b'{'
+}
+
+/// ONNX导出器总是先写 `ir_version`（字段1，varint），随后是ModelProto的其他字段
+fn is_onnx(head: &[u8]) -> bool {
+    if head.first() != Some(&0x08) {
+        return false;
+    }
+    let mut pos = 1;
+    let ir_version = match read_varint(head, &mut pos) {
+        Some(v) => v,
+        None => return false,
+    };
+    if ir_version == 0 || ir_version > 64 {
+        return false;
+    }
+    match read_varint(head, &mut pos) {
+        Some(key) => ONNX_MODEL_FIELDS.iter().any(|&(field, wire)| key >> 3 == field && (key & 0x7) as u8 == wire),
+        None => false,
+    }
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Option<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos)?;
+        *pos += 1;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+    }
+    None
+}
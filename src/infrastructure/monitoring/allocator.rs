@@ -0,0 +1,134 @@
+//! 内存分配器统计
+//!
+//! 服务器以jemalloc作为全局分配器（见 `main.rs`），这里读取其统计：已分配、活跃页、常驻、映射等
+//! 全局字节数以及每个arena的线程数和页占用，通过 `GET /debug/memory` 和Prometheus指标提供，
+//! 用于排查长时间运行后的内存增长。jemalloc的统计按epoch缓存，读取前先推进epoch。
+
+use std::time::Duration;
+
+use serde::Serialize;
+use tikv_jemalloc_ctl::{arenas, epoch, raw, stats};
+use tracing::{info, warn};
+
+use crate::common::error::*;
+
+/// 单个arena的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct ArenaStats {
+    pub index: u32,
+    /// 绑定到该arena的线程数
+    pub threads: u32,
+    /// 活跃页占用的字节数
+    pub active_bytes: u64,
+    /// 已释放但尚未归还系统的脏页字节数
+    pub dirty_bytes: u64,
+    pub resident_bytes: u64,
+}
+
+/// 分配器统计
+#[derive(Debug, Clone, Serialize)]
+pub struct AllocatorStats {
+    /// 应用实际分配的字节数
+    pub allocated_bytes: u64,
+    /// 活跃页的字节数，不小于 `allocated_bytes`
+    pub active_bytes: u64,
+    /// 物理内存中的字节数，含元数据和脏页
+    pub resident_bytes: u64,
+    pub mapped_bytes: u64,
+    /// 已取消映射但保留虚拟地址的字节数
+    pub retained_bytes: u64,
+    pub metadata_bytes: u64,
+    /// 活跃页中未被分配使用的比例：`1 - allocated / active`
+    pub fragmentation: f64,
+    pub arenas: Vec<ArenaStats>,
+}
+
+/// 读取当前的分配器统计
+pub fn allocator_stats() -> Result<AllocatorStats> {
+    epoch::advance().map_err(ctl_error)?;
+    let allocated_bytes = stats::allocated::read().map_err(ctl_error)? as u64;
+    let active_bytes = stats::active::read().map_err(ctl_error)? as u64;
+    let page_size = unsafe { raw::read::<usize>(b"arenas.page\0") }.map_err(ctl_error)? as u64;
+
+    // 未初始化的arena没有统计，直接跳过
+    let arenas = (0..arenas::narenas::read().map_err(ctl_error)?)
+        .filter_map(|index| arena_stats(index, page_size))
+        .collect();
+
+    Ok(AllocatorStats {
+        allocated_bytes,
+        active_bytes,
+        resident_bytes: stats::resident::read().map_err(ctl_error)? as u64,
+        mapped_bytes: stats::mapped::read().map_err(ctl_error)? as u64,
+        retained_bytes: stats::retained::read().map_err(ctl_error)? as u64,
+        metadata_bytes: stats::metadata::read().map_err(ctl_error)? as u64,
+        fragmentation: fragmentation(allocated_bytes, active_bytes),
+        arenas,
+    })
+}
+
+/// 活跃页中未被分配使用的比例
+pub fn fragmentation(allocated_bytes: u64, active_bytes: u64) -> f64 {
+    if active_bytes == 0 {
+        return 0.0;
+    }
+    (1.0 - allocated_bytes as f64 / active_bytes as f64).max(0.0)
+}
+
+fn arena_stats(index: u32, page_size: u64) -> Option<ArenaStats> {
+    let read = |field: &str| -> Option<usize> {
+        let name = format!("stats.arenas.{}.{}\0", index, field);
+        unsafe { raw::read::<usize>(name.as_bytes()) }.ok()
+    };
+    let threads = unsafe { raw::read::<u32>(format!("stats.arenas.{}.nthreads\0", index).as_bytes()) }.ok()?;
+    Some(ArenaStats {
+        index,
+        threads,
+        active_bytes: read("pactive")? as u64 * page_size,
+        dirty_bytes: read("pdirty")? as u64 * page_size,
+        resident_bytes: read("resident")? as u64,
+    })
+}
+
+fn ctl_error(e: tikv_jemalloc_ctl::Error) -> UniModelError {
+    UniModelError::internal(format!("Failed to read allocator statistics: {}", e))
+}
+
+/// 把统计写入指标
+pub fn publish_allocator_metrics(stats: &AllocatorStats) {
+    metrics::gauge!("unimodel_allocator_allocated_bytes", stats.allocated_bytes as f64);
+    metrics::gauge!("unimodel_allocator_active_bytes", stats.active_bytes as f64);
+    metrics::gauge!("unimodel_allocator_resident_bytes", stats.resident_bytes as f64);
+    metrics::gauge!("unimodel_allocator_mapped_bytes", stats.mapped_bytes as f64);
+    metrics::gauge!("unimodel_allocator_retained_bytes", stats.retained_bytes as f64);
+    metrics::gauge!("unimodel_allocator_metadata_bytes", stats.metadata_bytes as f64);
+    metrics::gauge!("unimodel_allocator_fragmentation", stats.fragmentation);
+    for arena in &stats.arenas {
+        let label = arena.index.to_string();
+        metrics::gauge!("unimodel_allocator_arena_threads", arena.threads as f64, "arena" => label.clone());
+        metrics::gauge!("unimodel_allocator_arena_active_bytes", arena.active_bytes as f64, "arena" => label.clone());
+        metrics::gauge!("unimodel_allocator_arena_dirty_bytes", arena.dirty_bytes as f64, "arena" => label.clone());
+        metrics::gauge!("unimodel_allocator_arena_resident_bytes", arena.resident_bytes as f64, "arena" => label);
+    }
+}
+
+/// 启动定期上报分配器指标的任务
+pub fn start_allocator_metrics_task(interval: Duration) {
+    tokio::spawn(async move {
+        info!("Allocator metrics task started (every {:?})", interval);
+        let mut ticker = tokio::time::interval(interval);
+        let mut warned = false;
+        loop {
+            ticker.tick().await;
+            match allocator_stats() {
+                Ok(stats) => publish_allocator_metrics(&stats),
+                // 统计不可用时每个周期都会失败，只警告一次
+                Err(e) if !warned => {
+                    warn!("{}", e);
+                    warned = true;
+                }
+                Err(_) => {}
+            }
+        }
+    });
+}
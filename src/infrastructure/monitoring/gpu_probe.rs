@@ -0,0 +1,193 @@
+//! GPU状态采集
+//!
+//! 通过 `nvidia-smi`（CUDA）或 `rocm-smi`（ROCm）查询各GPU的利用率与显存，未安装驱动或
+//! 查询失败时返回错误。NPU的管理工具没有稳定的机器可读输出，不做探测。
+//! 模型的 `device_type` 为 `Auto` 时，按 CUDA → ROCm → Metal → CPU 的顺序探测可用的设备。
+
+use tokio::process::Command;
+use tracing::debug;
+
+use crate::common::error::*;
+use crate::common::types::GpuUsage;
+use crate::domain::model::{DeviceSelection, DeviceType};
+
+/// nvidia-smi 查询字段
+const QUERY_FIELDS: &str = "index,utilization.gpu,memory.used,memory.total,temperature.gpu,power.draw";
+
+/// 查询指定类型的所有加速器的当前状态
+pub async fn query_devices(device_type: &DeviceType) -> Result<Vec<GpuUsage>> {
+    match device_type {
+        DeviceType::CUDA => query_gpus().await,
+        DeviceType::ROCm => query_rocm_gpus().await,
+        other => Err(UniModelError::resource(format!("Probing {:?} devices is not supported", other))),
+    }
+}
+
+/// 查询所有GPU的当前状态
+pub async fn query_gpus() -> Result<Vec<GpuUsage>> {
+    let output = Command::new("nvidia-smi")
+        .arg(format!("--query-gpu={}", QUERY_FIELDS))
+        .arg("--format=csv,noheader,nounits")
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(UniModelError::resource(format!(
+            "nvidia-smi failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_gpu_line)
+        .collect()
+}
+
+/// 解析一行 nvidia-smi CSV 输出
+fn parse_gpu_line(line: &str) -> Result<GpuUsage> {
+    let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+    if fields.len() < 4 {
+        return Err(UniModelError::resource(format!("Unexpected nvidia-smi output: {}", line)));
+    }
+
+    let parse_u64 = |s: &str| {
+        s.parse::<u64>()
+            .map_err(|_| UniModelError::resource(format!("Unexpected nvidia-smi value: {}", s)))
+    };
+    // 不支持的字段显示为 "[N/A]"
+    let parse_optional = |s: Option<&&str>| s.and_then(|v| v.parse::<f32>().ok());
+
+    Ok(GpuUsage {
+        device_id: parse_u64(fields[0])? as u32,
+        utilization: parse_optional(fields.get(1)).unwrap_or(0.0) / 100.0,
+        memory_used_bytes: parse_u64(fields[2])? * 1024 * 1024,
+        memory_total_bytes: parse_u64(fields[3])? * 1024 * 1024,
+        temperature_celsius: parse_optional(fields.get(4)),
+        power_usage_watts: parse_optional(fields.get(5)),
+    })
+}
+
+/// 查询所有ROCm GPU的当前状态
+pub async fn query_rocm_gpus() -> Result<Vec<GpuUsage>> {
+    let output = Command::new("rocm-smi")
+        .args(["--showuse", "--showmeminfo", "vram", "--csv"])
+        .output()
+        .await?;
+
+    if !output.status.success() {
+        return Err(UniModelError::resource(format!(
+            "rocm-smi failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    parse_rocm_smi_csv(&String::from_utf8_lossy(&output.stdout))
+}
+
+/// 解析 rocm-smi 的CSV输出：首行为列名，每行一张卡，设备列为 `cardN`，显存单位为字节
+pub fn parse_rocm_smi_csv(output: &str) -> Result<Vec<GpuUsage>> {
+    let mut lines = output.lines().map(str::trim).filter(|line| !line.is_empty());
+    let header: Vec<&str> = lines
+        .next()
+        .ok_or_else(|| UniModelError::resource("Empty rocm-smi output"))?
+        .split(',')
+        .map(str::trim)
+        .collect();
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|h| h.starts_with(name))
+            .ok_or_else(|| UniModelError::resource(format!("rocm-smi output has no '{}' column", name)))
+    };
+    let (use_col, total_col, used_col) = (column("GPU use")?, column("VRAM Total Memory")?, column("VRAM Total Used")?);
+
+    lines
+        .map(|line| {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |index: usize| {
+                fields
+                    .get(index)
+                    .copied()
+                    .ok_or_else(|| UniModelError::resource(format!("Unexpected rocm-smi output: {}", line)))
+            };
+            let parse_u64 = |s: &str| {
+                s.parse::<u64>()
+                    .map_err(|_| UniModelError::resource(format!("Unexpected rocm-smi value: {}", s)))
+            };
+            Ok(GpuUsage {
+                device_id: parse_u64(field(0)?.trim_start_matches("card"))? as u32,
+                utilization: field(use_col)?.parse::<f32>().unwrap_or(0.0) / 100.0,
+                memory_used_bytes: parse_u64(field(used_col)?)?,
+                memory_total_bytes: parse_u64(field(total_col)?)?,
+                temperature_celsius: None,
+                power_usage_watts: None,
+            })
+        })
+        .collect()
+}
+
+/// 本机可用的设备
+#[derive(Debug, Clone, Default)]
+pub struct DeviceProbe {
+    /// 探测到的CUDA GPU
+    pub cuda_devices: Vec<u32>,
+    /// CUDA探测失败的原因
+    pub cuda_error: Option<String>,
+    /// 探测到的ROCm GPU
+    pub rocm_devices: Vec<u32>,
+    /// 能否使用Metal（macOS）
+    pub metal: bool,
+}
+
+impl DeviceProbe {
+    /// 探测本机设备，探测失败的设备视为不可用
+    pub async fn detect() -> Self {
+        let (cuda_devices, cuda_error) = match query_gpus().await {
+            Ok(gpus) => (gpus.iter().map(|gpu| gpu.device_id).collect(), None),
+            Err(e) => {
+                debug!("CUDA probing failed: {}", e);
+                (Vec::new(), Some(e.to_string()))
+            }
+        };
+        // 只有没有CUDA GPU时才需要知道ROCm GPU
+        let rocm_devices = if cuda_devices.is_empty() {
+            query_rocm_gpus().await.map_or_else(|_| Vec::new(), |gpus| gpus.iter().map(|gpu| gpu.device_id).collect())
+        } else {
+            Vec::new()
+        };
+        Self {
+            cuda_devices,
+            cuda_error,
+            rocm_devices,
+            metal: cfg!(target_os = "macos"),
+        }
+    }
+
+    /// 按 CUDA → ROCm → Metal → CPU 的顺序选择第一个可用且后端支持的设备；
+    /// `supported` 为后端声明的设备，为空时不限制。都不满足时退回CPU。
+    pub fn select(&self, supported: &[DeviceType]) -> DeviceSelection {
+        let allowed = |device: &DeviceType| supported.is_empty() || supported.contains(device);
+        let cuda = match &self.cuda_error {
+            Some(e) => format!("CUDA unavailable ({})", e),
+            None if self.cuda_devices.is_empty() => "no CUDA GPU found".to_string(),
+            None => format!("CUDA GPUs {:?}", self.cuda_devices),
+        };
+
+        let (device_type, reason) = if !self.cuda_devices.is_empty() && allowed(&DeviceType::CUDA) {
+            (DeviceType::CUDA, format!("{} detected", cuda))
+        } else if !self.rocm_devices.is_empty() && allowed(&DeviceType::ROCm) {
+            (DeviceType::ROCm, format!("{}, ROCm GPUs {:?} detected", cuda, self.rocm_devices))
+        } else if self.metal && allowed(&DeviceType::Metal) {
+            (DeviceType::Metal, format!("{}, Metal available", cuda))
+        } else {
+            let metal = if self.metal { "Metal not supported by the backend" } else { "Metal unavailable" };
+            (DeviceType::CPU, format!("{}, {}, falling back to CPU", cuda, metal))
+        };
+        DeviceSelection {
+            requested: DeviceType::Auto,
+            device_type,
+            reason,
+        }
+    }
+}
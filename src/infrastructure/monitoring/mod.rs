@@ -0,0 +1,13 @@
+//! 监控基础设施
+
+pub mod allocator;
+pub mod gpu_probe;
+pub mod health_check;
+pub mod profiler;
+pub mod prometheus;
+pub mod runtime_metrics;
+pub mod tracing;
+
+pub use allocator::start_allocator_metrics_task;
+pub use gpu_probe::*;
+pub use runtime_metrics::start_runtime_metrics_task;
@@ -0,0 +1,89 @@
+//! 按需CPU性能分析
+//!
+//! 以固定频率对所有线程采样调用栈，结束后输出pprof格式（`go tool pprof` 等工具可直接读取）
+//! 或火焰图SVG。同一时间只允许一次采样。
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use pprof::protos::Message;
+use tracing::info;
+
+use crate::common::error::*;
+
+/// 采样频率（Hz），避开与定时任务同频
+pub const SAMPLE_FREQUENCY: i32 = 99;
+
+/// 单次采样的最长时间
+pub const MAX_PROFILE_DURATION: Duration = Duration::from_secs(300);
+
+/// 采样时跳过的库，在这些库中被中断时回溯不可靠
+const BLOCKLIST: &[&str] = &["libc", "libgcc", "pthread", "vdso"];
+
+/// 是否有正在进行的采样
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+/// 性能分析的输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProfileFormat {
+    /// 未压缩的pprof protobuf
+    Pprof,
+    /// 火焰图SVG
+    Flamegraph,
+}
+
+impl ProfileFormat {
+    /// 响应的Content-Type
+    pub fn content_type(self) -> &'static str {
+        match self {
+            ProfileFormat::Pprof => "application/octet-stream",
+            ProfileFormat::Flamegraph => "image/svg+xml",
+        }
+    }
+}
+
+/// 采样指定时长的CPU调用栈并按格式编码
+pub async fn capture_cpu_profile(duration: Duration, format: ProfileFormat) -> Result<Vec<u8>> {
+    if duration.is_zero() || duration > MAX_PROFILE_DURATION {
+        return Err(UniModelError::validation(format!(
+            "Profile duration must be between 1 and {} seconds",
+            MAX_PROFILE_DURATION.as_secs()
+        )));
+    }
+    if PROFILING.swap(true, Ordering::AcqRel) {
+        return Err(UniModelError::resource("A CPU profile is already being captured"));
+    }
+
+    info!("Capturing a {:?} CPU profile", duration);
+    // 采样器不能跨await持有，整个采样在阻塞线程中完成
+    let profile = tokio::task::spawn_blocking(move || profile_blocking(duration, format)).await;
+    PROFILING.store(false, Ordering::Release);
+    profile.map_err(|e| UniModelError::internal(format!("Profiling task failed: {}", e)))?
+}
+
+fn profile_blocking(duration: Duration, format: ProfileFormat) -> Result<Vec<u8>> {
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(SAMPLE_FREQUENCY)
+        .blocklist(BLOCKLIST)
+        .build()
+        .map_err(|e| UniModelError::internal(format!("Failed to start profiler: {}", e)))?;
+    std::thread::sleep(duration);
+    let report = guard.report()
+        .build()
+        .map_err(|e| UniModelError::internal(format!("Failed to build profile: {}", e)))?;
+
+    let mut body = Vec::new();
+    match format {
+        ProfileFormat::Pprof => {
+            let profile = report.pprof()
+                .map_err(|e| UniModelError::internal(format!("Failed to encode profile: {}", e)))?;
+            profile.encode(&mut body)
+                .map_err(|e| UniModelError::internal(format!("Failed to encode profile: {}", e)))?;
+        }
+        ProfileFormat::Flamegraph => {
+            report.flamegraph(&mut body)
+                .map_err(|e| UniModelError::internal(format!("Failed to render flamegraph: {}", e)))?;
+        }
+    }
+    Ok(body)
+}
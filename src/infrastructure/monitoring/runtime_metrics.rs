@@ -0,0 +1,110 @@
+//! 异步运行时指标
+//!
+//! 定期采样tokio运行时并写入Prometheus：存活任务数、全局队列和阻塞线程池队列深度、
+//! 每个工作线程的忙碌比例和平均轮询耗时，以及阻塞的工作线程数。一个工作线程在采样间隔内
+//! 几乎一直忙碌却没有完成任何一次轮询，说明某个任务在轮询中执行了阻塞操作，记为阻塞。
+//!
+//! 这些指标依赖tokio的不稳定API，只在以 `RUSTFLAGS="--cfg tokio_unstable"` 构建时可用。
+
+use std::time::Duration;
+
+use tracing::warn;
+
+/// 工作线程在采样间隔内的忙碌比例达到该值且没有完成轮询时视为阻塞
+pub const BLOCKED_BUSY_RATIO: f64 = 0.9;
+
+/// 按最近一次采样的增量判断工作线程是否阻塞
+pub fn is_worker_blocked(polls: u64, busy: Duration, interval: Duration) -> bool {
+    polls == 0 && busy_ratio(busy, interval) >= BLOCKED_BUSY_RATIO
+}
+
+/// 忙碌时间占采样间隔的比例
+pub fn busy_ratio(busy: Duration, interval: Duration) -> f64 {
+    if interval.is_zero() {
+        return 0.0;
+    }
+    (busy.as_secs_f64() / interval.as_secs_f64()).min(1.0)
+}
+
+/// 启动定期采样运行时指标的任务；未以 `tokio_unstable` 构建时只记录警告
+pub fn start_runtime_metrics_task(interval: Duration) {
+    #[cfg(tokio_unstable)]
+    {
+        let handle = tokio::runtime::Handle::current();
+        tokio::spawn(async move {
+            tracing::info!("Runtime metrics task started (every {:?})", interval);
+            let mut sampler = RuntimeSampler::new(handle.metrics());
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                sampler.publish();
+            }
+        });
+    }
+    #[cfg(not(tokio_unstable))]
+    {
+        let _ = interval;
+        warn!("Runtime metrics need a build with RUSTFLAGS=\"--cfg tokio_unstable\", not collecting them");
+    }
+}
+
+/// 保存上一次的累计值，按增量计算每个间隔的指标
+#[cfg(tokio_unstable)]
+struct RuntimeSampler {
+    metrics: tokio::runtime::RuntimeMetrics,
+    sampled_at: std::time::Instant,
+    /// 每个工作线程的累计轮询次数和忙碌时间
+    workers: Vec<(u64, Duration)>,
+}
+
+#[cfg(tokio_unstable)]
+impl RuntimeSampler {
+    fn new(metrics: tokio::runtime::RuntimeMetrics) -> Self {
+        let workers = (0..metrics.num_workers())
+            .map(|worker| (metrics.worker_poll_count(worker), metrics.worker_total_busy_duration(worker)))
+            .collect();
+        Self {
+            metrics,
+            sampled_at: std::time::Instant::now(),
+            workers,
+        }
+    }
+
+    fn publish(&mut self) {
+        let metrics = &self.metrics;
+        let elapsed = self.sampled_at.elapsed();
+        self.sampled_at = std::time::Instant::now();
+
+        metrics::gauge!("unimodel_runtime_workers", metrics.num_workers() as f64);
+        metrics::gauge!("unimodel_runtime_alive_tasks", metrics.num_alive_tasks() as f64);
+        metrics::gauge!("unimodel_runtime_global_queue_depth", metrics.global_queue_depth() as f64);
+        metrics::gauge!("unimodel_runtime_blocking_threads", metrics.num_blocking_threads() as f64);
+        metrics::gauge!("unimodel_runtime_idle_blocking_threads", metrics.num_idle_blocking_threads() as f64);
+        metrics::gauge!("unimodel_runtime_blocking_queue_depth", metrics.blocking_queue_depth() as f64);
+
+        let mut blocked = 0;
+        for (worker, previous) in self.workers.iter_mut().enumerate() {
+            let polls = metrics.worker_poll_count(worker);
+            let busy = metrics.worker_total_busy_duration(worker);
+            let (poll_delta, busy_delta) = (polls.saturating_sub(previous.0), busy.saturating_sub(previous.1));
+            *previous = (polls, busy);
+
+            let label = worker.to_string();
+            metrics::counter!("unimodel_runtime_polls_total", poll_delta, "worker" => label.clone());
+            metrics::gauge!("unimodel_runtime_worker_busy_ratio", busy_ratio(busy_delta, elapsed), "worker" => label.clone());
+            metrics::gauge!(
+                "unimodel_runtime_worker_mean_poll_seconds",
+                metrics.worker_mean_poll_time(worker).as_secs_f64(),
+                "worker" => label.clone()
+            );
+            metrics::gauge!("unimodel_runtime_worker_local_queue_depth", metrics.worker_local_queue_depth(worker) as f64, "worker" => label);
+            if is_worker_blocked(poll_delta, busy_delta, elapsed) {
+                blocked += 1;
+            }
+        }
+        metrics::gauge!("unimodel_runtime_blocked_workers", blocked as f64);
+        if blocked > 0 {
+            warn!("{} runtime worker(s) were blocked for the last {:?}", blocked, elapsed);
+        }
+    }
+}
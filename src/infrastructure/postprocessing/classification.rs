@@ -0,0 +1,70 @@
+//! 分类输出的top-k
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::common::types::Tensor;
+use crate::domain::model::{ClassificationPostprocessing, LabelMap};
+
+/// 单个类别的分类结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Classification {
+    pub label: String,
+    pub class_id: usize,
+    pub score: f32,
+}
+
+/// 取分数最高的 `top_k` 个类别，按分数降序排列
+pub fn top_k_classes(
+    tensors: &HashMap<String, Tensor>,
+    config: &ClassificationPostprocessing,
+    top_k: usize,
+    labels: Option<&LabelMap>,
+) -> Result<Vec<Classification>> {
+    let scores = match &config.scores_output {
+        Some(name) => tensors.get(name)
+            .ok_or_else(|| UniModelError::model(format!("Classification model has no output '{}'", name)))?,
+        None => match (tensors.values().next(), tensors.len()) {
+            (Some(scores), 1) => scores,
+            _ => return Err(UniModelError::config(
+                "Classification model must have one output or set scores_output in its post-processing config",
+            )),
+        },
+    };
+    if scores.shape.iter().rev().skip(1).any(|&dim| dim != 1) {
+        return Err(UniModelError::model(format!(
+            "Classification scores have shape {:?}; only a batch of one input is supported",
+            scores.shape
+        )));
+    }
+
+    let mut scores = scores.to_f32_vec()?;
+    if config.softmax {
+        softmax(&mut scores);
+    }
+    let mut ranked: Vec<(usize, f32)> = scores.into_iter().enumerate().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked.truncate(top_k);
+
+    Ok(ranked.into_iter()
+        .map(|(class_id, score)| Classification {
+            label: labels.map_or_else(|| class_id.to_string(), |labels| labels.label(class_id)),
+            class_id,
+            score,
+        })
+        .collect())
+}
+
+fn softmax(values: &mut [f32]) {
+    let max = values.iter().copied().fold(f32::NEG_INFINITY, f32::max);
+    let mut sum = 0.0;
+    for value in values.iter_mut() {
+        *value = (*value - max).exp();
+        sum += *value;
+    }
+    if sum > 0.0 {
+        values.iter_mut().for_each(|value| *value /= sum);
+    }
+}
@@ -0,0 +1,177 @@
+//! 检测模型输出
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::common::types::{OutputData, Tensor};
+use crate::domain::model::{DetectionPostprocessing, LabelMap};
+
+/// 像素坐标的轴对齐边界框
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub struct BoundingBox {
+    pub x_min: f32,
+    pub y_min: f32,
+    pub x_max: f32,
+    pub y_max: f32,
+}
+
+impl BoundingBox {
+    /// 由 `[x_min, y_min, x_max, y_max]` 创建，坐标顺序颠倒时自动交换
+    pub fn from_corners([x1, y1, x2, y2]: [f32; 4]) -> Self {
+        Self {
+            x_min: x1.min(x2),
+            y_min: y1.min(y2),
+            x_max: x1.max(x2),
+            y_max: y1.max(y2),
+        }
+    }
+
+    pub fn width(&self) -> f32 {
+        (self.x_max - self.x_min).max(0.0)
+    }
+
+    pub fn height(&self) -> f32 {
+        (self.y_max - self.y_min).max(0.0)
+    }
+
+    pub fn area(&self) -> f32 {
+        self.width() * self.height()
+    }
+
+    /// 交并比
+    pub fn iou(&self, other: &BoundingBox) -> f32 {
+        let intersection = BoundingBox {
+            x_min: self.x_min.max(other.x_min),
+            y_min: self.y_min.max(other.y_min),
+            x_max: self.x_max.min(other.x_max),
+            y_max: self.y_max.min(other.y_max),
+        }
+        .area();
+        let union = self.area() + other.area() - intersection;
+        if union > 0.0 { intersection / union } else { 0.0 }
+    }
+}
+
+/// 带类别的检测结果
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Detection {
+    #[serde(rename = "box")]
+    pub bbox: BoundingBox,
+    pub label: String,
+    pub class_id: usize,
+    pub score: f32,
+}
+
+/// 检测到的区域
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct DetectedRegion {
+    #[serde(rename = "box")]
+    pub bbox: BoundingBox,
+    pub score: f32,
+}
+
+#[derive(Deserialize)]
+struct RawRegion {
+    #[serde(rename = "box")]
+    bbox: [f32; 4],
+    #[serde(default = "default_score")]
+    score: f32,
+}
+
+fn default_score() -> f32 {
+    1.0
+}
+
+/// 解析检测模型的JSON输出
+///
+/// 接受区域数组或 `{"regions": [...]}`，每个区域为 `{"box": [x_min, y_min, x_max, y_max], "score": ...}`。
+pub fn parse_regions(output: &OutputData) -> Result<Vec<DetectedRegion>> {
+    let json = match output {
+        OutputData::Json(json) => json,
+        _ => return Err(UniModelError::model("Detection model must return JSON regions")),
+    };
+    let regions = json.get("regions").unwrap_or(json);
+    let raw: Vec<RawRegion> = serde_json::from_value(regions.clone())
+        .map_err(|e| UniModelError::model(format!("Invalid detection output: {}", e)))?;
+
+    Ok(raw.into_iter()
+        .map(|region| DetectedRegion {
+            bbox: BoundingBox::from_corners(region.bbox),
+            score: region.score,
+        })
+        .collect())
+}
+
+/// 把检测模型的原始张量输出转换为按分数降序排列的检测结果
+///
+/// 依次执行分数阈值过滤、非极大值抑制（默认按类别分别进行）和标签映射。
+pub fn postprocess_detections(
+    tensors: &HashMap<String, Tensor>,
+    config: &DetectionPostprocessing,
+    labels: Option<&LabelMap>,
+) -> Result<Vec<Detection>> {
+    // 只接受单张图像的输出：除最后一维外各维（即批次维）大小须为1
+    let output = |name: &str, per_detection: usize| {
+        let tensor = tensors.get(name)
+            .ok_or_else(|| UniModelError::model(format!("Detection model has no output '{}'", name)))?;
+        let rank = tensor.shape.len();
+        let batch_dims = rank.saturating_sub(if per_detection > 1 { 2 } else { 1 });
+        if tensor.shape[..batch_dims].iter().any(|&dim| dim != 1) {
+            return Err(UniModelError::model(format!(
+                "Detection output '{}' has shape {:?}; only a batch of one image is supported",
+                name, tensor.shape
+            )));
+        }
+        tensor.to_f32_vec()
+    };
+    let boxes = output(&config.boxes_output, 4)?;
+    let scores = output(&config.scores_output, 1)?;
+    let classes = output(&config.classes_output, 1)?;
+    if boxes.len() != scores.len() * 4 || classes.len() != scores.len() {
+        return Err(UniModelError::model(format!(
+            "Detection outputs disagree: {} box values, {} scores, {} classes",
+            boxes.len(), scores.len(), classes.len()
+        )));
+    }
+
+    let mut candidates: Vec<Detection> = scores.iter()
+        .enumerate()
+        .filter(|(_, &score)| score >= config.score_threshold)
+        .map(|(i, &score)| {
+            let class_id = classes[i].max(0.0) as usize;
+            Detection {
+                bbox: BoundingBox::from_corners([boxes[i * 4], boxes[i * 4 + 1], boxes[i * 4 + 2], boxes[i * 4 + 3]]),
+                label: labels.map_or_else(|| class_id.to_string(), |labels| labels.label(class_id)),
+                class_id,
+                score,
+            }
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+
+    Ok(non_max_suppression(candidates, config.iou_threshold, config.class_agnostic, config.max_detections))
+}
+
+/// 贪心非极大值抑制，输入需按分数降序排列
+pub fn non_max_suppression(
+    candidates: Vec<Detection>,
+    iou_threshold: f32,
+    class_agnostic: bool,
+    max_detections: usize,
+) -> Vec<Detection> {
+    let mut kept: Vec<Detection> = Vec::new();
+    for candidate in candidates {
+        if kept.len() >= max_detections {
+            break;
+        }
+        let suppressed = kept.iter().any(|kept| {
+            (class_agnostic || kept.class_id == candidate.class_id) && kept.bbox.iou(&candidate.bbox) > iou_threshold
+        });
+        if !suppressed {
+            kept.push(candidate);
+        }
+    }
+    kept
+}
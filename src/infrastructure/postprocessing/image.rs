@@ -0,0 +1,104 @@
+//! 生成图像的输出转换
+
+use std::io::Cursor;
+
+use image::{ImageOutputFormat, RgbImage, RgbaImage};
+
+use crate::common::error::*;
+use crate::common::types::{DataType, OutputData, Tensor};
+use crate::infrastructure::postprocessing::BoundingBox;
+
+/// 从生成模型的输出中取出编码后的图像
+///
+/// 支持三种输出：已编码的二进制图像、以图像序号为键的多模态二进制输出，
+/// 以及形状为 `[N, H, W, C]` 或 `[H, W, C]` 的uint8张量（C为3或4，编码为PNG）。
+pub fn output_images(output: OutputData) -> Result<Vec<Vec<u8>>> {
+    match output {
+        OutputData::Binary(image) => Ok(vec![image.to_vec()]),
+        OutputData::Multimodal(parts) => {
+            let mut parts: Vec<_> = parts.into_iter().collect();
+            parts.sort_by(|(a, _), (b, _)| natural_key(a).cmp(&natural_key(b)));
+            parts.into_iter()
+                .map(|(_, part)| match part {
+                    OutputData::Binary(image) => Ok(image.to_vec()),
+                    _ => Err(UniModelError::model("Image generation output parts must be binary images")),
+                })
+                .collect()
+        }
+        OutputData::Tensors(tensors) => {
+            let mut tensors = tensors.into_values();
+            match (tensors.next(), tensors.next()) {
+                (Some(tensor), None) => tensor_images(&tensor),
+                _ => Err(UniModelError::model("Image generation must return a single image tensor")),
+            }
+        }
+        _ => Err(UniModelError::model("Model did not return images")),
+    }
+}
+
+/// 按 `[N, H, W, C]` 拆分uint8张量并逐张编码为PNG
+fn tensor_images(tensor: &Tensor) -> Result<Vec<Vec<u8>>> {
+    tensor.validate()?;
+    if tensor.dtype != DataType::U8 {
+        return Err(UniModelError::model(format!("Image tensors must be uint8, got {:?}", tensor.dtype)));
+    }
+    let (count, height, width, channels) = match tensor.shape.as_slice() {
+        &[count, height, width, channels] => (count, height, width, channels),
+        &[height, width, channels] => (1, height, width, channels),
+        shape => return Err(UniModelError::model(format!("Unsupported image tensor shape {:?}", shape))),
+    };
+
+    let image_bytes = height * width * channels;
+    (0..count)
+        .map(|i| encode_png(&tensor.data[i * image_bytes..(i + 1) * image_bytes], width, height, channels))
+        .collect()
+}
+
+/// 把HWC排布的RGB/RGBA像素编码为PNG
+pub fn encode_png(pixels: &[u8], width: usize, height: usize, channels: usize) -> Result<Vec<u8>> {
+    let invalid = || UniModelError::model("Image pixel data does not match its dimensions");
+    let image = match channels {
+        3 => image::DynamicImage::ImageRgb8(
+            RgbImage::from_raw(width as u32, height as u32, pixels.to_vec()).ok_or_else(invalid)?,
+        ),
+        4 => image::DynamicImage::ImageRgba8(
+            RgbaImage::from_raw(width as u32, height as u32, pixels.to_vec()).ok_or_else(invalid)?,
+        ),
+        other => return Err(UniModelError::model(format!("Unsupported image channel count {}", other))),
+    };
+
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+        .map_err(|e| UniModelError::internal(format!("Failed to encode PNG: {}", e)))?;
+    Ok(png)
+}
+
+/// 解码图像并按边界框裁剪，每个区域编码为PNG
+///
+/// 边界框被限制在图像范围内；完全落在图像外的区域返回错误。
+pub fn crop_regions(bytes: &[u8], boxes: &[BoundingBox]) -> Result<Vec<Vec<u8>>> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| UniModelError::validation(format!("Failed to decode image: {}", e)))?
+        .to_rgb8();
+    let (width, height) = image.dimensions();
+
+    boxes.iter()
+        .map(|bbox| {
+            let x = (bbox.x_min.max(0.0) as u32).min(width);
+            let y = (bbox.y_min.max(0.0) as u32).min(height);
+            let x_max = (bbox.x_max.ceil().max(0.0) as u32).min(width);
+            let y_max = (bbox.y_max.ceil().max(0.0) as u32).min(height);
+            if x_max <= x || y_max <= y {
+                return Err(UniModelError::model(format!("Region {:?} lies outside the image", bbox)));
+            }
+
+            let region = image::imageops::crop_imm(&image, x, y, x_max - x, y_max - y).to_image();
+            encode_png(region.as_raw(), region.width() as usize, region.height() as usize, 3)
+        })
+        .collect()
+}
+
+/// 数字键按数值排序（"2" 在 "10" 之前），其余按字典序
+fn natural_key(key: &str) -> (u64, &str) {
+    (key.parse().unwrap_or(u64::MAX), key)
+}
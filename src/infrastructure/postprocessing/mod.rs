@@ -0,0 +1,66 @@
+//! 模型输出后处理
+
+pub mod classification;
+pub mod detection;
+pub mod image;
+pub mod segmentation;
+
+use crate::common::error::*;
+use crate::common::types::{OutputData, PredictionParameters};
+use crate::domain::model::{LabelMap, PostprocessingConfig};
+
+pub use self::classification::{top_k_classes, Classification};
+pub use self::detection::{
+    non_max_suppression, parse_regions, postprocess_detections, BoundingBox, DetectedRegion, Detection,
+};
+pub use self::image::{crop_regions, encode_png, output_images};
+pub use self::segmentation::{encode_segmentation, ClassMask, EncodedSegmentation, Rle, Segment};
+
+/// 按模型的后处理配置转换输出；多模态输出（如按帧拆分的视频输出）逐项处理
+///
+/// 各阶段的结果合并为一个JSON对象（`classifications`、`detections`、`segmentation`），
+/// 没有阶段产生结果时（如请求原始掩码）张量原样返回。
+pub fn postprocess(
+    output: OutputData,
+    config: &PostprocessingConfig,
+    labels: Option<&LabelMap>,
+    parameters: &PredictionParameters,
+) -> Result<OutputData> {
+    let tensors = match output {
+        OutputData::Multimodal(parts) => {
+            return Ok(OutputData::Multimodal(
+                parts.into_iter()
+                    .map(|(key, part)| Ok((key, postprocess(part, config, labels, parameters)?)))
+                    .collect::<Result<_>>()?,
+            ));
+        }
+        OutputData::Tensors(tensors) => tensors,
+        other => return Ok(other),
+    };
+
+    let mut result = serde_json::Map::new();
+    if let Some(classification) = &config.classification {
+        let top_k = parameters.top_k.map_or(classification.top_k, |k| k as usize);
+        let classes = top_k_classes(&tensors, classification, top_k, labels)?;
+        result.insert("classifications".to_string(), serde_json::to_value(classes)?);
+    }
+    if let Some(detection) = &config.detection {
+        let detections = postprocess_detections(&tensors, detection, labels)?;
+        result.insert("detections".to_string(), serde_json::to_value(detections)?);
+    }
+    if let Some(segmentation) = &config.segmentation {
+        let mask = tensors.get(&segmentation.mask_output).ok_or_else(|| {
+            UniModelError::model(format!("Segmentation model has no output '{}'", segmentation.mask_output))
+        })?;
+        let format = parameters.mask_format.unwrap_or(segmentation.default_format);
+        if let Some(encoded) = encode_segmentation(mask, segmentation, format, labels)? {
+            result.insert("segmentation".to_string(), serde_json::to_value(encoded)?);
+        }
+    }
+
+    if result.is_empty() {
+        Ok(OutputData::Tensors(tensors))
+    } else {
+        Ok(OutputData::Json(serde_json::Value::Object(result)))
+    }
+}
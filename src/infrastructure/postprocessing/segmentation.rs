@@ -0,0 +1,262 @@
+//! 分割掩码编码
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+
+use base64::Engine;
+use image::{ImageBuffer, ImageOutputFormat, Luma};
+use serde::Serialize;
+
+use crate::common::error::*;
+use crate::common::types::{DataType, MaskFormat, Tensor};
+use crate::domain::model::{LabelMap, SegmentationPostprocessing};
+
+/// 8邻域，按图像坐标系（y向下）顺时针排列，从正东开始
+const NEIGHBORS: [(i64, i64); 8] = [(1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1), (1, -1)];
+
+/// 每个像素的类别序号，行优先
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClassMask {
+    pub width: usize,
+    pub height: usize,
+    pub classes: Vec<u32>,
+}
+
+/// 编码后的分割结果
+#[derive(Debug, Clone, Serialize)]
+pub struct EncodedSegmentation {
+    pub format: MaskFormat,
+    pub width: usize,
+    pub height: usize,
+    /// PNG编码时为base64的灰度图，像素值即类别序号
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mask: Option<String>,
+    pub segments: Vec<Segment>,
+}
+
+/// 单个类别的掩码
+#[derive(Debug, Clone, Serialize)]
+pub struct Segment {
+    pub class_id: u32,
+    pub label: String,
+    /// 像素数
+    pub area: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub rle: Option<Rle>,
+    /// 每个连通区域外轮廓的顶点 `[x, y]`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub polygons: Option<Vec<Vec<[i64; 2]>>>,
+}
+
+/// COCO未压缩RLE：`size` 为 `[高, 宽]`，`counts` 从背景游程开始按列优先交替计数
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Rle {
+    pub size: [usize; 2],
+    pub counts: Vec<u32>,
+}
+
+impl ClassMask {
+    /// 从分割模型的掩码张量构造
+    pub fn from_tensor(tensor: &Tensor, threshold: f32) -> Result<Self> {
+        let values = tensor.to_f32_vec()?;
+        let mut dims = tensor.shape.as_slice();
+        while dims.len() > 3 && dims[0] == 1 {
+            dims = &dims[1..];
+        }
+        let (channels, height, width) = match *dims {
+            [height, width] => (1, height, width),
+            [channels, height, width] => (channels, height, width),
+            _ => return Err(UniModelError::model(format!("Unsupported mask tensor shape {:?}", tensor.shape))),
+        };
+
+        let pixels = height * width;
+        let classes = if channels > 1 {
+            (0..pixels)
+                .map(|p| {
+                    (0..channels)
+                        .max_by(|&a, &b| values[a * pixels + p].total_cmp(&values[b * pixels + p]))
+                        .unwrap_or(0) as u32
+                })
+                .collect()
+        } else if is_float(tensor.dtype) {
+            values.iter().map(|&v| u32::from(v >= threshold)).collect()
+        } else {
+            values.iter().map(|&v| v.max(0.0) as u32).collect()
+        };
+        Ok(Self { width, height, classes })
+    }
+
+    /// 掩码中出现的类别及其像素数
+    pub fn class_areas(&self) -> BTreeMap<u32, usize> {
+        let mut areas = BTreeMap::new();
+        for &class_id in &self.classes {
+            *areas.entry(class_id).or_insert(0) += 1;
+        }
+        areas
+    }
+
+    /// 某个类别的COCO RLE
+    pub fn rle(&self, class_id: u32) -> Rle {
+        let mut counts = Vec::new();
+        let (mut inside, mut run) = (false, 0u32);
+        for x in 0..self.width {
+            for y in 0..self.height {
+                if (self.classes[y * self.width + x] == class_id) != inside {
+                    counts.push(run);
+                    inside = !inside;
+                    run = 0;
+                }
+                run += 1;
+            }
+        }
+        counts.push(run);
+        Rle {
+            size: [self.height, self.width],
+            counts,
+        }
+    }
+
+    /// 灰度PNG，类别数不超过256时为8位，否则为16位
+    pub fn png(&self) -> Result<Vec<u8>> {
+        let (width, height) = (self.width as u32, self.height as u32);
+        let invalid = || UniModelError::internal("Mask does not match its dimensions");
+        let image = if self.classes.iter().all(|&c| c <= u8::MAX as u32) {
+            let pixels = self.classes.iter().map(|&c| c as u8).collect();
+            image::DynamicImage::ImageLuma8(ImageBuffer::<Luma<u8>, _>::from_raw(width, height, pixels).ok_or_else(invalid)?)
+        } else if self.classes.iter().all(|&c| c <= u16::MAX as u32) {
+            let pixels = self.classes.iter().map(|&c| c as u16).collect();
+            image::DynamicImage::ImageLuma16(ImageBuffer::<Luma<u16>, _>::from_raw(width, height, pixels).ok_or_else(invalid)?)
+        } else {
+            return Err(UniModelError::model("Mask class indices do not fit in a 16-bit PNG"));
+        };
+
+        let mut png = Vec::new();
+        image.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)
+            .map_err(|e| UniModelError::internal(format!("Failed to encode PNG: {}", e)))?;
+        Ok(png)
+    }
+
+    /// 某个类别各8连通区域的外轮廓（不含孔洞），共线顶点被合并
+    pub fn polygons(&self, class_id: u32) -> Vec<Vec<[i64; 2]>> {
+        let mut visited = vec![false; self.classes.len()];
+        let mut polygons = Vec::new();
+        for start in 0..self.classes.len() {
+            if visited[start] || self.classes[start] != class_id {
+                continue;
+            }
+            self.mark_component(start, class_id, &mut visited);
+            let start = ((start % self.width) as i64, (start / self.width) as i64);
+            polygons.push(simplify(self.trace(start, class_id)));
+        }
+        polygons
+    }
+
+    fn contains(&self, (x, y): (i64, i64), class_id: u32) -> bool {
+        x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height
+            && self.classes[y as usize * self.width + x as usize] == class_id
+    }
+
+    fn mark_component(&self, start: usize, class_id: u32, visited: &mut [bool]) {
+        let mut stack = vec![start];
+        visited[start] = true;
+        while let Some(index) = stack.pop() {
+            let (x, y) = ((index % self.width) as i64, (index / self.width) as i64);
+            for (dx, dy) in NEIGHBORS {
+                let neighbor = (x + dx, y + dy);
+                if self.contains(neighbor, class_id) {
+                    let index = neighbor.1 as usize * self.width + neighbor.0 as usize;
+                    if !visited[index] {
+                        visited[index] = true;
+                        stack.push(index);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Moore邻域轮廓跟踪；起点为区域按行扫描的第一个像素，其西侧必为背景
+    fn trace(&self, start: (i64, i64), class_id: u32) -> Vec<(i64, i64)> {
+        let mut contour = vec![start];
+        let (mut current, mut backtrack) = (start, 4);
+        // 轮廓长度不会超过区域像素数的4倍，防止异常输入下死循环
+        for _ in 0..self.classes.len() * 4 {
+            let k = match (0..8)
+                .map(|i| (backtrack + i) % 8)
+                .find(|&k| self.contains((current.0 + NEIGHBORS[k].0, current.1 + NEIGHBORS[k].1), class_id))
+            {
+                Some(k) => k,
+                None => break,
+            };
+            let next = (current.0 + NEIGHBORS[k].0, current.1 + NEIGHBORS[k].1);
+            if current == start && contour.len() > 1 && contour[1] == next {
+                contour.pop();
+                break;
+            }
+            let previous = (current.0 + NEIGHBORS[(k + 7) % 8].0, current.1 + NEIGHBORS[(k + 7) % 8].1);
+            backtrack = NEIGHBORS.iter()
+                .position(|&(dx, dy)| (next.0 + dx, next.1 + dy) == previous)
+                .unwrap_or(4);
+            current = next;
+            contour.push(current);
+        }
+        contour
+    }
+}
+
+/// 按模型配置和请求指定的编码输出分割掩码；`MaskFormat::Raw` 返回 `None`
+pub fn encode_segmentation(
+    tensor: &Tensor,
+    config: &SegmentationPostprocessing,
+    format: MaskFormat,
+    labels: Option<&LabelMap>,
+) -> Result<Option<EncodedSegmentation>> {
+    if format == MaskFormat::Raw {
+        return Ok(None);
+    }
+    let mask = ClassMask::from_tensor(tensor, config.threshold)?;
+    let segments = mask.class_areas()
+        .into_iter()
+        .filter(|&(class_id, _)| config.include_background || class_id != 0)
+        .map(|(class_id, area)| Segment {
+            class_id,
+            label: labels.map_or_else(|| class_id.to_string(), |labels| labels.label(class_id as usize)),
+            area,
+            rle: (format == MaskFormat::Rle).then(|| mask.rle(class_id)),
+            polygons: (format == MaskFormat::Polygon).then(|| mask.polygons(class_id)),
+        })
+        .collect();
+    let png = match format {
+        MaskFormat::Png => Some(base64::engine::general_purpose::STANDARD.encode(mask.png()?)),
+        _ => None,
+    };
+
+    Ok(Some(EncodedSegmentation {
+        format,
+        width: mask.width,
+        height: mask.height,
+        mask: png,
+        segments,
+    }))
+}
+
+fn is_float(dtype: DataType) -> bool {
+    matches!(
+        dtype,
+        DataType::F16 | DataType::BF16 | DataType::F32 | DataType::F64 | DataType::F8E4M3 | DataType::F8E5M2
+    )
+}
+
+/// 合并同方向的连续步进，只保留拐点
+fn simplify(contour: Vec<(i64, i64)>) -> Vec<[i64; 2]> {
+    let n = contour.len();
+    if n < 3 {
+        return contour.into_iter().map(|(x, y)| [x, y]).collect();
+    }
+    (0..n)
+        .filter(|&i| {
+            let (prev, point, next) = (contour[(i + n - 1) % n], contour[i], contour[(i + 1) % n]);
+            (point.0 - prev.0, point.1 - prev.1) != (next.0 - point.0, next.1 - point.1)
+        })
+        .map(|i| [contour[i].0, contour[i].1])
+        .collect()
+}
@@ -0,0 +1,151 @@
+//! 音频解码与重采样
+
+use bytes::Bytes;
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::DecoderOptions;
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+
+use crate::common::error::*;
+use crate::common::types::{DataType, Tensor};
+use crate::domain::model::AudioPreprocessing;
+
+/// 解码后的交错PCM
+struct DecodedAudio {
+    samples: Vec<f32>,
+    channels: usize,
+    sample_rate: u32,
+}
+
+/// 解码WAV/MP3/FLAC/OGG音频并重采样为模型期望采样率的float32张量
+pub fn preprocess_audio(bytes: Bytes, config: &AudioPreprocessing) -> Result<Tensor> {
+    if config.sample_rate == 0 {
+        return Err(UniModelError::config("Audio preprocessing sample_rate must be positive"));
+    }
+
+    let audio = decode(bytes, config.max_duration_secs)?;
+    let mut channels = deinterleave(&audio.samples, audio.channels);
+    if config.mono && channels.len() > 1 {
+        channels = vec![downmix(&channels)];
+    }
+    for channel in &mut channels {
+        *channel = resample(channel, audio.sample_rate, config.sample_rate);
+    }
+
+    let frames = channels.first().map_or(0, Vec::len);
+    let shape = if channels.len() == 1 {
+        vec![1, frames]
+    } else {
+        vec![1, channels.len(), frames]
+    };
+    let data = channels.iter().flatten().flat_map(|v| v.to_le_bytes()).collect();
+    Tensor::new(DataType::F32, shape, data)
+}
+
+fn decode(bytes: Bytes, max_duration_secs: u32) -> Result<DecodedAudio> {
+    let invalid = |e: SymphoniaError| UniModelError::validation(format!("Failed to decode audio: {}", e));
+
+    let source = MediaSourceStream::new(Box::new(std::io::Cursor::new(bytes)), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(&Hint::new(), source, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(invalid)?;
+    let mut format = probed.format;
+
+    let track = format.default_track()
+        .ok_or_else(|| UniModelError::validation("Audio contains no playable track"))?;
+    let track_id = track.id;
+    let sample_rate = track.codec_params.sample_rate
+        .ok_or_else(|| UniModelError::validation("Audio track has no sample rate"))?;
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(invalid)?;
+
+    let mut samples = Vec::new();
+    let mut channels = track.codec_params.channels.map_or(1, |c| c.count());
+    let mut max_samples = None;
+    loop {
+        let packet = match format.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(e) => return Err(invalid(e)),
+        };
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // 损坏的帧跳过，与常见播放器的行为一致
+            Err(SymphoniaError::DecodeError(_)) => continue,
+            Err(e) => return Err(invalid(e)),
+        };
+        let spec = *decoded.spec();
+        channels = spec.channels.count();
+        let limit = *max_samples.get_or_insert(max_duration_secs as usize * sample_rate as usize * channels);
+
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+        samples.extend_from_slice(buffer.samples());
+        if samples.len() > limit {
+            return Err(UniModelError::validation(format!(
+                "Audio exceeds the maximum duration of {} seconds",
+                max_duration_secs
+            )));
+        }
+    }
+
+    if samples.is_empty() {
+        return Err(UniModelError::validation("Audio contains no samples"));
+    }
+    Ok(DecodedAudio {
+        samples,
+        channels,
+        sample_rate,
+    })
+}
+
+fn deinterleave(samples: &[f32], channels: usize) -> Vec<Vec<f32>> {
+    let channels = channels.max(1);
+    (0..channels)
+        .map(|channel| samples.iter().skip(channel).step_by(channels).copied().collect())
+        .collect()
+}
+
+fn downmix(channels: &[Vec<f32>]) -> Vec<f32> {
+    let frames = channels.iter().map(Vec::len).min().unwrap_or(0);
+    let scale = 1.0 / channels.len() as f32;
+    (0..frames)
+        .map(|i| channels.iter().map(|channel| channel[i]).sum::<f32>() * scale)
+        .collect()
+}
+
+/// 重采样：升采样线性插值，降采样对每个输出样本覆盖的输入区间取平均以抑制混叠
+///
+/// 对语音识别和音频分类模型精度足够，不需要引入完整的多相滤波器。
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let ratio = from_rate as f64 / to_rate as f64;
+    let frames = ((samples.len() as f64 / ratio).round() as usize).max(1);
+    let last = samples.len() - 1;
+    (0..frames)
+        .map(|i| {
+            let position = i as f64 * ratio;
+            if ratio > 1.0 {
+                let start = (position as usize).min(last);
+                let end = (((i + 1) as f64 * ratio) as usize).clamp(start + 1, samples.len());
+                samples[start..end].iter().sum::<f32>() / (end - start) as f32
+            } else {
+                let index = (position as usize).min(last);
+                let next = (index + 1).min(last);
+                let fraction = (position - index as f64) as f32;
+                samples[index] + (samples[next] - samples[index]) * fraction
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,85 @@
+//! 图像预处理
+
+use image::imageops::FilterType;
+use image::RgbImage;
+
+use crate::common::error::*;
+use crate::common::types::{DataType, Tensor};
+use crate::domain::model::{ChannelOrder, ImagePreprocessing, TensorLayout};
+
+/// 解码JPEG/PNG等图像并按配置转换为 `[1, 3, H, W]`（或NHWC）的float32张量
+pub fn preprocess_image(bytes: &[u8], config: &ImagePreprocessing) -> Result<Tensor> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| UniModelError::validation(format!("Failed to decode image: {}", e)))?
+        .to_rgb8();
+
+    let image = match config.resize_shorter_side {
+        Some(target) => resize_shorter_side(&image, target)?,
+        None => image,
+    };
+    let image = match config.center_crop {
+        Some([height, width]) => center_crop(&image, height, width)?,
+        None => image,
+    };
+
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let channel_map = match config.channel_order {
+        ChannelOrder::Rgb => [0, 1, 2],
+        ChannelOrder::Bgr => [2, 1, 0],
+    };
+
+    let mut values = vec![0f32; 3 * height * width];
+    for (x, y, pixel) in image.enumerate_pixels() {
+        let (x, y) = (x as usize, y as usize);
+        for (channel, &source) in channel_map.iter().enumerate() {
+            let value = (pixel[source] as f32 * config.scale - config.mean[channel]) / config.std[channel];
+            let index = match config.layout {
+                TensorLayout::Nchw => (channel * height + y) * width + x,
+                TensorLayout::Nhwc => (y * width + x) * 3 + channel,
+            };
+            values[index] = value;
+        }
+    }
+
+    let shape = match config.layout {
+        TensorLayout::Nchw => vec![1, 3, height, width],
+        TensorLayout::Nhwc => vec![1, height, width, 3],
+    };
+    let data = values.iter().flat_map(|v| v.to_le_bytes()).collect();
+    Tensor::new(DataType::F32, shape, data)
+}
+
+/// 等比缩放，使短边等于 `target`
+fn resize_shorter_side(image: &RgbImage, target: u32) -> Result<RgbImage> {
+    let (width, height) = image.dimensions();
+    let shorter = width.min(height);
+    if shorter == 0 || target == 0 {
+        return Err(UniModelError::validation("Image has zero size"));
+    }
+    if shorter == target {
+        return Ok(image.clone());
+    }
+
+    let scale = target as f64 / shorter as f64;
+    let (new_width, new_height) = if width <= height {
+        (target, ((height as f64 * scale).round() as u32).max(target))
+    } else {
+        (((width as f64 * scale).round() as u32).max(target), target)
+    };
+    Ok(image::imageops::resize(image, new_width, new_height, FilterType::Triangle))
+}
+
+/// 从图像中心裁剪 `height × width` 区域
+fn center_crop(image: &RgbImage, height: u32, width: u32) -> Result<RgbImage> {
+    let (image_width, image_height) = image.dimensions();
+    if width > image_width || height > image_height {
+        return Err(UniModelError::validation(format!(
+            "Image of {}x{} is smaller than the {}x{} crop",
+            image_width, image_height, width, height
+        )));
+    }
+
+    let x = (image_width - width) / 2;
+    let y = (image_height - height) / 2;
+    Ok(image::imageops::crop_imm(image, x, y, width, height).to_image())
+}
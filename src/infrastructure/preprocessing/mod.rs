@@ -0,0 +1,59 @@
+//! 服务端输入预处理
+
+pub mod audio;
+pub mod image;
+pub mod video;
+
+use bytes::Bytes;
+
+use crate::common::error::*;
+use crate::common::types::Tensor;
+use crate::domain::model::PreprocessingConfig;
+
+pub use self::audio::{preprocess_audio, resample};
+pub use self::image::preprocess_image;
+pub use self::video::{extract_frames, is_video, split_frame_outputs, VideoFrames};
+
+/// 二进制输入的预处理结果
+#[derive(Debug, Clone)]
+pub struct Preprocessed {
+    /// 配置中指定的张量名
+    pub input_name: Option<String>,
+    pub tensor: Tensor,
+    /// 视频输入每帧的时间戳（秒），用于按帧拆分输出
+    pub frame_timestamps: Option<Vec<f64>>,
+}
+
+/// 按配置把二进制输入转换为张量
+///
+/// 按文件头识别图像和视频，其余数据按音频处理；只配置了一种预处理时直接使用该预处理。
+pub fn preprocess_binary(bytes: Bytes, config: &PreprocessingConfig) -> Result<Preprocessed> {
+    let single = |input_name: &Option<String>, tensor| Preprocessed {
+        input_name: input_name.clone(),
+        tensor,
+        frame_timestamps: None,
+    };
+
+    let configured = [config.image.is_some(), config.audio.is_some(), config.video.is_some()];
+    let only = configured.iter().filter(|&&c| c).count() == 1;
+
+    if let Some(image) = &config.image {
+        if only || ::image::guess_format(&bytes).is_ok() {
+            return Ok(single(&image.input_name, preprocess_image(&bytes, image)?));
+        }
+    }
+    if let Some(video) = &config.video {
+        if only || is_video(&bytes) {
+            let frames = extract_frames(&bytes, video)?;
+            return Ok(Preprocessed {
+                input_name: video.input_name.clone(),
+                tensor: frames.tensor,
+                frame_timestamps: Some(frames.timestamps),
+            });
+        }
+    }
+    match &config.audio {
+        Some(audio) => Ok(single(&audio.input_name, preprocess_audio(bytes, audio)?)),
+        None => Err(UniModelError::validation("Binary input does not match any configured preprocessing")),
+    }
+}
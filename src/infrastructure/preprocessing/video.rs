@@ -0,0 +1,159 @@
+//! 视频抽帧
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+use crate::common::error::*;
+use crate::common::types::{OutputData, Tensor};
+use crate::domain::model::VideoPreprocessing;
+use crate::infrastructure::preprocessing::preprocess_image;
+use crate::infrastructure::tensor_format::unstack;
+
+/// 抽取的帧张量及每帧的时间戳（秒）
+#[derive(Debug, Clone)]
+pub struct VideoFrames {
+    pub tensor: Tensor,
+    pub timestamps: Vec<f64>,
+}
+
+/// 常见视频容器的文件头（MP4/MOV、Matroska/WebM、AVI、MPEG-TS）
+pub fn is_video(bytes: &[u8]) -> bool {
+    (bytes.len() > 8 && &bytes[4..8] == b"ftyp")
+        || bytes.starts_with(&[0x1a, 0x45, 0xdf, 0xa3])
+        || (bytes.len() > 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"AVI ")
+        || (bytes.len() > 188 && bytes[0] == 0x47 && bytes[188] == 0x47)
+}
+
+/// 调用ffmpeg按帧率或步长抽帧，逐帧做图像预处理后沿第一维堆叠
+///
+/// 视频先写入临时文件，因为MP4的索引可能位于文件末尾，无法从管道读取。
+/// 帧以BMP流输出，时间戳从 `showinfo` 滤镜的日志中解析。
+pub fn extract_frames(bytes: &[u8], config: &VideoPreprocessing) -> Result<VideoFrames> {
+    let select = match (config.fps, config.stride) {
+        (Some(_), Some(_)) => return Err(UniModelError::config("Video preprocessing accepts fps or stride, not both")),
+        (_, Some(0)) => return Err(UniModelError::config("Video stride must be positive")),
+        (_, Some(stride)) => format!("select=not(mod(n\\,{})),showinfo", stride),
+        (fps, None) => {
+            let fps = fps.unwrap_or(1.0);
+            if fps <= 0.0 {
+                return Err(UniModelError::config("Video fps must be positive"));
+            }
+            format!("fps={},showinfo", fps)
+        }
+    };
+
+    let input = TempVideo::write(bytes)?;
+    let output = Command::new(&config.ffmpeg_path)
+        .args(["-hide_banner", "-nostdin", "-loglevel", "info", "-i"])
+        .arg(&input.0)
+        .args(["-vf", &select, "-vsync", "vfr", "-frames:v", &config.max_frames.to_string()])
+        .args(["-f", "image2pipe", "-vcodec", "bmp", "pipe:1"])
+        .output()
+        .map_err(|e| UniModelError::internal(format!("Failed to run {}: {}", config.ffmpeg_path, e)))?;
+
+    let log = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        let reason = log.lines().last().unwrap_or_default();
+        return Err(UniModelError::validation(format!("Failed to decode video: {}", reason)));
+    }
+
+    let frames = split_bmp_stream(&output.stdout)?;
+    let timestamps: Vec<f64> = log.lines().filter_map(showinfo_timestamp).collect();
+    if frames.is_empty() {
+        return Err(UniModelError::validation("Video contains no frames"));
+    }
+    if frames.len() != timestamps.len() {
+        return Err(UniModelError::internal(format!(
+            "Extracted {} frames but {} timestamps",
+            frames.len(), timestamps.len()
+        )));
+    }
+
+    let tensors = frames.iter()
+        .map(|frame| preprocess_image(frame, &config.frame))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(VideoFrames {
+        tensor: stack(tensors)?,
+        timestamps,
+    })
+}
+
+/// 把批量输出按帧拆分，以时间戳为键组成多模态输出；非张量输出原样返回
+pub fn split_frame_outputs(output: OutputData, timestamps: &[f64]) -> Result<OutputData> {
+    let tensors = match output {
+        OutputData::Tensors(tensors) => tensors,
+        other => return Ok(other),
+    };
+
+    let frames = unstack(&tensors)?;
+    if frames.len() != timestamps.len() {
+        return Err(UniModelError::internal(format!(
+            "Model returned {} frame outputs for {} frames",
+            frames.len(), timestamps.len()
+        )));
+    }
+    Ok(OutputData::Multimodal(
+        timestamps.iter()
+            .zip(frames)
+            .map(|(timestamp, frame)| (format!("{:.3}", timestamp), OutputData::Tensors(frame)))
+            .collect::<HashMap<_, _>>(),
+    ))
+}
+
+/// 沿第一维拼接形状为 `[1, ...]` 的张量
+fn stack(tensors: Vec<Tensor>) -> Result<Tensor> {
+    let first = &tensors[0];
+    let (dtype, mut shape) = (first.dtype, first.shape.clone());
+    if tensors.iter().any(|t| t.dtype != dtype || t.shape != shape) {
+        return Err(UniModelError::validation("Video frames have different sizes; configure a center crop"));
+    }
+
+    shape[0] = tensors.len();
+    let data = tensors.into_iter().flat_map(|t| t.data).collect();
+    Tensor::new(dtype, shape, data)
+}
+
+/// 按BMP文件头中的文件大小切分连续的BMP流
+fn split_bmp_stream(mut stream: &[u8]) -> Result<Vec<&[u8]>> {
+    let mut frames = Vec::new();
+    while !stream.is_empty() {
+        if stream.len() < 6 || &stream[..2] != b"BM" {
+            return Err(UniModelError::internal("Malformed frame stream from ffmpeg"));
+        }
+        let size = u32::from_le_bytes([stream[2], stream[3], stream[4], stream[5]]) as usize;
+        if size < 6 || size > stream.len() {
+            return Err(UniModelError::internal("Truncated frame in ffmpeg output"));
+        }
+        frames.push(&stream[..size]);
+        stream = &stream[size..];
+    }
+    Ok(frames)
+}
+
+/// 解析 `showinfo` 日志行中的 `pts_time:`
+fn showinfo_timestamp(line: &str) -> Option<f64> {
+    if !line.contains("Parsed_showinfo") {
+        return None;
+    }
+    line.split_whitespace()
+        .find_map(|token| token.strip_prefix("pts_time:"))
+        .and_then(|value| value.parse().ok())
+}
+
+/// 抽帧期间的临时视频文件，离开作用域时删除
+struct TempVideo(PathBuf);
+
+impl TempVideo {
+    fn write(bytes: &[u8]) -> Result<Self> {
+        let path = std::env::temp_dir().join(format!("unimodel-video-{}", uuid::Uuid::new_v4()));
+        std::fs::write(&path, bytes)?;
+        Ok(Self(path))
+    }
+}
+
+impl Drop for TempVideo {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
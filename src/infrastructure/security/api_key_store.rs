@@ -0,0 +1,253 @@
+//! API密钥持久化存储
+//!
+//! 密钥明文只在创建时返回一次，存储中只保存argon2哈希。
+//! 密钥格式为 `um_<id>_<secret>`，校验时先按ID定位记录再验证哈希。
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use argon2::password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use chrono::{DateTime, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::{info, warn};
+
+use crate::common::error::*;
+use crate::infrastructure::storage::file_system::JsonFile;
+
+/// 密钥前缀
+const API_KEY_PREFIX: &str = "um";
+
+/// 密钥随机部分长度
+const API_KEY_SECRET_LEN: usize = 40;
+
+/// 通配权限
+pub const SCOPE_ALL: &str = "*";
+
+/// 管理权限
+pub const SCOPE_ADMIN: &str = "admin";
+
+/// API密钥记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiKeyRecord {
+    /// 密钥ID
+    pub id: String,
+    /// 密钥名称
+    pub name: String,
+    /// 所属租户（None表示全局密钥）
+    #[serde(default)]
+    pub tenant: Option<String>,
+    /// argon2哈希（PHC格式）
+    pub key_hash: String,
+    /// 权限范围
+    pub scopes: Vec<String>,
+    /// 过期时间
+    pub expires_at: Option<DateTime<Utc>>,
+    /// 每分钟请求数限制
+    pub rate_limit_per_minute: Option<u32>,
+    /// 创建时间
+    pub created_at: DateTime<Utc>,
+    /// 吊销时间
+    pub revoked_at: Option<DateTime<Utc>>,
+}
+
+impl ApiKeyRecord {
+    /// 检查密钥是否可用（未吊销且未过期）
+    pub fn is_active(&self) -> bool {
+        if self.revoked_at.is_some() {
+            return false;
+        }
+        match self.expires_at {
+            Some(expires_at) => expires_at > Utc::now(),
+            None => true,
+        }
+    }
+
+    /// 检查是否拥有指定权限
+    pub fn has_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| s == scope || s == SCOPE_ALL)
+    }
+}
+
+/// 新建密钥参数
+#[derive(Debug, Clone)]
+pub struct NewApiKey {
+    pub name: String,
+    pub tenant: Option<String>,
+    pub scopes: Vec<String>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub rate_limit_per_minute: Option<u32>,
+}
+
+/// API密钥存储
+#[derive(Debug)]
+pub struct ApiKeyStore {
+    /// 持久化文件，每次修改后立即写入
+    file: JsonFile,
+    /// 内存中的密钥记录
+    keys: RwLock<HashMap<String, ApiKeyRecord>>,
+}
+
+impl ApiKeyStore {
+    /// 打开密钥存储，文件不存在时创建空存储
+    pub async fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = JsonFile::new(path.as_ref());
+        let records: Vec<ApiKeyRecord> = file.load().await?.unwrap_or_default();
+        let keys: HashMap<String, ApiKeyRecord> = records.into_iter().map(|r| (r.id.clone(), r)).collect();
+
+        info!("API key store opened: {} ({} keys)", file.path().display(), keys.len());
+
+        Ok(Self {
+            file,
+            keys: RwLock::new(keys),
+        })
+    }
+
+    /// 创建密钥，返回记录和仅此一次可见的明文密钥
+    pub async fn create(&self, request: NewApiKey) -> Result<(ApiKeyRecord, String)> {
+        if request.name.is_empty() {
+            return Err(UniModelError::validation("API key name cannot be empty"));
+        }
+        if let Some(expires_at) = request.expires_at {
+            if expires_at <= Utc::now() {
+                return Err(UniModelError::validation("API key expiry must be in the future"));
+            }
+        }
+
+        let id = uuid::Uuid::new_v4().simple().to_string();
+        let secret: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(API_KEY_SECRET_LEN)
+            .map(char::from)
+            .collect();
+        let raw_key = format!("{}_{}_{}", API_KEY_PREFIX, id, secret);
+
+        let key_hash = tokio::task::spawn_blocking(move || hash_secret(&secret))
+            .await
+            .map_err(|e| UniModelError::internal(format!("Hashing task failed: {}", e)))??;
+
+        let record = ApiKeyRecord {
+            id: id.clone(),
+            name: request.name,
+            tenant: request.tenant,
+            key_hash,
+            scopes: request.scopes,
+            expires_at: request.expires_at,
+            rate_limit_per_minute: request.rate_limit_per_minute,
+            created_at: Utc::now(),
+            revoked_at: None,
+        };
+
+        let mut keys = self.keys.write().await;
+        keys.insert(id.clone(), record.clone());
+        self.persist(&keys).await?;
+
+        info!("API key created: {} ({})", id, record.name);
+        Ok((record, raw_key))
+    }
+
+    /// 吊销密钥
+    pub async fn revoke(&self, id: &str) -> Result<ApiKeyRecord> {
+        let mut keys = self.keys.write().await;
+
+        let record = keys.get_mut(id)
+            .ok_or_else(|| UniModelError::validation(format!("API key not found: {}", id)))?;
+        if record.revoked_at.is_none() {
+            record.revoked_at = Some(Utc::now());
+        }
+        let record = record.clone();
+
+        self.persist(&keys).await?;
+
+        info!("API key revoked: {}", id);
+        Ok(record)
+    }
+
+    /// 列出所有密钥记录
+    pub async fn list(&self) -> Vec<ApiKeyRecord> {
+        let keys = self.keys.read().await;
+        let mut records: Vec<ApiKeyRecord> = keys.values().cloned().collect();
+        records.sort_by_key(|a| a.created_at);
+        records
+    }
+
+    /// 获取密钥记录
+    pub async fn get(&self, id: &str) -> Option<ApiKeyRecord> {
+        let keys = self.keys.read().await;
+        keys.get(id).cloned()
+    }
+
+    /// 校验明文密钥，返回可用的密钥记录
+    pub async fn verify(&self, raw_key: &str) -> Option<ApiKeyRecord> {
+        let (id, secret) = parse_raw_key(raw_key)?;
+
+        let record = {
+            let keys = self.keys.read().await;
+            keys.get(id).cloned()?
+        };
+        if !record.is_active() {
+            return None;
+        }
+
+        let key_hash = record.key_hash.clone();
+        let secret = secret.to_string();
+        let valid = tokio::task::spawn_blocking(move || verify_secret(&secret, &key_hash))
+            .await
+            .unwrap_or(false);
+
+        if valid {
+            Some(record)
+        } else {
+            None
+        }
+    }
+
+    /// 将密钥记录写入文件
+    async fn persist(&self, keys: &HashMap<String, ApiKeyRecord>) -> Result<()> {
+        let records: Vec<&ApiKeyRecord> = keys.values().collect();
+        self.file.write(serde_json::to_string_pretty(&records)?).await
+    }
+}
+
+/// 明文密钥中的密钥ID，格式不符时返回None
+pub fn api_key_id(raw_key: &str) -> Option<&str> {
+    parse_raw_key(raw_key).map(|(id, _)| id)
+}
+
+/// 拆分明文密钥为 (id, secret)
+fn parse_raw_key(raw_key: &str) -> Option<(&str, &str)> {
+    let mut parts = raw_key.splitn(3, '_');
+    let prefix = parts.next()?;
+    let id = parts.next()?;
+    let secret = parts.next()?;
+
+    if prefix != API_KEY_PREFIX || id.is_empty() || secret.is_empty() {
+        return None;
+    }
+    Some((id, secret))
+}
+
+/// 计算密钥哈希
+fn hash_secret(secret: &str) -> Result<String> {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(secret.as_bytes(), &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| UniModelError::internal(format!("Failed to hash API key: {}", e)))
+}
+
+/// 校验密钥哈希
+fn verify_secret(secret: &str, key_hash: &str) -> bool {
+    match PasswordHash::new(key_hash) {
+        Ok(parsed) => Argon2::default()
+            .verify_password(secret.as_bytes(), &parsed)
+            .is_ok(),
+        Err(e) => {
+            warn!("Malformed API key hash in store: {}", e);
+            false
+        }
+    }
+}
@@ -0,0 +1,12 @@
+//! 安全基础设施
+
+pub mod api_key_store;
+pub mod audit_logger;
+pub mod certificate;
+pub mod encryption;
+pub mod quota_manager;
+pub mod rate_limiter;
+
+pub use api_key_store::*;
+pub use quota_manager::*;
+pub use rate_limiter::*;
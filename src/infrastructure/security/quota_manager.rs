@@ -0,0 +1,145 @@
+//! 月度配额管理
+//!
+//! 月度token用量持久化到文件，由后台任务定期落盘，进程重启后继续累计。
+//! 每分钟请求数由 [`RateLimiter`](super::RateLimiter) 负责。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{Datelike, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::common::error::*;
+use crate::infrastructure::configuration::RateLimitConfig;
+use crate::infrastructure::storage::file_system::{self, JsonFile};
+
+/// 月度用量计数器
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MonthlyUsage {
+    /// 计数所属月份（YYYY-MM）
+    pub month: String,
+    /// 已消耗请求数
+    pub requests: u64,
+    /// 已消耗token数
+    pub tokens: u64,
+}
+
+/// 配额管理器
+#[derive(Debug)]
+pub struct QuotaManager {
+    config: RateLimitConfig,
+    file: JsonFile,
+    /// 月度用量：主体 -> 用量
+    monthly: RwLock<HashMap<String, MonthlyUsage>>,
+}
+
+impl QuotaManager {
+    /// 打开配额管理器并加载持久化的月度用量
+    pub async fn open(config: &RateLimitConfig) -> Result<Self> {
+        let file = JsonFile::new(&config.quota_store_path);
+        let monthly = file.load().await?.unwrap_or_default();
+
+        Ok(Self {
+            config: config.clone(),
+            file,
+            monthly: RwLock::new(monthly),
+        })
+    }
+
+    /// 计算主体的月度token配额（配置覆盖优先于默认值）
+    pub fn tokens_per_month_for(&self, subject: &str) -> Option<u64> {
+        self.config.overrides
+            .get(subject)
+            .and_then(|o| o.tokens_per_month)
+            .or(self.config.tokens_per_month)
+    }
+
+    /// 请求准入检查：月度token配额
+    pub async fn check_request(&self, subject: &str) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        if let Some(quota) = self.tokens_per_month_for(subject) {
+            let used = self.monthly_usage(subject).await.tokens;
+            if used >= quota {
+                return Err(UniModelError::QuotaExceeded(format!(
+                    "Monthly token quota of {} exhausted ({} used)",
+                    quota, used
+                )));
+            }
+        }
+
+        let mut monthly = self.monthly.write().await;
+        let usage = current_month_entry(&mut monthly, subject);
+        usage.requests += 1;
+        self.file.mark_dirty();
+
+        Ok(())
+    }
+
+    /// 记录token消耗
+    pub async fn record_tokens(&self, subject: &str, tokens: u64) {
+        if tokens == 0 {
+            return;
+        }
+
+        let mut monthly = self.monthly.write().await;
+        let usage = current_month_entry(&mut monthly, subject);
+        usage.tokens += tokens;
+        self.file.mark_dirty();
+    }
+
+    /// 获取主体本月用量
+    pub async fn monthly_usage(&self, subject: &str) -> MonthlyUsage {
+        let month = current_month();
+        let monthly = self.monthly.read().await;
+
+        match monthly.get(subject) {
+            Some(usage) if usage.month == month => usage.clone(),
+            _ => MonthlyUsage {
+                month,
+                ..Default::default()
+            },
+        }
+    }
+
+    /// 有未落盘的修改时将月度用量写入文件
+    pub async fn flush(&self) -> Result<()> {
+        self.file.flush(async {
+            let monthly = self.monthly.read().await;
+            serde_json::to_string_pretty(&*monthly)
+        }).await
+    }
+
+    /// 启动定期落盘任务
+    pub fn start_flush_task(self: &Arc<Self>, interval: Duration) {
+        file_system::start_flush_task(Arc::clone(self), interval, "quota usage", |manager| async move {
+            manager.flush().await
+        });
+    }
+}
+
+/// 当前月份标识
+fn current_month() -> String {
+    let now = Utc::now();
+    format!("{:04}-{:02}", now.year(), now.month())
+}
+
+/// 获取主体本月计数，跨月时重置
+fn current_month_entry<'a>(
+    monthly: &'a mut HashMap<String, MonthlyUsage>,
+    subject: &str,
+) -> &'a mut MonthlyUsage {
+    let month = current_month();
+    let usage = monthly.entry(subject.to_string()).or_default();
+    if usage.month != month {
+        *usage = MonthlyUsage {
+            month,
+            ..Default::default()
+        };
+    }
+    usage
+}
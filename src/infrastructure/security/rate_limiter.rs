@@ -0,0 +1,129 @@
+//! 令牌桶速率限制器
+//!
+//! 每个限流主体（API密钥或客户端IP）拥有独立的令牌桶：
+//! 容量为 `burst_size`，按 `requests_per_minute / 60` 每秒的速率补充。
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use tracing::{debug, info};
+
+use crate::infrastructure::configuration::RateLimitConfig;
+
+/// 空闲令牌桶的回收时间
+const BUCKET_IDLE_TTL: Duration = Duration::from_secs(600);
+
+/// 令牌桶
+#[derive(Debug, Clone)]
+struct TokenBucket {
+    tokens: f64,
+    capacity: f64,
+    refill_per_sec: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            tokens: capacity,
+            capacity,
+            refill_per_sec,
+            last_refill: Instant::now(),
+        }
+    }
+
+    /// 尝试取出一个令牌，失败时返回需要等待的时间
+    fn try_acquire(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        self.last_refill = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else if self.refill_per_sec > 0.0 {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.refill_per_sec))
+        } else {
+            Err(Duration::from_secs(60))
+        }
+    }
+}
+
+/// 速率限制器
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: DashMap<String, TokenBucket>,
+}
+
+impl RateLimiter {
+    /// 创建速率限制器
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            config: config.clone(),
+            buckets: DashMap::new(),
+        }
+    }
+
+    /// 是否启用限流
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// 计算主体的每分钟请求数（密钥自身限额优先，其次配置覆盖，最后默认值）
+    pub fn requests_per_minute_for(&self, key_id: Option<&str>, key_rate_limit: Option<u32>) -> u32 {
+        key_rate_limit
+            .or_else(|| {
+                key_id
+                    .and_then(|id| self.config.overrides.get(id))
+                    .and_then(|o| o.requests_per_minute)
+            })
+            .unwrap_or(self.config.requests_per_minute)
+    }
+
+    /// 检查限流主体是否可以发起请求，超限时返回建议的重试等待时间
+    pub fn check(&self, bucket_key: &str, requests_per_minute: u32) -> Result<(), Duration> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+
+        let capacity = self.config.burst_size.max(1) as f64;
+        let refill_per_sec = requests_per_minute as f64 / 60.0;
+        let now = Instant::now();
+
+        let mut bucket = self.buckets
+            .entry(bucket_key.to_string())
+            .or_insert_with(|| TokenBucket::new(capacity, refill_per_sec));
+
+        // 限额可能随密钥更新而变化
+        bucket.capacity = capacity;
+        bucket.refill_per_sec = refill_per_sec;
+
+        let result = bucket.try_acquire(now);
+        if let Err(retry_after) = &result {
+            debug!("Rate limit hit for {} (retry after {:?})", bucket_key, retry_after);
+        }
+        result
+    }
+
+    /// 回收长期空闲的令牌桶
+    pub fn evict_idle(&self, idle_ttl: Duration) {
+        let now = Instant::now();
+        self.buckets.retain(|_, bucket| now.duration_since(bucket.last_refill) < idle_ttl);
+    }
+
+    /// 启动空闲令牌桶回收任务
+    pub fn start_cleanup_task(self: &Arc<Self>) {
+        let limiter = Arc::clone(self);
+
+        tokio::spawn(async move {
+            info!("Rate limiter cleanup task started");
+            let mut ticker = tokio::time::interval(BUCKET_IDLE_TTL);
+            loop {
+                ticker.tick().await;
+                limiter.evict_idle(BUCKET_IDLE_TTL);
+            }
+        });
+    }
+}
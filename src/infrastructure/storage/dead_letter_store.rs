@@ -0,0 +1,132 @@
+//! 死信存储
+//!
+//! 批次执行永久失败（重试用尽或不可重试）的请求连同输入、参数和错误一起保存，
+//! 便于排查后重新投递。超过容量上限时丢弃最早的记录。
+
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tracing::warn;
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::infrastructure::storage::file_system::{self, JsonFile};
+
+/// 死信记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetterRecord {
+    /// 死信ID
+    pub id: String,
+    /// 原始请求ID
+    pub request_id: RequestId,
+    pub model_id: ModelId,
+    pub tenant: Option<String>,
+    pub input: InputData,
+    pub parameters: PredictionParameters,
+    /// 错误代码
+    pub error_code: String,
+    /// 错误信息
+    pub error: String,
+    /// 失败时间
+    pub failed_at: DateTime<Utc>,
+}
+
+/// 死信存储
+#[derive(Debug)]
+pub struct DeadLetterStore {
+    file: JsonFile,
+    max_entries: usize,
+    records: RwLock<VecDeque<DeadLetterRecord>>,
+}
+
+impl DeadLetterStore {
+    /// 打开死信存储并加载已持久化的记录
+    pub async fn open<P: Into<PathBuf>>(path: P, max_entries: usize) -> Result<Self> {
+        let file = JsonFile::new(path);
+        let records = file.load().await?.unwrap_or_default();
+
+        Ok(Self {
+            file,
+            max_entries: max_entries.max(1),
+            records: RwLock::new(records),
+        })
+    }
+
+    /// 记录一个永久失败的请求
+    pub async fn record(
+        &self,
+        request_id: RequestId,
+        model_id: ModelId,
+        tenant: Option<String>,
+        input: InputData,
+        parameters: PredictionParameters,
+        err: &UniModelError,
+    ) {
+        let record = DeadLetterRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            request_id,
+            model_id,
+            tenant,
+            input,
+            parameters,
+            error_code: err.error_code().to_string(),
+            error: err.to_string(),
+            failed_at: Utc::now(),
+        };
+
+        let mut records = self.records.write().await;
+        if records.len() >= self.max_entries {
+            if let Some(dropped) = records.pop_front() {
+                warn!("Dead-letter store full, dropping oldest record {}", dropped.id);
+            }
+        }
+        records.push_back(record);
+        self.file.mark_dirty();
+    }
+
+    /// 列出死信记录（最新的在前），可按模型和租户过滤
+    pub async fn list(&self, model_id: Option<&str>, tenant: Option<&str>, limit: usize) -> Vec<DeadLetterRecord> {
+        let records = self.records.read().await;
+        records.iter()
+            .rev()
+            .filter(|r| model_id.is_none_or(|m| r.model_id == m))
+            .filter(|r| tenant.is_none_or(|t| r.tenant.as_deref() == Some(t)))
+            .take(limit)
+            .cloned()
+            .collect()
+    }
+
+    /// 获取单条死信记录
+    pub async fn get(&self, id: &str) -> Option<DeadLetterRecord> {
+        let records = self.records.read().await;
+        records.iter().find(|r| r.id == id).cloned()
+    }
+
+    /// 删除死信记录
+    pub async fn remove(&self, id: &str) -> Option<DeadLetterRecord> {
+        let mut records = self.records.write().await;
+        let index = records.iter().position(|r| r.id == id)?;
+        self.file.mark_dirty();
+        records.remove(index)
+    }
+
+    /// 有未落盘的修改时将死信记录写入文件
+    pub async fn flush(&self) -> Result<()> {
+        self.file.flush(async {
+            let records = self.records.read().await;
+            serde_json::to_string(&*records)
+        }).await
+    }
+
+    /// 启动定期落盘任务
+    pub fn start_flush_task(self: &Arc<Self>, interval: Duration) {
+        file_system::start_flush_task(Arc::clone(self), interval, "dead-letter store", |store| async move {
+            store.flush().await
+        });
+    }
+}
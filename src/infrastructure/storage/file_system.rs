@@ -0,0 +1,107 @@
+//! 本地文件持久化
+//!
+//! 配额、用量、死信和API密钥都把内存状态整体序列化为JSON文件：启动时加载，
+//! 修改后标记为未落盘，由后台任务定期写入。写入先写临时文件再原子替换，进程中途退出不会留下半个文件。
+
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use serde::de::DeserializeOwned;
+use tokio::fs;
+use tracing::{error, info};
+
+use crate::common::error::*;
+
+/// 以JSON整体持久化的状态文件及其未落盘标记
+#[derive(Debug)]
+pub struct JsonFile {
+    path: PathBuf,
+    dirty: AtomicBool,
+}
+
+impl JsonFile {
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            dirty: AtomicBool::new(false),
+        }
+    }
+
+    /// 文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 读取并解析文件，文件不存在时返回None
+    pub async fn load<T: DeserializeOwned>(&self) -> Result<Option<T>> {
+        match fs::read_to_string(&self.path).await {
+            Ok(content) => Ok(Some(serde_json::from_str(&content)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// 标记内存状态有未落盘的修改
+    pub fn mark_dirty(&self) {
+        self.dirty.store(true, Ordering::Release);
+    }
+
+    /// 是否有未落盘的修改
+    pub fn is_dirty(&self) -> bool {
+        self.dirty.load(Ordering::Acquire)
+    }
+
+    /// 有未落盘的修改时写入 `snapshot` 生成的内容
+    ///
+    /// 标记在生成快照前清除，快照之后的修改会重新标记；写入失败时恢复标记，由下次落盘重试。
+    pub async fn flush<F>(&self, snapshot: F) -> Result<()>
+    where
+        F: Future<Output = serde_json::Result<String>>,
+    {
+        if !self.dirty.swap(false, Ordering::AcqRel) {
+            return Ok(());
+        }
+
+        let result = match snapshot.await {
+            Ok(content) => self.write(content).await,
+            Err(e) => Err(e.into()),
+        };
+        if result.is_err() {
+            self.mark_dirty();
+        }
+        result
+    }
+
+    /// 立即写入内容：先写临时文件再原子替换
+    pub async fn write(&self, content: String) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).await?;
+        }
+        let tmp_path = self.path.with_extension("json.tmp");
+        fs::write(&tmp_path, content).await?;
+        fs::rename(&tmp_path, &self.path).await?;
+        Ok(())
+    }
+}
+
+/// 启动定期落盘任务，`name` 用于日志
+pub fn start_flush_task<S, F, Fut>(store: Arc<S>, interval: Duration, name: &'static str, flush: F)
+where
+    S: Send + Sync + 'static,
+    F: Fn(Arc<S>) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<()>> + Send,
+{
+    tokio::spawn(async move {
+        info!("Flush task for {} started (every {:?})", name, interval);
+        let mut ticker = tokio::time::interval(interval);
+        loop {
+            ticker.tick().await;
+            if let Err(e) = flush(Arc::clone(&store)).await {
+                error!("Failed to flush {}: {}", name, e);
+            }
+        }
+    });
+}
@@ -0,0 +1,10 @@
+//! 存储基础设施
+
+pub mod cache;
+pub mod dead_letter_store;
+pub mod file_system;
+pub mod s3_storage;
+pub mod usage_store;
+
+pub use dead_letter_store::*;
+pub use usage_store::*;
@@ -0,0 +1,229 @@
+//! 用量计量存储
+//!
+//! 用量按小时分桶，以 (小时, 租户, 模型) 为键累加，定期持久化到文件。
+//! GPU秒数取自批处理器实测的推理耗时，按批次大小分摊；超过保留期的分桶在落盘时删除。
+
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, DurationRound, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::infrastructure::storage::file_system::{self, JsonFile};
+
+/// 未指定租户时使用的租户名
+pub const DEFAULT_TENANT: &str = "default";
+
+/// 聚合的分组键：租户、模型、统计周期起点
+type GroupKey = (Option<String>, Option<ModelId>, Option<i64>);
+
+/// 用量计数
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct UsageCounters {
+    /// 请求数
+    pub requests: u64,
+    /// 输入token数
+    pub input_tokens: u64,
+    /// 输出token数
+    pub output_tokens: u64,
+    /// GPU占用秒数（按批次大小分摊）
+    pub gpu_seconds: f64,
+}
+
+impl UsageCounters {
+    /// 从单次推理的性能指标计算用量
+    pub fn from_metrics(metrics: &PerformanceMetrics) -> Self {
+        let batch_size = metrics.batch_size.max(1) as f64;
+        Self {
+            requests: 1,
+            input_tokens: metrics.tokens_input.unwrap_or(0) as u64,
+            output_tokens: metrics.tokens_generated.unwrap_or(0) as u64,
+            gpu_seconds: metrics.inference_latency_ms as f64 / 1000.0 / batch_size,
+        }
+    }
+
+    /// 累加另一份用量
+    pub fn add(&mut self, other: &UsageCounters) {
+        self.requests += other.requests;
+        self.input_tokens += other.input_tokens;
+        self.output_tokens += other.output_tokens;
+        self.gpu_seconds += other.gpu_seconds;
+    }
+}
+
+/// 用量分桶键
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub struct UsageKey {
+    /// 小时起始时间戳（秒）
+    pub hour: i64,
+    pub tenant: String,
+    pub model_id: ModelId,
+}
+
+/// 分组维度
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageGroupBy {
+    Tenant,
+    Model,
+    Day,
+    Hour,
+}
+
+impl std::str::FromStr for UsageGroupBy {
+    type Err = UniModelError;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "tenant" => Ok(UsageGroupBy::Tenant),
+            "model" => Ok(UsageGroupBy::Model),
+            "day" => Ok(UsageGroupBy::Day),
+            "hour" => Ok(UsageGroupBy::Hour),
+            other => Err(UniModelError::validation(format!(
+                "Invalid group_by '{}', expected tenant, model, day or hour",
+                other
+            ))),
+        }
+    }
+}
+
+/// 用量查询结果行
+#[derive(Debug, Clone, Serialize)]
+pub struct UsageRow {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<ModelId>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub period_start: Option<DateTime<Utc>>,
+    #[serde(flatten)]
+    pub usage: UsageCounters,
+}
+
+/// 用量存储
+#[derive(Debug)]
+pub struct UsageStore {
+    file: JsonFile,
+    /// 保留期，None表示永久保留
+    retention: Option<chrono::Duration>,
+    buckets: RwLock<BTreeMap<UsageKey, UsageCounters>>,
+}
+
+impl UsageStore {
+    /// 打开用量存储并加载已持久化的数据，`retention_days` 为0时永久保留
+    pub async fn open<P: Into<PathBuf>>(path: P, retention_days: u32) -> Result<Self> {
+        let file = JsonFile::new(path);
+        let entries: Vec<(UsageKey, UsageCounters)> = file.load().await?.unwrap_or_default();
+
+        let store = Self {
+            file,
+            retention: (retention_days > 0).then(|| chrono::Duration::days(retention_days as i64)),
+            buckets: RwLock::new(entries.into_iter().collect()),
+        };
+        store.prune(Utc::now()).await;
+        Ok(store)
+    }
+
+    /// 记录一次推理的用量
+    pub async fn record(&self, tenant: Option<&str>, model_id: &ModelId, metrics: &PerformanceMetrics) {
+        let hour = metrics.end_time
+            .duration_trunc(chrono::Duration::hours(1))
+            .unwrap_or(metrics.end_time)
+            .timestamp();
+
+        let key = UsageKey {
+            hour,
+            tenant: tenant.unwrap_or(DEFAULT_TENANT).to_string(),
+            model_id: model_id.clone(),
+        };
+
+        let mut buckets = self.buckets.write().await;
+        buckets.entry(key).or_default().add(&UsageCounters::from_metrics(metrics));
+        self.file.mark_dirty();
+    }
+
+    /// 查询时间范围内的用量并按维度聚合
+    pub async fn query(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        tenant: Option<&str>,
+        group_by: &[UsageGroupBy],
+    ) -> Vec<UsageRow> {
+        let buckets = self.buckets.read().await;
+        let mut grouped: BTreeMap<GroupKey, UsageCounters> = BTreeMap::new();
+
+        for (key, usage) in buckets.iter() {
+            if key.hour < from.timestamp() || key.hour >= to.timestamp() {
+                continue;
+            }
+            if let Some(tenant) = tenant {
+                if key.tenant != tenant {
+                    continue;
+                }
+            }
+
+            let period = if group_by.contains(&UsageGroupBy::Hour) {
+                Some(key.hour)
+            } else if group_by.contains(&UsageGroupBy::Day) {
+                Some(key.hour - key.hour.rem_euclid(86_400))
+            } else {
+                None
+            };
+            let group_key = (
+                group_by.contains(&UsageGroupBy::Tenant).then(|| key.tenant.clone()),
+                group_by.contains(&UsageGroupBy::Model).then(|| key.model_id.clone()),
+                period,
+            );
+
+            grouped.entry(group_key).or_default().add(usage);
+        }
+
+        grouped.into_iter()
+            .map(|((tenant, model_id, period), usage)| UsageRow {
+                tenant,
+                model_id,
+                period_start: period.and_then(|ts| Utc.timestamp_opt(ts, 0).single()),
+                usage,
+            })
+            .collect()
+    }
+
+    /// 删除 `now` 之前超过保留期的小时分桶，返回删除的数量
+    pub async fn prune(&self, now: DateTime<Utc>) -> usize {
+        let cutoff = match self.retention {
+            Some(retention) => (now - retention).timestamp(),
+            None => return 0,
+        };
+
+        let mut buckets = self.buckets.write().await;
+        let before = buckets.len();
+        buckets.retain(|key, _| key.hour >= cutoff);
+        let removed = before - buckets.len();
+        if removed > 0 {
+            self.file.mark_dirty();
+        }
+        removed
+    }
+
+    /// 删除过期分桶，有未落盘的修改时将用量写入文件
+    pub async fn flush(&self) -> Result<()> {
+        self.prune(Utc::now()).await;
+        self.file.flush(async {
+            let buckets = self.buckets.read().await;
+            let entries: Vec<(&UsageKey, &UsageCounters)> = buckets.iter().collect();
+            serde_json::to_string(&entries)
+        }).await
+    }
+
+    /// 启动定期落盘任务
+    pub fn start_flush_task(self: &Arc<Self>, interval: Duration) {
+        file_system::start_flush_task(Arc::clone(self), interval, "usage store", |store| async move {
+            store.flush().await
+        });
+    }
+}
@@ -0,0 +1,250 @@
+//! Apache Arrow IPC
+//!
+//! 每列映射为一个张量，行数为第一维：基本类型列为一维张量，
+//! `FixedSizeList` 列为 `[行数, 列表长度]`，更高维的张量展平为 `FixedSizeList`，
+//! 并在字段元数据中记录除第一维外的形状。缓冲区按本机字节序（小端）直接复制。
+
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Arc;
+
+use arrow::array::{Array, ArrayRef, AsArray, BooleanArray, FixedSizeListArray, PrimitiveArray};
+use arrow::buffer::{Buffer, ScalarBuffer};
+use arrow::datatypes::{
+    ArrowPrimitiveType, DataType as ArrowType, Field, Float16Type, Float32Type, Float64Type, Int16Type,
+    Int32Type, Int64Type, Int8Type, Schema, UInt16Type, UInt32Type, UInt64Type, UInt8Type,
+};
+use arrow::error::ArrowError;
+use arrow::ipc::reader::{FileReader, StreamReader};
+use arrow::ipc::writer::StreamWriter;
+use arrow::record_batch::RecordBatch;
+
+use crate::common::error::*;
+use crate::common::types::{DataType, Tensor};
+
+/// IPC文件格式的魔数
+const ARROW_FILE_MAGIC: &[u8] = b"ARROW1";
+
+/// 字段元数据中记录张量形状（不含第一维）的键
+pub const SHAPE_METADATA_KEY: &str = "unimodel.shape";
+
+/// 读取IPC流或文件，每个记录批次对应一组张量
+pub fn read_arrow_ipc(bytes: &[u8]) -> Result<Vec<HashMap<String, Tensor>>> {
+    let batches: Vec<RecordBatch> = if bytes.starts_with(ARROW_FILE_MAGIC) {
+        FileReader::try_new(Cursor::new(bytes), None)
+            .map_err(invalid_payload)?
+            .collect::<std::result::Result<_, _>>()
+            .map_err(invalid_payload)?
+    } else {
+        StreamReader::try_new(Cursor::new(bytes), None)
+            .map_err(invalid_payload)?
+            .collect::<std::result::Result<_, _>>()
+            .map_err(invalid_payload)?
+    };
+
+    batches.iter().map(batch_tensors).collect()
+}
+
+/// 读取IPC流或文件，并将所有记录批次沿第一维拼接
+pub fn read_arrow_table(bytes: &[u8]) -> Result<HashMap<String, Tensor>> {
+    let mut batches = read_arrow_ipc(bytes)?.into_iter();
+    let mut table = batches
+        .next()
+        .ok_or_else(|| UniModelError::validation("Arrow payload contains no record batches"))?;
+
+    for batch in batches {
+        for (name, tensor) in batch {
+            let column = table
+                .get_mut(&name)
+                .ok_or_else(|| UniModelError::validation(format!("Column '{}' missing from first record batch", name)))?;
+            if column.dtype != tensor.dtype || column.shape[1..] != tensor.shape[1..] {
+                return Err(UniModelError::validation(format!("Column '{}' changes type between record batches", name)));
+            }
+            column.shape[0] += tensor.shape[0];
+            column.data.extend_from_slice(&tensor.data);
+        }
+    }
+
+    Ok(table)
+}
+
+/// 将每组张量写为一个记录批次，输出IPC流
+pub fn write_arrow_ipc(batches: &[HashMap<String, Tensor>]) -> Result<Vec<u8>> {
+    let batches = batches.iter().map(tensors_batch).collect::<Result<Vec<_>>>()?;
+    let schema = match batches.first() {
+        Some(batch) => batch.schema(),
+        None => return Err(UniModelError::validation("No tensors to encode as Arrow")),
+    };
+    if batches.iter().any(|batch| batch.schema() != schema) {
+        return Err(UniModelError::validation("All record batches in an Arrow stream must share a schema"));
+    }
+
+    let mut writer = StreamWriter::try_new(Vec::new(), &schema).map_err(encode_error)?;
+    for batch in &batches {
+        writer.write(batch).map_err(encode_error)?;
+    }
+    writer.into_inner().map_err(encode_error)
+}
+
+fn batch_tensors(batch: &RecordBatch) -> Result<HashMap<String, Tensor>> {
+    let schema = batch.schema();
+    schema
+        .fields()
+        .iter()
+        .zip(batch.columns())
+        .map(|(field, column)| Ok((field.name().clone(), column_tensor(field, column.as_ref())?)))
+        .collect()
+}
+
+fn column_tensor(field: &Field, column: &dyn Array) -> Result<Tensor> {
+    let rows = column.len();
+    let size = match column.data_type() {
+        ArrowType::FixedSizeList(_, size) => *size as usize,
+        _ => {
+            let (dtype, data) = column_values(field.name(), column)?;
+            return Tensor::new(dtype, vec![rows], data);
+        }
+    };
+
+    if column.null_count() > 0 {
+        return Err(UniModelError::validation(format!("Column '{}' contains nulls", field.name())));
+    }
+    let list = column.as_fixed_size_list();
+    let values = list.values().slice(list.offset() * size, rows * size);
+    let (dtype, data) = column_values(field.name(), values.as_ref())?;
+
+    let mut shape = vec![rows];
+    match field.metadata().get(SHAPE_METADATA_KEY) {
+        Some(dims) => {
+            let dims: Vec<usize> = serde_json::from_str(dims)
+                .map_err(|e| UniModelError::validation(format!("Invalid shape metadata on column '{}': {}", field.name(), e)))?;
+            if dims.iter().product::<usize>() != size {
+                return Err(UniModelError::validation(format!(
+                    "Shape metadata {:?} on column '{}' does not match list size {}",
+                    dims, field.name(), size
+                )));
+            }
+            shape.extend(dims);
+        }
+        None => shape.push(size),
+    }
+
+    Tensor::new(dtype, shape, data)
+}
+
+/// 基本类型数组的元素类型与数据
+fn column_values(name: &str, array: &dyn Array) -> Result<(DataType, Vec<u8>)> {
+    if array.null_count() > 0 {
+        return Err(UniModelError::validation(format!("Column '{}' contains nulls", name)));
+    }
+
+    Ok(match array.data_type() {
+        ArrowType::Boolean => (DataType::Bool, array.as_boolean().values().iter().map(u8::from).collect()),
+        ArrowType::UInt8 => (DataType::U8, primitive_bytes::<UInt8Type>(array)),
+        ArrowType::Int8 => (DataType::I8, primitive_bytes::<Int8Type>(array)),
+        ArrowType::UInt16 => (DataType::U16, primitive_bytes::<UInt16Type>(array)),
+        ArrowType::Int16 => (DataType::I16, primitive_bytes::<Int16Type>(array)),
+        ArrowType::Float16 => (DataType::F16, primitive_bytes::<Float16Type>(array)),
+        ArrowType::UInt32 => (DataType::U32, primitive_bytes::<UInt32Type>(array)),
+        ArrowType::Int32 => (DataType::I32, primitive_bytes::<Int32Type>(array)),
+        ArrowType::Float32 => (DataType::F32, primitive_bytes::<Float32Type>(array)),
+        ArrowType::UInt64 => (DataType::U64, primitive_bytes::<UInt64Type>(array)),
+        ArrowType::Int64 => (DataType::I64, primitive_bytes::<Int64Type>(array)),
+        ArrowType::Float64 => (DataType::F64, primitive_bytes::<Float64Type>(array)),
+        other => {
+            return Err(UniModelError::validation(format!(
+                "Column '{}' has unsupported Arrow type {}",
+                name, other
+            )))
+        }
+    })
+}
+
+fn primitive_bytes<T: ArrowPrimitiveType>(array: &dyn Array) -> Vec<u8> {
+    array.as_primitive::<T>().values().inner().as_slice().to_vec()
+}
+
+fn tensors_batch(tensors: &HashMap<String, Tensor>) -> Result<RecordBatch> {
+    let mut names: Vec<&String> = tensors.keys().collect();
+    names.sort();
+
+    let mut rows = None;
+    let mut fields = Vec::with_capacity(names.len());
+    let mut columns = Vec::with_capacity(names.len());
+
+    for name in names {
+        let tensor = &tensors[name];
+        tensor.validate()?;
+        let (&leading, trailing) = tensor
+            .shape
+            .split_first()
+            .ok_or_else(|| UniModelError::validation(format!("Scalar tensor '{}' cannot be encoded as a column", name)))?;
+        let expected_rows = *rows.get_or_insert(leading);
+        if leading != expected_rows {
+            return Err(UniModelError::validation(format!(
+                "Tensor '{}' has {} rows, other columns have {}",
+                name, leading, expected_rows
+            )));
+        }
+
+        let values = tensor_values(tensor)?;
+        if trailing.is_empty() {
+            fields.push(Field::new(name.as_str(), values.data_type().clone(), false));
+            columns.push(values);
+            continue;
+        }
+
+        let size = trailing.iter().product::<usize>();
+        let item = Arc::new(Field::new("item", values.data_type().clone(), false));
+        let list = FixedSizeListArray::try_new(item, size as i32, values, None).map_err(encode_error)?;
+        let mut field = Field::new(name.as_str(), list.data_type().clone(), false);
+        if trailing.len() > 1 {
+            let dims = serde_json::to_string(trailing)?;
+            field = field.with_metadata(HashMap::from([(SHAPE_METADATA_KEY.to_string(), dims)]));
+        }
+        fields.push(field);
+        columns.push(Arc::new(list) as ArrayRef);
+    }
+
+    RecordBatch::try_new(Arc::new(Schema::new(fields)), columns).map_err(encode_error)
+}
+
+/// 张量数据展平为基本类型数组
+fn tensor_values(tensor: &Tensor) -> Result<ArrayRef> {
+    let data = &tensor.data;
+    let len = tensor.num_elements();
+
+    Ok(match tensor.dtype {
+        DataType::Bool => Arc::new(BooleanArray::from(data.iter().map(|&b| b != 0).collect::<Vec<_>>())),
+        DataType::U8 => primitive_array::<UInt8Type>(data, len),
+        DataType::I8 => primitive_array::<Int8Type>(data, len),
+        DataType::U16 => primitive_array::<UInt16Type>(data, len),
+        DataType::I16 => primitive_array::<Int16Type>(data, len),
+        DataType::F16 => primitive_array::<Float16Type>(data, len),
+        DataType::U32 => primitive_array::<UInt32Type>(data, len),
+        DataType::I32 => primitive_array::<Int32Type>(data, len),
+        DataType::F32 => primitive_array::<Float32Type>(data, len),
+        DataType::U64 => primitive_array::<UInt64Type>(data, len),
+        DataType::I64 => primitive_array::<Int64Type>(data, len),
+        DataType::F64 => primitive_array::<Float64Type>(data, len),
+        DataType::BF16 | DataType::F8E4M3 | DataType::F8E5M2 => {
+            return Err(UniModelError::validation(format!(
+                "Tensor dtype {:?} has no Arrow equivalent",
+                tensor.dtype
+            )))
+        }
+    })
+}
+
+fn primitive_array<T: ArrowPrimitiveType>(data: &[u8], len: usize) -> ArrayRef {
+    // from_slice_ref 复制到按Arrow要求对齐的缓冲区
+    Arc::new(PrimitiveArray::<T>::new(ScalarBuffer::new(Buffer::from_slice_ref(data), 0, len), None))
+}
+
+fn invalid_payload(e: ArrowError) -> UniModelError {
+    UniModelError::validation(format!("Invalid Arrow IPC payload: {}", e))
+}
+
+fn encode_error(e: ArrowError) -> UniModelError {
+    UniModelError::internal(format!("Failed to encode Arrow IPC: {}", e))
+}
@@ -0,0 +1,44 @@
+//! 张量数据交换格式
+
+pub mod arrow;
+pub mod npy;
+
+use std::collections::HashMap;
+
+use crate::common::error::*;
+use crate::common::types::Tensor;
+
+pub use self::arrow::{read_arrow_ipc, read_arrow_table, write_arrow_ipc};
+pub use self::npy::{read_npy, read_npz};
+
+/// 沿第一维拆分一组张量，第i组由每个张量的第i个切片组成
+///
+/// 用于把整块上传的数组拆成批量推理的多个输入，所有张量的第一维必须相同。
+pub fn unstack(tensors: &HashMap<String, Tensor>) -> Result<Vec<HashMap<String, Tensor>>> {
+    let mut rows = None;
+    for (name, tensor) in tensors {
+        let leading = *tensor.shape.first()
+            .ok_or_else(|| UniModelError::validation(format!("Scalar tensor '{}' cannot be split into a batch", name)))?;
+        if *rows.get_or_insert(leading) != leading {
+            return Err(UniModelError::validation("All tensors in a batch must have the same first dimension"));
+        }
+    }
+
+    let rows = rows.unwrap_or_default();
+    Ok((0..rows)
+        .map(|row| {
+            tensors
+                .iter()
+                .map(|(name, tensor)| {
+                    let row_bytes = tensor.data.len() / rows;
+                    let slice = Tensor {
+                        dtype: tensor.dtype,
+                        shape: tensor.shape[1..].to_vec(),
+                        data: tensor.data[row * row_bytes..(row + 1) * row_bytes].to_vec(),
+                    };
+                    (name.clone(), slice)
+                })
+                .collect()
+        })
+        .collect())
+}
@@ -0,0 +1,145 @@
+//! NumPy `.npy` / `.npz`
+//!
+//! `.npy` 布局：魔数 `\x93NUMPY`、版本号、头部长度、Python字典字面量形式的头部
+//! （`descr`、`fortran_order`、`shape`），之后是按C顺序排列的数据。
+//! `.npz` 是由多个 `.npy` 组成的zip包，条目名（去掉 `.npy`）即数组名。
+
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+
+use crate::common::error::*;
+use crate::common::types::{DataType, Tensor};
+
+const NPY_MAGIC: &[u8] = b"\x93NUMPY";
+
+/// `.npz` 解压后的总字节数上限，防止压缩炸弹
+const MAX_NPZ_BYTES: u64 = 512 * 1024 * 1024;
+
+/// 解析 `.npy`
+pub fn read_npy(bytes: &[u8]) -> Result<Tensor> {
+    parse_npy(bytes).map_err(|e| UniModelError::validation(format!("Invalid .npy payload: {}", e)))
+}
+
+/// 解析 `.npz`，返回数组名到张量的映射
+pub fn read_npz(bytes: &[u8]) -> Result<HashMap<String, Tensor>> {
+    let invalid = |e: String| UniModelError::validation(format!("Invalid .npz payload: {}", e));
+    let mut archive = zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| invalid(e.to_string()))?;
+
+    let mut tensors = HashMap::new();
+    let mut remaining = MAX_NPZ_BYTES;
+    for index in 0..archive.len() {
+        let entry = archive.by_index(index).map_err(|e| invalid(e.to_string()))?;
+        if entry.is_dir() {
+            continue;
+        }
+        let name = entry.name().trim_end_matches(".npy").to_string();
+
+        let mut data = Vec::new();
+        entry.take(remaining + 1).read_to_end(&mut data).map_err(|e| invalid(e.to_string()))?;
+        if data.len() as u64 > remaining {
+            return Err(invalid(format!("archive expands beyond {} bytes", MAX_NPZ_BYTES)));
+        }
+        remaining -= data.len() as u64;
+
+        let tensor = parse_npy(&data).map_err(|e| invalid(format!("array '{}': {}", name, e)))?;
+        tensors.insert(name, tensor);
+    }
+
+    if tensors.is_empty() {
+        return Err(invalid("archive contains no arrays".to_string()));
+    }
+    Ok(tensors)
+}
+
+fn parse_npy(bytes: &[u8]) -> std::result::Result<Tensor, String> {
+    if !bytes.starts_with(NPY_MAGIC) || bytes.len() < NPY_MAGIC.len() + 2 {
+        return Err("bad magic".to_string());
+    }
+
+    let major = bytes[NPY_MAGIC.len()];
+    let rest = &bytes[NPY_MAGIC.len() + 2..];
+    let (header_len, rest) = match major {
+        1 if rest.len() >= 2 => (u16::from_le_bytes([rest[0], rest[1]]) as usize, &rest[2..]),
+        2 | 3 if rest.len() >= 4 => (u32::from_le_bytes([rest[0], rest[1], rest[2], rest[3]]) as usize, &rest[4..]),
+        1..=3 => return Err("truncated header".to_string()),
+        other => return Err(format!("unsupported version {}", other)),
+    };
+    if rest.len() < header_len {
+        return Err("truncated header".to_string());
+    }
+
+    let header = std::str::from_utf8(&rest[..header_len]).map_err(|_| "header is not valid text".to_string())?;
+    let mut data = rest[header_len..].to_vec();
+
+    let descr = dict_value(header, "descr")
+        .and_then(quoted)
+        .ok_or_else(|| "missing 'descr'".to_string())?;
+    let (dtype, big_endian) = parse_descr(descr).ok_or_else(|| format!("unsupported dtype '{}'", descr))?;
+
+    if dict_value(header, "fortran_order").is_some_and(|v| v.starts_with("True")) {
+        return Err("Fortran-ordered arrays are not supported".to_string());
+    }
+
+    let shape = dict_value(header, "shape")
+        .and_then(parse_shape)
+        .ok_or_else(|| "missing or invalid 'shape'".to_string())?;
+
+    if big_endian {
+        data.chunks_exact_mut(dtype.size_bytes()).for_each(|element| element.reverse());
+    }
+
+    Tensor::new(dtype, shape, data).map_err(|e| e.to_string())
+}
+
+/// 头部字典中某个键之后的文本
+fn dict_value<'a>(header: &'a str, key: &str) -> Option<&'a str> {
+    ["'", "\""].iter().find_map(|quote| {
+        let pattern = format!("{q}{key}{q}", q = quote, key = key);
+        let start = header.find(&pattern)? + pattern.len();
+        Some(header[start..].trim_start().strip_prefix(':')?.trim_start())
+    })
+}
+
+/// 引号包围的字符串
+fn quoted(value: &str) -> Option<&str> {
+    let quote = value.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+    let rest = &value[1..];
+    Some(&rest[..rest.find(quote)?])
+}
+
+/// 形状元组，如 `(2, 3)`、`(4,)`、`()`
+fn parse_shape(value: &str) -> Option<Vec<usize>> {
+    let inner = value.strip_prefix('(')?;
+    inner[..inner.find(')')?]
+        .split(',')
+        .map(str::trim)
+        .filter(|dim| !dim.is_empty())
+        .map(|dim| dim.trim_end_matches('L').parse().ok())
+        .collect()
+}
+
+/// 解析 `<f4` 形式的类型描述，返回元素类型和是否为大端序
+fn parse_descr(descr: &str) -> Option<(DataType, bool)> {
+    let (big_endian, code) = match descr.as_bytes().first()? {
+        b'<' | b'=' | b'|' => (false, &descr[1..]),
+        b'>' => (true, &descr[1..]),
+        _ => (false, descr),
+    };
+
+    let dtype = match code {
+        "b1" | "?" => DataType::Bool,
+        "u1" => DataType::U8,
+        "i1" => DataType::I8,
+        "u2" => DataType::U16,
+        "i2" => DataType::I16,
+        "f2" => DataType::F16,
+        "u4" => DataType::U32,
+        "i4" => DataType::I32,
+        "f4" => DataType::F32,
+        "u8" => DataType::U64,
+        "i8" => DataType::I64,
+        "f8" => DataType::F64,
+        _ => return None,
+    };
+    Some((dtype, big_endian))
+}
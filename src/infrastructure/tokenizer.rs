@@ -0,0 +1,145 @@
+//! 模型分词器
+//!
+//! 支持HuggingFace的 `tokenizer.json` 和GGUF内嵌词表。GGUF的字节级BPE词表（`gpt2`）
+//! 按合并规则构造BPE模型；SentencePiece词表（`llama`）按词表分数用unigram算法切分，
+//! 结果与llama.cpp可能有细微差异，但token数和解码结果可用于预算与截断。
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use tokenizers::decoders::byte_level::ByteLevel as ByteLevelDecoder;
+use tokenizers::decoders::metaspace::Metaspace as MetaspaceDecoder;
+use tokenizers::models::bpe::BPE;
+use tokenizers::models::unigram::Unigram;
+use tokenizers::pre_tokenizers::byte_level::ByteLevel;
+use tokenizers::pre_tokenizers::metaspace::Metaspace;
+use tokenizers::Tokenizer;
+
+use crate::common::error::*;
+use crate::infrastructure::model_format::GgufVocab;
+
+/// GGUF的控制token类型
+const CONTROL_TOKEN_TYPE: i32 = 3;
+
+/// GGUF的未知token类型
+const UNKNOWN_TOKEN_TYPE: i32 = 2;
+
+/// 模型分词器
+pub struct ModelTokenizer {
+    inner: Tokenizer,
+    /// 编码时需要手动添加的BOS（GGUF词表），HuggingFace分词器由其后处理器添加
+    bos_token_id: Option<u32>,
+    /// 解码时可跳过的特殊token（GGUF词表）
+    special_tokens: HashSet<u32>,
+}
+
+impl std::fmt::Debug for ModelTokenizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ModelTokenizer")
+            .field("vocab_size", &self.vocab_size())
+            .field("bos_token_id", &self.bos_token_id)
+            .finish()
+    }
+}
+
+impl ModelTokenizer {
+    /// 加载HuggingFace的 `tokenizer.json`
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let inner = Tokenizer::from_file(path)
+            .map_err(|e| UniModelError::validation(format!("Failed to load tokenizer {}: {}", path.display(), e)))?;
+        Ok(Self {
+            inner,
+            bos_token_id: None,
+            special_tokens: HashSet::new(),
+        })
+    }
+
+    /// 由GGUF内嵌词表构造
+    pub fn from_gguf_vocab(vocab: GgufVocab) -> Result<Self> {
+        let invalid = |e: tokenizers::Error| UniModelError::model(format!("Invalid GGUF vocabulary: {}", e));
+        let token_type = |id: usize| vocab.token_types.get(id).copied().unwrap_or(1);
+
+        let inner = match vocab.model.as_str() {
+            "gpt2" => {
+                let ids: HashMap<String, u32> = vocab.tokens.iter()
+                    .enumerate()
+                    .map(|(id, token)| (token.clone(), id as u32))
+                    .collect();
+                let merges = vocab.merges.iter()
+                    .filter_map(|merge| merge.split_once(' '))
+                    .map(|(a, b)| (a.to_string(), b.to_string()))
+                    .collect();
+                let model = BPE::builder().vocab_and_merges(ids, merges).build().map_err(invalid)?;
+                let mut tokenizer = Tokenizer::new(model);
+                tokenizer
+                    .with_pre_tokenizer(ByteLevel::default().add_prefix_space(false))
+                    .with_decoder(ByteLevelDecoder::default());
+                tokenizer
+            }
+            "llama" => {
+                let pieces = vocab.tokens.iter()
+                    .enumerate()
+                    .map(|(id, token)| (token.clone(), vocab.scores.get(id).copied().unwrap_or(0.0) as f64))
+                    .collect();
+                let unk_id = (0..vocab.tokens.len()).find(|&id| token_type(id) == UNKNOWN_TOKEN_TYPE);
+                let model = Unigram::from(pieces, unk_id).map_err(invalid)?;
+                let mut tokenizer = Tokenizer::new(model);
+                tokenizer
+                    .with_pre_tokenizer(Metaspace::new('▁', true))
+                    .with_decoder(MetaspaceDecoder::new('▁', true));
+                tokenizer
+            }
+            other => {
+                return Err(UniModelError::model(format!("Unsupported GGUF tokenizer model '{}'", other)));
+            }
+        };
+
+        let special_tokens = (0..vocab.tokens.len())
+            .filter(|&id| token_type(id) == CONTROL_TOKEN_TYPE)
+            .map(|id| id as u32)
+            .chain(vocab.bos_token_id)
+            .chain(vocab.eos_token_id)
+            .collect();
+        Ok(Self {
+            inner,
+            bos_token_id: vocab.bos_token_id.filter(|_| vocab.add_bos_token),
+            special_tokens,
+        })
+    }
+
+    /// 词表大小（含添加的特殊token）
+    pub fn vocab_size(&self) -> usize {
+        self.inner.get_vocab_size(true)
+    }
+
+    /// 编码文本
+    pub fn encode(&self, text: &str, add_special_tokens: bool) -> Result<Vec<u32>> {
+        let encoding = self.inner.encode(text, add_special_tokens && self.bos_token_id.is_none())
+            .map_err(|e| UniModelError::internal(format!("Tokenization failed: {}", e)))?;
+        let bos = self.bos_token_id.filter(|_| add_special_tokens);
+        Ok(bos.into_iter().chain(encoding.get_ids().iter().copied()).collect())
+    }
+
+    /// 文本的token数
+    pub fn count(&self, text: &str, add_special_tokens: bool) -> Result<usize> {
+        Ok(self.encode(text, add_special_tokens)?.len())
+    }
+
+    /// 解码token序列，超出词表的token返回校验错误
+    pub fn decode(&self, ids: &[u32], skip_special_tokens: bool) -> Result<String> {
+        let vocab_size = self.vocab_size();
+        if let Some(id) = ids.iter().find(|&&id| id as usize >= vocab_size) {
+            return Err(UniModelError::validation(format!(
+                "Token id {} is outside the vocabulary of {} tokens",
+                id, vocab_size
+            )));
+        }
+        let ids = ids.iter()
+            .copied()
+            .filter(|id| !(skip_special_tokens && self.special_tokens.contains(id)))
+            .collect();
+        self.inner.decode(ids, skip_special_tokens)
+            .map_err(|e| UniModelError::internal(format!("Detokenization failed: {}", e)))
+    }
+}
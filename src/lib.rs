@@ -11,13 +11,12 @@
 //!
 //! # 快速开始
 //!
-//! ```rust
-//! use unimodel::UniModelServer;
-//! use unimodel::config::Config;
+//! ```no_run
+//! use unimodel::{Config, UniModelServer};
 //!
 //! #[tokio::main]
 //! async fn main() -> Result<(), Box<dyn std::error::Error>> {
-//!     let config = Config::from_file("config/default.yaml")?;
+//!     let config = Config::from_file_blocking("config/default.yaml")?;
 //!     let server = UniModelServer::new(config).await?;
 //!     server.start().await?;
 //!     Ok(())
@@ -34,9 +33,26 @@ pub mod common;
 // 重新导出核心类型
 pub use crate::common::error::{UniModelError, Result};
 pub use crate::domain::model::{Model, ModelInfo, ModelStatus};
-pub use crate::domain::service::{ModelManager, BatchProcessor, Scheduler};
-pub use crate::application::services::{ModelService, PredictionService};
+pub use crate::domain::service::{ModelManager, BatchProcessor, ConversionManager, Scheduler};
+pub use crate::application::services::{ModelService, OcrService, PredictionService, ResponseCache};
 pub use crate::infrastructure::configuration::{Config, ServerConfig, EngineConfig};
+pub use crate::infrastructure::security::{ApiKeyStore, QuotaManager, RateLimiter};
+pub use crate::infrastructure::storage::{DeadLetterStore, UsageStore};
+pub use crate::plugins::builtin::WasmRuntime;
+
+/// 常用类型，测试和基准测试中 `use unimodel::prelude::*` 一次导入
+pub mod prelude {
+    pub use std::sync::Arc;
+
+    pub use crate::common::error::{Result, UniModelError};
+    pub use crate::common::types::*;
+    pub use crate::domain::model::*;
+}
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
 
 // 版本信息
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -45,44 +61,212 @@ pub const NAME: &str = env!("CARGO_PKG_NAME");
 /// UniModel服务器主入口
 pub struct UniModelServer {
     config: Config,
-    model_manager: ModelManager,
-    batch_processor: BatchProcessor,
-    scheduler: Scheduler,
+    model_manager: Arc<ModelManager>,
+    batch_processor: Arc<BatchProcessor>,
+    scheduler: Arc<Scheduler>,
+    api_key_store: Arc<ApiKeyStore>,
+    quota_manager: Arc<QuotaManager>,
+    usage_store: Arc<UsageStore>,
+    dead_letter_store: Arc<DeadLetterStore>,
+    rate_limiter: Arc<RateLimiter>,
+    shutdown: CancellationToken,
 }
 
 impl UniModelServer {
+    /// 按 `server` 配置构建异步运行时：工作线程数、阻塞线程池上限、线程名和栈大小
+    pub fn build_runtime(config: &Config) -> std::io::Result<tokio::runtime::Runtime> {
+        let mut builder = tokio::runtime::Builder::new_multi_thread();
+        builder.enable_all().thread_name(config.server.thread_name.clone());
+        if let Some(worker_threads) = config.server.worker_threads {
+            builder.worker_threads(worker_threads);
+        }
+        if let Some(max_blocking_threads) = config.server.max_blocking_threads {
+            builder.max_blocking_threads(max_blocking_threads);
+        }
+        if let Some(stack_size_kb) = config.server.thread_stack_size_kb {
+            builder.thread_stack_size(stack_size_kb * 1024);
+        }
+        builder.build()
+    }
+
     /// 创建新的UniModel服务器实例
     pub async fn new(config: Config) -> Result<Self> {
-        let model_manager = ModelManager::new(&config).await?;
-        let batch_processor = BatchProcessor::new(&config).await?;
-        let scheduler = Scheduler::new(&config).await?;
+        let scheduler = Arc::new(Scheduler::new(&config).await?);
+        let model_manager = Arc::new(
+            ModelManager::new(&config).await?
+                .with_scheduler(Arc::clone(&scheduler)),
+        );
+        let dead_letter_store = Arc::new(DeadLetterStore::open(
+            &config.storage.dead_letter_path,
+            config.storage.dead_letter_max_entries,
+        ).await?);
+        let batch_processor = Arc::new(
+            BatchProcessor::new(&config).await?
+                .with_dead_letter_store(Arc::clone(&dead_letter_store))
+                .with_model_manager(Arc::clone(&model_manager)),
+        );
+        let api_key_store = Arc::new(ApiKeyStore::open(&config.security.api_key_store_path).await?);
+        let quota_manager = Arc::new(QuotaManager::open(&config.security.rate_limiting).await?);
+        let usage_store = Arc::new(UsageStore::open(
+            &config.storage.usage_store_path,
+            config.storage.usage_retention_days,
+        ).await?);
+        let rate_limiter = Arc::new(RateLimiter::new(&config.security.rate_limiting));
 
         Ok(Self {
             config,
             model_manager,
             batch_processor,
             scheduler,
+            api_key_store,
+            quota_manager,
+            usage_store,
+            dead_letter_store,
+            rate_limiter,
+            shutdown: CancellationToken::new(),
         })
     }
 
-    /// 启动服务器
+    /// 请求优雅关闭：`start` 停止接受新请求，排空队列后卸载模型并返回
+    pub fn shutdown(&self) {
+        self.shutdown.cancel();
+    }
+
+    /// 启动服务器，直到调用 `shutdown` 并完成关闭后返回
+    ///
+    /// 关闭时HTTP和gRPC服务停止接受新连接，批处理器拒绝新请求并在 `server.shutdown_timeout_secs`
+    /// 内等待已受理的请求完成、响应写回客户端，之后卸载模型、结束插件进程并把配额、用量和死信落盘。
     pub async fn start(&self) -> Result<()> {
         tracing::info!("Starting UniModel Server v{}", VERSION);
 
         // 启动各个组件
         self.scheduler.start().await?;
         self.batch_processor.start().await?;
+        self.model_manager.start_eviction_task();
+        self.model_manager.preload().await?;
+        if self.config.server.wait_for_preload {
+            // 就绪前不监听，与 `reuse_port` 配合时旧进程一直服务到新进程加载完成
+            let timeout = Duration::from_secs(self.config.server.preload_timeout_secs);
+            tracing::info!("Waiting up to {:?} for preloaded models before listening", timeout);
+            tokio::select! {
+                result = self.model_manager.wait_until_ready(timeout) => result?,
+                _ = self.shutdown.cancelled() => {
+                    self.stop().await;
+                    return Ok(());
+                }
+            }
+        }
+        let flush_interval = std::time::Duration::from_secs(
+            self.config.monitoring.metrics_collection_interval_secs,
+        );
+        self.quota_manager.start_flush_task(flush_interval);
+        self.usage_store.start_flush_task(flush_interval);
+        self.dead_letter_store.start_flush_task(flush_interval);
+        self.model_manager.plugin_manager().start_metrics_task(flush_interval);
+        infrastructure::monitoring::start_allocator_metrics_task(flush_interval);
+        if self.config.monitoring.runtime.enabled {
+            infrastructure::monitoring::start_runtime_metrics_task(std::time::Duration::from_millis(
+                self.config.monitoring.runtime.interval_ms,
+            ));
+        }
+        self.rate_limiter.start_cleanup_task();
+
+        let prediction_service = Arc::new(
+            PredictionService::new(
+                Arc::clone(&self.model_manager),
+                Arc::clone(&self.batch_processor),
+            )
+            .with_response_cache(Arc::new(ResponseCache::new(
+                &self.config.engine.memory.response_cache,
+            )))
+            .with_wasm_runtime(Arc::new(WasmRuntime::from_plugin_config(
+                self.config.plugins.plugin_configs.get(plugins::builtin::wasm_plugin::BACKEND_NAME),
+            )?)),
+        );
 
-        // 启动API服务器
-        let api_server = api::rest::server::ApiServer::new(&self.config).await?;
-        let grpc_server = api::grpc::server::GrpcServer::new(&self.config).await?;
+        let state = api::rest::handlers::AppState {
+            model_service: Arc::new(
+                ModelService::new(Arc::clone(&self.model_manager))
+                    .with_conversion_manager(Arc::new(ConversionManager::new(
+                        Arc::clone(&self.model_manager),
+                        &self.config,
+                    ))),
+            ),
+            prediction_service: Arc::clone(&prediction_service),
+            ocr_service: Arc::new(OcrService::new(Arc::clone(&prediction_service))),
+            api_key_store: Arc::clone(&self.api_key_store),
+            quota_manager: Arc::clone(&self.quota_manager),
+            usage_store: Arc::clone(&self.usage_store),
+            dead_letter_store: Arc::clone(&self.dead_letter_store),
+        };
+
+        // 启动API服务器，systemd socket activation传入的监听套接字优先于配置的端口
+        let inherited = api::listener::ListenFds::from_env()?;
+        let api_server = api::rest::server::ApiServer::new(
+            &self.config,
+            state.clone(),
+            Arc::clone(&self.rate_limiter),
+            &inherited,
+        ).await?;
+        let grpc_server = api::grpc::server::GrpcServer::new(
+            &self.config,
+            state,
+            Arc::clone(&self.rate_limiter),
+            &inherited,
+        ).await?;
 
         // 并行启动HTTP和gRPC服务器
-        tokio::try_join!(
-            api_server.serve(),
-            grpc_server.serve()
-        )?;
+        let servers = async {
+            tokio::try_join!(
+                api_server.serve(self.shutdown.clone()),
+                grpc_server.serve(self.shutdown.clone())
+            )
+        };
+        tokio::pin!(servers);
+        // 服务器只会在出错或收到关闭请求后返回
+        let stopped = tokio::select! {
+            result = &mut servers => {
+                result?;
+                true
+            }
+            _ = self.shutdown.cancelled() => false,
+        };
+
+        let timeout = Duration::from_secs(self.config.server.shutdown_timeout_secs);
+        let deadline = tokio::time::Instant::now() + timeout;
+        tracing::info!("Shutting down, draining requests for up to {:?}", timeout);
+        self.batch_processor.drain(timeout).await;
+        if !stopped {
+            // 等待排空的响应写回客户端，超过期限的连接直接关闭
+            match tokio::time::timeout_at(deadline, &mut servers).await {
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => tracing::error!("Server error during shutdown: {}", e),
+                Err(_) => tracing::warn!("Connections still open at the shutdown deadline, closing them"),
+            }
+        }
 
+        self.stop().await;
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// 排空后的清理：停止批处理器，卸载模型，结束插件进程，把配额、用量和死信落盘
+    async fn stop(&self) {
+        if let Err(e) = self.batch_processor.stop().await {
+            tracing::warn!("Failed to stop batch processor: {}", e);
+        }
+        let unloaded = self.model_manager.unload_all().await;
+        tracing::info!("Unloaded {} model(s)", unloaded.len());
+        self.model_manager.plugin_manager().shutdown();
+
+        if let Err(e) = self.quota_manager.flush().await {
+            tracing::error!("Failed to flush quota usage: {}", e);
+        }
+        if let Err(e) = self.usage_store.flush().await {
+            tracing::error!("Failed to flush usage store: {}", e);
+        }
+        if let Err(e) = self.dead_letter_store.flush().await {
+            tracing::error!("Failed to flush dead-letter store: {}", e);
+        }
+        tracing::info!("UniModel Server stopped");
+    }
+}
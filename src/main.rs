@@ -1,24 +1,50 @@
 //! UniModel服务器主程序
 
 use std::env;
-use tracing::{info, error};
+use std::path::Path;
+use std::sync::Arc;
+use tracing::{info, error, warn};
+use unimodel::application::bench::{run_bench, BenchOptions, BENCH_COMMAND};
+use unimodel::plugins::manager::plugin_host::{run_plugin_host, PLUGIN_HOST_COMMAND};
 use unimodel::{UniModelServer, Config, VERSION};
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// 使用jemalloc作为全局分配器，其统计见 `GET /debug/memory` 和 `unimodel_allocator_*` 指标
+#[global_allocator]
+static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 初始化日志系统
     init_tracing()?;
 
-    info!("UniModel Server v{} starting...", VERSION);
-
     // 解析命令行参数
     let args: Vec<String> = env::args().collect();
+
+    // 服务器为隔离的动态库插件启动的子进程
+    if args.get(1).map(String::as_str) == Some(PLUGIN_HOST_COMMAND) {
+        let library = args.get(2).ok_or("plugin-host needs the plugin library path")?;
+        tokio::runtime::Runtime::new()?.block_on(run_plugin_host(Path::new(library)))?;
+        return Ok(());
+    }
+
+    // 对运行中的服务器压测
+    if args.get(1).map(String::as_str) == Some(BENCH_COMMAND) {
+        let options = BenchOptions::parse(&args[2..])?;
+        let report = tokio::runtime::Runtime::new()?.block_on(run_bench(&options))?;
+        print!("{}", report);
+        if let Some(output) = &options.output {
+            std::fs::write(output, serde_json::to_string_pretty(&report)?)?;
+            info!("Report written to {}", output.display());
+        }
+        return Ok(());
+    }
+
+    info!("UniModel Server v{} starting...", VERSION);
     let config_path = args.get(1)
         .map(String::as_str)
         .unwrap_or("config/default.yaml");
 
-    // 加载配置
-    let config = Config::from_file(config_path)
+    // 加载配置，运行时按配置构建
+    let config = Config::from_file_blocking(config_path)
         .map_err(|e| {
             error!("Failed to load config from {}: {}", config_path, e);
             e
@@ -26,41 +52,50 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     info!("Configuration loaded from: {}", config_path);
 
-    // 创建并启动服务器
-    let server = UniModelServer::new(config).await?;
+    let runtime = UniModelServer::build_runtime(&config)?;
+    runtime.block_on(serve(config))
+}
+
+/// 创建并启动服务器
+async fn serve(config: Config) -> Result<(), Box<dyn std::error::Error>> {
+    let server = Arc::new(UniModelServer::new(config).await?);
 
     // 注册信号处理器
-    setup_signal_handlers().await;
+    setup_signal_handlers(Arc::clone(&server)).await;
 
-    // 启动服务器
+    // 启动服务器，收到关闭信号后排空请求再返回
     if let Err(e) = server.start().await {
-        error!("Server failed to start: {}", e);
+        error!("Server failed: {}", e);
         std::process::exit(1);
     }
 
+    info!("Shutdown complete");
     Ok(())
 }
 
 /// 初始化分布式追踪
+///
+/// 以 `RUSTFLAGS="--cfg tokio_unstable"` 构建时同时启用tokio-console，监听地址由 `TOKIO_CONSOLE_BIND` 指定。
+/// 日志过滤只作用于日志输出，不影响tokio-console需要的运行时事件。
 fn init_tracing() -> Result<(), Box<dyn std::error::Error>> {
-    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
+    use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, Layer};
 
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "unimodel=info,tower_http=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| "unimodel=info,tower_http=debug".into());
+    let registry = tracing_subscriber::registry()
+        .with(tracing_subscriber::fmt::layer().with_filter(filter));
+    #[cfg(tokio_unstable)]
+    let registry = registry.with(console_subscriber::spawn());
+    registry.init();
 
     Ok(())
 }
 
-/// 设置信号处理器用于优雅关闭
-async fn setup_signal_handlers() {
+/// 设置信号处理器用于优雅关闭，排空期间再次收到信号时立即退出
+async fn setup_signal_handlers(server: Arc<UniModelServer>) {
     use tokio::signal;
 
-    tokio::spawn(async {
+    tokio::spawn(async move {
         let mut term = signal::unix::signal(signal::unix::SignalKind::terminate())
             .expect("Failed to register SIGTERM handler");
         let mut int = signal::unix::signal(signal::unix::SignalKind::interrupt())
@@ -76,6 +111,13 @@ async fn setup_signal_handlers() {
         }
 
         // 触发优雅关闭
-        std::process::exit(0);
+        server.shutdown();
+
+        tokio::select! {
+            _ = term.recv() => {}
+            _ = int.recv() => {}
+        }
+        warn!("Received a second signal, exiting without draining");
+        std::process::exit(1);
     });
 }
\ No newline at end of file
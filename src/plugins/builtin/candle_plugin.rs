@@ -0,0 +1,293 @@
+//! candle后端（Apple Silicon Metal）
+//!
+//! 用candle在Metal GPU上运行GGUF量化的llama系列模型，macOS开发机无需CUDA即可在本地跑通完整链路；
+//! 同一后端也可在CPU或CUDA（candle以cuda特性编译时）上运行。
+//! 权重只加载一次，每个序列克隆一份模型获得独立的KV缓存（量化权重在克隆间共享）。
+//! Metal只有一个命令队列，`step` 依次为各序列前进一个token。
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use candle_core::quantized::gguf_file;
+use candle_core::{DType, Device, Tensor};
+use candle_transformers::models::quantized_llama;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use tracing::info;
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{DeviceConfig, DeviceType, ModelConfig};
+use crate::domain::service::conversion::ModelFormat;
+use crate::domain::service::generation::{apply_logit_bias, sample_token, token_logprob};
+use crate::domain::service::grammar::{apply_grammar_mask, Grammar, GrammarMatcher};
+use crate::infrastructure::model_format::GgufHeader;
+use crate::infrastructure::tokenizer::ModelTokenizer;
+use crate::plugins::interface::{BackendCapabilities, DecodeStep, IterativeDecoder, ModelWeights};
+
+/// 后端名称，对应 `ModelConfig::backend`
+pub const BACKEND_NAME: &str = "candle";
+
+fn candle_error(e: candle_core::Error) -> UniModelError {
+    UniModelError::plugin(format!("candle: {}", e))
+}
+
+/// 按设备配置选择candle设备
+pub fn candle_device(device: &DeviceConfig) -> Result<Device> {
+    let ordinal = device.device_ids.first().copied().unwrap_or(0) as usize;
+    match device.device_type {
+        DeviceType::CPU => Ok(Device::Cpu),
+        DeviceType::Metal => Device::new_metal(ordinal).map_err(|e| {
+            UniModelError::plugin_unavailable(format!("Metal device {} is unavailable: {}", ordinal, e))
+        }),
+        DeviceType::CUDA => Device::new_cuda(ordinal).map_err(|e| {
+            UniModelError::plugin_unavailable(format!("CUDA device {} is unavailable: {}", ordinal, e))
+        }),
+        ref other => Err(UniModelError::validation(format!("candle backend does not support {:?} devices", other))),
+    }
+}
+
+/// candle后端
+#[derive(Debug, Clone, Default)]
+pub struct CandleBackend;
+
+impl CandleBackend {
+    /// 创建后端
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// 后端能力：GGUF格式，CPU、Metal或CUDA
+    pub fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            formats: vec![ModelFormat::Gguf],
+            devices: vec![DeviceType::CPU, DeviceType::Metal, DeviceType::CUDA],
+            ..BackendCapabilities::undeclared(BACKEND_NAME)
+        }
+    }
+
+    /// 加载GGUF模型，返回可供连续批处理使用的解码器
+    ///
+    /// 分词器优先使用 `tokenizer_path`，否则使用GGUF内嵌词表。读取权重涉及大量IO，应在阻塞线程池中调用。
+    pub fn load(&self, config: &ModelConfig, weights: &ModelWeights) -> Result<CandleModel> {
+        let path = match weights {
+            ModelWeights::File(path) if path.extension().is_some_and(|ext| ext == "gguf") => path,
+            _ => return Err(UniModelError::model(format!(
+                "candle backend needs a GGUF file, got '{}'",
+                config.model_path
+            ))),
+        };
+        let device = candle_device(&config.device)?;
+
+        let header = GgufHeader::read_with_vocab(path)?;
+        let vocab = header.vocab();
+        let eos = vocab.as_ref().and_then(|v| v.eos_token_id);
+        let tokenizer = match (&config.tokenizer_path, vocab) {
+            (Some(tokenizer_path), _) => ModelTokenizer::from_file(tokenizer_path)?,
+            (None, Some(vocab)) => ModelTokenizer::from_gguf_vocab(vocab)?,
+            (None, None) => return Err(UniModelError::model(format!(
+                "'{}' has no embedded vocabulary and no tokenizer_path is set",
+                config.model_path
+            ))),
+        };
+
+        let mut file = std::fs::File::open(path)?;
+        let content = gguf_file::Content::read(&mut file).map_err(candle_error)?;
+        let model = quantized_llama::ModelWeights::from_gguf(content, &mut file, &device).map_err(candle_error)?;
+
+        // 语法约束需要每个token的文本
+        let vocab_texts = (0..tokenizer.vocab_size() as u32)
+            .map(|id| tokenizer.decode(&[id], false).unwrap_or_default())
+            .collect();
+        info!("candle loaded {} on {:?}", path.display(), device);
+
+        Ok(CandleModel {
+            base: model,
+            device,
+            tokenizer,
+            vocab_texts,
+            eos,
+            context_length: header.context_length().unwrap_or(4096) as usize,
+            sequences: Mutex::new(HashMap::new()),
+        })
+    }
+}
+
+/// 序列状态
+struct CandleSequence {
+    /// 持有该序列KV缓存的模型副本
+    model: quantized_llama::ModelWeights,
+    /// 已生成的token
+    generated: Vec<u32>,
+    /// 已写入KV缓存的token数
+    position: usize,
+    /// 下一步采样所用的logits
+    logits: Vec<f32>,
+    /// 已输出的文本字节数
+    emitted: usize,
+    temperature: f32,
+    top_k: usize,
+    top_p: f32,
+    logit_bias: HashMap<u32, f32>,
+    top_logprobs: Option<usize>,
+    grammar: Option<GrammarMatcher>,
+    rng: StdRng,
+}
+
+/// 已加载的GGUF模型
+pub struct CandleModel {
+    base: quantized_llama::ModelWeights,
+    device: Device,
+    tokenizer: ModelTokenizer,
+    vocab_texts: Vec<String>,
+    eos: Option<u32>,
+    context_length: usize,
+    sequences: Mutex<HashMap<String, CandleSequence>>,
+}
+
+impl std::fmt::Debug for CandleModel {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CandleModel")
+            .field("device", &self.device)
+            .field("context_length", &self.context_length)
+            .field("sequences", &self.sequences.lock().len())
+            .finish()
+    }
+}
+
+impl CandleModel {
+    /// 输入token并返回最后一个位置的logits
+    fn forward(&self, model: &mut quantized_llama::ModelWeights, tokens: &[u32], position: usize) -> Result<Vec<f32>> {
+        let input = Tensor::new(tokens, &self.device)
+            .and_then(|t| t.unsqueeze(0))
+            .map_err(candle_error)?;
+        model.forward(&input, position)
+            .and_then(|logits| logits.squeeze(0))
+            .and_then(|logits| logits.to_dtype(DType::F32))
+            .and_then(|logits| logits.to_vec1::<f32>())
+            .map_err(candle_error)
+    }
+
+    fn token_text(&self, token: u32) -> String {
+        self.vocab_texts.get(token as usize).cloned().unwrap_or_default()
+    }
+
+    /// 为序列前进一个token
+    fn advance(&self, sequence_id: &str, sequence: &mut CandleSequence) -> Result<DecodeStep> {
+        let mut logits = std::mem::take(&mut sequence.logits);
+        apply_logit_bias(&mut logits, &sequence.logit_bias);
+        if let Some(matcher) = &sequence.grammar {
+            apply_grammar_mask(&mut logits, matcher, |t| self.token_text(t), self.eos);
+        }
+        let token = sample_token(&logits, sequence.temperature, sequence.top_k, sequence.top_p, &mut sequence.rng);
+        let logprob = sequence.top_logprobs
+            .map(|top| token_logprob(&logits, token, top, |t| self.token_text(t)));
+
+        let mut step = DecodeStep {
+            sequence_id: sequence_id.to_string(),
+            token: String::new(),
+            finished: true,
+            stop_sequence: None,
+            logprob,
+            candidates: Vec::new(),
+            draft_tokens: 0,
+        };
+        if Some(token) == self.eos {
+            return Ok(step);
+        }
+
+        // 按已生成的全部token解码，末尾不完整的多字节字符留到下一步输出
+        sequence.generated.push(token);
+        let text = self.tokenizer.decode(&sequence.generated, true)?;
+        let complete = text.trim_end_matches('\u{FFFD}');
+        if complete.len() > sequence.emitted && complete.is_char_boundary(sequence.emitted) {
+            step.token = complete[sequence.emitted..].to_string();
+            sequence.emitted = complete.len();
+        }
+        if let Some(matcher) = &mut sequence.grammar {
+            matcher.accept(&step.token);
+        }
+
+        sequence.logits = self.forward(&mut sequence.model, &[token], sequence.position)?;
+        sequence.position += 1;
+        step.finished = sequence.position >= self.context_length;
+        Ok(step)
+    }
+}
+
+impl IterativeDecoder for CandleModel {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()> {
+        let tokens = self.tokenizer.encode(prompt, true)?;
+        if tokens.is_empty() {
+            return Err(UniModelError::validation("Prompt is empty"));
+        }
+        if tokens.len() >= self.context_length {
+            return Err(UniModelError::validation(format!(
+                "Prompt has {} tokens, the model context holds {}",
+                tokens.len(),
+                self.context_length
+            )));
+        }
+        if self.sequences.lock().contains_key(sequence_id) {
+            return Err(UniModelError::internal(format!("Sequence {} already exists", sequence_id)));
+        }
+        let grammar = match &parameters.grammar {
+            Some(source) => Some(GrammarMatcher::new(Arc::new(Grammar::parse(source)?))),
+            None => None,
+        };
+
+        let mut model = self.base.clone();
+        let logits = self.forward(&mut model, &tokens, 0)?;
+        self.sequences.lock().insert(sequence_id.to_string(), CandleSequence {
+            model,
+            generated: Vec::new(),
+            position: tokens.len(),
+            logits,
+            emitted: 0,
+            temperature: parameters.temperature.unwrap_or(1.0),
+            top_k: parameters.top_k.map_or(0, |k| k as usize),
+            top_p: parameters.top_p.unwrap_or(1.0),
+            logit_bias: parameters.logit_bias.clone(),
+            top_logprobs: parameters.logprobs.then(|| parameters.top_logprobs.unwrap_or(0) as usize),
+            grammar,
+            rng: match parameters.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+        });
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> Result<Vec<DecodeStep>> {
+        let mut sequences = self.sequences.lock();
+        let mut steps = Vec::with_capacity(sequence_ids.len());
+        for id in sequence_ids {
+            if let Some(sequence) = sequences.get_mut(id) {
+                steps.push(self.advance(id, sequence)?);
+            }
+        }
+        Ok(steps)
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.sequences.lock().remove(sequence_id);
+    }
+
+    fn supports_logit_bias(&self) -> bool {
+        true
+    }
+
+    fn supports_logprobs(&self) -> bool {
+        true
+    }
+
+    fn supports_grammar(&self) -> bool {
+        true
+    }
+}
@@ -0,0 +1,1005 @@
+//! llama.cpp（GGUF）后端
+//!
+//! 运行时通过 `libloading` 加载 `libllama`，调用其C API执行推理，不需要在编译期链接llama.cpp；
+//! CUDA/Metal支持取决于所加载的库的编译选项。所有序列共享一个llama上下文，每个序列占用一个
+//! `seq_id`，各自的KV缓存在上下文中按序列隔离，序列结束时释放。
+//! 采样在Rust侧完成，支持温度、top-k/top-p、种子、logit偏置、对数概率和语法约束。
+//! 模型可作为投机解码的草稿模型（按贪心提出草稿）或目标模型（一次前向验证全部草稿）。
+//!
+//! 结构体布局对应 llama.cpp b3600 前后的 `llama.h`。参数结构体按值在库和本文件之间传递，
+//! 布局不一致会破坏内存，因此加载库时按导出符号确认其版本落在该区间内，不一致时拒绝加载。
+
+use std::collections::HashMap;
+use std::ffi::{c_char, CString};
+use std::path::Path;
+use std::sync::Arc;
+
+use libloading::Library;
+use parking_lot::Mutex;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use tracing::{debug, info};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{DeviceType, ModelConfig, QuantizationType};
+use crate::domain::service::conversion::ModelFormat;
+use crate::domain::service::generation::{
+    accept_draft, apply_logit_bias, argmax, residual_distribution, sample_distribution, sample_token,
+    sampling_distribution, token_logprob,
+};
+use crate::domain::service::grammar::{apply_grammar_mask, Grammar, GrammarMatcher};
+use crate::infrastructure::model_format::GgufHeader;
+use crate::plugins::interface::{
+    BackendCapabilities, DecodeStep, DraftModel, DraftToken, IterativeDecoder, ModelWeights, Quantizer,
+    SpeculativeTarget, Verification,
+};
+
+/// 后端名称，对应 `ModelConfig::backend`
+pub const BACKEND_NAME: &str = "gguf";
+
+/// 本文件的结构体布局适用的库须导出的符号：LoRA适配器接口在b3400前后加入
+const ABI_SINCE_SYMBOL: &str = "llama_lora_adapter_init";
+
+/// 本文件的结构体布局适用的库不能导出的符号：b3680前后的采样接口重构加入该函数，
+/// 同时从 `llama_context_params` 中移除了 `seed`
+const ABI_UNTIL_SYMBOL: &str = "llama_sampler_init";
+
+/// 未指定时的上下文长度上限，避免按模型的最大上下文一次性申请过大的KV缓存
+const DEFAULT_MAX_CONTEXT: u32 = 8192;
+
+type LlamaToken = i32;
+
+#[repr(C)]
+struct LlamaModelHandle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+struct LlamaContextHandle {
+    _private: [u8; 0],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct LlamaModelParams {
+    n_gpu_layers: i32,
+    split_mode: i32,
+    main_gpu: i32,
+    tensor_split: *const f32,
+    rpc_servers: *const c_char,
+    progress_callback: *const std::ffi::c_void,
+    progress_callback_user_data: *mut std::ffi::c_void,
+    kv_overrides: *const std::ffi::c_void,
+    vocab_only: bool,
+    use_mmap: bool,
+    use_mlock: bool,
+    check_tensors: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct LlamaContextParams {
+    seed: u32,
+    n_ctx: u32,
+    n_batch: u32,
+    n_ubatch: u32,
+    n_seq_max: u32,
+    n_threads: i32,
+    n_threads_batch: i32,
+    rope_scaling_type: i32,
+    pooling_type: i32,
+    attention_type: i32,
+    rope_freq_base: f32,
+    rope_freq_scale: f32,
+    yarn_ext_factor: f32,
+    yarn_attn_factor: f32,
+    yarn_beta_fast: f32,
+    yarn_beta_slow: f32,
+    yarn_orig_ctx: u32,
+    defrag_thold: f32,
+    cb_eval: *const std::ffi::c_void,
+    cb_eval_user_data: *mut std::ffi::c_void,
+    type_k: i32,
+    type_v: i32,
+    logits_all: bool,
+    embeddings: bool,
+    offload_kqv: bool,
+    flash_attn: bool,
+    abort_callback: *const std::ffi::c_void,
+    abort_callback_data: *mut std::ffi::c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct LlamaBatch {
+    n_tokens: i32,
+    token: *mut LlamaToken,
+    embd: *mut f32,
+    pos: *mut i32,
+    n_seq_id: *mut i32,
+    seq_id: *mut *mut i32,
+    logits: *mut i8,
+    all_pos_0: i32,
+    all_pos_1: i32,
+    all_seq_id: i32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct LlamaQuantizeParams {
+    nthread: i32,
+    ftype: i32,
+    output_tensor_type: i32,
+    token_embedding_type: i32,
+    allow_requantize: bool,
+    quantize_output_tensor: bool,
+    only_copy: bool,
+    pure: bool,
+    keep_split: bool,
+    imatrix: *mut std::ffi::c_void,
+    kv_overrides: *mut std::ffi::c_void,
+}
+
+/// `llama_ftype` 取值
+const LLAMA_FTYPE_MOSTLY_F16: i32 = 1;
+const LLAMA_FTYPE_MOSTLY_Q8_0: i32 = 7;
+const LLAMA_FTYPE_MOSTLY_Q4_K_M: i32 = 15;
+
+/// 从动态库解析出的llama.cpp函数
+struct LlamaApi {
+    backend_init: unsafe extern "C" fn(),
+    model_default_params: unsafe extern "C" fn() -> LlamaModelParams,
+    context_default_params: unsafe extern "C" fn() -> LlamaContextParams,
+    load_model_from_file: unsafe extern "C" fn(*const c_char, LlamaModelParams) -> *mut LlamaModelHandle,
+    free_model: unsafe extern "C" fn(*mut LlamaModelHandle),
+    new_context_with_model: unsafe extern "C" fn(*mut LlamaModelHandle, LlamaContextParams) -> *mut LlamaContextHandle,
+    free: unsafe extern "C" fn(*mut LlamaContextHandle),
+    n_vocab: unsafe extern "C" fn(*const LlamaModelHandle) -> i32,
+    n_ctx: unsafe extern "C" fn(*const LlamaContextHandle) -> u32,
+    token_is_eog: unsafe extern "C" fn(*const LlamaModelHandle, LlamaToken) -> bool,
+    tokenize: unsafe extern "C" fn(*const LlamaModelHandle, *const c_char, i32, *mut LlamaToken, i32, bool, bool) -> i32,
+    token_to_piece: unsafe extern "C" fn(*const LlamaModelHandle, LlamaToken, *mut c_char, i32, i32, bool) -> i32,
+    batch_init: unsafe extern "C" fn(i32, i32, i32) -> LlamaBatch,
+    batch_free: unsafe extern "C" fn(LlamaBatch),
+    decode: unsafe extern "C" fn(*mut LlamaContextHandle, LlamaBatch) -> i32,
+    get_logits_ith: unsafe extern "C" fn(*mut LlamaContextHandle, i32) -> *mut f32,
+    kv_cache_seq_rm: unsafe extern "C" fn(*mut LlamaContextHandle, i32, i32, i32) -> bool,
+    model_quantize_default_params: unsafe extern "C" fn() -> LlamaQuantizeParams,
+    model_quantize: unsafe extern "C" fn(*const c_char, *const c_char, *const LlamaQuantizeParams) -> u32,
+    // 函数指针依赖库保持加载，须最后释放
+    _library: Library,
+}
+
+impl std::fmt::Debug for LlamaApi {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LlamaApi").finish_non_exhaustive()
+    }
+}
+
+unsafe fn symbol<T: Copy>(library: &Library, name: &str) -> Result<T> {
+    let symbol = library.get::<T>(name.as_bytes())
+        .map_err(|e| UniModelError::plugin(format!("llama.cpp library lacks {}: {}", name, e)))?;
+    Ok(*symbol)
+}
+
+/// 按导出符号确认库的版本与本文件的结构体布局一致
+fn check_abi(library: &Library, path: &str) -> Result<()> {
+    // SAFETY: 只查找符号是否存在，不调用
+    let exports = |name: &str| unsafe { library.get::<unsafe extern "C" fn()>(name.as_bytes()).is_ok() };
+    if !exports(ABI_SINCE_SYMBOL) || exports(ABI_UNTIL_SYMBOL) {
+        return Err(UniModelError::plugin_unavailable(format!(
+            "'{}' is not a llama.cpp build compatible with this server (needs b3600 or nearby: exports {} but not {})",
+            path, ABI_SINCE_SYMBOL, ABI_UNTIL_SYMBOL
+        )));
+    }
+    Ok(())
+}
+
+impl LlamaApi {
+    fn open(path: &str) -> Result<Self> {
+        // SAFETY: 加载的是llama.cpp共享库，check_abi确认了符号签名与本文件声明的布局一致
+        unsafe {
+            let library = Library::new(path)
+                .map_err(|e| UniModelError::plugin_unavailable(format!("Cannot load llama.cpp from '{}': {}", path, e)))?;
+            check_abi(&library, path)?;
+            let api = Self {
+                backend_init: symbol(&library, "llama_backend_init")?,
+                model_default_params: symbol(&library, "llama_model_default_params")?,
+                context_default_params: symbol(&library, "llama_context_default_params")?,
+                load_model_from_file: symbol(&library, "llama_load_model_from_file")?,
+                free_model: symbol(&library, "llama_free_model")?,
+                new_context_with_model: symbol(&library, "llama_new_context_with_model")?,
+                free: symbol(&library, "llama_free")?,
+                n_vocab: symbol(&library, "llama_n_vocab")?,
+                n_ctx: symbol(&library, "llama_n_ctx")?,
+                token_is_eog: symbol(&library, "llama_token_is_eog")?,
+                tokenize: symbol(&library, "llama_tokenize")?,
+                token_to_piece: symbol(&library, "llama_token_to_piece")?,
+                batch_init: symbol(&library, "llama_batch_init")?,
+                batch_free: symbol(&library, "llama_batch_free")?,
+                decode: symbol(&library, "llama_decode")?,
+                get_logits_ith: symbol(&library, "llama_get_logits_ith")?,
+                kv_cache_seq_rm: symbol(&library, "llama_kv_cache_seq_rm")?,
+                model_quantize_default_params: symbol(&library, "llama_model_quantize_default_params")?,
+                model_quantize: symbol(&library, "llama_model_quantize")?,
+                _library: library,
+            };
+            (api.backend_init)();
+            Ok(api)
+        }
+    }
+}
+
+/// 后端配置，取自 `plugins.plugin_configs.gguf`
+#[derive(Debug, Clone, Deserialize)]
+pub struct LlamaCppConfig {
+    /// libllama 的路径
+    #[serde(default = "default_library_path")]
+    pub library_path: String,
+    /// 推理线程数，默认为可用CPU核数
+    #[serde(default)]
+    pub n_threads: Option<i32>,
+    /// 上下文长度上限（所有序列共享）
+    #[serde(default = "default_max_context")]
+    pub max_context: u32,
+    /// 在GPU设备上时卸载到GPU的层数，默认全部
+    #[serde(default = "default_gpu_layers")]
+    pub n_gpu_layers: i32,
+}
+
+fn default_library_path() -> String {
+    if cfg!(target_os = "macos") {
+        "libllama.dylib".to_string()
+    } else if cfg!(target_os = "windows") {
+        "llama.dll".to_string()
+    } else {
+        "libllama.so".to_string()
+    }
+}
+
+fn default_max_context() -> u32 {
+    DEFAULT_MAX_CONTEXT
+}
+
+fn default_gpu_layers() -> i32 {
+    999
+}
+
+impl Default for LlamaCppConfig {
+    fn default() -> Self {
+        Self {
+            library_path: default_library_path(),
+            n_threads: None,
+            max_context: default_max_context(),
+            n_gpu_layers: default_gpu_layers(),
+        }
+    }
+}
+
+/// llama.cpp后端
+#[derive(Debug, Clone)]
+pub struct LlamaCppBackend {
+    api: Arc<LlamaApi>,
+    config: LlamaCppConfig,
+}
+
+impl LlamaCppBackend {
+    /// 加载llama.cpp动态库
+    pub fn new(config: LlamaCppConfig) -> Result<Self> {
+        let api = Arc::new(LlamaApi::open(&config.library_path)?);
+        info!("Loaded llama.cpp from {}", config.library_path);
+        Ok(Self { api, config })
+    }
+
+    /// 按插件配置创建后端，未配置时使用默认值
+    pub fn from_plugin_config(value: Option<&serde_json::Value>) -> Result<Self> {
+        let config = match value {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| UniModelError::config(format!("Invalid gguf plugin config: {}", e)))?,
+            None => LlamaCppConfig::default(),
+        };
+        Self::new(config)
+    }
+
+    /// 后端能力：GGUF格式，CPU或库编译时支持的GPU，可离线量化
+    pub fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            formats: vec![ModelFormat::Gguf],
+            devices: vec![DeviceType::CPU, DeviceType::CUDA, DeviceType::ROCm, DeviceType::Metal],
+            quantization: vec![QuantizationType::INT8, QuantizationType::INT4, QuantizationType::FP16],
+            ..BackendCapabilities::undeclared(BACKEND_NAME)
+        }
+    }
+
+    /// 加载GGUF模型，返回可供连续批处理使用的解码器
+    pub fn load(&self, config: &ModelConfig, weights: &ModelWeights) -> Result<LlamaCppModel> {
+        let path = match weights {
+            ModelWeights::File(path) if path.extension().is_some_and(|ext| ext == "gguf") => path,
+            _ => return Err(UniModelError::model(format!(
+                "llama.cpp backend needs a GGUF file, got '{}'",
+                config.model_path
+            ))),
+        };
+        let header = GgufHeader::read(path)?;
+        let n_ctx = header.context_length()
+            .map_or(self.config.max_context, |len| (len as u32).min(self.config.max_context));
+        let n_seq_max = config.batch_config.max_batch_size.max(1);
+        let n_threads = self.config.n_threads.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(4, |n| n.get() as i32)
+        });
+
+        let c_path = path_to_cstring(path)?;
+        // SAFETY: 参数结构体由库返回的默认值修改而来，指针在调用期间有效
+        let (model, ctx) = unsafe {
+            let mut model_params = (self.api.model_default_params)();
+            match config.device.device_type {
+                DeviceType::CPU => model_params.n_gpu_layers = 0,
+                _ => {
+                    model_params.n_gpu_layers = self.config.n_gpu_layers;
+                    model_params.main_gpu = config.device.device_ids.first().copied().unwrap_or(0) as i32;
+                }
+            }
+            let model = (self.api.load_model_from_file)(c_path.as_ptr(), model_params);
+            if model.is_null() {
+                return Err(UniModelError::model(format!("llama.cpp failed to load '{}'", path.display())));
+            }
+
+            let mut ctx_params = (self.api.context_default_params)();
+            ctx_params.n_ctx = n_ctx;
+            ctx_params.n_batch = n_ctx.min(2048).max(n_seq_max);
+            ctx_params.n_seq_max = n_seq_max;
+            ctx_params.n_threads = n_threads;
+            ctx_params.n_threads_batch = n_threads;
+            ctx_params.offload_kqv = config.optimization.kv_cache && config.device.device_type != DeviceType::CPU;
+            let ctx = (self.api.new_context_with_model)(model, ctx_params);
+            if ctx.is_null() {
+                (self.api.free_model)(model);
+                return Err(UniModelError::out_of_memory(format!(
+                    "llama.cpp failed to create a {}-token context for '{}'",
+                    n_ctx,
+                    path.display()
+                )));
+            }
+            (model, ctx)
+        };
+
+        LlamaCppModel::new(Arc::clone(&self.api), model, ctx, n_seq_max)
+    }
+
+    /// 把F32/F16的GGUF量化为目标精度的量化器
+    pub fn quantizer(&self) -> LlamaCppQuantizer {
+        LlamaCppQuantizer {
+            api: Arc::clone(&self.api),
+        }
+    }
+}
+
+fn path_to_cstring(path: &Path) -> Result<CString> {
+    CString::new(path.display().to_string())
+        .map_err(|_| UniModelError::validation(format!("Path '{}' contains a NUL byte", path.display())))
+}
+
+/// 序列的采样状态
+#[derive(Debug)]
+struct LlamaSequence {
+    slot: i32,
+    /// 下一个待写入KV缓存的位置
+    position: i32,
+    /// 下一步输入的token：提示的最后一个token或上一步采样的token
+    next_input: LlamaToken,
+    temperature: f32,
+    top_k: usize,
+    top_p: f32,
+    logit_bias: HashMap<u32, f32>,
+    top_logprobs: Option<usize>,
+    grammar: Option<GrammarMatcher>,
+    rng: StdRng,
+    /// 尚未组成完整UTF-8字符的字节
+    pending: Vec<u8>,
+    /// 作为草稿模型时最近一次提出、尚未提交的草稿
+    draft: Option<LlamaDraft>,
+}
+
+/// 草稿模型提出的一段草稿
+#[derive(Debug)]
+struct LlamaDraft {
+    /// 提出草稿前的 `position`
+    start: i32,
+    tokens: Vec<LlamaToken>,
+}
+
+#[derive(Debug)]
+struct LlamaState {
+    ctx: *mut LlamaContextHandle,
+    batch: LlamaBatch,
+    batch_capacity: usize,
+    free_slots: Vec<i32>,
+    sequences: HashMap<String, LlamaSequence>,
+}
+
+impl LlamaState {
+    /// SAFETY: 调用方保证 `batch` 容量足够
+    unsafe fn push(&mut self, token: LlamaToken, position: i32, slot: i32, logits: bool) {
+        let i = self.batch.n_tokens as usize;
+        *self.batch.token.add(i) = token;
+        *self.batch.pos.add(i) = position;
+        *self.batch.n_seq_id.add(i) = 1;
+        *(*self.batch.seq_id.add(i)) = slot;
+        *self.batch.logits.add(i) = i8::from(logits);
+        self.batch.n_tokens += 1;
+    }
+}
+
+/// 已加载的GGUF模型
+#[derive(Debug)]
+pub struct LlamaCppModel {
+    api: Arc<LlamaApi>,
+    model: *mut LlamaModelHandle,
+    /// 各token解码后的字节
+    vocab: Vec<Vec<u8>>,
+    /// 语法约束下只在完整匹配时允许的结束token
+    eos: Option<u32>,
+    n_ctx: u32,
+    state: Mutex<LlamaState>,
+}
+
+// SAFETY: 模型在加载后只读；上下文和批次只在持有 `state` 锁时访问
+unsafe impl Send for LlamaCppModel {}
+unsafe impl Sync for LlamaCppModel {}
+
+impl LlamaCppModel {
+    fn new(api: Arc<LlamaApi>, model: *mut LlamaModelHandle, ctx: *mut LlamaContextHandle, n_seq_max: u32) -> Result<Self> {
+        // SAFETY: model和ctx均已成功创建
+        unsafe {
+            let n_vocab = (api.n_vocab)(model).max(0);
+            let vocab = (0..n_vocab).map(|token| token_piece(&api, model, token)).collect();
+            let eos = (0..n_vocab).find(|&token| (api.token_is_eog)(model, token)).map(|token| token as u32);
+            let n_ctx = (api.n_ctx)(ctx);
+            let batch_capacity = n_ctx.min(2048).max(n_seq_max) as usize;
+            let batch = (api.batch_init)(batch_capacity as i32, 0, 1);
+            Ok(Self {
+                api,
+                model,
+                vocab,
+                eos,
+                n_ctx,
+                state: Mutex::new(LlamaState {
+                    ctx,
+                    batch,
+                    batch_capacity,
+                    free_slots: (0..n_seq_max as i32).rev().collect(),
+                    sequences: HashMap::new(),
+                }),
+            })
+        }
+    }
+
+    /// 上下文长度
+    pub fn context_length(&self) -> u32 {
+        self.n_ctx
+    }
+
+    /// 词表大小
+    pub fn vocab_size(&self) -> usize {
+        self.vocab.len()
+    }
+
+    fn token_text(&self, token: u32) -> String {
+        self.vocab.get(token as usize)
+            .map(|bytes| String::from_utf8_lossy(bytes).into_owned())
+            .unwrap_or_default()
+    }
+
+    fn is_eog(&self, token: LlamaToken) -> bool {
+        // SAFETY: 模型指针在self存活期间有效
+        unsafe { (self.api.token_is_eog)(self.model, token) }
+    }
+
+    fn tokenize(&self, text: &str) -> Result<Vec<LlamaToken>> {
+        let len = i32::try_from(text.len()).map_err(|_| UniModelError::validation("Prompt is too long"))?;
+        let mut tokens = vec![0; text.len() + 2];
+        // SAFETY: 缓冲区长度与传入的上限一致
+        unsafe {
+            let mut n = (self.api.tokenize)(self.model, text.as_ptr().cast(), len, tokens.as_mut_ptr(), tokens.len() as i32, true, true);
+            if n < 0 {
+                tokens.resize((-n) as usize, 0);
+                n = (self.api.tokenize)(self.model, text.as_ptr().cast(), len, tokens.as_mut_ptr(), tokens.len() as i32, true, true);
+            }
+            if n < 0 {
+                return Err(UniModelError::plugin("llama.cpp failed to tokenize the prompt"));
+            }
+            tokens.truncate(n as usize);
+        }
+        Ok(tokens)
+    }
+
+    /// SAFETY: 调用方持有 `state` 锁，批次中的token数不超过容量
+    unsafe fn decode(&self, state: &mut LlamaState) -> Result<()> {
+        let status = (self.api.decode)(state.ctx, state.batch);
+        state.batch.n_tokens = 0;
+        match status {
+            0 => Ok(()),
+            1 => Err(UniModelError::out_of_memory("llama.cpp KV cache has no room for the batch")),
+            code => Err(UniModelError::plugin(format!("llama_decode failed with {}", code))),
+        }
+    }
+
+    /// 采样一个token，返回token及其对数概率
+    fn sample(&self, sequence: &mut LlamaSequence, logits: &mut [f32]) -> (u32, Option<TokenLogprob>) {
+        apply_logit_bias(logits, &sequence.logit_bias);
+        if let Some(matcher) = &sequence.grammar {
+            apply_grammar_mask(logits, matcher, |t| self.token_text(t), self.eos);
+        }
+
+        let token = sample_token(logits, sequence.temperature, sequence.top_k, sequence.top_p, &mut sequence.rng);
+        let logprob = sequence.top_logprobs
+            .map(|top| token_logprob(logits, token, top, |t| self.token_text(t)));
+        (token, logprob)
+    }
+
+    /// 与 `sample` 相同的采样分布，供验证草稿
+    fn distribution(&self, sequence: &LlamaSequence, logits: &mut [f32]) -> Vec<f32> {
+        apply_logit_bias(logits, &sequence.logit_bias);
+        if let Some(matcher) = &sequence.grammar {
+            apply_grammar_mask(logits, matcher, |t| self.token_text(t), self.eos);
+        }
+        sampling_distribution(logits, sequence.temperature, sequence.top_k, sequence.top_p)
+    }
+
+    /// 把生成的token追加到序列输出，返回新组成的完整UTF-8文本
+    fn push_text(&self, pending: &mut Vec<u8>, token: u32) -> String {
+        if let Some(bytes) = self.vocab.get(token as usize) {
+            pending.extend_from_slice(bytes);
+        }
+        take_utf8(pending)
+    }
+
+    /// 接受一个非结束token：更新输出缓冲和语法状态，返回新组成的文本
+    fn accept_token(&self, sequence: &mut LlamaSequence, token: u32) -> String {
+        let text = self.push_text(&mut sequence.pending, token);
+        if let Some(matcher) = &mut sequence.grammar {
+            matcher.accept(&text);
+        }
+        text
+    }
+
+    /// SAFETY: 调用方持有 `state` 锁，第 `i` 个输入在上一次解码时请求了logits
+    unsafe fn logits(&self, ctx: *mut LlamaContextHandle, i: usize) -> Result<Vec<f32>> {
+        let ptr = (self.api.get_logits_ith)(ctx, i as i32);
+        if ptr.is_null() {
+            return Err(UniModelError::plugin("llama.cpp returned no logits"));
+        }
+        Ok(std::slice::from_raw_parts(ptr, self.vocab.len()).to_vec())
+    }
+
+    /// 一次前向验证一组序列的草稿，调用方保证token总数不超过批次容量
+    fn verify_chunk(&self, state: &mut LlamaState, chunk: &[(&str, &[DraftToken])]) -> Result<Vec<Verification>> {
+        // SAFETY: 调用方保证token总数不超过批次容量
+        unsafe {
+            for &(id, draft) in chunk {
+                let sequence = &state.sequences[id];
+                let (token, position, slot) = (sequence.next_input, sequence.position, sequence.slot);
+                state.push(token, position, slot, true);
+                for (offset, draft_token) in draft.iter().enumerate() {
+                    state.push(draft_token.token_id as LlamaToken, position + 1 + offset as i32, slot, true);
+                }
+            }
+            self.decode(state)?;
+        }
+
+        let ctx = state.ctx;
+        let mut verifications = Vec::with_capacity(chunk.len());
+        let mut first_row = 0;
+        for &(id, draft) in chunk {
+            let rows = first_row;
+            first_row += draft.len() + 1;
+            let sequence = match state.sequences.get_mut(id) {
+                Some(sequence) => sequence,
+                None => continue,
+            };
+
+            // 逐个位置做接受判定，拒绝时从修正分布采样，全部接受时在最后一个位置采样
+            let mut accepted = 0;
+            let (next, prob) = loop {
+                // SAFETY: 批次中的每个输入都请求了logits
+                let mut logits = unsafe { self.logits(ctx, rows + accepted)? };
+                let probs = self.distribution(sequence, &mut logits);
+                let draft_token = match draft.get(accepted) {
+                    Some(draft_token) => draft_token,
+                    None => {
+                        let token = sample_distribution(&probs, &mut sequence.rng);
+                        break (token, probs[token as usize]);
+                    }
+                };
+                let target_prob = probs.get(draft_token.token_id as usize).copied().unwrap_or(0.0);
+                if accept_draft(draft_token.prob, target_prob, sequence.rng.gen()) {
+                    if self.is_eog(draft_token.token_id as LlamaToken) {
+                        break (draft_token.token_id, target_prob);
+                    }
+                    self.accept_token(sequence, draft_token.token_id);
+                    accepted += 1;
+                    continue;
+                }
+                let mut draft_probs = vec![0.0; probs.len()];
+                if let Some(p) = draft_probs.get_mut(draft_token.token_id as usize) {
+                    *p = draft_token.prob;
+                }
+                let token = sample_distribution(&residual_distribution(&probs, &draft_probs), &mut sequence.rng);
+                break (token, probs[token as usize]);
+            };
+
+            let eog = self.is_eog(next as LlamaToken);
+            let text = if eog { String::new() } else { self.accept_token(sequence, next) };
+            // 只保留被接受的前缀写入的KV缓存
+            let last_kept = sequence.position + accepted as i32;
+            // SAFETY: 持有锁
+            unsafe { (self.api.kv_cache_seq_rm)(ctx, sequence.slot, last_kept + 1, -1) };
+            sequence.position = last_kept + 1;
+            sequence.next_input = next as LlamaToken;
+            verifications.push(Verification {
+                sequence_id: id.to_string(),
+                accepted,
+                next_token: DraftToken { token_id: next, text, prob },
+                finished: eog || sequence.position as u32 + 1 >= self.n_ctx,
+            });
+        }
+        Ok(verifications)
+    }
+}
+
+impl Drop for LlamaCppModel {
+    fn drop(&mut self) {
+        let state = self.state.get_mut();
+        // SAFETY: 指针由本结构体独占，之后不再使用
+        unsafe {
+            (self.api.batch_free)(state.batch);
+            (self.api.free)(state.ctx);
+            (self.api.free_model)(self.model);
+        }
+    }
+}
+
+impl IterativeDecoder for LlamaCppModel {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()> {
+        let tokens = self.tokenize(prompt)?;
+        let (&last, prefix) = tokens.split_last()
+            .ok_or_else(|| UniModelError::validation("Prompt is empty"))?;
+        if tokens.len() >= self.n_ctx as usize {
+            return Err(UniModelError::validation(format!(
+                "Prompt has {} tokens, the llama.cpp context holds {}",
+                tokens.len(),
+                self.n_ctx
+            )));
+        }
+        let grammar = match &parameters.grammar {
+            Some(source) => Some(GrammarMatcher::new(Arc::new(Grammar::parse(source)?))),
+            None => None,
+        };
+
+        let mut state = self.state.lock();
+        if state.sequences.contains_key(sequence_id) {
+            return Err(UniModelError::internal(format!("Sequence {} already exists", sequence_id)));
+        }
+        let slot = state.free_slots.pop()
+            .ok_or_else(|| UniModelError::resource("All llama.cpp sequence slots are in use"))?;
+
+        // 先写入提示除最后一个token外的部分，最后一个token在第一步解码时输入
+        for (chunk_index, chunk) in prefix.chunks(state.batch_capacity).enumerate() {
+            let start = chunk_index * state.batch_capacity;
+            // SAFETY: 每块不超过批次容量
+            let result = unsafe {
+                for (offset, &token) in chunk.iter().enumerate() {
+                    state.push(token, (start + offset) as i32, slot, false);
+                }
+                self.decode(&mut state)
+            };
+            if let Err(e) = result {
+                // SAFETY: 持有锁，清除该序列写入的部分KV缓存
+                unsafe { (self.api.kv_cache_seq_rm)(state.ctx, slot, -1, -1) };
+                state.free_slots.push(slot);
+                return Err(e);
+            }
+        }
+
+        state.sequences.insert(sequence_id.to_string(), LlamaSequence {
+            slot,
+            position: prefix.len() as i32,
+            next_input: last,
+            temperature: parameters.temperature.unwrap_or(1.0),
+            top_k: parameters.top_k.map_or(0, |k| k as usize),
+            top_p: parameters.top_p.unwrap_or(1.0),
+            logit_bias: parameters.logit_bias.clone(),
+            top_logprobs: parameters.logprobs.then(|| parameters.top_logprobs.unwrap_or(0) as usize),
+            grammar,
+            rng: match parameters.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            pending: Vec::new(),
+            draft: None,
+        });
+        debug!("llama.cpp sequence {} prefilled {} tokens in slot {}", sequence_id, prefix.len(), slot);
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> Result<Vec<DecodeStep>> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let active: Vec<&String> = sequence_ids.iter()
+            .filter(|id| state.sequences.contains_key(id.as_str()))
+            .take(state.batch_capacity)
+            .collect();
+        if active.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // SAFETY: 活跃序列数不超过批次容量
+        unsafe {
+            for id in &active {
+                let sequence = &state.sequences[id.as_str()];
+                let (token, position, slot) = (sequence.next_input, sequence.position, sequence.slot);
+                state.push(token, position, slot, true);
+            }
+            self.decode(state)?;
+        }
+
+        let mut steps = Vec::with_capacity(active.len());
+        for (i, id) in active.into_iter().enumerate() {
+            // SAFETY: 第i个输入请求了logits
+            let mut logits = unsafe { self.logits(state.ctx, i)? };
+            let sequence = match state.sequences.get_mut(id.as_str()) {
+                Some(sequence) => sequence,
+                None => continue,
+            };
+            let (token, logprob) = self.sample(sequence, &mut logits);
+            sequence.position += 1;
+
+            let eog = self.is_eog(token as LlamaToken);
+            let mut text = String::new();
+            if !eog {
+                text = self.accept_token(sequence, token);
+                sequence.next_input = token as LlamaToken;
+            }
+            steps.push(DecodeStep {
+                sequence_id: id.clone(),
+                token: text,
+                finished: eog || sequence.position as u32 + 1 >= self.n_ctx,
+                stop_sequence: None,
+                logprob,
+                candidates: Vec::new(),
+                draft_tokens: 0,
+            });
+        }
+        Ok(steps)
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        let mut state = self.state.lock();
+        if let Some(sequence) = state.sequences.remove(sequence_id) {
+            // SAFETY: 持有锁
+            unsafe { (self.api.kv_cache_seq_rm)(state.ctx, sequence.slot, -1, -1) };
+            state.free_slots.push(sequence.slot);
+        }
+    }
+
+    fn supports_logit_bias(&self) -> bool {
+        true
+    }
+
+    fn supports_logprobs(&self) -> bool {
+        true
+    }
+
+    fn supports_grammar(&self) -> bool {
+        true
+    }
+
+    fn as_draft_model(self: Arc<Self>) -> Option<Arc<dyn DraftModel>> {
+        Some(self)
+    }
+
+    fn as_speculative_target(self: Arc<Self>) -> Option<Arc<dyn SpeculativeTarget>> {
+        Some(self)
+    }
+}
+
+/// 草稿按贪心逐个提出并记概率为1，目标模型据此做接受判定和修正采样，输出分布仍与目标模型一致；
+/// 草稿不受语法约束，不合语法的草稿由目标模型拒绝
+impl DraftModel for LlamaCppModel {
+    fn add_sequence(&self, sequence_id: &str, prompt: &str, parameters: &PredictionParameters) -> Result<()> {
+        IterativeDecoder::add_sequence(self, sequence_id, prompt, parameters)
+    }
+
+    fn propose(&self, sequence_id: &str, num_tokens: usize) -> Result<Vec<DraftToken>> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let sequence = state.sequences.get(sequence_id)
+            .ok_or_else(|| UniModelError::internal(format!("Unknown sequence {}", sequence_id)))?;
+        let (slot, start, mut input) = (sequence.slot, sequence.position, sequence.next_input);
+        let logit_bias = sequence.logit_bias.clone();
+        let mut pending = sequence.pending.clone();
+        let room = (self.n_ctx as i32 - start - 1).max(0) as usize;
+
+        let mut proposed = Vec::with_capacity(num_tokens.min(room));
+        let mut tokens = Vec::with_capacity(num_tokens.min(room));
+        for offset in 0..num_tokens.min(room) {
+            // SAFETY: 每次只写入一个token并请求其logits
+            let logits = unsafe {
+                state.push(input, start + offset as i32, slot, true);
+                self.decode(state).and_then(|()| self.logits(state.ctx, 0))
+            };
+            let mut logits = match logits {
+                Ok(logits) => logits,
+                Err(e) => {
+                    // SAFETY: 持有锁，丢弃本次草稿写入的KV缓存
+                    unsafe { (self.api.kv_cache_seq_rm)(state.ctx, slot, start, -1) };
+                    return Err(e);
+                }
+            };
+            apply_logit_bias(&mut logits, &logit_bias);
+            let token = argmax(&logits);
+            let eog = self.is_eog(token as LlamaToken);
+            let text = if eog { String::new() } else { self.push_text(&mut pending, token) };
+            proposed.push(DraftToken { token_id: token, text, prob: 1.0 });
+            tokens.push(token as LlamaToken);
+            input = token as LlamaToken;
+            if eog {
+                break;
+            }
+        }
+
+        if let Some(sequence) = state.sequences.get_mut(sequence_id) {
+            sequence.position = start + tokens.len() as i32;
+            sequence.next_input = input;
+            sequence.draft = Some(LlamaDraft { start, tokens });
+        }
+        Ok(proposed)
+    }
+
+    fn commit(&self, sequence_id: &str, accepted: usize, next_token_id: u32) -> Result<()> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let sequence = state.sequences.get_mut(sequence_id)
+            .ok_or_else(|| UniModelError::internal(format!("Unknown sequence {}", sequence_id)))?;
+        let draft = sequence.draft.take()
+            .unwrap_or(LlamaDraft { start: sequence.position, tokens: Vec::new() });
+        let accepted = accepted.min(draft.tokens.len());
+        // 输出缓冲与目标模型保持一致，下一次草稿的文本才能按相同的字符边界切分
+        for &token in draft.tokens[..accepted].iter().chain(&[next_token_id as LlamaToken]) {
+            if !self.is_eog(token) {
+                self.push_text(&mut sequence.pending, token as u32);
+            }
+        }
+        let (slot, position, input) = (sequence.slot, sequence.position, sequence.next_input);
+        let last_kept = draft.start + accepted as i32;
+        sequence.position = last_kept + 1;
+        sequence.next_input = next_token_id as LlamaToken;
+
+        if last_kept < position {
+            // SAFETY: 持有锁，丢弃被拒绝的草稿写入的KV缓存
+            unsafe { (self.api.kv_cache_seq_rm)(state.ctx, slot, last_kept + 1, -1) };
+            return Ok(());
+        }
+        // 草稿全部被接受（或没有草稿）时，最后一个草稿token（或原输入token）尚未写入KV缓存
+        // SAFETY: 只写入一个token
+        unsafe {
+            state.push(input, position, slot, false);
+            self.decode(state)
+        }
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        IterativeDecoder::remove_sequence(self, sequence_id);
+    }
+}
+
+impl SpeculativeTarget for LlamaCppModel {
+    fn add_sequence(&self, sequence_id: &str, prompt: &str, parameters: &PredictionParameters) -> Result<()> {
+        IterativeDecoder::add_sequence(self, sequence_id, prompt, parameters)
+    }
+
+    /// 每条序列的输入token和草稿一起写入批次，超出批次容量时分多次前向
+    fn verify(&self, drafts: &[(String, Vec<DraftToken>)]) -> Result<Vec<Verification>> {
+        let mut guard = self.state.lock();
+        let state = &mut *guard;
+        let mut verifications = Vec::with_capacity(drafts.len());
+        let mut chunk: Vec<(&str, &[DraftToken])> = Vec::new();
+        let mut chunk_tokens = 0;
+        for (id, draft) in drafts {
+            let position = match state.sequences.get(id.as_str()) {
+                Some(sequence) => sequence.position,
+                None => continue,
+            };
+            // 草稿不超出上下文，单条序列不超出批次容量
+            let room = (self.n_ctx as i32 - position - 1).max(0) as usize;
+            let draft = &draft[..draft.len().min(room).min(state.batch_capacity.saturating_sub(1))];
+            if chunk_tokens + draft.len() + 1 > state.batch_capacity {
+                verifications.extend(self.verify_chunk(state, &chunk)?);
+                chunk.clear();
+                chunk_tokens = 0;
+            }
+            chunk_tokens += draft.len() + 1;
+            chunk.push((id.as_str(), draft));
+        }
+        if !chunk.is_empty() {
+            verifications.extend(self.verify_chunk(state, &chunk)?);
+        }
+        Ok(verifications)
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        IterativeDecoder::remove_sequence(self, sequence_id);
+    }
+}
+
+/// SAFETY: model有效
+unsafe fn token_piece(api: &LlamaApi, model: *const LlamaModelHandle, token: LlamaToken) -> Vec<u8> {
+    let mut buf = vec![0u8; 32];
+    let mut n = (api.token_to_piece)(model, token, buf.as_mut_ptr().cast(), buf.len() as i32, 0, false);
+    if n < 0 {
+        buf.resize((-n) as usize, 0);
+        n = (api.token_to_piece)(model, token, buf.as_mut_ptr().cast(), buf.len() as i32, 0, false);
+    }
+    buf.truncate(n.max(0) as usize);
+    buf
+}
+
+/// 取出缓冲区中完整的UTF-8前缀，不完整的多字节字符留待下一个token
+fn take_utf8(pending: &mut Vec<u8>) -> String {
+    let valid = match std::str::from_utf8(pending) {
+        Ok(_) => pending.len(),
+        Err(e) if e.error_len().is_none() => e.valid_up_to(),
+        // 非法字节不会再变得合法，整体按有损方式输出
+        Err(_) => pending.len(),
+    };
+    let bytes: Vec<u8> = pending.drain(..valid).collect();
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// 用llama.cpp把F32/F16的GGUF量化为Q8_0、Q4_K_M或F16
+#[derive(Debug, Clone)]
+pub struct LlamaCppQuantizer {
+    api: Arc<LlamaApi>,
+}
+
+impl Quantizer for LlamaCppQuantizer {
+    fn supports(&self, quantization: &QuantizationType) -> bool {
+        matches!(quantization, QuantizationType::INT8 | QuantizationType::INT4 | QuantizationType::FP16)
+    }
+
+    fn quantize(&self, weights: &ModelWeights, quantization: &QuantizationType, output: &Path) -> Result<()> {
+        let input = match weights {
+            ModelWeights::File(path) if path.extension().is_some_and(|ext| ext == "gguf") => path,
+            _ => return Err(UniModelError::model("llama.cpp can only quantize GGUF files")),
+        };
+        let ftype = match quantization {
+            QuantizationType::INT8 => LLAMA_FTYPE_MOSTLY_Q8_0,
+            QuantizationType::INT4 => LLAMA_FTYPE_MOSTLY_Q4_K_M,
+            QuantizationType::FP16 => LLAMA_FTYPE_MOSTLY_F16,
+            _ => return Err(UniModelError::validation(format!("llama.cpp cannot quantize to {}", quantization.name()))),
+        };
+        let c_input = path_to_cstring(input)?;
+        let c_output = path_to_cstring(output)?;
+        // SAFETY: 路径在调用期间有效，参数由库的默认值修改而来
+        let status = unsafe {
+            let mut params = (self.api.model_quantize_default_params)();
+            params.ftype = ftype;
+            (self.api.model_quantize)(c_input.as_ptr(), c_output.as_ptr(), &params)
+        };
+        if status != 0 {
+            return Err(UniModelError::plugin(format!(
+                "llama.cpp failed to quantize '{}' to {}",
+                input.display(),
+                quantization.name()
+            )));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,399 @@
+//! 模拟后端
+//!
+//! 不需要GPU和模型文件，供开发、集成测试和压测使用。延迟按配置的分布抽样，
+//! 按错误率注入失败，输出可以是固定的预设结果（依次轮换），未预设时回显输入
+//! （文本输入返回 `Processed: <输入>`）。默认配置取自 `plugins.plugin_configs.mock`，
+//! 单个模型可用 `custom_params.mock` 覆盖。
+//! 回显输入的模型同时提供逐token解码器，启用连续批处理时按词输出同样的回显文本；
+//! 解码器也可作为投机解码的草稿或目标模型，草稿与目标的回显文本一致时草稿全部被接受。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use parking_lot::Mutex;
+use rand::Rng;
+use serde::Deserialize;
+use tracing::debug;
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::ModelConfig;
+use crate::plugins::interface::{DecodeStep, DraftModel, DraftToken, IterativeDecoder, SpeculativeTarget, Verification};
+
+/// 后端名称，对应 `ModelConfig::backend`
+pub const BACKEND_NAME: &str = "mock";
+
+/// 覆盖模型配置的自定义参数名
+pub const MOCK_PARAM: &str = "mock";
+
+/// 每批推理的延迟分布
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "distribution", rename_all = "snake_case")]
+pub enum MockLatency {
+    Fixed { ms: u64 },
+    Uniform { min_ms: u64, max_ms: u64 },
+    /// 正态分布，抽样结果小于0时取0
+    Normal { mean_ms: f64, stddev_ms: f64 },
+}
+
+impl MockLatency {
+    /// 抽样一次延迟
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> Duration {
+        match *self {
+            MockLatency::Fixed { ms } => Duration::from_millis(ms),
+            MockLatency::Uniform { min_ms, max_ms } => {
+                Duration::from_millis(rng.gen_range(min_ms..=max_ms.max(min_ms)))
+            }
+            MockLatency::Normal { mean_ms, stddev_ms } => {
+                // Box-Muller变换
+                let u1: f64 = rng.gen_range(f64::EPSILON..1.0);
+                let u2: f64 = rng.gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                Duration::from_secs_f64((mean_ms + stddev_ms * z).max(0.0) / 1000.0)
+            }
+        }
+    }
+}
+
+/// 注入的错误类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MockErrorKind {
+    /// 插件错误，不重试
+    Plugin,
+    /// 插件不可用，批处理器按退避重试
+    Unavailable,
+    /// 显存不足，触发逐出后重试
+    OutOfMemory,
+}
+
+impl MockErrorKind {
+    fn error(self) -> UniModelError {
+        let message = "Injected mock backend failure";
+        match self {
+            MockErrorKind::Plugin => UniModelError::plugin(message),
+            MockErrorKind::Unavailable => UniModelError::plugin_unavailable(message),
+            MockErrorKind::OutOfMemory => UniModelError::out_of_memory(message),
+        }
+    }
+}
+
+/// 模拟后端配置
+#[derive(Debug, Clone, Deserialize)]
+pub struct MockConfig {
+    #[serde(default = "default_latency")]
+    pub latency: MockLatency,
+    /// 每批失败的概率（0到1）
+    #[serde(default)]
+    pub error_rate: f64,
+    #[serde(default = "default_error_kind")]
+    pub error_kind: MockErrorKind,
+    /// 预设输出，按调用顺序轮换；为空时回显输入
+    #[serde(default)]
+    pub outputs: Vec<OutputData>,
+}
+
+fn default_latency() -> MockLatency {
+    MockLatency::Fixed { ms: 50 }
+}
+
+fn default_error_kind() -> MockErrorKind {
+    MockErrorKind::Plugin
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            latency: default_latency(),
+            error_rate: 0.0,
+            error_kind: default_error_kind(),
+            outputs: Vec::new(),
+        }
+    }
+}
+
+impl MockConfig {
+    fn validate(self) -> Result<Self> {
+        if !(0.0..=1.0).contains(&self.error_rate) {
+            return Err(UniModelError::config(format!(
+                "Mock error_rate must be between 0 and 1, got {}",
+                self.error_rate
+            )));
+        }
+        Ok(self)
+    }
+}
+
+/// 已加载的模拟模型
+#[derive(Debug)]
+struct MockModel {
+    config: MockConfig,
+    /// 下一个预设输出的位置
+    next_output: AtomicUsize,
+    /// 回显输入时提供的逐token解码器
+    decoder: Option<Arc<MockDecoder>>,
+}
+
+/// 模拟后端
+#[derive(Debug)]
+pub struct MockBackend {
+    config: MockConfig,
+    models: DashMap<u64, MockModel>,
+    next_handle: AtomicU64,
+    /// 未加载模型时直接推理（`infer_default`）使用的预设输出位置
+    next_default_output: AtomicUsize,
+}
+
+impl MockBackend {
+    /// 创建后端
+    pub fn new(config: MockConfig) -> Result<Self> {
+        Ok(Self {
+            config: config.validate()?,
+            models: DashMap::new(),
+            next_handle: AtomicU64::new(1),
+            next_default_output: AtomicUsize::new(0),
+        })
+    }
+
+    /// 按插件配置创建后端，未配置时使用默认值
+    pub fn from_plugin_config(value: Option<&serde_json::Value>) -> Result<Self> {
+        let config = match value {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| UniModelError::config(format!("Invalid mock plugin config: {}", e)))?,
+            None => MockConfig::default(),
+        };
+        Self::new(config)
+    }
+
+    /// 加载模型，返回句柄；`model_path` 不会被读取
+    pub fn load_model(&self, config: &ModelConfig) -> Result<u64> {
+        let model_config = match config.custom_params.get(MOCK_PARAM) {
+            Some(value) => serde_json::from_value::<MockConfig>(value.clone())
+                .map_err(|e| UniModelError::validation(format!("Invalid custom_params.mock: {}", e)))?
+                .validate()
+                .map_err(|e| UniModelError::validation(e.to_string()))?,
+            None => self.config.clone(),
+        };
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        let decoder = model_config.outputs.is_empty().then(|| Arc::new(MockDecoder::new(&model_config)));
+        self.models.insert(handle, MockModel {
+            config: model_config,
+            next_output: AtomicUsize::new(0),
+            decoder,
+        });
+        debug!("Mock model loaded as handle {}", handle);
+        Ok(handle)
+    }
+
+    /// 卸载模型
+    pub fn unload_model(&self, handle: u64) -> Result<()> {
+        self.models.remove(&handle)
+            .map(|_| ())
+            .ok_or_else(|| UniModelError::model(format!("Unknown mock model handle {}", handle)))
+    }
+
+    /// 模型的逐token解码器；配置了预设输出的模型没有解码器
+    pub fn decoder(&self, handle: u64) -> Option<Arc<dyn IterativeDecoder>> {
+        let model = self.models.get(&handle)?;
+        model.decoder.as_ref().map(|decoder| Arc::clone(decoder) as Arc<dyn IterativeDecoder>)
+    }
+
+    /// 在已加载的模型上推理
+    pub async fn infer(&self, handle: u64, inputs: &[InputData]) -> Result<Vec<OutputData>> {
+        let (config, start) = {
+            let model = self.models.get(&handle)
+                .ok_or_else(|| UniModelError::model(format!("Unknown mock model handle {}", handle)))?;
+            let start = model.next_output.fetch_add(inputs.len(), Ordering::Relaxed);
+            (model.config.clone(), start)
+        };
+        run(&config, start, inputs).await
+    }
+
+    /// 不经过模型加载，按后端默认配置推理；供未关联模型管理器的批处理器使用
+    pub async fn infer_default(&self, inputs: &[InputData]) -> Result<Vec<OutputData>> {
+        let start = self.next_default_output.fetch_add(inputs.len(), Ordering::Relaxed);
+        run(&self.config, start, inputs).await
+    }
+}
+
+async fn run(config: &MockConfig, start: usize, inputs: &[InputData]) -> Result<Vec<OutputData>> {
+    let (latency, failed) = {
+        let mut rng = rand::thread_rng();
+        (config.latency.sample(&mut rng), rng.gen_bool(config.error_rate))
+    };
+    tokio::time::sleep(latency).await;
+    if failed {
+        return Err(config.error_kind.error());
+    }
+
+    Ok(inputs.iter()
+        .enumerate()
+        .map(|(i, input)| match config.outputs.len() {
+            0 => echo(input),
+            n => config.outputs[(start + i) % n].clone(),
+        })
+        .collect())
+}
+
+fn echo(input: &InputData) -> OutputData {
+    match input {
+        InputData::Text(text) => OutputData::Text(format!("Processed: {}", text)),
+        InputData::Binary(data) => OutputData::Binary(data.clone()),
+        InputData::Json(json) => OutputData::Json(json.clone()),
+        InputData::Multimodal(map) => OutputData::Multimodal(map.iter().map(|(k, v)| (k.clone(), echo(v))).collect()),
+        InputData::Tensors(tensors) => OutputData::Tensors(tensors.clone()),
+    }
+}
+
+/// 回显模型的逐token解码器：每步输出 `Processed: <提示>` 的一个词
+///
+/// 按错误率在加入序列时注入失败；不模拟延迟，解码步的耗时由批处理器的调度决定。
+#[derive(Debug)]
+pub struct MockDecoder {
+    error_rate: f64,
+    error_kind: MockErrorKind,
+    sequences: Mutex<HashMap<String, MockSequence>>,
+}
+
+#[derive(Debug)]
+struct MockSequence {
+    /// 尚未输出的词
+    words: VecDeque<String>,
+    /// 已输出的词数，用作下一个词的token ID
+    emitted: u32,
+}
+
+impl MockSequence {
+    /// 输出前 `count` 个词
+    fn advance(&mut self, count: usize) {
+        let count = count.min(self.words.len());
+        self.words.drain(..count);
+        self.emitted += count as u32;
+    }
+}
+
+impl MockDecoder {
+    fn new(config: &MockConfig) -> Self {
+        Self {
+            error_rate: config.error_rate,
+            error_kind: config.error_kind,
+            sequences: Mutex::new(HashMap::new()),
+        }
+    }
+}
+
+impl IterativeDecoder for MockDecoder {
+    fn add_sequence(&self, sequence_id: &str, prompt: &str, _parameters: &PredictionParameters) -> Result<()> {
+        if rand::thread_rng().gen_bool(self.error_rate) {
+            return Err(self.error_kind.error());
+        }
+        let text = format!("Processed: {}", prompt);
+        let words = text.split_inclusive(' ').map(str::to_string).collect();
+        self.sequences.lock().insert(sequence_id.to_string(), MockSequence { words, emitted: 0 });
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> Result<Vec<DecodeStep>> {
+        let mut sequences = self.sequences.lock();
+        Ok(sequence_ids.iter()
+            .filter_map(|id| {
+                let sequence = sequences.get_mut(id)?;
+                let token = sequence.words.front().cloned().unwrap_or_default();
+                sequence.advance(1);
+                Some(DecodeStep {
+                    sequence_id: id.clone(),
+                    token,
+                    finished: sequence.words.is_empty(),
+                    stop_sequence: None,
+                    logprob: None,
+                    candidates: Vec::new(),
+                    draft_tokens: 0,
+                })
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.sequences.lock().remove(sequence_id);
+    }
+
+    fn as_draft_model(self: Arc<Self>) -> Option<Arc<dyn DraftModel>> {
+        Some(self)
+    }
+
+    fn as_speculative_target(self: Arc<Self>) -> Option<Arc<dyn SpeculativeTarget>> {
+        Some(self)
+    }
+}
+
+impl DraftModel for MockDecoder {
+    fn add_sequence(&self, sequence_id: &str, prompt: &str, parameters: &PredictionParameters) -> Result<()> {
+        IterativeDecoder::add_sequence(self, sequence_id, prompt, parameters)
+    }
+
+    fn propose(&self, sequence_id: &str, num_tokens: usize) -> Result<Vec<DraftToken>> {
+        let sequences = self.sequences.lock();
+        let sequence = sequences.get(sequence_id)
+            .ok_or_else(|| UniModelError::internal(format!("Unknown sequence {}", sequence_id)))?;
+        Ok(sequence.words.iter()
+            .take(num_tokens)
+            .enumerate()
+            .map(|(i, word)| DraftToken {
+                token_id: sequence.emitted + i as u32,
+                text: word.clone(),
+                prob: 1.0,
+            })
+            .collect())
+    }
+
+    fn commit(&self, sequence_id: &str, accepted: usize, _next_token_id: u32) -> Result<()> {
+        if let Some(sequence) = self.sequences.lock().get_mut(sequence_id) {
+            sequence.advance(accepted + 1);
+        }
+        Ok(())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        IterativeDecoder::remove_sequence(self, sequence_id);
+    }
+}
+
+impl SpeculativeTarget for MockDecoder {
+    fn add_sequence(&self, sequence_id: &str, prompt: &str, parameters: &PredictionParameters) -> Result<()> {
+        IterativeDecoder::add_sequence(self, sequence_id, prompt, parameters)
+    }
+
+    /// 接受与本模型回显文本一致的最长草稿前缀，至少留下一个词作为 `next_token`
+    fn verify(&self, drafts: &[(String, Vec<DraftToken>)]) -> Result<Vec<Verification>> {
+        let mut sequences = self.sequences.lock();
+        Ok(drafts.iter()
+            .filter_map(|(id, draft)| {
+                let sequence = sequences.get_mut(id)?;
+                let accepted = draft.iter()
+                    .zip(&sequence.words)
+                    .take_while(|(token, word)| token.text == **word)
+                    .count()
+                    .min(sequence.words.len().saturating_sub(1));
+                let next_token = DraftToken {
+                    token_id: sequence.emitted + accepted as u32,
+                    text: sequence.words.get(accepted).cloned().unwrap_or_default(),
+                    prob: 1.0,
+                };
+                sequence.advance(accepted + 1);
+                Some(Verification {
+                    sequence_id: id.clone(),
+                    accepted,
+                    next_token,
+                    finished: sequence.words.is_empty(),
+                })
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        IterativeDecoder::remove_sequence(self, sequence_id);
+    }
+}
@@ -0,0 +1,13 @@
+//! 内置插件
+
+pub mod candle_plugin;
+pub mod llamacpp_plugin;
+pub mod mock_plugin;
+pub mod tensorrt_llm_plugin;
+pub mod wasm_plugin;
+
+pub use candle_plugin::{CandleBackend, CandleModel};
+pub use llamacpp_plugin::{LlamaCppBackend, LlamaCppConfig, LlamaCppModel, LlamaCppQuantizer};
+pub use mock_plugin::{MockBackend, MockConfig, MockDecoder, MockErrorKind, MockLatency};
+pub use tensorrt_llm_plugin::{TrtLlmBackend, TrtLlmConfig, TrtLlmModel};
+pub use wasm_plugin::{WasmModel, WasmRuntime, WasmSandboxConfig};
@@ -0,0 +1,348 @@
+//! TensorRT-LLM后端
+//!
+//! 通过Triton推理服务器的 `generate_stream` 接口驱动TensorRT-LLM引擎（`ensemble` 或
+//! `tensorrt_llm_bls` 模型）。TensorRT-LLM自身做in-flight批处理，本后端不再组批：
+//! 每个序列加入时立即发出一个流式请求，`step` 只收集服务器已返回的token，
+//! 连续批处理的活跃序列上限即为同时在途的请求数。序列被移除时中止对应请求，服务器随之取消生成。
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::time::{Duration, Instant};
+
+use futures::StreamExt;
+use parking_lot::Mutex;
+use serde::Deserialize;
+use tokio::runtime::Handle;
+use tokio::task::JoinHandle;
+use tracing::{debug, info, warn};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{DeviceType, ModelConfig};
+use crate::domain::service::batch_processor::DEFAULT_MAX_NEW_TOKENS;
+use crate::plugins::interface::{BackendCapabilities, DecodeStep, IterativeDecoder};
+
+/// 后端名称，对应 `ModelConfig::backend`
+pub const BACKEND_NAME: &str = "tensorrt_llm";
+
+/// `step` 等待服务器返回token的最长时间，超时后返回已收到的部分
+const STEP_WAIT: Duration = Duration::from_millis(20);
+
+/// 后端配置，取自 `plugins.plugin_configs.tensorrt_llm`
+#[derive(Debug, Clone, Deserialize)]
+pub struct TrtLlmConfig {
+    /// Triton HTTP地址
+    #[serde(default = "default_endpoint")]
+    pub endpoint: String,
+    /// 默认的Triton模型名，可用模型的 `custom_params.triton_model` 覆盖
+    #[serde(default = "default_triton_model")]
+    pub triton_model: String,
+    /// 连接超时（秒）
+    #[serde(default = "default_connect_timeout_secs")]
+    pub connect_timeout_secs: u64,
+}
+
+fn default_endpoint() -> String {
+    "http://127.0.0.1:8000".to_string()
+}
+
+fn default_triton_model() -> String {
+    "ensemble".to_string()
+}
+
+fn default_connect_timeout_secs() -> u64 {
+    5
+}
+
+impl Default for TrtLlmConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: default_endpoint(),
+            triton_model: default_triton_model(),
+            connect_timeout_secs: default_connect_timeout_secs(),
+        }
+    }
+}
+
+/// TensorRT-LLM后端
+#[derive(Debug, Clone)]
+pub struct TrtLlmBackend {
+    client: reqwest::Client,
+    config: TrtLlmConfig,
+}
+
+impl TrtLlmBackend {
+    /// 创建后端
+    pub fn new(config: TrtLlmConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(config.connect_timeout_secs))
+            .build()
+            .map_err(|e| UniModelError::config(format!("Cannot create Triton client: {}", e)))?;
+        Ok(Self { client, config })
+    }
+
+    /// 按插件配置创建后端，未配置时使用默认值
+    pub fn from_plugin_config(value: Option<&serde_json::Value>) -> Result<Self> {
+        let config = match value {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| UniModelError::config(format!("Invalid tensorrt_llm plugin config: {}", e)))?,
+            None => TrtLlmConfig::default(),
+        };
+        Self::new(config)
+    }
+
+    /// 后端能力：引擎由Triton在CUDA设备上运行，模型格式取决于Triton的模型仓库，不参与自动选择
+    pub fn capabilities(&self) -> BackendCapabilities {
+        BackendCapabilities {
+            devices: vec![DeviceType::CUDA],
+            ..BackendCapabilities::undeclared(BACKEND_NAME)
+        }
+    }
+
+    /// 确认Triton上的引擎已就绪，返回解码器
+    ///
+    /// 引擎由Triton按其模型仓库加载，`model_path` 仅用于记录。
+    pub async fn load(&self, config: &ModelConfig) -> Result<TrtLlmModel> {
+        let triton_model = config.custom_params.get("triton_model")
+            .and_then(|v| v.as_str())
+            .unwrap_or(&self.config.triton_model)
+            .to_string();
+        let endpoint = self.config.endpoint.trim_end_matches('/');
+        let ready_url = format!("{}/v2/models/{}/ready", endpoint, triton_model);
+        let response = self.client.get(&ready_url)
+            .send()
+            .await
+            .map_err(|e| UniModelError::plugin_unavailable(format!("Triton at {} is unreachable: {}", endpoint, e)))?;
+        if !response.status().is_success() {
+            return Err(UniModelError::model(format!(
+                "Triton model '{}' is not ready ({})",
+                triton_model,
+                response.status()
+            )));
+        }
+        info!("TensorRT-LLM engine '{}' ready at {}", triton_model, endpoint);
+
+        let (events_tx, events_rx) = channel();
+        Ok(TrtLlmModel {
+            client: self.client.clone(),
+            generate_url: format!("{}/v2/models/{}/generate_stream", endpoint, triton_model),
+            runtime: Handle::current(),
+            events_tx,
+            state: Mutex::new(TrtLlmState {
+                events_rx,
+                sequences: HashMap::new(),
+            }),
+        })
+    }
+}
+
+/// 流式请求产生的事件
+#[derive(Debug)]
+enum StreamEvent {
+    Text(String),
+    Finished,
+    Failed(String),
+}
+
+#[derive(Debug)]
+struct TrtLlmSequence {
+    task: JoinHandle<()>,
+    buffered: VecDeque<StreamEvent>,
+}
+
+#[derive(Debug)]
+struct TrtLlmState {
+    events_rx: Receiver<(String, StreamEvent)>,
+    sequences: HashMap<String, TrtLlmSequence>,
+}
+
+impl TrtLlmState {
+    fn deliver(&mut self, sequence_id: String, event: StreamEvent) {
+        // 已移除序列的迟到事件直接丢弃
+        if let Some(sequence) = self.sequences.get_mut(&sequence_id) {
+            sequence.buffered.push_back(event);
+        }
+    }
+}
+
+/// Triton上的TensorRT-LLM引擎
+#[derive(Debug)]
+pub struct TrtLlmModel {
+    client: reqwest::Client,
+    generate_url: String,
+    runtime: Handle,
+    events_tx: Sender<(String, StreamEvent)>,
+    state: Mutex<TrtLlmState>,
+}
+
+impl TrtLlmModel {
+    fn request_body(prompt: &str, parameters: &PredictionParameters) -> serde_json::Value {
+        let mut body = serde_json::json!({
+            "text_input": prompt,
+            "max_tokens": parameters.max_tokens.unwrap_or(DEFAULT_MAX_NEW_TOKENS),
+            "bad_words": "",
+            "stop_words": "",
+            "stream": true,
+        });
+        if let Some(temperature) = parameters.temperature {
+            body["temperature"] = serde_json::json!(temperature);
+        }
+        if let Some(top_k) = parameters.top_k {
+            body["top_k"] = serde_json::json!(top_k);
+        }
+        if let Some(top_p) = parameters.top_p {
+            body["top_p"] = serde_json::json!(top_p);
+        }
+        if let Some(seed) = parameters.seed {
+            body["random_seed"] = serde_json::json!(seed);
+        }
+        body
+    }
+}
+
+/// 读取SSE流，把每个 `data:` 事件中的 `text_output` 转发给解码器
+async fn stream_tokens(
+    client: reqwest::Client,
+    url: String,
+    body: serde_json::Value,
+    sequence_id: String,
+    events: Sender<(String, StreamEvent)>,
+) {
+    let send = |event| {
+        let _ = events.send((sequence_id.clone(), event));
+    };
+    let response = match client.post(&url).json(&body).send().await {
+        Ok(response) if response.status().is_success() => response,
+        Ok(response) => {
+            let status = response.status();
+            let message = response.text().await.unwrap_or_default();
+            send(StreamEvent::Failed(format!("Triton returned {}: {}", status, message)));
+            return;
+        }
+        Err(e) => {
+            send(StreamEvent::Failed(format!("Triton request failed: {}", e)));
+            return;
+        }
+    };
+
+    let mut stream = response.bytes_stream();
+    let mut buffer = Vec::new();
+    while let Some(chunk) = stream.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                send(StreamEvent::Failed(format!("Triton stream interrupted: {}", e)));
+                return;
+            }
+        };
+        buffer.extend_from_slice(&chunk);
+        while let Some(newline) = buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = buffer.drain(..=newline).collect();
+            let line = String::from_utf8_lossy(&line);
+            let data = match line.trim().strip_prefix("data:") {
+                Some(data) => data.trim(),
+                None => continue,
+            };
+            match serde_json::from_str::<serde_json::Value>(data) {
+                Ok(event) => {
+                    if let Some(error) = event.get("error").and_then(|e| e.as_str()) {
+                        send(StreamEvent::Failed(format!("TensorRT-LLM error: {}", error)));
+                        return;
+                    }
+                    if let Some(text) = event.get("text_output").and_then(|t| t.as_str()) {
+                        send(StreamEvent::Text(text.to_string()));
+                    }
+                }
+                Err(e) => warn!("Ignoring malformed Triton event for {}: {}", sequence_id, e),
+            }
+        }
+    }
+    send(StreamEvent::Finished);
+}
+
+impl IterativeDecoder for TrtLlmModel {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()> {
+        let mut state = self.state.lock();
+        if state.sequences.contains_key(sequence_id) {
+            return Err(UniModelError::internal(format!("Sequence {} already exists", sequence_id)));
+        }
+        let task = self.runtime.spawn(stream_tokens(
+            self.client.clone(),
+            self.generate_url.clone(),
+            Self::request_body(prompt, parameters),
+            sequence_id.to_string(),
+            self.events_tx.clone(),
+        ));
+        state.sequences.insert(sequence_id.to_string(), TrtLlmSequence {
+            task,
+            buffered: VecDeque::new(),
+        });
+        debug!("Submitted sequence {} to TensorRT-LLM", sequence_id);
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> Result<Vec<DecodeStep>> {
+        let mut state = self.state.lock();
+        let has_output = |state: &TrtLlmState| {
+            sequence_ids.iter()
+                .any(|id| state.sequences.get(id).is_some_and(|s| !s.buffered.is_empty()))
+        };
+
+        // 收取已到达的事件；所有序列都没有新token时最多等待 STEP_WAIT
+        let deadline = Instant::now() + STEP_WAIT;
+        loop {
+            while let Ok((id, event)) = state.events_rx.try_recv() {
+                state.deliver(id, event);
+            }
+            if has_output(&state) {
+                break;
+            }
+            match state.events_rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok((id, event)) => state.deliver(id, event),
+                Err(RecvTimeoutError::Timeout) | Err(RecvTimeoutError::Disconnected) => break,
+            }
+        }
+
+        let mut steps = Vec::new();
+        for id in sequence_ids {
+            let sequence = match state.sequences.get_mut(id) {
+                Some(sequence) => sequence,
+                None => continue,
+            };
+            let (token, finished) = match sequence.buffered.pop_front() {
+                Some(StreamEvent::Text(token)) => {
+                    let finished = matches!(sequence.buffered.front(), Some(StreamEvent::Finished));
+                    if finished {
+                        sequence.buffered.pop_front();
+                    }
+                    (token, finished)
+                }
+                Some(StreamEvent::Finished) => (String::new(), true),
+                Some(StreamEvent::Failed(message)) => return Err(UniModelError::plugin(message)),
+                None => continue,
+            };
+            steps.push(DecodeStep {
+                sequence_id: id.clone(),
+                token,
+                finished,
+                stop_sequence: None,
+                logprob: None,
+                candidates: Vec::new(),
+                draft_tokens: 0,
+            });
+        }
+        Ok(steps)
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        if let Some(sequence) = self.state.lock().sequences.remove(sequence_id) {
+            // 断开连接后Triton取消该请求
+            sequence.task.abort();
+        }
+    }
+}
@@ -0,0 +1,340 @@
+//! WebAssembly插件沙箱
+//!
+//! 用wasmtime在进程内运行不受信任的扩展：模型的预处理、后处理阶段，以及轻量的CPU模型。
+//! 每次调用都在新的实例中执行，调用之间不共享状态；执行受燃料（指令预算）和内存上限约束，
+//! 超出时调用中止而不影响服务器。模块以WASI preview1运行，只能访问配置中显式授予的目录和环境变量，
+//! 不继承标准输入输出，也不授予任何套接字，因此无法发起网络连接。
+//!
+//! 模块须导出：
+//! - `memory`
+//! - `unimodel_abi_version() -> i32`，返回 [`ABI_VERSION`]
+//! - `unimodel_alloc(len: i32) -> i32`，分配供宿主写入请求的内存
+//! - 处理函数 `preprocess` / `postprocess` / `predict`，签名为 `(ptr: i32, len: i32) -> i64`
+//!
+//! 请求和响应均为MessagePack，处理函数返回 `(响应指针 << 32) | 响应长度`。
+//! 预处理和后处理的请求为 `{data, parameters}`，`predict` 的请求为 `{inputs, parameters}`；
+//! 响应为 `{result}` 或 `{error}`。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use wasmtime::component::ResourceTable;
+use wasmtime::{Engine, Instance, Linker, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+use wasmtime_wasi::preview2::preview1::{self as wasi_preview1, WasiPreview1Adapter, WasiPreview1View};
+use wasmtime_wasi::preview2::{DirPerms, FilePerms, WasiCtx, WasiCtxBuilder, WasiView};
+use wasmtime_wasi::sync::{ambient_authority, Dir};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::ModelConfig;
+
+/// 后端名称，对应 `ModelConfig::backend`
+pub const BACKEND_NAME: &str = "wasm";
+
+/// 宿主与模块之间的调用约定版本
+pub const ABI_VERSION: i32 = 1;
+
+/// 预处理阶段的导出函数
+pub const PREPROCESS_EXPORT: &str = "preprocess";
+/// 后处理阶段的导出函数
+pub const POSTPROCESS_EXPORT: &str = "postprocess";
+/// 模型推理的导出函数
+pub const PREDICT_EXPORT: &str = "predict";
+
+/// 沙箱配置，取自 `plugins.plugin_configs.wasm`
+#[derive(Debug, Clone, Deserialize)]
+pub struct WasmSandboxConfig {
+    /// 单次调用的燃料（约等于执行的指令数）
+    #[serde(default = "default_fuel_per_call")]
+    pub fuel_per_call: u64,
+    /// 单个实例的线性内存上限（MB）
+    #[serde(default = "default_max_memory_mb")]
+    pub max_memory_mb: u64,
+    /// 授予模块的目录
+    #[serde(default)]
+    pub dirs: Vec<WasmDirGrant>,
+    /// 传给模块的环境变量，服务器自身的环境变量不会传入
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+}
+
+/// 授予模块的目录
+#[derive(Debug, Clone, Deserialize)]
+pub struct WasmDirGrant {
+    /// 宿主上的目录
+    pub host: String,
+    /// 模块内看到的路径
+    pub guest: String,
+    /// 是否允许写入，默认只读
+    #[serde(default)]
+    pub writable: bool,
+}
+
+fn default_fuel_per_call() -> u64 {
+    10_000_000_000
+}
+
+fn default_max_memory_mb() -> u64 {
+    256
+}
+
+impl Default for WasmSandboxConfig {
+    fn default() -> Self {
+        Self {
+            fuel_per_call: default_fuel_per_call(),
+            max_memory_mb: default_max_memory_mb(),
+            dirs: Vec::new(),
+            env: HashMap::new(),
+        }
+    }
+}
+
+fn wasm_error(e: wasmtime::Error) -> UniModelError {
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => UniModelError::resource("WASM plugin exceeded its fuel budget"),
+        _ => UniModelError::plugin(format!("WASM plugin: {:#}", e)),
+    }
+}
+
+/// 单次调用的实例状态
+struct SandboxState {
+    table: ResourceTable,
+    wasi: WasiCtx,
+    adapter: WasiPreview1Adapter,
+    limits: StoreLimits,
+}
+
+impl WasiView for SandboxState {
+    fn table(&self) -> &ResourceTable {
+        &self.table
+    }
+
+    fn table_mut(&mut self) -> &mut ResourceTable {
+        &mut self.table
+    }
+
+    fn ctx(&self) -> &WasiCtx {
+        &self.wasi
+    }
+
+    fn ctx_mut(&mut self) -> &mut WasiCtx {
+        &mut self.wasi
+    }
+}
+
+impl WasiPreview1View for SandboxState {
+    fn adapter(&self) -> &WasiPreview1Adapter {
+        &self.adapter
+    }
+
+    fn adapter_mut(&mut self) -> &mut WasiPreview1Adapter {
+        &mut self.adapter
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct StageRequest<'a, T> {
+    data: &'a T,
+    parameters: &'a PredictionParameters,
+}
+
+#[derive(Debug, Serialize)]
+struct PredictRequest<'a> {
+    inputs: &'a [InputData],
+    parameters: &'a PredictionParameters,
+}
+
+#[derive(Debug, Deserialize)]
+struct WasmResponse<R> {
+    result: Option<R>,
+    error: Option<String>,
+}
+
+/// WASM运行时：编译好的模块按路径缓存，调用在阻塞线程池中执行
+pub struct WasmRuntime {
+    engine: Engine,
+    linker: Linker<SandboxState>,
+    config: WasmSandboxConfig,
+    modules: DashMap<PathBuf, Module>,
+}
+
+impl std::fmt::Debug for WasmRuntime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WasmRuntime")
+            .field("config", &self.config)
+            .field("modules", &self.modules.len())
+            .finish()
+    }
+}
+
+impl WasmRuntime {
+    /// 创建运行时
+    pub fn new(config: WasmSandboxConfig) -> Result<Self> {
+        let mut engine_config = wasmtime::Config::new();
+        engine_config.consume_fuel(true);
+        let engine = Engine::new(&engine_config).map_err(wasm_error)?;
+        let mut linker = Linker::new(&engine);
+        wasi_preview1::add_to_linker_sync(&mut linker).map_err(wasm_error)?;
+        for grant in &config.dirs {
+            if !Path::new(&grant.host).is_dir() {
+                return Err(UniModelError::config(format!("WASM sandbox directory '{}' does not exist", grant.host)));
+            }
+        }
+        Ok(Self {
+            engine,
+            linker,
+            config,
+            modules: DashMap::new(),
+        })
+    }
+
+    /// 按插件配置创建运行时，未配置时使用默认值
+    pub fn from_plugin_config(value: Option<&serde_json::Value>) -> Result<Self> {
+        let config = match value {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| UniModelError::config(format!("Invalid wasm plugin config: {}", e)))?,
+            None => WasmSandboxConfig::default(),
+        };
+        Self::new(config)
+    }
+
+    /// 执行预处理或后处理阶段，`export` 为 [`PREPROCESS_EXPORT`] 或 [`POSTPROCESS_EXPORT`]
+    pub fn run_stage<T>(&self, module: &Path, export: &str, data: &T, parameters: &PredictionParameters) -> Result<T>
+    where
+        T: Serialize + DeserializeOwned,
+    {
+        self.call(module, export, &StageRequest { data, parameters })
+    }
+
+    /// 编译模块并缓存，同一路径只编译一次
+    fn module(&self, path: &Path) -> Result<Module> {
+        if let Some(module) = self.modules.get(path) {
+            return Ok(module.clone());
+        }
+        let module = Module::from_file(&self.engine, path).map_err(|e| {
+            UniModelError::model(format!("Cannot compile WASM module '{}': {:#}", path.display(), e))
+        })?;
+        info!("Compiled WASM module {}", path.display());
+        self.modules.insert(path.to_path_buf(), module.clone());
+        Ok(module)
+    }
+
+    /// 为一次调用创建带能力限制的实例
+    fn instantiate(&self, module: &Module) -> Result<(Store<SandboxState>, Instance)> {
+        let mut wasi = WasiCtxBuilder::new();
+        for (key, value) in &self.config.env {
+            wasi.env(key, value);
+        }
+        for grant in &self.config.dirs {
+            let dir = Dir::open_ambient_dir(&grant.host, ambient_authority())?;
+            let (dir_perms, file_perms) = if grant.writable {
+                (DirPerms::all(), FilePerms::all())
+            } else {
+                (DirPerms::READ, FilePerms::READ)
+            };
+            wasi.preopened_dir(dir, dir_perms, file_perms, &grant.guest);
+        }
+        let state = SandboxState {
+            table: ResourceTable::new(),
+            wasi: wasi.build(),
+            adapter: WasiPreview1Adapter::new(),
+            limits: StoreLimitsBuilder::new()
+                .memory_size((self.config.max_memory_mb * 1024 * 1024) as usize)
+                .instances(1)
+                .build(),
+        };
+        let mut store = Store::new(&self.engine, state);
+        store.limiter(|state| &mut state.limits);
+        store.set_fuel(self.config.fuel_per_call).map_err(wasm_error)?;
+
+        let instance = self.linker.instantiate(&mut store, module).map_err(wasm_error)?;
+        // WASI reactor模块需要先初始化运行时
+        if let Ok(initialize) = instance.get_typed_func::<(), ()>(&mut store, "_initialize") {
+            initialize.call(&mut store, ()).map_err(wasm_error)?;
+        }
+        let version = instance.get_typed_func::<(), i32>(&mut store, "unimodel_abi_version")
+            .and_then(|f| f.call(&mut store, ()))
+            .map_err(wasm_error)?;
+        if version != ABI_VERSION {
+            return Err(UniModelError::plugin(format!(
+                "WASM module uses ABI version {}, the server needs {}",
+                version, ABI_VERSION
+            )));
+        }
+        Ok((store, instance))
+    }
+
+    /// 写入请求、调用导出函数并读取响应
+    fn call<P: Serialize, R: DeserializeOwned>(&self, module_path: &Path, export: &str, request: &P) -> Result<R> {
+        let module = self.module(module_path)?;
+        let (mut store, instance) = self.instantiate(&module)?;
+        let memory = instance.get_memory(&mut store, "memory")
+            .ok_or_else(|| UniModelError::plugin("WASM module does not export its memory"))?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "unimodel_alloc").map_err(wasm_error)?;
+        let func = instance.get_typed_func::<(i32, i32), i64>(&mut store, export).map_err(|_| {
+            UniModelError::plugin(format!("WASM module '{}' does not export '{}'", module_path.display(), export))
+        })?;
+
+        let payload = rmp_serde::to_vec_named(request)
+            .map_err(|e| UniModelError::internal(format!("Cannot encode WASM request: {}", e)))?;
+        let len = i32::try_from(payload.len())
+            .map_err(|_| UniModelError::validation("Request is too large for a WASM plugin"))?;
+        let ptr = alloc.call(&mut store, len).map_err(wasm_error)?;
+        memory.write(&mut store, ptr as u32 as usize, &payload)
+            .map_err(|_| UniModelError::plugin("WASM module returned an invalid allocation"))?;
+
+        let packed = func.call(&mut store, (ptr, len)).map_err(wasm_error)? as u64;
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        let mut frame = vec![0; out_len];
+        memory.read(&store, out_ptr, &mut frame)
+            .map_err(|_| UniModelError::plugin("WASM module returned a response outside its memory"))?;
+
+        let response: WasmResponse<R> = rmp_serde::from_slice(&frame)
+            .map_err(|e| UniModelError::plugin(format!("Malformed response from WASM module: {}", e)))?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(UniModelError::plugin(format!("WASM plugin: {}", error))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(UniModelError::plugin(format!("WASM module returned no result for '{}'", export))),
+        }
+    }
+}
+
+/// 以WASM模块实现的模型
+#[derive(Debug, Clone)]
+pub struct WasmModel {
+    runtime: Arc<WasmRuntime>,
+    module: PathBuf,
+}
+
+impl WasmModel {
+    /// 编译 `model_path` 指向的模块并确认其导出 `predict`
+    pub fn load(runtime: Arc<WasmRuntime>, config: &ModelConfig) -> Result<Self> {
+        let module = PathBuf::from(&config.model_path);
+        let compiled = runtime.module(&module)?;
+        if compiled.get_export(PREDICT_EXPORT).is_none() {
+            return Err(UniModelError::model(format!(
+                "WASM module '{}' does not export '{}'",
+                config.model_path, PREDICT_EXPORT
+            )));
+        }
+        Ok(Self { runtime, module })
+    }
+
+    /// 执行推理，在阻塞线程池中调用
+    pub fn predict(&self, inputs: &[InputData], parameters: &PredictionParameters) -> Result<Vec<OutputData>> {
+        let outputs: Vec<OutputData> = self.runtime.call(&self.module, PREDICT_EXPORT, &PredictRequest { inputs, parameters })?;
+        if outputs.len() != inputs.len() {
+            return Err(UniModelError::plugin(format!(
+                "WASM model returned {} outputs for {} inputs",
+                outputs.len(),
+                inputs.len()
+            )));
+        }
+        Ok(outputs)
+    }
+}
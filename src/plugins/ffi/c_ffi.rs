@@ -0,0 +1,421 @@
+//! C ABI动态插件
+//!
+//! 插件是导出以下两个符号的动态库（`.so`/`.dylib`/`.dll`），头文件见 `include/unimodel_plugin.h`：
+//! - `uint32_t unimodel_plugin_abi_version(void)`：插件编译时的ABI版本
+//! - `const UniModelPluginDescriptor *unimodel_plugin_descriptor(void)`：插件信息和函数表
+//!
+//! 加载时先比较ABI版本，版本不一致的插件在读取描述符之前即被拒绝；函数表带 `struct_size`，
+//! 同一版本内追加的可选函数按大小判断是否存在。所有调用的请求和响应都是MessagePack，
+//! 返回0表示成功并在响应缓冲区中写入结果，非0表示失败并写入UTF-8错误信息；
+//! 缓冲区由插件分配，宿主读取后通过 `free_buffer` 交还。插件须保证函数可被多个线程同时调用。
+
+use std::ffi::{c_char, CStr};
+use std::path::{Path, PathBuf};
+
+use libloading::Library;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{ModelConfig, Precision};
+use crate::domain::service::parallelism::ParallelPlan;
+use crate::plugins::interface::BackendCapabilities;
+
+/// 宿主支持的ABI版本，函数表布局或调用约定不兼容时递增
+pub const ABI_VERSION: u32 = 1;
+
+/// 返回ABI版本的导出符号
+pub const ABI_VERSION_SYMBOL: &[u8] = b"unimodel_plugin_abi_version\0";
+/// 返回插件描述符的导出符号
+pub const DESCRIPTOR_SYMBOL: &[u8] = b"unimodel_plugin_descriptor\0";
+
+/// 插件分配的字节缓冲区
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct UniModelBuffer {
+    pub data: *mut u8,
+    pub len: usize,
+}
+
+impl UniModelBuffer {
+    const EMPTY: UniModelBuffer = UniModelBuffer {
+        data: std::ptr::null_mut(),
+        len: 0,
+    };
+}
+
+/// 插件调用：MessagePack请求进，响应或错误信息出
+pub type PluginCall =
+    unsafe extern "C" fn(request: *const u8, request_len: usize, response: *mut UniModelBuffer) -> i32;
+
+/// 插件函数表
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct UniModelPluginVTable {
+    /// 插件编译时的函数表大小
+    pub struct_size: usize,
+    pub load_model: PluginCall,
+    pub unload_model: PluginCall,
+    pub infer: PluginCall,
+    pub free_buffer: unsafe extern "C" fn(buffer: UniModelBuffer),
+    /// 可选，不支持LoRA适配器的插件置空
+    pub load_adapter: Option<PluginCall>,
+    /// 可选
+    pub unload_adapter: Option<PluginCall>,
+    /// 可选，返回各后端的能力声明
+    pub capabilities: Option<PluginCall>,
+}
+
+/// 插件描述符
+#[repr(C)]
+#[derive(Debug)]
+pub struct UniModelPluginDescriptor {
+    pub abi_version: u32,
+    pub name: *const c_char,
+    pub version: *const c_char,
+    /// 插件提供的后端名称，以NULL结尾
+    pub backends: *const *const c_char,
+    pub vtable: UniModelPluginVTable,
+}
+
+/// `load_model` 的请求
+#[derive(Debug, Serialize)]
+struct LoadRequest<'a> {
+    model_id: &'a str,
+    model_path: &'a str,
+    config: &'a ModelConfig,
+    /// 多GPU的切分方案，单卡运行时为空
+    parallel: Option<&'a ParallelPlan>,
+    /// 请求的计算精度，FP16/BF16时插件启用自动混合精度
+    precision: Precision,
+}
+
+/// `load_model` 的响应
+#[derive(Debug, Clone, Copy, Deserialize)]
+pub struct LoadedModel {
+    pub handle: u64,
+    #[serde(default)]
+    pub supports_batching: bool,
+    #[serde(default = "default_max_batch_size")]
+    pub max_batch_size: u32,
+    #[serde(default)]
+    pub supports_multi_lora: bool,
+    /// 插件实际使用的计算精度，未报告时按能力声明推断
+    #[serde(default)]
+    pub precision: Option<Precision>,
+}
+
+fn default_max_batch_size() -> u32 {
+    1
+}
+
+#[derive(Debug, Serialize)]
+struct HandleRequest {
+    handle: u64,
+}
+
+#[derive(Debug, Serialize)]
+struct InferRequest<'a> {
+    handle: u64,
+    inputs: &'a [InputData],
+    parameters: &'a PredictionParameters,
+    /// 执行该批次的CUDA流编号，见 `OptimizationConfig::cuda_streams`
+    stream: u32,
+    /// 各输入使用的LoRA适配器，批次混用适配器时才发送
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    adapters: &'a [Option<String>],
+}
+
+#[derive(Debug, Serialize)]
+struct AdapterRequest<'a> {
+    handle: u64,
+    name: &'a str,
+    path: Option<&'a str>,
+}
+
+/// 已加载的C ABI插件
+pub struct NativePlugin {
+    name: String,
+    version: String,
+    backends: Vec<String>,
+    capabilities: Vec<BackendCapabilities>,
+    path: PathBuf,
+    vtable: UniModelPluginVTable,
+    /// 函数表指向库中的代码，库须比函数表活得更久
+    _library: Library,
+}
+
+impl std::fmt::Debug for NativePlugin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("NativePlugin")
+            .field("name", &self.name)
+            .field("version", &self.version)
+            .field("backends", &self.backends)
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+/// 读取插件提供的C字符串
+///
+/// # Safety
+/// `ptr` 为空或指向以NUL结尾的字符串。
+unsafe fn c_string(ptr: *const c_char) -> Option<String> {
+    if ptr.is_null() {
+        return None;
+    }
+    Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+/// 按插件声明的 `struct_size` 读取函数表，旧插件缺少的末尾可选函数视为不支持
+///
+/// # Safety
+/// `table` 指向插件导出的函数表，且至少包含 `struct_size` 字节。
+unsafe fn read_vtable(table: *const UniModelPluginVTable) -> Option<UniModelPluginVTable> {
+    use std::ptr::addr_of;
+
+    let struct_size = addr_of!((*table).struct_size).read();
+    let has = |offset: usize| offset + std::mem::size_of::<Option<PluginCall>>() <= struct_size;
+    let load_adapter_offset = addr_of!((*table).load_adapter) as usize - table as usize;
+    if load_adapter_offset > struct_size {
+        return None;
+    }
+    let unload_adapter_offset = addr_of!((*table).unload_adapter) as usize - table as usize;
+    let capabilities_offset = addr_of!((*table).capabilities) as usize - table as usize;
+    Some(UniModelPluginVTable {
+        struct_size,
+        load_model: addr_of!((*table).load_model).read(),
+        unload_model: addr_of!((*table).unload_model).read(),
+        infer: addr_of!((*table).infer).read(),
+        free_buffer: addr_of!((*table).free_buffer).read(),
+        load_adapter: if has(load_adapter_offset) { addr_of!((*table).load_adapter).read() } else { None },
+        unload_adapter: if has(unload_adapter_offset) { addr_of!((*table).unload_adapter).read() } else { None },
+        capabilities: if has(capabilities_offset) { addr_of!((*table).capabilities).read() } else { None },
+    })
+}
+
+impl NativePlugin {
+    /// 打开动态库并完成ABI握手，不兼容的插件返回错误
+    pub fn open(path: &Path) -> Result<Self> {
+        let display = path.display();
+        // 加载动态库会执行其初始化代码，插件目录只应包含受信任的库
+        let library = unsafe { Library::new(path) }
+            .map_err(|e| UniModelError::plugin(format!("Cannot load plugin {}: {}", display, e)))?;
+
+        let abi_version = unsafe {
+            let symbol = library
+                .get::<unsafe extern "C" fn() -> u32>(ABI_VERSION_SYMBOL)
+                .map_err(|_| UniModelError::plugin(format!("{} is not a UniModel plugin", display)))?;
+            symbol()
+        };
+        if abi_version != ABI_VERSION {
+            return Err(UniModelError::plugin(format!(
+                "Plugin {} was built for ABI version {}, the server needs {}",
+                display, abi_version, ABI_VERSION
+            )));
+        }
+
+        let descriptor = unsafe {
+            let symbol = library
+                .get::<unsafe extern "C" fn() -> *const UniModelPluginDescriptor>(DESCRIPTOR_SYMBOL)
+                .map_err(|_| UniModelError::plugin(format!("Plugin {} has no descriptor", display)))?;
+            symbol()
+        };
+        if descriptor.is_null() {
+            return Err(UniModelError::plugin(format!("Plugin {} returned a null descriptor", display)));
+        }
+        let declared = unsafe { std::ptr::addr_of!((*descriptor).abi_version).read() };
+        if declared != ABI_VERSION {
+            return Err(UniModelError::plugin(format!(
+                "Plugin {} declares ABI version {} in its descriptor, expected {}",
+                display, declared, ABI_VERSION
+            )));
+        }
+        let vtable = unsafe { read_vtable(std::ptr::addr_of!((*descriptor).vtable)) }
+            .ok_or_else(|| UniModelError::plugin(format!("Plugin {} has a truncated function table", display)))?;
+        let (name, version, backends) = unsafe {
+            use std::ptr::addr_of;
+
+            let mut backends = Vec::new();
+            let mut cursor = addr_of!((*descriptor).backends).read();
+            if !cursor.is_null() {
+                while let Some(backend) = c_string(*cursor) {
+                    backends.push(backend);
+                    cursor = cursor.add(1);
+                }
+            }
+            (
+                c_string(addr_of!((*descriptor).name).read()).unwrap_or_default(),
+                c_string(addr_of!((*descriptor).version).read()).unwrap_or_default(),
+                backends,
+            )
+        };
+        if name.is_empty() {
+            return Err(UniModelError::plugin(format!("Plugin {} has no name", display)));
+        }
+        if backends.is_empty() {
+            return Err(UniModelError::plugin(format!("Plugin {} provides no backends", display)));
+        }
+        info!("Loaded plugin '{}' {} from {}, backends: {}", name, version, path.display(), backends.join(", "));
+
+        let mut plugin = Self {
+            name,
+            version,
+            capabilities: backends.iter().map(BackendCapabilities::undeclared).collect(),
+            backends,
+            path: path.to_path_buf(),
+            vtable,
+            _library: library,
+        };
+        if let Some(call) = plugin.vtable.capabilities {
+            let declared: Vec<BackendCapabilities> = plugin.call(call, "capabilities", &serde_json::json!({}))?;
+            for capabilities in declared {
+                match plugin.capabilities.iter_mut().find(|c| c.backend == capabilities.backend) {
+                    Some(slot) => *slot = capabilities,
+                    None => warn!(
+                        "Plugin '{}' declares capabilities for unknown backend '{}'",
+                        plugin.name, capabilities.backend
+                    ),
+                }
+            }
+        }
+        Ok(plugin)
+    }
+
+    /// 插件名称
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// 插件版本
+    pub fn version(&self) -> &str {
+        &self.version
+    }
+
+    /// 插件提供的后端
+    pub fn backends(&self) -> &[String] {
+        &self.backends
+    }
+
+    /// 各后端的能力声明，未声明的后端不受限制
+    pub fn capabilities(&self) -> &[BackendCapabilities] {
+        &self.capabilities
+    }
+
+    /// 动态库路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 加载模型，在阻塞线程池中调用；多GPU并行时按 `parallel` 把权重分到各GPU
+    pub fn load_model(
+        &self,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        parallel: Option<&ParallelPlan>,
+        precision: Precision,
+    ) -> Result<LoadedModel> {
+        let request = LoadRequest {
+            model_id,
+            model_path: &config.model_path,
+            config,
+            parallel,
+            precision,
+        };
+        self.call(self.vtable.load_model, "load_model", &request)
+    }
+
+    /// 卸载模型
+    pub fn unload_model(&self, handle: u64) -> Result<()> {
+        self.call::<_, serde::de::IgnoredAny>(self.vtable.unload_model, "unload_model", &HandleRequest { handle })
+            .map(|_| ())
+    }
+
+    /// 在 `stream` 号流上执行推理，在阻塞线程池中调用
+    ///
+    /// `adapters` 非空时与 `inputs` 等长，为各输入选择LoRA适配器，只能发给声明 `supports_multi_lora` 的插件。
+    pub fn infer(
+        &self,
+        handle: u64,
+        inputs: &[InputData],
+        parameters: &PredictionParameters,
+        adapters: &[Option<String>],
+        stream: u32,
+    ) -> Result<Vec<OutputData>> {
+        let request = InferRequest {
+            handle,
+            inputs,
+            parameters,
+            stream,
+            adapters,
+        };
+        let outputs: Vec<OutputData> = self.call(self.vtable.infer, "infer", &request)?;
+        if outputs.len() != inputs.len() {
+            return Err(UniModelError::plugin(format!(
+                "Plugin '{}' returned {} outputs for {} inputs",
+                self.name,
+                outputs.len(),
+                inputs.len()
+            )));
+        }
+        Ok(outputs)
+    }
+
+    /// 加载LoRA适配器
+    pub fn load_adapter(&self, handle: u64, name: &str, path: &str) -> Result<()> {
+        let call = self.vtable.load_adapter.ok_or_else(|| {
+            UniModelError::validation(format!("Plugin '{}' does not support LoRA adapters", self.name))
+        })?;
+        let request = AdapterRequest {
+            handle,
+            name,
+            path: Some(path),
+        };
+        self.call::<_, serde::de::IgnoredAny>(call, "load_adapter", &request).map(|_| ())
+    }
+
+    /// 卸载LoRA适配器
+    pub fn unload_adapter(&self, handle: u64, name: &str) -> Result<()> {
+        let call = self.vtable.unload_adapter.ok_or_else(|| {
+            UniModelError::validation(format!("Plugin '{}' does not support LoRA adapters", self.name))
+        })?;
+        let request = AdapterRequest {
+            handle,
+            name,
+            path: None,
+        };
+        self.call::<_, serde::de::IgnoredAny>(call, "unload_adapter", &request).map(|_| ())
+    }
+
+    fn call<P: Serialize, R: DeserializeOwned>(&self, function: PluginCall, method: &str, request: &P) -> Result<R> {
+        let payload = rmp_serde::to_vec_named(request)
+            .map_err(|e| UniModelError::internal(format!("Cannot encode plugin request: {}", e)))?;
+        let mut buffer = UniModelBuffer::EMPTY;
+        let status = unsafe { function(payload.as_ptr(), payload.len(), &mut buffer) };
+
+        let response = if buffer.data.is_null() {
+            Vec::new()
+        } else {
+            let bytes = unsafe { std::slice::from_raw_parts(buffer.data, buffer.len) }.to_vec();
+            unsafe { (self.vtable.free_buffer)(buffer) };
+            bytes
+        };
+        if status != 0 {
+            return Err(UniModelError::plugin(format!(
+                "Plugin '{}' {} failed ({}): {}",
+                self.name,
+                method,
+                status,
+                String::from_utf8_lossy(&response)
+            )));
+        }
+        rmp_serde::from_slice(&response)
+            .map_err(|e| UniModelError::plugin(format!("Malformed {} response from plugin '{}': {}", method, self.name, e)))
+    }
+}
+
+// 插件按ABI约定保证函数表可被多个线程同时调用
+unsafe impl Send for NativePlugin {}
+unsafe impl Sync for NativePlugin {}
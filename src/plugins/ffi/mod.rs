@@ -0,0 +1,7 @@
+//! 外部语言后端
+
+pub mod c_ffi;
+pub mod python_ffi;
+
+pub use c_ffi::NativePlugin;
+pub use python_ffi::{PythonWorker, PythonWorkerConfig};
@@ -0,0 +1,312 @@
+//! Python后端
+//!
+//! 每个模型启动一个受管的Python工作进程（`python -m unimodel.worker <脚本>`），
+//! 用户脚本实现 `unimodel.plugins.base.ModelPlugin` 的 `load`/`predict`/`unload`，
+//! 不需要编写Rust即可服务任意Python模型。进程崩溃不会影响服务器本身。
+//!
+//! 与工作进程通过标准输入输出交换消息：每帧为4字节小端长度加MessagePack编码的请求或响应，
+//! 请求带递增的 `id`，响应按 `id` 匹配，可同时有多个请求在途。
+//! 工作进程的标准错误（含用户脚本的 `print`）转发到日志。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use dashmap::DashMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::process::{Child, ChildStdin, ChildStdout, Command};
+use tokio::sync::{oneshot, Mutex};
+use tracing::{debug, info, warn};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::ModelConfig;
+
+/// 后端名称，对应 `ModelConfig::backend`
+pub const BACKEND_NAME: &str = "python";
+
+/// 指定用户脚本的自定义参数名；`model_path` 本身是 `.py` 文件时可省略
+pub const SCRIPT_PARAM: &str = "python_script";
+
+/// 单帧大小上限
+const MAX_FRAME_BYTES: u32 = 256 * 1024 * 1024;
+
+/// 后端配置，取自 `plugins.plugin_configs.python`
+#[derive(Debug, Clone, Deserialize)]
+pub struct PythonWorkerConfig {
+    /// Python解释器
+    #[serde(default = "default_interpreter")]
+    pub interpreter: String,
+    /// 追加到 `PYTHONPATH` 的目录（`unimodel` 包所在目录等）
+    #[serde(default)]
+    pub python_path: Vec<String>,
+    /// 传给工作进程的环境变量
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// 等待用户脚本 `load` 完成的时间（秒）
+    #[serde(default = "default_load_timeout_secs")]
+    pub load_timeout_secs: u64,
+    /// 单次 `predict` 的超时（秒）
+    #[serde(default = "default_request_timeout_secs")]
+    pub request_timeout_secs: u64,
+}
+
+fn default_interpreter() -> String {
+    "python3".to_string()
+}
+
+fn default_load_timeout_secs() -> u64 {
+    600
+}
+
+fn default_request_timeout_secs() -> u64 {
+    300
+}
+
+impl Default for PythonWorkerConfig {
+    fn default() -> Self {
+        Self {
+            interpreter: default_interpreter(),
+            python_path: Vec::new(),
+            env: HashMap::new(),
+            load_timeout_secs: default_load_timeout_secs(),
+            request_timeout_secs: default_request_timeout_secs(),
+        }
+    }
+}
+
+/// 发给工作进程的请求
+#[derive(Debug, Serialize)]
+struct WorkerRequest<'a, P> {
+    id: u64,
+    method: &'a str,
+    params: P,
+}
+
+/// 只读取响应的 `id`，用于把响应交给对应的调用方
+#[derive(Debug, Deserialize)]
+struct ResponseEnvelope {
+    id: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct WorkerResponse<R> {
+    result: Option<R>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct LoadParams<'a> {
+    model_path: &'a str,
+    config: &'a ModelConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct PredictParams<'a> {
+    inputs: &'a [InputData],
+    parameters: &'a PredictionParameters,
+}
+
+type PendingCalls = DashMap<u64, oneshot::Sender<Vec<u8>>>;
+
+/// 受管的Python工作进程
+#[derive(Debug)]
+pub struct PythonWorker {
+    model_id: ModelId,
+    child: Mutex<Child>,
+    stdin: Mutex<ChildStdin>,
+    pending: Arc<PendingCalls>,
+    next_id: AtomicU64,
+    alive: Arc<AtomicBool>,
+    request_timeout: Duration,
+}
+
+impl PythonWorker {
+    /// 启动工作进程并加载模型
+    pub async fn start(model_id: &ModelId, config: &ModelConfig, worker: &PythonWorkerConfig) -> Result<Self> {
+        let script = user_script(config)?;
+        let mut command = Command::new(&worker.interpreter);
+        command
+            .arg("-m")
+            .arg("unimodel.worker")
+            .arg(&script)
+            .envs(&worker.env)
+            .env("PYTHONUNBUFFERED", "1")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if !worker.python_path.is_empty() {
+            let mut paths = worker.python_path.clone();
+            paths.extend(std::env::var("PYTHONPATH").ok());
+            command.env("PYTHONPATH", paths.join(if cfg!(windows) { ";" } else { ":" }));
+        }
+        let mut child = command.spawn().map_err(|e| {
+            UniModelError::plugin_unavailable(format!("Cannot start Python worker '{}': {}", worker.interpreter, e))
+        })?;
+        let (stdin, stdout, stderr) = match (child.stdin.take(), child.stdout.take(), child.stderr.take()) {
+            (Some(stdin), Some(stdout), Some(stderr)) => (stdin, stdout, stderr),
+            _ => return Err(UniModelError::internal("Python worker pipes are unavailable")),
+        };
+        info!("Started Python worker (pid {:?}) for model {} with {}", child.id(), model_id, script.display());
+
+        let pending = Arc::new(PendingCalls::new());
+        let alive = Arc::new(AtomicBool::new(true));
+        tokio::spawn(read_responses(model_id.clone(), stdout, Arc::clone(&pending), Arc::clone(&alive)));
+        let log_model_id = model_id.clone();
+        tokio::spawn(async move {
+            let mut lines = BufReader::new(stderr).lines();
+            while let Ok(Some(line)) = lines.next_line().await {
+                info!(target: "python_worker", "[{}] {}", log_model_id, line);
+            }
+        });
+
+        let worker_handle = Self {
+            model_id: model_id.clone(),
+            child: Mutex::new(child),
+            stdin: Mutex::new(stdin),
+            pending,
+            next_id: AtomicU64::new(1),
+            alive,
+            request_timeout: Duration::from_secs(worker.request_timeout_secs),
+        };
+        let params = LoadParams {
+            model_path: &config.model_path,
+            config,
+        };
+        let load_timeout = Duration::from_secs(worker.load_timeout_secs);
+        if let Err(e) = worker_handle.call::<_, serde::de::IgnoredAny>("load", &params, load_timeout).await {
+            worker_handle.shutdown().await;
+            return Err(e);
+        }
+        Ok(worker_handle)
+    }
+
+    /// 工作进程是否仍在运行
+    pub fn is_alive(&self) -> bool {
+        self.alive.load(Ordering::Acquire)
+    }
+
+    /// 执行推理
+    pub async fn predict(&self, inputs: &[InputData], parameters: &PredictionParameters) -> Result<Vec<OutputData>> {
+        let params = PredictParams { inputs, parameters };
+        self.call("predict", &params, self.request_timeout).await
+    }
+
+    /// 健康检查，用户脚本可覆盖 `health` 报告自身状态
+    pub async fn health(&self) -> Result<bool> {
+        self.call("health", &(), Duration::from_secs(10)).await
+    }
+
+    /// 通知用户脚本卸载并结束进程
+    pub async fn shutdown(&self) {
+        if self.is_alive() {
+            if let Err(e) = self.call::<_, serde::de::IgnoredAny>("unload", &(), Duration::from_secs(30)).await {
+                warn!("Python worker for model {} failed to unload: {}", self.model_id, e);
+            }
+        }
+        let mut child = self.child.lock().await;
+        let _ = child.kill().await;
+        self.alive.store(false, Ordering::Release);
+        debug!("Python worker for model {} stopped", self.model_id);
+    }
+
+    async fn call<P: Serialize, R: DeserializeOwned>(&self, method: &str, params: &P, timeout: Duration) -> Result<R> {
+        if !self.is_alive() {
+            return Err(UniModelError::plugin_unavailable(format!("Python worker for model {} has exited", self.model_id)));
+        }
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let payload = rmp_serde::to_vec_named(&WorkerRequest { id, method, params })
+            .map_err(|e| UniModelError::internal(format!("Cannot encode worker request: {}", e)))?;
+        let (tx, rx) = oneshot::channel();
+        self.pending.insert(id, tx);
+
+        let written: std::io::Result<()> = async {
+            let mut stdin = self.stdin.lock().await;
+            stdin.write_all(&(payload.len() as u32).to_le_bytes()).await?;
+            stdin.write_all(&payload).await?;
+            stdin.flush().await
+        }
+        .await;
+        if let Err(e) = written {
+            self.pending.remove(&id);
+            return Err(UniModelError::plugin_unavailable(format!("Python worker for model {} is gone: {}", self.model_id, e)));
+        }
+
+        let frame = match tokio::time::timeout(timeout, rx).await {
+            Ok(Ok(frame)) => frame,
+            Ok(Err(_)) => {
+                return Err(UniModelError::plugin_unavailable(format!("Python worker for model {} exited", self.model_id)));
+            }
+            Err(_) => {
+                self.pending.remove(&id);
+                return Err(UniModelError::plugin(format!(
+                    "Python worker for model {} did not answer '{}' within {:?}",
+                    self.model_id, method, timeout
+                )));
+            }
+        };
+        let response: WorkerResponse<R> = rmp_serde::from_slice(&frame)
+            .map_err(|e| UniModelError::plugin(format!("Malformed response from Python worker: {}", e)))?;
+        match (response.result, response.error) {
+            (_, Some(error)) => Err(UniModelError::plugin(format!("Python worker: {}", error))),
+            (Some(result), None) => Ok(result),
+            (None, None) => Err(UniModelError::plugin(format!("Python worker returned no result for '{}'", method))),
+        }
+    }
+}
+
+/// 读取响应帧并交给等待的调用方；进程退出后所有在途调用随之失败
+async fn read_responses(model_id: ModelId, mut stdout: ChildStdout, pending: Arc<PendingCalls>, alive: Arc<AtomicBool>) {
+    loop {
+        let len = match stdout.read_u32_le().await {
+            Ok(len) if len <= MAX_FRAME_BYTES => len,
+            Ok(len) => {
+                warn!("Python worker for model {} sent a {}-byte frame, closing", model_id, len);
+                break;
+            }
+            Err(_) => break,
+        };
+        let mut frame = vec![0; len as usize];
+        if stdout.read_exact(&mut frame).await.is_err() {
+            break;
+        }
+        match rmp_serde::from_slice::<ResponseEnvelope>(&frame) {
+            Ok(envelope) => {
+                if let Some((_, tx)) = pending.remove(&envelope.id) {
+                    let _ = tx.send(frame);
+                }
+            }
+            Err(e) => warn!("Ignoring malformed frame from Python worker for model {}: {}", model_id, e),
+        }
+    }
+    alive.store(false, Ordering::Release);
+    // 丢弃发送端，等待中的调用收到进程退出错误
+    pending.clear();
+    warn!("Python worker for model {} exited", model_id);
+}
+
+/// 用户脚本路径：`custom_params.python_script`，或 `.py` 结尾的 `model_path`
+fn user_script(config: &ModelConfig) -> Result<PathBuf> {
+    let script = match config.custom_params.get(SCRIPT_PARAM).and_then(|v| v.as_str()) {
+        Some(script) => PathBuf::from(script),
+        None if Path::new(&config.model_path).extension().is_some_and(|ext| ext == "py") => {
+            PathBuf::from(&config.model_path)
+        }
+        None => {
+            return Err(UniModelError::validation(format!(
+                "Python backend needs custom_params.{} or a .py model_path",
+                SCRIPT_PARAM
+            )))
+        }
+    };
+    if !script.is_file() {
+        return Err(UniModelError::validation(format!("Python script '{}' does not exist", script.display())));
+    }
+    Ok(script)
+}
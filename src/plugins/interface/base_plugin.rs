@@ -0,0 +1,199 @@
+//! 插件基础接口
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::common::error::*;
+use crate::domain::model::{DeviceType, ModelConfig, Parallelism, Precision, QuantizationType};
+use crate::domain::service::conversion::ModelFormat;
+use crate::infrastructure::model_format::{SafeTensorsFile, TensorView};
+
+/// 插件加载模型时拿到的权重
+#[derive(Debug, Clone)]
+pub enum ModelWeights {
+    /// safetensors 文件（分片模型按文件名排序），张量数据通过mmap零拷贝读取
+    SafeTensors(Vec<Arc<SafeTensorsFile>>),
+    /// 由后端自行解析的模型文件
+    File(PathBuf),
+}
+
+impl ModelWeights {
+    /// 按模型路径打开权重
+    ///
+    /// `.safetensors` 文件或包含 `.safetensors` 分片的目录会被预先解析，其余格式交给后端。
+    pub fn open(config: &ModelConfig) -> Result<Self> {
+        Self::open_path(Path::new(&config.model_path))
+    }
+
+    /// 按路径打开权重
+    pub fn open_path(path: &Path) -> Result<Self> {
+        if path.is_dir() {
+            let mut shards: Vec<PathBuf> = std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| is_safetensors(p))
+                .collect();
+            if shards.is_empty() {
+                return Ok(ModelWeights::File(path.to_path_buf()));
+            }
+            shards.sort();
+
+            let files = shards
+                .iter()
+                .map(|shard| SafeTensorsFile::open(shard).map(Arc::new))
+                .collect::<Result<Vec<_>>>()?;
+            return Ok(ModelWeights::SafeTensors(files));
+        }
+
+        if is_safetensors(path) {
+            return Ok(ModelWeights::SafeTensors(vec![Arc::new(SafeTensorsFile::open(path)?)]));
+        }
+
+        Ok(ModelWeights::File(path.to_path_buf()))
+    }
+
+    /// 按名称在所有分片中查找张量
+    pub fn tensor(&self, name: &str) -> Option<TensorView<'_>> {
+        match self {
+            ModelWeights::SafeTensors(files) => files.iter().find_map(|f| f.tensor(name)),
+            ModelWeights::File(_) => None,
+        }
+    }
+}
+
+fn is_safetensors(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "safetensors")
+}
+
+/// 后端的离线量化能力
+///
+/// 模型配置要求量化而模型文件是全精度时，加载前调用 `quantize` 生成量化后的模型文件，
+/// 见 [`crate::domain::service::quantization::QuantizedCache`]。
+pub trait Quantizer: Send + Sync + std::fmt::Debug {
+    /// 能否产出该量化类型
+    fn supports(&self, quantization: &QuantizationType) -> bool;
+
+    /// 量化权重并把结果写入 `output`，格式须能被同一后端直接加载
+    fn quantize(&self, weights: &ModelWeights, quantization: &QuantizationType, output: &Path) -> Result<()>;
+}
+
+/// 后端能力声明，插件加载时报告
+///
+/// 列表为空表示未声明，校验时不限制该项。
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackendCapabilities {
+    /// 后端名称，对应 `ModelConfig::backend`
+    pub backend: String,
+    /// 能直接加载的模型格式
+    #[serde(default)]
+    pub formats: Vec<ModelFormat>,
+    /// 支持的设备类型
+    #[serde(default)]
+    pub devices: Vec<DeviceType>,
+    /// 单批最大请求数，0表示不限制
+    #[serde(default)]
+    pub max_batch_size: u32,
+    /// 是否支持流式输出
+    #[serde(default)]
+    pub streaming: bool,
+    /// 加载时支持的量化类型
+    #[serde(default)]
+    pub quantization: Vec<QuantizationType>,
+    /// 能否按切分方案把模型分布到多张GPU上执行（张量并行），未声明视为不支持
+    #[serde(default)]
+    pub tensor_parallel: bool,
+    /// 能否按层把模型分为多个阶段放到多张GPU上执行（流水线并行），未声明视为不支持
+    #[serde(default)]
+    pub pipeline_parallel: bool,
+    /// 混合精度时能自动转换到的低精度（FP16/BF16）
+    #[serde(default)]
+    pub mixed_precision: Vec<Precision>,
+}
+
+impl BackendCapabilities {
+    /// 未声明任何能力的后端
+    pub fn undeclared(backend: impl Into<String>) -> Self {
+        Self {
+            backend: backend.into(),
+            formats: Vec::new(),
+            devices: Vec::new(),
+            max_batch_size: 0,
+            streaming: false,
+            quantization: Vec::new(),
+            tensor_parallel: false,
+            pipeline_parallel: false,
+            mixed_precision: Vec::new(),
+        }
+    }
+
+    /// 模型向后端请求的计算精度
+    ///
+    /// 未启用混合精度时为FP32；启用时取设备优先的低精度中后端声明支持的第一个，都不支持时按FP32运行。
+    /// 后端未声明时请求设备最优先的精度，实际精度以插件加载时的报告为准。
+    pub fn precision(&self, config: &ModelConfig) -> Precision {
+        if !config.device.mixed_precision {
+            return Precision::FP32;
+        }
+        let preferred = config.device.device_type.autocast_precisions();
+        if self.mixed_precision.is_empty() {
+            return preferred[0];
+        }
+        preferred
+            .into_iter()
+            .find(|precision| self.mixed_precision.contains(precision))
+            .unwrap_or(Precision::FP32)
+    }
+
+    /// 检查模型配置是否在后端能力范围内
+    pub fn check(&self, config: &ModelConfig) -> Result<()> {
+        if let Some(format) = ModelFormat::inspect(Path::new(&config.model_path)) {
+            if !self.formats.is_empty() && !self.formats.contains(&format) {
+                return Err(UniModelError::validation(format!(
+                    "Backend '{}' cannot load {} models",
+                    self.backend,
+                    format.name()
+                )));
+            }
+        }
+        if !self.devices.is_empty() && !self.devices.contains(&config.device.device_type) {
+            return Err(UniModelError::validation(format!(
+                "Backend '{}' does not run on {:?} devices",
+                self.backend, config.device.device_type
+            )));
+        }
+        if let Some(quantization) = &config.optimization.quantization {
+            if !self.quantization.is_empty() && !self.quantization.contains(quantization) {
+                return Err(UniModelError::validation(format!(
+                    "Backend '{}' does not support {} quantization",
+                    self.backend,
+                    quantization.name()
+                )));
+            }
+        }
+        if config.device.parallelism == Parallelism::Tensor && !self.tensor_parallel {
+            return Err(UniModelError::validation(format!(
+                "Backend '{}' does not support tensor parallelism",
+                self.backend
+            )));
+        }
+        if config.device.parallelism == Parallelism::Pipeline && !self.pipeline_parallel {
+            return Err(UniModelError::validation(format!(
+                "Backend '{}' does not support pipeline parallelism",
+                self.backend
+            )));
+        }
+        if self.max_batch_size > 0 && config.batch_config.max_batch_size > self.max_batch_size {
+            return Err(UniModelError::validation(format!(
+                "Backend '{}' accepts batches of at most {}, max_batch_size is {}",
+                self.backend, self.max_batch_size, config.batch_config.max_batch_size
+            )));
+        }
+        Ok(())
+    }
+
+    /// 是否明确声明能加载该格式
+    pub fn declares_format(&self, format: ModelFormat) -> bool {
+        self.formats.contains(&format)
+    }
+}
@@ -0,0 +1,163 @@
+//! LLM插件接口
+
+use std::sync::Arc;
+
+use crate::common::error::*;
+use crate::common::types::*;
+
+/// 单步解码结果
+#[derive(Debug, Clone)]
+pub struct DecodeStep {
+    /// 序列ID
+    pub sequence_id: String,
+    /// 本步生成的文本片段
+    pub token: String,
+    /// 序列是否已结束（遇到EOS等）
+    pub finished: bool,
+    /// 后端原生处理停止序列时命中的序列
+    pub stop_sequence: Option<String>,
+    /// 请求开启 `logprobs` 时本步token的对数概率
+    pub logprob: Option<TokenLogprob>,
+    /// 请求多个候选（`n` 或 `num_beams` 大于1）时，序列结束这一步返回的其余候选，按得分从高到低
+    pub candidates: Vec<String>,
+    /// 投机解码时本步额外接受的草稿token数，本步共生成 `1 + draft_tokens` 个token
+    pub draft_tokens: u32,
+}
+
+/// 逐token解码接口
+///
+/// 支持连续批处理的后端实现该接口：调度器在token边界加入新序列、
+/// 移除已完成序列，每次调用 `step` 对当前所有活跃序列前进一步。
+pub trait IterativeDecoder: Send + Sync + std::fmt::Debug {
+    /// 加入新序列并执行prefill
+    ///
+    /// 指定了 `PredictionParameters::seed` 的序列应使用独立的随机数发生器，
+    /// 采样结果不受同批次其他序列影响。
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()>;
+
+    /// 对给定的活跃序列执行一步解码
+    fn step(&self, sequence_ids: &[String]) -> Result<Vec<DecodeStep>>;
+
+    /// 释放序列占用的资源（KV缓存等）
+    fn remove_sequence(&self, sequence_id: &str);
+
+    /// 带 `session_id` 的序列正常结束后调用：保留其KV缓存，同一会话下一次 `add_sequence`
+    /// 可复用与新提示的共同前缀。默认直接释放。
+    fn retain_for_session(&self, sequence_id: &str, _session_id: &str) {
+        self.remove_sequence(sequence_id);
+    }
+
+    /// 会话过期或被挤出时释放其保留的KV缓存
+    fn drop_session(&self, _session_id: &str) {}
+
+    /// 是否在解码时原生处理 `PredictionParameters::stop`，否则由调度器扫描输出
+    fn supports_stop_sequences(&self) -> bool {
+        false
+    }
+
+    /// 是否支持 `PredictionParameters::logit_bias`，不支持时带偏置的请求被拒绝
+    fn supports_logit_bias(&self) -> bool {
+        false
+    }
+
+    /// 是否能按 `PredictionParameters::logprobs` 返回对数概率，不支持时这类请求被拒绝
+    fn supports_logprobs(&self) -> bool {
+        false
+    }
+
+    /// 是否支持 `PredictionParameters::n` 和 `num_beams`，不支持时多候选请求被拒绝
+    fn supports_multiple_completions(&self) -> bool {
+        false
+    }
+
+    /// 是否支持 `PredictionParameters::grammar`，后端在采样时按语法屏蔽token
+    /// （见 [`crate::domain::service::grammar::apply_grammar_mask`]），不支持时带语法的请求被拒绝
+    fn supports_grammar(&self) -> bool {
+        false
+    }
+
+    /// 是否支持 `PredictionParameters::adapter`：每个序列在 `add_sequence` 时选择自己的LoRA适配器，
+    /// 同一批次中的序列可使用不同适配器。不支持时带适配器的请求被拒绝
+    fn supports_adapters(&self) -> bool {
+        false
+    }
+
+    /// 作为其他模型投机解码的草稿模型，不支持时返回 `None`
+    fn as_draft_model(self: Arc<Self>) -> Option<Arc<dyn DraftModel>> {
+        None
+    }
+
+    /// 作为投机解码的目标模型验证草稿，不支持时返回 `None`
+    fn as_speculative_target(self: Arc<Self>) -> Option<Arc<dyn SpeculativeTarget>> {
+        None
+    }
+}
+
+/// 草稿模型提出的token
+#[derive(Debug, Clone, PartialEq)]
+pub struct DraftToken {
+    pub token_id: u32,
+    pub text: String,
+    /// 草稿模型采样该token的概率，目标模型据此做接受判定
+    pub prob: f32,
+}
+
+/// 目标模型对一条序列草稿的验证结果
+#[derive(Debug, Clone)]
+pub struct Verification {
+    pub sequence_id: String,
+    /// 被接受的草稿前缀长度
+    pub accepted: usize,
+    /// 目标模型在接受的前缀之后生成的token：拒绝位置的修正token，或全部接受时的下一个token
+    pub next_token: DraftToken,
+    /// 序列是否已结束（接受的前缀或 `next_token` 中出现EOS）
+    pub finished: bool,
+}
+
+/// 投机解码的草稿模型
+///
+/// 小模型按自回归方式一次提出若干token，验证后回滚到被接受的前缀。
+pub trait DraftModel: Send + Sync + std::fmt::Debug {
+    /// 加入新序列并执行prefill
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()>;
+
+    /// 为序列提出 `num_tokens` 个草稿token
+    fn propose(&self, sequence_id: &str, num_tokens: usize) -> Result<Vec<DraftToken>>;
+
+    /// 保留前 `accepted` 个草稿token的KV缓存，丢弃其余，并追加目标模型生成的token
+    fn commit(&self, sequence_id: &str, accepted: usize, next_token_id: u32) -> Result<()>;
+
+    /// 释放序列占用的资源
+    fn remove_sequence(&self, sequence_id: &str);
+}
+
+/// 投机解码的目标模型
+///
+/// 一次前向计算同时为草稿的每个位置打分，按
+/// [`crate::domain::service::generation::accept_draft`] 逐个接受，
+/// 拒绝时从 [`crate::domain::service::generation::residual_distribution`] 采样修正token。
+pub trait SpeculativeTarget: Send + Sync + std::fmt::Debug {
+    /// 加入新序列并执行prefill
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> Result<()>;
+
+    /// 批量验证各序列的草稿，返回顺序不限
+    fn verify(&self, drafts: &[(String, Vec<DraftToken>)]) -> Result<Vec<Verification>>;
+
+    /// 释放序列占用的资源
+    fn remove_sequence(&self, sequence_id: &str);
+}
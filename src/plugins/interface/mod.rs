@@ -0,0 +1,9 @@
+//! 插件接口定义
+
+pub mod audio_plugin;
+pub mod base_plugin;
+pub mod cv_plugin;
+pub mod llm_plugin;
+
+pub use base_plugin::*;
+pub use llm_plugin::*;
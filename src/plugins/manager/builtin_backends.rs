@@ -0,0 +1,259 @@
+//! 内置后端
+//!
+//! 编译在服务器中的后端由插件管理器直接调用，不经过插件协议。LLM后端加载出的模型是逐token解码器：
+//! 启用连续批处理时由批处理器直接驱动；否则一个批次的各提示作为序列加入解码器，解码到全部结束后一并返回。
+//! Python后端为每个模型启动一个工作进程，卸载时结束。
+//! 依赖外部库或服务的后端在启动时不可用不影响服务器，原因在加载该后端的模型时报告。
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+use dashmap::DashMap;
+use tracing::{debug, info};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::ModelConfig;
+use crate::domain::service::batch_processor::DEFAULT_MAX_NEW_TOKENS;
+use crate::domain::service::generation::{find_stop_sequence, stop_scan_overlap};
+use crate::domain::service::inference_pool::InferencePool;
+use crate::plugins::builtin::candle_plugin::{self, CandleBackend};
+use crate::plugins::builtin::llamacpp_plugin::{self, LlamaCppBackend};
+use crate::plugins::builtin::tensorrt_llm_plugin::{self, TrtLlmBackend};
+use crate::plugins::ffi::python_ffi::{self, PythonWorker, PythonWorkerConfig};
+use crate::plugins::interface::{BackendCapabilities, IterativeDecoder, ModelWeights, Quantizer};
+
+/// 内置后端加载的模型
+#[derive(Debug, Clone)]
+enum BuiltinModel {
+    /// 逐token解码的LLM
+    Decoder(Arc<dyn IterativeDecoder>),
+    /// Python工作进程
+    Python(Arc<PythonWorker>),
+}
+
+/// 内置后端及其加载的模型
+#[derive(Debug)]
+pub struct BuiltinBackends {
+    candle: CandleBackend,
+    llamacpp: Option<LlamaCppBackend>,
+    /// 只在配置了 `plugin_configs.tensorrt_llm` 时启用
+    tensorrt_llm: Option<TrtLlmBackend>,
+    python: PythonWorkerConfig,
+    /// 启动时未能启用的后端及原因
+    unavailable: HashMap<String, String>,
+    models: DashMap<u64, BuiltinModel>,
+    next_handle: AtomicU64,
+}
+
+impl BuiltinBackends {
+    /// 按 `plugins.plugin_configs` 启用内置后端
+    ///
+    /// 配置无法解析时返回错误；库或服务不可用时该后端不启用，不影响其他后端。
+    pub fn new(plugin_configs: &HashMap<String, serde_json::Value>) -> Result<Self> {
+        let mut unavailable = HashMap::new();
+        let llamacpp = match LlamaCppBackend::from_plugin_config(plugin_configs.get(llamacpp_plugin::BACKEND_NAME)) {
+            Ok(backend) => Some(backend),
+            Err(e @ UniModelError::Config(_)) => return Err(e),
+            Err(e) => {
+                info!("Backend '{}' is disabled: {}", llamacpp_plugin::BACKEND_NAME, e);
+                unavailable.insert(llamacpp_plugin::BACKEND_NAME.to_string(), e.to_string());
+                None
+            }
+        };
+        let tensorrt_llm = plugin_configs.get(tensorrt_llm_plugin::BACKEND_NAME)
+            .map(|value| TrtLlmBackend::from_plugin_config(Some(value)))
+            .transpose()?;
+        let python = match plugin_configs.get(python_ffi::BACKEND_NAME) {
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| UniModelError::config(format!("Invalid python plugin config: {}", e)))?,
+            None => PythonWorkerConfig::default(),
+        };
+
+        Ok(Self {
+            candle: CandleBackend::new(),
+            llamacpp,
+            tensorrt_llm,
+            python,
+            unavailable,
+            models: DashMap::new(),
+            next_handle: AtomicU64::new(1),
+        })
+    }
+
+    /// 已启用的后端的能力声明
+    pub fn capabilities(&self) -> Vec<BackendCapabilities> {
+        let mut capabilities = vec![self.candle.capabilities()];
+        capabilities.extend(self.llamacpp.iter().map(LlamaCppBackend::capabilities));
+        capabilities.extend(self.tensorrt_llm.iter().map(TrtLlmBackend::capabilities));
+        capabilities.push(BackendCapabilities::undeclared(python_ffi::BACKEND_NAME));
+        capabilities
+    }
+
+    /// 是否提供该后端
+    pub fn provides(&self, backend: &str) -> bool {
+        self.capabilities().iter().any(|c| c.backend == backend)
+    }
+
+    /// 内置后端的名称及是否已启用，按名称排序
+    pub fn backends(&self) -> Vec<(String, bool)> {
+        let mut backends: Vec<(String, bool)> = self.capabilities()
+            .into_iter()
+            .map(|c| (c.backend, true))
+            .chain(self.unavailable.keys().map(|backend| (backend.clone(), false)))
+            .collect();
+        backends.sort();
+        backends
+    }
+
+    /// 后端的离线量化器，目前只有llama.cpp提供
+    pub fn quantizer(&self, backend: &str) -> Option<Arc<dyn Quantizer>> {
+        match backend {
+            llamacpp_plugin::BACKEND_NAME => self.llamacpp.as_ref().map(|b| Arc::new(b.quantizer()) as Arc<dyn Quantizer>),
+            _ => None,
+        }
+    }
+
+    /// 后端在启动时未能启用的原因
+    pub fn unavailable_reason(&self, backend: &str) -> Option<&str> {
+        self.unavailable.get(backend).map(String::as_str)
+    }
+
+    /// 加载模型，返回实例句柄；读取权重的同步调用在推理线程池中执行
+    pub async fn load(
+        &self,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        weights: &ModelWeights,
+        pool: &InferencePool,
+    ) -> Result<u64> {
+        let model = match config.backend.as_str() {
+            candle_plugin::BACKEND_NAME => {
+                let (backend, config, weights) = (self.candle.clone(), config.clone(), weights.clone());
+                let model = pool.run(move || backend.load(&config, &weights)).await?;
+                BuiltinModel::Decoder(Arc::new(model))
+            }
+            llamacpp_plugin::BACKEND_NAME => {
+                let backend = self.llamacpp.clone().ok_or_else(|| self.not_enabled(&config.backend))?;
+                let (config, weights) = (config.clone(), weights.clone());
+                let model = pool.run(move || backend.load(&config, &weights)).await?;
+                BuiltinModel::Decoder(Arc::new(model))
+            }
+            tensorrt_llm_plugin::BACKEND_NAME => {
+                // 只检查Triton上的引擎是否就绪，不读取本地权重
+                let backend = self.tensorrt_llm.as_ref().ok_or_else(|| self.not_enabled(&config.backend))?;
+                BuiltinModel::Decoder(Arc::new(backend.load(config).await?))
+            }
+            python_ffi::BACKEND_NAME => {
+                BuiltinModel::Python(Arc::new(PythonWorker::start(model_id, config, &self.python).await?))
+            }
+            backend => return Err(self.not_enabled(backend)),
+        };
+        let handle = self.next_handle.fetch_add(1, Ordering::Relaxed);
+        self.models.insert(handle, model);
+        debug!("Builtin backend '{}' loaded {} as handle {}", config.backend, config.model_path, handle);
+        Ok(handle)
+    }
+
+    /// 卸载模型；解码器在正在执行的批次结束后释放，Python工作进程立即结束
+    pub async fn unload(&self, handle: u64) -> Result<()> {
+        let (_, model) = self.models.remove(&handle)
+            .ok_or_else(|| UniModelError::model(format!("Unknown model handle {}", handle)))?;
+        if let BuiltinModel::Python(worker) = model {
+            worker.shutdown().await;
+        }
+        Ok(())
+    }
+
+    /// 对一批输入执行推理，输出与输入一一对应
+    pub async fn infer(
+        &self,
+        handle: u64,
+        inputs: &[InputData],
+        parameters: &PredictionParameters,
+        pool: &InferencePool,
+    ) -> Result<Vec<OutputData>> {
+        match self.model(handle)? {
+            BuiltinModel::Decoder(decoder) => {
+                let (inputs, parameters) = (inputs.to_vec(), parameters.clone());
+                pool.run(move || generate(decoder.as_ref(), &inputs, &parameters)).await
+            }
+            BuiltinModel::Python(worker) => worker.predict(inputs, parameters).await,
+        }
+    }
+
+    /// 模型的逐token解码器，Python模型没有
+    pub fn decoder(&self, handle: u64) -> Option<Arc<dyn IterativeDecoder>> {
+        match self.model(handle).ok()? {
+            BuiltinModel::Decoder(decoder) => Some(decoder),
+            BuiltinModel::Python(_) => None,
+        }
+    }
+
+    fn model(&self, handle: u64) -> Result<BuiltinModel> {
+        self.models.get(&handle)
+            .map(|model| model.clone())
+            .ok_or_else(|| UniModelError::model(format!("Unknown model handle {}", handle)))
+    }
+
+    fn not_enabled(&self, backend: &str) -> UniModelError {
+        match self.unavailable_reason(backend) {
+            Some(reason) => UniModelError::plugin_unavailable(format!("Backend '{}' is unavailable: {}", backend, reason)),
+            None => UniModelError::plugin_unavailable(format!("Backend '{}' is not enabled", backend)),
+        }
+    }
+}
+
+/// 不经连续批处理执行一个批次：各提示作为序列加入解码器，逐步解码到全部结束、
+/// 达到 `max_tokens` 或命中停止序列（截断由批处理器完成）
+fn generate(
+    decoder: &dyn IterativeDecoder,
+    inputs: &[InputData],
+    parameters: &PredictionParameters,
+) -> Result<Vec<OutputData>> {
+    let batch_id = uuid::Uuid::new_v4();
+    let ids: Vec<String> = (0..inputs.len()).map(|i| format!("{}-{}", batch_id, i)).collect();
+    let max_tokens = parameters.max_tokens.unwrap_or(DEFAULT_MAX_NEW_TOKENS).max(1);
+    let native_stop = decoder.supports_stop_sequences();
+    let mut outputs = vec![String::new(); inputs.len()];
+    let mut generated = vec![0u32; inputs.len()];
+    let mut active: Vec<String> = Vec::with_capacity(inputs.len());
+
+    let result = (|| {
+        for (id, input) in ids.iter().zip(inputs) {
+            let prompt = match input {
+                InputData::Text(text) => text,
+                _ => return Err(UniModelError::validation("LLM backends require text input")),
+            };
+            decoder.add_sequence(id, prompt, parameters)?;
+            active.push(id.clone());
+        }
+
+        while !active.is_empty() {
+            for step in decoder.step(&active)? {
+                let index = match ids.iter().position(|id| *id == step.sequence_id) {
+                    Some(index) => index,
+                    None => continue,
+                };
+                let output = &mut outputs[index];
+                let scan_from = output.len().saturating_sub(stop_scan_overlap(&parameters.stop));
+                output.push_str(&step.token);
+                generated[index] += 1 + step.draft_tokens;
+                let stopped = step.finished
+                    || step.stop_sequence.is_some()
+                    || (!native_stop && find_stop_sequence(output, &parameters.stop, scan_from).is_some());
+                if stopped || generated[index] >= max_tokens {
+                    decoder.remove_sequence(&step.sequence_id);
+                    active.retain(|id| *id != step.sequence_id);
+                }
+            }
+        }
+        Ok(())
+    })();
+
+    for id in &active {
+        decoder.remove_sequence(id);
+    }
+    result.map(|()| outputs.into_iter().map(OutputData::Text).collect())
+}
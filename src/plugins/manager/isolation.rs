@@ -0,0 +1,215 @@
+//! 进程外插件的资源隔离
+//!
+//! Linux上为每个插件创建cgroup v2子组并写入 `memory.max`、`cpu.max`，插件进程在exec前加入该组。
+//! 超出内存上限时内核结束组内全部进程，监督任务按常规流程重启插件；CPU上限以限流方式生效。
+//! cgroup不可用（未挂载cgroup v2、目录未委派或无写权限）或非Linux平台时，
+//! 退回到在exec前用 `setrlimit` 限制地址空间，此时CPU上限不生效。
+
+use std::ffi::CString;
+use std::io;
+use std::os::unix::ffi::OsStrExt;
+use std::path::PathBuf;
+
+use tokio::process::Command;
+use tracing::{info, warn};
+
+use crate::infrastructure::configuration::ProcessLimits;
+
+/// `cpu.max` 的统计周期（微秒）
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// 一个插件的资源限制
+#[derive(Debug)]
+pub struct ProcessLimiter {
+    name: String,
+    limits: ProcessLimits,
+    /// 插件的cgroup目录，未使用cgroup时为None
+    cgroup: Option<PathBuf>,
+}
+
+impl ProcessLimiter {
+    /// 按配置为插件创建cgroup；没有设置上限时不做任何限制
+    pub fn new(name: &str, limits: &ProcessLimits, cgroup_root: Option<&str>) -> Self {
+        let cgroup = if limits.is_limited() {
+            match create_cgroup(name, limits, cgroup_root) {
+                Ok(path) => {
+                    info!("Plugin '{}' is limited by cgroup {}", name, path.display());
+                    Some(path)
+                }
+                Err(e) => {
+                    warn!(
+                        "Cannot create a cgroup for plugin '{}' ({}), only its address space will be limited",
+                        name, e
+                    );
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        Self {
+            name: name.to_string(),
+            limits: limits.clone(),
+            cgroup,
+        }
+    }
+
+    /// 让命令启动的进程在exec前加入cgroup，或在cgroup不可用时设置地址空间上限
+    pub fn prepare(&self, command: &mut Command) -> io::Result<()> {
+        if let Some(cgroup) = &self.cgroup {
+            let procs = CString::new(cgroup.join("cgroup.procs").as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            // SAFETY: 闭包在fork后的子进程中运行，只调用async-signal-safe的open/write/close
+            unsafe {
+                command.pre_exec(move || join_cgroup(&procs));
+            }
+        } else if let Some(memory_mb) = self.limits.memory_mb {
+            let bytes = memory_mb.saturating_mul(1024 * 1024);
+            // SAFETY: 同上，setrlimit是async-signal-safe的
+            unsafe {
+                command.pre_exec(move || limit_address_space(bytes));
+            }
+        }
+        Ok(())
+    }
+
+    /// 组内因超出内存上限被结束的次数；未使用cgroup时为0
+    pub fn oom_kills(&self) -> u64 {
+        let events = match &self.cgroup {
+            Some(cgroup) => std::fs::read_to_string(cgroup.join("memory.events")).unwrap_or_default(),
+            None => return 0,
+        };
+        events.lines()
+            .find_map(|line| line.strip_prefix("oom_kill "))
+            .and_then(|count| count.trim().parse().ok())
+            .unwrap_or(0)
+    }
+
+    /// 内存上限（MB）
+    pub fn memory_limit_mb(&self) -> Option<u64> {
+        self.limits.memory_mb
+    }
+
+    /// cgroup内所有进程当前使用的内存；未使用cgroup时为None
+    pub fn memory_usage_bytes(&self) -> Option<u64> {
+        let cgroup = self.cgroup.as_ref()?;
+        std::fs::read_to_string(cgroup.join("memory.current")).ok()?.trim().parse().ok()
+    }
+}
+
+/// 进程的常驻内存；只在Linux上可用
+pub fn process_rss_bytes(pid: u32) -> Option<u64> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid)).ok()?;
+    let kilobytes: u64 = status.lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))?
+        .trim()
+        .trim_end_matches("kB")
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kilobytes * 1024)
+}
+
+impl Drop for ProcessLimiter {
+    fn drop(&mut self) {
+        // 组内仍有进程时删除失败，由系统在进程结束后回收
+        if let Some(cgroup) = &self.cgroup {
+            if let Err(e) = std::fs::remove_dir(cgroup) {
+                warn!("Cannot remove cgroup {} of plugin '{}': {}", cgroup.display(), self.name, e);
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn create_cgroup(name: &str, limits: &ProcessLimits, cgroup_root: Option<&str>) -> io::Result<PathBuf> {
+    let parent = match cgroup_root {
+        Some(root) => PathBuf::from(root),
+        None => own_cgroup()?,
+    };
+    let available = std::fs::read_to_string(parent.join("cgroup.controllers"))?;
+    let mut controllers = Vec::new();
+    if limits.memory_mb.is_some() {
+        controllers.push("memory");
+    }
+    if limits.cpu_cores.is_some() {
+        controllers.push("cpu");
+    }
+    if let Some(missing) = controllers.iter().find(|c| !available.split_whitespace().any(|a| a == **c)) {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("controller '{}' is not available in {}", missing, parent.display()),
+        ));
+    }
+    let enable: Vec<String> = controllers.iter().map(|c| format!("+{}", c)).collect();
+    std::fs::write(parent.join("cgroup.subtree_control"), enable.join(" "))?;
+
+    let safe_name: String = name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect();
+    let path = parent.join(format!("unimodel-{}-{}", std::process::id(), safe_name));
+    match std::fs::create_dir(&path) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {}
+        Err(e) => return Err(e),
+    }
+    if let Some(memory_mb) = limits.memory_mb {
+        std::fs::write(path.join("memory.max"), memory_mb.saturating_mul(1024 * 1024).to_string())?;
+        // 超限时结束整个插件进程树，而不是其中某个子进程；交换区不计入上限，一并禁用
+        std::fs::write(path.join("memory.oom.group"), "1")?;
+        let _ = std::fs::write(path.join("memory.swap.max"), "0");
+    }
+    if let Some(cores) = limits.cpu_cores {
+        let quota = ((cores * CPU_PERIOD_US as f64).round() as u64).max(1000);
+        std::fs::write(path.join("cpu.max"), format!("{} {}", quota, CPU_PERIOD_US))?;
+    }
+    Ok(path)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn create_cgroup(_name: &str, _limits: &ProcessLimits, _cgroup_root: Option<&str>) -> io::Result<PathBuf> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "cgroups are only available on Linux"))
+}
+
+/// 服务器进程所在的cgroup v2目录
+#[cfg(target_os = "linux")]
+fn own_cgroup() -> io::Result<PathBuf> {
+    let membership = std::fs::read_to_string("/proc/self/cgroup")?;
+    let relative = membership.lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "cgroup v2 is not mounted"))?;
+    Ok(PathBuf::from("/sys/fs/cgroup").join(relative.trim_start_matches('/')))
+}
+
+/// 把当前进程写入cgroup，只在fork后的子进程中调用
+fn join_cgroup(procs: &CString) -> io::Result<()> {
+    // 写入"0"表示写入者自身
+    unsafe {
+        let fd = libc::open(procs.as_ptr(), libc::O_WRONLY | libc::O_CLOEXEC);
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let written = libc::write(fd, b"0".as_ptr().cast(), 1);
+        let error = io::Error::last_os_error();
+        libc::close(fd);
+        if written != 1 {
+            return Err(error);
+        }
+    }
+    Ok(())
+}
+
+fn limit_address_space(bytes: u64) -> io::Result<()> {
+    let limit = libc::rlimit {
+        rlim_cur: bytes as libc::rlim_t,
+        rlim_max: bytes as libc::rlim_t,
+    };
+    if unsafe { libc::setrlimit(libc::RLIMIT_AS, &limit) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
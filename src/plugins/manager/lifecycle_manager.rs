@@ -0,0 +1,712 @@
+//! 插件管理器
+//!
+//! 启动时从 `plugin_dir` 加载C ABI动态库插件并启动进程外插件，之后按模型配置的 `backend`
+//! 把加载、推理、卸载和适配器操作路由到提供该后端的插件；同一后端由动态库插件优先提供。
+//! 内置后端排在插件之后，内置的 `mock` 后端总是可用，不需要GPU和模型文件。
+//! 插件声明的后端能力用于在注册时校验模型配置，以及在未指定后端时自动选择。
+//! 启用混合精度的模型按后端声明的低精度加载，插件报告实际使用的精度。
+//! 每个插件的模型数、请求数和错误数按插件ID记录，并以 `plugin` 标签写入指标。
+//! 启用 `chaos` 时在加载和推理前按配置注入故障。
+//! 动态库插件的调用在独立的推理线程池中执行，不占用异步运行时的线程。
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use serde::Serialize;
+use tracing::{info, warn};
+
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{DeviceType, LoraAdapter, ModelConfig, ModelInstance, Precision};
+use crate::domain::service::chaos::{ChaosInjector, ChaosSite, Fault};
+use crate::domain::service::inference_pool::InferencePool;
+use crate::domain::service::parallelism::ParallelPlan;
+use crate::domain::service::conversion::ModelFormat;
+use crate::infrastructure::configuration::Config;
+use crate::infrastructure::memory::numa::NumaTopology;
+use crate::plugins::builtin::mock_plugin::{self, MockBackend};
+use crate::plugins::ffi::NativePlugin;
+use crate::plugins::interface::{BackendCapabilities, IterativeDecoder, ModelWeights, Quantizer};
+use crate::plugins::manager::builtin_backends::BuiltinBackends;
+use crate::plugins::manager::plugin_host::start_isolated_plugins;
+use crate::plugins::manager::plugin_loader::load_native_plugins;
+use crate::plugins::manager::remote_plugin::{parse_precision, RemotePlugin, RemotePlugins};
+use crate::plugins::manager::stream_pool::{StreamLease, StreamPool};
+
+/// 模型配置中表示自动选择后端的取值
+pub const AUTO_BACKEND: &str = "auto";
+
+/// 模型实例所在的插件
+#[derive(Debug, Clone)]
+enum PluginRoute {
+    Native(Arc<NativePlugin>),
+    Remote(Arc<RemotePlugin>),
+    Builtin,
+    Mock,
+}
+
+/// 插件类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluginKind {
+    /// 从 `plugin_dir` 加载的动态库
+    Native,
+    /// 独立进程
+    External,
+    /// 编译在服务器中
+    Builtin,
+}
+
+/// 插件概况
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub id: PluginId,
+    pub kind: PluginKind,
+    pub version: String,
+    pub backends: Vec<String>,
+    /// 可以接受调用；进程外插件重启期间为false
+    pub ready: bool,
+    /// 当前加载的模型实例数
+    pub models: usize,
+    pub requests: u64,
+    pub errors: u64,
+    /// 出错请求占比，没有请求时为0
+    pub error_rate: f64,
+    /// 插件进程使用的内存；动态库插件与服务器共享进程，无法单独统计
+    pub memory_bytes: Option<u64>,
+}
+
+/// 单个插件的调用统计
+#[derive(Debug, Default)]
+struct PluginStats {
+    models: AtomicUsize,
+    requests: AtomicU64,
+    errors: AtomicU64,
+}
+
+/// 插件管理器
+#[derive(Debug)]
+pub struct PluginManager {
+    /// 按插件名称索引的动态库插件
+    native: HashMap<PluginId, Arc<NativePlugin>>,
+    /// 按后端名称索引的动态库插件
+    native_backends: HashMap<String, Arc<NativePlugin>>,
+    remote: RemotePlugins,
+    builtin: BuiltinBackends,
+    mock: MockBackend,
+    /// 进程外插件按模型ID寻址，这里为其分配实例句柄
+    remote_models: DashMap<u64, ModelId>,
+    next_remote_handle: AtomicU64,
+    /// 按插件ID记录的调用统计
+    stats: DashMap<PluginId, Arc<PluginStats>>,
+    chaos: Option<Arc<ChaosInjector>>,
+    /// 执行动态库插件调用的线程池
+    inference_pool: Arc<InferencePool>,
+    /// 按（插件ID, 实例句柄）索引的CUDA模型实例的流
+    streams: DashMap<(PluginId, u64), Arc<StreamPool>>,
+}
+
+impl PluginManager {
+    /// 加载插件目录中的动态库并启动进程外插件
+    ///
+    /// 启用 `isolate_native_plugins` 时动态库在各自的隔离进程中加载，按进程外插件管理。
+    pub async fn new(config: &Config) -> Result<Self> {
+        let mut remote = RemotePlugins::start(&config.plugins).await;
+        let loaded = if config.plugins.isolate_native_plugins {
+            for plugin in start_isolated_plugins(&config.plugins).await {
+                remote.add(plugin);
+            }
+            Vec::new()
+        } else {
+            let dir = PathBuf::from(&config.plugins.plugin_dir);
+            let enabled = config.plugins.enabled_plugins.clone();
+            tokio::task::spawn_blocking(move || load_native_plugins(&dir, &enabled))
+                .await
+                .map_err(|e| UniModelError::internal(format!("Plugin loading task failed: {}", e)))?
+        };
+
+        let mut native = HashMap::new();
+        let mut native_backends = HashMap::new();
+        for plugin in loaded {
+            let plugin = Arc::new(plugin);
+            if native.contains_key(plugin.name()) {
+                warn!("Plugin '{}' from {} is already loaded, skipping", plugin.name(), plugin.path().display());
+                continue;
+            }
+            for backend in plugin.backends() {
+                native_backends.entry(backend.clone()).or_insert_with(|| Arc::clone(&plugin));
+            }
+            native.insert(plugin.name().to_string(), plugin);
+        }
+        info!(
+            "Plugin manager ready: {} in-process plugin(s), {} out-of-process plugin(s)",
+            native.len(),
+            remote.plugins().len()
+        );
+
+        let builtin = BuiltinBackends::new(&config.plugins.plugin_configs)?;
+        let mock = MockBackend::from_plugin_config(config.plugins.plugin_configs.get(mock_plugin::BACKEND_NAME))?;
+        let placement = NumaTopology::global().placement(&config.engine.inference_pool.affinity, &config.engine.gpu);
+
+        Ok(Self {
+            native,
+            native_backends,
+            remote,
+            builtin,
+            mock,
+            remote_models: DashMap::new(),
+            next_remote_handle: AtomicU64::new(1),
+            stats: DashMap::new(),
+            chaos: ChaosInjector::new(&config.chaos),
+            inference_pool: InferencePool::new(&config.engine.inference_pool, placement)?,
+            streams: DashMap::new(),
+        })
+    }
+
+    /// 所有插件提供的后端
+    pub fn backends(&self) -> Vec<String> {
+        self.all_capabilities().into_iter().map(|c| c.backend).collect()
+    }
+
+    /// 所有后端的能力声明，按后端名称排序；同名后端取实际路由到的插件
+    pub fn all_capabilities(&self) -> Vec<BackendCapabilities> {
+        let mut capabilities: Vec<BackendCapabilities> = self.native_backends
+            .iter()
+            .filter_map(|(backend, plugin)| plugin.capabilities().iter().find(|c| &c.backend == backend).cloned())
+            .collect();
+        for plugin in self.remote.plugins() {
+            for declared in plugin.capabilities() {
+                let routed = self.remote.for_backend(&declared.backend)
+                    .is_some_and(|p| p.name() == plugin.name());
+                if routed && !self.native_backends.contains_key(&declared.backend) {
+                    capabilities.push(declared);
+                }
+            }
+        }
+        for declared in self.builtin.capabilities() {
+            if !self.provided_by_plugin(&declared.backend) {
+                capabilities.push(declared);
+            }
+        }
+        if !self.provided_by_plugin(mock_plugin::BACKEND_NAME) {
+            capabilities.push(BackendCapabilities::undeclared(mock_plugin::BACKEND_NAME));
+        }
+        capabilities.sort_by(|a, b| a.backend.cmp(&b.backend));
+        capabilities
+    }
+
+    /// 后端的能力声明，没有插件提供该后端时返回None
+    pub fn capabilities(&self, backend: &str) -> Option<BackendCapabilities> {
+        self.all_capabilities().into_iter().find(|c| c.backend == backend)
+    }
+
+    /// 为模型配置选择后端
+    ///
+    /// 模型格式按文件内容识别（无法识别时按扩展名）。候选为明确声明能加载该格式、
+    /// 且设备、量化和批大小都满足的后端；格式的默认后端在候选中时优先，否则按名称取第一个。
+    /// 没有候选时错误中列出声明了该格式的后端及各自不满足的原因。
+    pub fn select_backend(&self, config: &ModelConfig) -> Result<String> {
+        let format = ModelFormat::inspect(Path::new(&config.model_path)).ok_or_else(|| {
+            UniModelError::validation(format!(
+                "Cannot recognize the model format of '{}', set backend explicitly",
+                config.model_path
+            ))
+        })?;
+
+        let mut candidates = Vec::new();
+        let mut rejected = Vec::new();
+        for capabilities in self.all_capabilities() {
+            if !capabilities.declares_format(format) {
+                continue;
+            }
+            match capabilities.check(config) {
+                Ok(()) => candidates.push(capabilities.backend),
+                Err(e) => rejected.push(format!("{} ({})", capabilities.backend, e)),
+            }
+        }
+        let preferred = format.default_backend();
+        if let Some(backend) = candidates.iter().find(|b| b.as_str() == preferred).or_else(|| candidates.first()) {
+            return Ok(backend.clone());
+        }
+
+        let reason = if rejected.is_empty() {
+            format!("no enabled backend declares {} support", format.name())
+        } else {
+            format!("backends declaring {} support cannot serve it: {}", format.name(), rejected.join("; "))
+        };
+        Err(UniModelError::validation(format!(
+            "No backend for '{}' ({} model on {:?}): {}",
+            config.model_path,
+            format.name(),
+            config.device.device_type,
+            reason
+        )))
+    }
+
+    /// 后端的离线量化能力；插件协议暂不提供量化，只有内置后端可能返回量化器
+    pub fn quantizer(&self, backend: &str) -> Option<Arc<dyn Quantizer>> {
+        if self.provided_by_plugin(backend) {
+            return None;
+        }
+        self.builtin.quantizer(backend)
+    }
+
+    /// 由提供 `config.backend` 的插件加载模型
+    ///
+    /// `weights` 用于生成多GPU并行的切分方案，并传给内置后端加载（目前内置后端只支持GGUF，
+    /// 按其中的文件路径自行读取）。插件按 `model_path` 自行读取权重，不共享已打开的检查点，
+    /// 同一检查点被多个模型加载时内存占用随模型数增长。
+    pub async fn load_model(
+        &self,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        weights: &ModelWeights,
+    ) -> Result<ModelInstance> {
+        // 多GPU并行时按权重生成切分方案，插件据此把各分片或阶段放到对应的GPU上
+        let parallel = ParallelPlan::for_model(config, weights)?;
+        match &parallel {
+            Some(ParallelPlan::Tensor(plan)) => info!(
+                "Model {} is split across GPUs {:?} ({} tensor(s) planned)",
+                model_id, plan.device_ids, plan.tensors.len()
+            ),
+            Some(ParallelPlan::Pipeline(plan)) => info!(
+                "Model {} runs as a {}-stage pipeline over GPUs {:?} with {} micro-batch(es)",
+                model_id,
+                plan.stages.len(),
+                config.device.device_ids,
+                plan.micro_batches
+            ),
+            None => {}
+        }
+        let capabilities = self.capabilities(&config.backend)
+            .unwrap_or_else(|| BackendCapabilities::undeclared(&config.backend));
+        let instance = self.load_instance(model_id, config, weights, parallel, &capabilities).await?;
+        if config.device.mixed_precision {
+            match instance.precision {
+                Precision::FP32 => warn!(
+                    "Backend '{}' cannot autocast on {:?}, model {} runs at fp32",
+                    config.backend, config.device.device_type, model_id
+                ),
+                precision => info!("Model {} runs with {} mixed precision", model_id, precision.name()),
+            }
+        }
+        if matches!(config.device.device_type, DeviceType::CUDA | DeviceType::ROCm) {
+            // 流水线的各阶段同时处理不同的微批次，每个在途的微批次占一条流
+            let streams = StreamPool::new(config.optimization.cuda_streams.max(config.micro_batches() as u32));
+            info!("Model {} runs on {} {:?} stream(s)", model_id, streams.streams(), config.device.device_type);
+            self.streams.insert((instance.plugin_id.clone(), instance.handle), streams);
+        }
+        Ok(instance)
+    }
+
+    async fn load_instance(
+        &self,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        weights: &ModelWeights,
+        parallel: Option<ParallelPlan>,
+        capabilities: &BackendCapabilities,
+    ) -> Result<ModelInstance> {
+        let precision = capabilities.precision(config);
+        let crash_target = if self.native_backends.contains_key(&config.backend) {
+            None
+        } else {
+            self.remote.for_backend(&config.backend)
+        };
+        self.inject_fault(crash_target.as_deref()).await?;
+
+        if let Some(plugin) = self.native_backends.get(&config.backend).cloned() {
+            let (id, model_config, loader) = (model_id.clone(), config.clone(), Arc::clone(&plugin));
+            let loaded = self.inference_pool
+                .run(move || loader.load_model(&id, &model_config, parallel.as_ref(), precision))
+                .await?;
+            self.record_models(plugin.name(), true);
+            return Ok(ModelInstance {
+                id: uuid::Uuid::new_v4().to_string(),
+                plugin_id: plugin.name().to_string(),
+                handle: loaded.handle,
+                supports_batching: loaded.supports_batching,
+                max_batch_size: loaded.max_batch_size,
+                supports_multi_lora: loaded.supports_multi_lora,
+                precision: effective_precision(capabilities, precision, loaded.precision),
+            });
+        }
+
+        if let Some(plugin) = self.remote.for_backend(&config.backend) {
+            let loaded = plugin.load_model(model_id, config, parallel.as_ref(), precision).await?;
+            let reported = parse_precision(plugin.name(), &loaded.precision);
+            let handle = self.next_remote_handle.fetch_add(1, Ordering::Relaxed);
+            self.remote_models.insert(handle, model_id.clone());
+            self.record_models(plugin.name(), true);
+            return Ok(ModelInstance {
+                id: uuid::Uuid::new_v4().to_string(),
+                plugin_id: plugin.name().to_string(),
+                handle,
+                supports_batching: loaded.supports_batching,
+                max_batch_size: loaded.max_batch_size.max(1),
+                supports_multi_lora: false,
+                precision: effective_precision(capabilities, precision, reported),
+            });
+        }
+
+        // 启动时未能启用的内置后端在这里返回原因
+        if self.builtin.provides(&config.backend) || self.builtin.unavailable_reason(&config.backend).is_some() {
+            let handle = self.builtin.load(model_id, config, weights, &self.inference_pool).await?;
+            self.record_models(&config.backend, true);
+            return Ok(ModelInstance {
+                id: uuid::Uuid::new_v4().to_string(),
+                plugin_id: config.backend.clone(),
+                handle,
+                supports_batching: true,
+                max_batch_size: config.batch_config.max_batch_size.max(1),
+                supports_multi_lora: false,
+                precision: effective_precision(capabilities, precision, None),
+            });
+        }
+        if config.backend == mock_plugin::BACKEND_NAME {
+            let handle = self.mock.load_model(config)?;
+            self.record_models(mock_plugin::BACKEND_NAME, true);
+            return Ok(ModelInstance {
+                id: uuid::Uuid::new_v4().to_string(),
+                plugin_id: mock_plugin::BACKEND_NAME.to_string(),
+                handle,
+                supports_batching: true,
+                max_batch_size: config.batch_config.max_batch_size.max(1),
+                supports_multi_lora: true,
+                precision,
+            });
+        }
+
+        Err(UniModelError::plugin_unavailable(format!(
+            "No plugin provides backend '{}'",
+            config.backend
+        )))
+    }
+
+    /// 卸载模型实例
+    pub async fn unload_model(&self, plugin_id: &PluginId, handle: &u64) -> Result<()> {
+        let unloaded = match self.route(plugin_id)? {
+            PluginRoute::Native(plugin) => {
+                let handle = *handle;
+                self.inference_pool.run(move || plugin.unload_model(handle)).await
+            }
+            PluginRoute::Remote(plugin) => {
+                let (_, model_id) = self.remote_models.remove(handle)
+                    .ok_or_else(|| UniModelError::model(format!("Unknown model handle {}", handle)))?;
+                plugin.unload_model(&model_id).await
+            }
+            PluginRoute::Builtin => self.builtin.unload(*handle).await,
+            PluginRoute::Mock => self.mock.unload_model(*handle),
+        };
+        if unloaded.is_ok() {
+            self.record_models(plugin_id, false);
+            self.streams.remove(&(plugin_id.clone(), *handle));
+        }
+        unloaded
+    }
+
+    /// 对一批输入执行推理，输出与输入一一对应
+    pub async fn infer(
+        &self,
+        instance: &ModelInstance,
+        inputs: &[InputData],
+        parameters: &PredictionParameters,
+    ) -> Result<Vec<OutputData>> {
+        self.infer_with_adapters(instance, inputs, parameters, &[]).await
+    }
+
+    /// 与 [`infer`](Self::infer) 相同，`adapters` 非空时与 `inputs` 等长，为每个输入选择LoRA适配器
+    ///
+    /// 只有 `supports_multi_lora` 的实例接受逐输入的适配器，其余实例整批使用 `parameters.adapter`。
+    pub async fn infer_with_adapters(
+        &self,
+        instance: &ModelInstance,
+        inputs: &[InputData],
+        parameters: &PredictionParameters,
+        adapters: &[Option<String>],
+    ) -> Result<Vec<OutputData>> {
+        if !adapters.is_empty() && !instance.supports_multi_lora {
+            return Err(UniModelError::validation(format!(
+                "Backend '{}' cannot mix LoRA adapters in one batch",
+                instance.plugin_id
+            )));
+        }
+        let route = self.route(&instance.plugin_id)?;
+        // 等待实例的一条空闲流，非CUDA实例始终使用0号流
+        let streams = self.streams
+            .get(&(instance.plugin_id.clone(), instance.handle))
+            .map(|entry| Arc::clone(entry.value()));
+        let lease = match &streams {
+            Some(streams) => Some(streams.acquire().await),
+            None => None,
+        };
+        let stream = lease.as_ref().map_or(0, StreamLease::stream);
+        let started = Instant::now();
+        let crash_target = match &route {
+            PluginRoute::Remote(plugin) => Some(plugin.as_ref()),
+            _ => None,
+        };
+        let result = match self.inject_fault(crash_target).await {
+            Err(e) => Err(e),
+            Ok(()) => match route {
+                PluginRoute::Native(plugin) => {
+                    let (handle, inputs, parameters) = (instance.handle, inputs.to_vec(), parameters.clone());
+                    let adapters = adapters.to_vec();
+                    self.inference_pool.run(move || plugin.infer(handle, &inputs, &parameters, &adapters, stream)).await
+                }
+                PluginRoute::Remote(plugin) => {
+                    let model_id = self.remote_model(instance.handle)?;
+                    plugin.infer(&model_id, inputs.to_vec(), parameters, stream).await
+                }
+                PluginRoute::Builtin => {
+                    self.builtin.infer(instance.handle, inputs, parameters, &self.inference_pool).await
+                }
+                // 模拟后端回显输入，不区分适配器
+                PluginRoute::Mock => self.mock.infer(instance.handle, inputs).await,
+            },
+        };
+        self.record_request(&instance.plugin_id, started.elapsed(), result.is_ok());
+        result
+    }
+
+    /// 实例的逐token解码器，供连续批处理使用；只有内置的LLM后端和回显输入的模拟模型提供
+    pub fn decoder(&self, instance: &ModelInstance) -> Option<Arc<dyn IterativeDecoder>> {
+        match self.route(&instance.plugin_id).ok()? {
+            PluginRoute::Builtin => self.builtin.decoder(instance.handle),
+            PluginRoute::Mock => self.mock.decoder(instance.handle),
+            PluginRoute::Native(_) | PluginRoute::Remote(_) => None,
+        }
+    }
+
+    /// 在模型实例上加载LoRA适配器
+    pub async fn load_adapter(&self, instance: &ModelInstance, adapter: &LoraAdapter) -> Result<()> {
+        match self.route(&instance.plugin_id)? {
+            PluginRoute::Native(plugin) => {
+                let (handle, name, path) = (instance.handle, adapter.name.clone(), adapter.path.clone());
+                self.inference_pool.run(move || plugin.load_adapter(handle, &name, &path)).await
+            }
+            PluginRoute::Remote(plugin) => Err(UniModelError::validation(format!(
+                "Plugin '{}' does not support LoRA adapters",
+                plugin.name()
+            ))),
+            PluginRoute::Builtin => Err(UniModelError::validation(format!(
+                "Backend '{}' does not support LoRA adapters",
+                instance.plugin_id
+            ))),
+            // 模拟后端接受任何适配器，不读取文件
+            PluginRoute::Mock => Ok(()),
+        }
+    }
+
+    /// 卸载模型实例上的LoRA适配器
+    pub async fn unload_adapter(&self, instance: &ModelInstance, name: &str) -> Result<()> {
+        match self.route(&instance.plugin_id)? {
+            PluginRoute::Native(plugin) => {
+                let (handle, name) = (instance.handle, name.to_string());
+                self.inference_pool.run(move || plugin.unload_adapter(handle, &name)).await
+            }
+            PluginRoute::Remote(plugin) => Err(UniModelError::validation(format!(
+                "Plugin '{}' does not support LoRA adapters",
+                plugin.name()
+            ))),
+            PluginRoute::Builtin => Err(UniModelError::validation(format!(
+                "Backend '{}' does not support LoRA adapters",
+                instance.plugin_id
+            ))),
+            PluginRoute::Mock => Ok(()),
+        }
+    }
+
+    /// 所有插件的概况，按插件ID排序
+    pub fn plugins(&self) -> Vec<PluginInfo> {
+        let mut plugins: Vec<PluginInfo> = self.native.values()
+            .map(|plugin| {
+                self.plugin_info(
+                    plugin.name(),
+                    PluginKind::Native,
+                    plugin.version().to_string(),
+                    plugin.backends().to_vec(),
+                    true,
+                    None,
+                )
+            })
+            .collect();
+        for plugin in self.remote.plugins() {
+            plugins.push(self.plugin_info(
+                plugin.name(),
+                PluginKind::External,
+                plugin.version(),
+                plugin.backends(),
+                plugin.is_ready(),
+                plugin.memory_bytes(),
+            ));
+        }
+        for (backend, ready) in self.builtin.backends() {
+            plugins.push(self.plugin_info(
+                &backend,
+                PluginKind::Builtin,
+                crate::VERSION.to_string(),
+                vec![backend.clone()],
+                ready,
+                None,
+            ));
+        }
+        plugins.push(self.plugin_info(
+            mock_plugin::BACKEND_NAME,
+            PluginKind::Builtin,
+            crate::VERSION.to_string(),
+            vec![mock_plugin::BACKEND_NAME.to_string()],
+            true,
+            None,
+        ));
+        plugins.sort_by(|a, b| a.id.cmp(&b.id));
+        plugins
+    }
+
+    /// 将各插件的就绪状态、内存和推理线程池负载写入指标；请求数和模型数在调用时直接更新
+    pub fn publish_metrics(&self) {
+        self.inference_pool.publish_metrics();
+        for plugin in self.plugins() {
+            metrics::gauge!("unimodel_plugin_ready", if plugin.ready { 1.0 } else { 0.0 }, "plugin" => plugin.id.clone());
+            if let Some(memory_bytes) = plugin.memory_bytes {
+                metrics::gauge!("unimodel_plugin_memory_bytes", memory_bytes as f64, "plugin" => plugin.id);
+            }
+        }
+    }
+
+    /// 执行后端调用的线程池，批处理器的解码器调用共用该线程池
+    pub fn inference_pool(&self) -> Arc<InferencePool> {
+        Arc::clone(&self.inference_pool)
+    }
+
+    /// 启动定期上报指标的任务
+    pub fn start_metrics_task(self: &Arc<Self>, interval: Duration) {
+        let manager = Arc::clone(self);
+
+        tokio::spawn(async move {
+            info!("Plugin metrics task started (every {:?})", interval);
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                manager.publish_metrics();
+            }
+        });
+    }
+
+    /// 结束所有进程外插件
+    pub fn shutdown(&self) {
+        self.remote.shutdown();
+    }
+
+    fn plugin_info(
+        &self,
+        id: &str,
+        kind: PluginKind,
+        version: String,
+        backends: Vec<String>,
+        ready: bool,
+        memory_bytes: Option<u64>,
+    ) -> PluginInfo {
+        let stats = self.stats(id);
+        let requests = stats.requests.load(Ordering::Relaxed);
+        let errors = stats.errors.load(Ordering::Relaxed);
+        PluginInfo {
+            id: id.to_string(),
+            kind,
+            version,
+            backends,
+            ready,
+            models: stats.models.load(Ordering::Relaxed),
+            requests,
+            errors,
+            error_rate: if requests == 0 { 0.0 } else { errors as f64 / requests as f64 },
+            memory_bytes,
+        }
+    }
+
+    fn stats(&self, plugin_id: &str) -> Arc<PluginStats> {
+        if let Some(stats) = self.stats.get(plugin_id) {
+            return Arc::clone(&stats);
+        }
+        Arc::clone(&self.stats.entry(plugin_id.to_string()).or_default())
+    }
+
+    fn record_models(&self, plugin_id: &str, loaded: bool) {
+        let stats = self.stats(plugin_id);
+        if loaded {
+            stats.models.fetch_add(1, Ordering::Relaxed);
+        } else {
+            let _ = stats.models.fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+        }
+        let models = stats.models.load(Ordering::Relaxed);
+        metrics::gauge!("unimodel_plugin_models", models as f64, "plugin" => plugin_id.to_string());
+    }
+
+    fn record_request(&self, plugin_id: &str, elapsed: Duration, ok: bool) {
+        let stats = self.stats(plugin_id);
+        stats.requests.fetch_add(1, Ordering::Relaxed);
+        metrics::increment_counter!("unimodel_plugin_requests_total", "plugin" => plugin_id.to_string());
+        metrics::histogram!("unimodel_plugin_inference_seconds", elapsed.as_secs_f64(), "plugin" => plugin_id.to_string());
+        if !ok {
+            stats.errors.fetch_add(1, Ordering::Relaxed);
+            metrics::increment_counter!("unimodel_plugin_errors_total", "plugin" => plugin_id.to_string());
+        }
+    }
+
+    /// 按故障注入配置注入延迟和故障；模拟崩溃时结束进程外插件的进程，其他插件只返回插件不可用
+    async fn inject_fault(&self, crash_target: Option<&RemotePlugin>) -> Result<()> {
+        let fault = match &self.chaos {
+            Some(chaos) => chaos.inject(ChaosSite::Plugin).await,
+            None => return Ok(()),
+        };
+        match fault {
+            Some(fault) => {
+                if let (Fault::Crash, Some(plugin)) = (fault, crash_target) {
+                    plugin.kill();
+                }
+                Err(fault.error(ChaosSite::Plugin))
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn route(&self, plugin_id: &str) -> Result<PluginRoute> {
+        if let Some(plugin) = self.native.get(plugin_id) {
+            return Ok(PluginRoute::Native(Arc::clone(plugin)));
+        }
+        if let Some(plugin) = self.remote.plugins().iter().find(|plugin| plugin.name() == plugin_id) {
+            return Ok(PluginRoute::Remote(Arc::clone(plugin)));
+        }
+        if self.builtin.provides(plugin_id) {
+            return Ok(PluginRoute::Builtin);
+        }
+        if plugin_id == mock_plugin::BACKEND_NAME {
+            return Ok(PluginRoute::Mock);
+        }
+        Err(UniModelError::plugin_unavailable(format!("Plugin '{}' is not loaded", plugin_id)))
+    }
+
+    /// 后端是否由动态库插件或进程外插件提供，这时同名的内置后端不参与路由
+    fn provided_by_plugin(&self, backend: &str) -> bool {
+        self.native_backends.contains_key(backend) || self.remote.for_backend(backend).is_some()
+    }
+
+    fn remote_model(&self, handle: u64) -> Result<ModelId> {
+        self.remote_models.get(&handle)
+            .map(|model_id| model_id.clone())
+            .ok_or_else(|| UniModelError::model(format!("Unknown model handle {}", handle)))
+    }
+}
+
+/// 实例实际使用的计算精度：以插件的报告为准；未报告时，明确声明支持所请求精度的后端视为已启用，否则为FP32
+fn effective_precision(capabilities: &BackendCapabilities, requested: Precision, reported: Option<Precision>) -> Precision {
+    reported.unwrap_or(if capabilities.mixed_precision.contains(&requested) {
+        requested
+    } else {
+        Precision::FP32
+    })
+}
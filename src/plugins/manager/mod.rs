@@ -0,0 +1,12 @@
+//! 插件管理
+
+pub mod builtin_backends;
+pub mod isolation;
+pub mod lifecycle_manager;
+pub mod plugin_host;
+pub mod plugin_loader;
+pub mod remote_plugin;
+pub mod stream_pool;
+
+pub use lifecycle_manager::{PluginInfo, PluginKind, PluginManager};
+pub use remote_plugin::{RemotePlugin, RemotePlugins};
@@ -0,0 +1,239 @@
+//! 动态库插件的隔离进程
+//!
+//! 启用 `plugins.isolate_native_plugins` 后，`plugin_dir` 中的每个动态库不再加载到服务器进程，
+//! 而是由服务器以 `unimodel plugin-host <动态库>` 启动一个子进程加载，子进程通过
+//! `plugin.proto` 协议提供服务，服务器把它当作进程外插件对待：动态库崩溃只结束该子进程，
+//! 由监督任务重启并重新加载其中的模型。隔离模式下暂不支持LoRA适配器。
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use tokio::net::UnixListener;
+use tokio_stream::wrappers::UnixListenerStream;
+use tonic::transport::Server;
+use tonic::{Request, Response, Status};
+use tracing::{error, info, warn};
+
+use crate::api::grpc::proto::inference as inference_pb;
+use crate::api::grpc::proto::plugin as pb;
+use crate::api::grpc::proto::plugin::model_plugin_server::{ModelPlugin, ModelPluginServer};
+use crate::api::grpc::server::MAX_MESSAGE_BYTES;
+use crate::api::grpc::service::enum_name;
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{ModelConfig, Precision};
+use crate::domain::service::parallelism::ParallelPlan;
+use crate::infrastructure::configuration::{
+    default_plugin_max_restarts, default_plugin_startup_timeout_secs, ExternalPluginConfig, PluginConfig,
+};
+use crate::plugins::ffi::NativePlugin;
+use crate::plugins::manager::plugin_loader::plugin_libraries;
+use crate::plugins::manager::remote_plugin::{RemotePlugin, PROTOCOL_VERSION, SOCKET_ENV};
+
+/// 服务器以该子命令启动隔离进程
+pub const PLUGIN_HOST_COMMAND: &str = "plugin-host";
+
+/// 为插件目录中的每个动态库启动隔离进程
+///
+/// 插件ID为动态库的文件名（不含扩展名）；名称和后端都不在 `enabled_plugins` 中的插件在握手后结束。
+pub async fn start_isolated_plugins(config: &PluginConfig) -> Vec<Arc<RemotePlugin>> {
+    let executable = match std::env::current_exe() {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Cannot locate the server executable to isolate plugins: {}", e);
+            return Vec::new();
+        }
+    };
+    let call_timeout = std::time::Duration::from_secs(config.plugin_timeout_secs);
+
+    let mut plugins = Vec::new();
+    for library in plugin_libraries(Path::new(&config.plugin_dir)) {
+        let host = host_config(&executable, &library);
+        let name = host.name.clone();
+        let plugin = match RemotePlugin::start(host, call_timeout, config.cgroup_root.as_deref()).await {
+            Ok(plugin) => plugin,
+            Err(e) => {
+                error!("Rejected plugin {}: {}", library.display(), e);
+                continue;
+            }
+        };
+        let backends = plugin.backends();
+        let is_enabled = config.enabled_plugins.iter()
+            .any(|enabled| *enabled == name || backends.contains(enabled));
+        if is_enabled {
+            plugins.push(plugin);
+        } else {
+            warn!("Plugin '{}' at {} is not in enabled_plugins, skipping", name, library.display());
+            plugin.shutdown();
+        }
+    }
+    plugins
+}
+
+fn host_config(executable: &Path, library: &Path) -> ExternalPluginConfig {
+    let stem = library.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let name = stem.strip_prefix("lib").unwrap_or(&stem).to_string();
+    ExternalPluginConfig {
+        name,
+        command: executable.display().to_string(),
+        args: vec![PLUGIN_HOST_COMMAND.to_string(), library.display().to_string()],
+        env: Default::default(),
+        startup_timeout_secs: default_plugin_startup_timeout_secs(),
+        max_restarts: default_plugin_max_restarts(),
+        limits: Default::default(),
+    }
+}
+
+/// 隔离进程入口：加载动态库，在 `UNIMODEL_PLUGIN_SOCKET` 上提供插件协议直到被结束
+pub async fn run_plugin_host(library: &Path) -> Result<()> {
+    // 服务器进程意外退出时随之结束，不留下孤儿进程
+    #[cfg(target_os = "linux")]
+    unsafe {
+        libc::prctl(libc::PR_SET_PDEATHSIG, libc::SIGKILL);
+    }
+
+    let socket = std::env::var_os(SOCKET_ENV)
+        .map(PathBuf::from)
+        .ok_or_else(|| UniModelError::config(format!("{} is not set", SOCKET_ENV)))?;
+    let path = library.to_path_buf();
+    let plugin = tokio::task::spawn_blocking(move || NativePlugin::open(&path))
+        .await
+        .map_err(|e| UniModelError::internal(format!("Plugin loading task failed: {}", e)))??;
+    info!("Hosting plugin '{}' {} from {}", plugin.name(), plugin.version(), library.display());
+
+    let _ = std::fs::remove_file(&socket);
+    let listener = UnixListener::bind(&socket)?;
+    let service = ModelPluginServer::new(NativePluginHost { plugin: Arc::new(plugin) })
+        .max_decoding_message_size(MAX_MESSAGE_BYTES)
+        .max_encoding_message_size(MAX_MESSAGE_BYTES);
+    Server::builder()
+        .add_service(service)
+        .serve_with_incoming(UnixListenerStream::new(listener))
+        .await
+        .map_err(|e| UniModelError::Network(format!("Plugin host error: {}", e)))
+}
+
+/// 把插件协议转发给动态库；动态库调用可能阻塞，在阻塞线程池中执行
+#[derive(Debug)]
+struct NativePluginHost {
+    plugin: Arc<NativePlugin>,
+}
+
+impl NativePluginHost {
+    async fn blocking<T, F>(&self, call: F) -> std::result::Result<T, Status>
+    where
+        T: Send + 'static,
+        F: FnOnce(&NativePlugin) -> Result<T> + Send + 'static,
+    {
+        let plugin = Arc::clone(&self.plugin);
+        tokio::task::spawn_blocking(move || call(&plugin))
+            .await
+            .map_err(|e| Status::internal(format!("Plugin call failed: {}", e)))?
+            .map_err(Status::from)
+    }
+}
+
+#[tonic::async_trait]
+impl ModelPlugin for NativePluginHost {
+    async fn handshake(
+        &self,
+        _request: Request<pb::HandshakeRequest>,
+    ) -> std::result::Result<Response<pb::HandshakeResponse>, Status> {
+        let capabilities = self.plugin.capabilities()
+            .iter()
+            .map(|c| pb::BackendCapabilities {
+                backend: c.backend.clone(),
+                formats: c.formats.iter().map(enum_name).collect(),
+                devices: c.devices.iter().map(enum_name).collect(),
+                max_batch_size: c.max_batch_size,
+                streaming: c.streaming,
+                quantization: c.quantization.iter().map(enum_name).collect(),
+                tensor_parallel: c.tensor_parallel,
+                pipeline_parallel: c.pipeline_parallel,
+                mixed_precision: c.mixed_precision.iter().map(enum_name).collect(),
+            })
+            .collect();
+        Ok(Response::new(pb::HandshakeResponse {
+            protocol_version: PROTOCOL_VERSION,
+            name: self.plugin.name().to_string(),
+            version: self.plugin.version().to_string(),
+            backends: self.plugin.backends().to_vec(),
+            capabilities,
+        }))
+    }
+
+    async fn load_model(
+        &self,
+        request: Request<pb::LoadModelRequest>,
+    ) -> std::result::Result<Response<pb::LoadModelResponse>, Status> {
+        let request = request.into_inner();
+        let config: ModelConfig = serde_json::from_str(&request.config_json)
+            .map_err(|e| Status::invalid_argument(format!("Invalid model config: {}", e)))?;
+        let parallel: Option<ParallelPlan> = match request.parallel_plan_json.as_str() {
+            "" => None,
+            json => Some(serde_json::from_str(json)
+                .map_err(|e| Status::invalid_argument(format!("Invalid parallel plan: {}", e)))?),
+        };
+        let precision: Precision = match request.precision.as_str() {
+            "" => Precision::FP32,
+            name => serde_json::from_value(serde_json::Value::String(name.to_string()))
+                .map_err(|_| Status::invalid_argument(format!("Unknown precision '{}'", name)))?,
+        };
+        let model_id = request.model_id;
+        let loaded = self
+            .blocking(move |plugin| plugin.load_model(&model_id, &config, parallel.as_ref(), precision))
+            .await?;
+        Ok(Response::new(pb::LoadModelResponse {
+            handle: loaded.handle.to_string(),
+            supports_batching: loaded.supports_batching,
+            max_batch_size: loaded.max_batch_size,
+            precision: loaded.precision.as_ref().map(enum_name).unwrap_or_default(),
+        }))
+    }
+
+    async fn unload_model(
+        &self,
+        request: Request<pb::UnloadModelRequest>,
+    ) -> std::result::Result<Response<pb::UnloadModelResponse>, Status> {
+        let handle = parse_handle(&request.into_inner().handle)?;
+        self.blocking(move |plugin| plugin.unload_model(handle)).await?;
+        Ok(Response::new(pb::UnloadModelResponse {}))
+    }
+
+    async fn infer(
+        &self,
+        request: Request<pb::InferRequest>,
+    ) -> std::result::Result<Response<pb::InferResponse>, Status> {
+        let request = request.into_inner();
+        let handle = parse_handle(&request.handle)?;
+        let inputs = request.inputs
+            .into_iter()
+            .map(InputData::try_from)
+            .collect::<Result<Vec<_>>>()?;
+        let parameters = match request.parameters {
+            Some(parameters) => parameters.try_into()?,
+            None => PredictionParameters::default(),
+        };
+        let stream = request.stream;
+        // 远程插件不声明多LoRA，批次不会混用适配器
+        let outputs = self.blocking(move |plugin| plugin.infer(handle, &inputs, &parameters, &[], stream)).await?;
+        Ok(Response::new(pb::InferResponse {
+            outputs: outputs.into_iter().map(inference_pb::OutputData::from).collect(),
+        }))
+    }
+
+    async fn health(
+        &self,
+        _request: Request<pb::HealthRequest>,
+    ) -> std::result::Result<Response<pb::HealthResponse>, Status> {
+        Ok(Response::new(pb::HealthResponse {
+            healthy: true,
+            message: String::new(),
+        }))
+    }
+}
+
+#[allow(clippy::result_large_err)]
+fn parse_handle(handle: &str) -> std::result::Result<u64, Status> {
+    handle.parse().map_err(|_| Status::not_found(format!("Unknown model handle '{}'", handle)))
+}
@@ -0,0 +1,52 @@
+//! 从插件目录加载C ABI插件
+
+use std::path::{Path, PathBuf};
+
+use tracing::{error, warn};
+
+use crate::plugins::ffi::NativePlugin;
+
+/// 当前平台的动态库扩展名
+pub const LIBRARY_EXTENSION: &str = std::env::consts::DLL_EXTENSION;
+
+/// 加载目录中的动态库插件
+///
+/// 只保留名称或任一后端出现在 `enabled` 中的插件；ABI不兼容或加载失败的插件记录错误后跳过，
+/// 不影响其他插件和服务器启动。目录不存在时返回空列表。
+pub fn load_native_plugins(dir: &Path, enabled: &[String]) -> Vec<NativePlugin> {
+    let mut plugins = Vec::new();
+    for path in plugin_libraries(dir) {
+        match NativePlugin::open(&path) {
+            Ok(plugin) => {
+                let is_enabled = enabled.iter()
+                    .any(|name| name == plugin.name() || plugin.backends().contains(name));
+                if is_enabled {
+                    plugins.push(plugin);
+                } else {
+                    warn!("Plugin '{}' at {} is not in enabled_plugins, skipping", plugin.name(), path.display());
+                }
+            }
+            Err(e) => error!("Rejected plugin {}: {}", path.display(), e),
+        }
+    }
+    plugins
+}
+
+/// 目录中的动态库，按路径排序；目录不存在时返回空列表
+pub fn plugin_libraries(dir: &Path) -> Vec<PathBuf> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            if dir.exists() {
+                warn!("Cannot read plugin directory {}: {}", dir.display(), e);
+            }
+            return Vec::new();
+        }
+    };
+    let mut libraries: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == LIBRARY_EXTENSION))
+        .collect();
+    libraries.sort();
+    libraries
+}
@@ -0,0 +1,562 @@
+//! 进程外插件
+//!
+//! 后端作为独立进程运行，可用任何语言实现 `plugin.proto` 中的 `ModelPlugin` 服务。
+//! 服务器为每个插件启动进程，通过环境变量 `UNIMODEL_PLUGIN_SOCKET` 告知其监听的Unix套接字，
+//! 握手确认协议版本和插件提供的后端后，按模型的 `backend` 把加载、卸载和推理转发给对应插件。
+//! 插件进程退出后按退避间隔重启，并重新加载退出前已加载的模型；重启期间的调用返回插件不可用。
+//! 配置了资源上限的插件由 [`ProcessLimiter`] 限制，超出内存上限被结束后同样重启。
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use dashmap::DashMap;
+use parking_lot::RwLock;
+use serde::de::DeserializeOwned;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::net::UnixStream;
+use tokio::process::{Child, Command};
+use tokio::sync::Notify;
+use tonic::transport::{Channel, Endpoint, Uri};
+use tower::service_fn;
+use tracing::{error, info, warn};
+
+use crate::api::grpc::proto::inference as inference_pb;
+use crate::api::grpc::proto::plugin as pb;
+use crate::api::grpc::proto::plugin::model_plugin_client::ModelPluginClient;
+use crate::api::grpc::server::MAX_MESSAGE_BYTES;
+use crate::api::grpc::service::enum_name;
+use crate::common::error::*;
+use crate::common::types::*;
+use crate::domain::model::{ModelConfig, Precision};
+use crate::domain::service::parallelism::ParallelPlan;
+use crate::infrastructure::configuration::{ExternalPluginConfig, PluginConfig};
+use crate::plugins::interface::BackendCapabilities;
+use crate::plugins::manager::isolation::{process_rss_bytes, ProcessLimiter};
+
+/// 插件协议版本，协议出现不兼容修改时递增
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// 告知插件监听地址的环境变量
+pub const SOCKET_ENV: &str = "UNIMODEL_PLUGIN_SOCKET";
+
+/// 重启退避的上限
+const MAX_RESTART_BACKOFF: Duration = Duration::from_secs(30);
+
+/// 进程持续运行超过该时间后视为稳定，重置重启计数
+const STABLE_RUN: Duration = Duration::from_secs(60);
+
+/// 已加载到插件中的模型
+#[derive(Debug, Clone)]
+struct RemoteModel {
+    config: ModelConfig,
+    parallel: Option<ParallelPlan>,
+    precision: Precision,
+    /// 插件分配的句柄，插件重启后重新加载时会变化
+    handle: String,
+}
+
+/// 一个进程外插件
+#[derive(Debug)]
+pub struct RemotePlugin {
+    config: ExternalPluginConfig,
+    socket_path: PathBuf,
+    call_timeout: Duration,
+    limiter: ProcessLimiter,
+    /// 插件进程号，进程未运行时为0
+    pid: AtomicU32,
+    /// 进程未就绪（启动或重启中）时为None
+    client: RwLock<Option<ModelPluginClient<Channel>>>,
+    handshake: RwLock<pb::HandshakeResponse>,
+    models: DashMap<ModelId, RemoteModel>,
+    shutdown: Notify,
+}
+
+impl RemotePlugin {
+    /// 启动插件进程并完成握手，之后由后台任务监督进程
+    pub async fn start(
+        config: ExternalPluginConfig,
+        call_timeout: Duration,
+        cgroup_root: Option<&str>,
+    ) -> Result<Arc<Self>> {
+        let socket_path = std::env::temp_dir()
+            .join(format!("unimodel-{}-{}.sock", config.name, std::process::id()));
+        let limiter = ProcessLimiter::new(&config.name, &config.limits, cgroup_root);
+        let plugin = Arc::new(Self {
+            config,
+            socket_path,
+            call_timeout,
+            limiter,
+            pid: AtomicU32::new(0),
+            client: RwLock::new(None),
+            handshake: RwLock::new(pb::HandshakeResponse::default()),
+            models: DashMap::new(),
+            shutdown: Notify::new(),
+        });
+        let child = plugin.launch().await?;
+        tokio::spawn(Arc::clone(&plugin).supervise(child));
+        Ok(plugin)
+    }
+
+    /// 插件名称
+    pub fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// 插件握手时报告的版本
+    pub fn version(&self) -> String {
+        self.handshake.read().version.clone()
+    }
+
+    /// 插件进程使用的内存：有cgroup时为整个进程组，否则为主进程的常驻内存
+    pub fn memory_bytes(&self) -> Option<u64> {
+        self.limiter.memory_usage_bytes().or_else(|| match self.pid.load(Ordering::Relaxed) {
+            0 => None,
+            pid => process_rss_bytes(pid),
+        })
+    }
+
+    /// 插件提供的后端
+    pub fn backends(&self) -> Vec<String> {
+        self.handshake.read().backends.clone()
+    }
+
+    /// 各后端的能力声明，未声明的后端不受限制
+    pub fn capabilities(&self) -> Vec<BackendCapabilities> {
+        let handshake = self.handshake.read();
+        handshake.backends.iter()
+            .map(|backend| {
+                handshake.capabilities.iter()
+                    .find(|c| &c.backend == backend)
+                    .map(|c| BackendCapabilities {
+                        backend: c.backend.clone(),
+                        formats: parse_names(&self.config.name, &c.formats),
+                        devices: parse_names(&self.config.name, &c.devices),
+                        max_batch_size: c.max_batch_size,
+                        streaming: c.streaming,
+                        quantization: parse_names(&self.config.name, &c.quantization),
+                        tensor_parallel: c.tensor_parallel,
+                        pipeline_parallel: c.pipeline_parallel,
+                        mixed_precision: parse_names(&self.config.name, &c.mixed_precision),
+                    })
+                    .unwrap_or_else(|| BackendCapabilities::undeclared(backend))
+            })
+            .collect()
+    }
+
+    /// 插件进程是否就绪
+    pub fn is_ready(&self) -> bool {
+        self.client.read().is_some()
+    }
+
+    /// 在插件中加载模型
+    pub async fn load_model(
+        &self,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        parallel: Option<&ParallelPlan>,
+        precision: Precision,
+    ) -> Result<pb::LoadModelResponse> {
+        let response = self.load_remote(model_id, config, parallel, precision).await?;
+        self.models.insert(model_id.clone(), RemoteModel {
+            config: config.clone(),
+            parallel: parallel.cloned(),
+            precision,
+            handle: response.handle.clone(),
+        });
+        Ok(response)
+    }
+
+    /// 卸载模型
+    pub async fn unload_model(&self, model_id: &ModelId) -> Result<()> {
+        let (_, model) = self.models.remove(model_id).ok_or_else(|| {
+            UniModelError::model(format!("Model {} is not loaded in plugin '{}'", model_id, self.config.name))
+        })?;
+        self.client()?
+            .unload_model(pb::UnloadModelRequest { handle: model.handle })
+            .await
+            .map_err(|status| self.status_error(status))?;
+        info!("Unloaded model {} from plugin '{}'", model_id, self.config.name);
+        Ok(())
+    }
+
+    /// 对一批输入执行推理，输出与输入一一对应
+    pub async fn infer(
+        &self,
+        model_id: &ModelId,
+        inputs: Vec<InputData>,
+        parameters: &PredictionParameters,
+        stream: u32,
+    ) -> Result<Vec<OutputData>> {
+        let handle = self.models.get(model_id)
+            .map(|model| model.handle.clone())
+            .ok_or_else(|| UniModelError::model(format!(
+                "Model {} is not loaded in plugin '{}'",
+                model_id, self.config.name
+            )))?;
+        let expected = inputs.len();
+        let request = pb::InferRequest {
+            handle,
+            inputs: inputs.into_iter().map(inference_pb::InputData::from).collect(),
+            parameters: Some(parameters.into()),
+            stream,
+        };
+        let response = self.client()?
+            .infer(request)
+            .await
+            .map_err(|status| self.status_error(status))?
+            .into_inner();
+        if response.outputs.len() != expected {
+            return Err(UniModelError::plugin(format!(
+                "Plugin '{}' returned {} outputs for {} inputs",
+                self.config.name,
+                response.outputs.len(),
+                expected
+            )));
+        }
+        response.outputs.into_iter().map(OutputData::try_from).collect()
+    }
+
+    /// 健康检查
+    pub async fn health(&self) -> Result<bool> {
+        let response = self.client()?
+            .health(pb::HealthRequest {})
+            .await
+            .map_err(|status| self.status_error(status))?
+            .into_inner();
+        if !response.healthy {
+            warn!("Plugin '{}' reports unhealthy: {}", self.config.name, response.message);
+        }
+        Ok(response.healthy)
+    }
+
+    /// 立即结束插件进程以模拟崩溃，之后由监督任务按常规流程重启
+    pub fn kill(&self) {
+        let pid = self.pid.load(Ordering::Relaxed);
+        if pid != 0 {
+            warn!("Killing plugin '{}' (pid {})", self.config.name, pid);
+            // SAFETY: kill只向进程发送信号，不访问内存
+            unsafe {
+                libc::kill(pid as libc::pid_t, libc::SIGKILL);
+            }
+        }
+    }
+
+    /// 停止监督并结束插件进程
+    pub fn shutdown(&self) {
+        self.client.write().take();
+        self.shutdown.notify_one();
+    }
+
+    fn client(&self) -> Result<ModelPluginClient<Channel>> {
+        self.client.read().clone().ok_or_else(|| {
+            UniModelError::plugin_unavailable(format!("Plugin '{}' is not running", self.config.name))
+        })
+    }
+
+    async fn load_remote(
+        &self,
+        model_id: &ModelId,
+        config: &ModelConfig,
+        parallel: Option<&ParallelPlan>,
+        precision: Precision,
+    ) -> Result<pb::LoadModelResponse> {
+        let request = pb::LoadModelRequest {
+            model_id: model_id.clone(),
+            model_path: config.model_path.clone(),
+            config_json: serde_json::to_string(config)?,
+            parallel_plan_json: match parallel {
+                Some(plan) => serde_json::to_string(plan)?,
+                None => String::new(),
+            },
+            precision: enum_name(&precision),
+        };
+        let response = self.client()?
+            .load_model(request)
+            .await
+            .map_err(|status| self.status_error(status))?
+            .into_inner();
+        info!("Loaded model {} in plugin '{}' as '{}'", model_id, self.config.name, response.handle);
+        Ok(response)
+    }
+
+    /// 启动进程、等待套接字就绪并握手
+    async fn launch(&self) -> Result<Child> {
+        let _ = std::fs::remove_file(&self.socket_path);
+        let mut command = Command::new(&self.config.command);
+        command
+            .args(&self.config.args)
+            .envs(&self.config.env)
+            .env(SOCKET_ENV, &self.socket_path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        self.limiter.prepare(&mut command).map_err(|e| {
+            UniModelError::plugin_unavailable(format!("Cannot limit plugin '{}': {}", self.config.name, e))
+        })?;
+        let mut child = command
+            .spawn()
+            .map_err(|e| UniModelError::plugin_unavailable(format!(
+                "Cannot start plugin '{}' ({}): {}",
+                self.config.name, self.config.command, e
+            )))?;
+        self.pid.store(child.id().unwrap_or(0), Ordering::Relaxed);
+        if let Some(stdout) = child.stdout.take() {
+            tokio::spawn(forward_output(self.config.name.clone(), stdout));
+        }
+        if let Some(stderr) = child.stderr.take() {
+            tokio::spawn(forward_output(self.config.name.clone(), stderr));
+        }
+
+        match self.connect(&mut child).await {
+            Ok((client, handshake)) => {
+                info!(
+                    "Plugin '{}' {} (pid {:?}) ready, backends: {}",
+                    handshake.name,
+                    handshake.version,
+                    child.id(),
+                    handshake.backends.join(", ")
+                );
+                *self.handshake.write() = handshake;
+                *self.client.write() = Some(client);
+                Ok(child)
+            }
+            Err(e) => {
+                let _ = child.kill().await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn connect(&self, child: &mut Child) -> Result<(ModelPluginClient<Channel>, pb::HandshakeResponse)> {
+        let deadline = Instant::now() + Duration::from_secs(self.config.startup_timeout_secs);
+        let channel = loop {
+            if let Ok(Some(status)) = child.try_wait() {
+                return Err(UniModelError::plugin_unavailable(format!(
+                    "Plugin '{}' exited during startup ({})",
+                    self.config.name, status
+                )));
+            }
+            if self.socket_path.exists() {
+                let path = self.socket_path.clone();
+                // Unix套接字连接不使用URI，这里只是占位
+                let connected = Endpoint::from_static("http://plugin.local")
+                    .timeout(self.call_timeout)
+                    .connect_with_connector(service_fn(move |_: Uri| UnixStream::connect(path.clone())))
+                    .await;
+                if let Ok(channel) = connected {
+                    break channel;
+                }
+            }
+            if Instant::now() >= deadline {
+                return Err(UniModelError::plugin_unavailable(format!(
+                    "Plugin '{}' did not listen on {} within {}s",
+                    self.config.name,
+                    self.socket_path.display(),
+                    self.config.startup_timeout_secs
+                )));
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        };
+
+        let mut client = ModelPluginClient::new(channel)
+            .max_decoding_message_size(MAX_MESSAGE_BYTES)
+            .max_encoding_message_size(MAX_MESSAGE_BYTES);
+        let handshake = client
+            .handshake(pb::HandshakeRequest { protocol_version: PROTOCOL_VERSION })
+            .await
+            .map_err(|status| self.status_error(status))?
+            .into_inner();
+        if handshake.protocol_version != PROTOCOL_VERSION {
+            return Err(UniModelError::plugin(format!(
+                "Plugin '{}' speaks protocol version {}, the server needs {}",
+                self.config.name, handshake.protocol_version, PROTOCOL_VERSION
+            )));
+        }
+        if handshake.backends.is_empty() {
+            return Err(UniModelError::plugin(format!("Plugin '{}' provides no backends", self.config.name)));
+        }
+        Ok((client, handshake))
+    }
+
+    /// 等待进程退出并重启，直到关闭或连续重启失败次数超过上限
+    async fn supervise(self: Arc<Self>, mut child: Child) {
+        let mut started = Instant::now();
+        let mut failures = 0u32;
+        loop {
+            let oom_kills = self.limiter.oom_kills();
+            let status = tokio::select! {
+                status = child.wait() => status,
+                _ = self.shutdown.notified() => {
+                    let _ = child.kill().await;
+                    let _ = std::fs::remove_file(&self.socket_path);
+                    info!("Plugin '{}' stopped", self.config.name);
+                    return;
+                }
+            };
+            self.client.write().take();
+            self.pid.store(0, Ordering::Relaxed);
+            match status {
+                Ok(status) => warn!("Plugin '{}' exited ({})", self.config.name, status),
+                Err(e) => warn!("Plugin '{}' could not be waited on: {}", self.config.name, e),
+            }
+            if self.limiter.oom_kills() > oom_kills {
+                warn!(
+                    "Plugin '{}' exceeded its memory limit of {} MB and was killed",
+                    self.config.name,
+                    self.limiter.memory_limit_mb().unwrap_or_default()
+                );
+            }
+            if started.elapsed() >= STABLE_RUN {
+                failures = 0;
+            }
+
+            child = loop {
+                failures += 1;
+                if failures > self.config.max_restarts {
+                    error!(
+                        "Plugin '{}' failed {} times in a row, giving up; its {} models are unavailable",
+                        self.config.name,
+                        failures - 1,
+                        self.models.len()
+                    );
+                    return;
+                }
+                let backoff = Duration::from_secs(1 << (failures - 1).min(5)).min(MAX_RESTART_BACKOFF);
+                tokio::select! {
+                    _ = tokio::time::sleep(backoff) => {}
+                    _ = self.shutdown.notified() => return,
+                }
+                match self.launch().await {
+                    Ok(child) => break child,
+                    Err(e) => warn!("Restarting plugin '{}' failed: {}", self.config.name, e),
+                }
+            };
+            started = Instant::now();
+            self.replay_models().await;
+        }
+    }
+
+    /// 重启后重新加载模型
+    async fn replay_models(&self) {
+        let models: Vec<(ModelId, ModelConfig, Option<ParallelPlan>, Precision)> = self.models.iter()
+            .map(|entry| (entry.key().clone(), entry.config.clone(), entry.parallel.clone(), entry.precision))
+            .collect();
+        for (model_id, config, parallel, precision) in models {
+            match self.load_remote(&model_id, &config, parallel.as_ref(), precision).await {
+                Ok(response) => {
+                    if let Some(mut model) = self.models.get_mut(&model_id) {
+                        model.handle = response.handle;
+                    }
+                }
+                Err(e) => error!(
+                    "Plugin '{}' could not reload model {} after restart: {}",
+                    self.config.name, model_id, e
+                ),
+            }
+        }
+    }
+
+    fn status_error(&self, status: tonic::Status) -> UniModelError {
+        let message = format!("Plugin '{}': {}", self.config.name, status.message());
+        match status.code() {
+            tonic::Code::InvalidArgument => UniModelError::validation(message),
+            tonic::Code::NotFound => UniModelError::model(message),
+            tonic::Code::ResourceExhausted => UniModelError::resource(message),
+            tonic::Code::Unavailable => UniModelError::plugin_unavailable(message),
+            _ => UniModelError::plugin(message),
+        }
+    }
+}
+
+/// 所有进程外插件，按后端名称路由
+#[derive(Debug, Default)]
+pub struct RemotePlugins {
+    plugins: Vec<Arc<RemotePlugin>>,
+    by_backend: HashMap<String, Arc<RemotePlugin>>,
+}
+
+impl RemotePlugins {
+    /// 启动配置中的所有插件；单个插件启动失败只记录错误，不影响服务器启动
+    pub async fn start(config: &PluginConfig) -> Self {
+        let call_timeout = Duration::from_secs(config.plugin_timeout_secs);
+        let cgroup_root = config.cgroup_root.as_deref();
+        let mut plugins = Self::default();
+        for plugin_config in &config.external_plugins {
+            let name = plugin_config.name.clone();
+            match RemotePlugin::start(plugin_config.clone(), call_timeout, cgroup_root).await {
+                Ok(plugin) => plugins.add(plugin),
+                Err(e) => error!("Plugin '{}' failed to start: {}", name, e),
+            }
+        }
+        plugins
+    }
+
+    /// 加入已启动的插件；后端已由其他插件提供时保留先加入的插件
+    pub fn add(&mut self, plugin: Arc<RemotePlugin>) {
+        for backend in plugin.backends() {
+            if let Some(existing) = self.by_backend.get(&backend) {
+                warn!(
+                    "Backend '{}' is provided by both '{}' and '{}', using '{}'",
+                    backend,
+                    existing.name(),
+                    plugin.name(),
+                    existing.name()
+                );
+                continue;
+            }
+            self.by_backend.insert(backend, Arc::clone(&plugin));
+        }
+        self.plugins.push(plugin);
+    }
+
+    /// 提供该后端的插件
+    pub fn for_backend(&self, backend: &str) -> Option<Arc<RemotePlugin>> {
+        self.by_backend.get(backend).cloned()
+    }
+
+    /// 所有插件
+    pub fn plugins(&self) -> &[Arc<RemotePlugin>] {
+        &self.plugins
+    }
+
+    /// 结束所有插件进程
+    pub fn shutdown(&self) {
+        for plugin in &self.plugins {
+            plugin.shutdown();
+        }
+    }
+}
+
+/// 按serde名称解析能力中的枚举值，无法识别的值记录警告后忽略
+fn parse_names<T: DeserializeOwned>(plugin: &str, names: &[String]) -> Vec<T> {
+    names.iter()
+        .filter_map(|name| match serde_json::from_value(serde_json::Value::String(name.clone())) {
+            Ok(value) => Some(value),
+            Err(_) => {
+                warn!("Plugin '{}' declares unknown capability value '{}'", plugin, name);
+                None
+            }
+        })
+        .collect()
+}
+
+/// 插件加载模型时报告的计算精度，未报告时返回None
+pub(crate) fn parse_precision(plugin: &str, name: &str) -> Option<Precision> {
+    if name.is_empty() {
+        return None;
+    }
+    parse_names(plugin, &[name.to_string()]).pop()
+}
+
+/// 把插件进程的输出按行转发到日志
+async fn forward_output<R: AsyncRead + Unpin>(name: String, output: R) {
+    let mut lines = BufReader::new(output).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        info!(target: "plugin", "[{}] {}", name, line);
+    }
+}
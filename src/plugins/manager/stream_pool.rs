@@ -0,0 +1,77 @@
+//! 模型实例的CUDA流
+//!
+//! 每个CUDA模型实例有N条流，同时执行的批次各占一条，相邻批次的主机到设备拷贝、计算和
+//! 设备到主机拷贝因此可以重叠，而不是在默认流上串行。流的编号随推理调用传给插件，
+//! 插件按编号把调用提交到自己创建的对应流上；N条流都在使用时后续批次等待。
+
+use std::sync::Arc;
+
+use parking_lot::Mutex;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// 一个模型实例的流
+#[derive(Debug)]
+pub struct StreamPool {
+    /// 空闲的流编号，末尾的先分配
+    free: Mutex<Vec<u32>>,
+    available: Arc<Semaphore>,
+    streams: u32,
+}
+
+impl StreamPool {
+    /// 创建 `streams` 条流（至少一条）
+    pub fn new(streams: u32) -> Arc<Self> {
+        let streams = streams.max(1);
+        Arc::new(Self {
+            free: Mutex::new((0..streams).rev().collect()),
+            available: Arc::new(Semaphore::new(streams as usize)),
+            streams,
+        })
+    }
+
+    /// 流的数量
+    pub fn streams(&self) -> u32 {
+        self.streams
+    }
+
+    /// 正在使用的流数
+    pub fn in_use(&self) -> u32 {
+        self.streams - self.available.available_permits() as u32
+    }
+
+    /// 占用一条空闲的流，全部在使用时等待
+    pub async fn acquire(self: &Arc<Self>) -> StreamLease {
+        let permit = Arc::clone(&self.available)
+            .acquire_owned()
+            .await
+            .expect("stream semaphore is never closed");
+        let stream = self.free.lock().pop().expect("a permit guarantees a free stream");
+        StreamLease {
+            pool: Arc::clone(self),
+            stream,
+            _permit: permit,
+        }
+    }
+}
+
+/// 占用中的流，释放时归还
+#[derive(Debug)]
+pub struct StreamLease {
+    pool: Arc<StreamPool>,
+    stream: u32,
+    _permit: OwnedSemaphorePermit,
+}
+
+impl StreamLease {
+    /// 流编号，从0开始
+    pub fn stream(&self) -> u32 {
+        self.stream
+    }
+}
+
+impl Drop for StreamLease {
+    fn drop(&mut self) {
+        // 先归还编号，许可在字段析构时才释放
+        self.pool.free.lock().push(self.stream);
+    }
+}
@@ -0,0 +1,6 @@
+//! 插件系统
+
+pub mod builtin;
+pub mod ffi;
+pub mod interface;
+pub mod manager;
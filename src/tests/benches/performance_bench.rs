@@ -2,11 +2,11 @@
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
 use tokio::runtime::Runtime;
-use std::time::Duration;
 
 use unimodel::prelude::*;
 use unimodel::infrastructure::configuration::Config;
 use unimodel::domain::service::batch_processor::BatchProcessor;
+use unimodel::domain::service::ModelManager;
 
 fn benchmark_batch_processing(c: &mut Criterion) {
     let rt = Runtime::new().unwrap();
@@ -27,14 +27,14 @@ fn benchmark_batch_processing(c: &mut Criterion) {
                 b.to_async(&rt).iter(|| async {
                     let mut tasks = Vec::new();
 
-                    for i in 0..*batch_size {
+                    for i in 0..batch_size {
                         let processor = batch_processor.clone();
                         let model_id = format!("test-model-{}", i % 4);
                         let input = InputData::Text(format!("Test input {}", i));
                         let parameters = PredictionParameters::default();
 
                         let task = tokio::spawn(async move {
-                            processor.submit_request(model_id, input, parameters).await
+                            processor.submit_request(model_id, input, parameters, None).await
                         });
                         tasks.push(task);
                     }
@@ -119,6 +119,8 @@ fn benchmark_model_operations(c: &mut Criterion) {
                     device_ids: vec![0],
                     memory_limit_mb: Some(1024),
                     mixed_precision: false,
+                    parallelism: Parallelism::None,
+                    micro_batches: None,
                 },
                 optimization: OptimizationConfig {
                     kv_cache: false,
@@ -126,15 +128,26 @@ fn benchmark_model_operations(c: &mut Criterion) {
                     graph_optimization: true,
                     inference_parallelism: 1,
                     memory_optimization: MemoryOptimization::Low,
+                    cuda_graphs: false,
+                    cuda_streams: 1,
                 },
                 batch_config: BatchConfig::default(),
+                max_concurrent_batches: None,
+                warmup: WarmupConfig::default(),
+                signature: None,
+                input_schema: None,
+                preprocessing: None,
+                postprocessing: None,
+                labels_path: None,
+                speculative: None,
                 custom_params: std::collections::HashMap::new(),
             };
 
             let model_id = model_manager.register_model(
                 format!("test-model-{}", rand::random::<u32>()),
-                ModelType::TextGeneration,
+                ModelType::LLM,
                 model_config,
+                None,
             ).await.unwrap();
 
             // 清理
@@ -6,7 +6,7 @@ use serde_json::json;
 
 use unimodel::prelude::*;
 use unimodel::infrastructure::configuration::Config;
-use unimodel::domain::service::ModelManager;
+use unimodel::domain::service::{BatchProcessor, ModelManager};
 use unimodel::application::services::{ModelService, PredictionService};
 
 #[tokio::test]
@@ -21,12 +21,14 @@ async fn test_model_lifecycle() {
         model_path: "test_model.onnx".to_string(),
         config_path: None,
         tokenizer_path: None,
-        backend: "onnx".to_string(),
+        backend: "mock".to_string(),
         device: DeviceConfig {
             device_type: DeviceType::CPU,
             device_ids: vec![0],
             memory_limit_mb: Some(1024),
             mixed_precision: false,
+            parallelism: Parallelism::None,
+            micro_batches: None,
         },
         optimization: OptimizationConfig {
             kv_cache: false,
@@ -34,35 +36,46 @@ async fn test_model_lifecycle() {
             graph_optimization: true,
             inference_parallelism: 1,
             memory_optimization: MemoryOptimization::Low,
+            cuda_graphs: false,
+            cuda_streams: 1,
         },
         batch_config: BatchConfig::default(),
+        max_concurrent_batches: None,
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
         custom_params: std::collections::HashMap::new(),
     };
 
     let model_id = model_service.register_model(
         "test-model".to_string(),
-        ModelType::ImageClassification,
+        ModelType::CV,
         model_config,
+        None,
     ).await.unwrap();
 
     // 等待模型加载
     sleep(Duration::from_millis(100)).await;
 
     // 获取模型信息
-    let model_info = model_service.get_model_info(&model_id).await.unwrap();
+    let model_info = model_service.get_model_info(&model_id, None).await.unwrap();
     assert_eq!(model_info.name, "test-model");
-    assert_eq!(model_info.model_type, ModelType::ImageClassification);
+    assert_eq!(model_info.model_type, ModelType::CV);
 
     // 获取模型列表
-    let models = model_service.list_models().await.unwrap();
+    let models = model_service.list_models(None).await.unwrap();
     assert_eq!(models.len(), 1);
-    assert_eq!(models[0].model_id, model_id);
+    assert_eq!(models[0].id, model_id);
 
     // 注销模型
-    model_service.unregister_model(&model_id).await.unwrap();
+    model_service.unregister_model(&model_id, None).await.unwrap();
 
     // 确认模型已注销
-    let models = model_service.list_models().await.unwrap();
+    let models = model_service.list_models(None).await.unwrap();
     assert_eq!(models.len(), 0);
 }
 
@@ -82,12 +95,14 @@ async fn test_prediction_service() {
         model_path: "test_model.onnx".to_string(),
         config_path: None,
         tokenizer_path: None,
-        backend: "onnx".to_string(),
+        backend: "mock".to_string(),
         device: DeviceConfig {
             device_type: DeviceType::CPU,
             device_ids: vec![0],
             memory_limit_mb: Some(1024),
             mixed_precision: false,
+            parallelism: Parallelism::None,
+            micro_batches: None,
         },
         optimization: OptimizationConfig {
             kv_cache: false,
@@ -95,15 +110,26 @@ async fn test_prediction_service() {
             graph_optimization: true,
             inference_parallelism: 1,
             memory_optimization: MemoryOptimization::Low,
+            cuda_graphs: false,
+            cuda_streams: 1,
         },
         batch_config: BatchConfig::default(),
+        max_concurrent_batches: None,
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
         custom_params: std::collections::HashMap::new(),
     };
 
     let model_id = model_service.register_model(
         "test-model".to_string(),
-        ModelType::TextGeneration,
+        ModelType::LLM,
         model_config,
+        None,
     ).await.unwrap();
 
     // 等待模型加载
@@ -117,6 +143,7 @@ async fn test_prediction_service() {
         model_id.clone(),
         input,
         parameters,
+        None,
     ).await.unwrap();
 
     assert_eq!(response.model_id, model_id);
@@ -133,6 +160,7 @@ async fn test_prediction_service() {
         model_id.clone(),
         inputs,
         parameters,
+        None,
     ).await.unwrap();
 
     assert_eq!(responses.len(), 2);
@@ -147,7 +175,6 @@ async fn test_batch_processing() {
     batch_processor.start().await.unwrap();
 
     let model_id = "test-model".to_string();
-    let input = InputData::Text("Test input".to_string());
     let parameters = PredictionParameters::default();
 
     // 提交多个请求
@@ -159,7 +186,7 @@ async fn test_batch_processing() {
         let parameters = parameters.clone();
 
         let task = tokio::spawn(async move {
-            processor.submit_request(model_id, input, parameters).await
+            processor.submit_request(model_id, input, parameters, None).await
         });
         tasks.push(task);
     }
@@ -176,4 +203,187 @@ async fn test_batch_processing() {
     assert!(stats.is_running);
 
     batch_processor.stop().await.unwrap();
-}
\ No newline at end of file
+}
+
+#[tokio::test]
+async fn test_token_accounting() {
+    use tokenizers::models::wordlevel::WordLevel;
+    use tokenizers::pre_tokenizers::whitespace::WhitespaceSplit;
+    use tokenizers::Tokenizer;
+
+    // 按空白切分的词级分词器
+    let vocab = [("[UNK]", 0), ("Processed:", 1), ("Hello", 2), ("world", 3)]
+        .into_iter()
+        .map(|(token, id)| (token.to_string(), id))
+        .collect();
+    let model = WordLevel::builder().vocab(vocab).unk_token("[UNK]".to_string()).build().unwrap();
+    let mut tokenizer = Tokenizer::new(model);
+    tokenizer.with_pre_tokenizer(WhitespaceSplit);
+    let dir = tempfile::tempdir().unwrap();
+    let tokenizer_path = dir.path().join("tokenizer.json");
+    tokenizer.save(&tokenizer_path, false).unwrap();
+
+    let config = Config::default();
+    let model_manager = Arc::new(ModelManager::new(&config).await.unwrap());
+    let batch_processor = Arc::new(BatchProcessor::new(&config).await.unwrap());
+    batch_processor.start().await.unwrap();
+
+    let model_service = ModelService::new(model_manager.clone());
+    let prediction_service = PredictionService::new(model_manager, batch_processor);
+
+    let model_config = ModelConfig {
+        model_path: "test_model.onnx".to_string(),
+        config_path: None,
+        tokenizer_path: Some(tokenizer_path.to_string_lossy().into_owned()),
+        backend: "mock".to_string(),
+        device: DeviceConfig {
+            device_type: DeviceType::CPU,
+            device_ids: vec![0],
+            memory_limit_mb: Some(1024),
+            mixed_precision: false,
+            parallelism: Parallelism::None,
+            micro_batches: None,
+        },
+        optimization: OptimizationConfig {
+            kv_cache: false,
+            quantization: None,
+            graph_optimization: true,
+            inference_parallelism: 1,
+            memory_optimization: MemoryOptimization::Low,
+            cuda_graphs: false,
+            cuda_streams: 1,
+        },
+        batch_config: BatchConfig::default(),
+        max_concurrent_batches: None,
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
+        custom_params: [("context_length".to_string(), json!(4))].into_iter().collect(),
+    };
+
+    let model_id = model_service.register_model(
+        "token-model".to_string(),
+        ModelType::LLM,
+        model_config,
+        None,
+    ).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    assert_eq!(
+        model_service.tokenize(&model_id, None, "Hello world".to_string(), true).await.unwrap(),
+        vec![2, 3]
+    );
+
+    // 模拟后端返回 "Processed: <输入>"
+    let response = prediction_service.predict(
+        model_id.clone(),
+        InputData::Text("Hello world".to_string()),
+        PredictionParameters::default(),
+        None,
+    ).await.unwrap();
+    assert_eq!(response.metrics.tokens_input, Some(2));
+    assert_eq!(response.metrics.tokens_generated, Some(3));
+
+    // 提示占满上下文窗口时在排队前拒绝
+    let result = prediction_service.predict(
+        model_id.clone(),
+        InputData::Text("Hello world Hello world".to_string()),
+        PredictionParameters::default(),
+        None,
+    ).await;
+    assert!(matches!(result, Err(unimodel::UniModelError::Validation(ref msg)) if msg.contains("4 tokens")));
+}
+
+#[tokio::test]
+async fn test_wait_for_preloaded_models() {
+    use unimodel::infrastructure::configuration::PreloadModelConfig;
+
+    let mut config = Config::default();
+    config.engine.preload.push(
+        serde_json::from_value::<PreloadModelConfig>(json!({
+            "name": "preloaded",
+            "model_type": "CV",
+            "backend": "mock",
+            "model_path": "test_model.onnx",
+            "device_type": "CPU"
+        }))
+        .unwrap(),
+    );
+    let model_manager = ModelManager::new(&config).await.unwrap();
+    model_manager.preload().await.unwrap();
+
+    model_manager.wait_until_ready(Duration::from_secs(5)).await.unwrap();
+    let readiness = model_manager.readiness().await;
+    assert!(readiness.ready);
+    assert!(readiness.pending.is_empty());
+    assert!(readiness.failed.is_empty());
+}
+
+#[tokio::test]
+async fn test_batch_predict_cancels_requests_when_dropped() {
+    let mut config = Config::default();
+    config.engine.batch_config.max_batch_size = 8;
+    config.engine.batch_config.max_wait_time_ms = 300;
+    let model_manager = Arc::new(ModelManager::new(&config).await.unwrap());
+    let batch_processor = Arc::new(BatchProcessor::new(&config).await.unwrap());
+    batch_processor.start().await.unwrap();
+
+    let model_service = ModelService::new(model_manager.clone());
+    let prediction_service = PredictionService::new(model_manager, batch_processor.clone());
+
+    let model_config = ModelConfig {
+        model_path: "test_model.onnx".to_string(),
+        config_path: None,
+        tokenizer_path: None,
+        backend: "mock".to_string(),
+        device: DeviceConfig {
+            device_type: DeviceType::CPU,
+            device_ids: vec![0],
+            memory_limit_mb: Some(1024),
+            mixed_precision: false,
+            parallelism: Parallelism::None,
+            micro_batches: None,
+        },
+        optimization: OptimizationConfig {
+            kv_cache: false,
+            quantization: None,
+            graph_optimization: true,
+            inference_parallelism: 1,
+            memory_optimization: MemoryOptimization::Low,
+            cuda_graphs: false,
+            cuda_streams: 1,
+        },
+        batch_config: BatchConfig::default(),
+        max_concurrent_batches: None,
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
+        custom_params: std::collections::HashMap::new(),
+    };
+    let model_id = model_service.register_model(
+        "cancel-model".to_string(),
+        ModelType::LLM,
+        model_config,
+        None,
+    ).await.unwrap();
+    sleep(Duration::from_millis(100)).await;
+
+    // 请求在凑批时调用方断开，排队中的请求随之放弃
+    let inputs = (0..3).map(|i| InputData::Text(format!("input {}", i))).collect();
+    let batch = prediction_service.batch_predict(model_id, inputs, PredictionParameters::default(), None);
+    assert!(tokio::time::timeout(Duration::from_millis(100), batch).await.is_err());
+
+    sleep(Duration::from_millis(500)).await;
+    let stats = batch_processor.get_batch_stats().await;
+    assert_eq!((stats.cancelled_requests, stats.total_processed), (3, 0));
+    assert_eq!(stats.pending_requests, 0);
+    assert_eq!(batch_processor.outstanding_requests(), 0);
+}
@@ -0,0 +1,297 @@
+//! 领域模型单元测试
+
+use std::collections::HashMap;
+
+use unimodel::common::types::*;
+use unimodel::domain::model::*;
+
+fn test_model_config() -> ModelConfig {
+    ModelConfig {
+        model_path: "/path/to/model.onnx".to_string(),
+        config_path: None,
+        tokenizer_path: None,
+        backend: "onnx".to_string(),
+        device: DeviceConfig {
+            device_type: DeviceType::CPU,
+            device_ids: vec![0],
+            memory_limit_mb: Some(1024),
+            mixed_precision: false,
+            parallelism: Parallelism::None,
+            micro_batches: None,
+        },
+        optimization: OptimizationConfig {
+            kv_cache: false,
+            quantization: None,
+            graph_optimization: true,
+            inference_parallelism: 1,
+            memory_optimization: MemoryOptimization::Low,
+            cuda_graphs: false,
+            cuda_streams: 1,
+        },
+        batch_config: BatchConfig::default(),
+        max_concurrent_batches: None,
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
+        custom_params: HashMap::new(),
+    }
+}
+
+#[test]
+fn test_model_tenant_visibility() {
+    let mut model = Model::new(
+        new_model_id(),
+        "resnet".to_string(),
+        ModelType::CV,
+        test_model_config(),
+    );
+
+    // 全局命名空间
+    assert_eq!(model.info.qualified_name(), "resnet");
+    assert!(model.info.is_visible_to(None));
+    assert!(!model.info.is_visible_to(Some("acme")));
+
+    // 租户命名空间
+    model.info.tenant = Some("acme".to_string());
+    assert_eq!(model.info.qualified_name(), "acme/resnet");
+    assert!(model.info.is_visible_to(None));
+    assert!(model.info.is_visible_to(Some("acme")));
+    assert!(!model.info.is_visible_to(Some("globex")));
+}
+
+#[test]
+fn test_model_info_pinned_defaults_to_false() {
+    let model = Model::new(
+        new_model_id(),
+        "resnet".to_string(),
+        ModelType::CV,
+        test_model_config(),
+    );
+    assert!(!model.info.pinned);
+
+    // 旧版本序列化的模型信息没有pinned字段
+    let mut value = serde_json::to_value(&model.info).unwrap();
+    value.as_object_mut().unwrap().remove("pinned");
+    let info: ModelInfo = serde_json::from_value(value).unwrap();
+    assert!(!info.pinned);
+}
+
+#[test]
+fn test_model_info_adapter_lookup() {
+    let mut model = Model::new(
+        new_model_id(),
+        "llama".to_string(),
+        ModelType::LLM,
+        test_model_config(),
+    );
+    assert!(model.info.adapters.is_empty());
+
+    model.info.adapters.push(LoraAdapter {
+        name: "sql".to_string(),
+        path: "/adapters/sql".to_string(),
+        loaded_at: chrono::Utc::now(),
+    });
+    assert_eq!(model.info.adapter("sql").map(|a| a.path.as_str()), Some("/adapters/sql"));
+    assert!(model.info.adapter("chat").is_none());
+
+    // 旧版本序列化的模型信息没有adapters字段
+    let mut value = serde_json::to_value(&model.info).unwrap();
+    value.as_object_mut().unwrap().remove("adapters");
+    let info: ModelInfo = serde_json::from_value(value).unwrap();
+    assert!(info.adapters.is_empty());
+}
+
+#[test]
+fn test_warmup_inputs() {
+    let warmup = WarmupConfig::default();
+    assert_eq!(warmup.requests, 0);
+    assert!(matches!(warmup.input(0, &ModelType::LLM), InputData::Text(_)));
+    assert!(matches!(warmup.input(0, &ModelType::CV), InputData::Binary(_)));
+
+    // 用户样本循环使用
+    let warmup = WarmupConfig {
+        requests: 3,
+        samples: vec![InputData::Text("a".to_string()), InputData::Text("b".to_string())],
+    };
+    let inputs: Vec<String> = (0..3)
+        .map(|i| match warmup.input(i, &ModelType::LLM) {
+            InputData::Text(text) => text,
+            other => panic!("unexpected input {:?}", other),
+        })
+        .collect();
+    assert_eq!(inputs, vec!["a", "b", "a"]);
+}
+
+#[test]
+fn test_user_signature_modalities() {
+    let mut signature: ModelSignature = serde_json::from_value(serde_json::json!({
+        "inputs": [
+            { "name": "input_ids", "dtype": "i64", "shape": [-1, -1] },
+            { "name": "prompt_image", "dtype": "f32", "shape": [-1, 3, 336, 336], "modality": "image" }
+        ],
+        "outputs": [
+            { "name": "logits", "dtype": "f32", "shape": [-1, -1, 32000] }
+        ]
+    }))
+    .unwrap();
+    assert_eq!(signature.source, SignatureSource::User);
+
+    signature.infer_modalities(&ModelType::LLM);
+    assert_eq!(signature.inputs[0].modality, Some(Modality::Text));
+    // 显式标注的模态保持不变
+    assert_eq!(signature.inputs[1].modality, Some(Modality::Image));
+    assert_eq!(signature.outputs[0].modality, Some(Modality::Tensor));
+
+    assert_eq!(Modality::infer(&ModelType::CV, Some(DataType::F32), 4), Modality::Image);
+    assert_eq!(Modality::infer(&ModelType::Audio, Some(DataType::F32), 2), Modality::Audio);
+}
+
+#[test]
+fn test_input_schema_validation() {
+    let schema: InputSchema = serde_json::from_value(serde_json::json!({
+        "type": "object",
+        "required": ["messages"],
+        "properties": {
+            "messages": {
+                "type": "array",
+                "items": {
+                    "type": "object",
+                    "required": ["role", "content"],
+                    "properties": { "role": { "enum": ["system", "user", "assistant"] } }
+                }
+            }
+        }
+    }))
+    .unwrap();
+
+    let valid = serde_json::json!({ "messages": [{ "role": "user", "content": "hi" }] });
+    assert!(schema.validate(&valid).is_ok());
+
+    let invalid = serde_json::json!({ "messages": [{ "role": "robot", "content": "hi" }] });
+    let err = schema.validate(&invalid).unwrap_err();
+    assert_eq!(err.status_code(), 400);
+    assert!(err.to_string().contains("/messages/0/role"));
+
+    // Schema原样序列化，无效的Schema在反序列化时被拒绝
+    assert_eq!(serde_json::to_value(&schema).unwrap()["required"], serde_json::json!(["messages"]));
+    assert!(serde_json::from_value::<InputSchema>(serde_json::json!({ "type": 42 })).is_err());
+}
+
+#[test]
+fn test_wasm_stage_config() {
+    let preprocessing: PreprocessingConfig = serde_json::from_value(serde_json::json!({
+        "wasm": { "module": "/plugins/tokenize.wasm" }
+    }))
+    .unwrap();
+    assert_eq!(preprocessing.wasm, Some(WasmStage { module: "/plugins/tokenize.wasm".to_string() }));
+    // WASM阶段作用于任意输入，不代表配置了二进制预处理
+    assert!(!preprocessing.handles_binary());
+
+    let postprocessing: PostprocessingConfig = serde_json::from_value(serde_json::json!({})).unwrap();
+    assert!(postprocessing.wasm.is_none());
+}
+
+#[test]
+fn test_cuda_graph_batch_sizes() {
+    let mut config = test_model_config();
+    config.batch_config.max_batch_size = 12;
+    assert!(config.cuda_graph_batch_sizes().is_empty());
+    assert_eq!(config.cuda_graph_padded_size(3), 3);
+    assert!(config.check_cuda_graphs().is_ok());
+
+    config.optimization.cuda_graphs = true;
+    assert_eq!(config.cuda_graph_batch_sizes(), vec![1, 2, 4, 8, 12]);
+    assert_eq!(config.cuda_graph_padded_size(3), 4);
+    assert_eq!(config.cuda_graph_padded_size(9), 12);
+    assert_eq!(config.cuda_graph_padded_size(8), 8);
+
+    // 只能在CUDA设备上捕获
+    assert!(config.check_cuda_graphs().is_err());
+    config.device.device_type = DeviceType::CUDA;
+    assert!(config.check_cuda_graphs().is_ok());
+
+    // 批维度可以是动态的，其余维度须固定
+    config.signature = Some(serde_json::from_value(serde_json::json!({
+        "inputs": [{ "name": "pixels", "dtype": "f32", "shape": [-1, 3, 224, 224] }],
+        "outputs": [{ "name": "logits", "dtype": "f32", "shape": [-1, 1000] }]
+    }))
+    .unwrap());
+    assert!(config.check_cuda_graphs().is_ok());
+    config.signature.as_mut().unwrap().inputs[0].shape = vec![-1, 3, -1, -1];
+    assert!(config.check_cuda_graphs().is_err());
+}
+
+#[test]
+fn test_tensor_parallel_validation() {
+    let mut config = test_model_config();
+    assert_eq!(config.parallel_size(), 1);
+    assert!(config.check_parallelism().is_ok());
+
+    config.device.parallelism = Parallelism::Tensor;
+    config.device.device_type = DeviceType::CUDA;
+    config.device.device_ids = vec![0, 1, 2, 3];
+    assert_eq!(config.parallel_size(), 4);
+    assert!(config.check_parallelism().is_ok());
+
+    // 至少两张不重复的GPU
+    config.device.device_ids = vec![0];
+    assert!(config.check_parallelism().is_err());
+    config.device.device_ids = vec![0, 0];
+    assert!(config.check_parallelism().is_err());
+
+    config.device.device_ids = vec![0, 1];
+    config.device.device_type = DeviceType::CPU;
+    assert!(config.check_parallelism().is_err());
+
+    let device: DeviceConfig = serde_json::from_value(serde_json::json!({
+        "device_type": "CUDA", "device_ids": [0, 1], "memory_limit_mb": null,
+        "mixed_precision": false, "parallelism": "tensor"
+    }))
+    .unwrap();
+    assert_eq!(device.parallelism, Parallelism::Tensor);
+}
+
+#[test]
+fn test_pipeline_micro_batches() {
+    let mut config = test_model_config();
+    assert_eq!(config.micro_batches(), 1);
+    assert_eq!(config.micro_batch_ranges(5), vec![0..5]);
+
+    config.device.device_type = DeviceType::CUDA;
+    config.device.device_ids = vec![0, 1, 2];
+    config.device.parallelism = Parallelism::Pipeline;
+    assert!(config.check_parallelism().is_ok());
+    // 默认每个阶段一个微批次，前面的微批次多分余下的输入
+    assert_eq!(config.micro_batches(), 3);
+    assert_eq!(config.micro_batch_ranges(8), vec![0..3, 3..6, 6..8]);
+    // 输入少于微批次数时每个输入一个微批次
+    assert_eq!(config.micro_batch_ranges(2), vec![0..1, 1..2]);
+
+    config.device.micro_batches = Some(4);
+    assert_eq!(config.micro_batch_ranges(8), vec![0..2, 2..4, 4..6, 6..8]);
+
+    config.device.micro_batches = Some(0);
+    assert!(config.check_parallelism().is_err());
+    config.device.micro_batches = None;
+    config.optimization.cuda_graphs = true;
+    assert!(config.check_parallelism().is_err());
+}
+
+#[test]
+fn test_accelerator_device_types() {
+    assert!(DeviceType::CUDA.is_accelerator());
+    assert!(DeviceType::ROCm.is_accelerator());
+    assert!(DeviceType::NPU.is_accelerator());
+    assert!(!DeviceType::CPU.is_accelerator());
+    assert!(!DeviceType::Metal.is_accelerator());
+    assert!(!DeviceType::Auto.is_accelerator());
+
+    // HIP是ROCm的别名
+    let device: DeviceType = serde_json::from_value(serde_json::json!("HIP")).unwrap();
+    assert_eq!(device, DeviceType::ROCm);
+    assert_eq!(serde_json::to_value(DeviceType::ROCm).unwrap(), "ROCm");
+}
@@ -0,0 +1,5 @@
+//! 单元测试
+
+mod domain_test;
+mod service_test;
+mod utils_test;
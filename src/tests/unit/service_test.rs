@@ -0,0 +1,2974 @@
+//! 服务组件单元测试
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+use tokio::sync::oneshot;
+use unimodel::common::error::UniModelError;
+use unimodel::common::types::{BatchConfig, InputData, OutputData, PredictionParameters, TokenLogprob};
+use unimodel::domain::model::{
+    DeviceConfig, DeviceType, MemoryOptimization, Model, ModelConfig, ModelEvent, ModelEventKind, ModelInstance,
+    ModelType, OptimizationConfig, Parallelism, Precision, QuantizationType, SpeculativeConfig, WarmupConfig,
+};
+use unimodel::domain::service::batch_processor::BatchRequest;
+use unimodel::domain::service::scheduler::{estimate_memory_mb, MEMORY_FOOTPRINT_PARAM};
+use unimodel::domain::service::{
+    BatchTuner, EventLog, PendingQueue, ReplicaBalancer, Scheduler, SessionTable, SpeculativeDecoder, WeightCache,
+};
+use unimodel::infrastructure::configuration::{Config, PinnedMemoryConfig, QuotaOverride, RateLimitConfig, TenantConfig};
+use unimodel::infrastructure::memory::{
+    BlockCopy, DeviceAllocator, DeviceMemoryPool, HostMemoryRegistrar, PagedKvCache, PagedKvConfig, PinnedMemoryPool,
+};
+use unimodel::infrastructure::security::RateLimiter;
+use unimodel::plugins::interface::{
+    DecodeStep, DraftModel, DraftToken, IterativeDecoder, ModelWeights, Quantizer, SpeculativeTarget, Verification,
+};
+use unimodel::BatchProcessor;
+
+fn rate_limit_config(requests_per_minute: u32, burst_size: u32) -> RateLimitConfig {
+    RateLimitConfig {
+        enabled: true,
+        requests_per_minute,
+        burst_size,
+        tokens_per_month: None,
+        overrides: HashMap::new(),
+        quota_store_path: "./data/quota_usage.json".to_string(),
+    }
+}
+
+#[test]
+fn test_rate_limiter_token_bucket() {
+    let limiter = RateLimiter::new(&rate_limit_config(60, 2));
+
+    // 突发容量内放行
+    assert!(limiter.check("ip:127.0.0.1", 60).is_ok());
+    assert!(limiter.check("ip:127.0.0.1", 60).is_ok());
+
+    // 超出突发容量后拒绝，并给出约1秒的重试时间
+    let retry_after = limiter.check("ip:127.0.0.1", 60).unwrap_err();
+    assert!(retry_after.as_secs_f64() > 0.0 && retry_after.as_secs_f64() <= 1.0);
+
+    // 不同主体使用独立的令牌桶
+    assert!(limiter.check("ip:10.0.0.1", 60).is_ok());
+}
+
+#[test]
+fn test_rate_limiter_per_key_limits() {
+    let mut config = rate_limit_config(1000, 10);
+    config.overrides.insert(
+        "key-1".to_string(),
+        QuotaOverride {
+            requests_per_minute: Some(10),
+            tokens_per_month: None,
+        },
+    );
+    let limiter = RateLimiter::new(&config);
+
+    assert_eq!(limiter.requests_per_minute_for(None, None), 1000);
+    assert_eq!(limiter.requests_per_minute_for(Some("key-1"), None), 10);
+    assert_eq!(limiter.requests_per_minute_for(Some("key-1"), Some(5)), 5);
+}
+
+#[tokio::test]
+async fn test_rate_limit_key_ignores_unverified_keys() {
+    use axum::http::HeaderMap;
+    use unimodel::api::auth::{rate_limit_key, AuthContext, Authenticator};
+    use unimodel::infrastructure::security::ApiKeyStore;
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(ApiKeyStore::open(dir.path().join("api_keys.json")).await.unwrap());
+    let ip = Some("10.0.0.7".parse().unwrap());
+
+    // 未启用认证时随机密钥不会得到新的限流桶
+    let authenticator = Authenticator::new(&Config::default().security, Arc::clone(&store));
+    let mut buckets = HashSet::new();
+    for i in 0..3 {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", format!("random-{}", i).parse().unwrap());
+        let context = authenticator.authenticate(&headers).await.unwrap();
+        buckets.insert(rate_limit_key(Some(&context), ip));
+    }
+    assert_eq!(buckets.into_iter().collect::<Vec<_>>(), vec!["ip:10.0.0.7".to_string()]);
+    assert_eq!(rate_limit_key(None, None), "ip:unknown");
+
+    // 匹配的静态密钥和动态密钥按密钥分桶
+    let mut security = Config::default().security;
+    security.auth_enabled = true;
+    security.api_keys = vec!["static-key".to_string()];
+    let authenticator = Authenticator::new(&security, store);
+    let mut headers = HeaderMap::new();
+    headers.insert("x-api-key", "static-key".parse().unwrap());
+    let context = authenticator.authenticate(&headers).await.unwrap();
+    assert!(rate_limit_key(Some(&context), ip).starts_with("key-hash:"));
+
+    let context = AuthContext {
+        key_id: Some("key-1".to_string()),
+        ..AuthContext::anonymous()
+    };
+    assert_eq!(rate_limit_key(Some(&context), ip), "key:key-1");
+}
+
+#[tokio::test]
+async fn test_api_key_store_lifecycle() {
+    use unimodel::infrastructure::security::{ApiKeyStore, NewApiKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("keys").join("api_keys.json");
+    let store = ApiKeyStore::open(&path).await.unwrap();
+    let new_key = |name: &str| NewApiKey {
+        name: name.to_string(),
+        tenant: Some("acme".to_string()),
+        scopes: vec!["predict".to_string()],
+        expires_at: None,
+        rate_limit_per_minute: Some(60),
+    };
+
+    assert!(store.create(new_key("")).await.is_err());
+    let mut expired = new_key("expired");
+    expired.expires_at = Some(chrono::Utc::now() - chrono::Duration::seconds(1));
+    assert!(store.create(expired).await.is_err());
+
+    // 明文密钥格式为 `um_<id>_<secret>`，存储中只有argon2哈希
+    let (record, raw_key) = store.create(new_key("ci")).await.unwrap();
+    let parts: Vec<&str> = raw_key.splitn(3, '_').collect();
+    assert_eq!(parts[..2], ["um", record.id.as_str()]);
+    assert_eq!(parts[2].len(), 40);
+    assert!(record.key_hash.starts_with("$argon2"));
+    assert!(!std::fs::read_to_string(&path).unwrap().contains(parts[2]));
+
+    let verified = store.verify(&raw_key).await.unwrap();
+    assert_eq!((verified.id.as_str(), verified.tenant.as_deref()), (record.id.as_str(), Some("acme")));
+    assert!(verified.has_scope("predict") && !verified.has_scope("admin"));
+
+    // 格式错误、前缀错误、ID不存在或密钥不匹配时校验失败
+    let wrong_secret = format!("um_{}_{}", record.id, "x".repeat(40));
+    let unknown_id = format!("um_missing_{}", parts[2]);
+    let wrong_prefix = raw_key.replacen("um_", "xx_", 1);
+    for key in ["", "um", "um_", &format!("um_{}", record.id), &format!("um__{}", parts[2]), &wrong_prefix, &unknown_id, &wrong_secret] {
+        assert!(store.verify(key).await.is_none(), "{:?} should not verify", key);
+    }
+
+    // 吊销后校验失败，重新打开后记录和吊销状态仍在
+    let (other, other_key) = store.create(new_key("other")).await.unwrap();
+    assert!(store.revoke(&record.id).await.unwrap().revoked_at.is_some());
+    assert!(store.verify(&raw_key).await.is_none());
+    assert!(store.revoke("missing").await.is_err());
+
+    let reopened = ApiKeyStore::open(&path).await.unwrap();
+    let names: Vec<String> = reopened.list().await.into_iter().map(|r| r.name).collect();
+    assert_eq!(names, vec!["ci".to_string(), "other".to_string()]);
+    assert!(reopened.verify(&raw_key).await.is_none());
+    assert_eq!(reopened.verify(&other_key).await.unwrap().id, other.id);
+}
+
+#[tokio::test]
+async fn test_authenticator_caches_verified_keys_but_honours_revocation() {
+    use axum::http::HeaderMap;
+    use unimodel::api::auth::Authenticator;
+    use unimodel::infrastructure::security::{ApiKeyStore, NewApiKey};
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(ApiKeyStore::open(dir.path().join("api_keys.json")).await.unwrap());
+    let (record, raw_key) = store
+        .create(NewApiKey {
+            name: "ci".to_string(),
+            tenant: None,
+            scopes: vec!["*".to_string()],
+            expires_at: None,
+            rate_limit_per_minute: None,
+        })
+        .await
+        .unwrap();
+    let mut security = Config::default().security;
+    security.auth_enabled = true;
+    let authenticator = Authenticator::new(&security, Arc::clone(&store));
+    let headers = |key: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-api-key", key.parse().unwrap());
+        headers
+    };
+
+    // 首次校验计算argon2哈希，缓存期内重复请求跳过哈希
+    let started = Instant::now();
+    let context = authenticator.authenticate(&headers(&raw_key)).await.unwrap();
+    let uncached = started.elapsed();
+    assert_eq!(context.key_id.as_deref(), Some(record.id.as_str()));
+    let started = Instant::now();
+    for _ in 0..5 {
+        authenticator.authenticate(&headers(&raw_key)).await.unwrap();
+    }
+    assert!(started.elapsed() < uncached, "cached verifications took {:?}, one argon2 check took {:?}", started.elapsed(), uncached);
+
+    // 缓存只对同一明文密钥生效，吊销立即生效
+    let forged = format!("um_{}_{}", record.id, "x".repeat(40));
+    assert!(authenticator.authenticate(&headers(&forged)).await.is_err());
+    store.revoke(&record.id).await.unwrap();
+    assert!(authenticator.authenticate(&headers(&raw_key)).await.is_err());
+}
+
+/// 模拟解码器的一步输出
+fn decode_step(sequence_id: &str, token: impl Into<String>, finished: bool) -> DecodeStep {
+    DecodeStep {
+        sequence_id: sequence_id.to_string(),
+        token: token.into(),
+        finished,
+        stop_sequence: None,
+        logprob: None,
+        candidates: Vec::new(),
+        draft_tokens: 0,
+    }
+}
+
+/// 开启连续批处理的批处理器，`decoder` 注册为模型 `llm` 的解码器
+async fn continuous_processor(decoder: Arc<dyn IterativeDecoder>) -> BatchProcessor {
+    let mut config = Config::default();
+    config.engine.batch_config.continuous_batching = true;
+
+    let processor = BatchProcessor::new(&config).await.unwrap();
+    processor.start().await.unwrap();
+    processor.register_decoder(&"llm".to_string(), decoder);
+    processor
+}
+
+/// 每个序列生成与提示词长度相同数量的token
+#[derive(Debug, Default)]
+struct CountdownDecoder {
+    remaining: Mutex<HashMap<String, usize>>,
+}
+
+impl IterativeDecoder for CountdownDecoder {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        self.remaining.lock().insert(sequence_id.to_string(), prompt.len());
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> unimodel::Result<Vec<DecodeStep>> {
+        let mut remaining = self.remaining.lock();
+        Ok(sequence_ids
+            .iter()
+            .map(|id| {
+                let left = remaining.get_mut(id).unwrap();
+                *left -= 1;
+                decode_step(id, "x", *left == 0)
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.remaining.lock().remove(sequence_id);
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_batching_finishes_sequences_independently() {
+    let processor = continuous_processor(Arc::new(CountdownDecoder::default())).await;
+
+    let (short, long) = tokio::join!(
+        processor.submit_request("llm".to_string(), InputData::Text("ab".to_string()), PredictionParameters::default(), None),
+        processor.submit_request("llm".to_string(), InputData::Text("abcdef".to_string()), PredictionParameters::default(), None),
+    );
+
+    let short = short.unwrap();
+    let long = long.unwrap();
+    assert!(matches!(short.output, OutputData::Text(ref t) if t == "xx"));
+    assert!(matches!(long.output, OutputData::Text(ref t) if t == "xxxxxx"));
+    assert_eq!(short.metrics.tokens_generated, Some(2));
+    assert_eq!(long.metrics.tokens_generated, Some(6));
+}
+
+#[tokio::test]
+async fn test_model_manager_registers_decoders_for_continuous_batching() {
+    use unimodel::ModelManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    config.engine.batch_config.continuous_batching = true;
+    let model_manager = Arc::new(ModelManager::new(&config).await.unwrap());
+    let processor = BatchProcessor::new(&config).await.unwrap().with_model_manager(Arc::clone(&model_manager));
+    processor.start().await.unwrap();
+
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "mock".to_string();
+    model_config.device.device_type = DeviceType::CPU;
+    let model_id = model_manager
+        .register_model("echo".to_string(), ModelType::LLM, model_config, None)
+        .await
+        .unwrap();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !model_manager.decoders().contains_key(&model_id) {
+        assert!(Instant::now() < deadline, "decoder was not registered");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // 请求由模拟模型的解码器逐词生成，按 `max_tokens` 截断
+    let parameters = PredictionParameters { max_tokens: Some(2), ..PredictionParameters::default() };
+    let response = processor
+        .submit_request(model_id.clone(), InputData::Text("hello big world".to_string()), parameters, None)
+        .await
+        .unwrap();
+    assert!(matches!(response.output, OutputData::Text(ref t) if t == "Processed: hello "), "{:?}", response.output);
+    assert_eq!(response.metrics.tokens_generated, Some(2));
+
+    // 卸载时注销解码器
+    model_manager.unregister_model(&model_id).await.unwrap();
+    assert!(model_manager.decoders().is_empty());
+}
+
+#[tokio::test]
+async fn test_model_manager_builds_speculative_decoder_from_draft_model() {
+    use unimodel::ModelManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    config.engine.batch_config.continuous_batching = true;
+    config.engine.eviction.enabled = true;
+    config.engine.eviction.idle_ttl_secs = 0;
+    let model_manager = Arc::new(ModelManager::new(&config).await.unwrap());
+    let processor = BatchProcessor::new(&config).await.unwrap().with_model_manager(Arc::clone(&model_manager));
+    processor.start().await.unwrap();
+
+    let mut draft_config = gpu_model_config(1000);
+    draft_config.backend = "mock".to_string();
+    draft_config.device.device_type = DeviceType::CPU;
+    let mut target_config = draft_config.clone();
+    target_config.speculative = Some(SpeculativeConfig { draft_model: "draft".to_string(), num_speculative_tokens: 3 });
+    let draft_id = model_manager
+        .register_model("draft".to_string(), ModelType::LLM, draft_config, None)
+        .await
+        .unwrap();
+    let target_id = model_manager
+        .register_model("target".to_string(), ModelType::LLM, target_config, None)
+        .await
+        .unwrap();
+    let is_speculative = |id: &String| {
+        model_manager.decoders().get(id).is_some_and(|decoder| format!("{:?}", *decoder).starts_with("SpeculativeDecoder"))
+    };
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !is_speculative(&target_id) {
+        assert!(Instant::now() < deadline, "speculative decoder was not registered");
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    // 草稿与目标的回显一致，草稿全部被接受：每步产出一个目标token加上接受的草稿token
+    let response = processor
+        .submit_request(target_id.clone(), InputData::Text("a b c d e f".to_string()), PredictionParameters::default(), None)
+        .await
+        .unwrap();
+    assert!(matches!(response.output, OutputData::Text(ref t) if t == "Processed: a b c d e f"), "{:?}", response.output);
+    assert_eq!(response.metrics.tokens_generated, Some(7));
+    assert_eq!(response.metadata.custom_metadata["accepted_draft_tokens"], serde_json::json!(5));
+
+    // 逐出草稿模型后目标模型退回普通解码
+    model_manager.set_pinned(&target_id, true).await.unwrap();
+    assert_eq!(model_manager.evict_idle().await, vec![draft_id.clone()]);
+    assert!(!model_manager.decoders().contains_key(&draft_id));
+    assert!(model_manager.decoders().contains_key(&target_id));
+    assert!(!is_speculative(&target_id));
+}
+
+#[tokio::test]
+async fn test_batch_processor_drains_before_shutdown() {
+    let mut config = Config::default();
+    config.plugins.plugin_configs.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 200 } }),
+    );
+    let processor = Arc::new(BatchProcessor::new(&config).await.unwrap());
+    processor.start().await.unwrap();
+
+    let submitter = Arc::clone(&processor);
+    let in_flight = tokio::spawn(async move {
+        submitter
+            .submit_request("m1".to_string(), InputData::Text("a".to_string()), PredictionParameters::default(), None)
+            .await
+    });
+    while processor.outstanding_requests() == 0 {
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    // 排空期间拒绝新请求，已受理的请求正常完成
+    let (drained, rejected) = tokio::join!(processor.drain(Duration::from_secs(5)), async {
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        processor
+            .submit_request("m1".to_string(), InputData::Text("b".to_string()), PredictionParameters::default(), None)
+            .await
+    });
+    assert!(drained);
+    assert!(matches!(rejected, Err(UniModelError::Overloaded { .. })));
+    assert!(in_flight.await.unwrap().is_ok());
+    assert_eq!(processor.outstanding_requests(), 0);
+}
+
+fn batch_request(request_id: &str, tenant: &str) -> BatchRequest {
+    let (response_sender, _) = oneshot::channel();
+    let submitted_at = Instant::now();
+    BatchRequest {
+        request_id: request_id.to_string(),
+        model_id: "shared".to_string(),
+        tenant: Some(tenant.to_string()),
+        input: InputData::Text("input".to_string()),
+        parameters: PredictionParameters::default(),
+        response_sender,
+        submitted_at,
+        deadline: submitted_at + Duration::from_secs(30),
+    }
+}
+
+#[test]
+fn test_pending_queue_weighted_fair_across_tenants() {
+    let mut config = Config::default();
+    config.tenants.insert("gold".to_string(), TenantConfig { weight: 3, ..Default::default() });
+
+    let mut queue = PendingQueue::new(&config);
+    // 吵闹租户先提交大量请求
+    for i in 0..8 {
+        queue.push(batch_request(&format!("noisy-{}", i), "noisy"));
+    }
+    for i in 0..6 {
+        queue.push(batch_request(&format!("gold-{}", i), "gold"));
+    }
+
+    // 按 3:1 的权重分配批次名额
+    let batch = queue.take(8, Duration::ZERO);
+    let gold = batch.iter().filter(|r| r.tenant.as_deref() == Some("gold")).count();
+    assert_eq!(gold, 6);
+    assert_eq!(queue.len(), 6);
+}
+
+#[test]
+fn test_pending_queue_prefers_requests_near_deadline() {
+    let mut queue = PendingQueue::new(&Config::default());
+    for i in 0..4 {
+        queue.push(batch_request(&format!("relaxed-{}", i), "a"));
+    }
+    let mut urgent = batch_request("urgent", "a");
+    urgent.deadline = urgent.submitted_at + Duration::from_millis(20);
+    queue.push(urgent);
+
+    let batch = queue.take(1, Duration::from_millis(100));
+    assert_eq!(batch[0].request_id, "urgent");
+}
+
+#[test]
+fn test_pending_queue_counts_multiple_completions() {
+    let mut queue = PendingQueue::new(&Config::default());
+    for i in 0..3 {
+        let mut wide = batch_request(&format!("wide-{}", i), "a");
+        wide.parameters.n = Some(3);
+        queue.push(wide);
+    }
+    queue.push(batch_request("single", "a"));
+    assert_eq!(queue.compute_units(), 10);
+
+    // 3个候选占用3个批次名额，候选数不同的请求不进入同一批次
+    let batch = queue.take(8, Duration::ZERO);
+    assert_eq!(batch.len(), 2);
+    assert_eq!(batch[0].request_id, "wide-0");
+    assert_eq!(queue.take(8, Duration::ZERO)[0].request_id, "wide-2");
+    assert_eq!(queue.take(8, Duration::ZERO)[0].request_id, "single");
+
+    // 超出容量的请求仍会单独取出
+    let mut beams = batch_request("beams", "a");
+    beams.parameters.num_beams = Some(8);
+    let mut queue = PendingQueue::new(&Config::default());
+    queue.push(beams);
+    assert_eq!(queue.take(4, Duration::ZERO).len(), 1);
+}
+
+#[test]
+fn test_pending_queue_groups_by_adapter() {
+    let adapters = [Some("sql"), None, Some("chat"), Some("sql"), None];
+    let fill = |queue: &mut PendingQueue| {
+        for (i, adapter) in adapters.iter().enumerate() {
+            let mut request = batch_request(&format!("r{}", i), "a");
+            request.parameters.adapter = adapter.map(str::to_string);
+            queue.push(request);
+        }
+    };
+
+    // 不能混用适配器时，每批只包含与队首相同适配器的请求
+    let mut queue = PendingQueue::new(&Config::default());
+    fill(&mut queue);
+    let ids = |batch: Vec<BatchRequest>| batch.into_iter().map(|r| r.request_id).collect::<Vec<_>>();
+    assert_eq!(ids(queue.take_single_adapter(8, Duration::ZERO)), vec!["r0", "r3"]);
+    assert_eq!(ids(queue.take_single_adapter(8, Duration::ZERO)), vec!["r1", "r4"]);
+    assert_eq!(ids(queue.take_single_adapter(8, Duration::ZERO)), vec!["r2"]);
+    assert!(queue.is_empty());
+
+    // 支持多LoRA的后端一次取出全部请求
+    let mut queue = PendingQueue::new(&Config::default());
+    fill(&mut queue);
+    assert_eq!(queue.take(8, Duration::ZERO).len(), 5);
+}
+
+#[test]
+fn test_pending_queue_groups_by_parameters() {
+    let mut queue = PendingQueue::new(&Config::default());
+    for (i, max_tokens) in [Some(16), Some(64), Some(16), None].into_iter().enumerate() {
+        let mut request = batch_request(&format!("r{}", i), "a");
+        request.parameters.max_tokens = max_tokens;
+        // 只影响排队的参数不拆分批次
+        request.parameters.timeout_ms = Some(1000 * i as u64);
+        queue.push(request);
+    }
+
+    // 后端整批共用一组参数，参数不同的请求进入不同批次
+    let ids = |batch: Vec<BatchRequest>| batch.into_iter().map(|r| r.request_id).collect::<Vec<_>>();
+    assert_eq!(ids(queue.take(8, Duration::ZERO)), vec!["r0", "r2"]);
+    assert_eq!(ids(queue.take(8, Duration::ZERO)), vec!["r1"]);
+    assert_eq!(ids(queue.take(8, Duration::ZERO)), vec!["r3"]);
+    assert!(queue.is_empty());
+}
+
+#[tokio::test]
+async fn test_batch_processor_reports_measured_metrics() {
+    let mut config = Config::default();
+    config.plugins.plugin_configs.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 30 } }),
+    );
+    let processor = BatchProcessor::new(&config).await.unwrap();
+    processor.start().await.unwrap();
+
+    let response = processor
+        .submit_request("m1".to_string(), InputData::Text("a".to_string()), PredictionParameters::default(), None)
+        .await
+        .unwrap();
+    let metrics = &response.metrics;
+    assert!(metrics.inference_latency_ms >= 30, "{:?}", metrics);
+    assert!(metrics.total_latency_ms >= metrics.inference_latency_ms + metrics.queue_wait_ms);
+    assert_eq!(metrics.preprocessing_ms, 0);
+    assert_eq!((metrics.gpu_utilization, metrics.memory_usage_mb), (None, None));
+}
+
+#[tokio::test]
+async fn test_usage_store_meters_measured_latency_and_prunes_old_buckets() {
+    use unimodel::infrastructure::storage::{UsageGroupBy, UsageStore};
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("usage.json");
+    let mut config = Config::default();
+    config.plugins.plugin_configs.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 30 } }),
+    );
+    let processor = BatchProcessor::new(&config).await.unwrap();
+    processor.start().await.unwrap();
+    let response = processor
+        .submit_request("m1".to_string(), InputData::Text("a".to_string()), PredictionParameters::default(), None)
+        .await
+        .unwrap();
+
+    let store = UsageStore::open(&path, 30).await.unwrap();
+    let mut metrics = response.metrics.clone();
+    metrics.tokens_input = Some(4);
+    metrics.tokens_generated = Some(6);
+    store.record(Some("acme"), &response.model_id, &metrics).await;
+    store.record(None, &response.model_id, &metrics).await;
+
+    // GPU秒数来自实测的推理耗时
+    let now = chrono::Utc::now();
+    let from = now - chrono::Duration::days(1);
+    let to = now + chrono::Duration::days(1);
+    let rows = store.query(from, to, Some("acme"), &[UsageGroupBy::Tenant]).await;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].tenant.as_deref(), Some("acme"));
+    assert_eq!((rows[0].usage.requests, rows[0].usage.input_tokens, rows[0].usage.output_tokens), (1, 4, 6));
+    assert!(rows[0].usage.gpu_seconds >= 0.03, "{:?}", rows[0].usage);
+    assert_eq!(store.query(from, to, None, &[]).await[0].usage.requests, 2);
+
+    // 超过保留期的分桶在落盘时删除
+    let mut old = metrics.clone();
+    old.end_time = now - chrono::Duration::days(45);
+    store.record(Some("acme"), &response.model_id, &old).await;
+    assert_eq!(store.query(now - chrono::Duration::days(60), to, None, &[]).await[0].usage.requests, 3);
+    store.flush().await.unwrap();
+
+    let reopened = UsageStore::open(&path, 30).await.unwrap();
+    let rows = reopened.query(now - chrono::Duration::days(60), to, None, &[UsageGroupBy::Day]).await;
+    assert_eq!(rows.len(), 1);
+    assert_eq!(rows[0].usage.requests, 2);
+
+    // 保留期为0时永久保留
+    let forever = UsageStore::open(&path, 0).await.unwrap();
+    assert_eq!(forever.prune(now + chrono::Duration::days(1000)).await, 0);
+    assert_eq!(reopened.prune(now + chrono::Duration::days(31)).await, 2);
+}
+
+#[test]
+fn test_batch_tuner_tracks_latency_slo() {
+    let config = BatchConfig {
+        max_batch_size: 16,
+        max_wait_time_ms: 20,
+        latency_slo_ms: Some(100),
+        ..Default::default()
+    };
+    assert!(BatchTuner::new(&BatchConfig::default()).is_none());
+
+    let mut tuner = BatchTuner::new(&config).unwrap();
+    assert_eq!(tuner.batch_size(), 16);
+
+    // 超出SLO时收缩
+    for _ in 0..20 {
+        tuner.observe(Duration::from_millis(250));
+    }
+    assert_eq!(tuner.batch_size(), 12);
+    assert!(tuner.max_wait_time() < Duration::from_millis(20));
+
+    // 延迟回落后逐步增长，但不超过配置上限
+    for _ in 0..400 {
+        tuner.observe(Duration::from_millis(10));
+    }
+    assert_eq!(tuner.batch_size(), 16);
+    assert_eq!(tuner.max_wait_time(), Duration::from_millis(20));
+}
+
+fn gpu_model_config(memory_limit_mb: u64) -> ModelConfig {
+    ModelConfig {
+        model_path: "/path/to/model.onnx".to_string(),
+        config_path: None,
+        tokenizer_path: None,
+        backend: "onnx".to_string(),
+        device: DeviceConfig {
+            device_type: DeviceType::CUDA,
+            device_ids: vec![],
+            memory_limit_mb: Some(memory_limit_mb),
+            mixed_precision: false,
+            parallelism: Parallelism::None,
+            micro_batches: None,
+        },
+        optimization: OptimizationConfig {
+            kv_cache: false,
+            quantization: None,
+            graph_optimization: true,
+            inference_parallelism: 1,
+            memory_optimization: MemoryOptimization::Low,
+            cuda_graphs: false,
+            cuda_streams: 1,
+        },
+        batch_config: BatchConfig::default(),
+        max_concurrent_batches: None,
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
+        custom_params: HashMap::new(),
+    }
+}
+
+#[tokio::test]
+async fn test_scheduler_places_models_by_free_memory() {
+    let mut config = Config::default();
+    config.engine.gpu.device_ids = vec![0, 1];
+    config.engine.gpu.device_memory_mb = Some(10000);
+    config.engine.gpu.memory_fraction = 0.8;
+
+    let scheduler = Scheduler::new(&config).await.unwrap();
+    let model_config = gpu_model_config(6000);
+
+    // 两个模型分别放到两张卡上
+    let first = scheduler.place(&"m1".to_string(), &model_config).unwrap();
+    let second = scheduler.place(&"m2".to_string(), &model_config).unwrap();
+    assert_ne!(first, second);
+
+    // 每张卡只剩2000MB可用，第三个模型被拒绝
+    assert!(scheduler.place(&"m3".to_string(), &model_config).is_err());
+
+    scheduler.release(&"m1".to_string());
+    assert_eq!(scheduler.place(&"m3".to_string(), &model_config).unwrap(), first);
+}
+
+#[tokio::test]
+async fn test_scheduler_places_tensor_parallel_shards() {
+    let mut config = Config::default();
+    config.engine.gpu.device_ids = vec![0, 1, 2, 3];
+    config.engine.gpu.device_memory_mb = Some(10000);
+    config.engine.gpu.memory_fraction = 0.8;
+
+    let scheduler = Scheduler::new(&config).await.unwrap();
+
+    // 单卡放不下的模型切分到4张卡上，每张卡预留四分之一
+    let mut model_config = gpu_model_config(20000);
+    assert!(scheduler.place(&"single".to_string(), &model_config).is_err());
+    model_config.device.device_ids = vec![0, 1, 2, 3];
+    model_config.device.parallelism = Parallelism::Tensor;
+    assert_eq!(scheduler.place(&"large".to_string(), &model_config).unwrap(), vec![0, 1, 2, 3]);
+    assert!(scheduler.device_status().iter().all(|d| d.reserved_memory_mb == 5000));
+
+    // 加载前按分片追加预留
+    model_config.custom_params.insert(MEMORY_FOOTPRINT_PARAM.to_string(), serde_json::json!(24000));
+    scheduler.admit_load(&"large".to_string(), &model_config).unwrap();
+    assert!(scheduler.device_status().iter().all(|d| d.reserved_memory_mb == 6000));
+
+    // 任一张卡放不下分片时整体拒绝，不留下部分预留
+    let mut second = gpu_model_config(6000);
+    second.device.device_ids = vec![1, 2];
+    second.device.parallelism = Parallelism::Tensor;
+    assert!(scheduler.place(&"second".to_string(), &second).is_err());
+    assert!(scheduler.device_status().iter().all(|d| d.models.len() == 1));
+
+    // 列出的设备不存在
+    second.device.device_ids = vec![3, 4];
+    assert!(scheduler.place(&"second".to_string(), &second).is_err());
+
+    scheduler.release(&"large".to_string());
+    assert!(scheduler.device_status().iter().all(|d| d.reserved_memory_mb == 0));
+
+    // 副本在每张卡上预留完整显存
+    let mut replicated = gpu_model_config(3000);
+    replicated.device.device_ids = vec![1, 3];
+    replicated.device.parallelism = Parallelism::Replicas;
+    assert_eq!(scheduler.place(&"replicated".to_string(), &replicated).unwrap(), vec![1, 3]);
+    let reserved: Vec<u64> = scheduler.device_status().iter().map(|d| d.reserved_memory_mb).collect();
+    assert_eq!(reserved, vec![0, 3000, 0, 3000]);
+}
+
+#[tokio::test]
+async fn test_scheduler_places_on_host_accelerator() {
+    let mut config = Config::default();
+    config.engine.gpu.accelerator = DeviceType::ROCm;
+    config.engine.gpu.device_ids = vec![0, 1];
+    config.engine.gpu.device_memory_mb = Some(10000);
+    config.engine.gpu.memory_fraction = 0.8;
+
+    let scheduler = Scheduler::new(&config).await.unwrap();
+    assert!(scheduler.device_status().iter().all(|d| d.device_type == DeviceType::ROCm));
+
+    // ROCm模型按显存放置
+    let mut model_config = gpu_model_config(6000);
+    model_config.device.device_type = DeviceType::ROCm;
+    scheduler.place(&"rocm".to_string(), &model_config).unwrap();
+    scheduler.admit_load(&"rocm".to_string(), &model_config).unwrap();
+    assert_eq!(scheduler.device_status().iter().map(|d| d.reserved_memory_mb).sum::<u64>(), 6000);
+
+    // 本机没有的加速器无法放置，CPU模型不占用设备
+    model_config.device.device_type = DeviceType::CUDA;
+    assert!(matches!(
+        scheduler.place(&"cuda".to_string(), &model_config),
+        Err(UniModelError::Scheduling(_))
+    ));
+    model_config.device.device_type = DeviceType::NPU;
+    assert!(scheduler.place(&"npu".to_string(), &model_config).is_err());
+    model_config.device.device_type = DeviceType::CPU;
+    assert!(scheduler.place(&"cpu".to_string(), &model_config).unwrap().is_empty());
+}
+
+fn replica(id: &str) -> ModelInstance {
+    ModelInstance {
+        id: id.to_string(),
+        plugin_id: "mock".to_string(),
+        handle: 0,
+        supports_batching: true,
+        max_batch_size: 8,
+        supports_multi_lora: false,
+        precision: Precision::FP32,
+    }
+}
+
+#[test]
+fn test_replica_balancer_prefers_shallowest_queue() {
+    let balancer = ReplicaBalancer::new();
+    let mut model = Model::new("m1".to_string(), "m1".to_string(), ModelType::CV, gpu_model_config(1000));
+    assert!(balancer.acquire(model.instances()).is_none());
+
+    model.instance = Some(replica("gpu0"));
+    model.replicas = vec![replica("gpu1"), replica("gpu2")];
+
+    // 深度相同时按顺序选择，之后总是选在途批次最少的副本
+    let first = balancer.acquire(model.instances()).unwrap();
+    let second = balancer.acquire(model.instances()).unwrap();
+    let third = balancer.acquire(model.instances()).unwrap();
+    assert_eq!(
+        [first.instance().id.as_str(), second.instance().id.as_str(), third.instance().id.as_str()],
+        ["gpu0", "gpu1", "gpu2"]
+    );
+    let fourth = balancer.acquire(model.instances()).unwrap();
+    assert_eq!(fourth.instance().id, "gpu0");
+    assert_eq!(balancer.depth("gpu0"), 2);
+
+    // 批次完成后归还
+    drop(second);
+    assert_eq!(balancer.depth("gpu1"), 0);
+    assert_eq!(balancer.acquire(model.instances()).unwrap().instance().id, "gpu1");
+
+    drop((first, third, fourth));
+    assert_eq!(balancer.depth("gpu0"), 0);
+}
+
+#[test]
+fn test_estimate_model_memory() {
+    let dir = tempfile::tempdir().unwrap();
+    let model_path = dir.path().join("model.onnx");
+    std::fs::write(&model_path, vec![0u8; 3 * 1024 * 1024]).unwrap();
+
+    let mut config = gpu_model_config(1000);
+    config.model_path = model_path.to_string_lossy().to_string();
+    // 3MB × onnx系数1.1
+    assert_eq!(estimate_memory_mb(&config), Some(4));
+
+    // 目录按所有文件求和
+    config.model_path = dir.path().to_string_lossy().to_string();
+    assert_eq!(estimate_memory_mb(&config), Some(4));
+
+    config.custom_params.insert(MEMORY_FOOTPRINT_PARAM.to_string(), serde_json::json!(2048));
+    assert_eq!(estimate_memory_mb(&config), Some(2048));
+
+    config.model_path = "/nonexistent/model.onnx".to_string();
+    config.custom_params.clear();
+    assert_eq!(estimate_memory_mb(&config), None);
+}
+
+#[tokio::test]
+async fn test_scheduler_admission_rejects_oversized_model() {
+    let mut config = Config::default();
+    config.engine.gpu.device_ids = vec![0];
+    config.engine.gpu.device_memory_mb = Some(10000);
+    config.engine.gpu.memory_fraction = 0.8;
+
+    let scheduler = Scheduler::new(&config).await.unwrap();
+    let model_id = "m1".to_string();
+    let mut model_config = gpu_model_config(2000);
+    scheduler.place(&model_id, &model_config).unwrap();
+
+    // 估算占用在剩余显存之内时追加预留
+    model_config.custom_params.insert(MEMORY_FOOTPRINT_PARAM.to_string(), serde_json::json!(6000));
+    scheduler.admit_load(&model_id, &model_config).unwrap();
+    assert_eq!(scheduler.device_status()[0].reserved_memory_mb, 6000);
+
+    // 超出可用显存（8000MB）时拒绝
+    model_config.custom_params.insert(MEMORY_FOOTPRINT_PARAM.to_string(), serde_json::json!(9000));
+    let err = scheduler.admit_load(&model_id, &model_config).unwrap_err();
+    assert!(matches!(err, UniModelError::Resource(_)));
+}
+
+#[test]
+fn test_event_log_keeps_latest_events() {
+    let log = EventLog::new(3);
+    let models: Vec<Model> = (0..2)
+        .map(|i| {
+            let mut config = gpu_model_config(1000);
+            config.device.device_type = DeviceType::CPU;
+            Model::new(format!("m{}", i), format!("model-{}", i), ModelType::CV, config)
+        })
+        .collect();
+
+    log.record(ModelEvent::new(&models[0].info, ModelEventKind::Registered, None));
+    log.record(ModelEvent::new(&models[1].info, ModelEventKind::Registered, None));
+    log.record(ModelEvent::new(&models[0].info, ModelEventKind::Loaded, None));
+    log.record(ModelEvent::new(&models[0].info, ModelEventKind::Evicted, Some("idle timeout".to_string())));
+
+    // 容量为3，最早的事件被丢弃，最新的在前
+    let all = log.list(None, 10);
+    assert_eq!(all.len(), 3);
+    assert_eq!(all[0].kind, ModelEventKind::Evicted);
+    assert_eq!(all[0].message.as_deref(), Some("idle timeout"));
+
+    let m0 = log.list(Some("m0"), 10);
+    assert_eq!(m0.len(), 2);
+    assert!(m0.iter().all(|e| e.model_id == "m0"));
+    assert_eq!(log.list(None, 1).len(), 1);
+}
+
+/// 记录分配次数的假分配器
+#[derive(Debug, Default)]
+struct CountingAllocator {
+    next_ptr: Mutex<u64>,
+    allocated: Mutex<u64>,
+    freed: Mutex<u64>,
+}
+
+impl DeviceAllocator for CountingAllocator {
+    fn allocate(&self, _device_id: u32, bytes: usize) -> unimodel::common::error::Result<u64> {
+        let mut next_ptr = self.next_ptr.lock();
+        let ptr = *next_ptr;
+        *next_ptr += bytes as u64;
+        *self.allocated.lock() += 1;
+        Ok(ptr)
+    }
+
+    fn free(&self, _device_id: u32, _ptr: u64, _bytes: usize) {
+        *self.freed.lock() += 1;
+    }
+}
+
+#[test]
+fn test_device_memory_pool_reuses_blocks() {
+    let mut config = Config::default();
+    config.engine.gpu.enable_pooling = true;
+    config.engine.gpu.device_memory_mb = Some(10);
+    config.engine.gpu.memory_fraction = 0.8;
+
+    let allocator = Arc::new(CountingAllocator::default());
+    let pool = Arc::new(DeviceMemoryPool::new(&config.engine.gpu, allocator.clone()));
+
+    // 同一尺寸级别的块被复用
+    let buffer = pool.allocate(0, 1000).unwrap();
+    assert_eq!(buffer.len(), 1000);
+    drop(buffer);
+    let buffer = pool.allocate(0, 900).unwrap();
+    assert_eq!(*allocator.allocated.lock(), 1);
+
+    let stats = &pool.stats()[0];
+    assert_eq!(stats.reserved_bytes, 1024);
+    assert_eq!(stats.in_use_bytes, 1024);
+    assert_eq!(stats.reuses, 1);
+    assert!((stats.fragmentation - (1.0 - 900.0 / 1024.0)).abs() < 1e-9);
+    drop(buffer);
+
+    // 超过可用显存（8MB）时先释放缓存块，仍不足则报显存不足
+    let large = pool.allocate(0, 6 * 1024 * 1024).unwrap();
+    assert_eq!(*allocator.freed.lock(), 0);
+    let err = pool.allocate(0, 4 * 1024 * 1024).unwrap_err();
+    assert!(matches!(err, UniModelError::OutOfMemory(_)));
+    assert_eq!(*allocator.freed.lock(), 1);
+    drop(large);
+
+    let stats = &pool.stats()[0];
+    assert_eq!(stats.in_use_bytes, 0);
+    assert_eq!(stats.peak_in_use_bytes, 6 * 1024 * 1024);
+    pool.empty_cache();
+    assert_eq!(pool.stats()[0].reserved_bytes, 0);
+}
+
+#[test]
+fn test_device_memory_pool_passthrough_when_disabled() {
+    let mut config = Config::default();
+    config.engine.gpu.enable_pooling = false;
+
+    let allocator = Arc::new(CountingAllocator::default());
+    let pool = Arc::new(DeviceMemoryPool::new(&config.engine.gpu, allocator.clone()));
+
+    drop(pool.allocate(1, 1000).unwrap());
+    drop(pool.allocate(1, 1000).unwrap());
+    assert_eq!(*allocator.allocated.lock(), 2);
+    assert_eq!(*allocator.freed.lock(), 2);
+    assert_eq!(pool.stats()[0].reserved_bytes, 0);
+}
+
+/// 记录锁页次数的假注册器
+#[derive(Debug, Default)]
+struct CountingRegistrar {
+    registered: Mutex<u64>,
+    unregistered: Mutex<u64>,
+    fail: bool,
+}
+
+impl HostMemoryRegistrar for CountingRegistrar {
+    fn register(&self, _ptr: u64, _bytes: usize) -> unimodel::common::error::Result<()> {
+        if self.fail {
+            return Err(UniModelError::resource("cudaHostRegister failed"));
+        }
+        *self.registered.lock() += 1;
+        Ok(())
+    }
+
+    fn unregister(&self, _ptr: u64, _bytes: usize) {
+        *self.unregistered.lock() += 1;
+    }
+}
+
+#[test]
+fn test_pinned_memory_pool() {
+    let config = PinnedMemoryConfig { enabled: true, max_pool_mb: 2 };
+    let registrar = Arc::new(CountingRegistrar::default());
+    let pool = Arc::new(PinnedMemoryPool::new(&config, registrar.clone()));
+
+    // 同一尺寸级别的缓冲区被复用，不再重复锁页
+    let mut buffer = pool.acquire_on_node(None, 1000).unwrap();
+    assert!(buffer.is_pinned());
+    assert_eq!(buffer.len(), 1000);
+    buffer[999] = 1;
+    drop(buffer);
+    let buffer = pool.acquire_on_node(None, 900).unwrap();
+    assert_eq!(*registrar.registered.lock(), 1);
+    let stats = &pool.stats()[0];
+    assert_eq!((stats.reserved_bytes, stats.in_use_bytes, stats.reuses), (1024, 1024, 1));
+
+    // 超过上限时先解锁缓存的缓冲区，仍不足则退化为可分页内存
+    let large = pool.acquire_on_node(None, 1024 * 1024).unwrap();
+    assert!(large.is_pinned());
+    let fallback = pool.acquire_on_node(None, 1024 * 1024).unwrap();
+    assert!(!fallback.is_pinned());
+    assert_eq!(pool.pageable_fallbacks(), 1);
+    drop((buffer, large, fallback));
+    assert_eq!(pool.stats()[0].in_use_bytes, 0);
+    pool.empty_cache();
+    assert_eq!(*registrar.unregistered.lock(), 2);
+    assert_eq!(pool.stats()[0].reserved_bytes, 0);
+
+    // 锁页失败或未启用时同样使用可分页内存
+    let failing = Arc::new(PinnedMemoryPool::new(&config, Arc::new(CountingRegistrar { fail: true, ..Default::default() })));
+    assert!(!failing.acquire_on_node(None, 1000).unwrap().is_pinned());
+    let disabled = Arc::new(PinnedMemoryPool::new(&PinnedMemoryConfig { enabled: false, max_pool_mb: 1 }, registrar.clone()));
+    assert!(!disabled.acquire(0, 1000).unwrap().is_pinned());
+    assert!(pool.acquire_on_node(None, 0).is_err());
+}
+
+#[test]
+fn test_paged_kv_cache_allocates_blocks() {
+    let allocator = Arc::new(CountingAllocator::default());
+    let pool = Arc::new(DeviceMemoryPool::new(&Config::default().engine.gpu, allocator.clone()));
+    let cache = PagedKvCache::new(&pool, PagedKvConfig {
+        device_id: 0,
+        block_tokens: 4,
+        bytes_per_token: 256,
+        num_blocks: 4,
+    })
+    .unwrap();
+    assert_eq!(*allocator.allocated.lock(), 1);
+
+    // 6个token占2块，追加到9个token时再分配1块
+    cache.allocate("a", 6).unwrap();
+    assert_eq!(cache.block_table("a").unwrap(), vec![0, 1]);
+    assert!(cache.append("a", 3).unwrap().is_empty());
+    assert_eq!(cache.block_table("a").unwrap(), vec![0, 1, 2]);
+    assert_eq!(cache.block_address(2), cache.block_address(0) + 2 * 1024);
+
+    // 分叉共享全部块，写入共享的最后一块时先复制
+    cache.fork("a", "b").unwrap();
+    assert_eq!(cache.stats().free_blocks, 1);
+    assert_eq!(cache.append("b", 1).unwrap(), vec![BlockCopy { src: 2, dst: 3 }]);
+    assert_eq!(cache.block_table("b").unwrap(), vec![0, 1, 3]);
+
+    let err = cache.allocate("c", 1).unwrap_err();
+    assert!(matches!(err, UniModelError::OutOfMemory(_)));
+    cache.free("a");
+    assert_eq!(cache.stats().free_blocks, 1);
+    cache.free("b");
+    assert_eq!(cache.stats().free_blocks, 4);
+    assert!(cache.can_allocate(16));
+}
+
+/// 逐字符输出固定文本的解码器
+#[derive(Debug)]
+struct ScriptDecoder {
+    text: &'static str,
+    positions: Mutex<HashMap<String, usize>>,
+}
+
+impl IterativeDecoder for ScriptDecoder {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        _prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        self.positions.lock().insert(sequence_id.to_string(), 0);
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> unimodel::Result<Vec<DecodeStep>> {
+        let mut positions = self.positions.lock();
+        Ok(sequence_ids
+            .iter()
+            .map(|id| {
+                let position = positions.get_mut(id).unwrap();
+                let token = &self.text[*position..*position + 1];
+                *position += 1;
+                decode_step(id, token, *position == self.text.len())
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.positions.lock().remove(sequence_id);
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_batching_stop_sequences() {
+    let processor = continuous_processor(Arc::new(ScriptDecoder {
+        text: "Hello\n\nWorld",
+        positions: Mutex::new(HashMap::new()),
+    }))
+    .await;
+
+    // 停止序列跨越两个token
+    let parameters = PredictionParameters {
+        stop: vec!["\n\n".to_string(), "xyz".to_string()],
+        ..Default::default()
+    };
+    let response = processor
+        .submit_request("llm".to_string(), InputData::Text("hi".to_string()), parameters, None)
+        .await
+        .unwrap();
+    assert!(matches!(response.output, OutputData::Text(ref t) if t == "Hello"));
+    assert_eq!(response.metadata.custom_metadata["finish_reason"], "stop");
+    assert_eq!(response.metadata.custom_metadata["stop_sequence"], "\n\n");
+    assert_eq!(response.metrics.tokens_generated, Some(7));
+}
+
+#[test]
+fn test_stop_sequence_scanning() {
+    use unimodel::domain::service::generation::{find_stop_sequence, truncate_at_stop};
+
+    let stop = vec!["END".to_string(), "。".to_string()];
+    assert_eq!(find_stop_sequence("abc。def END", &stop, 0), Some((3, "。")));
+    // 起点落在多字节字符中间时回退到字符边界
+    assert_eq!(find_stop_sequence("abc。def", &stop, 4), Some((3, "。")));
+    assert_eq!(find_stop_sequence("abc", &stop, 0), None);
+
+    let mut text = "Processed: done END trailing".to_string();
+    assert_eq!(truncate_at_stop(&mut text, &stop).as_deref(), Some("END"));
+    assert_eq!(text, "Processed: done ");
+    assert_eq!(truncate_at_stop(&mut text, &[]), None);
+}
+
+#[tokio::test]
+async fn test_logit_bias_requires_backend_support() {
+    use unimodel::domain::service::generation::apply_logit_bias;
+
+    let processor = continuous_processor(Arc::new(CountdownDecoder::default())).await;
+
+    let parameters = PredictionParameters {
+        logit_bias: HashMap::from([(7, -100.0)]),
+        ..Default::default()
+    };
+    let result = processor
+        .submit_request("llm".to_string(), InputData::Text("ab".to_string()), parameters, None)
+        .await;
+    assert!(matches!(result, Err(UniModelError::Validation(_))));
+
+    let mut logits = vec![0.5, 1.0, 2.0];
+    apply_logit_bias(&mut logits, &HashMap::from([(0, 1.5), (1, -100.0), (9, 5.0)]));
+    assert_eq!(logits[0], 2.0);
+    assert_eq!(logits[1], f32::NEG_INFINITY);
+    assert_eq!(logits[2], 2.0);
+}
+
+/// 按固定logits生成token并返回对数概率的解码器
+#[derive(Debug, Default)]
+struct LogprobDecoder {
+    remaining: Mutex<HashMap<String, (usize, usize)>>,
+}
+
+impl IterativeDecoder for LogprobDecoder {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        _prompt: &str,
+        parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        let top = parameters.top_logprobs.unwrap_or(0) as usize;
+        self.remaining.lock().insert(sequence_id.to_string(), (2, top));
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> unimodel::Result<Vec<DecodeStep>> {
+        use unimodel::domain::service::generation::token_logprob;
+
+        let mut remaining = self.remaining.lock();
+        Ok(sequence_ids
+            .iter()
+            .map(|id| {
+                let (left, top) = remaining.get_mut(id).unwrap();
+                *left -= 1;
+                let logprob = token_logprob(&[0.0, 2.0, 1.0], 1, *top, |t| format!("t{}", t));
+                DecodeStep {
+                    logprob: Some(logprob.clone()),
+                    ..decode_step(id, logprob.token, *left == 0)
+                }
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.remaining.lock().remove(sequence_id);
+    }
+
+    fn supports_logprobs(&self) -> bool {
+        true
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_batching_returns_logprobs() {
+    let processor = continuous_processor(Arc::new(LogprobDecoder::default())).await;
+    processor.register_decoder(&"plain".to_string(), Arc::new(CountdownDecoder::default()));
+
+    let parameters = PredictionParameters {
+        logprobs: true,
+        top_logprobs: Some(2),
+        ..Default::default()
+    };
+    let response = processor
+        .submit_request("llm".to_string(), InputData::Text("hi".to_string()), parameters.clone(), None)
+        .await
+        .unwrap();
+    let logprobs: Vec<TokenLogprob> =
+        serde_json::from_value(response.metadata.custom_metadata["logprobs"].clone()).unwrap();
+    assert_eq!(logprobs.len(), 2);
+    assert_eq!(logprobs[0].token, "t1");
+    assert!((logprobs[0].logprob - (-0.4076)).abs() < 1e-3);
+    assert_eq!(logprobs[0].top_logprobs.len(), 2);
+    assert_eq!(logprobs[0].top_logprobs[1].token, "t2");
+
+    // 不支持对数概率的后端拒绝请求
+    let result = processor
+        .submit_request("plain".to_string(), InputData::Text("hi".to_string()), parameters, None)
+        .await;
+    assert!(matches!(result, Err(UniModelError::Validation(_))));
+}
+
+/// 结束时返回多个候选的解码器
+#[derive(Debug, Default)]
+struct CandidateDecoder;
+
+impl IterativeDecoder for CandidateDecoder {
+    fn add_sequence(
+        &self,
+        _sequence_id: &str,
+        _prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> unimodel::Result<Vec<DecodeStep>> {
+        Ok(sequence_ids
+            .iter()
+            .map(|id| DecodeStep {
+                candidates: vec!["second. extra".to_string(), "third".to_string()],
+                ..decode_step(id, "best", true)
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, _sequence_id: &str) {}
+
+    fn supports_multiple_completions(&self) -> bool {
+        true
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_batching_returns_multiple_completions() {
+    let processor = continuous_processor(Arc::new(CandidateDecoder)).await;
+    processor.register_decoder(&"plain".to_string(), Arc::new(CountdownDecoder::default()));
+
+    let parameters = PredictionParameters {
+        n: Some(2),
+        stop: vec![".".to_string()],
+        ..Default::default()
+    };
+    let response = processor
+        .submit_request("llm".to_string(), InputData::Text("hi".to_string()), parameters.clone(), None)
+        .await
+        .unwrap();
+    match response.output {
+        OutputData::Json(json) => assert_eq!(
+            json,
+            serde_json::json!({ "choices": [
+                { "index": 0, "text": "best" },
+                { "index": 1, "text": "second" },
+            ]})
+        ),
+        other => panic!("unexpected output: {:?}", other),
+    }
+
+    // 不支持多候选的后端拒绝请求
+    let result = processor
+        .submit_request("plain".to_string(), InputData::Text("hi".to_string()), parameters, None)
+        .await;
+    assert!(matches!(result, Err(UniModelError::Validation(_))));
+}
+
+#[test]
+fn test_grammar_constrains_output() {
+    use unimodel::domain::service::grammar::{apply_grammar_mask, Grammar, GrammarMatcher};
+
+    let grammar = Grammar::parse(
+        r#"
+        # 简单的SELECT语句
+        root   ::= "SELECT " column (", " column)* " FROM " ident
+        column ::= ident | "*"
+        ident  ::= [a-z_] [a-z0-9_]*
+        "#,
+    )
+    .unwrap();
+    let mut matcher = GrammarMatcher::new(Arc::new(grammar));
+    assert!(matcher.accept("SELECT id, "));
+    assert!(!matcher.accept("FROM"));
+    assert!(matcher.accept("name FROM users"));
+    assert!(matcher.is_complete());
+
+    // 未完整匹配时屏蔽结束token和不合语法的token
+    let grammar = Arc::new(Grammar::parse(r#"root ::= "yes" | "no""#).unwrap());
+    let tokens = ["ye", "no", "maybe", ""];
+    let mut logits = vec![0.0; 5];
+    let token_text = |t: u32| tokens.get(t as usize).map_or(String::new(), |s| s.to_string());
+    apply_grammar_mask(&mut logits, &GrammarMatcher::new(grammar), token_text, Some(4));
+    assert_eq!(&logits[..2], &[0.0, 0.0]);
+    assert!(logits[2..].iter().all(|l| *l == f32::NEG_INFINITY));
+
+    assert!(matches!(Grammar::parse(r#"root ::= expr"#), Err(UniModelError::Validation(_))));
+    assert!(matches!(Grammar::parse(r#"root ::= root "a" | "a""#), Err(UniModelError::Validation(_))));
+    assert!(matches!(Grammar::parse(r#"item ::= "a""#), Err(UniModelError::Validation(_))));
+}
+
+#[test]
+fn test_json_schema_compiles_to_grammar() {
+    use unimodel::domain::service::grammar::{Grammar, GrammarMatcher};
+    use unimodel::domain::service::json_schema::{schema_to_grammar, validate_json};
+
+    let schema = serde_json::json!({
+        "type": "object",
+        "properties": {
+            "name": { "type": "string" },
+            "tags": { "type": "array", "items": { "$ref": "#/$defs/tag" } },
+            "score": { "type": ["number", "null"] },
+        },
+        "required": ["name", "score"],
+        "$defs": { "tag": { "enum": ["a", "b"] } },
+    });
+    let grammar = Arc::new(Grammar::parse(&schema_to_grammar(&schema).unwrap()).unwrap());
+
+    let accepts = |text: &str| {
+        let mut matcher = GrammarMatcher::new(Arc::clone(&grammar));
+        matcher.accept(text) && matcher.is_complete()
+    };
+    assert!(accepts(r#"{"name": "x", "score": 1.5, "tags": ["a", "b"]}"#));
+    assert!(accepts(r#"{"name":"x","score":null}"#));
+    assert!(!accepts(r#"{"name": "x"}"#));
+    assert!(!accepts(r#"{"name": "x", "score": 1, "tags": ["c"]}"#));
+
+    let valid = serde_json::json!({ "tags": ["b"], "name": "x", "score": null });
+    assert!(validate_json(&valid, &schema).is_ok());
+    let invalid = serde_json::json!({ "name": 1, "score": 2 });
+    assert!(matches!(validate_json(&invalid, &schema), Err(UniModelError::Model(_))));
+}
+
+/// 永不结束的解码器，记录仍在解码的序列
+#[derive(Debug, Default)]
+struct EndlessDecoder {
+    active: Mutex<HashSet<String>>,
+}
+
+impl IterativeDecoder for EndlessDecoder {
+    fn add_sequence(
+        &self,
+        sequence_id: &str,
+        _prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        self.active.lock().insert(sequence_id.to_string());
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> unimodel::Result<Vec<DecodeStep>> {
+        std::thread::sleep(Duration::from_millis(5));
+        Ok(sequence_ids
+            .iter()
+            .map(|id| decode_step(id, "x", false))
+            .collect())
+    }
+
+    fn remove_sequence(&self, sequence_id: &str) {
+        self.active.lock().remove(sequence_id);
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_batching_cancels_abandoned_sequences() {
+    let decoder = Arc::new(EndlessDecoder::default());
+    let processor = continuous_processor(decoder.clone()).await;
+
+    // 客户端断开时处理请求的future被丢弃
+    let submit = processor.submit_request(
+        "llm".to_string(),
+        InputData::Text("hi".to_string()),
+        PredictionParameters::default(),
+        None,
+    );
+    assert!(tokio::time::timeout(Duration::from_millis(50), submit).await.is_err());
+    assert_eq!(decoder.active.lock().len(), 1);
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    assert!(decoder.active.lock().is_empty());
+    assert_eq!(processor.get_batch_stats().await.cancelled_requests, 1);
+}
+
+#[test]
+fn test_session_table_evicts_least_recently_used() {
+    let mut sessions = SessionTable::new(Duration::from_secs(60), 2);
+    assert_eq!(sessions.touch("a"), None);
+    assert_eq!(sessions.touch("b"), None);
+    assert_eq!(sessions.touch("a"), None);
+    assert_eq!(sessions.touch("c"), Some("b".to_string()));
+    assert!(sessions.expire().is_empty());
+
+    let mut sessions = SessionTable::new(Duration::ZERO, 2);
+    sessions.touch("a");
+    assert_eq!(sessions.expire(), vec!["a".to_string()]);
+    assert!(sessions.is_empty());
+}
+
+/// 记录会话保留的解码器，每个序列生成一个token后结束
+#[derive(Debug, Default)]
+struct SessionDecoder {
+    retained: Mutex<Vec<String>>,
+}
+
+impl IterativeDecoder for SessionDecoder {
+    fn add_sequence(
+        &self,
+        _sequence_id: &str,
+        _prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        Ok(())
+    }
+
+    fn step(&self, sequence_ids: &[String]) -> unimodel::Result<Vec<DecodeStep>> {
+        Ok(sequence_ids
+            .iter()
+            .map(|id| decode_step(id, "ok", true))
+            .collect())
+    }
+
+    fn remove_sequence(&self, _sequence_id: &str) {}
+
+    fn retain_for_session(&self, _sequence_id: &str, session_id: &str) {
+        self.retained.lock().push(session_id.to_string());
+    }
+}
+
+#[tokio::test]
+async fn test_continuous_batching_retains_session_cache() {
+    let decoder = Arc::new(SessionDecoder::default());
+    let processor = continuous_processor(decoder.clone()).await;
+
+    for session_id in [Some("s1"), None, Some("s1")] {
+        let parameters = PredictionParameters {
+            session_id: session_id.map(str::to_string),
+            ..Default::default()
+        };
+        processor
+            .submit_request("llm".to_string(), InputData::Text("hi".to_string()), parameters, None)
+            .await
+            .unwrap();
+    }
+    assert_eq!(*decoder.retained.lock(), vec!["s1".to_string(), "s1".to_string()]);
+}
+
+/// 每个草稿token都是 "d"，记录每次提交的接受数
+#[derive(Debug, Default)]
+struct FakeDraft {
+    commits: Mutex<Vec<usize>>,
+}
+
+impl DraftModel for FakeDraft {
+    fn add_sequence(
+        &self,
+        _sequence_id: &str,
+        _prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        Ok(())
+    }
+
+    fn propose(&self, _sequence_id: &str, num_tokens: usize) -> unimodel::Result<Vec<DraftToken>> {
+        Ok((0..num_tokens)
+            .map(|_| DraftToken { token_id: 1, text: "d".to_string(), prob: 0.5 })
+            .collect())
+    }
+
+    fn commit(&self, _sequence_id: &str, accepted: usize, _next_token_id: u32) -> unimodel::Result<()> {
+        self.commits.lock().push(accepted);
+        Ok(())
+    }
+
+    fn remove_sequence(&self, _sequence_id: &str) {}
+}
+
+/// 每次最多接受两个草稿token，再补一个 "T"
+#[derive(Debug)]
+struct FakeTarget;
+
+impl SpeculativeTarget for FakeTarget {
+    fn add_sequence(
+        &self,
+        _sequence_id: &str,
+        _prompt: &str,
+        _parameters: &PredictionParameters,
+    ) -> unimodel::Result<()> {
+        Ok(())
+    }
+
+    fn verify(&self, drafts: &[(String, Vec<DraftToken>)]) -> unimodel::Result<Vec<Verification>> {
+        Ok(drafts
+            .iter()
+            .map(|(id, draft)| Verification {
+                sequence_id: id.clone(),
+                accepted: draft.len().min(2),
+                next_token: DraftToken { token_id: 2, text: "T".to_string(), prob: 1.0 },
+                finished: false,
+            })
+            .collect())
+    }
+
+    fn remove_sequence(&self, _sequence_id: &str) {}
+}
+
+#[tokio::test]
+async fn test_speculative_decoding_respects_max_tokens() {
+    use unimodel::domain::service::generation::{accept_draft, residual_distribution};
+
+    assert!(accept_draft(0.5, 0.8, 0.99));
+    assert!(!accept_draft(0.8, 0.4, 0.6));
+    assert_eq!(residual_distribution(&[0.5, 0.5], &[1.0, 0.0]), vec![0.0, 1.0]);
+
+    let draft = Arc::new(FakeDraft::default());
+    let decoder = Arc::new(SpeculativeDecoder::new(draft.clone(), Arc::new(FakeTarget), 4));
+    let processor = continuous_processor(decoder.clone()).await;
+
+    let parameters = PredictionParameters {
+        max_tokens: Some(7),
+        ..Default::default()
+    };
+    let response = processor
+        .submit_request("llm".to_string(), InputData::Text("hi".to_string()), parameters, None)
+        .await
+        .unwrap();
+
+    // 第一步提出4个接受2个，第二步只剩4个配额提出3个接受2个，最后一步只由目标模型生成
+    assert!(matches!(&response.output, OutputData::Text(text) if text == "ddTddTT"));
+    assert_eq!(response.metrics.tokens_generated, Some(7));
+    assert_eq!(response.metadata.custom_metadata["accepted_draft_tokens"], 4);
+    assert_eq!(response.metadata.custom_metadata["finish_reason"], "length");
+    assert_eq!(*draft.commits.lock(), vec![2, 2, 0]);
+    assert!((decoder.acceptance_rate() - 4.0 / 7.0).abs() < 1e-9);
+}
+
+#[test]
+fn test_weight_cache_shares_checkpoints() {
+    let dir = tempfile::tempdir().unwrap();
+    let base = dir.path().join("base.gguf");
+    let other = dir.path().join("other.gguf");
+    std::fs::write(&base, vec![0u8; 64]).unwrap();
+    std::fs::write(&other, vec![0u8; 16]).unwrap();
+
+    let config_for = |path: &std::path::Path| ModelConfig {
+        model_path: path.display().to_string(),
+        ..gpu_model_config(1024)
+    };
+    let cache = WeightCache::new();
+    let v1 = cache.acquire(&"v1".to_string(), &config_for(&base)).unwrap();
+    let v2 = cache.acquire(&"v2".to_string(), &config_for(&base)).unwrap();
+    let v3 = cache.acquire(&"v3".to_string(), &config_for(&other)).unwrap();
+    assert!(Arc::ptr_eq(&v1, &v2));
+    assert!(!Arc::ptr_eq(&v1, &v3));
+    assert_eq!(cache.saved_bytes(), 64);
+
+    let checkpoints = cache.checkpoints();
+    assert_eq!(checkpoints.len(), 2);
+    assert_eq!(checkpoints[0].holders, vec!["v1".to_string(), "v2".to_string()]);
+
+    // 最后一个持有者释放后检查点关闭
+    cache.release(&"v1".to_string());
+    assert_eq!(cache.checkpoints()[0].holders, vec!["v2".to_string()]);
+    cache.release(&"v2".to_string());
+    cache.release(&"v3".to_string());
+    assert!(cache.checkpoints().is_empty());
+}
+
+/// 写出只含一个张量的safetensors文件
+fn write_single_tensor(path: &std::path::Path, dtype: &str, element_bytes: usize) {
+    let header = serde_json::to_vec(&serde_json::json!({
+        "w": { "dtype": dtype, "shape": [2], "data_offsets": [0, 2 * element_bytes] }
+    }))
+    .unwrap();
+    let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+    bytes.extend_from_slice(&header);
+    bytes.extend(std::iter::repeat_n(0u8, 2 * element_bytes));
+    std::fs::write(path, bytes).unwrap();
+}
+
+/// 记录调用次数、只支持INT8的量化器
+#[derive(Debug, Default)]
+struct CountingQuantizer {
+    calls: Mutex<usize>,
+}
+
+impl Quantizer for CountingQuantizer {
+    fn supports(&self, quantization: &QuantizationType) -> bool {
+        *quantization == QuantizationType::INT8
+    }
+
+    fn quantize(
+        &self,
+        _weights: &ModelWeights,
+        _quantization: &QuantizationType,
+        output: &std::path::Path,
+    ) -> unimodel::Result<()> {
+        *self.calls.lock() += 1;
+        std::fs::write(output, b"int8")?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_quantize_on_load_caches_artifact() {
+    use unimodel::domain::service::quantization::{needs_quantization, QuantizedCache};
+
+    let dir = tempfile::tempdir().unwrap();
+    let full = dir.path().join("full.safetensors");
+    let half = dir.path().join("half.safetensors");
+    write_single_tensor(&full, "F32", 4);
+    write_single_tensor(&half, "F16", 2);
+    assert!(needs_quantization(&full, &QuantizationType::INT8).unwrap());
+    assert!(needs_quantization(&full, &QuantizationType::FP16).unwrap());
+    assert!(!needs_quantization(&half, &QuantizationType::FP16).unwrap());
+
+    let config_for = |path: &std::path::Path, quantization: QuantizationType| {
+        let mut config = ModelConfig {
+            model_path: path.display().to_string(),
+            ..gpu_model_config(1024)
+        };
+        config.optimization.quantization = Some(quantization);
+        config
+    };
+    let cache = QuantizedCache::new(dir.path().join("cache"));
+    let quantizer = CountingQuantizer::default();
+
+    let artifact = cache.prepare(&config_for(&full, QuantizationType::INT8), Some(&quantizer)).unwrap().unwrap();
+    assert_eq!(std::fs::read(&artifact).unwrap(), b"int8");
+    assert!(artifact.to_string_lossy().contains("-int8."));
+
+    // 第二次加载直接使用缓存
+    let cached = cache.prepare(&config_for(&full, QuantizationType::INT8), Some(&quantizer)).unwrap();
+    assert_eq!(cached, Some(artifact));
+    assert_eq!(*quantizer.calls.lock(), 1);
+
+    // 已是目标精度、后端不支持或动态量化时按原文件加载
+    assert!(cache.prepare(&config_for(&half, QuantizationType::FP16), Some(&quantizer)).unwrap().is_none());
+    assert!(cache.prepare(&config_for(&full, QuantizationType::INT4), Some(&quantizer)).unwrap().is_none());
+    assert!(cache.prepare(&config_for(&full, QuantizationType::Dynamic), None).unwrap().is_none());
+    assert_eq!(*quantizer.calls.lock(), 1);
+}
+
+#[cfg(target_os = "linux")]
+/// 用系统C编译器构建假的libllama：导出ABI检查所需的符号，量化时把目标 `ftype` 写入输出文件，
+/// 其余函数为空实现，不能用于加载模型。没有C编译器时返回None
+fn build_fake_llama(dir: &std::path::Path) -> Option<std::path::PathBuf> {
+    let stubs = [
+        "llama_backend_init", "llama_lora_adapter_init", "llama_model_default_params", "llama_context_default_params",
+        "llama_load_model_from_file", "llama_free_model", "llama_new_context_with_model", "llama_free", "llama_n_vocab",
+        "llama_n_ctx", "llama_token_is_eog", "llama_tokenize", "llama_token_to_piece", "llama_batch_init",
+        "llama_batch_free", "llama_decode", "llama_get_logits_ith", "llama_kv_cache_seq_rm",
+    ];
+    let mut source = String::from(
+        "#include <stdbool.h>\n#include <stdint.h>\n#include <stdio.h>\n\
+         struct params { int32_t nthread, ftype, output_tensor_type, token_embedding_type;\n\
+             bool allow_requantize, quantize_output_tensor, only_copy, pure, keep_split; void *imatrix, *kv_overrides; };\n\
+         struct params llama_model_quantize_default_params(void) { struct params p = {0}; return p; }\n\
+         uint32_t llama_model_quantize(const char *in, const char *out, const struct params *p) {\n\
+             FILE *f = fopen(out, \"w\"); if (!f) return 1; fprintf(f, \"ftype=%d\", p->ftype); fclose(f); return 0; }\n",
+    );
+    for stub in stubs {
+        source.push_str(&format!("void {}(void) {{}}\n", stub));
+    }
+    let source_path = dir.join("fake_llama.c");
+    std::fs::write(&source_path, source).unwrap();
+    let library = dir.join("libllama.so");
+    let built = std::process::Command::new("cc")
+        .args(["-shared", "-fPIC", "-o"])
+        .arg(&library)
+        .arg(&source_path)
+        .status();
+    match built {
+        Ok(status) if status.success() => Some(library),
+        _ => None,
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[tokio::test]
+async fn test_llamacpp_quantizer_produces_artifact() {
+    use unimodel::domain::service::quantization::QuantizedCache;
+    use unimodel::plugins::manager::PluginManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let library = match build_fake_llama(dir.path()) {
+        Some(library) => library,
+        None => {
+            eprintln!("skipping: no C compiler to build a stand-in libllama");
+            return;
+        }
+    };
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    config.plugins.plugin_configs.insert(
+        "gguf".to_string(),
+        serde_json::json!({ "library_path": library.display().to_string() }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    assert!(manager.plugins().iter().any(|p| p.id == "gguf" && p.ready));
+    assert!(manager.quantizer("mock").is_none());
+    assert!(manager.quantizer("candle").is_none());
+    let quantizer = manager.quantizer("gguf").expect("llama.cpp provides a quantizer");
+
+    // 没有 `general.file_type` 的GGUF视为全精度，按目标精度量化后缓存
+    let model = dir.path().join("model.gguf");
+    let mut bytes = b"GGUF".to_vec();
+    bytes.extend_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    std::fs::write(&model, bytes).unwrap();
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "gguf".to_string();
+    model_config.model_path = model.display().to_string();
+    model_config.optimization.quantization = Some(QuantizationType::INT4);
+
+    let cache = QuantizedCache::new(dir.path().join("cache"));
+    let artifact = cache.prepare(&model_config, Some(quantizer.as_ref())).unwrap().expect("quantized artifact");
+    assert!(artifact.to_string_lossy().ends_with("-int4.gguf"));
+    // Q4_K_M
+    assert_eq!(std::fs::read_to_string(&artifact).unwrap(), "ftype=15");
+    assert!(!artifact.with_extension("partial").exists());
+}
+
+#[test]
+fn test_model_format_detection() {
+    use unimodel::domain::service::conversion::ModelFormat;
+
+    let dir = tempfile::tempdir().unwrap();
+    let hf = dir.path().join("llama");
+    std::fs::create_dir(&hf).unwrap();
+    assert_eq!(ModelFormat::detect(&hf), None);
+    std::fs::write(hf.join("config.json"), b"{}").unwrap();
+    assert_eq!(ModelFormat::detect(&hf), Some(ModelFormat::HuggingFace));
+
+    assert_eq!(ModelFormat::detect(std::path::Path::new("model.pth")), Some(ModelFormat::PyTorch));
+    assert_eq!(ModelFormat::detect(std::path::Path::new("model.onnx")), Some(ModelFormat::Onnx));
+    assert_eq!(ModelFormat::detect(std::path::Path::new("model.gguf")), Some(ModelFormat::Gguf));
+    assert_eq!(ModelFormat::detect(std::path::Path::new("model.txt")), None);
+
+    assert_eq!(ModelFormat::Gguf.default_backend(), "gguf");
+    assert_eq!(ModelFormat::HuggingFace.extension(), None);
+}
+
+#[test]
+fn test_model_format_sniffing() {
+    use unimodel::domain::service::conversion::ModelFormat;
+    use unimodel::infrastructure::model_format::sniff::sniff_bytes;
+
+    assert_eq!(sniff_bytes(b"GGUF\x03\x00\x00\x00", 1024), Some(ModelFormat::Gguf));
+    assert_eq!(sniff_bytes(b"PK\x03\x04\x14\x00", 1024), Some(ModelFormat::PyTorch));
+    assert_eq!(sniff_bytes(&[0x80, 0x02, 0x8a, 0x0a], 1024), Some(ModelFormat::PyTorch));
+    // ir_version=8，随后是producer_name
+    assert_eq!(sniff_bytes(&[0x08, 0x08, 0x12, 0x07, b'p', b'y'], 1024), Some(ModelFormat::Onnx));
+    let mut safetensors = 2u64.to_le_bytes().to_vec();
+    safetensors.extend_from_slice(b"{}");
+    assert_eq!(sniff_bytes(&safetensors, 10), Some(ModelFormat::SafeTensors));
+    // 头部长度超出文件
+    assert_eq!(sniff_bytes(&safetensors, 9), None);
+    assert_eq!(sniff_bytes(b"hello world", 11), None);
+
+    // 内容优先于扩展名，无法识别时退回扩展名
+    let dir = tempfile::tempdir().unwrap();
+    let renamed = dir.path().join("model.bin");
+    std::fs::write(&renamed, b"GGUF\x03\x00\x00\x00").unwrap();
+    assert_eq!(ModelFormat::inspect(&renamed), Some(ModelFormat::Gguf));
+    assert_eq!(ModelFormat::inspect(std::path::Path::new("missing.onnx")), Some(ModelFormat::Onnx));
+}
+
+#[test]
+fn test_sample_token_respects_masks_and_seed() {
+    use rand::SeedableRng;
+    use unimodel::domain::service::generation::{sample_distribution, sample_token, sampling_distribution};
+
+    let logits = [1.0, 3.0, f32::NEG_INFINITY, 2.0];
+    let mut rng = rand::rngs::StdRng::seed_from_u64(7);
+    assert_eq!(sample_token(&logits, 0.0, 0, 1.0, &mut rng), 1);
+    // top_k为1时退化为贪心
+    assert_eq!(sample_token(&logits, 1.0, 1, 1.0, &mut rng), 1);
+    for _ in 0..100 {
+        assert_ne!(sample_token(&logits, 2.0, 0, 1.0, &mut rng), 2);
+    }
+
+    let sample = |seed| {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..16).map(|_| sample_token(&logits, 1.0, 0, 0.9, &mut rng)).collect::<Vec<_>>()
+    };
+    assert_eq!(sample(42), sample(42));
+
+    // 投机解码所用的分布与采样一致：贪心时集中在argmax，被屏蔽和截断的token概率为0
+    assert_eq!(sampling_distribution(&logits, 0.0, 0, 1.0), vec![0.0, 1.0, 0.0, 0.0]);
+    let probs = sampling_distribution(&logits, 1.0, 2, 1.0);
+    assert_eq!((probs[0], probs[2]), (0.0, 0.0));
+    assert!((probs.iter().sum::<f32>() - 1.0).abs() < 1e-6);
+    assert!(probs[1] > probs[3]);
+    assert_ne!(sample_distribution(&probs, &mut rng), 0);
+}
+
+#[test]
+fn test_native_plugin_loader_rejects_invalid_libraries() {
+    use unimodel::plugins::manager::plugin_loader::{load_native_plugins, plugin_libraries, LIBRARY_EXTENSION};
+
+    let dir = tempfile::tempdir().unwrap();
+    // 扩展名正确但不是动态库的文件被拒绝，其余文件直接忽略
+    let library = dir.path().join(format!("broken.{}", LIBRARY_EXTENSION));
+    std::fs::write(&library, b"not a library").unwrap();
+    std::fs::write(dir.path().join("README.txt"), b"docs").unwrap();
+    assert_eq!(plugin_libraries(dir.path()), vec![library]);
+    let enabled = vec!["broken".to_string()];
+    assert!(load_native_plugins(dir.path(), &enabled).is_empty());
+
+    // 目录不存在时不报错
+    assert!(load_native_plugins(&dir.path().join("missing"), &enabled).is_empty());
+}
+
+#[test]
+fn test_backend_capabilities_check_model_config() {
+    use unimodel::domain::service::conversion::ModelFormat;
+    use unimodel::plugins::interface::BackendCapabilities;
+
+    let capabilities: BackendCapabilities = serde_json::from_value(serde_json::json!({
+        "backend": "trt",
+        "formats": ["onnx"],
+        "devices": ["CUDA"],
+        "max_batch_size": 16,
+        "quantization": ["FP16"],
+    }))
+    .unwrap();
+    assert!(capabilities.declares_format(ModelFormat::Onnx));
+
+    let mut config = gpu_model_config(1000);
+    config.batch_config.max_batch_size = 16;
+    assert!(capabilities.check(&config).is_ok());
+
+    let mut wrong_format = config.clone();
+    wrong_format.model_path = "/path/to/model.gguf".to_string();
+    let mut wrong_device = config.clone();
+    wrong_device.device.device_type = DeviceType::CPU;
+    let mut wrong_quantization = config.clone();
+    wrong_quantization.optimization.quantization = Some(QuantizationType::INT4);
+    let mut oversized_batch = config.clone();
+    oversized_batch.batch_config.max_batch_size = 32;
+    for rejected in [wrong_format, wrong_device, wrong_quantization, oversized_batch] {
+        assert!(matches!(capabilities.check(&rejected), Err(UniModelError::Validation(_))));
+    }
+
+    // 未声明能力的后端不限制，也不会被自动选择
+    let undeclared = BackendCapabilities::undeclared("custom");
+    assert!(undeclared.check(&config).is_ok());
+    assert!(!undeclared.declares_format(ModelFormat::Onnx));
+}
+
+#[test]
+fn test_backend_capabilities_mixed_precision() {
+    use unimodel::plugins::interface::BackendCapabilities;
+
+    let capabilities: BackendCapabilities = serde_json::from_value(serde_json::json!({
+        "backend": "trt",
+        "mixed_precision": ["FP16"],
+    }))
+    .unwrap();
+    let undeclared = BackendCapabilities::undeclared("custom");
+
+    // 未启用混合精度时总是FP32
+    let mut config = gpu_model_config(1000);
+    assert_eq!(capabilities.precision(&config), Precision::FP32);
+    assert_eq!(undeclared.precision(&config), Precision::FP32);
+
+    // GPU上优先BF16，后端只支持FP16时使用FP16；未声明的后端请求BF16
+    config.device.mixed_precision = true;
+    assert_eq!(capabilities.precision(&config), Precision::FP16);
+    assert_eq!(undeclared.precision(&config), Precision::BF16);
+
+    // Metal上优先FP16
+    let bf16_only = BackendCapabilities {
+        mixed_precision: vec![Precision::BF16],
+        ..BackendCapabilities::undeclared("mlx")
+    };
+    config.device.device_type = DeviceType::Metal;
+    assert_eq!(undeclared.precision(&config), Precision::FP16);
+    assert_eq!(bf16_only.precision(&config), Precision::BF16);
+}
+
+#[tokio::test]
+async fn test_plugin_manager_passes_adapters_only_to_multi_lora_backends() {
+    use unimodel::plugins::manager::PluginManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    let manager = PluginManager::new(&config).await.unwrap();
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "mock".to_string();
+    let weights = ModelWeights::File(model_config.model_path.clone().into());
+    let instance = manager.load_model(&"lora".to_string(), &model_config, &weights).await.unwrap();
+    assert!(instance.supports_multi_lora);
+
+    // 支持多LoRA的实例按输入接受各自的适配器
+    let inputs = vec![InputData::Text("a".to_string()), InputData::Text("b".to_string())];
+    let adapters = vec![Some("sql".to_string()), None];
+    let outputs = manager
+        .infer_with_adapters(&instance, &inputs, &PredictionParameters::default(), &adapters)
+        .await
+        .unwrap();
+    assert_eq!(outputs.len(), 2);
+
+    // 其余实例不能在一个批次中混用适配器
+    let single = ModelInstance { supports_multi_lora: false, ..instance };
+    assert!(matches!(
+        manager.infer_with_adapters(&single, &inputs, &PredictionParameters::default(), &adapters).await,
+        Err(UniModelError::Validation(_))
+    ));
+    assert!(manager.infer(&single, &inputs, &PredictionParameters::default()).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_plugin_manager_without_plugins() {
+    use unimodel::plugins::manager::{PluginKind, PluginManager};
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    config.plugins.plugin_configs.insert(
+        "gguf".to_string(),
+        serde_json::json!({ "library_path": dir.path().join("libllama.so").display().to_string() }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+
+    // 只有内置后端，找不到库的llama.cpp后端列出但未就绪
+    let plugins = manager.plugins();
+    let ids: Vec<&str> = plugins.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, vec!["candle", "gguf", "mock", "python"]);
+    assert!(plugins.iter().all(|p| p.kind == PluginKind::Builtin));
+    let ready: Vec<bool> = plugins.iter().map(|p| p.ready).collect();
+    assert_eq!(ready, vec![true, false, true, true]);
+    assert!(manager.capabilities("candle").is_some());
+    assert!(manager.capabilities("gguf").is_none());
+    assert!(manager.capabilities("onnx").is_none());
+    // 没有声明能力的后端时自动选择失败，错误说明原因
+    match manager.select_backend(&gpu_model_config(1000)) {
+        Err(UniModelError::Validation(message)) => assert!(message.contains("no enabled backend declares onnx")),
+        other => panic!("unexpected selection: {:?}", other),
+    }
+
+    // 模拟后端按请求的精度加载
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "mock".to_string();
+    model_config.device.device_type = DeviceType::CPU;
+    let weights = ModelWeights::File(model_config.model_path.clone().into());
+    let full = manager.load_model(&"full".to_string(), &model_config, &weights).await.unwrap();
+    assert_eq!(full.precision, Precision::FP32);
+    model_config.device.mixed_precision = true;
+    let mixed = manager.load_model(&"mixed".to_string(), &model_config, &weights).await.unwrap();
+    assert_eq!(mixed.precision, Precision::BF16);
+}
+
+#[test]
+fn test_llamacpp_backend_config_and_library_errors() {
+    use unimodel::plugins::builtin::{LlamaCppBackend, LlamaCppConfig};
+
+    // 配置字段类型错误是配置错误
+    let invalid = serde_json::json!({ "max_context": "large" });
+    match LlamaCppBackend::from_plugin_config(Some(&invalid)) {
+        Err(UniModelError::Config(message)) => assert!(message.contains("Invalid gguf plugin config")),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // 省略的字段取默认值
+    let config: LlamaCppConfig = serde_json::from_value(serde_json::json!({ "n_threads": 2 })).unwrap();
+    assert_eq!(config.n_threads, Some(2));
+    assert_eq!(config.n_gpu_layers, LlamaCppConfig::default().n_gpu_layers);
+    assert_eq!(config.max_context, LlamaCppConfig::default().max_context);
+
+    // 库不存在时后端不可用
+    let dir = tempfile::tempdir().unwrap();
+    let missing = serde_json::json!({ "library_path": dir.path().join("libllama.so").display().to_string() });
+    match LlamaCppBackend::from_plugin_config(Some(&missing)) {
+        Err(UniModelError::PluginUnavailable(message)) => assert!(message.contains("libllama.so")),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 不是llama.cpp的库在解析结构体前被拒绝
+    if cfg!(target_os = "linux") {
+        match LlamaCppBackend::new(LlamaCppConfig { library_path: "libc.so.6".to_string(), ..LlamaCppConfig::default() }) {
+            Err(UniModelError::PluginUnavailable(message)) => {
+                assert!(message.contains("not a llama.cpp build compatible"), "{}", message)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_plugin_manager_reports_unavailable_builtin_backend() {
+    use unimodel::plugins::manager::PluginManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    config.plugins.plugin_configs.insert(
+        "gguf".to_string(),
+        serde_json::json!({ "library_path": dir.path().join("libllama.so").display().to_string() }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+
+    // 加载时返回后端未能启用的原因，而不是没有插件提供该后端
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "gguf".to_string();
+    model_config.model_path = dir.path().join("model.gguf").display().to_string();
+    let weights = ModelWeights::File(model_config.model_path.clone().into());
+    match manager.load_model(&"llama".to_string(), &model_config, &weights).await {
+        Err(UniModelError::PluginUnavailable(message)) => {
+            assert!(message.contains("Backend 'gguf' is unavailable"), "{}", message);
+            assert!(message.contains("libllama.so"), "{}", message);
+        }
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 配置无法解析时管理器创建失败
+    config.plugins.plugin_configs.insert("gguf".to_string(), serde_json::json!({ "n_gpu_layers": "all" }));
+    assert!(matches!(PluginManager::new(&config).await, Err(UniModelError::Config(_))));
+}
+
+/// 模拟Triton的 `ready` 和 `generate_stream` 接口：`ensemble` 分两段返回"Hello"，`broken` 在流中返回错误
+async fn start_fake_triton() -> String {
+    use axum::routing::{get, post};
+
+    let sse = |events: &'static [&'static str]| async move {
+        events.iter().map(|event| format!("data: {}\n\n", event)).collect::<String>()
+    };
+    let app = axum::Router::new()
+        .route("/v2/models/:model/ready", get(|| async { "" }))
+        .route(
+            "/v2/models/ensemble/generate_stream",
+            post(move || sse(&[r#"{"text_output":"Hel"}"#, r#"{"text_output":"lo"}"#])),
+        )
+        .route(
+            "/v2/models/broken/generate_stream",
+            post(move || sse(&[r#"{"error":"engine crashed"}"#])),
+        );
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let endpoint = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+    endpoint
+}
+
+#[tokio::test]
+async fn test_plugin_manager_tensorrt_llm_backend() {
+    use unimodel::plugins::manager::PluginManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "tensorrt_llm".to_string();
+    let weights = ModelWeights::File(model_config.model_path.clone().into());
+
+    // 未配置时不启用
+    let manager = PluginManager::new(&config).await.unwrap();
+    assert!(manager.capabilities("tensorrt_llm").is_none());
+    match manager.load_model(&"trt".to_string(), &model_config, &weights).await {
+        Err(UniModelError::PluginUnavailable(message)) => assert!(message.contains("No plugin provides"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 配置无法解析时管理器创建失败
+    config.plugins.plugin_configs.insert("tensorrt_llm".to_string(), serde_json::json!({ "endpoint": 8000 }));
+    match PluginManager::new(&config).await {
+        Err(UniModelError::Config(message)) => assert!(message.contains("Invalid tensorrt_llm plugin config")),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    // Triton不可达时加载失败
+    let unreachable = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    config.plugins.plugin_configs.insert(
+        "tensorrt_llm".to_string(),
+        serde_json::json!({ "endpoint": format!("http://{}", unreachable), "connect_timeout_secs": 1 }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    assert_eq!(manager.capabilities("tensorrt_llm").unwrap().devices, vec![DeviceType::CUDA]);
+    match manager.load_model(&"trt".to_string(), &model_config, &weights).await {
+        Err(UniModelError::PluginUnavailable(message)) => assert!(message.contains("unreachable"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 流式返回的token拼成完整输出，流中的错误作为插件错误返回
+    config.plugins.plugin_configs.insert(
+        "tensorrt_llm".to_string(),
+        serde_json::json!({ "endpoint": start_fake_triton().await }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    let instance = manager.load_model(&"trt".to_string(), &model_config, &weights).await.unwrap();
+    assert_eq!(instance.plugin_id, "tensorrt_llm");
+    let outputs = manager
+        .infer(&instance, &[InputData::Text("Say hello".to_string())], &PredictionParameters::default())
+        .await
+        .unwrap();
+    assert!(matches!(&outputs[..], [OutputData::Text(text)] if text == "Hello"));
+
+    model_config.custom_params.insert("triton_model".to_string(), serde_json::json!("broken"));
+    let broken = manager.load_model(&"broken".to_string(), &model_config, &weights).await.unwrap();
+    match manager.infer(&broken, &[InputData::Text("Say hello".to_string())], &PredictionParameters::default()).await {
+        Err(UniModelError::Plugin(message)) => assert!(message.contains("engine crashed"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    manager.unload_model(&instance.plugin_id, &instance.handle).await.unwrap();
+    assert!(manager.infer(&instance, &[InputData::Text("again".to_string())], &PredictionParameters::default()).await.is_err());
+}
+
+#[cfg(unix)]
+/// 假的Python解释器：忽略参数，每读到一个请求帧就按文件名顺序回复 `dir` 中的下一个 `response-*`，
+/// 回复完后读到下一个请求帧（或标准输入关闭）即退出
+fn fake_python_worker(dir: &std::path::Path, responses: &[Vec<u8>]) -> serde_json::Value {
+    use std::os::unix::fs::PermissionsExt;
+
+    for (i, response) in responses.iter().enumerate() {
+        std::fs::write(dir.join(format!("response-{}", i)), response).unwrap();
+    }
+    let interpreter = dir.join("python");
+    std::fs::write(&interpreter, r#"#!/bin/sh
+read_frame() {
+    len=$(dd bs=1 count=4 2>/dev/null | od -An -tu4 | tr -d ' ')
+    [ -n "$len" ] && dd bs=1 count="$len" of=/dev/null 2>/dev/null
+}
+for response in "$FAKE_WORKER_DIR"/response-*; do
+    [ -e "$response" ] || break
+    read_frame
+    cat "$response"
+done
+read_frame
+"#).unwrap();
+    std::fs::set_permissions(&interpreter, std::fs::Permissions::from_mode(0o755)).unwrap();
+    serde_json::json!({
+        "interpreter": interpreter.display().to_string(),
+        "env": { "FAKE_WORKER_DIR": dir.display().to_string() },
+        "load_timeout_secs": 10,
+        "request_timeout_secs": 10,
+    })
+}
+
+#[cfg(unix)]
+/// 工作进程的响应帧：4字节小端长度加MessagePack
+fn worker_frame(response: serde_json::Value) -> Vec<u8> {
+    let payload = rmp_serde::to_vec_named(&response).unwrap();
+    let mut frame = (payload.len() as u32).to_le_bytes().to_vec();
+    frame.extend(payload);
+    frame
+}
+
+#[cfg(unix)]
+#[tokio::test]
+async fn test_plugin_manager_python_backend() {
+    use unimodel::plugins::manager::PluginManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let script = dir.path().join("model.py");
+    std::fs::write(&script, "").unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "python".to_string();
+    model_config.device.device_type = DeviceType::CPU;
+    model_config.model_path = script.display().to_string();
+    let weights = ModelWeights::File(script.clone());
+    let inputs = [InputData::Text("hi".to_string())];
+    let parameters = PredictionParameters::default();
+
+    // 配置无法解析时管理器创建失败
+    config.plugins.plugin_configs.insert("python".to_string(), serde_json::json!({ "python_path": "python" }));
+    match PluginManager::new(&config).await {
+        Err(UniModelError::Config(message)) => assert!(message.contains("Invalid python plugin config")),
+        other => panic!("unexpected result: {:?}", other.map(|_| ())),
+    }
+
+    // 解释器无法启动
+    config.plugins.plugin_configs.insert(
+        "python".to_string(),
+        serde_json::json!({ "interpreter": dir.path().join("missing-python").display().to_string() }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    match manager.load_model(&"py".to_string(), &model_config, &weights).await {
+        Err(UniModelError::PluginUnavailable(message)) => assert!(message.contains("Cannot start Python worker"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // 脚本不存在
+    let mut missing_script = model_config.clone();
+    missing_script.model_path = dir.path().join("missing.py").display().to_string();
+    assert!(matches!(
+        manager.load_model(&"py".to_string(), &missing_script, &weights).await,
+        Err(UniModelError::Validation(_))
+    ));
+
+    // 用户脚本的 `load` 报错
+    let load_error = tempfile::tempdir().unwrap();
+    config.plugins.plugin_configs.insert(
+        "python".to_string(),
+        fake_python_worker(load_error.path(), &[worker_frame(serde_json::json!({ "id": 1, "error": "no weights" }))]),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    match manager.load_model(&"py".to_string(), &model_config, &weights).await {
+        Err(UniModelError::Plugin(message)) => assert!(message.contains("no weights"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 超长的帧使工作进程被视为已退出
+    let oversized = tempfile::tempdir().unwrap();
+    config.plugins.plugin_configs.insert(
+        "python".to_string(),
+        fake_python_worker(oversized.path(), &[u32::MAX.to_le_bytes().to_vec()]),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    match manager.load_model(&"py".to_string(), &model_config, &weights).await {
+        Err(UniModelError::PluginUnavailable(message)) => assert!(message.contains("exited"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+
+    // 加载成功后推理经工作进程执行，类型不符的结果是协议错误
+    let worker = tempfile::tempdir().unwrap();
+    config.plugins.plugin_configs.insert(
+        "python".to_string(),
+        fake_python_worker(worker.path(), &[
+            worker_frame(serde_json::json!({ "id": 1, "result": true })),
+            worker_frame(serde_json::json!({ "id": 2, "result": [{ "type": "Text", "data": "hello" }] })),
+            worker_frame(serde_json::json!({ "id": 3, "result": "not a list" })),
+        ]),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+    let instance = manager.load_model(&"py".to_string(), &model_config, &weights).await.unwrap();
+    assert_eq!(instance.plugin_id, "python");
+    let outputs = manager.infer(&instance, &inputs, &parameters).await.unwrap();
+    assert!(matches!(&outputs[..], [OutputData::Text(text)] if text == "hello"));
+    match manager.infer(&instance, &inputs, &parameters).await {
+        Err(UniModelError::Plugin(message)) => assert!(message.contains("Malformed response"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // 卸载结束工作进程
+    manager.unload_model(&instance.plugin_id, &instance.handle).await.unwrap();
+    assert!(manager.infer(&instance, &inputs, &parameters).await.is_err());
+}
+
+#[tokio::test]
+async fn test_plugin_manager_routes_gguf_to_candle() {
+    use unimodel::plugins::manager::PluginManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = Config::default();
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    config.plugins.plugin_configs.insert(
+        "gguf".to_string(),
+        serde_json::json!({ "library_path": dir.path().join("libllama.so").display().to_string() }),
+    );
+    let manager = PluginManager::new(&config).await.unwrap();
+
+    // llama.cpp不可用时GGUF模型由candle加载
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "auto".to_string();
+    model_config.device.device_type = DeviceType::CPU;
+    model_config.model_path = dir.path().join("model.gguf").display().to_string();
+    assert_eq!(manager.select_backend(&model_config).unwrap(), "candle");
+    // candle只接受GGUF文件，错误来自后端而不是路由
+    model_config.backend = "candle".to_string();
+    let weights = ModelWeights::File(dir.path().join("model.bin"));
+    match manager.load_model(&"llama".to_string(), &model_config, &weights).await {
+        Err(UniModelError::Model(message)) => assert!(message.contains("candle backend needs a GGUF file"), "{}", message),
+        other => panic!("unexpected result: {:?}", other),
+    }
+    // 不支持的设备在能力检查时被拒绝
+    model_config.device.device_type = DeviceType::ROCm;
+    assert!(manager.capabilities("candle").unwrap().check(&model_config).is_err());
+}
+
+#[tokio::test]
+async fn test_mock_backend() {
+    use unimodel::plugins::builtin::{MockBackend, MockConfig, MockErrorKind, MockLatency};
+
+    let config = MockConfig {
+        latency: MockLatency::Fixed { ms: 0 },
+        outputs: vec![OutputData::Text("a".to_string()), OutputData::Text("b".to_string())],
+        ..MockConfig::default()
+    };
+    let backend = MockBackend::new(config).unwrap();
+    let handle = backend.load_model(&gpu_model_config(1000)).unwrap();
+
+    // 预设输出依次轮换
+    let inputs = vec![InputData::Text("x".to_string()); 3];
+    let outputs = backend.infer(handle, &inputs).await.unwrap();
+    let texts: Vec<&str> = outputs.iter()
+        .map(|output| match output {
+            OutputData::Text(text) => text.as_str(),
+            other => panic!("unexpected output: {:?}", other),
+        })
+        .collect();
+    assert_eq!(texts, vec!["a", "b", "a"]);
+
+    // 模型可单独覆盖配置：未预设输出时回显输入，按错误率注入失败
+    let mut echo_config = gpu_model_config(1000);
+    echo_config.custom_params.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 0 } }),
+    );
+    let echo = backend.load_model(&echo_config).unwrap();
+    let outputs = backend.infer(echo, &[InputData::Text("hello".to_string())]).await.unwrap();
+    assert!(matches!(outputs.as_slice(), [OutputData::Text(text)] if text == "Processed: hello"));
+
+    let mut failing_config = gpu_model_config(1000);
+    failing_config.custom_params.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 0 }, "error_rate": 1.0, "error_kind": "unavailable" }),
+    );
+    let failing = backend.load_model(&failing_config).unwrap();
+    assert!(matches!(
+        backend.infer(failing, &inputs).await,
+        Err(UniModelError::PluginUnavailable(_))
+    ));
+
+    backend.unload_model(handle).unwrap();
+    assert!(backend.infer(handle, &inputs).await.is_err());
+
+    let invalid = MockConfig { error_rate: 1.5, ..MockConfig::default() };
+    assert!(MockBackend::new(invalid).is_err());
+    assert_eq!(MockConfig::default().error_kind, MockErrorKind::Plugin);
+}
+
+#[test]
+fn test_chaos_injection() {
+    use unimodel::domain::service::chaos::{ChaosInjector, ChaosSite, Fault};
+    use unimodel::infrastructure::configuration::{ChaosConfig, FaultRates};
+
+    assert!(ChaosInjector::new(&ChaosConfig::default()).is_none());
+
+    let chaos = ChaosConfig {
+        enabled: true,
+        seed: Some(7),
+        batch: FaultRates { latency_rate: 0.5, latency_ms: 10, error_rate: 0.2, crash_rate: 0.2, oom_rate: 0.2 },
+        plugins: FaultRates { oom_rate: 1.0, ..FaultRates::default() },
+    };
+    // 同一种子得到同样的注入序列
+    let first = ChaosInjector::new(&chaos).unwrap();
+    let second = ChaosInjector::new(&chaos).unwrap();
+    let sequence: Vec<_> = (0..200).map(|_| first.sample(ChaosSite::Batch)).collect();
+    assert_eq!(sequence, (0..200).map(|_| second.sample(ChaosSite::Batch)).collect::<Vec<_>>());
+    for fault in [Fault::Error, Fault::Crash, Fault::OutOfMemory] {
+        assert!(sequence.iter().any(|(_, f)| *f == Some(fault)));
+    }
+    assert!(sequence.iter().any(|(_, f)| f.is_none()));
+    assert!(sequence.iter().any(|(delay, _)| *delay == Some(Duration::from_millis(10))));
+
+    // 各注入点按各自的概率，注入的错误与真实错误一样参与重试判断
+    let (delay, fault) = first.sample(ChaosSite::Plugin);
+    assert_eq!(delay, None);
+    assert_eq!(fault, Some(Fault::OutOfMemory));
+    assert!(Fault::OutOfMemory.error(ChaosSite::Plugin).is_retriable());
+    assert!(Fault::Crash.error(ChaosSite::Plugin).is_retriable());
+    assert!(!Fault::Error.error(ChaosSite::Plugin).is_retriable());
+
+    let mut config = Config { chaos, ..Default::default() };
+    config.chaos.batch.error_rate = 0.7;
+    assert!(config.validate().is_err());
+    config.chaos.batch.error_rate = 0.1;
+    assert!(config.validate().is_ok());
+}
+
+#[tokio::test]
+async fn test_inference_pool() {
+    use unimodel::domain::service::InferencePool;
+    use unimodel::infrastructure::configuration::InferencePoolConfig;
+
+    let pool = InferencePool::new(&InferencePoolConfig { threads: Some(2), ..Default::default() }, None).unwrap();
+    assert_eq!(pool.threads(), 2);
+
+    let thread = pool.run(|| Ok(std::thread::current().name().map(str::to_string))).await.unwrap();
+    assert!(thread.unwrap().starts_with("unimodel-infer"));
+    assert!(matches!(
+        pool.run(|| -> Result<(), UniModelError> { Err(UniModelError::plugin("failed")) }).await,
+        Err(UniModelError::Plugin(_))
+    ));
+    // panic不会结束线程池
+    assert!(matches!(
+        pool.run(|| -> Result<(), UniModelError> { panic!("backend crashed") }).await,
+        Err(UniModelError::Internal(_))
+    ));
+
+    // 线程数限制了并发的调用数
+    let started = Instant::now();
+    let calls: Vec<_> = (0..4)
+        .map(|_| pool.run(|| {
+            std::thread::sleep(Duration::from_millis(100));
+            Ok(())
+        }))
+        .collect();
+    for result in futures::future::join_all(calls).await {
+        result.unwrap();
+    }
+    assert!(started.elapsed() >= Duration::from_millis(200));
+    assert_eq!(pool.queued() + pool.running(), 0);
+}
+
+#[tokio::test]
+async fn test_cuda_stream_pool() {
+    use unimodel::plugins::manager::stream_pool::StreamPool;
+
+    let pool = StreamPool::new(2);
+    assert_eq!(pool.streams(), 2);
+    assert_eq!(StreamPool::new(0).streams(), 1);
+
+    // 同时执行的批次各占一条流
+    let first = pool.acquire().await;
+    let second = pool.acquire().await;
+    assert_ne!(first.stream(), second.stream());
+    assert_eq!(pool.in_use(), 2);
+
+    // 流都在使用时等待，归还的流被下一个批次复用
+    let waiting = tokio::spawn({
+        let pool = Arc::clone(&pool);
+        async move { pool.acquire().await.stream() }
+    });
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!waiting.is_finished());
+    let released = second.stream();
+    drop(second);
+    assert_eq!(waiting.await.unwrap(), released);
+    drop(first);
+    assert_eq!(pool.in_use(), 0);
+}
+
+#[tokio::test]
+async fn test_grpc_auth_layer() {
+    use tower::{Layer, Service, ServiceExt};
+    use unimodel::api::auth::{AuthContext, Authenticator};
+    use unimodel::api::grpc::auth::GrpcAuthLayer;
+    use unimodel::infrastructure::security::ApiKeyStore;
+
+    let dir = tempfile::tempdir().unwrap();
+    let store = Arc::new(ApiKeyStore::open(dir.path().join("api_keys.json")).await.unwrap());
+    let mut security = Config::default().security;
+    security.auth_enabled = true;
+    security.api_keys = vec!["static-key".to_string()];
+    let layer = GrpcAuthLayer::new(Arc::new(Authenticator::new(&security, store)));
+    let mut service = layer.layer(tower::service_fn(|request: http::Request<()>| async move {
+        let authenticated = request.extensions().get::<AuthContext>().is_some();
+        Ok::<_, std::convert::Infallible>(
+            http::Response::builder()
+                .header("authenticated", authenticated.to_string())
+                .body(tonic::body::empty_body())
+                .unwrap(),
+        )
+    }));
+    let request = |path: &str, key: Option<&str>| {
+        let mut builder = http::Request::builder().uri(path);
+        if let Some(key) = key {
+            builder = builder.header("x-api-key", key);
+        }
+        builder.body(()).unwrap()
+    };
+    const PREDICT: &str = "/unimodel.inference.InferenceService/Predict";
+
+    // 缺少或无效的密钥被拒绝，不会到达推理服务
+    for key in [None, Some("random-key")] {
+        let response = service.ready().await.unwrap().call(request(PREDICT, key)).await.unwrap();
+        assert_eq!(response.headers()["grpc-status"], "16");
+        assert!(response.headers().get("authenticated").is_none());
+    }
+
+    let response = service.ready().await.unwrap().call(request(PREDICT, Some("static-key"))).await.unwrap();
+    assert_eq!(response.headers()["authenticated"], "true");
+
+    // 健康检查不需要认证
+    let response = service
+        .ready()
+        .await
+        .unwrap()
+        .call(request("/grpc.health.v1.Health/Check", None))
+        .await
+        .unwrap();
+    assert_eq!(response.headers()["authenticated"], "false");
+}
+
+#[test]
+fn test_fit_context_window_keeps_default_max_tokens() {
+    use unimodel::application::services::prediction_service::fit_context_window;
+
+    let mut model = Model::new("llm".to_string(), "llm".to_string(), ModelType::LLM, gpu_model_config(1000));
+    model.info.metadata.custom_metadata.insert("context_length".to_string(), serde_json::json!(4096));
+    let fit = |prompt_tokens: u32, max_tokens: Option<u32>| {
+        let mut parameters = PredictionParameters {
+            max_tokens,
+            ..Default::default()
+        };
+        fit_context_window(&model.info, Some(prompt_tokens), &mut parameters).map(|_| parameters.max_tokens)
+    };
+
+    // 未指定时不会放大到整个剩余窗口
+    assert_eq!(fit(100, None).unwrap(), None);
+    // 剩余空间小于默认生成长度时截断
+    assert_eq!(fit(4000, None).unwrap(), Some(96));
+    assert_eq!(fit(100, Some(8192)).unwrap(), Some(3996));
+    assert_eq!(fit(100, Some(16)).unwrap(), Some(16));
+    assert!(fit(4096, None).is_err());
+}
+
+#[tokio::test]
+async fn test_quota_manager_enforces_monthly_tokens_and_persists() {
+    use unimodel::QuotaManager;
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = rate_limit_config(60, 10);
+    config.tokens_per_month = Some(100);
+    config.overrides.insert("big".to_string(), QuotaOverride { requests_per_minute: None, tokens_per_month: Some(1000) });
+    config.quota_store_path = dir.path().join("quota.json").display().to_string();
+
+    let quota = QuotaManager::open(&config).await.unwrap();
+    assert_eq!(quota.tokens_per_month_for("small"), Some(100));
+    assert_eq!(quota.tokens_per_month_for("big"), Some(1000));
+
+    // 用完月度token后拒绝，返回429
+    quota.check_request("small").await.unwrap();
+    quota.record_tokens("small", 60).await;
+    quota.check_request("small").await.unwrap();
+    quota.record_tokens("small", 40).await;
+    let err = quota.check_request("small").await.unwrap_err();
+    assert!(matches!(err, UniModelError::QuotaExceeded(_)), "{:?}", err);
+    assert_eq!(err.status_code(), 429);
+    quota.record_tokens("big", 500).await;
+    quota.check_request("big").await.unwrap();
+
+    // 落盘后重新打开继续累计
+    quota.flush().await.unwrap();
+    let reopened = QuotaManager::open(&config).await.unwrap();
+    let usage = reopened.monthly_usage("small").await;
+    assert_eq!((usage.requests, usage.tokens), (2, 100));
+    assert!(reopened.check_request("small").await.is_err());
+
+    config.enabled = false;
+    QuotaManager::open(&config).await.unwrap().check_request("small").await.unwrap();
+}
+
+#[tokio::test]
+async fn test_overload_responses_carry_backoff_hints() {
+    use axum::response::IntoResponse;
+    use unimodel::common::error::OverloadHint;
+
+    let mut config = Config::default();
+    config.engine.batch_config.max_batch_size = 4;
+    config.engine.batch_config.max_wait_time_ms = 200;
+    let processor = BatchProcessor::new(&config).await.unwrap();
+    // 还没有批次延迟样本时按等待时间估算
+    let hint = processor.overload_hint();
+    assert_eq!((hint.queue_depth, hint.estimated_wait_ms, hint.retry_after_ms), (Some(0), Some(200), 200));
+
+    let response = UniModelError::Overloaded {
+        message: "Batch queue is full".to_string(),
+        hint: OverloadHint { retry_after_ms: 1500, queue_depth: Some(12), estimated_wait_ms: Some(1500) },
+    }
+    .into_response();
+    assert_eq!(response.status(), 503);
+    assert_eq!(response.headers()["retry-after"], "2");
+    assert_eq!(response.headers()["x-queue-depth"], "12");
+    assert_eq!(response.headers()["x-estimated-wait-ms"], "1500");
+    let body: serde_json::Value =
+        serde_json::from_slice(&hyper::body::to_bytes(response.into_body()).await.unwrap()).unwrap();
+    assert_eq!(body["error"], "OVERLOADED");
+    assert_eq!((body["queue_depth"].as_u64(), body["retry_after_ms"].as_u64()), (Some(12), Some(1500)));
+
+    // 限流只给出重试时间
+    let limiter = RateLimiter::new(&rate_limit_config(60, 1));
+    limiter.check("ip:10.0.0.1", 60).unwrap();
+    let retry_after = limiter.check("ip:10.0.0.1", 60).unwrap_err();
+    let response = UniModelError::RateLimited {
+        message: "Too many requests".to_string(),
+        hint: OverloadHint::retry_after(retry_after),
+    }
+    .into_response();
+    assert_eq!(response.status(), 429);
+    assert_eq!(response.headers()["retry-after"], "1");
+    assert!(!response.headers().contains_key("x-queue-depth"));
+    assert!(UniModelError::validation("bad input").overload_hint().is_none());
+}
+
+/// 延迟为0的模拟后端和指定批大小、等待时间的配置
+fn batching_config(max_batch_size: u32, max_wait_time_ms: u64) -> Config {
+    let mut config = Config::default();
+    config.engine.batch_config.max_batch_size = max_batch_size;
+    config.engine.batch_config.max_wait_time_ms = max_wait_time_ms;
+    config.plugins.plugin_configs.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 0 } }),
+    );
+    config
+}
+
+/// 在后台提交一个文本请求
+fn spawn_submit(
+    processor: &Arc<BatchProcessor>,
+    model_id: &str,
+    text: &str,
+) -> tokio::task::JoinHandle<unimodel::Result<unimodel::domain::service::batch_processor::PredictionResponse>> {
+    let (processor, model_id, text) = (Arc::clone(processor), model_id.to_string(), text.to_string());
+    tokio::spawn(async move {
+        processor.submit_request(model_id, InputData::Text(text), PredictionParameters::default(), None).await
+    })
+}
+
+#[tokio::test]
+async fn test_batch_processor_rejects_when_queue_is_full() {
+    let mut config = batching_config(8, 300);
+    config.engine.batch_config.max_queue_size = 2;
+    let processor = Arc::new(BatchProcessor::new(&config).await.unwrap());
+    processor.start().await.unwrap();
+
+    let queued = vec![spawn_submit(&processor, "m1", "q0"), spawn_submit(&processor, "m1", "q1")];
+    let deadline = Instant::now() + Duration::from_secs(2);
+    while processor.get_batch_stats().await.pending_requests < 2 {
+        assert!(Instant::now() < deadline, "requests were not queued");
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+
+    // 队列已满时立即拒绝而不是继续缓冲
+    let err = processor
+        .submit_request("m1".to_string(), InputData::Text("q2".to_string()), PredictionParameters::default(), None)
+        .await
+        .unwrap_err();
+    assert!(matches!(err, UniModelError::Overloaded { .. }), "{:?}", err);
+    assert_eq!(err.status_code(), 503);
+    assert_eq!(err.overload_hint().unwrap().queue_depth, Some(2));
+
+    for handle in queued {
+        handle.await.unwrap().unwrap();
+    }
+    assert_eq!(processor.get_batch_stats().await.pending_requests, 0);
+    spawn_submit(&processor, "m1", "q3").await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn test_batch_processor_queues_models_independently() {
+    let processor = Arc::new(BatchProcessor::new(&batching_config(4, 500)).await.unwrap());
+    processor.start().await.unwrap();
+
+    // 模型a的请求在等待凑批，不影响模型b凑满的批次立即执行
+    let started = Instant::now();
+    let lone = spawn_submit(&processor, "a", "lone");
+    let full: Vec<_> = (0..4).map(|i| spawn_submit(&processor, "b", &format!("b{}", i))).collect();
+    for handle in full {
+        let response = handle.await.unwrap().unwrap();
+        assert_eq!((response.model_id.as_str(), response.metrics.batch_size), ("b", 4));
+    }
+    assert!(started.elapsed() < Duration::from_millis(400), "model b waited {:?}", started.elapsed());
+    assert!(!lone.is_finished());
+
+    let response = lone.await.unwrap().unwrap();
+    assert_eq!((response.model_id.as_str(), response.metrics.batch_size), ("a", 1));
+    assert!(started.elapsed() >= Duration::from_millis(500));
+}
+
+#[tokio::test]
+async fn test_batch_processor_dispatches_on_size_or_wait_time() {
+    let processor = Arc::new(BatchProcessor::new(&batching_config(2, 300)).await.unwrap());
+    processor.start().await.unwrap();
+
+    // 凑满批大小时立即下发
+    let started = Instant::now();
+    let (first, second) = tokio::join!(spawn_submit(&processor, "m1", "x"), spawn_submit(&processor, "m1", "y"));
+    for response in [first.unwrap().unwrap(), second.unwrap().unwrap()] {
+        assert_eq!(response.metrics.batch_size, 2);
+    }
+    assert!(started.elapsed() < Duration::from_millis(250), "full batch waited {:?}", started.elapsed());
+
+    // 凑不满时在队首请求等待 `max_wait_time_ms` 后下发
+    let started = Instant::now();
+    let response = spawn_submit(&processor, "m1", "z").await.unwrap().unwrap();
+    let elapsed = started.elapsed();
+    assert_eq!(response.metrics.batch_size, 1);
+    assert!(elapsed >= Duration::from_millis(300) && elapsed < Duration::from_millis(1500), "{:?}", elapsed);
+}
+
+#[tokio::test]
+async fn test_dead_letter_store_captures_failed_batches() {
+    use unimodel::DeadLetterStore;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("dead_letters.json");
+    let store = Arc::new(DeadLetterStore::open(&path, 2).await.unwrap());
+    let mut config = batching_config(1, 0);
+    config.plugins.plugin_configs.insert(
+        "mock".to_string(),
+        serde_json::json!({ "latency": { "distribution": "fixed", "ms": 0 }, "error_rate": 1.0 }),
+    );
+    let processor = BatchProcessor::new(&config).await.unwrap().with_dead_letter_store(Arc::clone(&store));
+    processor.start().await.unwrap();
+
+    let parameters = PredictionParameters { max_tokens: Some(7), ..PredictionParameters::default() };
+    for i in 0..3 {
+        let err = processor
+            .submit_request("m1".to_string(), InputData::Text(format!("req-{}", i)), parameters.clone(), Some("acme"))
+            .await
+            .unwrap_err();
+        assert!(matches!(err, UniModelError::BatchProcessing(_)), "{:?}", err);
+    }
+
+    // 永久失败的请求连同输入、参数和错误保存，超过容量时丢弃最早的记录，最新的在前
+    let records = store.list(None, None, 10).await;
+    let inputs: Vec<_> = records.iter().map(|r| r.input.clone()).collect();
+    assert!(matches!(inputs.as_slice(), [InputData::Text(a), InputData::Text(b)] if a == "req-2" && b == "req-1"));
+    assert_eq!((records[0].model_id.as_str(), records[0].tenant.as_deref()), ("m1", Some("acme")));
+    assert_eq!((records[0].error_code.as_str(), records[0].parameters.max_tokens), ("PLUGIN_ERROR", Some(7)));
+    assert!(store.list(Some("other"), None, 10).await.is_empty());
+    assert_eq!(store.list(Some("m1"), Some("acme"), 1).await.len(), 1);
+
+    let id = records[0].id.clone();
+    assert!(store.get(&id).await.is_some());
+    assert_eq!(store.remove(&id).await.unwrap().id, id);
+    assert!(store.get(&id).await.is_none());
+
+    store.flush().await.unwrap();
+    let reopened = DeadLetterStore::open(&path, 10).await.unwrap();
+    let records = reopened.list(None, None, 10).await;
+    assert_eq!(records.len(), 1);
+    assert!(matches!(&records[0].input, InputData::Text(text) if text == "req-1"));
+}
+
+#[tokio::test]
+async fn test_response_cache_limits_and_expiry() {
+    use unimodel::common::types::{request_fingerprint, Priority};
+    use unimodel::infrastructure::configuration::{ModelCacheOverride, ResponseCacheConfig};
+    use unimodel::ResponseCache;
+
+    let processor = BatchProcessor::new(&batching_config(1, 0)).await.unwrap();
+    processor.start().await.unwrap();
+    let response = processor
+        .submit_request("m1".to_string(), InputData::Text("hello".to_string()), PredictionParameters::default(), None)
+        .await
+        .unwrap();
+
+    let mut models = HashMap::new();
+    models.insert("small".to_string(), ModelCacheOverride { max_entries: Some(1), ..Default::default() });
+    models.insert("expiring".to_string(), ModelCacheOverride { ttl_secs: Some(0), ..Default::default() });
+    models.insert("off".to_string(), ModelCacheOverride { enabled: Some(false), ..Default::default() });
+    let cache = ResponseCache::new(&ResponseCacheConfig { enabled: true, max_entries: 10, ttl_secs: 60, models });
+    assert!(cache.is_enabled_for("big") && !cache.is_enabled_for("off"));
+
+    // 键只取决于模型、输入和影响输出的参数；只缓存确定性请求
+    let input = InputData::Text("hello".to_string());
+    let parameters = PredictionParameters::default();
+    let key = request_fingerprint("big", &input, &parameters);
+    let urgent = PredictionParameters { priority: Priority::High, timeout_ms: Some(5), ..PredictionParameters::default() };
+    assert_eq!(key, request_fingerprint("big", &input, &urgent));
+    assert_ne!(key, request_fingerprint("small", &input, &parameters));
+    let limited = PredictionParameters { max_tokens: Some(3), ..PredictionParameters::default() };
+    assert_ne!(key, request_fingerprint("big", &input, &limited));
+    let sampled = PredictionParameters { temperature: Some(0.8), ..PredictionParameters::default() };
+    assert!(parameters.is_deterministic() && !sampled.is_deterministic());
+    assert!(PredictionParameters { seed: Some(1), ..sampled }.is_deterministic());
+
+    let (big, small, expiring) = ("big".to_string(), "small".to_string(), "expiring".to_string());
+    cache.put(&big, key.clone(), response.clone());
+    assert_eq!(cache.get(&big, &key).unwrap().request_id, response.request_id);
+
+    // 每个模型单独限制容量，淘汰最久未用的条目
+    cache.put(&small, "a".to_string(), response.clone());
+    cache.put(&small, "b".to_string(), response.clone());
+    assert!(cache.get(&small, "a").is_none());
+    assert!(cache.get(&small, "b").is_some());
+    assert!(cache.get(&big, &key).is_some());
+
+    cache.put(&expiring, "a".to_string(), response.clone());
+    assert!(cache.get(&expiring, "a").is_none());
+
+    cache.invalidate_model(&big);
+    assert!(cache.get(&big, &key).is_none());
+}
+
+#[tokio::test]
+async fn test_oom_evicts_least_recently_used_model_on_same_gpu() {
+    use unimodel::{ModelManager, ModelStatus};
+
+    let dir = tempfile::tempdir().unwrap();
+    let mut config = batching_config(1, 0);
+    config.plugins.plugin_dir = dir.path().join("plugins").display().to_string();
+    let model_manager = Arc::new(ModelManager::new(&config).await.unwrap());
+    let processor = BatchProcessor::new(&config).await.unwrap().with_model_manager(Arc::clone(&model_manager));
+    processor.start().await.unwrap();
+
+    let mock_on = |device: u32, mock: serde_json::Value| {
+        let mut model_config = gpu_model_config(1000);
+        model_config.backend = "mock".to_string();
+        model_config.device.device_ids = vec![device];
+        model_config.custom_params.insert("mock".to_string(), mock);
+        model_config
+    };
+    let healthy = serde_json::json!({ "latency": { "distribution": "fixed", "ms": 0 } });
+    let oom = serde_json::json!({ "latency": { "distribution": "fixed", "ms": 0 }, "error_rate": 1.0, "error_kind": "out_of_memory" });
+    let mut ids = Vec::new();
+    for (name, model_config) in [
+        ("older", mock_on(0, healthy.clone())),
+        ("newer", mock_on(0, healthy.clone())),
+        ("other-gpu", mock_on(1, healthy.clone())),
+        ("pinned", mock_on(0, healthy.clone())),
+        ("failing", mock_on(0, oom)),
+    ] {
+        ids.push(model_manager.register_model(name.to_string(), ModelType::LLM, model_config, None).await.unwrap());
+    }
+    let status = |id: &String| {
+        let model_manager = Arc::clone(&model_manager);
+        let id = id.clone();
+        async move { model_manager.get_model_info(&id).await.unwrap().status }
+    };
+    let deadline = Instant::now() + Duration::from_secs(5);
+    for id in &ids {
+        while status(id).await != ModelStatus::Ready {
+            assert!(Instant::now() < deadline, "model {} did not load", id);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    }
+    let (older, newer, other_gpu, pinned, failing) = (&ids[0], &ids[1], &ids[2], &ids[3], &ids[4]);
+    model_manager.set_pinned(pinned, true).await.unwrap();
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    model_manager.get_model_for_inference(newer).await.unwrap();
+
+    // 显存不足时逐出同一GPU上最久未访问的未固定模型并重试一次，仍失败时返回错误
+    let err = processor
+        .submit_request(failing.clone(), InputData::Text("x".to_string()), PredictionParameters::default(), None)
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("Injected mock backend failure"), "{:?}", err);
+    assert_eq!(status(older).await, ModelStatus::Unloaded);
+    for id in [newer, other_gpu, pinned] {
+        assert_eq!(status(id).await, ModelStatus::Ready, "{} should stay loaded", id);
+    }
+
+    // 其他GPU上的模型和固定的模型不会被逐出
+    assert_eq!(model_manager.evict_for_oom(failing).await.as_ref(), Some(newer));
+    assert_eq!(model_manager.evict_for_oom(failing).await, None);
+}
+
+/// 回显输入的进程外插件，记录加载次数
+#[derive(Debug, Default)]
+struct EchoRemotePlugin {
+    loads: Arc<std::sync::atomic::AtomicUsize>,
+}
+
+#[tonic::async_trait]
+impl unimodel::api::grpc::proto::plugin::model_plugin_server::ModelPlugin for EchoRemotePlugin {
+    async fn handshake(
+        &self,
+        request: tonic::Request<unimodel::api::grpc::proto::plugin::HandshakeRequest>,
+    ) -> Result<tonic::Response<unimodel::api::grpc::proto::plugin::HandshakeResponse>, tonic::Status> {
+        Ok(tonic::Response::new(unimodel::api::grpc::proto::plugin::HandshakeResponse {
+            protocol_version: request.into_inner().protocol_version,
+            name: "echo".to_string(),
+            version: "0.1.0".to_string(),
+            backends: vec!["remote-echo".to_string()],
+            capabilities: Vec::new(),
+        }))
+    }
+
+    async fn load_model(
+        &self,
+        request: tonic::Request<unimodel::api::grpc::proto::plugin::LoadModelRequest>,
+    ) -> Result<tonic::Response<unimodel::api::grpc::proto::plugin::LoadModelResponse>, tonic::Status> {
+        let loads = self.loads.fetch_add(1, std::sync::atomic::Ordering::SeqCst) + 1;
+        Ok(tonic::Response::new(unimodel::api::grpc::proto::plugin::LoadModelResponse {
+            handle: format!("{}#{}", request.into_inner().model_id, loads),
+            supports_batching: true,
+            max_batch_size: 0,
+            precision: String::new(),
+        }))
+    }
+
+    async fn unload_model(
+        &self,
+        _request: tonic::Request<unimodel::api::grpc::proto::plugin::UnloadModelRequest>,
+    ) -> Result<tonic::Response<unimodel::api::grpc::proto::plugin::UnloadModelResponse>, tonic::Status> {
+        Ok(tonic::Response::new(unimodel::api::grpc::proto::plugin::UnloadModelResponse {}))
+    }
+
+    async fn infer(
+        &self,
+        request: tonic::Request<unimodel::api::grpc::proto::plugin::InferRequest>,
+    ) -> Result<tonic::Response<unimodel::api::grpc::proto::plugin::InferResponse>, tonic::Status> {
+        let request = request.into_inner();
+        let mut outputs = Vec::with_capacity(request.inputs.len());
+        for input in request.inputs {
+            match InputData::try_from(input) {
+                Ok(InputData::Text(text)) => outputs.push(OutputData::Text(format!("{}: {}", request.handle, text)).into()),
+                _ => return Err(tonic::Status::invalid_argument("text input expected")),
+            }
+        }
+        Ok(tonic::Response::new(unimodel::api::grpc::proto::plugin::InferResponse { outputs }))
+    }
+
+    async fn health(
+        &self,
+        _request: tonic::Request<unimodel::api::grpc::proto::plugin::HealthRequest>,
+    ) -> Result<tonic::Response<unimodel::api::grpc::proto::plugin::HealthResponse>, tonic::Status> {
+        Ok(tonic::Response::new(unimodel::api::grpc::proto::plugin::HealthResponse {
+            healthy: true,
+            message: String::new(),
+        }))
+    }
+}
+
+#[tokio::test]
+async fn test_remote_plugin_protocol_and_restart() {
+    use unimodel::api::grpc::proto::plugin::model_plugin_server::ModelPluginServer;
+    use unimodel::infrastructure::configuration::ExternalPluginConfig;
+    use unimodel::plugins::manager::remote_plugin::RemotePlugin;
+
+    // 插件进程把服务器给的套接字路径链接到测试中提供协议的套接字
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("echo.sock");
+    let listener = tokio::net::UnixListener::bind(&socket).unwrap();
+    let loads = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let service = ModelPluginServer::new(EchoRemotePlugin { loads: Arc::clone(&loads) });
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(service)
+            .serve_with_incoming(tokio_stream::wrappers::UnixListenerStream::new(listener)),
+    );
+    let config = ExternalPluginConfig {
+        name: "protocol-test".to_string(),
+        command: "sh".to_string(),
+        args: vec![
+            "-c".to_string(),
+            format!("ln -sf '{}' \"$UNIMODEL_PLUGIN_SOCKET\" && exec sleep 60", socket.display()),
+        ],
+        env: HashMap::new(),
+        startup_timeout_secs: 5,
+        max_restarts: 3,
+        limits: Default::default(),
+    };
+    let plugin = RemotePlugin::start(config, Duration::from_secs(5), None).await.unwrap();
+    assert_eq!((plugin.version(), plugin.backends()), ("0.1.0".to_string(), vec!["remote-echo".to_string()]));
+    assert!(plugin.health().await.unwrap());
+
+    let model_id = "remote".to_string();
+    let mut model_config = gpu_model_config(1000);
+    model_config.backend = "remote-echo".to_string();
+    let loaded = plugin.load_model(&model_id, &model_config, None, Precision::FP32).await.unwrap();
+    assert_eq!(loaded.handle, "remote#1");
+    let parameters = PredictionParameters::default();
+    let inputs = vec![InputData::Text("a".to_string()), InputData::Text("b".to_string())];
+    let outputs = plugin.infer(&model_id, inputs, &parameters, 0).await.unwrap();
+    assert!(matches!(outputs.as_slice(), [OutputData::Text(a), OutputData::Text(b)] if a == "remote#1: a" && b == "remote#1: b"));
+
+    // 进程退出后重启并重新加载模型，之后的调用使用新句柄
+    plugin.kill();
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while !(plugin.is_ready() && loads.load(std::sync::atomic::Ordering::SeqCst) == 2) {
+        assert!(Instant::now() < deadline, "plugin was not restarted");
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let outputs = plugin.infer(&model_id, vec![InputData::Text("c".to_string())], &parameters, 0).await.unwrap();
+    assert!(matches!(outputs.as_slice(), [OutputData::Text(c)] if c == "remote#2: c"));
+
+    plugin.unload_model(&model_id).await.unwrap();
+    assert!(plugin.infer(&model_id, vec![InputData::Text("d".to_string())], &parameters, 0).await.is_err());
+    plugin.shutdown();
+}
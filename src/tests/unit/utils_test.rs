@@ -1,8 +1,19 @@
 //! 单元测试
 
+use std::collections::HashMap;
+
 use unimodel::common::types::*;
 use unimodel::common::error::*;
 use unimodel::domain::model::*;
+use unimodel::infrastructure::configuration::{Config, PreloadModelConfig};
+use unimodel::infrastructure::model_format::{read_onnx_signature, GgufHeader, GgufValue, SafeTensorsFile};
+use unimodel::infrastructure::preprocessing::{is_video, preprocess_audio, preprocess_image, resample, split_frame_outputs};
+use unimodel::infrastructure::tensor_format::{read_arrow_ipc, read_arrow_table, read_npy, read_npz, unstack, write_arrow_ipc};
+use unimodel::api::rest::content::{deserialize_body, read_multipart, serialize_body, WireFormat, ARROW_STREAM_CONTENT_TYPE};
+use unimodel::domain::service::parallelism::{ParallelPlan, PipelinePlan, ShardSplit, TensorParallelPlan};
+use unimodel::plugins::interface::ModelWeights;
+use unimodel::infrastructure::monitoring::{parse_rocm_smi_csv, DeviceProbe};
+use unimodel::infrastructure::storage::file_system::JsonFile;
 
 #[test]
 fn test_model_id_validation() {
@@ -15,7 +26,7 @@ fn test_model_id_validation() {
     ];
 
     for id in valid_ids {
-        assert!(id.len() > 0);
+        assert!(!id.is_empty());
         // 这里应该有更详细的验证逻辑
     }
 
@@ -67,22 +78,22 @@ fn test_prediction_parameters() {
     let mut params = PredictionParameters::default();
 
     // 测试默认值
-    assert_eq!(params.temperature, 1.0);
-    assert_eq!(params.max_tokens, 100);
-    assert_eq!(params.top_p, 0.9);
-    assert_eq!(params.top_k, 50);
+    assert_eq!(params.temperature, None);
+    assert_eq!(params.max_tokens, None);
+    assert_eq!(params.top_p, None);
+    assert_eq!(params.top_k, None);
 
     // 测试参数修改
-    params.temperature = 0.8;
-    params.max_tokens = 200;
-    assert_eq!(params.temperature, 0.8);
-    assert_eq!(params.max_tokens, 200);
+    params.temperature = Some(0.8);
+    params.max_tokens = Some(200);
+    assert_eq!(params.temperature, Some(0.8));
+    assert_eq!(params.max_tokens, Some(200));
 
     // 测试序列化
     let serialized = serde_json::to_string(&params).unwrap();
     let deserialized: PredictionParameters = serde_json::from_str(&serialized).unwrap();
-    assert_eq!(deserialized.temperature, 0.8);
-    assert_eq!(deserialized.max_tokens, 200);
+    assert_eq!(deserialized.temperature, Some(0.8));
+    assert_eq!(deserialized.max_tokens, Some(200));
 }
 
 #[test]
@@ -102,8 +113,9 @@ fn test_error_handling() {
 
     // 测试错误链
     let source_error = std::io::Error::new(std::io::ErrorKind::NotFound, "File not found");
-    let chained_error = UniModelError::from_source("File operation failed", source_error);
-    assert!(chained_error.to_string().contains("File operation failed"));
+    let chained_error = UniModelError::from(source_error);
+    assert_eq!(chained_error.error_code(), "IO_ERROR");
+    assert!(chained_error.to_string().contains("File not found"));
 }
 
 #[test]
@@ -119,6 +131,8 @@ fn test_model_config_validation() {
             device_ids: vec![0],
             memory_limit_mb: Some(2048),
             mixed_precision: true,
+            parallelism: Parallelism::None,
+            micro_batches: None,
         },
         optimization: OptimizationConfig {
             kv_cache: true,
@@ -126,12 +140,23 @@ fn test_model_config_validation() {
             graph_optimization: true,
             inference_parallelism: 2,
             memory_optimization: MemoryOptimization::High,
+            cuda_graphs: false,
+            cuda_streams: 1,
         },
         batch_config: BatchConfig {
             max_batch_size: 32,
             max_wait_time_ms: 100,
             timeout_ms: 30000,
+            ..Default::default()
         },
+        max_concurrent_batches: Some(2),
+        warmup: WarmupConfig::default(),
+        signature: None,
+        input_schema: None,
+        preprocessing: None,
+        postprocessing: None,
+        labels_path: None,
+        speculative: None,
         custom_params: std::collections::HashMap::new(),
     };
 
@@ -169,4 +194,1249 @@ fn test_performance_metrics() {
     assert_eq!(deserialized.total_latency_ms, 150);
     assert_eq!(deserialized.batch_size, 8);
     assert_eq!(deserialized.tokens_generated, Some(50));
-}
\ No newline at end of file
+}
+#[test]
+fn test_priority_parsing() {
+    assert_eq!("high".parse::<Priority>().unwrap(), Priority::High);
+    assert_eq!(" LOW ".parse::<Priority>().unwrap(), Priority::Low);
+    assert!("urgent".parse::<Priority>().is_err());
+
+    // 未指定时为normal
+    let params: PredictionParameters = serde_json::from_str(r#"{"custom": {}}"#).unwrap();
+    assert_eq!(params.priority, Priority::Normal);
+    assert!(Priority::High.rank() < Priority::Low.rank());
+}
+
+#[test]
+fn test_retriable_error_classification() {
+    assert!(UniModelError::out_of_memory("CUDA out of memory").is_retriable());
+    assert!(UniModelError::plugin_unavailable("plugin restarting").is_retriable());
+    assert!(!UniModelError::validation("bad input").is_retriable());
+    assert!(!UniModelError::plugin("unsupported op").is_retriable());
+
+    let retry = RetryConfig::default();
+    assert_eq!(retry.backoff(0), std::time::Duration::from_millis(50));
+    assert_eq!(retry.backoff(2), std::time::Duration::from_millis(200));
+    assert_eq!(retry.backoff(10), std::time::Duration::from_millis(1000));
+}
+
+#[test]
+fn test_request_fingerprint() {
+    let input = InputData::Text("hello".to_string());
+    let mut parameters = PredictionParameters::default();
+    let base = request_fingerprint("model-a", &input, &parameters);
+
+    // 优先级不影响推理结果
+    parameters.priority = Priority::High;
+    assert_eq!(request_fingerprint("model-a", &input, &parameters), base);
+
+    parameters.max_tokens = Some(16);
+    assert_ne!(request_fingerprint("model-a", &input, &parameters), base);
+    assert_ne!(request_fingerprint("model-b", &input, &PredictionParameters::default()), base);
+
+    parameters.temperature = Some(0.7);
+    assert!(!parameters.is_deterministic());
+
+    // 固定种子的采样可复现
+    parameters.seed = Some(42);
+    assert!(parameters.is_deterministic());
+    let seeded = request_fingerprint("model-a", &input, &parameters);
+    parameters.seed = Some(43);
+    assert_ne!(request_fingerprint("model-a", &input, &parameters), seeded);
+}
+
+#[test]
+fn test_preload_model_config_defaults() {
+    let entry: PreloadModelConfig = serde_json::from_value(serde_json::json!({
+        "name": "llama-7b",
+        "model_type": "LLM",
+        "backend": "pytorch",
+        "model_path": "/models/llama-7b"
+    }))
+    .unwrap();
+
+    assert_eq!(entry.model_type, ModelType::LLM);
+    assert!(entry.tenant.is_none());
+    assert!(entry.device_ids.is_empty());
+    assert!(!entry.pinned);
+    assert_eq!(entry.warmup.requests, 0);
+    assert!(Config::default().engine.preload.is_empty());
+}
+
+#[test]
+fn test_external_plugin_limits_config() {
+    use unimodel::infrastructure::configuration::ExternalPluginConfig;
+
+    let plugin: ExternalPluginConfig = serde_json::from_value(serde_json::json!({
+        "name": "python-bridge",
+        "command": "/opt/plugins/python-bridge",
+        "limits": { "memory_mb": 2048, "cpu_cores": 1.5 }
+    }))
+    .unwrap();
+    assert!(plugin.limits.is_limited());
+    assert_eq!(plugin.limits.memory_mb, Some(2048));
+    assert_eq!(plugin.limits.cpu_cores, Some(1.5));
+
+    // 未配置上限时不限制
+    let plugin: ExternalPluginConfig = serde_json::from_value(serde_json::json!({
+        "name": "echo",
+        "command": "echo-plugin"
+    }))
+    .unwrap();
+    assert!(!plugin.limits.is_limited());
+    assert!(Config::default().plugins.cgroup_root.is_none());
+    assert!(!Config::default().plugins.isolate_native_plugins);
+}
+
+/// 按 safetensors 布局拼出文件内容
+fn safetensors_bytes(header: serde_json::Value, data: &[u8]) -> Vec<u8> {
+    let header = serde_json::to_vec(&header).unwrap();
+    let mut bytes = (header.len() as u64).to_le_bytes().to_vec();
+    bytes.extend_from_slice(&header);
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn test_safetensors_parsing() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.safetensors");
+
+    let weight: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let bias: Vec<u8> = [0.5f32, -0.5].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let mut data = weight.clone();
+    data.extend_from_slice(&bias);
+
+    let header = serde_json::json!({
+        "__metadata__": { "format": "pt" },
+        "linear.bias": { "dtype": "F32", "shape": [2], "data_offsets": [16, 24] },
+        "linear.weight": { "dtype": "F32", "shape": [2, 2], "data_offsets": [0, 16] }
+    });
+    std::fs::write(&path, safetensors_bytes(header, &data)).unwrap();
+
+    let file = SafeTensorsFile::open(&path).unwrap();
+    assert_eq!(file.metadata().get("format").map(String::as_str), Some("pt"));
+    assert_eq!(file.num_parameters(), 6);
+    // 按数据偏移排序
+    assert_eq!(file.tensors()[0].name, "linear.weight");
+
+    let tensor = file.tensor("linear.weight").unwrap();
+    assert_eq!(tensor.info.dtype, DataType::F32);
+    assert_eq!(tensor.info.shape, vec![2, 2]);
+    assert_eq!(tensor.data, &weight[..]);
+    assert_eq!(file.tensor("linear.bias").unwrap().data, &bias[..]);
+    assert!(file.tensor("missing").is_none());
+
+    // 数据长度与形状不符
+    let bad = serde_json::json!({
+        "w": { "dtype": "F32", "shape": [3], "data_offsets": [0, 8] }
+    });
+    std::fs::write(&path, safetensors_bytes(bad, &[0u8; 8])).unwrap();
+    assert!(SafeTensorsFile::open(&path).is_err());
+}
+
+#[test]
+fn test_tensor_parallel_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.safetensors");
+    let header = serde_json::json!({
+        "layers.0.self_attn.q_proj.weight": { "dtype": "F32", "shape": [4, 2], "data_offsets": [0, 32] },
+        "layers.0.self_attn.o_proj.weight": { "dtype": "F32", "shape": [2, 4], "data_offsets": [32, 64] },
+        "layers.0.input_layernorm.weight": { "dtype": "F32", "shape": [3], "data_offsets": [64, 76] }
+    });
+    std::fs::write(&path, safetensors_bytes(header, &[0u8; 76])).unwrap();
+    let weights = ModelWeights::open_path(&path).unwrap();
+
+    let plan = TensorParallelPlan::new(&weights, &[2, 3]).unwrap();
+    assert_eq!(plan.world_size(), 2);
+
+    // 列并行按输出维度切分
+    let q_proj = plan.tensor("layers.0.self_attn.q_proj.weight").unwrap();
+    assert_eq!(q_proj.split, ShardSplit::Column);
+    assert_eq!(q_proj.shard_shape, vec![2, 2]);
+    assert_eq!(q_proj.range(1), Some((2, 4)));
+
+    // 行并行按输入维度切分
+    let o_proj = plan.tensor("layers.0.self_attn.o_proj.weight").unwrap();
+    assert_eq!(o_proj.split, ShardSplit::Row);
+    assert_eq!(o_proj.shard_shape, vec![2, 2]);
+
+    // 归一化权重在每张卡上复制，维度不必整除
+    let norm = plan.tensor("layers.0.input_layernorm.weight").unwrap();
+    assert_eq!(norm.split, ShardSplit::Replicated);
+    assert_eq!(norm.shard_shape, vec![3]);
+    assert_eq!(norm.range(0), None);
+
+    // 被切分的维度不能被GPU数整除
+    assert!(TensorParallelPlan::new(&weights, &[0, 1, 2]).is_err());
+    assert!(TensorParallelPlan::new(&weights, &[0]).is_err());
+
+    // 非safetensors权重由后端自行切分
+    let plan = TensorParallelPlan::new(&ModelWeights::open_path(&dir.path().join("model.onnx")).unwrap(), &[0, 1]).unwrap();
+    assert!(plan.tensors.is_empty());
+}
+
+#[test]
+fn test_pipeline_plan() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.safetensors");
+    let mut header = serde_json::Map::new();
+    let mut names = vec!["model.embed_tokens.weight".to_string(), "model.norm.weight".to_string(), "lm_head.weight".to_string()];
+    names.extend((0..5).map(|layer| format!("model.layers.{}.mlp.down_proj.weight", layer)));
+    for (i, name) in names.iter().enumerate() {
+        header.insert(name.clone(), serde_json::json!({ "dtype": "F32", "shape": [1], "data_offsets": [i * 4, i * 4 + 4] }));
+    }
+    std::fs::write(&path, safetensors_bytes(serde_json::Value::Object(header), &vec![0u8; names.len() * 4])).unwrap();
+    let weights = ModelWeights::open_path(&path).unwrap();
+
+    // 5层分到2个阶段，第一个阶段多分一层
+    let plan = PipelinePlan::new(&weights, &[4, 5], 4).unwrap();
+    assert_eq!(plan.num_layers, 5);
+    assert_eq!((plan.stages[0].device_id, plan.stages[0].first_layer, plan.stages[0].end_layer), (4, 0, 3));
+    assert_eq!((plan.stages[1].device_id, plan.stages[1].first_layer, plan.stages[1].end_layer), (5, 3, 5));
+    assert_eq!(plan.stage_of("model.layers.2.mlp.down_proj.weight"), Some(0));
+    assert_eq!(plan.stage_of("model.layers.3.mlp.down_proj.weight"), Some(1));
+    // 嵌入在第一个阶段，最后的归一化和输出头在最后一个阶段
+    assert_eq!(plan.stage_of("model.embed_tokens.weight"), Some(0));
+    assert_eq!(plan.stage_of("model.norm.weight"), Some(1));
+    assert_eq!(plan.stage_of("lm_head.weight"), Some(1));
+
+    // 阶段数多于层数
+    assert!(PipelinePlan::new(&weights, &[0, 1, 2, 3, 4, 5], 1).is_err());
+
+    // 方案按配置生成，序列化时带上类型
+    let mut config: ModelConfig = serde_json::from_value(serde_json::json!({
+        "model_path": path.to_string_lossy(), "backend": "vllm",
+        "device": { "device_type": "CUDA", "device_ids": [0, 1, 2], "memory_limit_mb": null,
+                    "mixed_precision": false, "parallelism": "pipeline" },
+        "optimization": { "kv_cache": true, "quantization": null, "graph_optimization": false,
+                          "inference_parallelism": 1, "memory_optimization": "Low" },
+        "batch_config": { "max_batch_size": 8, "max_wait_time_ms": 10, "dynamic_padding": false, "timeout_ms": 1000 },
+        "custom_params": {}
+    }))
+    .unwrap();
+    let plan = ParallelPlan::for_model(&config, &weights).unwrap().unwrap();
+    assert_eq!(serde_json::to_value(&plan).unwrap()["kind"], "pipeline");
+    match plan {
+        ParallelPlan::Pipeline(plan) => assert_eq!((plan.stages.len(), plan.micro_batches), (3, 3)),
+        other => panic!("unexpected plan {:?}", other),
+    }
+    config.device.parallelism = Parallelism::None;
+    assert!(ParallelPlan::for_model(&config, &weights).unwrap().is_none());
+}
+
+/// GGUF 字符串：u64长度 + 字节
+fn gguf_string(out: &mut Vec<u8>, value: &str) {
+    out.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    out.extend_from_slice(value.as_bytes());
+}
+
+#[test]
+fn test_gguf_header_parsing() {
+    let mut bytes = b"GGUF".to_vec();
+    bytes.extend_from_slice(&3u32.to_le_bytes()); // 版本
+    bytes.extend_from_slice(&1u64.to_le_bytes()); // 张量数
+    bytes.extend_from_slice(&4u64.to_le_bytes()); // 元数据数
+
+    gguf_string(&mut bytes, "general.architecture");
+    bytes.extend_from_slice(&8u32.to_le_bytes());
+    gguf_string(&mut bytes, "llama");
+
+    gguf_string(&mut bytes, "llama.context_length");
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    bytes.extend_from_slice(&4096u32.to_le_bytes());
+
+    gguf_string(&mut bytes, "general.file_type");
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    bytes.extend_from_slice(&15u32.to_le_bytes());
+
+    // 字符串数组只记录长度
+    gguf_string(&mut bytes, "tokenizer.ggml.tokens");
+    bytes.extend_from_slice(&9u32.to_le_bytes());
+    bytes.extend_from_slice(&8u32.to_le_bytes());
+    bytes.extend_from_slice(&2u64.to_le_bytes());
+    gguf_string(&mut bytes, "a");
+    gguf_string(&mut bytes, "bc");
+
+    gguf_string(&mut bytes, "token_embd.weight");
+    bytes.extend_from_slice(&2u32.to_le_bytes());
+    bytes.extend_from_slice(&4u64.to_le_bytes());
+    bytes.extend_from_slice(&8u64.to_le_bytes());
+    bytes.extend_from_slice(&0u32.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.gguf");
+    std::fs::write(&path, &bytes).unwrap();
+
+    let header = GgufHeader::read(&path).unwrap();
+    assert_eq!(header.version, 3);
+    assert_eq!(header.architecture(), Some("llama"));
+    assert_eq!(header.context_length(), Some(4096));
+    assert_eq!(header.quantization().as_deref(), Some("Q4_K_M"));
+    assert_eq!(header.parameter_count(), 32);
+    assert_eq!(header.metadata.get("tokenizer.ggml.tokens"), Some(&GgufValue::Array(2)));
+    assert_eq!(header.tensors[0].name, "token_embd.weight");
+
+    // 截断的文件
+    std::fs::write(&path, &bytes[..bytes.len() - 4]).unwrap();
+    assert!(GgufHeader::read(&path).is_err());
+}
+
+/// 编码protobuf长度分隔字段
+fn proto_field(field: u32, payload: &[u8]) -> Vec<u8> {
+    let mut out = proto_varint(((field as u64) << 3) | 2);
+    out.extend(proto_varint(payload.len() as u64));
+    out.extend_from_slice(payload);
+    out
+}
+
+fn proto_varint(mut value: u64) -> Vec<u8> {
+    let mut out = Vec::new();
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return out;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+/// ValueInfoProto：维度为None时编码为符号维度
+fn onnx_value_info(name: &str, elem_type: u64, dims: &[Option<u64>]) -> Vec<u8> {
+    let mut shape = Vec::new();
+    for dim in dims {
+        let dim = match dim {
+            Some(size) => [proto_varint(1 << 3), proto_varint(*size)].concat(),
+            None => proto_field(2, b"batch"),
+        };
+        shape.extend(proto_field(1, &dim));
+    }
+    let tensor_type = [proto_varint(1 << 3), proto_varint(elem_type), proto_field(2, &shape)].concat();
+    [proto_field(1, name.as_bytes()), proto_field(2, &proto_field(1, &tensor_type))].concat()
+}
+
+#[test]
+fn test_onnx_signature_parsing() {
+    let initializer = [proto_field(8, b"weight"), proto_field(9, &[0u8; 16])].concat();
+    let graph = [
+        proto_field(1, b"node"),
+        proto_field(5, &initializer),
+        proto_field(11, &onnx_value_info("pixel_values", 1, &[None, Some(3), Some(224), Some(224)])),
+        proto_field(11, &onnx_value_info("weight", 1, &[Some(4)])),
+        proto_field(12, &onnx_value_info("logits", 1, &[None, Some(1000)])),
+    ]
+    .concat();
+    let model = [proto_varint(1 << 3), proto_varint(8), proto_field(7, &graph)].concat();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.onnx");
+    std::fs::write(&path, &model).unwrap();
+
+    let signature = read_onnx_signature(&path).unwrap();
+    assert_eq!(signature.source, SignatureSource::Introspected);
+    // 权重不计入输入
+    assert_eq!(signature.inputs.len(), 1);
+    assert_eq!(signature.inputs[0].name, "pixel_values");
+    assert_eq!(signature.inputs[0].dtype, Some(DataType::F32));
+    assert_eq!(signature.inputs[0].shape, vec![-1, 3, 224, 224]);
+    assert_eq!(signature.outputs[0].name, "logits");
+    assert_eq!(signature.outputs[0].shape, vec![-1, 1000]);
+
+    let image = |channels: usize| vec![vec![vec![vec![0.0f32; 224]; 224]; channels]];
+    let valid = serde_json::json!({ "pixel_values": image(3) });
+    assert!(signature.validate_json_input(&valid).is_ok());
+    let wrong_channels = serde_json::json!({ "pixel_values": image(1) });
+    assert!(signature.validate_json_input(&wrong_channels).is_err());
+    assert!(signature.validate_json_input(&serde_json::json!({ "input_ids": [[1, 2]] })).is_err());
+
+    std::fs::write(&path, &model[..model.len() - 3]).unwrap();
+    assert!(read_onnx_signature(&path).is_err());
+}
+
+fn f32_tensor(shape: Vec<usize>, values: &[f32]) -> Tensor {
+    Tensor::new(DataType::F32, shape, values.iter().flat_map(|v| v.to_le_bytes()).collect()).unwrap()
+}
+
+#[test]
+fn test_arrow_ipc_round_trip() {
+    let batch = |offset: f32| {
+        HashMap::from([
+            ("features".to_string(), f32_tensor(vec![2, 3], &[offset, 1.0, 2.0, 3.0, 4.0, 5.0])),
+            ("mask".to_string(), Tensor::new(DataType::Bool, vec![2], vec![1, 0]).unwrap()),
+            ("image".to_string(), Tensor::new(DataType::U8, vec![2, 2, 2], (0..8).collect()).unwrap()),
+        ])
+    };
+
+    let bytes = write_arrow_ipc(&[batch(0.0), batch(10.0)]).unwrap();
+    let batches = read_arrow_ipc(&bytes).unwrap();
+    assert_eq!(batches, vec![batch(0.0), batch(10.0)]);
+
+    // 多个记录批次沿第一维拼接
+    let table = read_arrow_table(&bytes).unwrap();
+    assert_eq!(table["features"].shape, vec![4, 3]);
+    assert_eq!(table["image"].shape, vec![4, 2, 2]);
+    assert_eq!(table["mask"].data, vec![1, 0, 1, 0]);
+
+    // 各列行数必须一致
+    let ragged = HashMap::from([
+        ("a".to_string(), f32_tensor(vec![2], &[1.0, 2.0])),
+        ("b".to_string(), f32_tensor(vec![3], &[1.0, 2.0, 3.0])),
+    ]);
+    assert!(write_arrow_ipc(&[ragged]).is_err());
+    assert!(read_arrow_ipc(b"not arrow").is_err());
+}
+
+#[test]
+fn test_wire_format_negotiation() {
+    use axum::http::{header, HeaderMap, HeaderValue};
+
+    let mut headers = HeaderMap::new();
+    assert_eq!(WireFormat::from_content_type(&headers).unwrap(), WireFormat::Json);
+    assert_eq!(WireFormat::from_accept(&headers), WireFormat::Json);
+
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(ARROW_STREAM_CONTENT_TYPE));
+    headers.insert(header::ACCEPT, HeaderValue::from_static("text/html, application/vnd.apache.arrow.stream;q=0.9"));
+    assert_eq!(WireFormat::from_content_type(&headers).unwrap(), WireFormat::Arrow);
+    assert_eq!(WireFormat::from_accept(&headers), WireFormat::Arrow);
+
+    // NumPy格式只用于上传
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("application/x-npy"));
+    headers.insert(header::ACCEPT, HeaderValue::from_static("application/x-npy"));
+    assert_eq!(WireFormat::from_content_type(&headers).unwrap(), WireFormat::Npy);
+    assert_eq!(WireFormat::from_accept(&headers), WireFormat::Json);
+
+    headers.insert(header::ACCEPT, HeaderValue::from_static("application/x-msgpack"));
+    assert_eq!(WireFormat::from_accept(&headers), WireFormat::MsgPack);
+
+    headers.insert(header::CONTENT_TYPE, HeaderValue::from_static("text/csv"));
+    assert!(WireFormat::from_content_type(&headers).is_err());
+}
+
+/// 构造 `.npy` 字节（v1头部，按64字节对齐）
+fn npy_bytes(descr: &str, shape: &str, data: &[u8]) -> Vec<u8> {
+    let mut header = format!("{{'descr': '{}', 'fortran_order': False, 'shape': {}, }}", descr, shape);
+    while (10 + header.len() + 1) % 64 != 0 {
+        header.push(' ');
+    }
+    header.push('\n');
+
+    let mut bytes = b"\x93NUMPY\x01\x00".to_vec();
+    bytes.extend_from_slice(&(header.len() as u16).to_le_bytes());
+    bytes.extend_from_slice(header.as_bytes());
+    bytes.extend_from_slice(data);
+    bytes
+}
+
+#[test]
+fn test_npy_parsing() {
+    let values: Vec<u8> = [1.0f32, 2.0, 3.0, 4.0, 5.0, 6.0].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let tensor = read_npy(&npy_bytes("<f4", "(2, 3)", &values)).unwrap();
+    assert_eq!(tensor, f32_tensor(vec![2, 3], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]));
+
+    // 大端序数据转换为小端
+    let big_endian: Vec<u8> = [7i32, -1].iter().flat_map(|v| v.to_be_bytes()).collect();
+    let tensor = read_npy(&npy_bytes(">i4", "(2,)", &big_endian)).unwrap();
+    assert_eq!(tensor.dtype, DataType::I32);
+    assert_eq!(tensor.data, [7i32, -1].iter().flat_map(|v| v.to_le_bytes()).collect::<Vec<u8>>());
+
+    // 标量
+    assert_eq!(read_npy(&npy_bytes("|u1", "()", &[9])).unwrap().shape, Vec::<usize>::new());
+
+    assert!(read_npy(&npy_bytes("<f4", "(2, 3)", &values[..20])).is_err());
+    assert!(read_npy(&npy_bytes("<c8", "(1,)", &[0; 8])).is_err());
+    assert!(read_npy(b"not numpy").is_err());
+}
+
+#[test]
+fn test_npz_parsing_and_unstack() {
+    use std::io::Write;
+
+    let ids: Vec<u8> = [1i64, 2, 3, 4].iter().flat_map(|v| v.to_le_bytes()).collect();
+    let mask = [1u8, 1, 0, 1];
+
+    let mut archive = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    let options = zip::write::FileOptions::default().compression_method(zip::CompressionMethod::Stored);
+    archive.start_file("input_ids.npy", options).unwrap();
+    archive.write_all(&npy_bytes("<i8", "(2, 2)", &ids)).unwrap();
+    archive.start_file("attention_mask.npy", options).unwrap();
+    archive.write_all(&npy_bytes("|b1", "(2, 2)", &mask)).unwrap();
+    let bytes = archive.finish().unwrap().into_inner();
+
+    let tensors = read_npz(&bytes).unwrap();
+    assert_eq!(tensors.len(), 2);
+    assert_eq!(tensors["input_ids"].shape, vec![2, 2]);
+    assert_eq!(tensors["attention_mask"].dtype, DataType::Bool);
+
+    let rows = unstack(&tensors).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1]["input_ids"].shape, vec![2]);
+    assert_eq!(rows[1]["input_ids"].data, ids[16..].to_vec());
+    assert_eq!(rows[1]["attention_mask"].data, vec![0, 1]);
+
+    assert!(read_npz(b"not a zip").is_err());
+}
+
+#[test]
+fn test_grpc_tensor_conversion() {
+    use unimodel::api::grpc::proto::inference as pb;
+
+    let tensor = f32_tensor(vec![1, 2], &[0.5, -1.5]);
+    let proto = pb::Tensor::from(tensor.clone());
+    assert_eq!(proto.dtype, pb::DataType::Float32 as i32);
+    assert_eq!(proto.shape, vec![1, 2]);
+    assert_eq!(Tensor::try_from(proto.clone()).unwrap(), tensor);
+
+    let input = pb::InputData {
+        data: Some(pb::input_data::Data::Tensors(pb::TensorMap {
+            tensors: HashMap::from([("x".to_string(), proto.clone())]),
+        })),
+    };
+    match InputData::try_from(input).unwrap() {
+        InputData::Tensors(tensors) => assert_eq!(tensors["x"], tensor),
+        other => panic!("unexpected input {:?}", other),
+    }
+
+    // 数据长度与形状不符、未指定类型均被拒绝
+    let truncated = pb::Tensor { data: proto.data.slice(..4), ..proto.clone() };
+    assert!(Tensor::try_from(truncated).is_err());
+    let unspecified = pb::Tensor { dtype: pb::DataType::Unspecified as i32, ..proto };
+    assert_eq!(tonic::Status::from(Tensor::try_from(unspecified).unwrap_err()).code(), tonic::Code::InvalidArgument);
+}
+
+#[test]
+fn test_msgpack_body_round_trip() {
+    let input = InputData::Binary(vec![7u8; 4096].into());
+
+    // 二进制按字节串编码，不膨胀为整数数组
+    let packed = rmp_serde::to_vec_named(&input).unwrap();
+    assert!(packed.len() < 4200);
+    match deserialize_body::<InputData>(WireFormat::MsgPack, &packed).unwrap() {
+        InputData::Binary(data) => assert_eq!(data, vec![7u8; 4096]),
+        other => panic!("unexpected input {:?}", other),
+    }
+
+    // JSON仍使用整数数组，保持兼容
+    let json = serde_json::to_value(InputData::Binary(vec![1, 2].into())).unwrap();
+    assert_eq!(json, serde_json::json!({ "type": "Binary", "data": [1, 2] }));
+    assert!(deserialize_body::<InputData>(WireFormat::Json, json.to_string().as_bytes()).is_ok());
+
+    let response = serialize_body(WireFormat::MsgPack, &input).unwrap();
+    assert_eq!(response.headers()["content-type"], "application/msgpack");
+    assert!(deserialize_body::<InputData>(WireFormat::MsgPack, b"\xc1").is_err());
+}
+
+#[test]
+fn test_cbor_body_round_trip() {
+    let tensors = HashMap::from([("x".to_string(), f32_tensor(vec![2], &[1.0, 2.0]))]);
+    let input = InputData::Tensors(tensors.clone());
+
+    let response = serialize_body(WireFormat::Cbor, &input).unwrap();
+    assert_eq!(response.headers()["content-type"], "application/cbor");
+
+    let mut encoded = Vec::new();
+    ciborium::ser::into_writer(&input, &mut encoded).unwrap();
+    match deserialize_body::<InputData>(WireFormat::Cbor, &encoded).unwrap() {
+        InputData::Tensors(decoded) => assert_eq!(decoded, tensors),
+        other => panic!("unexpected input {:?}", other),
+    }
+    assert!(deserialize_body::<InputData>(WireFormat::Cbor, &[0xff]).is_err());
+}
+
+#[tokio::test]
+async fn test_multipart_parsing() {
+    let body = concat!(
+        "--XYZ\r\n",
+        "Content-Disposition: form-data; name=\"parameters\"\r\n",
+        "Content-Type: application/json\r\n\r\n",
+        "{\"temperature\": 0.5, \"custom\": {}}\r\n",
+        "--XYZ\r\n",
+        "Content-Disposition: form-data; name=\"image\"; filename=\"cat.png\"\r\n",
+        "Content-Type: image/png\r\n\r\n",
+        "\x01\x02\x03\r\n",
+        "--XYZ\r\n",
+        "Content-Disposition: form-data; name=\"prompt\"\r\n\r\n",
+        "describe\r\n",
+        "--XYZ--\r\n",
+    );
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert("content-type", "multipart/form-data; boundary=XYZ".parse().unwrap());
+    assert_eq!(WireFormat::from_content_type(&headers).unwrap(), WireFormat::Multipart);
+    assert_eq!(WireFormat::from_accept(&headers), WireFormat::Json);
+
+    let payload = read_multipart(&headers, body.into()).await.unwrap();
+    assert_eq!(payload.parameters.as_ref().and_then(|p| p.temperature), Some(0.5));
+    assert_eq!(payload.parts.len(), 2);
+    match payload.into_input().unwrap() {
+        InputData::Multimodal(map) => {
+            assert!(matches!(&map["image"], InputData::Binary(data) if data[..] == [1, 2, 3]));
+            assert!(matches!(&map["prompt"], InputData::Text(text) if text == "describe"));
+        }
+        other => panic!("unexpected input {:?}", other),
+    }
+
+    // 只有参数字段时拒绝
+    let body = "--XYZ\r\nContent-Disposition: form-data; name=\"parameters\"\r\n\r\n{\"custom\": {}}\r\n--XYZ--\r\n";
+    assert!(read_multipart(&headers, body.into()).await.is_err());
+}
+
+#[test]
+fn test_raw_predict_query_overrides() {
+    use unimodel::api::rest::handlers::RawPredictQuery;
+
+    let mut parameters = PredictionParameters {
+        max_tokens: Some(16),
+        temperature: Some(1.0),
+        ..Default::default()
+    };
+    let query: RawPredictQuery = serde_json::from_value(serde_json::json!({
+        "temperature": 0.2,
+        "priority": "high",
+    })).unwrap();
+    query.apply(&mut parameters).unwrap();
+
+    assert_eq!(parameters.max_tokens, Some(16));
+    assert_eq!(parameters.temperature, Some(0.2));
+    assert_eq!(parameters.priority, Priority::High);
+
+    let query = RawPredictQuery {
+        priority: Some("urgent".to_string()),
+        ..Default::default()
+    };
+    assert!(query.apply(&mut parameters).is_err());
+}
+
+#[test]
+fn test_image_preprocessing() {
+    // 4x2 图像，第x列像素为 (x, 10x, 100+x)
+    let image = image::RgbImage::from_fn(4, 2, |x, _| image::Rgb([x as u8, 10 * x as u8, 100 + x as u8]));
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .unwrap();
+
+    let config = ImagePreprocessing {
+        resize_shorter_side: Some(2),
+        center_crop: Some([2, 2]),
+        scale: 1.0,
+        mean: [0.0; 3],
+        std: [1.0; 3],
+        ..Default::default()
+    };
+    let tensor = preprocess_image(&png, &config).unwrap();
+    assert_eq!(tensor.dtype, DataType::F32);
+    assert_eq!(tensor.shape, vec![1, 3, 2, 2]);
+    let values: Vec<f32> = tensor.data.chunks(4).map(|b| f32::from_le_bytes(b.try_into().unwrap())).collect();
+    // 裁剪保留中间两列 x=1,2
+    assert_eq!(&values[..4], &[1.0, 2.0, 1.0, 2.0]);
+    assert_eq!(&values[4..8], &[10.0, 20.0, 10.0, 20.0]);
+
+    let config = ImagePreprocessing {
+        channel_order: ChannelOrder::Bgr,
+        layout: TensorLayout::Nhwc,
+        ..config
+    };
+    let tensor = preprocess_image(&png, &config).unwrap();
+    assert_eq!(tensor.shape, vec![1, 2, 2, 3]);
+    assert_eq!(f32::from_le_bytes(tensor.data[..4].try_into().unwrap()), 101.0);
+
+    // 裁剪尺寸大于图像、非图像数据都被拒绝
+    let config = ImagePreprocessing { center_crop: Some([8, 8]), ..Default::default() };
+    assert!(preprocess_image(&png, &ImagePreprocessing { resize_shorter_side: None, ..config }).is_err());
+    assert!(preprocess_image(b"not an image", &ImagePreprocessing::default()).is_err());
+}
+
+/// 16位PCM WAV文件
+fn wav_bytes(sample_rate: u32, channels: u16, samples: &[i16]) -> Vec<u8> {
+    let data_len = samples.len() as u32 * 2;
+    let mut out = Vec::new();
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVEfmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&(sample_rate * channels as u32 * 2).to_le_bytes());
+    out.extend_from_slice(&(channels * 2).to_le_bytes());
+    out.extend_from_slice(&16u16.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    for sample in samples {
+        out.extend_from_slice(&sample.to_le_bytes());
+    }
+    out
+}
+
+#[test]
+fn test_audio_preprocessing() {
+    // 8kHz 立体声 0.5秒，左声道 0.5、右声道 0
+    let samples: Vec<i16> = (0..4000).flat_map(|_| [16384i16, 0]).collect();
+    let wav = wav_bytes(8000, 2, &samples);
+
+    let config = AudioPreprocessing::default();
+    let tensor = preprocess_audio(wav.clone().into(), &config).unwrap();
+    assert_eq!(tensor.dtype, DataType::F32);
+    assert_eq!(tensor.shape, vec![1, 8000]);
+    let first = f32::from_le_bytes(tensor.data[..4].try_into().unwrap());
+    assert!((first - 0.25).abs() < 1e-3);
+
+    let config = AudioPreprocessing { sample_rate: 8000, mono: false, ..Default::default() };
+    assert_eq!(preprocess_audio(wav.clone().into(), &config).unwrap().shape, vec![1, 2, 4000]);
+
+    let config = AudioPreprocessing { max_duration_secs: 0, ..Default::default() };
+    assert!(preprocess_audio(wav.into(), &config).is_err());
+    assert!(preprocess_audio(b"not audio".to_vec().into(), &AudioPreprocessing::default()).is_err());
+
+    assert_eq!(resample(&[0.0, 1.0, 2.0, 3.0], 4, 2), vec![0.5, 2.5]);
+    assert_eq!(resample(&[0.0, 1.0], 1, 2), vec![0.0, 0.5, 1.0, 1.0]);
+}
+
+#[test]
+fn test_video_frame_outputs() {
+    assert!(is_video(b"\0\0\0\x18ftypmp42\0\0\0\0"));
+    assert!(is_video(&[0x1a, 0x45, 0xdf, 0xa3, 0x01]));
+    assert!(!is_video(b"\x89PNG\r\n\x1a\n"));
+
+    // 两帧的批量输出按时间戳拆分
+    let output = OutputData::Tensors(HashMap::from([(
+        "logits".to_string(),
+        f32_tensor(vec![2, 2], &[0.1, 0.9, 0.8, 0.2]),
+    )]));
+    match split_frame_outputs(output, &[0.0, 0.5]).unwrap() {
+        OutputData::Multimodal(frames) => {
+            assert_eq!(frames.len(), 2);
+            match &frames["0.500"] {
+                OutputData::Tensors(tensors) => assert_eq!(tensors["logits"], f32_tensor(vec![2], &[0.8, 0.2])),
+                other => panic!("unexpected output {:?}", other),
+            }
+        }
+        other => panic!("unexpected output {:?}", other),
+    }
+
+    let output = OutputData::Tensors(HashMap::from([("logits".to_string(), f32_tensor(vec![1, 1], &[0.5]))]));
+    assert!(split_frame_outputs(output, &[0.0, 1.0]).is_err());
+    assert!(matches!(split_frame_outputs(OutputData::Text("ok".into()), &[0.0]), Ok(OutputData::Text(_))));
+}
+
+#[test]
+fn test_image_generation_request() {
+    use unimodel::api::rest::handlers::{parse_image_size, ImageGenerationRequest};
+    use unimodel::infrastructure::postprocessing::output_images;
+
+    assert_eq!(parse_image_size("1024x768").unwrap(), (1024, 768));
+    assert!(parse_image_size("1020x1020").is_err());
+    assert!(parse_image_size("large").is_err());
+
+    let request: ImageGenerationRequest = serde_json::from_value(serde_json::json!({
+        "prompt": "a red fox",
+        "size": "768x512",
+        "steps": 30,
+        "seed": 42,
+    })).unwrap();
+    let parameters = request.to_parameters().unwrap();
+    assert_eq!(parameters.custom["width"], 768);
+    assert_eq!(parameters.custom["num_images"], 1);
+    assert_eq!(parameters.custom["seed"], 42);
+    assert_eq!(parameters.seed, Some(42));
+
+    let request: ImageGenerationRequest = serde_json::from_value(serde_json::json!({
+        "prompt": "a red fox",
+        "n": 2,
+        "response_format": "binary",
+    })).unwrap();
+    assert!(request.to_parameters().is_err());
+
+    // uint8 NHWC 张量按张编码为PNG
+    let pixels = Tensor::new(DataType::U8, vec![2, 2, 2, 3], vec![255; 24]).unwrap();
+    let images = output_images(OutputData::Tensors(HashMap::from([("images".to_string(), pixels)]))).unwrap();
+    assert_eq!(images.len(), 2);
+    assert!(images[0].starts_with(b"\x89PNG"));
+    assert!(output_images(OutputData::Text("no image".to_string())).is_err());
+}
+
+#[test]
+fn test_detection_regions_and_crops() {
+    use unimodel::infrastructure::postprocessing::{crop_regions, parse_regions, BoundingBox};
+
+    let output = OutputData::Json(serde_json::json!({
+        "regions": [
+            { "box": [10.0, 5.0, 2.0, 1.0], "score": 0.9 },
+            { "box": [0.0, 0.0, 4.0, 4.0] },
+        ]
+    }));
+    let regions = parse_regions(&output).unwrap();
+    assert_eq!(regions[0].bbox, BoundingBox { x_min: 2.0, y_min: 1.0, x_max: 10.0, y_max: 5.0 });
+    assert_eq!(regions[1].score, 1.0);
+    assert!(parse_regions(&OutputData::Text("none".to_string())).is_err());
+
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgb8(image::RgbImage::new(8, 8))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageOutputFormat::Png)
+        .unwrap();
+    let crops = crop_regions(&png, &[regions[0].bbox, BoundingBox::from_corners([6.0, 6.0, 20.0, 20.0])]).unwrap();
+    let first = image::load_from_memory(&crops[0]).unwrap();
+    assert_eq!((first.width(), first.height()), (6, 4));
+    let clamped = image::load_from_memory(&crops[1]).unwrap();
+    assert_eq!((clamped.width(), clamped.height()), (2, 2));
+    assert!(crop_regions(&png, &[BoundingBox::from_corners([9.0, 9.0, 12.0, 12.0])]).is_err());
+}
+
+#[test]
+fn test_detection_postprocessing() {
+    use unimodel::infrastructure::postprocessing::{postprocess, postprocess_detections};
+
+    let labels = LabelMap::parse("cat\n\ndog\n").unwrap();
+    assert_eq!(labels.label(0), "cat");
+    assert_eq!(labels.label(2), "dog");
+    assert_eq!(labels.label(7), "7");
+    assert_eq!(LabelMap::parse(r#"{"1": "car"}"#).unwrap().label(1), "car");
+    assert_eq!(LabelMap::parse(r#"["a", "b"]"#).unwrap().len(), 2);
+    assert!(LabelMap::parse(r#"{"x": "car"}"#).is_err());
+
+    let tensors = HashMap::from([
+        ("boxes".to_string(), f32_tensor(vec![1, 4, 4], &[
+            0.0, 0.0, 10.0, 10.0,
+            1.0, 1.0, 10.0, 10.0,
+            1.0, 1.0, 10.0, 10.0,
+            20.0, 20.0, 30.0, 30.0,
+        ])),
+        ("scores".to_string(), f32_tensor(vec![1, 4], &[0.8, 0.9, 0.7, 0.1])),
+        ("classes".to_string(), f32_tensor(vec![1, 4], &[0.0, 0.0, 2.0, 0.0])),
+    ]);
+    let config = DetectionPostprocessing::default();
+
+    // 同类重叠框被抑制，低分框被过滤，不同类别的重叠框保留
+    let detections = postprocess_detections(&tensors, &config, Some(&labels)).unwrap();
+    assert_eq!(detections.len(), 2);
+    assert_eq!((detections[0].label.as_str(), detections[0].score), ("cat", 0.9));
+    assert_eq!(detections[1].label, "dog");
+
+    let agnostic = DetectionPostprocessing { class_agnostic: true, ..config.clone() };
+    assert_eq!(postprocess_detections(&tensors, &agnostic, None).unwrap().len(), 1);
+
+    let output = postprocess(
+        OutputData::Tensors(tensors),
+        &PostprocessingConfig { detection: Some(config), ..Default::default() },
+        None,
+        &PredictionParameters::default(),
+    ).unwrap();
+    match output {
+        OutputData::Json(json) => assert_eq!(json["detections"][1]["label"], "2"),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+#[test]
+fn test_segmentation_mask_encoding() {
+    use unimodel::infrastructure::postprocessing::{encode_segmentation, postprocess, ClassMask};
+
+    // 4x3的类别掩码：左上角2x2为类别1，右下角像素为类别2
+    let mask = Tensor::new(DataType::U8, vec![1, 3, 4], vec![
+        1, 1, 0, 0,
+        1, 1, 0, 0,
+        0, 0, 0, 2,
+    ]).unwrap();
+    let classes = ClassMask::from_tensor(&mask, 0.5).unwrap();
+    assert_eq!((classes.width, classes.height), (4, 3));
+    assert_eq!(classes.rle(1).counts, vec![0, 2, 1, 2, 7]);
+    assert_eq!(classes.rle(2).counts, vec![11, 1]);
+    assert_eq!(classes.polygons(1), vec![vec![[0, 0], [1, 0], [1, 1], [0, 1]]]);
+    assert_eq!(classes.polygons(2), vec![vec![[3, 2]]]);
+
+    // 逐类别分数取argmax，单通道概率按阈值二值化
+    let scores = f32_tensor(vec![1, 2, 1, 2], &[0.9, 0.1, 0.1, 0.9]);
+    assert_eq!(ClassMask::from_tensor(&scores, 0.5).unwrap().classes, vec![0, 1]);
+    let probabilities = f32_tensor(vec![1, 1, 1, 2], &[0.2, 0.7]);
+    assert_eq!(ClassMask::from_tensor(&probabilities, 0.5).unwrap().classes, vec![0, 1]);
+
+    let config = SegmentationPostprocessing::default();
+    let labels = LabelMap::parse(r#"["background", "person", "car"]"#).unwrap();
+    let encoded = encode_segmentation(&mask, &config, MaskFormat::Png, Some(&labels)).unwrap().unwrap();
+    assert_eq!(encoded.segments.len(), 2);
+    assert_eq!((encoded.segments[0].label.as_str(), encoded.segments[0].area), ("person", 4));
+    let png = base64::Engine::decode(&base64::engine::general_purpose::STANDARD, encoded.mask.unwrap()).unwrap();
+    let decoded = image::load_from_memory(&png).unwrap().to_luma8();
+    assert_eq!(decoded.get_pixel(3, 2).0, [2]);
+    assert!(encode_segmentation(&mask, &config, MaskFormat::Raw, None).unwrap().is_none());
+
+    // 请求参数覆盖模型默认编码，原始格式保留张量
+    let postprocessing = PostprocessingConfig { segmentation: Some(config), ..Default::default() };
+    let tensors = HashMap::from([("masks".to_string(), mask)]);
+    let parameters = PredictionParameters { mask_format: Some(MaskFormat::Raw), ..Default::default() };
+    let raw = postprocess(OutputData::Tensors(tensors.clone()), &postprocessing, None, &parameters).unwrap();
+    assert!(matches!(raw, OutputData::Tensors(_)));
+    match postprocess(OutputData::Tensors(tensors), &postprocessing, None, &PredictionParameters::default()).unwrap() {
+        OutputData::Json(json) => assert_eq!(json["segmentation"]["segments"][0]["rle"]["size"], serde_json::json!([3, 4])),
+        other => panic!("unexpected output: {:?}", other),
+    }
+    assert_eq!("Polygon".parse::<MaskFormat>().unwrap(), MaskFormat::Polygon);
+    assert!("bitmap".parse::<MaskFormat>().is_err());
+}
+
+#[test]
+fn test_classification_top_k() {
+    use unimodel::infrastructure::postprocessing::{postprocess, top_k_classes};
+
+    let labels = LabelMap::parse("cat\ndog\nbird\n").unwrap();
+    let tensors = HashMap::from([("logits".to_string(), f32_tensor(vec![1, 3], &[1.0, 3.0, 2.0]))]);
+    let config = ClassificationPostprocessing { softmax: true, ..Default::default() };
+
+    let classes = top_k_classes(&tensors, &config, 2, Some(&labels)).unwrap();
+    assert_eq!(classes.len(), 2);
+    assert_eq!((classes[0].label.as_str(), classes[0].class_id), ("dog", 1));
+    assert_eq!(classes[1].label, "bird");
+    assert!((classes[0].score - 0.665).abs() < 1e-3);
+
+    let batched = HashMap::from([("logits".to_string(), f32_tensor(vec![2, 2], &[0.0; 4]))]);
+    assert!(top_k_classes(&batched, &config, 1, None).is_err());
+
+    // 请求的top_k覆盖模型默认值
+    let postprocessing = PostprocessingConfig { classification: Some(config), ..Default::default() };
+    let parameters = PredictionParameters { top_k: Some(1), ..Default::default() };
+    match postprocess(OutputData::Tensors(tensors), &postprocessing, Some(&labels), &parameters).unwrap() {
+        OutputData::Json(json) => assert_eq!(json["classifications"], serde_json::json!([
+            { "label": "dog", "class_id": 1, "score": classes[0].score }
+        ])),
+        other => panic!("unexpected output: {:?}", other),
+    }
+}
+
+fn gguf_string_array(out: &mut Vec<u8>, key: &str, values: &[&str]) {
+    gguf_string(out, key);
+    out.extend_from_slice(&9u32.to_le_bytes());
+    out.extend_from_slice(&8u32.to_le_bytes());
+    out.extend_from_slice(&(values.len() as u64).to_le_bytes());
+    for value in values {
+        gguf_string(out, value);
+    }
+}
+
+#[test]
+fn test_gguf_vocab_tokenizer() {
+    use unimodel::infrastructure::tokenizer::ModelTokenizer;
+
+    let mut bytes = b"GGUF".to_vec();
+    bytes.extend_from_slice(&3u32.to_le_bytes());
+    bytes.extend_from_slice(&0u64.to_le_bytes());
+    bytes.extend_from_slice(&5u64.to_le_bytes());
+
+    gguf_string(&mut bytes, "tokenizer.ggml.model");
+    bytes.extend_from_slice(&8u32.to_le_bytes());
+    gguf_string(&mut bytes, "gpt2");
+    gguf_string_array(&mut bytes, "tokenizer.ggml.tokens", &["a", "b", "ab", "Ġ", "Ġab", "Ġa", "<s>"]);
+    gguf_string_array(&mut bytes, "tokenizer.ggml.merges", &["Ġ a", "Ġa b", "a b"]);
+
+    gguf_string(&mut bytes, "tokenizer.ggml.token_type");
+    bytes.extend_from_slice(&9u32.to_le_bytes());
+    bytes.extend_from_slice(&5u32.to_le_bytes());
+    bytes.extend_from_slice(&7u64.to_le_bytes());
+    for token_type in [1i32, 1, 1, 1, 1, 1, 3] {
+        bytes.extend_from_slice(&token_type.to_le_bytes());
+    }
+
+    gguf_string(&mut bytes, "tokenizer.ggml.bos_token_id");
+    bytes.extend_from_slice(&4u32.to_le_bytes());
+    bytes.extend_from_slice(&6u32.to_le_bytes());
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("vocab.gguf");
+    std::fs::write(&path, &bytes).unwrap();
+
+    // 默认只记录数组长度
+    assert!(GgufHeader::read(&path).unwrap().vocab().is_none());
+
+    let vocab = GgufHeader::read_with_vocab(&path).unwrap().vocab().unwrap();
+    assert_eq!(vocab.model, "gpt2");
+    assert_eq!(vocab.tokens.len(), 7);
+    assert_eq!(vocab.token_types[6], 3);
+    assert_eq!(vocab.bos_token_id, Some(6));
+    assert!(vocab.add_bos_token);
+
+    let tokenizer = ModelTokenizer::from_gguf_vocab(vocab).unwrap();
+    assert_eq!(tokenizer.encode("ab ab", true).unwrap(), vec![6, 2, 4]);
+    assert_eq!(tokenizer.count("ab ab", false).unwrap(), 2);
+    assert_eq!(tokenizer.decode(&[6, 2, 4], true).unwrap(), "ab ab");
+    assert!(tokenizer.decode(&[99], true).is_err());
+}
+
+#[test]
+fn test_bench_options() {
+    use std::time::Duration;
+    use unimodel::application::bench::{parse_duration, BenchOptions, LatencySummary};
+
+    assert_eq!(parse_duration("60s").unwrap(), Duration::from_secs(60));
+    assert_eq!(parse_duration("500ms").unwrap(), Duration::from_millis(500));
+    assert_eq!(parse_duration("2m").unwrap(), Duration::from_secs(120));
+    assert_eq!(parse_duration("15").unwrap(), Duration::from_secs(15));
+    assert!(parse_duration("1d").is_err());
+    assert!(parse_duration("s").is_err());
+
+    let args: Vec<String> = ["--model", "m1", "--concurrency", "8", "--duration", "30s", "--url", "http://host:8000/"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+    let options = BenchOptions::parse(&args).unwrap();
+    assert_eq!(options.model_id, "m1");
+    assert_eq!(options.concurrency, 8);
+    assert_eq!(options.duration, Duration::from_secs(30));
+    assert_eq!(options.url, "http://host:8000");
+    assert!(BenchOptions::parse(&["--concurrency".to_string(), "4".to_string()]).is_err());
+    assert!(BenchOptions::parse(&["--model".to_string(), "m1".to_string(), "--concurrency".to_string(), "0".to_string()]).is_err());
+
+    // 最近秩法：100个样本的p99为第99个
+    let mut samples: Vec<Duration> = (1..=100).rev().map(Duration::from_millis).collect();
+    let summary = LatencySummary::from_samples(&mut samples);
+    assert_eq!(summary.min, 1.0);
+    assert_eq!(summary.p50, 50.0);
+    assert_eq!(summary.p99, 99.0);
+    assert_eq!(summary.max, 100.0);
+    assert!((summary.mean - 50.5).abs() < 1e-9);
+    assert_eq!(LatencySummary::from_samples(&mut []), LatencySummary::default());
+}
+
+#[test]
+fn test_runtime_worker_blocking() {
+    use std::time::Duration;
+    use unimodel::infrastructure::monitoring::runtime_metrics::{busy_ratio, is_worker_blocked};
+
+    let interval = Duration::from_secs(1);
+    assert_eq!(busy_ratio(Duration::from_millis(250), interval), 0.25);
+    assert_eq!(busy_ratio(Duration::from_millis(1200), interval), 1.0);
+    assert_eq!(busy_ratio(Duration::from_millis(10), Duration::ZERO), 0.0);
+
+    // 一直忙碌却没有完成轮询：某个任务阻塞了工作线程
+    assert!(is_worker_blocked(0, Duration::from_millis(950), interval));
+    assert!(!is_worker_blocked(120, Duration::from_millis(950), interval));
+    assert!(!is_worker_blocked(0, Duration::from_millis(100), interval));
+}
+
+#[tokio::test]
+async fn test_cpu_profile_duration_bounds() {
+    use std::time::Duration;
+    use unimodel::infrastructure::monitoring::profiler::{capture_cpu_profile, ProfileFormat, MAX_PROFILE_DURATION};
+
+    assert!(!Config::default().monitoring.debug_endpoints);
+    for duration in [Duration::ZERO, MAX_PROFILE_DURATION + Duration::from_secs(1)] {
+        assert!(matches!(
+            capture_cpu_profile(duration, ProfileFormat::Pprof).await,
+            Err(UniModelError::Validation(_))
+        ));
+    }
+    assert_eq!(ProfileFormat::Flamegraph.content_type(), "image/svg+xml");
+}
+
+#[test]
+fn test_allocator_stats() {
+    use unimodel::infrastructure::monitoring::allocator::{allocator_stats, fragmentation};
+
+    assert_eq!(fragmentation(0, 0), 0.0);
+    assert_eq!(fragmentation(750, 1000), 0.25);
+    assert_eq!(fragmentation(1000, 1000), 0.0);
+
+    // 测试进程不以jemalloc为全局分配器，统计仍可读取
+    let stats = allocator_stats().unwrap();
+    assert!(stats.active_bytes >= stats.allocated_bytes);
+    assert!(stats.mapped_bytes >= stats.active_bytes);
+    assert!((0.0..1.0).contains(&stats.fragmentation));
+}
+
+#[test]
+fn test_runtime_from_config() {
+    use unimodel::UniModelServer;
+
+    let mut config = Config::default();
+    config.server.worker_threads = Some(2);
+    config.server.max_blocking_threads = Some(4);
+    config.server.thread_name = "test-worker".to_string();
+    config.server.thread_stack_size_kb = Some(4096);
+    assert!(config.validate().is_ok());
+
+    let runtime = UniModelServer::build_runtime(&config).unwrap();
+    let name = runtime.block_on(async {
+        tokio::spawn(async { std::thread::current().name().map(str::to_string) }).await.unwrap()
+    });
+    assert_eq!(name.as_deref(), Some("test-worker"));
+
+    config.server.worker_threads = Some(0);
+    assert!(config.validate().is_err());
+
+    // 未配置的运行时参数使用默认值
+    let parsed = Config::from_yaml(&serde_yaml::to_string(&Config::default()).unwrap()).unwrap();
+    assert_eq!(parsed.server.thread_name, "unimodel-worker");
+    assert_eq!(parsed.server.max_blocking_threads, None);
+}
+
+#[test]
+fn test_numa_placement() {
+    use std::collections::{BTreeMap, HashMap};
+    use unimodel::infrastructure::configuration::AffinityConfig;
+    use unimodel::infrastructure::memory::numa::{parse_cpu_list, HostBuffer, NumaTopology, ThreadPlacement};
+
+    assert_eq!(parse_cpu_list("0-3,8,10-11\n").unwrap(), vec![0, 1, 2, 3, 8, 10, 11]);
+    assert!(parse_cpu_list("0-x").is_err());
+
+    // 双路服务器：GPU 0 在节点0，GPU 1 在节点1
+    let topology = NumaTopology::new(
+        BTreeMap::from([(0, vec![0, 1, 2, 3]), (1, vec![4, 5, 6, 7])]),
+        HashMap::from([(0, 0), (1, 1)]),
+    );
+    let mut gpu = Config::default().engine.gpu;
+    gpu.device_ids = vec![1, 0];
+
+    assert_eq!(topology.placement(&AffinityConfig::default(), &gpu), None);
+    let follow_gpu = AffinityConfig { follow_gpu: true, ..Default::default() };
+    let placement = topology.placement(&follow_gpu, &gpu).unwrap();
+    assert_eq!(placement.node(), Some(1));
+    assert_eq!(placement.cpus_for_thread(3), vec![4, 5, 6, 7]);
+
+    // 显式指定的节点优先于GPU所在的节点，逐核绑定优先于节点
+    let node = AffinityConfig { numa_node: Some(0), follow_gpu: true, ..Default::default() };
+    assert_eq!(topology.placement(&node, &gpu).unwrap().node(), Some(0));
+    let cores = AffinityConfig { cpus: vec![2, 3], numa_node: Some(0), ..Default::default() };
+    let placement = topology.placement(&cores, &gpu).unwrap();
+    assert_eq!(placement, ThreadPlacement::PerThread(vec![2, 3]));
+    assert_eq!(placement.cpus_for_thread(3), vec![3]);
+    assert_eq!(topology.placement(&AffinityConfig { numa_node: Some(5), ..Default::default() }, &gpu), None);
+
+    // 节点策略不可用时仍能分配
+    let mut buffer = HostBuffer::new(4096, Some(0)).unwrap();
+    buffer.as_mut_slice()[4095] = 7;
+    assert_eq!(buffer.as_slice()[4095], 7);
+    assert!(HostBuffer::new(0, None).is_err());
+}
+
+#[test]
+fn test_auto_device_selection() {
+    let probe = DeviceProbe {
+        cuda_devices: vec![0, 1],
+        cuda_error: None,
+        rocm_devices: Vec::new(),
+        metal: true,
+    };
+    let selection = probe.select(&[]);
+    assert_eq!(selection.requested, DeviceType::Auto);
+    assert_eq!(selection.device_type, DeviceType::CUDA);
+    assert!(selection.reason.contains("[0, 1]"));
+
+    // 只在后端支持的设备中选择
+    assert_eq!(probe.select(&[DeviceType::Metal, DeviceType::CPU]).device_type, DeviceType::Metal);
+
+    // GPU探测失败时退回CPU，并记录原因
+    let probe = DeviceProbe {
+        cuda_devices: Vec::new(),
+        cuda_error: Some("nvidia-smi not found".to_string()),
+        rocm_devices: Vec::new(),
+        metal: false,
+    };
+    let selection = probe.select(&[]);
+    assert_eq!(selection.device_type, DeviceType::CPU);
+    assert!(selection.reason.contains("nvidia-smi not found"));
+
+    let device: DeviceConfig = serde_json::from_value(serde_json::json!({
+        "device_type": "Auto", "device_ids": [], "memory_limit_mb": null, "mixed_precision": false
+    }))
+    .unwrap();
+    assert_eq!(device.device_type, DeviceType::Auto);
+}
+
+#[test]
+fn test_rocm_smi_parsing() {
+    let output = "device,GPU use (%),VRAM Total Memory (B),VRAM Total Used Memory (B)\n\
+                  card0,35,68702699520,17179869184\n\
+                  card1,0,68702699520,10854400\n";
+    let gpus = parse_rocm_smi_csv(output).unwrap();
+    assert_eq!(gpus.len(), 2);
+    assert_eq!(gpus[0].device_id, 0);
+    assert!((gpus[0].utilization - 0.35).abs() < 1e-6);
+    assert_eq!(gpus[0].memory_total_bytes, 68702699520);
+    assert_eq!(gpus[0].memory_used_bytes, 17179869184);
+    assert_eq!(gpus[1].device_id, 1);
+
+    // 缺少显存列
+    assert!(parse_rocm_smi_csv("device,GPU use (%)\ncard0,1\n").is_err());
+    assert!(parse_rocm_smi_csv("").is_err());
+
+    // 只有ROCm GPU时自动选择ROCm
+    let probe = DeviceProbe {
+        cuda_error: Some("nvidia-smi not found".to_string()),
+        rocm_devices: vec![0],
+        ..Default::default()
+    };
+    assert_eq!(probe.select(&[]).device_type, DeviceType::ROCm);
+    assert_eq!(probe.select(&[DeviceType::CUDA, DeviceType::CPU]).device_type, DeviceType::CPU);
+
+    // 调度的加速器只能是CUDA、ROCm或NPU
+    let mut config = Config::default();
+    config.engine.gpu.accelerator = DeviceType::ROCm;
+    assert!(config.validate().is_ok());
+    config.engine.gpu.accelerator = DeviceType::Metal;
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn test_systemd_listen_fds() {
+    use unimodel::api::listener::{ListenFds, SD_LISTEN_FDS_START};
+
+    // LISTEN_PID不是本进程时忽略
+    let fds = ListenFds::parse(42, Some("41"), Some("2"), None).unwrap();
+    assert!(fds.is_empty());
+    assert!(ListenFds::parse(42, None, None, None).unwrap().is_empty());
+    assert!(ListenFds::parse(42, Some("42"), Some("two"), None).is_err());
+
+    // 未命名时按位置
+    let fds = ListenFds::parse(42, Some("42"), Some("2"), None).unwrap();
+    assert_eq!(fds.get("http", 0), Some(SD_LISTEN_FDS_START));
+    assert_eq!(fds.get("grpc", 1), Some(SD_LISTEN_FDS_START + 1));
+
+    // 命名时按名称，缺少的服务自行绑定
+    let fds = ListenFds::parse(42, Some("42"), Some("2"), Some("grpc:http")).unwrap();
+    assert_eq!(fds.get("http", 0), Some(SD_LISTEN_FDS_START + 1));
+    assert_eq!(fds.get("grpc", 1), Some(SD_LISTEN_FDS_START));
+    let fds = ListenFds::parse(42, Some("42"), Some("1"), Some("http")).unwrap();
+    assert_eq!(fds.get("grpc", 1), None);
+}
+
+#[test]
+fn test_bind_reuse_port() {
+    use unimodel::api::listener::bind;
+
+    let first = bind("127.0.0.1:0".parse().unwrap(), true).unwrap();
+    let addr = first.local_addr().unwrap();
+    // 新进程与旧进程同时监听
+    let second = bind(addr, true).unwrap();
+    assert_eq!(second.local_addr().unwrap(), addr);
+    drop(second);
+
+    let exclusive = bind("127.0.0.1:0".parse().unwrap(), false).unwrap();
+    assert!(bind(exclusive.local_addr().unwrap(), false).is_err());
+}
+
+#[tokio::test]
+async fn test_json_file_keeps_dirty_flag_when_write_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    // 父路径是普通文件，写入失败
+    let blocker = dir.path().join("state");
+    std::fs::write(&blocker, "").unwrap();
+    let file = JsonFile::new(blocker.join("state.json"));
+
+    // 没有修改时不写入
+    file.flush(async { Ok("{}".to_string()) }).await.unwrap();
+    assert!(!blocker.join("state.json").exists());
+
+    file.mark_dirty();
+    assert!(file.flush(async { Ok("{\"a\":1}".to_string()) }).await.is_err());
+    assert!(file.is_dirty(), "failed flush must be retried");
+
+    // 路径恢复后下次落盘写入最新内容并清除标记
+    std::fs::remove_file(&blocker).unwrap();
+    std::fs::create_dir(&blocker).unwrap();
+    file.flush(async { Ok("{\"a\":2}".to_string()) }).await.unwrap();
+    assert!(!file.is_dirty());
+    let loaded: HashMap<String, u32> = file.load().await.unwrap().unwrap();
+    assert_eq!(loaded["a"], 2);
+    assert!(!blocker.join("state.json.tmp").exists());
+    assert!(JsonFile::new(dir.path().join("missing.json")).load::<HashMap<String, u32>>().await.unwrap().is_none());
+}